@@ -0,0 +1,238 @@
+//! Runtime CPU architecture detection.
+//!
+//! ARM laptops often run x64 builds (of the app or of Tesseract) under emulation, which makes OCR slow.
+//! This module reports the host vs. process architecture and inspects the external engine binaries the app runs
+//! (Tesseract, the offline translation engine, whisper.cpp), so native builds are preferred and emulated ones can be
+//! pointed out. Nothing in the app loads ONNX models, so no execution provider is reported.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Arch {
+  X86,
+  X86_64,
+  Aarch64,
+  Universal,
+  Unknown,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BinaryInfo {
+  pub path: String,
+  pub arch: Arch,
+  /// Runs natively on this host (no x64 emulation).
+  pub native: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AccelerationInfo {
+  pub os: String,
+  pub process_arch: Arch,
+  pub host_arch: Arch,
+  /// The app itself runs under emulation (Rosetta 2 / Windows x64-on-ARM).
+  pub emulated: bool,
+  pub cpu_features: Vec<String>,
+  pub tesseract: Option<BinaryInfo>,
+  pub offline_engine: Option<BinaryInfo>,
+  /// whisper.cpp, when speech input runs locally.
+  pub speech_engine: Option<BinaryInfo>,
+}
+
+/// Architecture this binary was compiled for.
+pub fn process_arch() -> Arch {
+  if cfg!(target_arch = "aarch64") {
+    Arch::Aarch64
+  } else if cfg!(target_arch = "x86_64") {
+    Arch::X86_64
+  } else if cfg!(target_arch = "x86") {
+    Arch::X86
+  } else {
+    Arch::Unknown
+  }
+}
+
+/// Native architecture of the machine, seeing through emulation (detected once; it may run a command).
+pub fn host_arch() -> Arch {
+  static HOST: OnceLock<Arch> = OnceLock::new();
+  *HOST.get_or_init(detect_host_arch)
+}
+
+fn detect_host_arch() -> Arch {
+  #[cfg(windows)]
+  {
+    use windows_sys::Win32::System::SystemInformation::{
+      IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64, IMAGE_FILE_MACHINE_I386,
+    };
+    use windows_sys::Win32::System::Threading::{GetCurrentProcess, IsWow64Process2};
+    let mut process_machine: IMAGE_FILE_MACHINE = 0;
+    let mut native_machine: IMAGE_FILE_MACHINE = 0;
+    let ok = unsafe { IsWow64Process2(GetCurrentProcess(), &mut process_machine, &mut native_machine) };
+    if ok != 0 {
+      return match native_machine {
+        IMAGE_FILE_MACHINE_ARM64 => Arch::Aarch64,
+        IMAGE_FILE_MACHINE_AMD64 => Arch::X86_64,
+        IMAGE_FILE_MACHINE_I386 => Arch::X86,
+        _ => process_arch(),
+      };
+    }
+    process_arch()
+  }
+
+  #[cfg(target_os = "macos")]
+  {
+    // `hw.optional.arm64` is 1 on Apple Silicon even when queried from a Rosetta process.
+    let out = std::process::Command::new("sysctl").args(["-in", "hw.optional.arm64"]).output();
+    match out {
+      Ok(o) if String::from_utf8_lossy(&o.stdout).trim() == "1" => Arch::Aarch64,
+      _ => process_arch(),
+    }
+  }
+
+  #[cfg(not(any(windows, target_os = "macos")))]
+  {
+    let out = std::process::Command::new("uname").arg("-m").output();
+    match out.map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string()).as_deref() {
+      Ok("aarch64") | Ok("arm64") => Arch::Aarch64,
+      Ok("x86_64") | Ok("amd64") => Arch::X86_64,
+      Ok("i686") | Ok("i386") => Arch::X86,
+      _ => process_arch(),
+    }
+  }
+}
+
+pub fn cpu_features() -> Vec<String> {
+  let mut out: Vec<String> = Vec::new();
+  #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+  {
+    if std::arch::is_x86_feature_detected!("sse4.2") {
+      out.push("sse4.2".to_string());
+    }
+    if std::arch::is_x86_feature_detected!("avx2") {
+      out.push("avx2".to_string());
+    }
+    if std::arch::is_x86_feature_detected!("fma") {
+      out.push("fma".to_string());
+    }
+    if std::arch::is_x86_feature_detected!("avx512f") {
+      out.push("avx512f".to_string());
+    }
+  }
+  #[cfg(target_arch = "aarch64")]
+  {
+    if std::arch::is_aarch64_feature_detected!("neon") {
+      out.push("neon".to_string());
+    }
+    if std::arch::is_aarch64_feature_detected!("dotprod") {
+      out.push("dotprod".to_string());
+    }
+    if std::arch::is_aarch64_feature_detected!("fp16") {
+      out.push("fp16".to_string());
+    }
+  }
+  out
+}
+
+/// Architecture of an executable from its PE / Mach-O / ELF header.
+pub fn binary_arch(path: &std::path::Path) -> Arch {
+  use std::io::{Read, Seek, SeekFrom};
+  let Ok(mut f) = std::fs::File::open(path) else {
+    return Arch::Unknown;
+  };
+  let mut head = [0u8; 64];
+  if f.read_exact(&mut head).is_err() {
+    return Arch::Unknown;
+  }
+  let u16_le = |b: &[u8]| u16::from_le_bytes([b[0], b[1]]);
+  let u32_le = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+
+  if &head[..2] == b"MZ" {
+    let pe_offset = u32_le(&head[0x3C..0x40]) as u64;
+    let mut pe = [0u8; 6];
+    if f.seek(SeekFrom::Start(pe_offset)).is_err() || f.read_exact(&mut pe).is_err() || &pe[..4] != b"PE\0\0" {
+      return Arch::Unknown;
+    }
+    return match u16_le(&pe[4..6]) {
+      0x8664 => Arch::X86_64,
+      0xAA64 => Arch::Aarch64,
+      0x014C => Arch::X86,
+      _ => Arch::Unknown,
+    };
+  }
+  match u32_le(&head[..4]) {
+    // Mach-O 64-bit (little endian)
+    0xFEEDFACF => match u32_le(&head[4..8]) {
+      0x0100_0007 => Arch::X86_64,
+      0x0100_000C => Arch::Aarch64,
+      _ => Arch::Unknown,
+    },
+    // Fat/universal binary (big endian magic read as LE)
+    0xBEBAFECA => Arch::Universal,
+    0x464C457F => match u16_le(&head[0x12..0x14]) {
+      0x3E => Arch::X86_64,
+      0xB7 => Arch::Aarch64,
+      0x03 => Arch::X86,
+      _ => Arch::Unknown,
+    },
+    _ => Arch::Unknown,
+  }
+}
+
+/// Whether a binary of `arch` runs natively on the host.
+pub fn is_native(arch: Arch) -> bool {
+  let host = host_arch();
+  match arch {
+    Arch::Universal => true,
+    Arch::Unknown => false,
+    // x86 runs natively on x86_64 hosts.
+    Arch::X86 => matches!(host, Arch::X86 | Arch::X86_64),
+    a => a == host,
+  }
+}
+
+pub fn binary_info(path: &str) -> BinaryInfo {
+  let arch = binary_arch(std::path::Path::new(path));
+  BinaryInfo {
+    path: path.to_string(),
+    arch,
+    native: is_native(arch),
+  }
+}
+
+/// Order candidate executables so native builds come first (stable otherwise).
+pub fn prefer_native(candidates: Vec<String>) -> Vec<String> {
+  let (mut native, other): (Vec<String>, Vec<String>) = candidates
+    .into_iter()
+    .partition(|p| is_native(binary_arch(std::path::Path::new(p))));
+  native.extend(other);
+  native
+}
+
+#[tauri::command]
+pub async fn get_acceleration_info(
+  app: tauri::AppHandle,
+  tesseract_path: Option<String>,
+  offline_engine_path: Option<String>,
+) -> Result<AccelerationInfo, String> {
+  let tesseract = match tesseract_path.filter(|s| !s.trim().is_empty()) {
+    Some(p) => Some(p),
+    None => crate::commands::detect_tesseract_path().await?,
+  };
+  let offline_engine = crate::offline_mt::detect_engine(offline_engine_path.as_deref());
+  let speech_engine = crate::settings::speech_config(&app).and_then(|c| match c.engine {
+    crate::speech::SpeechEngine::Local { binary, .. } => Some(binary),
+    crate::speech::SpeechEngine::Remote { .. } => None,
+  });
+  let host = host_arch();
+  Ok(AccelerationInfo {
+    os: std::env::consts::OS.to_string(),
+    process_arch: process_arch(),
+    host_arch: host,
+    emulated: process_arch() != host && !(process_arch() == Arch::X86 && host == Arch::X86_64),
+    cpu_features: cpu_features(),
+    tesseract: tesseract.as_deref().map(binary_info),
+    offline_engine: offline_engine.as_deref().map(binary_info),
+    speech_engine: speech_engine.as_deref().map(binary_info),
+  })
+}
@@ -0,0 +1,186 @@
+//! Background agent mode.
+//!
+//! Enabled by the `backgroundAgent` setting or the `--background` flag. The app starts with no webview at
+//! all: only the tray icon (see `tray`) and the global hotkeys (see `hotkeys`) are live. The main window is created
+//! on first use — hidden when a hotkey needs it, shown from the tray — and is destroyed again when closed, so an
+//! idle translator keeps no webview in memory.
+//!
+//! Hotkey actions reach the main window through here in either mode: sent right away once it's ready, or kept
+//! until a window that is still loading (or about to be created) says it is.
+
+use std::sync::Mutex;
+use tauri::{Emitter, Listener, Manager};
+
+use crate::settings;
+
+const MAIN_LABEL: &str = "main";
+/// Emitted by the main window once its settings are loaded and it can handle actions.
+const MAIN_READY_EVENT: &str = "erudaite://main/ready";
+/// Sent to the main window with a hotkey action (`"translate"`, `"translate_if_selected"`, `"translate_clipboard"`,
+/// `"ocr"`, `"repeat_region"`, `"show_popup"`), or with `"settings"` when the tray asks for the settings.
+const AGENT_ACTION_EVENT: &str = "erudaite://agent/action";
+
+#[derive(Default)]
+pub struct AgentState {
+  enabled: Mutex<bool>,
+  /// Action waiting for the lazily created main window to become ready.
+  pending: Mutex<Option<&'static str>>,
+  /// Whether the main window has said it's ready (and not been destroyed since).
+  main_ready: Mutex<bool>,
+}
+
+pub fn is_enabled(app: &tauri::AppHandle) -> bool {
+  *app.state::<AgentState>().enabled.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Create the main window from its `tauri.conf.json` entry (which has `create: false`).
+fn create_main(app: &tauri::AppHandle, visible: bool) -> Result<tauri::WebviewWindow, String> {
+  let config = app
+    .config()
+    .app
+    .windows
+    .iter()
+    .find(|w| w.label == MAIN_LABEL)
+    .cloned()
+    .ok_or_else(|| "main window config missing".to_string())?;
+  tauri::WebviewWindowBuilder::from_config(app, &config)
+    .and_then(|b| b.visible(visible).focused(visible).build())
+    .map_err(|e| format!("failed to create main window: {e}"))
+}
+
+/// Show (creating it if needed) and focus the main window.
+pub fn show_main(app: &tauri::AppHandle) {
+  let window = match app.get_webview_window(MAIN_LABEL) {
+    Some(w) => w,
+    None => match create_main(app, true) {
+      Ok(w) => w,
+      Err(e) => {
+        log::warn!("{e}");
+        return;
+      }
+    },
+  };
+  let _ = window.show();
+  let _ = window.unminimize();
+  let _ = window.set_focus();
+}
+
+/// Hide the main window if it's showing, otherwise show it (tray menu).
+pub fn toggle_main(app: &tauri::AppHandle) {
+  if let Some(window) = app.get_webview_window(MAIN_LABEL) {
+    let showing = window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false);
+    if showing {
+      let _ = window.hide();
+      return;
+    }
+  }
+  show_main(app);
+}
+
+/// Show the main window with its settings open (tray menu).
+pub fn open_settings(app: &tauri::AppHandle) {
+  if app.get_webview_window(MAIN_LABEL).is_some() {
+    show_main(app);
+    let _ = app.emit_to(MAIN_LABEL, AGENT_ACTION_EVENT, "settings");
+    return;
+  }
+  // A new window gets it with its ready event.
+  *app.state::<AgentState>().pending.lock().unwrap_or_else(|e| e.into_inner()) = Some("settings");
+  show_main(app);
+}
+
+/// Run a hotkey action without a main window: create it hidden and hand it the action once it's ready.
+fn dispatch(app: &tauri::AppHandle, action: &'static str) {
+  *app.state::<AgentState>().pending.lock().unwrap_or_else(|e| e.into_inner()) = Some(action);
+  // A window that is still loading picks the action up with its ready event.
+  if app.get_webview_window(MAIN_LABEL).is_some() {
+    return;
+  }
+  // Hidden and unfocused, so the selection capture still targets the app the user is in.
+  if let Err(e) = create_main(app, false) {
+    log::warn!("{e}");
+  }
+}
+
+/// Run a hotkey action in the main window: right away when it's ready, otherwise once it is (creating it hidden
+/// if there is none).
+pub fn run_action(app: &tauri::AppHandle, action: &'static str) {
+  let ready = *app.state::<AgentState>().main_ready.lock().unwrap_or_else(|e| e.into_inner());
+  if ready && app.get_webview_window(MAIN_LABEL).is_some() {
+    let _ = app.emit_to(MAIN_LABEL, AGENT_ACTION_EVENT, action);
+  } else {
+    dispatch(app, action);
+  }
+}
+
+/// Switch into agent mode: the tray icon.
+fn enter(app: &tauri::AppHandle) -> Result<(), String> {
+  *app.state::<AgentState>().enabled.lock().unwrap_or_else(|e| e.into_inner()) = true;
+  crate::tray::sync(app)
+}
+
+/// Leave agent mode: drop the tray (unless `trayIcon` keeps it) and show the main window, which no longer goes
+/// away when closed.
+fn leave(app: &tauri::AppHandle) {
+  *app.state::<AgentState>().enabled.lock().unwrap_or_else(|e| e.into_inner()) = false;
+  if let Err(e) = crate::tray::sync(app) {
+    log::warn!("{e}");
+  }
+  show_main(app);
+}
+
+/// Apply `backgroundAgent` changes without a restart.
+fn reload(app: &tauri::AppHandle, _: &settings::ConfigChange) -> Result<(), String> {
+  let want = wanted(app);
+  let was = is_enabled(app);
+  if want && !was {
+    return enter(app);
+  }
+  if !want && was {
+    leave(app);
+  }
+  Ok(())
+}
+
+/// The `--background` flag wins over the setting.
+fn wanted(app: &tauri::AppHandle) -> bool {
+  std::env::args().any(|a| a == "--background") || settings::background_agent(app)
+}
+
+/// Start either normally (create the main window) or as a background agent (`setup`).
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "agent", &["backgroundAgent"], reload);
+  let handle = app.clone();
+  app.listen_any(MAIN_READY_EVENT, move |_| {
+    let state = handle.state::<AgentState>();
+    *state.main_ready.lock().unwrap_or_else(|e| e.into_inner()) = true;
+    let pending = state.pending.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(action) = pending {
+      let _ = handle.emit_to(MAIN_LABEL, AGENT_ACTION_EVENT, action);
+    }
+  });
+
+  if !wanted(app) {
+    if let Err(e) = create_main(app, true) {
+      log::error!("{e}");
+    }
+    return;
+  }
+  if let Err(e) = enter(app) {
+    log::warn!("{e}");
+  }
+}
+
+/// Once the main window is gone, actions wait for the next one to be ready (`on_window_event`).
+pub fn on_window_event(window: &tauri::Window, event: &tauri::WindowEvent) {
+  if window.label() == MAIN_LABEL && matches!(event, tauri::WindowEvent::Destroyed) {
+    *window.state::<AgentState>().main_ready.lock().unwrap_or_else(|e| e.into_inner()) = false;
+  }
+}
+
+/// Keep running with no windows open (`RunEvent::ExitRequested`); explicit exits still go through.
+pub fn on_exit_requested(app: &tauri::AppHandle, code: Option<i32>, api: &tauri::ExitRequestApi) {
+  if code.is_none() && is_enabled(app) {
+    api.prevent_exit();
+  }
+}
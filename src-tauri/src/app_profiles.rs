@@ -0,0 +1,62 @@
+//! Per-application profiles: translation settings that follow the app the text comes from, e.g. casual mode in
+//! Discord and formal mode in Outlook.
+//!
+//! `appProfiles` is a list of `{ "app": "discord.exe", "targetLang": ..., "mode": ..., "ocrLang": ...,
+//! "hotkeys": false }`; every field but `app` is optional and falls back to the normal settings. The app is matched
+//! by executable name, case-insensitively and with or without `.exe`, against the window text was last taken from
+//! (see `history::origin_app`); the frontend asks for the profile with `get_app_profile` when it translates. A
+//! profile with `hotkeys: false` suspends the hotkeys while the app is in front (see `hotkey_suspend`). Windows only:
+//! elsewhere there's no foreground app to match and no profile applies.
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppProfile {
+  /// Executable the profile is for ("discord.exe" or "discord").
+  pub app: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub target_lang: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub mode: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ocr_lang: Option<String>,
+  /// `false` suspends the hotkeys while the app is in front.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub hotkeys: Option<bool>,
+}
+
+/// Executable name without `.exe`, lowercased.
+pub(crate) fn stem(name: &str) -> String {
+  let name = name.trim().to_lowercase();
+  name.strip_suffix(".exe").unwrap_or(&name).to_string()
+}
+
+/// The first profile for executable `exe`.
+pub fn matching(app: &tauri::AppHandle, exe: &str) -> Option<AppProfile> {
+  let exe = stem(exe);
+  settings::app_profiles(app).into_iter().find(|p| stem(&p.app) == exe)
+}
+
+/// Executables whose profile turns the hotkeys off.
+pub fn hotkeys_disabled(app: &tauri::AppHandle) -> Vec<String> {
+  settings::app_profiles(app)
+    .into_iter()
+    .filter(|p| p.hotkeys == Some(false))
+    .map(|p| p.app)
+    .collect()
+}
+
+/// The translation mode for the app text is being translated from: its profile's, else "standard".
+pub fn mode(app: &tauri::AppHandle) -> String {
+  get_app_profile(app.clone()).and_then(|p| p.mode).unwrap_or_else(|| "standard".to_string())
+}
+
+/// The profile for the app text is being translated from, if one matches.
+#[tauri::command]
+pub fn get_app_profile(app: tauri::AppHandle) -> Option<AppProfile> {
+  let exe = crate::history::origin_app(&app)?;
+  matching(&app, &exe)
+}
@@ -0,0 +1,321 @@
+//! Backing up all app data into one archive and restoring it, for moving to another machine or reinstalling.
+//!
+//! `create_backup` writes a zip with the settings, the history database, the glossary, the phrasebook, the usage
+//! statistics and the list of installed OCR languages, plus a `manifest.json` naming them. API keys stay in the
+//! keychain and are not backed up. `restore_backup` reads and checks everything in the archive before it replaces
+//! anything, then swaps each part in, putting back the ones already swapped in when one fails. The OCR language
+//! data itself isn't in the archive, so the languages that aren't installed here are returned for the frontend to
+//! download again (`download_tessdata`). A `backup.restored` event tells windows to reload their settings.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use crate::{clock, events, glossary, history, phrasebook, settings, settings_schema, temp, tessdata};
+use crate::usage::{DailyUsage, UsageState};
+
+/// Version of the archive layout, written to the manifest.
+const FORMAT: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const SETTINGS: &str = "settings.json";
+const HISTORY: &str = "history.sqlite3";
+const GLOSSARY: &str = "glossary.json";
+const PHRASEBOOK: &str = "phrasebook.json";
+const USAGE: &str = "usage.json";
+const TESSDATA: &str = "tessdata.json";
+/// Largest archive member read back, so a corrupt or hostile archive can't exhaust memory.
+const MAX_MEMBER_BYTES: u64 = 1024 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupManifest {
+  pub format: u32,
+  pub app_version: String,
+  pub created_at: u128,
+  /// Archive members besides the manifest.
+  pub files: Vec<String>,
+}
+
+/// An installed OCR language, as listed in the backup.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackedUpTessdata {
+  pub lang: String,
+  pub variant: Option<tessdata::Variant>,
+  pub sha256: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RestoreReport {
+  pub manifest: BackupManifest,
+  /// Parts that were restored (archive member names).
+  pub restored: Vec<String>,
+  /// OCR languages the backup had that aren't installed here.
+  pub missing_tessdata: Vec<BackedUpTessdata>,
+}
+
+fn app_data_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  app.path().app_data_dir().map_err(|e| format!("no app data dir: {e}"))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+  serde_json::to_vec_pretty(value).map_err(|e| format!("failed to serialize backup: {e}"))
+}
+
+fn write_archive(app: &tauri::AppHandle, path: &Path) -> Result<BackupManifest, String> {
+  let mut members: Vec<(&str, Vec<u8>)> = Vec::new();
+  if let Some(stored) = settings::stored(app) {
+    members.push((SETTINGS, to_json(&stored)?));
+  }
+  let db_copy = temp::path("history-backup", "sqlite3");
+  match history::copy_to(app, &db_copy) {
+    Ok(()) => {
+      let bytes = std::fs::read(&db_copy).map_err(|e| format!("cannot read history copy: {e}"));
+      let _ = std::fs::remove_file(&db_copy);
+      members.push((HISTORY, bytes?));
+    }
+    Err(e) => events::warn(app, None, "backup_partial", format!("the history isn't in the backup: {e}")),
+  }
+  members.push((GLOSSARY, to_json(&glossary::entries(app))?));
+  members.push((PHRASEBOOK, to_json(&phrasebook::phrases(app))?));
+  members.push((USAGE, to_json(&app.state::<UsageState>().days())?));
+  let langs: Vec<BackedUpTessdata> = tessdata::list_installed_tessdata()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|t| BackedUpTessdata {
+      lang: t.lang,
+      variant: t.variant,
+      sha256: t.sha256,
+    })
+    .collect();
+  members.push((TESSDATA, to_json(&langs)?));
+
+  let manifest = BackupManifest {
+    format: FORMAT,
+    app_version: app.package_info().version.to_string(),
+    created_at: clock::now_millis(),
+    files: members.iter().map(|(name, _)| name.to_string()).collect(),
+  };
+  let file = std::fs::File::create(path).map_err(|e| format!("cannot create {}: {e}", path.display()))?;
+  let mut zip = zip::ZipWriter::new(file);
+  let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+  let manifest_bytes = to_json(&manifest)?;
+  for (name, bytes) in std::iter::once((MANIFEST, &manifest_bytes)).chain(members.iter().map(|(n, b)| (*n, b))) {
+    zip.start_file(name, options).map_err(|e| format!("failed to write backup: {e}"))?;
+    zip.write_all(bytes).map_err(|e| format!("failed to write backup: {e}"))?;
+  }
+  zip.finish().map_err(|e| format!("failed to write backup: {e}"))?;
+  Ok(manifest)
+}
+
+/// Back up the settings, history, glossary, phrasebook, usage statistics and installed OCR languages to `path`
+/// (a `.zip`). The archive is written next to `path` first, so an existing backup is only replaced by a complete one.
+#[tauri::command]
+pub async fn create_backup(app: tauri::AppHandle, path: String) -> Result<BackupManifest, String> {
+  let path = PathBuf::from(path);
+  let partial = path.with_extension("partial");
+  let result = write_archive(&app, &partial).and_then(|manifest| {
+    std::fs::rename(&partial, &path).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    Ok(manifest)
+  });
+  if result.is_err() {
+    let _ = std::fs::remove_file(&partial);
+  }
+  result
+}
+
+/// Member `name` of the archive, if present.
+fn member<R: Read + std::io::Seek>(zip: &mut zip::ZipArchive<R>, name: &str) -> Result<Option<Vec<u8>>, String> {
+  let mut file = match zip.by_name(name) {
+    Ok(file) => file,
+    Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+    Err(e) => return Err(format!("corrupt backup: {e}")),
+  };
+  if file.size() > MAX_MEMBER_BYTES {
+    return Err(format!("corrupt backup: {name} is too large"));
+  }
+  let mut bytes = Vec::new();
+  file.read_to_end(&mut bytes).map_err(|e| format!("corrupt backup ({name}): {e}"))?;
+  Ok(Some(bytes))
+}
+
+fn from_json<T: DeserializeOwned>(name: &str, bytes: Option<Vec<u8>>) -> Result<Option<T>, String> {
+  bytes
+    .map(|b| serde_json::from_slice(&b).map_err(|e| format!("corrupt backup ({name}): {e}")))
+    .transpose()
+}
+
+/// The parts of a backup, read and checked; `history` is the database staged next to the live one.
+struct Parts {
+  history: Option<PathBuf>,
+  glossary: Option<Vec<glossary::GlossaryEntry>>,
+  phrasebook: Option<Vec<phrasebook::Phrase>>,
+  usage: Option<Vec<DailyUsage>>,
+  settings: Option<serde_json::Value>,
+}
+
+/// What a part held before the restore replaced it.
+enum Previous {
+  /// A copy of the history database; `None` when there was none to copy.
+  History(Option<PathBuf>),
+  Glossary(Vec<glossary::GlossaryEntry>),
+  Phrasebook(Vec<phrasebook::Phrase>),
+  Usage(Vec<DailyUsage>),
+  Settings(Option<serde_json::Value>),
+}
+
+impl Previous {
+  fn put_back(self, app: &tauri::AppHandle, dir: &Path) -> Result<(), String> {
+    match self {
+      Previous::History(Some(copy)) => {
+        let result = history::replace_with(app, dir, &copy);
+        let _ = std::fs::remove_file(&copy);
+        result
+      }
+      Previous::History(None) => Ok(()),
+      Previous::Glossary(entries) => glossary::replace(app, entries),
+      Previous::Phrasebook(phrases) => phrasebook::replace(app, phrases),
+      Previous::Usage(days) => {
+        app.state::<UsageState>().replace_days(days);
+        Ok(())
+      }
+      Previous::Settings(stored) => settings::save(app, stored.unwrap_or_else(|| serde_json::json!({}))),
+    }
+  }
+}
+
+/// Remove the history copy kept for undoing the restore.
+fn discard(previous: Vec<Previous>) {
+  for part in previous {
+    if let Previous::History(Some(copy)) = part {
+      let _ = std::fs::remove_file(copy);
+    }
+  }
+}
+
+/// Replace each part the backup has, recording in `previous` what it held; returns the restored members' names.
+fn swap_in(
+  app: &tauri::AppHandle,
+  dir: &Path,
+  parts: Parts,
+  previous: &mut Vec<Previous>,
+) -> Result<Vec<String>, String> {
+  let mut restored = Vec::new();
+  if let Some(staged) = parts.history {
+    let copy = dir.join(format!("{HISTORY}.previous"));
+    let _ = std::fs::remove_file(&copy);
+    let kept = match history::copy_to(app, &copy) {
+      Ok(()) => Some(copy),
+      Err(e) => {
+        log::warn!("no copy of the current history to undo the restore with: {e}");
+        None
+      }
+    };
+    // A database that fails to open is never swapped in, so there's nothing to put back then.
+    if let Err(e) = history::replace_with(app, dir, &staged) {
+      if let Some(copy) = kept {
+        let _ = std::fs::remove_file(copy);
+      }
+      return Err(e);
+    }
+    previous.push(Previous::History(kept));
+    restored.push(HISTORY.to_string());
+  }
+  if let Some(entries) = parts.glossary {
+    previous.push(Previous::Glossary(glossary::entries(app)));
+    glossary::replace(app, entries)?;
+    restored.push(GLOSSARY.to_string());
+  }
+  if let Some(phrases) = parts.phrasebook {
+    previous.push(Previous::Phrasebook(phrasebook::phrases(app)));
+    phrasebook::replace(app, phrases)?;
+    restored.push(PHRASEBOOK.to_string());
+  }
+  if let Some(days) = parts.usage {
+    let state = app.state::<UsageState>();
+    previous.push(Previous::Usage(state.days()));
+    state.replace_days(days);
+    restored.push(USAGE.to_string());
+  }
+  if let Some(stored) = parts.settings {
+    previous.push(Previous::Settings(settings::stored(app)));
+    settings::save(app, stored)?;
+    restored.push(SETTINGS.to_string());
+  }
+  Ok(restored)
+}
+
+/// Restore the backup at `path`, replacing the settings, history, glossary, phrasebook and usage statistics it
+/// contains. Nothing is replaced if any part is unreadable, and the parts already replaced are put back if one
+/// can't be.
+#[tauri::command]
+pub async fn restore_backup(app: tauri::AppHandle, path: String) -> Result<RestoreReport, String> {
+  let file = std::fs::File::open(&path).map_err(|e| format!("cannot open {path}: {e}"))?;
+  let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("not a backup: {e}"))?;
+  let manifest: BackupManifest =
+    from_json(MANIFEST, member(&mut zip, MANIFEST)?)?.ok_or_else(|| "not a backup: no manifest".to_string())?;
+  if manifest.format > FORMAT {
+    return Err(format!(
+      "the backup was made by a newer version ({}); update the app to restore it",
+      manifest.app_version
+    ));
+  }
+
+  // Read and check every part before anything is replaced.
+  let stored: Option<serde_json::Value> = from_json(SETTINGS, member(&mut zip, SETTINGS)?)?;
+  let entries: Option<Vec<glossary::GlossaryEntry>> = from_json(GLOSSARY, member(&mut zip, GLOSSARY)?)?;
+  let phrases: Option<Vec<phrasebook::Phrase>> = from_json(PHRASEBOOK, member(&mut zip, PHRASEBOOK)?)?;
+  let days: Option<Vec<DailyUsage>> = from_json(USAGE, member(&mut zip, USAGE)?)?;
+  let langs: Vec<BackedUpTessdata> = from_json(TESSDATA, member(&mut zip, TESSDATA)?)?.unwrap_or_default();
+  let stored = stored.map(settings_schema::sanitized);
+  let dir = app_data_dir(&app)?;
+  let staged_db = match member(&mut zip, HISTORY)? {
+    Some(bytes) => {
+      let staged = dir.join(format!("{HISTORY}.restore"));
+      std::fs::write(&staged, bytes).map_err(|e| format!("cannot stage the history: {e}"))?;
+      Some(staged)
+    }
+    None => None,
+  };
+  let parts = Parts {
+    history: staged_db.clone(),
+    glossary: entries,
+    phrasebook: phrases,
+    usage: days,
+    settings: stored,
+  };
+
+  // Swap every part in; if one fails, the ones already swapped in are put back.
+  let mut previous = Vec::new();
+  let swapped = swap_in(&app, &dir, parts, &mut previous);
+  if let Some(staged) = &staged_db {
+    let _ = std::fs::remove_file(staged);
+  }
+  let restored = match swapped {
+    Ok(restored) => {
+      discard(previous);
+      restored
+    }
+    Err(e) => {
+      for part in previous.into_iter().rev() {
+        if let Err(e) = part.put_back(&app, &dir) {
+          log::warn!("failed to undo a partial restore: {e}");
+        }
+      }
+      return Err(e);
+    }
+  };
+
+  let installed: Vec<String> = tessdata::list_installed_tessdata()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|t| t.lang)
+    .collect();
+  let missing_tessdata = langs.into_iter().filter(|t| !installed.contains(&t.lang)).collect();
+  events::publish(&app, "backup.restored", None, serde_json::json!({ "restored": restored }));
+  Ok(RestoreReport {
+    manifest,
+    restored,
+    missing_tessdata,
+  })
+}
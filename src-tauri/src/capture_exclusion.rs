@@ -0,0 +1,46 @@
+//! Keeping the app's own popup and overlays out of screen captures.
+//!
+//! Those windows are marked content-protected (`SetWindowDisplayAffinity(WDA_EXCLUDEFROMCAPTURE)` on Windows 10
+//! 2004+, `NSWindowSharingNone` on macOS), so capturing a region the popup overlaps, or re-capturing it with live
+//! OCR, reads the text underneath instead of the translation. `excludeOwnWindowsFromCapture` (default on) turns it
+//! off, e.g. for screenshots of the app itself; changes apply to open windows right away. Elsewhere it's a no-op.
+
+use tauri::Manager;
+
+use crate::settings;
+
+/// Whether `label` is one of the windows drawn over other apps.
+pub(crate) fn is_overlay(label: &str) -> bool {
+  label.starts_with("popup")
+    || crate::ocr_overlay::is_overlay_window(label)
+    || label == crate::translation_overlay::LABEL
+}
+
+fn log_failure(label: &str, result: tauri::Result<()>) {
+  if let Err(e) = result {
+    log::warn!("failed to set capture exclusion on {label}: {e}");
+  }
+}
+
+/// Mark popup and overlay windows as they load.
+pub fn on_page_load(webview: &tauri::Webview, payload: &tauri::webview::PageLoadPayload<'_>) {
+  if payload.event() != tauri::webview::PageLoadEvent::Started || !is_overlay(webview.label()) {
+    return;
+  }
+  let exclude = settings::exclude_own_windows_from_capture(webview.app_handle());
+  log_failure(webview.label(), webview.window().set_content_protected(exclude));
+}
+
+fn reload(app: &tauri::AppHandle, _: &settings::ConfigChange) -> Result<(), String> {
+  let exclude = settings::exclude_own_windows_from_capture(app);
+  for (label, window) in app.webview_windows() {
+    if is_overlay(&label) {
+      log_failure(&label, window.set_content_protected(exclude));
+    }
+  }
+  Ok(())
+}
+
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "capture-exclusion", &["excludeOwnWindowsFromCapture"], reload);
+}
@@ -0,0 +1,393 @@
+//! How a capture is saved: its largest allowed dimension and the file format.
+//!
+//! Full-screen captures of 4K/5K displays make PNGs of tens of megabytes that are slow to encode and to hand
+//! around. With `captureMaxDimension` set, larger captures are scaled down (area averaging, which keeps thin
+//! strokes legible) while they are encoded; `captureFormat: "jpeg"` saves them lossy when exact pixels don't
+//! matter, and `"webp"` lossless but smaller than PNG. PNGs are streamed row by row; JPEG and WebP (encoded by the
+//! `image` crate) are encoded once the last row is in. Captures used internally (OCR of selections, stitching,
+//! snapshots) stay lossless at full size.
+
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+  Png,
+  Jpeg,
+  WebP,
+}
+
+impl ImageFormat {
+  pub fn parse(s: &str) -> Result<ImageFormat, String> {
+    match s.to_ascii_lowercase().as_str() {
+      "png" => Ok(ImageFormat::Png),
+      "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+      "webp" => Ok(ImageFormat::WebP),
+      _ => Err(format!("unsupported capture format: {s}")),
+    }
+  }
+
+  /// The format a file name's extension stands for, if it's one we write.
+  pub fn from_path(path: &std::path::Path) -> Option<ImageFormat> {
+    path.extension().and_then(|e| e.to_str()).and_then(|e| ImageFormat::parse(e).ok())
+  }
+
+  fn extension(self) -> &'static str {
+    match self {
+      ImageFormat::Png => "png",
+      ImageFormat::Jpeg => "jpg",
+      ImageFormat::WebP => "webp",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureOutput {
+  /// Longest side in pixels; larger captures are scaled down to fit.
+  pub max_dimension: Option<u32>,
+  pub format: ImageFormat,
+  /// JPEG quality, 1..=100; PNG and WebP are always lossless.
+  pub quality: u8,
+}
+
+impl Default for CaptureOutput {
+  fn default() -> Self {
+    CaptureOutput { max_dimension: None, format: ImageFormat::Png, quality: 90 }
+  }
+}
+
+impl CaptureOutput {
+  /// Whether captures come out as full-size PNGs, as the capture backends write them.
+  pub fn is_lossless_full_size(&self) -> bool {
+    self.max_dimension.is_none() && self.format == ImageFormat::Png
+  }
+
+  /// The size a `width` x `height` capture is saved at.
+  pub fn fitted_size(&self, width: u32, height: u32) -> (u32, u32) {
+    match self.max_dimension {
+      Some(max) if width.max(height) > max && max > 0 => {
+        let scale = max as f64 / width.max(height) as f64;
+        (((width as f64 * scale).round() as u32).max(1), ((height as f64 * scale).round() as u32).max(1))
+      }
+      _ => (width, height),
+    }
+  }
+}
+
+/// Streaming area-average downscaler for RGB rows: each output pixel is the mean of the source pixels it covers,
+/// with partial weights at the edges.
+struct Downscaler {
+  /// `(source x, output x, weight)` for every overlap of a source column with an output column.
+  taps: Vec<(usize, usize, f32)>,
+  /// Source rows per output row.
+  scale_y: f64,
+  src_y: usize,
+  dst_y: usize,
+  acc: Vec<f32>,
+  row: Vec<f32>,
+  out: Vec<u8>,
+}
+
+impl Downscaler {
+  fn new(src_width: u32, src_height: u32, dst_width: u32, dst_height: u32) -> Downscaler {
+    let scale_x = src_width as f64 / dst_width as f64;
+    let mut taps = Vec::new();
+    for sx in 0..src_width as usize {
+      let (start, end) = (sx as f64, sx as f64 + 1.0);
+      let first = (start / scale_x).floor() as usize;
+      for dx in first..dst_width as usize {
+        let (lo, hi) = (dx as f64 * scale_x, (dx + 1) as f64 * scale_x);
+        if lo >= end {
+          break;
+        }
+        let overlap = hi.min(end) - lo.max(start);
+        if overlap > 0.0 {
+          taps.push((sx, dx, (overlap / scale_x) as f32));
+        }
+      }
+    }
+    let dst_width = dst_width as usize;
+    Downscaler {
+      taps,
+      scale_y: src_height as f64 / dst_height as f64,
+      src_y: 0,
+      dst_y: 0,
+      acc: vec![0.0; dst_width * 3],
+      row: vec![0.0; dst_width * 3],
+      out: vec![0; dst_width * 3],
+    }
+  }
+
+  /// Add the next source row; calls `emit` with each output row it completes.
+  fn push(&mut self, rgb: &[u8], mut emit: impl FnMut(&[u8]) -> Result<(), String>) -> Result<(), String> {
+    self.row.fill(0.0);
+    for &(sx, dx, w) in &self.taps {
+      for c in 0..3 {
+        self.row[dx * 3 + c] += rgb[sx * 3 + c] as f32 * w;
+      }
+    }
+    let (start, end) = (self.src_y as f64, self.src_y as f64 + 1.0);
+    self.src_y += 1;
+    let mut lo = start;
+    while lo < end - 1e-9 {
+      let boundary = (self.dst_y + 1) as f64 * self.scale_y;
+      let hi = boundary.min(end);
+      let w = ((hi - lo) / self.scale_y) as f32;
+      for (a, v) in self.acc.iter_mut().zip(&self.row) {
+        *a += v * w;
+      }
+      if boundary <= end + 1e-9 {
+        for (o, a) in self.out.iter_mut().zip(&self.acc) {
+          *o = a.round().clamp(0.0, 255.0) as u8;
+        }
+        emit(&self.out)?;
+        self.acc.fill(0.0);
+        self.dst_y += 1;
+      }
+      lo = hi;
+    }
+    Ok(())
+  }
+}
+
+#[allow(clippy::large_enum_variant)] // one per capture being written
+enum Encoder {
+  Png(png::StreamWriter<'static, std::io::BufWriter<std::fs::File>>),
+  /// RGB rows collected for the `image` crate's JPEG or WebP encoder.
+  Buffered {
+    file: std::io::BufWriter<std::fs::File>,
+    format: ImageFormat,
+    quality: u8,
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+  },
+}
+
+/// Encode a whole `width` x `height` RGB image as `format` (JPEG or WebP) to `out`.
+fn encode_buffered(
+  out: impl Write,
+  format: ImageFormat,
+  quality: u8,
+  width: u32,
+  height: u32,
+  rgb: &[u8],
+) -> Result<(), String> {
+  use image::ImageEncoder;
+  let color = image::ExtendedColorType::Rgb8;
+  match format {
+    ImageFormat::Jpeg => image::codecs::jpeg::JpegEncoder::new_with_quality(out, quality)
+      .write_image(rgb, width, height, color)
+      .map_err(|e| format!("jpeg write failed: {e}")),
+    // The `image` crate only writes lossless WebP; `save_capture` refuses a quality for it.
+    ImageFormat::WebP => image::codecs::webp::WebPEncoder::new_lossless(out)
+      .write_image(rgb, width, height, color)
+      .map_err(|e| format!("webp write failed: {e}")),
+    ImageFormat::Png => Err("png captures are streamed".to_string()),
+  }
+}
+
+/// Writes a capture row by row (RGB, top to bottom) to a temp file as `CaptureOutput` asks.
+pub struct CaptureWriter {
+  path: std::path::PathBuf,
+  encoder: Encoder,
+  downscaler: Option<Downscaler>,
+}
+
+impl CaptureWriter {
+  /// Start a temp file for a `width` x `height` capture.
+  pub fn create(width: u32, height: u32, output: &CaptureOutput) -> Result<CaptureWriter, String> {
+    let path = crate::temp::path("capture", output.format.extension());
+    match Self::open(&path, width, height, output) {
+      Ok(writer) => Ok(writer),
+      Err(e) => {
+        let _ = std::fs::remove_file(&path);
+        Err(e)
+      }
+    }
+  }
+
+  fn open(path: &std::path::Path, width: u32, height: u32, output: &CaptureOutput) -> Result<CaptureWriter, String> {
+    let (out_width, out_height) = output.fitted_size(width, height);
+    let file = std::fs::File::create(path).map_err(|e| format!("create capture failed: {e}"))?;
+    let file = std::io::BufWriter::new(file);
+    let encoder = match output.format {
+      ImageFormat::Png => {
+        let mut encoder = png::Encoder::new(file, out_width, out_height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let writer = encoder.write_header().map_err(|e| format!("png header failed: {e}"))?;
+        Encoder::Png(writer.into_stream_writer().map_err(|e| format!("png write failed: {e}"))?)
+      }
+      format @ (ImageFormat::Jpeg | ImageFormat::WebP) => Encoder::Buffered {
+        file,
+        format,
+        quality: output.quality,
+        width: out_width,
+        height: out_height,
+        rgb: Vec::with_capacity(out_width as usize * out_height as usize * 3),
+      },
+    };
+    let downscaler = ((out_width, out_height) != (width, height))
+      .then(|| Downscaler::new(width, height, out_width, out_height));
+    Ok(CaptureWriter { path: path.to_path_buf(), encoder, downscaler })
+  }
+
+  /// Add the next row of the capture.
+  pub fn write_row(&mut self, rgb: &[u8]) -> Result<(), String> {
+    let encoder = &mut self.encoder;
+    let mut encode = |row: &[u8]| match encoder {
+      Encoder::Png(stream) => stream.write_all(row).map_err(|e| format!("png write failed: {e}")),
+      Encoder::Buffered { rgb, .. } => {
+        rgb.extend_from_slice(row);
+        Ok(())
+      }
+    };
+    match &mut self.downscaler {
+      Some(downscaler) => downscaler.push(rgb, encode),
+      None => encode(rgb),
+    }
+  }
+
+  /// Finish the file and return its path.
+  pub fn finish(self) -> Result<String, String> {
+    let result = match self.encoder {
+      Encoder::Png(stream) => stream.finish().map_err(|e| format!("png write failed: {e}")),
+      Encoder::Buffered { mut file, format, quality, width, height, rgb } => {
+        encode_buffered(&mut file, format, quality, width, height, &rgb)
+          .and_then(|()| file.flush().map_err(|e| format!("capture write failed: {e}")))
+      }
+    };
+    match result {
+      Ok(()) => Ok(self.path.to_string_lossy().to_string()),
+      Err(e) => {
+        let _ = std::fs::remove_file(&self.path);
+        Err(e)
+      }
+    }
+  }
+
+  /// Give up on the file.
+  pub fn discard(self) {
+    let _ = std::fs::remove_file(&self.path);
+  }
+}
+
+/// Write `rows` (a `width` x `height` RGB capture) through a `CaptureWriter`.
+pub fn write_rows<'a>(
+  width: u32,
+  height: u32,
+  rows: impl IntoIterator<Item = &'a [u8]>,
+  output: &CaptureOutput,
+) -> Result<String, String> {
+  let mut writer = CaptureWriter::create(width, height, output)?;
+  for row in rows {
+    if let Err(e) = writer.write_row(row) {
+      writer.discard();
+      return Err(e);
+    }
+  }
+  writer.finish()
+}
+
+/// Re-encode a full-size PNG capture as `output` asks, replacing it (for backends that write PNGs themselves).
+#[cfg_attr(windows, allow(dead_code))]
+pub fn convert_png(path: String, output: &CaptureOutput) -> Result<String, String> {
+  if output.is_lossless_full_size() {
+    return Ok(path);
+  }
+  let converted = encode_file(&path, output);
+  let _ = std::fs::remove_file(&path);
+  converted
+}
+
+/// Encode the image at `path` (a PNG capture, or any format `image_io` reads) to a new temp file as `output` asks,
+/// leaving the original alone.
+pub fn encode_file(path: &str, output: &CaptureOutput) -> Result<String, String> {
+  let rgb = crate::image_io::read_rgb(path)?;
+  let (width, height) = rgb.dimensions();
+  write_rows(width, height, rgb.as_raw().chunks_exact(width as usize * 3), output)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A `width` x `height` RGB gradient.
+  fn gradient(width: u32, height: u32) -> Vec<u8> {
+    (0..height)
+      .flat_map(|y| (0..width).flat_map(move |x| [(x * 40) as u8, (y * 40) as u8, 200]))
+      .collect()
+  }
+
+  fn write(width: u32, height: u32, rgb: &[u8], output: &CaptureOutput) -> image::RgbImage {
+    let path = write_rows(width, height, rgb.chunks_exact(width as usize * 3), output).unwrap();
+    let image = crate::image_io::read_rgb(&path);
+    let _ = std::fs::remove_file(&path);
+    image.unwrap()
+  }
+
+  #[test]
+  fn parses_formats() {
+    assert_eq!(ImageFormat::parse("JPG"), Ok(ImageFormat::Jpeg));
+    assert_eq!(ImageFormat::from_path(std::path::Path::new("a/b.webp")), Some(ImageFormat::WebP));
+    assert!(ImageFormat::parse("gif").is_err());
+  }
+
+  #[test]
+  fn fits_the_longest_side() {
+    let output = CaptureOutput { max_dimension: Some(1000), ..Default::default() };
+    assert_eq!(output.fitted_size(4000, 2000), (1000, 500));
+    assert_eq!(output.fitted_size(800, 600), (800, 600));
+    assert_eq!(output.fitted_size(100_000, 10), (1000, 1));
+    let unlimited = CaptureOutput { max_dimension: Some(0), ..Default::default() };
+    assert_eq!(unlimited.fitted_size(4000, 2000), (4000, 2000));
+  }
+
+  #[test]
+  fn downscaler_averages_areas() {
+    // Black and white columns, halved both ways: every output pixel covers two of each.
+    let row: Vec<u8> = (0..4).flat_map(|x| [if x % 2 == 0 { 0 } else { 255 }; 3]).collect();
+    let mut downscaler = Downscaler::new(4, 4, 2, 2);
+    let mut out = Vec::new();
+    for _ in 0..4 {
+      downscaler
+        .push(&row, |r| {
+          out.push(r.to_vec());
+          Ok(())
+        })
+        .unwrap();
+    }
+    assert_eq!(out, vec![vec![128; 6]; 2]);
+  }
+
+  #[test]
+  fn png_and_webp_are_lossless() {
+    let rgb = gradient(5, 3);
+    for format in [ImageFormat::Png, ImageFormat::WebP] {
+      let image = write(5, 3, &rgb, &CaptureOutput { format, ..Default::default() });
+      assert_eq!(image.dimensions(), (5, 3));
+      assert_eq!(image.into_raw(), rgb, "{format:?}");
+    }
+  }
+
+  #[test]
+  fn jpeg_is_close() {
+    let rgb = vec![90; 16 * 16 * 3];
+    let output = CaptureOutput { format: ImageFormat::Jpeg, quality: 95, ..Default::default() };
+    let image = write(16, 16, &rgb, &output);
+    assert_eq!(image.dimensions(), (16, 16));
+    assert!(image.into_raw().iter().all(|&v| v.abs_diff(90) <= 2));
+  }
+
+  #[test]
+  fn scales_while_encoding() {
+    let output = CaptureOutput { max_dimension: Some(4), ..Default::default() };
+    let image = write(8, 2, &gradient(8, 2), &output);
+    assert_eq!(image.dimensions(), (4, 1));
+  }
+
+  #[test]
+  fn png_is_not_buffered() {
+    assert!(encode_buffered(Vec::new(), ImageFormat::Png, 90, 1, 1, &[0, 0, 0]).is_err());
+  }
+}
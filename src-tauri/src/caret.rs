@@ -0,0 +1,142 @@
+//! Where the text caret of the focused control is, so the popup can open at the text being typed or read rather
+//! than at the pointer.
+//!
+//! Windows reports the system caret of the foreground thread (`GetGUIThreadInfo`), which native edit controls and
+//! most classic apps keep; browsers and Electron apps draw their own and report none. macOS asks the focused
+//! element for the bounds of its selected text range (Accessibility, the permission the selection copy already
+//! needs). Coordinates are the same as `get_cursor_position`'s. Elsewhere, and whenever the caret can't be found,
+//! the answer is `None` and the caller falls back to the pointer.
+
+use crate::commands::CaptureRect;
+
+#[cfg(windows)]
+fn caret_rect() -> Option<CaptureRect> {
+  use windows_sys::Win32::Foundation::POINT;
+  use windows_sys::Win32::Graphics::Gdi::ClientToScreen;
+  use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, GUITHREADINFO,
+  };
+
+  unsafe {
+    let foreground = GetForegroundWindow();
+    if foreground.is_null() {
+      return None;
+    }
+    let thread = GetWindowThreadProcessId(foreground, std::ptr::null_mut());
+    let mut info: GUITHREADINFO = std::mem::zeroed();
+    info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+    if GetGUIThreadInfo(thread, &mut info) == 0 || info.hwndCaret.is_null() {
+      return None;
+    }
+    let rc = info.rcCaret;
+    let mut top_left = POINT { x: rc.left, y: rc.top };
+    if ClientToScreen(info.hwndCaret, &mut top_left) == 0 {
+      return None;
+    }
+    Some(CaptureRect {
+      x: top_left.x,
+      y: top_left.y,
+      width: (rc.right - rc.left).max(1) as u32,
+      height: (rc.bottom - rc.top).max(1) as u32,
+    })
+  }
+}
+
+#[cfg(target_os = "macos")]
+fn caret_rect() -> Option<CaptureRect> {
+  use core_graphics::geometry::CGRect;
+  use std::ffi::{c_char, c_void, CStr};
+
+  type CFTypeRef = *const c_void;
+  const K_AX_ERROR_SUCCESS: i32 = 0;
+  const K_AX_VALUE_CG_RECT_TYPE: u32 = 3;
+  const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+  #[link(name = "ApplicationServices", kind = "framework")]
+  extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+    fn AXUIElementCreateSystemWide() -> CFTypeRef;
+    fn AXUIElementCopyAttributeValue(element: CFTypeRef, attribute: CFTypeRef, value: *mut CFTypeRef) -> i32;
+    fn AXUIElementCopyParameterizedAttributeValue(
+      element: CFTypeRef,
+      attribute: CFTypeRef,
+      parameter: CFTypeRef,
+      value: *mut CFTypeRef,
+    ) -> i32;
+    fn AXValueGetValue(value: CFTypeRef, value_type: u32, out: *mut c_void) -> bool;
+  }
+
+  #[link(name = "CoreFoundation", kind = "framework")]
+  extern "C" {
+    fn CFStringCreateWithCString(alloc: CFTypeRef, s: *const c_char, encoding: u32) -> CFTypeRef;
+    fn CFRelease(cf: CFTypeRef);
+  }
+
+  /// A `CFTypeRef` we own, released on drop.
+  struct Owned(CFTypeRef);
+  impl Drop for Owned {
+    fn drop(&mut self) {
+      if !self.0.is_null() {
+        unsafe { CFRelease(self.0) };
+      }
+    }
+  }
+
+  unsafe fn name(s: &CStr) -> Owned {
+    Owned(CFStringCreateWithCString(std::ptr::null(), s.as_ptr(), K_CF_STRING_ENCODING_UTF8))
+  }
+
+  unsafe fn attribute(element: &Owned, attr: &CStr) -> Option<Owned> {
+    let attr = name(attr);
+    let mut value: CFTypeRef = std::ptr::null();
+    let err = AXUIElementCopyAttributeValue(element.0, attr.0, &mut value);
+    (err == K_AX_ERROR_SUCCESS && !value.is_null()).then_some(Owned(value))
+  }
+
+  unsafe {
+    if !AXIsProcessTrusted() {
+      return None;
+    }
+    let system = Owned(AXUIElementCreateSystemWide());
+    let focused = attribute(&system, c"AXFocusedUIElement")?;
+    let range = attribute(&focused, c"AXSelectedTextRange")?;
+    let bounds_attr = name(c"AXBoundsForRange");
+    let mut bounds: CFTypeRef = std::ptr::null();
+    let err = AXUIElementCopyParameterizedAttributeValue(focused.0, bounds_attr.0, range.0, &mut bounds);
+    if err != K_AX_ERROR_SUCCESS || bounds.is_null() {
+      return None;
+    }
+    let bounds = Owned(bounds);
+    let mut rect: CGRect = std::mem::zeroed();
+    if !AXValueGetValue(bounds.0, K_AX_VALUE_CG_RECT_TYPE, &mut rect as *mut CGRect as *mut c_void) {
+      return None;
+    }
+    // Some apps answer an empty range with a zero rect at the screen origin.
+    if rect.size.height <= 0.0 && rect.origin.x == 0.0 && rect.origin.y == 0.0 {
+      return None;
+    }
+    Some(CaptureRect {
+      x: rect.origin.x.round() as i32,
+      y: rect.origin.y.round() as i32,
+      width: (rect.size.width.round() as u32).max(1),
+      height: (rect.size.height.round() as u32).max(1),
+    })
+  }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn caret_rect() -> Option<CaptureRect> {
+  None
+}
+
+/// The caret of the focused control in the foreground app, or `None` when it can't be found (see above).
+#[tauri::command]
+#[cfg_attr(feature = "deterministic", allow(unreachable_code))]
+pub fn get_caret_position() -> Option<CaptureRect> {
+  #[cfg(feature = "deterministic")]
+  {
+    return None;
+  }
+
+  caret_rect()
+}
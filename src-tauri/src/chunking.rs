@@ -0,0 +1,139 @@
+//! Sentence-aware splitting of long source text into request-sized chunks.
+
+/// Default maximum characters per translation request.
+pub const DEFAULT_MAX_CHUNK_CHARS: usize = 4000;
+
+fn is_sentence_end(c: char) -> bool {
+  matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '．' | '…')
+}
+
+/// Split into sentences, keeping terminators and trailing whitespace attached so chunks rejoin losslessly.
+fn sentences(text: &str) -> Vec<&str> {
+  let mut out: Vec<&str> = Vec::new();
+  let mut start = 0;
+  let mut chars = text.char_indices().peekable();
+  while let Some((i, c)) = chars.next() {
+    let boundary = if c == '\n' {
+      true
+    } else if is_sentence_end(c) {
+      // End of sentence only if followed by whitespace/end (avoids "3.14"); CJK terminators need no space.
+      // Runs like "?!" stay together.
+      match chars.peek() {
+        None => true,
+        Some((_, n)) => !is_sentence_end(*n) && (n.is_whitespace() || !c.is_ascii()),
+      }
+    } else {
+      false
+    };
+    if boundary {
+      let mut end = i + c.len_utf8();
+      while let Some((j, n)) = chars.peek() {
+        if n.is_whitespace() {
+          end = j + n.len_utf8();
+          chars.next();
+        } else {
+          break;
+        }
+      }
+      out.push(&text[start..end]);
+      start = end;
+    }
+  }
+  if start < text.len() {
+    out.push(&text[start..]);
+  }
+  out
+}
+
+/// Hard-split a single overlong sentence, preferring whitespace near the limit.
+fn split_long(s: &str, max_chars: usize) -> Vec<String> {
+  let mut out: Vec<String> = Vec::new();
+  let mut rest = s;
+  while rest.chars().count() > max_chars {
+    let limit = rest.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(rest.len());
+    let cut = rest[..limit]
+      .rfind(char::is_whitespace)
+      .filter(|&i| i > limit / 2)
+      .map(|i| i + rest[i..].chars().next().map(|c| c.len_utf8()).unwrap_or(1))
+      .unwrap_or(limit);
+    out.push(rest[..cut].to_string());
+    rest = &rest[cut..];
+  }
+  if !rest.is_empty() {
+    out.push(rest.to_string());
+  }
+  out
+}
+
+/// Split `text` into chunks of at most `max_chars` characters on sentence boundaries.
+/// Concatenating the chunks yields the original text.
+pub fn split_text(text: &str, max_chars: usize) -> Vec<String> {
+  let max_chars = max_chars.max(1);
+  if text.chars().count() <= max_chars {
+    return vec![text.to_string()];
+  }
+  let mut chunks: Vec<String> = Vec::new();
+  let mut cur = String::new();
+  let mut cur_len = 0usize;
+  for sentence in sentences(text) {
+    let len = sentence.chars().count();
+    if len > max_chars {
+      if !cur.is_empty() {
+        chunks.push(std::mem::take(&mut cur));
+        cur_len = 0;
+      }
+      chunks.extend(split_long(sentence, max_chars));
+      continue;
+    }
+    if cur_len + len > max_chars && !cur.is_empty() {
+      chunks.push(std::mem::take(&mut cur));
+      cur_len = 0;
+    }
+    cur.push_str(sentence);
+    cur_len += len;
+  }
+  if !cur.is_empty() {
+    chunks.push(cur);
+  }
+  chunks
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sentences_keep_terminators_and_spaces() {
+    assert_eq!(sentences("Hello world. How are you?"), ["Hello world. ", "How are you?"]);
+    assert_eq!(sentences("Really?! Yes.\nNext"), ["Really?! ", "Yes.\n", "Next"]);
+    assert_eq!(sentences("え？本当！"), ["え？", "本当！"]);
+  }
+
+  #[test]
+  fn sentences_dont_end_inside_numbers() {
+    assert_eq!(sentences("Pi is 3.14 exactly."), ["Pi is 3.14 exactly."]);
+  }
+
+  #[test]
+  fn split_text_packs_whole_sentences() {
+    assert_eq!(split_text("Short.", 10), ["Short."]);
+    assert_eq!(split_text("One. Two. Three.", 10), ["One. Two. ", "Three."]);
+  }
+
+  #[test]
+  fn split_text_cuts_long_sentences_at_whitespace() {
+    assert_eq!(split_text("aaaa bbbb cccc", 10), ["aaaa bbbb ", "cccc"]);
+    assert_eq!(split_text("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+    assert_eq!(split_text("ab", 0), ["a", "b"]);
+  }
+
+  #[test]
+  fn split_text_rejoins_to_the_original() {
+    let text = "第一文。第二文です！ Then some English. And a very long sentence without any stop at all";
+    for max in [1, 5, 12, 40] {
+      let chunks = split_text(text, max);
+      assert_eq!(chunks.concat(), text);
+      assert!(chunks.iter().all(|c| c.chars().count() <= max));
+    }
+  }
+}
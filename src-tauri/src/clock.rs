@@ -0,0 +1,49 @@
+//! Time and ID source for the backend.
+//!
+//! With the `deterministic` feature the clock is a settable counter and IDs come from a seeded sequence,
+//! so integration tests and repro scripts produce stable file names, sentinels and IDs.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static ID_SEQ: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "deterministic")]
+static FAKE_NOW_MS: AtomicU64 = AtomicU64::new(1_700_000_000_000);
+
+/// Milliseconds since the Unix epoch.
+pub fn now_millis() -> u128 {
+  #[cfg(feature = "deterministic")]
+  {
+    FAKE_NOW_MS.load(Ordering::SeqCst) as u128
+  }
+
+  #[cfg(not(feature = "deterministic"))]
+  {
+    std::time::SystemTime::now()
+      .duration_since(std::time::UNIX_EPOCH)
+      .map(|d| d.as_millis())
+      .unwrap_or(0)
+  }
+}
+
+/// Unique ID with the given prefix, e.g. `job-1700000000000-3`.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn next_id(prefix: &str) -> String {
+  let n = ID_SEQ.fetch_add(1, Ordering::SeqCst);
+  format!("{}-{}-{}", prefix, now_millis(), n)
+}
+
+#[cfg(feature = "deterministic")]
+pub fn set_now(ms: u64) {
+  FAKE_NOW_MS.store(ms, Ordering::SeqCst);
+}
+
+#[cfg(feature = "deterministic")]
+pub fn advance(ms: u64) {
+  FAKE_NOW_MS.fetch_add(ms, Ordering::SeqCst);
+}
+
+#[cfg(feature = "deterministic")]
+pub fn reset_ids(seed: u64) {
+  ID_SEQ.store(seed, Ordering::SeqCst);
+}
@@ -0,0 +1,131 @@
+//! Locale-aware ordering (ICU4X collation) for lists shown to the user: history, glossary, phrasebook.
+//!
+//! Byte order puts "Äpfel" after "zebra" and scatters kana/kanji; a collator sorts per the user's locale
+//! (e.g. Swedish sorts "ä" after "z", Turkish distinguishes dotted/dotless i). Callers pass an optional
+//! BCP-47 locale per query; without one the `collationLocale` setting, then the OS locale, is used.
+
+use icu_collator::options::{CollatorOptions, Strength};
+use icu_collator::{Collator, CollatorBorrowed, CollatorPreferences};
+use icu_locale_core::Locale;
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+/// Per-query collation options (camelCase on the wire, all optional).
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CollationOptions {
+  /// BCP-47 tag such as "ja", "tr", "sv" or "de-u-co-phonebk".
+  pub locale: Option<String>,
+  /// "base" (ignore accents and case), "accent", "case" or "variant" (default: "case").
+  pub sensitivity: Option<String>,
+  /// Descending order.
+  #[serde(default)]
+  pub descending: bool,
+}
+
+/// Locale used when a query doesn't specify one.
+pub fn default_locale(app: &tauri::AppHandle) -> String {
+  crate::settings::collation_locale(app)
+    .or_else(sys_locale::get_locale)
+    .unwrap_or_else(|| "und".to_string())
+}
+
+/// Build a collator; unknown or malformed locales fall back to the root collation.
+pub fn collator(locale: &str, sensitivity: Option<&str>) -> Result<CollatorBorrowed<'static>, String> {
+  let locale: Locale = locale
+    .replace('_', "-")
+    .parse()
+    .unwrap_or(Locale::UNKNOWN);
+  let mut options = CollatorOptions::default();
+  options.strength = Some(match sensitivity.unwrap_or("case") {
+    "base" => Strength::Primary,
+    "accent" => Strength::Secondary,
+    "variant" => Strength::Quaternary,
+    _ => Strength::Tertiary,
+  });
+  Collator::try_new(CollatorPreferences::from(&locale), options).map_err(|e| format!("collator unavailable: {e}"))
+}
+
+/// Comparator for the query's options; ties keep their original order when used with a stable sort.
+pub fn comparator(
+  app: &tauri::AppHandle,
+  opts: &CollationOptions,
+) -> Result<impl Fn(&str, &str) -> Ordering, String> {
+  let locale = opts
+    .locale
+    .clone()
+    .filter(|s| !s.trim().is_empty())
+    .unwrap_or_else(|| default_locale(app));
+  let collator = collator(&locale, opts.sensitivity.as_deref())?;
+  let descending = opts.descending;
+  Ok(move |a: &str, b: &str| {
+    let ord = collator.compare(a, b);
+    if descending {
+      ord.reverse()
+    } else {
+      ord
+    }
+  })
+}
+
+/// Sort `items` in place by a string key.
+pub fn sort_by_key<T>(
+  app: &tauri::AppHandle,
+  items: &mut [T],
+  opts: &CollationOptions,
+  key: impl Fn(&T) -> &str,
+) -> Result<(), String> {
+  let cmp = comparator(app, opts)?;
+  items.sort_by(|a, b| cmp(key(a), key(b)));
+  Ok(())
+}
+
+#[tauri::command]
+pub fn collation_locale(app: tauri::AppHandle) -> String {
+  default_locale(&app)
+}
+
+/// Sort arbitrary strings for lists the frontend builds itself.
+#[tauri::command]
+pub fn sort_strings(
+  app: tauri::AppHandle,
+  mut items: Vec<String>,
+  options: Option<CollationOptions>,
+) -> Result<Vec<String>, String> {
+  sort_by_key(&app, &mut items, &options.unwrap_or_default(), |s| s.as_str())?;
+  Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sorted(locale: &str, mut items: Vec<&'static str>) -> Vec<&'static str> {
+    let collator = collator(locale, None).unwrap();
+    items.sort_by(|a, b| collator.compare(a, b));
+    items
+  }
+
+  #[test]
+  fn sorts_per_locale() {
+    assert_eq!(sorted("de", vec!["zebra", "Äpfel", "Apfel"]), ["Apfel", "Äpfel", "zebra"]);
+    assert_eq!(sorted("sv", vec!["ä", "z", "a"]), ["a", "z", "ä"]);
+    assert_eq!(sorted("sv_SE", vec!["ä", "z"]), ["z", "ä"]);
+  }
+
+  #[test]
+  fn sensitivity_sets_what_counts_as_equal() {
+    let base = collator("en", Some("base")).unwrap();
+    assert_eq!(base.compare("resume", "Résumé"), Ordering::Equal);
+    let accent = collator("en", Some("accent")).unwrap();
+    assert_eq!(accent.compare("resume", "Resume"), Ordering::Equal);
+    assert_ne!(accent.compare("resume", "résumé"), Ordering::Equal);
+    let case = collator("en", None).unwrap();
+    assert_ne!(case.compare("resume", "Resume"), Ordering::Equal);
+  }
+
+  #[test]
+  fn malformed_locales_use_the_root_collation() {
+    assert_eq!(sorted("not a locale!", vec!["b", "Ä", "a"]), ["a", "Ä", "b"]);
+  }
+}
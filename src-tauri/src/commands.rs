@@ -1,3 +1,13 @@
+use crate::accel;
+use crate::capture_output::CaptureOutput;
+use crate::clock;
+use crate::events;
+use crate::output::OutputControls;
+use crate::queue::Priority;
+use crate::translate::{self, TranslateRequest};
+use crate::usage::TokenPricing;
+#[cfg(windows)]
+use crate::win_gfx;
 use serde::{Deserialize, Serialize};
 use tauri::ipc::Channel;
 // (no hashing needed)
@@ -6,11 +16,9 @@ use windows_sys::Win32::Foundation::POINT;
 #[cfg(windows)]
 use windows_sys::Win32::UI::WindowsAndMessaging::GetCursorPos;
 #[cfg(windows)]
-use windows_sys::Win32::Foundation::HWND;
-#[cfg(windows)]
 use windows_sys::Win32::Graphics::Gdi::{
-  BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits, ReleaseDC,
-  SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, CAPTUREBLT, DIB_RGB_COLORS, HBITMAP, HDC, SRCCOPY,
+  BitBlt, GetDIBits, PatBlt, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, BLACKNESS, CAPTUREBLT, DIB_RGB_COLORS, HBITMAP,
+  HDC, SRCCOPY,
 };
 #[cfg(windows)]
 use windows_sys::Win32::UI::Shell::ShellExecuteW;
@@ -24,12 +32,47 @@ use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
 #[derive(Debug, Serialize, Clone)]
 #[serde(tag = "type")]
 pub enum StreamEvent {
+  /// The request was queued; `request_id` can be passed to `cancel_request`.
+  #[serde(rename = "queued")]
+  Queued { request_id: String },
   #[serde(rename = "delta")]
   Delta { content: String },
+  /// Following deltas belong to chunk `index` of `total` (long input is translated in chunks).
+  #[serde(rename = "chunk")]
+  Chunk { index: usize, total: usize },
+  /// Following deltas belong to this section (e.g. "translation", "explanation").
+  #[serde(rename = "section")]
+  Section { name: String },
+  /// Replaces the text streamed so far (e.g. after post-processing).
+  #[serde(rename = "replace")]
+  Replace { content: String },
+  /// Text streamed for alternative translation `index` (1-based), alongside or after the main translation.
+  #[serde(rename = "variant_delta")]
+  VariantDelta { index: usize, content: String },
+  /// Alternative translation `index` complete, replacing what was streamed for it once the main translation has
+  /// finished; empty when it was dropped (a duplicate of the main translation or of another alternative).
+  #[serde(rename = "variant")]
+  Variant { index: usize, content: String },
+  /// The stream dropped mid-translation and is being resumed (`attempt` starts at 1).
+  #[serde(rename = "reconnecting")]
+  Reconnecting { attempt: u32 },
+  #[serde(rename = "usage")]
+  Usage {
+    input_tokens: u64,
+    output_tokens: u64,
+    estimated_cost: f64,
+  },
   #[serde(rename = "done")]
   Done,
   #[serde(rename = "error")]
   Error { message: String },
+  /// Something degraded the result without failing it (fallback engine used, output cut, ...).
+  /// `code` is a stable snake_case identifier (see `events::warn`), `message` is for display.
+  #[serde(rename = "warning")]
+  Warning { code: String, message: String },
+  /// Cancelled by a newer request or `cancel_request`.
+  #[serde(rename = "cancelled")]
+  Cancelled,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -37,6 +80,8 @@ pub struct DetectResult {
   pub detected_lang: String,
   pub confidence: f64,
   pub is_mixed: bool,
+  /// Which detector answered: "server", "local" (fallback) or "mock".
+  pub engine: String,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -45,7 +90,7 @@ pub struct CursorPosition {
   pub y: i32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct CaptureRect {
   pub x: i32,
   pub y: i32,
@@ -54,7 +99,13 @@ pub struct CaptureRect {
 }
 
 #[tauri::command]
+#[cfg_attr(feature = "deterministic", allow(unreachable_code))]
 pub fn get_cursor_position() -> Result<CursorPosition, String> {
+  #[cfg(feature = "deterministic")]
+  {
+    return Ok(CursorPosition { x: 100, y: 100 });
+  }
+
   #[cfg(windows)]
   unsafe {
     let mut pt = POINT { x: 0, y: 0 };
@@ -83,16 +134,17 @@ pub fn get_cursor_position() -> Result<CursorPosition, String> {
   }
 }
 
-fn normalize_base_url(base_url: &str) -> String {
+pub(crate) fn normalize_base_url(base_url: &str) -> String {
   let trimmed = base_url.trim().trim_end_matches('/');
   trimmed.to_string()
 }
 
 #[tauri::command]
-pub async fn capture_selected_text(timeout_ms: Option<u64>) -> Result<String, String> {
+pub async fn capture_selected_text(app: tauri::AppHandle, timeout_ms: Option<u64>) -> Result<String, String> {
   // Strategy: save clipboard text -> simulate Ctrl/Cmd+C -> poll clipboard -> restore.
   // NOTE: This only preserves text clipboard (v0). Non-text clipboard formats are not preserved yet.
   let timeout_ms = timeout_ms.unwrap_or(1200);
+  crate::type_back::remember_target(&app);
 
   let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
   let prev_text = clipboard.get_text().ok();
@@ -101,28 +153,30 @@ pub async fn capture_selected_text(timeout_ms: Option<u64>) -> Result<String, St
   std::thread::sleep(std::time::Duration::from_millis(180));
 
   // Put a sentinel into clipboard so we can reliably detect changes even if the copied text equals previous clipboard.
-  let sentinel = format!(
-    "__ERUDAITE_SENTINEL__{}__",
-    std::time::SystemTime::now()
-      .duration_since(std::time::UNIX_EPOCH)
-      .map(|d| d.as_millis())
-      .unwrap_or(0)
-  );
+  let sentinel = format!("__ERUDAITE_SENTINEL__{}__", clock::now_millis());
   let _ = clipboard.set_text(sentinel.clone());
   // Wait until the sentinel is actually observable (Windows clipboard can lag).
-  let mut _sentinel_observed = false;
+  let mut sentinel_observed = false;
   {
     let started = std::time::Instant::now();
     while started.elapsed().as_millis() < 300 {
       if let Ok(cur) = clipboard.get_text() {
         if cur.trim() == sentinel {
-          _sentinel_observed = true;
+          sentinel_observed = true;
           break;
         }
       }
       std::thread::sleep(std::time::Duration::from_millis(20));
     }
   }
+  if !sentinel_observed {
+    events::warn(
+      &app,
+      None,
+      "clipboard_sentinel_not_observed",
+      "The clipboard did not update in time; the captured text may be stale.",
+    );
+  }
 
   // simulate copy
   #[cfg(target_os = "windows")]
@@ -164,7 +218,7 @@ pub async fn capture_selected_text(timeout_ms: Option<u64>) -> Result<String, St
   let started = std::time::Instant::now();
   let mut picked: Option<String> = None;
   let mut polls: u32 = 0;
-  let mut _last_kind: &'static str = "none";
+  let mut last_kind: &'static str = "none";
   let mut tried_alt_copy: bool = false;
   while started.elapsed().as_millis() < timeout_ms as u128 {
     std::thread::sleep(std::time::Duration::from_millis(90));
@@ -172,7 +226,7 @@ pub async fn capture_selected_text(timeout_ms: Option<u64>) -> Result<String, St
     if let Some(cur_s) = cur {
       polls += 1;
       let cur_t = cur_s.trim().to_string();
-      _last_kind = if cur_t.is_empty() {
+      last_kind = if cur_t.is_empty() {
         "empty"
       } else if cur_t == sentinel {
         "sentinel"
@@ -229,32 +283,97 @@ pub async fn capture_selected_text(timeout_ms: Option<u64>) -> Result<String, St
 
   // restore clipboard text (best effort)
   if let Some(prev) = prev_text {
-    let _ = clipboard.set_text(prev);
+    if let Err(e) = clipboard.set_text(prev) {
+      events::warn(
+        &app,
+        None,
+        "clipboard_restore_failed",
+        format!("Your previous clipboard content could not be restored: {e}"),
+      );
+    }
+  }
+  if picked.is_none() {
+    events::warn(
+      &app,
+      None,
+      "selection_not_captured",
+      format!("No selected text was copied (clipboard last held: {last_kind})."),
+    );
   }
 
   Ok(picked.unwrap_or_default())
 }
 
+/// The clipboard's text as it is, with no copy simulated: the translate-clipboard action, for apps where the
+/// synthetic copy of `capture_selected_text` is unreliable.
+#[tauri::command]
+pub fn read_clipboard_text(app: tauri::AppHandle) -> Result<String, String> {
+  crate::type_back::remember_target(&app);
+  let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
+  clipboard.get_text().map_err(|e| format!("no text on the clipboard: {e}"))
+}
+
+/// Server detection gives up after this long and the local detector answers instead.
+const DETECT_TIMEOUT_MS: u64 = 3000;
+
+/// Detect via the configured server, falling back to the local detector when it is unreachable, slow or
+/// returns garbage. `engine` in the result says which one answered.
 #[tauri::command]
-pub async fn detect_language(base_url: String, text: String) -> Result<DetectResult, String> {
+pub async fn detect_language(app: tauri::AppHandle, base_url: String, text: String) -> Result<DetectResult, String> {
   let base = normalize_base_url(&base_url);
-  let url = format!("{}/api/detect-language", base);
 
+  #[cfg(feature = "deterministic")]
+  if crate::mock::is_mock(&base) {
+    return Ok(crate::mock::detect_language(&text));
+  }
+
+  let server_err = if base.is_empty() || crate::offline_mt::is_offline(&base) {
+    "no detection server configured".to_string()
+  } else {
+    match detect_via_server(&app, &base, &text).await {
+      Ok(r) => return Ok(r),
+      Err(e) => e,
+    }
+  };
+
+  let local = crate::langdetect::detect(&text).ok_or_else(|| server_err.clone())?;
+  events::warn(
+    &app,
+    None,
+    "detect_fallback",
+    format!("Language detected locally; the server was unavailable ({server_err})."),
+  );
+  events::publish(
+    &app,
+    "detect.fallback",
+    None,
+    serde_json::json!({ "reason": server_err, "engine": "local" }),
+  );
+  Ok(DetectResult {
+    detected_lang: local.detected_lang,
+    confidence: local.confidence,
+    is_mixed: local.is_mixed,
+    engine: "local".to_string(),
+  })
+}
+
+async fn detect_via_server(app: &tauri::AppHandle, base: &str, text: &str) -> Result<DetectResult, String> {
+  let url = format!("{}/api/detect-language", base);
   let body = serde_json::json!({ "text": text });
-  let client = reqwest::Client::new();
-  let res = client
-    .post(url)
-    .header("Content-Type", "application/json")
-    .json(&body)
+  let res = crate::http::post_json(app, &crate::http::client(), &url, &body)?
+    .timeout(std::time::Duration::from_millis(DETECT_TIMEOUT_MS))
     .send()
     .await
     .map_err(|e| format!("request failed: {e}"))?;
+  if !res.status().is_success() {
+    return Err(format!("api error {}", res.status()));
+  }
 
   let v: serde_json::Value = res.json().await.map_err(|e| format!("invalid json: {e}"))?;
   let detected_lang = v
     .get("detected_lang")
     .and_then(|x| x.as_str())
-    .unwrap_or("Unknown")
+    .ok_or_else(|| "detected_lang missing".to_string())?
     .to_string();
   let confidence = v.get("confidence").and_then(|x| x.as_f64()).unwrap_or(0.0);
   let is_mixed = v.get("is_mixed").and_then(|x| x.as_bool()).unwrap_or(true);
@@ -263,237 +382,601 @@ pub async fn detect_language(base_url: String, text: String) -> Result<DetectRes
     detected_lang,
     confidence,
     is_mixed,
+    engine: "server".to_string(),
   })
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn translate_sse(
+  app: tauri::AppHandle,
   base_url: String,
   text: String,
   target_lang: String,
   mode: String,
   explanation_lang: String,
   is_reverse: Option<bool>,
+  pricing: Option<TokenPricing>,
+  priority: Option<Priority>,
+  output: Option<OutputControls>,
+  alternatives: Option<usize>,
   on_event: Channel<StreamEvent>,
 ) -> Result<(), String> {
-  // (debug logging removed)
-  let base = normalize_base_url(&base_url);
-  let url = format!("{}/api/translate", base);
-
-  let mut body = serde_json::json!({
-    "text": text,
-    "target_lang": target_lang,
-    "mode": mode,
-    "explanation_lang": explanation_lang,
-    "skip_points": true
-  });
-  if is_reverse.unwrap_or(false) {
-    body["is_reverse"] = serde_json::Value::Bool(true);
-  }
+  let req = TranslateRequest {
+    base_url,
+    text,
+    target_lang,
+    mode,
+    explanation_lang,
+    is_reverse: is_reverse.unwrap_or(false),
+    pricing,
+    priority: priority.unwrap_or_default(),
+    output: output.unwrap_or_default(),
+    alternatives: alternatives.unwrap_or(0).min(translate::MAX_ALTERNATIVES),
+    ..Default::default()
+  };
+  let sink = |ev: StreamEvent| {
+    let _ = on_event.send(ev);
+  };
+  translate::run_translation(&app, req, &sink).await.map(|_| ())
+}
 
-  let client = reqwest::Client::new();
-  let res = client
-    .post(url)
-    .header("Content-Type", "application/json")
-    .header("Accept", "text/event-stream")
-    .json(&body)
-    .send()
-    .await
-    .map_err(|e| format!("request failed: {e}"))?;
+/// Capture a region to a temp image and return its path. `rect` is in physical pixels; pass `logical` instead for
+/// a rectangle in logical pixels (scaled by the monitor under it, see `screen`). `include_cursor` draws the mouse
+/// pointer in (Windows and X11). `max_dimension` (0 for none) and `format` ("png", "jpeg" or "webp") override
+/// `captureMaxDimension` / `captureFormat` (see `capture_output`). The region is added to the history (see
+/// `regions`).
+#[tauri::command]
+pub async fn capture_screen_region(
+  app: tauri::AppHandle,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+  include_cursor: Option<bool>,
+  max_dimension: Option<u32>,
+  format: Option<String>,
+) -> Result<String, String> {
+  let rect = physical_rect(&app, rect, logical)?;
+  let mut output = crate::settings::capture_output(&app);
+  if let Some(max) = max_dimension {
+    output.max_dimension = (max > 0).then_some(max);
+  }
+  if let Some(format) = format {
+    output.format = crate::capture_output::ImageFormat::parse(&format)?;
+  }
+  let include_cursor = include_cursor.unwrap_or(false);
+  let path = {
+    let (app, rect) = (app.clone(), rect.clone());
+    tauri::async_runtime::spawn_blocking(move || capture_region_as(&app, &rect, include_cursor, &output))
+      .await
+      .map_err(|e| format!("capture failed: {e}"))??
+  };
+  crate::regions::record(&app, &rect);
+  Ok(path)
+}
 
-  if !res.status().is_success() {
-    let status = res.status();
-    let text = res.text().await.unwrap_or_default();
-    let _ = on_event.send(StreamEvent::Error {
-      message: format!("api error {status}: {text}"),
-    });
-    return Err(format!("api error {status}"));
+/// The physical rectangle of a command's `rect` / `logical` pair (exactly one given).
+pub fn physical_rect(
+  app: &tauri::AppHandle,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+) -> Result<CaptureRect, String> {
+  match (rect, logical) {
+    (Some(rect), None) => Ok(rect),
+    (None, Some(logical)) => Ok(crate::screen::to_physical(app, logical).physical),
+    _ => Err("pass either rect or logical".to_string()),
   }
+}
 
-  use futures_util::StreamExt;
-  let mut buffer = String::new();
-  let mut stream = res.bytes_stream();
-  while let Some(item) = stream.next().await {
-    let chunk = match item {
-      Ok(b) => b,
-      Err(e) => {
-        let _ = on_event.send(StreamEvent::Error {
-          message: format!("stream error: {e}"),
-        });
-        return Err(format!("stream error: {e}"));
-      }
+/// Capture a region (as `capture_screen_region`) and put the image on the clipboard.
+#[tauri::command]
+pub async fn copy_capture_to_clipboard(
+  app: tauri::AppHandle,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+) -> Result<(), String> {
+  let rect = physical_rect(&app, rect, logical)?;
+  tauri::async_runtime::spawn_blocking(move || {
+    let path = capture_region(&app, &rect, false)?;
+    let image = crate::image_io::read_rgba(&path);
+    let _ = std::fs::remove_file(&path);
+    let image = image?;
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
+    clipboard
+      .set_image(arboard::ImageData { width, height, bytes: image.into_raw().into() })
+      .map_err(|e| format!("clipboard write failed: {e}"))
+  })
+  .await
+  .map_err(|e| format!("capture failed: {e}"))?
+}
+
+/// Save a capture to `path`: `source`, a capture already taken (e.g. the one just translated), or a fresh capture of
+/// `rect` / `logical` (as `capture_screen_region`); `source` can be in any common image format. `format` ("png",
+/// "jpeg" or "webp") defaults to the one `path`'s extension names, else PNG; `quality` (1-100) defaults to
+/// `captureJpegQuality` and is refused for PNG and WebP, which are written lossless. Returns `path`.
+#[tauri::command]
+pub async fn save_capture(
+  app: tauri::AppHandle,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+  source: Option<String>,
+  path: String,
+  format: Option<String>,
+  quality: Option<u8>,
+) -> Result<String, String> {
+  use crate::capture_output::ImageFormat;
+
+  let dest = std::path::PathBuf::from(&path);
+  if !dest.is_absolute() {
+    return Err("path must be absolute".to_string());
+  }
+  let format = match format {
+    Some(format) => ImageFormat::parse(&format)?,
+    None => ImageFormat::from_path(&dest).unwrap_or(ImageFormat::Png),
+  };
+  if quality.is_some() && format != ImageFormat::Jpeg {
+    return Err("quality only applies to JPEG; PNG and WebP captures are lossless".to_string());
+  }
+  let output = CaptureOutput {
+    max_dimension: None,
+    format,
+    quality: quality.map_or(crate::settings::capture_output(&app).quality, |q| q.clamp(1, 100)),
+  };
+  let rect = match source {
+    Some(_) => None,
+    None => Some(physical_rect(&app, rect, logical)?),
+  };
+  tauri::async_runtime::spawn_blocking(move || {
+    let encoded = match (source, rect) {
+      (Some(source), _) => crate::capture_output::encode_file(&source, &output)?,
+      (None, Some(rect)) => capture_region_as(&app, &rect, false, &output)?,
+      (None, None) => unreachable!("a fresh capture has a rect"),
     };
+    // A rename fails across volumes (the temp dir vs. the user's drive); copy there instead.
+    let moved = std::fs::rename(&encoded, &dest).or_else(|_| std::fs::copy(&encoded, &dest).map(|_| ()));
+    let _ = std::fs::remove_file(&encoded);
+    moved.map_err(|e| format!("cannot save capture to {path}: {e}"))?;
+    Ok(path)
+  })
+  .await
+  .map_err(|e| format!("capture failed: {e}"))?
+}
 
-    let s = String::from_utf8_lossy(&chunk);
-    buffer.push_str(&s);
+/// Capture `rect` to a temp PNG (full size) and return its path, publishing `capture.*` events.
+pub fn capture_region(app: &tauri::AppHandle, rect: &CaptureRect, include_cursor: bool) -> Result<String, String> {
+  capture_region_as(app, rect, include_cursor, &CaptureOutput::default())
+}
 
-    // process by lines; keep trailing partial line in buffer
-    while let Some(pos) = buffer.find('\n') {
-      let line = buffer[..pos].to_string();
-      buffer = buffer[pos + 1..].to_string();
+/// `capture_region`, saved as `output` asks.
+pub fn capture_region_as(
+  app: &tauri::AppHandle,
+  rect: &CaptureRect,
+  include_cursor: bool,
+  output: &CaptureOutput,
+) -> Result<String, String> {
+  events::publish(app, "capture.started", None, serde_json::json!({ "rect": rect }));
+  let result = capture_region_file(rect, include_cursor, crate::settings::capture_tone_map(app), output);
+  match &result {
+    Ok(path) => events::publish(app, "capture.finished", None, serde_json::json!({ "rect": rect, "path": path })),
+    Err(e) => events::publish(app, "capture.failed", None, serde_json::json!({ "rect": rect, "error": e })),
+  }
+  result
+}
 
-      let line = line.trim_end_matches('\r').to_string();
-      if !line.starts_with("data: ") {
-        continue;
-      }
-      let data = line.trim_start_matches("data: ").trim();
-      if data == "[DONE]" {
-        let _ = on_event.send(StreamEvent::Done);
-        return Ok(());
-      }
-      if data.is_empty() {
-        continue;
-      }
+/// `tone_map`: `Some` forces HDR tone mapping on or off; `None` applies it where the display is in HDR mode
+/// (detected on Windows only).
+#[cfg_attr(feature = "deterministic", allow(unreachable_code))]
+fn capture_region_file(
+  rect: &CaptureRect,
+  include_cursor: bool,
+  tone_map: Option<bool>,
+  output: &CaptureOutput,
+) -> Result<String, String> {
+  #[cfg(feature = "deterministic")]
+  {
+    let _ = (include_cursor, tone_map);
+    return crate::capture_output::convert_png(crate::mock::capture_png(rect)?, output);
+  }
 
-      let v: serde_json::Value = match serde_json::from_str(data) {
-        Ok(v) => v,
-        Err(_) => continue,
-      };
-      if let Some(content) = v.get("content").and_then(|x| x.as_str()) {
-        if !content.is_empty() {
-          let _ = on_event.send(StreamEvent::Delta {
-            content: content.to_string(),
-          });
-        }
-      } else if let Some(err) = v.get("error").and_then(|x| x.as_str()) {
-        let _ = on_event.send(StreamEvent::Error {
-          message: err.to_string(),
-        });
-        return Err(err.to_string());
-      }
+  #[cfg(windows)]
+  {
+    if rect.width == 0 || rect.height == 0 {
+      return Err("invalid rect".to_string());
     }
+
+    let screen_dc = win_gfx::WindowDc::screen()?;
+    let mem_dc = win_gfx::MemoryDc::compatible_with(screen_dc.hdc())?;
+    let bmp = win_gfx::Bitmap::compatible(screen_dc.hdc(), rect.width as i32, rect.height as i32)?;
+    let _selection = mem_dc.select(&bmp)?;
+    blit_screen(mem_dc.hdc(), screen_dc.hdc(), rect)?;
+    if include_cursor {
+      win_gfx::draw_cursor(mem_dc.hdc(), rect.x, rect.y);
+    }
+
+    // Stream the bitmap into the encoder a band of scanlines at a time instead of materialising
+    // the whole frame, so peak memory stays at the GDI bitmap plus one band (matters for 4K/5K regions).
+    // The GDI guards are released when they go out of scope.
+    let tone_map = tone_map.unwrap_or_else(|| win_gfx::hdr_active(rect.x, rect.y, rect.width, rect.height));
+    return encode_bitmap(mem_dc.hdc(), bmp.handle(), rect.width, rect.height, tone_map, output);
   }
 
-  let _ = on_event.send(StreamEvent::Done);
-  Ok(())
+  #[cfg(target_os = "macos")]
+  {
+    if rect.width == 0 || rect.height == 0 {
+      return Err("invalid rect".to_string());
+    }
+    // CoreGraphics leaves the pointer out of screen images, and maps HDR content to SDR itself.
+    let _ = include_cursor;
+    let path = crate::mac_capture::capture(rect)?;
+    crate::capture_output::convert_png(tone_map_png(path, tone_map)?, output)
+  }
+
+  #[cfg(target_os = "linux")]
+  {
+    if rect.width == 0 || rect.height == 0 {
+      return Err("invalid rect".to_string());
+    }
+    let path = crate::linux_capture::capture(rect, include_cursor)?;
+    crate::capture_output::convert_png(tone_map_png(path, tone_map)?, output)
+  }
+
+  #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+  {
+    let _ = (rect, include_cursor, tone_map, output);
+    Err("capture_screen_region not supported on this platform".to_string())
+  }
+}
+
+/// Tone map a finished capture when forced on (platforms without HDR detection).
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn tone_map_png(path: String, tone_map: Option<bool>) -> Result<String, String> {
+  if tone_map == Some(true) {
+    if let Err(e) = crate::tone_map::apply_to_png(&path) {
+      let _ = std::fs::remove_file(&path);
+      return Err(e);
+    }
+  }
+  Ok(path)
+}
+
+/// Whether screen capture is allowed (macOS Screen Recording permission; always true elsewhere).
+#[tauri::command]
+pub fn screen_capture_permission() -> bool {
+  #[cfg(target_os = "macos")]
+  {
+    crate::mac_capture::has_permission()
+  }
+
+  #[cfg(not(target_os = "macos"))]
+  {
+    true
+  }
 }
 
+/// Prompt for the Screen Recording permission on macOS. Returns whether it's granted.
 #[tauri::command]
-pub async fn capture_screen_region(rect: CaptureRect) -> Result<String, String> {
+pub fn request_screen_capture_permission() -> bool {
+  #[cfg(target_os = "macos")]
+  {
+    crate::mac_capture::request_permission()
+  }
+
+  #[cfg(not(target_os = "macos"))]
+  {
+    true
+  }
+}
+
+/// Capture the foreground window to a temp PNG. Returns its path and the window's bounds on screen.
+pub fn capture_active_window_png(app: &tauri::AppHandle) -> Result<(String, CaptureRect), String> {
+  let result = capture_foreground_png();
+  match &result {
+    Ok((path, rect)) => events::publish(
+      app,
+      "capture.finished",
+      None,
+      serde_json::json!({ "rect": rect, "path": path, "window": true }),
+    ),
+    Err(e) => events::publish(app, "capture.failed", None, serde_json::json!({ "window": true, "error": e })),
+  }
+  result
+}
+
+#[cfg_attr(feature = "deterministic", allow(unreachable_code))]
+fn capture_foreground_png() -> Result<(String, CaptureRect), String> {
+  #[cfg(feature = "deterministic")]
+  {
+    let rect = CaptureRect {
+      x: 0,
+      y: 0,
+      width: 800,
+      height: 600,
+    };
+    return crate::mock::capture_png(&rect).map(|path| (path, rect));
+  }
+
   #[cfg(windows)]
   {
-    if rect.width == 0 || rect.height == 0 {
-      return Err("invalid rect".to_string());
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_null() {
+      return Err("no active window".to_string());
     }
+    return capture_hwnd_png(hwnd);
+  }
 
-    unsafe {
-      let screen_dc: HDC = GetDC(0 as HWND);
-      if screen_dc.is_null() {
-        return Err("GetDC failed".to_string());
-      }
-      let mem_dc: HDC = CreateCompatibleDC(screen_dc);
-      if mem_dc.is_null() {
-        let _ = ReleaseDC(0 as HWND, screen_dc);
-        return Err("CreateCompatibleDC failed".to_string());
-      }
-      let bmp: HBITMAP = CreateCompatibleBitmap(screen_dc, rect.width as i32, rect.height as i32);
-      if bmp.is_null() {
-        let _ = DeleteDC(mem_dc);
-        let _ = ReleaseDC(0 as HWND, screen_dc);
-        return Err("CreateCompatibleBitmap failed".to_string());
-      }
+  #[cfg(not(windows))]
+  {
+    Err("capture_active_window not supported on this platform".to_string())
+  }
+}
 
-      let old = SelectObject(mem_dc, bmp as _);
-      if old.is_null() {
-        let _ = DeleteObject(bmp as _);
-        let _ = DeleteDC(mem_dc);
-        let _ = ReleaseDC(0 as HWND, screen_dc);
-        return Err("SelectObject failed".to_string());
-      }
+/// Which window `capture_window` captures.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(not(any(windows, feature = "deterministic")), allow(dead_code))]
+pub enum WindowTarget {
+  /// A window handle (`HWND`) as a number.
+  Hwnd(i64),
+  /// The frontmost visible window whose title contains this (case-insensitive).
+  Title(String),
+  /// The frontmost window of a process, by executable name (`"notepad.exe"`; `.exe` optional).
+  Process(String),
+}
 
-      let ok = BitBlt(
-        mem_dc,
-        0,
-        0,
-        rect.width as i32,
-        rect.height as i32,
-        screen_dc,
-        rect.x,
-        rect.y,
-        SRCCOPY | CAPTUREBLT,
-      );
-      if ok == 0 {
-        let _ = SelectObject(mem_dc, old);
-        let _ = DeleteObject(bmp as _);
-        let _ = DeleteDC(mem_dc);
-        let _ = ReleaseDC(0 as HWND, screen_dc);
-        return Err("BitBlt failed".to_string());
-      }
+#[derive(Debug, Serialize, Clone)]
+pub struct WindowCapture {
+  /// Temp PNG of the window.
+  pub path: String,
+  /// The window's bounds on screen.
+  pub rect: CaptureRect,
+  pub title: String,
+}
 
-      // Prepare 32-bit BGRA DIB
-      let mut bmi: BITMAPINFO = std::mem::zeroed();
-      bmi.bmiHeader = BITMAPINFOHEADER {
-        biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
-        biWidth: rect.width as i32,
-        biHeight: -(rect.height as i32), // top-down
-        biPlanes: 1,
-        biBitCount: 32,
-        biCompression: BI_RGB,
-        biSizeImage: 0,
-        biXPelsPerMeter: 0,
-        biYPelsPerMeter: 0,
-        biClrUsed: 0,
-        biClrImportant: 0,
-      };
+/// Capture one window, even one in the background or partly covered, to a temp PNG.
+#[tauri::command]
+pub async fn capture_window(app: tauri::AppHandle, target: WindowTarget) -> Result<WindowCapture, String> {
+  let result = tauri::async_runtime::spawn_blocking(move || find_and_capture_window(&target))
+    .await
+    .map_err(|e| format!("capture failed: {e}"))
+    .and_then(|r| r);
+  match &result {
+    Ok(c) => events::publish(
+      &app,
+      "capture.finished",
+      None,
+      serde_json::json!({ "rect": c.rect, "path": c.path, "window": true }),
+    ),
+    Err(e) => events::publish(&app, "capture.failed", None, serde_json::json!({ "window": true, "error": e })),
+  }
+  result
+}
 
-      let mut bgra = vec![0u8; rect.width as usize * rect.height as usize * 4];
-      let lines = GetDIBits(
-        mem_dc,
-        bmp,
-        0,
-        rect.height as u32,
-        bgra.as_mut_ptr() as *mut _,
-        &mut bmi as *mut _,
-        DIB_RGB_COLORS,
-      );
-      // cleanup GDI
-      let _ = SelectObject(mem_dc, old);
-      let _ = DeleteObject(bmp as _);
-      let _ = DeleteDC(mem_dc);
-      let _ = ReleaseDC(0 as HWND, screen_dc);
-
-      if lines == 0 {
-        return Err("GetDIBits failed".to_string());
-      }
+#[cfg_attr(feature = "deterministic", allow(unreachable_code))]
+fn find_and_capture_window(target: &WindowTarget) -> Result<WindowCapture, String> {
+  #[cfg(feature = "deterministic")]
+  {
+    let rect = CaptureRect {
+      x: 0,
+      y: 0,
+      width: 800,
+      height: 600,
+    };
+    let title = match target {
+      WindowTarget::Hwnd(h) => format!("window {h}"),
+      WindowTarget::Title(t) | WindowTarget::Process(t) => t.clone(),
+    };
+    return crate::mock::capture_png(&rect).map(|path| WindowCapture { path, rect, title });
+  }
 
-      // Convert BGRA -> RGBA
-      for px in bgra.chunks_exact_mut(4) {
-        let b = px[0];
-        let r = px[2];
-        px[0] = r;
-        px[2] = b;
-      }
+  #[cfg(windows)]
+  {
+    use windows_sys::Win32::UI::WindowsAndMessaging::IsWindow;
 
-      let mut out_path = std::env::temp_dir();
-      let name = format!(
-        "erudaite-ocr-{}.png",
-        std::time::SystemTime::now()
-          .duration_since(std::time::UNIX_EPOCH)
-          .map(|d| d.as_millis())
-          .unwrap_or(0)
-      );
-      out_path.push(name);
-
-      let file = std::fs::File::create(&out_path).map_err(|e| format!("create png failed: {e}"))?;
-      let w = std::io::BufWriter::new(file);
-      let mut encoder = png::Encoder::new(w, rect.width, rect.height);
-      encoder.set_color(png::ColorType::Rgba);
-      encoder.set_depth(png::BitDepth::Eight);
-      let mut writer = encoder
-        .write_header()
-        .map_err(|e| format!("png header failed: {e}"))?;
-      writer
-        .write_image_data(&bgra)
-        .map_err(|e| format!("png write failed: {e}"))?;
-      return Ok(out_path.to_string_lossy().to_string());
+    let hwnd = match target {
+      WindowTarget::Hwnd(h) => {
+        Some(*h as isize as windows_sys::Win32::Foundation::HWND).filter(|&w| unsafe { IsWindow(w) } != 0)
+      }
+      WindowTarget::Title(t) => crate::win_window::find_by_title(t),
+      WindowTarget::Process(p) => crate::win_window::find_by_process(p),
     }
+    .ok_or_else(|| "WINDOW_NOT_FOUND".to_string())?;
+    let (path, rect) = capture_hwnd_png(hwnd)?;
+    return Ok(WindowCapture {
+      path,
+      rect,
+      title: crate::win_window::title(hwnd),
+    });
   }
 
   #[cfg(not(windows))]
   {
-    let _ = rect;
-    Err("capture_screen_region not supported on this platform".to_string())
+    let _ = target;
+    Err("capture_window not supported on this platform".to_string())
+  }
+}
+
+/// Capture a window to a temp PNG with `PrintWindow`. Returns its path and the window's bounds on screen.
+#[cfg(windows)]
+fn capture_hwnd_png(hwnd: windows_sys::Win32::Foundation::HWND) -> Result<(String, CaptureRect), String> {
+  use windows_sys::Win32::Foundation::RECT;
+  use windows_sys::Win32::Storage::Xps::PrintWindow;
+  use windows_sys::Win32::UI::WindowsAndMessaging::{GetWindowRect, IsIconic, PW_RENDERFULLCONTENT};
+
+  if unsafe { IsIconic(hwnd) } != 0 {
+    return Err("window is minimized".to_string());
+  }
+  let mut r: RECT = unsafe { std::mem::zeroed() };
+  if unsafe { GetWindowRect(hwnd, &mut r) } == 0 {
+    return Err("GetWindowRect failed".to_string());
+  }
+  let (width, height) = ((r.right - r.left).max(0) as u32, (r.bottom - r.top).max(0) as u32);
+  if width == 0 || height == 0 {
+    return Err("window has no area".to_string());
+  }
+  let rect = CaptureRect {
+    x: r.left,
+    y: r.top,
+    width,
+    height,
+  };
+
+  let screen_dc = win_gfx::WindowDc::screen()?;
+  let mem_dc = win_gfx::MemoryDc::compatible_with(screen_dc.hdc())?;
+  let bmp = win_gfx::Bitmap::compatible(screen_dc.hdc(), width as i32, height as i32)?;
+  let _selection = mem_dc.select(&bmp)?;
+  // PrintWindow renders the window itself, so overlapping windows don't end up in the capture. Some windows
+  // (older DirectX games) can't render that way; copy what's on screen for those.
+  let printed = unsafe { PrintWindow(hwnd, mem_dc.hdc(), PW_RENDERFULLCONTENT) } != 0;
+  if !printed {
+    blit_screen(mem_dc.hdc(), screen_dc.hdc(), &rect)?;
+  }
+  let tone_map = win_gfx::hdr_active(rect.x, rect.y, width, height);
+  encode_bitmap(mem_dc.hdc(), bmp.handle(), width, height, tone_map, &CaptureOutput::default()).map(|path| (path, rect))
+}
+
+/// Copy `rect` of the virtual screen into the bitmap selected in `mem_dc` (sized to `rect`). Whatever lies off
+/// every monitor comes out black.
+#[cfg(windows)]
+fn blit_screen(mem_dc: HDC, screen_dc: HDC, rect: &CaptureRect) -> Result<(), String> {
+  let (vx, vy, vw, vh) = win_gfx::virtual_screen();
+  let (left, top) = (rect.x.max(vx), rect.y.max(vy));
+  let right = (rect.x + rect.width as i32).min(vx + vw);
+  let bottom = (rect.y + rect.height as i32).min(vy + vh);
+  if right <= left || bottom <= top {
+    return Err("capture region is outside the screen".to_string());
   }
+  let clipped = (left, top, right, bottom) != (rect.x, rect.y, rect.x + rect.width as i32, rect.y + rect.height as i32);
+  if clipped {
+    unsafe { PatBlt(mem_dc, 0, 0, rect.width as i32, rect.height as i32, BLACKNESS) };
+  }
+  let ok = unsafe {
+    BitBlt(
+      mem_dc,
+      left - rect.x,
+      top - rect.y,
+      right - left,
+      bottom - top,
+      screen_dc,
+      left,
+      top,
+      SRCCOPY | CAPTUREBLT,
+    )
+  };
+  if ok == 0 {
+    return Err("BitBlt failed".to_string());
+  }
+  Ok(())
+}
+
+/// Rows fetched from the DIB per `GetDIBits` call while encoding.
+#[cfg(windows)]
+const CAPTURE_BAND_ROWS: u32 = 64;
+
+/// Encode a selected bitmap to a temp image (see `capture_output`) band by band and return its path. `tone_map`
+/// stretches the levels first (HDR displays, see `tone_map`), which takes an extra pass over the bitmap.
+#[cfg(windows)]
+fn encode_bitmap(
+  mem_dc: HDC,
+  bmp: HBITMAP,
+  width: u32,
+  height: u32,
+  tone_map: bool,
+  output: &CaptureOutput,
+) -> Result<String, String> {
+  let lut = if tone_map {
+    let mut hist = [0u64; 256];
+    for_each_bitmap_row(mem_dc, bmp, width, height, |row| {
+      crate::tone_map::add_to_histogram(&mut hist, row);
+      Ok(())
+    })?;
+    crate::tone_map::levels(&hist)
+  } else {
+    None
+  };
+
+  let mut writer = crate::capture_output::CaptureWriter::create(width, height, output)?;
+  let mut mapped = Vec::new();
+  let written = for_each_bitmap_row(mem_dc, bmp, width, height, |row| match &lut {
+    Some(lut) => {
+      mapped.clear();
+      mapped.extend_from_slice(row);
+      crate::tone_map::apply(lut, &mut mapped);
+      writer.write_row(&mapped)
+    }
+    None => writer.write_row(row),
+  });
+  match written {
+    Ok(()) => writer.finish(),
+    Err(e) => {
+      writer.discard();
+      Err(e)
+    }
+  }
+}
+
+/// Call `f` with each row of the selected bitmap as RGB, top to bottom, reading a band of scanlines at a time.
+#[cfg(windows)]
+fn for_each_bitmap_row(
+  mem_dc: HDC,
+  bmp: HBITMAP,
+  width: u32,
+  height: u32,
+  mut f: impl FnMut(&[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+  // Bottom-up 32-bit BGRA DIB: with a positive height `uStartScan` counts from the bottom row, which is
+  // well defined for partial reads.
+  let mut bmi: BITMAPINFO = unsafe { std::mem::zeroed() };
+  bmi.bmiHeader = BITMAPINFOHEADER {
+    biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+    biWidth: width as i32,
+    biHeight: height as i32,
+    biPlanes: 1,
+    biBitCount: 32,
+    biCompression: BI_RGB,
+    biSizeImage: 0,
+    biXPelsPerMeter: 0,
+    biYPelsPerMeter: 0,
+    biClrUsed: 0,
+    biClrImportant: 0,
+  };
+
+  let stride = width as usize * 4;
+  let mut band = vec![0u8; stride * CAPTURE_BAND_ROWS.min(height) as usize];
+  let mut row_rgb = vec![0u8; width as usize * 3];
+  let mut top = 0u32;
+  while top < height {
+    let rows = CAPTURE_BAND_ROWS.min(height - top);
+    // Band covering image rows [top, top + rows), addressed from the bottom.
+    let start_scan = height - top - rows;
+    let lines = unsafe {
+      GetDIBits(
+        mem_dc,
+        bmp,
+        start_scan,
+        rows,
+        band.as_mut_ptr() as *mut _,
+        &mut bmi as *mut _,
+        DIB_RGB_COLORS,
+      )
+    };
+    if lines <= 0 || lines as u32 != rows {
+      return Err("GetDIBits failed".to_string());
+    }
+    // Rows within the band are bottom-up as well: emit them last-to-first.
+    for r in (0..rows as usize).rev() {
+      let src = &band[r * stride..(r + 1) * stride];
+      for (dst, px) in row_rgb.chunks_exact_mut(3).zip(src.chunks_exact(4)) {
+        dst[0] = px[2];
+        dst[1] = px[1];
+        dst[2] = px[0];
+      }
+      f(&row_rgb)?;
+    }
+    top += rows;
+  }
+  Ok(())
 }
 
 #[tauri::command]
@@ -517,10 +1000,13 @@ pub async fn detect_tesseract_path() -> Result<Option<String>, String> {
       candidates.push(format!(r"{}\Programs\Tesseract-OCR\tesseract.exe", local));
     }
 
-    for p in &candidates {
-      if std::path::Path::new(p).exists() {
-        return Ok(Some(p.to_string()));
-      }
+    // Prefer a native build (e.g. ARM64 Tesseract on Windows on ARM) over one that runs emulated.
+    let existing: Vec<String> = candidates
+      .into_iter()
+      .filter(|p| std::path::Path::new(p).exists())
+      .collect();
+    if let Some(p) = accel::prefer_native(existing).into_iter().next() {
+      return Ok(Some(p));
     }
 
     // Try PATH via `where`
@@ -537,146 +1023,36 @@ pub async fn detect_tesseract_path() -> Result<Option<String>, String> {
     Ok(None)
   }
 
-  #[cfg(not(windows))]
+  #[cfg(target_os = "macos")]
   {
-    Ok(None)
+    // Apple Silicon Homebrew (native arm64) first, then Intel Homebrew / MacPorts.
+    let existing: Vec<String> = ["/opt/homebrew/bin/tesseract", "/usr/local/bin/tesseract", "/opt/local/bin/tesseract"]
+      .iter()
+      .filter(|p| std::path::Path::new(p).exists())
+      .map(|s| s.to_string())
+      .collect();
+    Ok(accel::prefer_native(existing).into_iter().next())
   }
-}
-
-#[tauri::command]
-pub async fn ocr_tesseract(
-  image_path: String,
-  lang: Option<String>,
-  tesseract_path: Option<String>,
-  tessdata_prefix: Option<String>,
-) -> Result<String, String> {
-  let lang = lang.unwrap_or_else(|| "jpn+eng".to_string());
-
-  let exe = if let Some(p) = tesseract_path.filter(|s| !s.trim().is_empty()) {
-    p
-  } else {
-    detect_tesseract_path().await?.ok_or_else(|| "TESSERACT_NOT_FOUND".to_string())?
-  };
 
-  let mut cmd = std::process::Command::new(exe);
-  if let Some(prefix) = tessdata_prefix
-    .clone()
-    .filter(|s| !s.trim().is_empty())
-    .map(|p| {
-      // Accept either "...\<parent>" or "...\tessdata" as input; normalize to tessdata dir if present.
-      let pb = std::path::PathBuf::from(p.trim());
-      let tess = pb.join("tessdata");
-      if tess.is_dir() {
-        tess.to_string_lossy().to_string()
-      } else {
-        pb.to_string_lossy().to_string()
-      }
-    })
+  #[cfg(not(any(windows, target_os = "macos")))]
   {
-    cmd.env("TESSDATA_PREFIX", prefix);
-  }
-  let output = cmd
-    .arg(image_path)
-    .arg("stdout")
-    .arg("-l")
-    .arg(lang)
-    .output()
-    .map_err(|e| format!("failed to run tesseract: {e}"))?;
-
-  if !output.status.success() {
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let msg = stderr.trim().to_string();
-    // If a language traineddata is missing, tesseract prints an "Error opening data file" message.
-    if msg.contains("Error opening data file") || msg.contains("Failed loading language") {
-      return Err(format!("TESSDATA_MISSING\n\n{}", msg));
-    }
-    return Err(format!("tesseract failed: {}", msg));
+    let existing: Vec<String> = ["/usr/bin/tesseract", "/usr/local/bin/tesseract"]
+      .iter()
+      .filter(|p| std::path::Path::new(p).exists())
+      .map(|s| s.to_string())
+      .collect();
+    Ok(accel::prefer_native(existing).into_iter().next())
   }
-  let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-  Ok(stdout.trim().to_string())
 }
 
-#[tauri::command]
-pub async fn tesseract_list_langs(tesseract_path: Option<String>, tessdata_prefix: Option<String>) -> Result<Vec<String>, String> {
-  let exe = if let Some(p) = tesseract_path.filter(|s| !s.trim().is_empty()) {
-    p
+/// Accept either the parent of `tessdata` or the `tessdata` directory itself; returns the latter if present.
+pub fn normalize_tessdata_prefix(p: &str) -> String {
+  let pb = std::path::PathBuf::from(p.trim());
+  let tess = pb.join("tessdata");
+  if tess.is_dir() {
+    tess.to_string_lossy().to_string()
   } else {
-    detect_tesseract_path().await?.ok_or_else(|| "TESSERACT_NOT_FOUND".to_string())?
-  };
-
-  let mut cmd = std::process::Command::new(exe);
-  if let Some(prefix) = tessdata_prefix
-    .filter(|s| !s.trim().is_empty())
-    .map(|p| {
-      let pb = std::path::PathBuf::from(p.trim());
-      let tess = pb.join("tessdata");
-      if tess.is_dir() {
-        tess.to_string_lossy().to_string()
-      } else {
-        pb.to_string_lossy().to_string()
-      }
-    })
-  {
-    cmd.env("TESSDATA_PREFIX", prefix);
-  }
-  let out = cmd.arg("--list-langs").output().map_err(|e| format!("failed to list langs: {e}"))?;
-  if !out.status.success() {
-    return Err(format!("list langs failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
-  }
-  let s = String::from_utf8_lossy(&out.stdout);
-  let mut langs: Vec<String> = Vec::new();
-  for line in s.lines() {
-    let t = line.trim();
-    if t.is_empty() {
-      continue;
-    }
-    if t.starts_with("List of available languages") {
-      continue;
-    }
-    langs.push(t.to_string());
-  }
-  Ok(langs)
-}
-
-#[tauri::command]
-pub async fn download_tessdata(lang: String) -> Result<String, String> {
-  #[cfg(windows)]
-  {
-    let lang = lang.trim().to_lowercase();
-    if lang.is_empty() {
-      return Err("invalid lang".to_string());
-    }
-    // Official tesseract-ocr tessdata_fast (smaller).
-    let url = format!(
-      "https://github.com/tesseract-ocr/tessdata_fast/raw/main/{}.traineddata",
-      lang
-    );
-
-    let client = reqwest::Client::builder()
-      .timeout(std::time::Duration::from_secs(60))
-      .build()
-      .map_err(|e| format!("client build failed: {e}"))?;
-    let res = client.get(&url).send().await.map_err(|e| format!("download failed: {e}"))?;
-    if !res.status().is_success() {
-      return Err(format!("download failed: http {}", res.status()));
-    }
-    let bytes = res.bytes().await.map_err(|e| format!("download read failed: {e}"))?;
-
-    let local = std::env::var("LOCALAPPDATA").map_err(|_| "LOCALAPPDATA not set".to_string())?;
-    let base = std::path::PathBuf::from(local).join("Erudaite").join("tessdata");
-    std::fs::create_dir_all(&base).map_err(|e| format!("create dir failed: {e}"))?;
-    let file_path = base.join(format!("{}.traineddata", lang));
-    std::fs::write(&file_path, &bytes).map_err(|e| format!("write traineddata failed: {e}"))?;
-
-    // TESSDATA_PREFIX should point to the tessdata directory.
-    let prefix = base.to_string_lossy().to_string();
-    Ok(prefix)
-  }
-
-  #[cfg(not(windows))]
-  {
-    let _ = lang;
-    Err("download_tessdata not supported on this platform".to_string())
+    pb.to_string_lossy().to_string()
   }
 }
 
@@ -774,8 +1150,7 @@ pub async fn download_tesseract_installer() -> Result<String, String> {
         }
       };
 
-      let mut out_path = std::env::temp_dir();
-      out_path.push("erudaite-tesseract-installer.exe");
+      let out_path = crate::temp::dir().join("tesseract-installer.exe");
       if let Err(e) = std::fs::write(&out_path, &bytes) {
         let msg = format!("write installer failed: {e}");
         last_err = Some(msg);
@@ -0,0 +1,412 @@
+//! File translation for plain text, Markdown and subtitles.
+//!
+//! A file is split into segments that are either kept verbatim (timestamps, cue numbers, code fences,
+//! Markdown markers, blank lines) or translated. Only the translatable segments go to the provider, so the
+//! structure of the file survives the round trip. The result is written next to the original as
+//! `<name>.<lang>.<ext>`.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::ipc::Channel;
+
+use crate::commands::StreamEvent;
+use crate::events;
+use crate::output::{OutputControls, Verbosity};
+use crate::queue::Priority;
+use crate::settings;
+use crate::translate::{self, TranslateRequest};
+
+/// Larger files are rejected rather than queued as thousands of requests.
+const MAX_FILE_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+  Text,
+  Markdown,
+  Srt,
+  Vtt,
+}
+
+impl Format {
+  fn from_path(path: &Path) -> Option<Format> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+      "txt" => Some(Format::Text),
+      "md" | "markdown" => Some(Format::Markdown),
+      "srt" => Some(Format::Srt),
+      "vtt" => Some(Format::Vtt),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+  Keep(String),
+  Translate(String),
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type")]
+pub enum FileEvent {
+  #[serde(rename = "started")]
+  Started { total: usize },
+  /// `done` of `total` segments translated.
+  #[serde(rename = "progress")]
+  Progress { done: usize, total: usize },
+  #[serde(rename = "done")]
+  Done { output_path: String },
+  #[serde(rename = "error")]
+  Error { message: String },
+}
+
+/// Split lines into (content, line ending) pairs; the last line may have no ending.
+fn lines_with_endings(text: &str) -> Vec<(&str, &str)> {
+  text
+    .split_inclusive('\n')
+    .map(|l| {
+      let body = l.trim_end_matches(['\n', '\r']);
+      (body, &l[body.len()..])
+    })
+    .collect()
+}
+
+/// Merge adjacent segments of the same kind (translatable lines of one paragraph become one request).
+fn push(out: &mut Vec<Segment>, seg: Segment) {
+  match (out.last_mut(), seg) {
+    (Some(Segment::Keep(a)), Segment::Keep(b)) => a.push_str(&b),
+    (_, seg) => out.push(seg),
+  }
+}
+
+fn flush_paragraph(out: &mut Vec<Segment>, para: &mut Vec<&str>, ending: &str) {
+  if para.is_empty() {
+    return;
+  }
+  push(out, Segment::Translate(para.join("\n")));
+  push(out, Segment::Keep(ending.to_string()));
+  para.clear();
+}
+
+/// Paragraphs (runs of non-blank lines) are translated as a whole; blank lines are kept.
+fn segment_text(text: &str) -> Vec<Segment> {
+  let mut out = Vec::new();
+  let mut para = Vec::new();
+  let mut para_ending = "";
+  for (line, ending) in lines_with_endings(text) {
+    if line.trim().is_empty() {
+      flush_paragraph(&mut out, &mut para, para_ending);
+      push(&mut out, Segment::Keep(format!("{line}{ending}")));
+    } else {
+      para.push(line);
+      para_ending = ending;
+    }
+  }
+  flush_paragraph(&mut out, &mut para, para_ending);
+  out
+}
+
+fn is_list_marker(s: &str) -> Option<usize> {
+  if s.starts_with(['-', '*', '+']) && s[1..].starts_with(' ') {
+    return Some(2);
+  }
+  let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+  (digits > 0 && (s[digits..].starts_with(". ") || s[digits..].starts_with(") "))).then_some(digits + 2)
+}
+
+/// Length of the Markdown block marker at the start of `line` (indent, `#`, `>`, list marker, task box).
+fn markdown_prefix_len(line: &str) -> usize {
+  let mut i = line.len() - line.trim_start().len();
+  loop {
+    let rest = &line[i..];
+    if rest.starts_with('#') {
+      let hashes = rest.chars().take_while(|c| *c == '#').count();
+      if rest[hashes..].starts_with(' ') {
+        i += hashes + 1;
+        continue;
+      }
+    }
+    if let Some(r) = rest.strip_prefix('>') {
+      i += 1 + usize::from(r.starts_with(' '));
+      continue;
+    }
+    if let Some(n) = is_list_marker(rest) {
+      i += n;
+      continue;
+    }
+    if rest.starts_with("[ ] ") || rest.starts_with("[x] ") || rest.starts_with("[X] ") {
+      i += 4;
+      continue;
+    }
+    return i;
+  }
+}
+
+fn is_markdown_rule(t: &str) -> bool {
+  let t: String = t.chars().filter(|c| !c.is_whitespace()).collect();
+  t.len() >= 3 && (t.chars().all(|c| c == '-') || t.chars().all(|c| c == '*') || t.chars().all(|c| c == '_'))
+}
+
+fn is_table_separator(t: &str) -> bool {
+  t.starts_with('|') && t.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+/// Keep fences, code, front matter, rules, HTML and link definitions; translate text after block markers,
+/// and table cells one by one.
+fn segment_markdown(text: &str) -> Vec<Segment> {
+  let mut out = Vec::new();
+  let mut fence: Option<&str> = None;
+  let lines = lines_with_endings(text);
+  let mut front_matter = lines.first().is_some_and(|(l, _)| l.trim_end() == "---");
+  for (i, (line, ending)) in lines.iter().enumerate() {
+    let t = line.trim();
+    let keep = |out: &mut Vec<Segment>| push(out, Segment::Keep(format!("{line}{ending}")));
+
+    if front_matter {
+      keep(&mut out);
+      if i > 0 && t == "---" {
+        front_matter = false;
+      }
+      continue;
+    }
+    if let Some(f) = fence {
+      keep(&mut out);
+      if t.starts_with(f) {
+        fence = None;
+      }
+      continue;
+    }
+    if t.starts_with("```") || t.starts_with("~~~") {
+      fence = Some(&t[..3]);
+      keep(&mut out);
+      continue;
+    }
+    let indented_code = line.starts_with("    ") || line.starts_with('\t');
+    let link_def = t.starts_with('[') && t.contains("]: ");
+    if t.is_empty() || indented_code || is_markdown_rule(t) || t.starts_with('<') || link_def || is_table_separator(t)
+    {
+      keep(&mut out);
+      continue;
+    }
+    if t.starts_with('|') {
+      let lead = &line[..line.len() - line.trim_start().len()];
+      push(&mut out, Segment::Keep(lead.to_string()));
+      for (j, cell) in t.split('|').enumerate() {
+        if j > 0 {
+          push(&mut out, Segment::Keep("|".to_string()));
+        }
+        let body = cell.trim();
+        if body.is_empty() {
+          push(&mut out, Segment::Keep(cell.to_string()));
+          continue;
+        }
+        let start = cell.len() - cell.trim_start().len();
+        push(&mut out, Segment::Keep(cell[..start].to_string()));
+        push(&mut out, Segment::Translate(body.to_string()));
+        push(&mut out, Segment::Keep(cell[start + body.len()..].to_string()));
+      }
+      push(&mut out, Segment::Keep(format!("{}{ending}", &line[lead.len() + t.len()..])));
+      continue;
+    }
+    let prefix = markdown_prefix_len(line);
+    let body = line[prefix..].trim_end();
+    push(&mut out, Segment::Keep(line[..prefix].to_string()));
+    if !body.is_empty() {
+      push(&mut out, Segment::Translate(body.to_string()));
+    }
+    push(&mut out, Segment::Keep(format!("{}{ending}", &line[prefix + body.len()..])));
+  }
+  out
+}
+
+/// SRT/VTT: cue numbers, ids, timings and header/NOTE/STYLE blocks are kept; each cue's text is translated.
+fn segment_subtitles(text: &str, format: Format) -> Vec<Segment> {
+  let mut out = Vec::new();
+  let lines = lines_with_endings(text);
+  let mut i = 0;
+  while i < lines.len() {
+    // One block: lines up to the next blank line.
+    let start = i;
+    while i < lines.len() && !lines[i].0.trim().is_empty() {
+      i += 1;
+    }
+    let block = &lines[start..i];
+    let first = block.first().map(|(l, _)| l.trim()).unwrap_or_default();
+    let is_meta = format == Format::Vtt
+      && (first.starts_with("WEBVTT") || first.starts_with("NOTE") || first == "STYLE" || first == "REGION");
+    let timing = block.iter().position(|(l, _)| l.contains("-->"));
+    match timing {
+      Some(t) if !is_meta => {
+        for (line, ending) in &block[..=t] {
+          push(&mut out, Segment::Keep(format!("{line}{ending}")));
+        }
+        let cue = &block[t + 1..];
+        if !cue.is_empty() {
+          let text = cue.iter().map(|(l, _)| *l).collect::<Vec<_>>().join("\n");
+          push(&mut out, Segment::Translate(text));
+          push(&mut out, Segment::Keep(cue.last().map(|(_, e)| *e).unwrap_or_default().to_string()));
+        }
+      }
+      _ => {
+        for (line, ending) in block {
+          push(&mut out, Segment::Keep(format!("{line}{ending}")));
+        }
+      }
+    }
+    // Blank separator lines.
+    while i < lines.len() && lines[i].0.trim().is_empty() {
+      push(&mut out, Segment::Keep(format!("{}{}", lines[i].0, lines[i].1)));
+      i += 1;
+    }
+  }
+  out
+}
+
+fn segment(text: &str, format: Format) -> Vec<Segment> {
+  let mut segments = match format {
+    Format::Text => segment_text(text),
+    Format::Markdown => segment_markdown(text),
+    Format::Srt | Format::Vtt => segment_subtitles(text, format),
+  };
+  segments.retain(|s| !matches!(s, Segment::Keep(k) if k.is_empty()));
+  segments
+}
+
+/// `notes.md` + "English (US)" -> `notes.english-us.md`.
+fn output_path_for(path: &Path, target_lang: &str) -> PathBuf {
+  let slug: String = target_lang
+    .to_lowercase()
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<_>>()
+    .join("-");
+  let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("translated");
+  let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("txt");
+  path.with_file_name(format!("{stem}.{slug}.{ext}"))
+}
+
+/// Translate one segment, keeping only the translation section of the response.
+async fn translate_segment(app: &tauri::AppHandle, req: TranslateRequest) -> Result<String, String> {
+  let collected = std::sync::Mutex::new((String::new(), None::<String>));
+  let sink = |ev: StreamEvent| {
+    let mut c = collected.lock().unwrap_or_else(|e| e.into_inner());
+    match ev {
+      StreamEvent::Section { name } => c.1 = Some(name),
+      StreamEvent::Delta { content } if c.1.as_deref().map_or(true, |s| s == "translation") => {
+        c.0.push_str(&content)
+      }
+      StreamEvent::Replace { content } if c.1.is_none() => c.0 = content,
+      _ => {}
+    }
+  };
+  let leading = req.text.len() - req.text.trim_start().len();
+  let source = req.text.clone();
+  translate::run_translation(app, req, &sink).await?;
+  let (text, _) = collected.into_inner().unwrap_or_else(|e| e.into_inner());
+  // Keep the source's indentation; the model tends to drop it.
+  Ok(format!("{}{}", &source[..leading], text.trim()))
+}
+
+/// Translate a .txt, .md, .srt or .vtt file segment by segment and write `<name>.<lang>.<ext>` next to it.
+#[tauri::command]
+pub async fn translate_file(
+  app: tauri::AppHandle,
+  path: String,
+  target_lang: String,
+  base_url: Option<String>,
+  on_event: Channel<FileEvent>,
+) -> Result<String, String> {
+  let result = translate_file_inner(&app, &path, &target_lang, base_url, &on_event).await;
+  match &result {
+    Ok(out) => {
+      events::publish(&app, "file.translated", None, serde_json::json!({ "path": path, "output_path": out }));
+    }
+    Err(e) => {
+      let _ = on_event.send(FileEvent::Error { message: e.clone() });
+      events::publish(&app, "file.failed", None, serde_json::json!({ "path": path, "error": e }));
+    }
+  }
+  result
+}
+
+async fn translate_file_inner(
+  app: &tauri::AppHandle,
+  path: &str,
+  target_lang: &str,
+  base_url: Option<String>,
+  on_event: &Channel<FileEvent>,
+) -> Result<String, String> {
+  let path = PathBuf::from(path);
+  let format = Format::from_path(&path)
+    .ok_or_else(|| "UNSUPPORTED_FILE_TYPE: expected .txt, .md, .srt or .vtt".to_string())?;
+  let size = std::fs::metadata(&path).map_err(|e| format!("cannot read {}: {e}", path.display()))?.len();
+  if size > MAX_FILE_BYTES {
+    return Err(format!("FILE_TOO_LARGE: {size} bytes (max {MAX_FILE_BYTES})"));
+  }
+  let bytes = std::fs::read(&path).map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+  let text = String::from_utf8(bytes).map_err(|_| "FILE_NOT_UTF8".to_string())?;
+  let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+  let base_url = match base_url.filter(|b| !b.trim().is_empty()) {
+    Some(b) => b,
+    None => settings::api_base_url(app)?,
+  };
+
+  let segments = segment(text, format);
+  let total = segments.iter().filter(|s| matches!(s, Segment::Translate(_))).count();
+  let _ = on_event.send(FileEvent::Started { total });
+
+  let request = |text: &str| TranslateRequest {
+    base_url: base_url.clone(),
+    text: text.to_string(),
+    target_lang: target_lang.to_string(),
+    mode: "standard".to_string(),
+    explanation_lang: "ja".to_string(),
+    priority: Priority::Background,
+    output: OutputControls {
+      verbosity: Some(Verbosity::Concise),
+      include_examples: Some(false),
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+
+  use futures_util::StreamExt;
+  // Collect first: a lazily-mapped iterator inside the stream trips the Send check on the command future.
+  let futures: Vec<_> = segments
+    .iter()
+    .filter_map(|s| match s {
+      Segment::Translate(t) => Some(translate_segment(app, request(t))),
+      Segment::Keep(_) => None,
+    })
+    .collect();
+  let mut results = futures_util::stream::iter(futures).buffered(settings::chunk_concurrency(app));
+  let mut translated = Vec::with_capacity(total);
+  while let Some(r) = results.next().await {
+    translated.push(r?);
+    let _ = on_event.send(FileEvent::Progress {
+      done: translated.len(),
+      total,
+    });
+  }
+
+  // Multi-line segments were joined with `\n`; match the file's own line endings.
+  let crlf = text.contains("\r\n");
+  let mut translated = translated.into_iter();
+  let mut out = String::with_capacity(text.len() * 2);
+  for seg in &segments {
+    match seg {
+      Segment::Keep(k) => out.push_str(k),
+      Segment::Translate(_) => {
+        let t = translated.next().unwrap_or_default();
+        out.push_str(&if crlf { t.replace("\r\n", "\n").replace('\n', "\r\n") } else { t });
+      }
+    }
+  }
+  let out_path = output_path_for(&path, target_lang);
+  std::fs::write(&out_path, out).map_err(|e| format!("cannot write {}: {e}", out_path.display()))?;
+  let out_path = out_path.to_string_lossy().to_string();
+  let _ = on_event.send(FileEvent::Done {
+    output_path: out_path.clone(),
+  });
+  Ok(out_path)
+}
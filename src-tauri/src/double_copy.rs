@@ -0,0 +1,103 @@
+//! Double-copy trigger: pressing Ctrl+C (Cmd+C) twice in quick succession translates what was copied, the way
+//! popular dictionary tools do.
+//!
+//! The first press copies as usual; the second, within `doubleCopyIntervalMs` (default 400), fires the
+//! translate-clipboard action once the clipboard has had a moment to take the copy. A pair fires once: a third
+//! press starts a new pair instead of firing again. Key presses come from `input_hook`, which runs only while the
+//! trigger is on. `doubleCopyTrigger` (default off) turns it on; `set_double_copy_enabled` overrides that for the
+//! session. Paused or suspended hotkeys pause it too.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::{agent, input_hook, settings};
+
+/// Time the app needs to put the second copy on the clipboard before it's read.
+const CLIPBOARD_SETTLE_MS: u64 = 150;
+
+#[derive(Default)]
+pub struct DoubleCopy {
+  /// Session override of `doubleCopyTrigger`.
+  enabled: Mutex<Option<bool>>,
+  /// First press of a pair that hasn't completed.
+  last: Mutex<Option<Instant>>,
+}
+
+/// Whether the second of two presses `interval` apart completes a pair; updates the pending first press.
+fn completes_pair(last: &mut Option<Instant>, at: Instant, interval: Duration) -> bool {
+  match last.take() {
+    Some(first) if at.saturating_duration_since(first) <= interval => true,
+    _ => {
+      *last = Some(at);
+      false
+    }
+  }
+}
+
+/// A copy shortcut was pressed (`input_hook`, on its thread).
+pub fn on_copy(app: &tauri::AppHandle) {
+  if crate::hotkeys::held(app) {
+    return;
+  }
+  let interval = Duration::from_millis(settings::double_copy_interval_ms(app));
+  let state = app.state::<DoubleCopy>();
+  let fired = completes_pair(&mut state.last.lock().unwrap_or_else(|e| e.into_inner()), Instant::now(), interval);
+  if !fired {
+    return;
+  }
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(Duration::from_millis(CLIPBOARD_SETTLE_MS)).await;
+    agent::run_action(&app, "translate_clipboard");
+  });
+}
+
+/// Whether the trigger is on: the session override, else `doubleCopyTrigger`.
+pub fn is_enabled(app: &tauri::AppHandle) -> bool {
+  let overridden = *app.state::<DoubleCopy>().enabled.lock().unwrap_or_else(|e| e.into_inner());
+  overridden.unwrap_or_else(|| settings::double_copy_trigger(app))
+}
+
+fn reload(app: &tauri::AppHandle, _: &settings::ConfigChange) -> Result<(), String> {
+  // A saved setting replaces the session override.
+  *app.state::<DoubleCopy>().enabled.lock().unwrap_or_else(|e| e.into_inner()) = None;
+  input_hook::sync(app)
+}
+
+/// Start listening if the trigger is on, and follow its setting (`setup`).
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "double_copy", &["doubleCopyTrigger"], reload);
+  if let Err(e) = input_hook::sync(app) {
+    crate::events::warn(app, None, "double_copy_unavailable", e);
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DoubleCopyStatus {
+  pub enabled: bool,
+  pub interval_ms: u64,
+}
+
+/// Turn the double-copy trigger on or off for this session; `enabled: None` toggles. Fails, leaving it off, when
+/// keys can't be watched here.
+#[tauri::command]
+pub fn set_double_copy_enabled(app: tauri::AppHandle, enabled: Option<bool>) -> Result<DoubleCopyStatus, String> {
+  let enabled = enabled.unwrap_or(!is_enabled(&app));
+  *app.state::<DoubleCopy>().enabled.lock().unwrap_or_else(|e| e.into_inner()) = Some(enabled);
+  if let Err(e) = input_hook::sync(&app) {
+    *app.state::<DoubleCopy>().enabled.lock().unwrap_or_else(|e| e.into_inner()) = Some(false);
+    return Err(e);
+  }
+  Ok(double_copy_status(app))
+}
+
+/// Whether the double-copy trigger is on, and the pair interval.
+#[tauri::command]
+pub fn double_copy_status(app: tauri::AppHandle) -> DoubleCopyStatus {
+  DoubleCopyStatus {
+    enabled: is_enabled(&app),
+    interval_ms: settings::double_copy_interval_ms(&app),
+  }
+}
@@ -0,0 +1,120 @@
+//! Backend event bus.
+//!
+//! Subsystems publish `BusEvent`s with a dotted `kind` (e.g. `capture.started`, `stream.delta`,
+//! `job.cancelled`, `hotkey.fired`). The frontend subscribes with a filter through `subscribe_events`;
+//! scripts bound to an event kind are run by the bus as well.
+
+use crate::clock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::ipc::Channel;
+use tauri::Manager;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BusEvent {
+  pub seq: u64,
+  pub ts: u128,
+  pub kind: String,
+  /// Request/job the event belongs to, if any.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub request_id: Option<String>,
+  pub payload: serde_json::Value,
+}
+
+/// Subscription filter. `kinds` entries match exactly, by prefix (`stream.*`) or everything (`*`).
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EventFilter {
+  #[serde(default)]
+  pub kinds: Vec<String>,
+  #[serde(default)]
+  pub request_id: Option<String>,
+}
+
+impl EventFilter {
+  pub fn matches(&self, ev: &BusEvent) -> bool {
+    if let Some(id) = &self.request_id {
+      if ev.request_id.as_deref() != Some(id.as_str()) {
+        return false;
+      }
+    }
+    self.kinds.is_empty() || self.kinds.iter().any(|k| kind_matches(k, &ev.kind))
+  }
+}
+
+pub fn kind_matches(pattern: &str, kind: &str) -> bool {
+  if pattern == "*" {
+    return true;
+  }
+  match pattern.strip_suffix(".*") {
+    Some(prefix) => kind.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('.')),
+    None => pattern == kind,
+  }
+}
+
+struct Subscription {
+  id: u64,
+  filter: EventFilter,
+  channel: Channel<BusEvent>,
+}
+
+/// Managed state.
+#[derive(Default)]
+pub struct EventBus {
+  seq: AtomicU64,
+  next_sub: AtomicU64,
+  subs: Mutex<Vec<Subscription>>,
+}
+
+impl EventBus {
+  fn dispatch(&self, ev: &BusEvent) {
+    let mut subs = self.subs.lock().unwrap_or_else(|e| e.into_inner());
+    // Drop subscriptions whose webview went away.
+    subs.retain(|s| !s.filter.matches(ev) || s.channel.send(ev.clone()).is_ok());
+  }
+}
+
+/// Publish an event to subscribers and bound scripts.
+pub fn publish(app: &tauri::AppHandle, kind: &str, request_id: Option<&str>, payload: serde_json::Value) {
+  let bus = app.state::<EventBus>();
+  let ev = BusEvent {
+    seq: bus.seq.fetch_add(1, Ordering::SeqCst),
+    ts: clock::now_millis(),
+    kind: kind.to_string(),
+    request_id: request_id.map(|s| s.to_string()),
+    payload,
+  };
+  bus.dispatch(&ev);
+  crate::scripting::fire_event(app, &ev);
+}
+
+/// Report a degradation outside a translation stream (those use `StreamEvent::Warning`): logged and
+/// published as `warning` with `{code, message}`.
+pub fn warn(app: &tauri::AppHandle, request_id: Option<&str>, code: &str, message: impl Into<String>) {
+  let message = message.into();
+  log::warn!("{code}: {message}");
+  publish(app, "warning", request_id, serde_json::json!({ "code": code, "message": message }));
+}
+
+#[tauri::command]
+pub fn subscribe_events(
+  filter: Option<EventFilter>,
+  on_event: Channel<BusEvent>,
+  bus: tauri::State<'_, EventBus>,
+) -> u64 {
+  let id = bus.next_sub.fetch_add(1, Ordering::SeqCst) + 1;
+  bus.subs.lock().unwrap_or_else(|e| e.into_inner()).push(Subscription {
+    id,
+    filter: filter.unwrap_or_default(),
+    channel: on_event,
+  });
+  id
+}
+
+#[tauri::command]
+pub fn unsubscribe_events(subscription_id: u64, bus: tauri::State<'_, EventBus>) -> bool {
+  let mut subs = bus.subs.lock().unwrap_or_else(|e| e.into_inner());
+  let before = subs.len();
+  subs.retain(|s| s.id != subscription_id);
+  subs.len() != before
+}
@@ -0,0 +1,365 @@
+//! Fading windows in and out, so the popup appears and goes away smoothly instead of popping.
+//!
+//! Windows has no animation for this: the window is made layered and its alpha stepped about every 16 ms (keeping
+//! the transparent style of a click-through window, which shares the layered style with it; see
+//! `popup::set_click_through`). On
+//! macOS the window's animator proxy runs the fade (`NSAnimationContext`), driven through the Objective-C runtime
+//! directly. Other platforms have no window opacity; windows there show and hide at once.
+//!
+//! The popup fades in on its own as its page loads and fades out when it auto-hides, over `popupFadeMs` (default
+//! 120; 0 turns fading off).
+//!
+//! A window can also be left see-through (`set_window_opacity`), say a pinned popup over a game or a video. That
+//! resting opacity is kept by label for the session, so the popup comes back at it, fading in to it rather than to
+//! fully opaque; without one the popup rests at `popupOpacity` (percent, default 100).
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::settings;
+
+/// The latest fade per window label (managed state); a newer one stops an older one mid-way.
+#[derive(Default)]
+pub struct Fades(Mutex<HashMap<String, Fade>>);
+
+#[derive(Clone, Copy)]
+struct Fade {
+  generation: u64,
+  /// Opacity the window was last set to, 0..=1.
+  opacity: f64,
+  /// Opacity chosen with `set_window_opacity`.
+  resting: Option<f64>,
+}
+
+impl Default for Fade {
+  fn default() -> Self {
+    Fade { generation: 0, opacity: 1.0, resting: None }
+  }
+}
+
+/// Lowest resting opacity, so a window can't be made invisible and forgotten.
+const MIN_OPACITY: f64 = 0.1;
+
+impl Fades {
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Fade>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+fn current(app: &tauri::AppHandle, label: &str) -> Fade {
+  app.state::<Fades>().lock().get(label).copied().unwrap_or_default()
+}
+
+/// The opacity window `label` shows at when not fading.
+pub fn resting_opacity(app: &tauri::AppHandle, label: &str) -> f64 {
+  match current(app, label).resting {
+    Some(opacity) => opacity,
+    None if label == crate::popup::POPUP_LABEL => settings::popup_opacity(app),
+    None => 1.0,
+  }
+}
+
+/// Start a fade of `label` and return its generation and starting opacity.
+fn begin(app: &tauri::AppHandle, label: &str) -> (u64, f64) {
+  let state = app.state::<Fades>();
+  let mut fades = state.lock();
+  let fade = fades.entry(label.to_string()).or_default();
+  fade.generation += 1;
+  (fade.generation, fade.opacity)
+}
+
+/// Record `opacity` for `label` if fade `generation` is still the latest.
+fn record(app: &tauri::AppHandle, label: &str, generation: u64, opacity: f64) -> bool {
+  let state = app.state::<Fades>();
+  let mut fades = state.lock();
+  match fades.get_mut(label) {
+    Some(fade) if fade.generation == generation => {
+      fade.opacity = opacity;
+      true
+    }
+    _ => false,
+  }
+}
+
+/// Whether this platform can change a window's opacity.
+pub fn supported() -> bool {
+  cfg!(any(windows, target_os = "macos"))
+}
+
+/// Set `window`'s opacity (0..=1) at once.
+pub fn set_opacity(window: &tauri::WebviewWindow, opacity: f64) -> Result<(), String> {
+  let opacity = opacity.clamp(0.0, 1.0);
+  let (generation, _) = begin(window.app_handle(), window.label());
+  record(window.app_handle(), window.label(), generation, opacity);
+  apply(window, opacity)
+}
+
+/// Set `window` to the opacity it was last given again, after something else changed its window style.
+pub fn reapply(window: &tauri::WebviewWindow) -> Result<(), String> {
+  if !supported() {
+    return Ok(());
+  }
+  apply(window, current(window.app_handle(), window.label()).opacity)
+}
+
+#[cfg(windows)]
+fn apply(window: &tauri::WebviewWindow, opacity: f64) -> Result<(), String> {
+  use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetWindowLongW, SetLayeredWindowAttributes, SetWindowLongW, GWL_EXSTYLE, LWA_ALPHA, WS_EX_LAYERED,
+    WS_EX_TRANSPARENT,
+  };
+  let hwnd = window.hwnd().map_err(|e| format!("no window handle: {e}"))?.0 as windows_sys::Win32::Foundation::HWND;
+  let click_through = crate::popup::is_click_through(window.state(), Some(window.label().to_string()));
+  unsafe {
+    let style = GetWindowLongW(hwnd, GWL_EXSTYLE);
+    let mut wanted = style | WS_EX_LAYERED as i32;
+    if click_through {
+      wanted |= WS_EX_TRANSPARENT as i32;
+    }
+    if wanted != style {
+      SetWindowLongW(hwnd, GWL_EXSTYLE, wanted);
+    }
+    if SetLayeredWindowAttributes(hwnd, 0, (opacity * 255.0).round() as u8, LWA_ALPHA) == 0 {
+      return Err("SetLayeredWindowAttributes failed".to_string());
+    }
+  }
+  Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn apply(window: &tauri::WebviewWindow, opacity: f64) -> Result<(), String> {
+  animate_mac(window, opacity, 0.0)
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn apply(_window: &tauri::WebviewWindow, _opacity: f64) -> Result<(), String> {
+  Ok(())
+}
+
+#[cfg(target_os = "macos")]
+mod objc {
+  use std::ffi::{c_char, c_void};
+
+  pub type Id = *mut c_void;
+
+  #[link(name = "objc")]
+  extern "C" {
+    fn objc_msgSend();
+    fn objc_getClass(name: *const c_char) -> Id;
+    fn sel_registerName(name: *const c_char) -> *const c_void;
+  }
+
+  #[link(name = "AppKit", kind = "framework")]
+  extern "C" {}
+
+  pub unsafe fn class(name: &std::ffi::CStr) -> Id {
+    objc_getClass(name.as_ptr())
+  }
+
+  /// `[receiver selector]`, returning an object.
+  pub unsafe fn send(receiver: Id, selector: &std::ffi::CStr) -> Id {
+    let f: unsafe extern "C" fn(Id, *const c_void) -> Id = std::mem::transmute(objc_msgSend as *const ());
+    f(receiver, sel_registerName(selector.as_ptr()))
+  }
+
+  /// `[receiver selector:value]` for a `CGFloat` / `NSTimeInterval` argument.
+  pub unsafe fn send_f64(receiver: Id, selector: &std::ffi::CStr, value: f64) {
+    let f: unsafe extern "C" fn(Id, *const c_void, f64) = std::mem::transmute(objc_msgSend as *const ());
+    f(receiver, sel_registerName(selector.as_ptr()), value)
+  }
+}
+
+/// Animate the `NSWindow`'s `alphaValue` to `opacity` over `seconds` (0: at once) on the main thread.
+#[cfg(target_os = "macos")]
+fn animate_mac(window: &tauri::WebviewWindow, opacity: f64, seconds: f64) -> Result<(), String> {
+  let ns_window = window.ns_window().map_err(|e| format!("no window handle: {e}"))? as usize;
+  window
+    .run_on_main_thread(move || unsafe {
+      let ns_window = ns_window as objc::Id;
+      if seconds <= 0.0 {
+        objc::send_f64(ns_window, c"setAlphaValue:", opacity);
+        return;
+      }
+      let context = objc::class(c"NSAnimationContext");
+      objc::send(context, c"beginGrouping");
+      objc::send_f64(objc::send(context, c"currentContext"), c"setDuration:", seconds);
+      objc::send_f64(objc::send(ns_window, c"animator"), c"setAlphaValue:", opacity);
+      objc::send(context, c"endGrouping");
+    })
+    .map_err(|e| format!("cannot change opacity: {e}"))
+}
+
+/// Fade `window` from its current opacity to `to` over `duration`. Returns false when a newer fade took over.
+pub async fn fade(window: &tauri::WebviewWindow, to: f64, duration: Duration) -> Result<bool, String> {
+  let to = to.clamp(0.0, 1.0);
+  let (generation, from) = begin(window.app_handle(), window.label());
+  if !supported() || duration.is_zero() {
+    record(window.app_handle(), window.label(), generation, to);
+    apply(window, to)?;
+    return Ok(true);
+  }
+  run(window, generation, from, to, duration).await
+}
+
+#[cfg(target_os = "macos")]
+async fn run(
+  window: &tauri::WebviewWindow,
+  generation: u64,
+  _from: f64,
+  to: f64,
+  duration: Duration,
+) -> Result<bool, String> {
+  animate_mac(window, to, duration.as_secs_f64())?;
+  tokio::time::sleep(duration).await;
+  Ok(record(window.app_handle(), window.label(), generation, to))
+}
+
+#[cfg(not(target_os = "macos"))]
+async fn run(
+  window: &tauri::WebviewWindow,
+  generation: u64,
+  from: f64,
+  to: f64,
+  duration: Duration,
+) -> Result<bool, String> {
+  const FRAME: Duration = Duration::from_millis(16);
+  let start = std::time::Instant::now();
+  loop {
+    let t = (start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1.0);
+    let opacity = from + (to - from) * t;
+    if !record(window.app_handle(), window.label(), generation, opacity) {
+      return Ok(false);
+    }
+    apply(window, opacity)?;
+    if t >= 1.0 {
+      return Ok(true);
+    }
+    tokio::time::sleep(FRAME).await;
+  }
+}
+
+/// Fade `window` out over `duration`, then hide it, or destroy it with `close`. A hidden window is put back to
+/// its resting opacity so it shows normally next time. Returns false when a newer fade took over (the window stays).
+pub async fn fade_out(window: &tauri::WebviewWindow, duration: Duration, close: bool) -> Result<bool, String> {
+  if !fade(window, 0.0, duration).await? {
+    return Ok(false);
+  }
+  if close {
+    window.destroy().map_err(|e| format!("cannot close {}: {e}", window.label()))?;
+    // A window opened again under the label starts opaque.
+    if let Some(fade) = window.app_handle().state::<Fades>().lock().get_mut(window.label()) {
+      fade.opacity = 1.0;
+    }
+  } else {
+    window.hide().map_err(|e| format!("cannot hide {}: {e}", window.label()))?;
+    set_opacity(window, resting_opacity(window.app_handle(), window.label()))?;
+  }
+  Ok(true)
+}
+
+fn duration(app: &tauri::AppHandle, duration_ms: Option<u64>) -> Duration {
+  Duration::from_millis(duration_ms.unwrap_or_else(|| settings::popup_fade_ms(app)))
+}
+
+/// Fade window `label` (default the popup) to `opacity` (0..=1) over `duration_ms` (default `popupFadeMs`).
+#[tauri::command]
+pub async fn fade_window(
+  app: tauri::AppHandle,
+  label: Option<String>,
+  opacity: f64,
+  duration_ms: Option<u64>,
+) -> Result<(), String> {
+  let label = label.unwrap_or_else(|| crate::popup::POPUP_LABEL.to_string());
+  let window = app.get_webview_window(&label).ok_or_else(|| format!("no window {label}"))?;
+  fade(&window, opacity, duration(&app, duration_ms)).await.map(|_| ())
+}
+
+/// Fade window `label` (default the popup) out over `duration_ms` (default `popupFadeMs`), then hide it, or
+/// destroy it with `close`.
+#[tauri::command]
+pub async fn fade_out_window(
+  app: tauri::AppHandle,
+  label: Option<String>,
+  duration_ms: Option<u64>,
+  close: Option<bool>,
+) -> Result<(), String> {
+  let label = label.unwrap_or_else(|| crate::popup::POPUP_LABEL.to_string());
+  let window = app.get_webview_window(&label).ok_or_else(|| format!("no window {label}"))?;
+  fade_out(&window, duration(&app, duration_ms), close.unwrap_or(false)).await.map(|_| ())
+}
+
+/// Leave window `label` (default the popup) at `opacity` (0.1..=1): now if it's open, and whenever it's shown
+/// again under that label this session. Returns the opacity set.
+#[tauri::command]
+pub fn set_window_opacity(app: tauri::AppHandle, label: Option<String>, opacity: f64) -> Result<f64, String> {
+  if !supported() {
+    return Err("window opacity isn't supported on this platform".to_string());
+  }
+  if !opacity.is_finite() {
+    return Err("invalid opacity".to_string());
+  }
+  let label = label.unwrap_or_else(|| crate::popup::POPUP_LABEL.to_string());
+  let opacity = opacity.clamp(MIN_OPACITY, 1.0);
+  app.state::<Fades>().lock().entry(label.clone()).or_default().resting = Some(opacity);
+  if let Some(window) = app.get_webview_window(&label) {
+    set_opacity(&window, opacity)?;
+  }
+  Ok(opacity)
+}
+
+/// The resting opacity of window `label` (default the popup).
+#[tauri::command]
+pub fn get_window_opacity(app: tauri::AppHandle, label: Option<String>) -> f64 {
+  resting_opacity(&app, label.as_deref().unwrap_or(crate::popup::POPUP_LABEL))
+}
+
+/// Follow `popupOpacity` changes on an open popup that has no opacity of its own.
+fn reload(app: &tauri::AppHandle, _change: &settings::ConfigChange) -> Result<(), String> {
+  let label = crate::popup::POPUP_LABEL;
+  if current(app, label).resting.is_some() {
+    return Ok(());
+  }
+  match app.get_webview_window(label) {
+    Some(window) if supported() => set_opacity(&window, settings::popup_opacity(app)),
+    _ => Ok(()),
+  }
+}
+
+/// Subscribe to `popupOpacity` (`setup`).
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "fade", &["popupOpacity"], reload);
+}
+
+/// Fade the popup in as it loads: transparent when loading starts, faded in to its resting opacity once the page
+/// is there.
+pub fn on_page_load(webview: &tauri::Webview, payload: &tauri::webview::PageLoadPayload<'_>) {
+  let app = webview.app_handle();
+  if webview.label() != crate::popup::POPUP_LABEL || !supported() {
+    return;
+  }
+  let Some(window) = app.get_webview_window(webview.label()) else {
+    return;
+  };
+  let resting = resting_opacity(app, webview.label());
+  let duration = duration(app, None);
+  match payload.event() {
+    tauri::webview::PageLoadEvent::Started => {
+      let opacity = if duration.is_zero() { resting } else { 0.0 };
+      if current(app, webview.label()).opacity != opacity {
+        if let Err(e) = set_opacity(&window, opacity) {
+          log::warn!("{e}");
+        }
+      }
+    }
+    tauri::webview::PageLoadEvent::Finished if !duration.is_zero() => {
+      tauri::async_runtime::spawn(async move {
+        if let Err(e) = fade(&window, resting, duration).await {
+          log::warn!("{e}");
+        }
+      });
+    }
+    tauri::webview::PageLoadEvent::Finished => {}
+  }
+}
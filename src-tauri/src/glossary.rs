@@ -0,0 +1,561 @@
+//! The glossary: the user's term base (a source term and the translation it must get), kept locally and handed to
+//! the translation providers with each request.
+//!
+//! Entries are added, edited and deleted one at a time or imported in bulk from CSV (`source,target[,note]`, or
+//! columns named by a header row) or TBX (one `termEntry`/`conceptEntry` per concept, a `langSet`/`langSec` per
+//! language), and saved to `glossary.json` in the app data dir. For each translation, the entries whose source term
+//! occurs in the text (ignoring case unless `case_sensitive`) and whose target language fits go to the server as
+//! `glossary: [{ "source", "target", "note" }]` in the request body and to provider plugins in their input. The
+//! words of the source terms are also Tesseract user words (see `ocr::request`), so OCR reads them as they are.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::clock;
+
+const GLOSSARY_FILE: &str = "glossary.json";
+/// Most terms sent with one request, longest source terms first.
+const MAX_TERMS_PER_REQUEST: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GlossaryEntry {
+  pub id: u64,
+  pub source: String,
+  pub target: String,
+  /// Language of the source term, as the app names it ("English (US)") or a code ("en").
+  #[serde(default)]
+  pub source_lang: Option<String>,
+  /// Language the entry applies to when translating into it; any target when unset.
+  #[serde(default)]
+  pub target_lang: Option<String>,
+  #[serde(default)]
+  pub note: Option<String>,
+  #[serde(default)]
+  pub case_sensitive: bool,
+  pub updated_at: u128,
+}
+
+/// An entry as the frontend sends it to `add_glossary_entry` and `update_glossary_entry`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct GlossaryInput {
+  pub source: String,
+  pub target: String,
+  #[serde(default)]
+  pub source_lang: Option<String>,
+  #[serde(default)]
+  pub target_lang: Option<String>,
+  #[serde(default)]
+  pub note: Option<String>,
+  #[serde(default)]
+  pub case_sensitive: bool,
+}
+
+/// A term as the providers get it.
+#[derive(Debug, Serialize, Clone)]
+pub struct Term {
+  pub source: String,
+  pub target: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GlossaryFormat {
+  Csv,
+  Tbx,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ImportReport {
+  pub added: usize,
+  /// Entries that replaced one with the same source term and target language.
+  pub updated: usize,
+  /// Rows or concepts without both a source and a target term.
+  pub skipped: usize,
+}
+
+#[derive(Default)]
+struct Inner {
+  path: Option<PathBuf>,
+  entries: Vec<GlossaryEntry>,
+}
+
+impl Inner {
+  fn save(&self) -> Result<(), String> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    crate::json_store::save(path, &self.entries).map_err(|e| format!("failed to save glossary: {e}"))
+  }
+
+  fn next_id(&self) -> u64 {
+    self.entries.iter().map(|e| e.id).max().unwrap_or(0) + 1
+  }
+
+  /// Add `input`, or replace the entry with the same source term and target language; true when added.
+  fn upsert(&mut self, input: GlossaryInput) -> bool {
+    let key = (input.source.to_lowercase(), lang_key(input.target_lang.as_deref()));
+    let existing = self
+      .entries
+      .iter_mut()
+      .find(|e| (e.source.to_lowercase(), lang_key(e.target_lang.as_deref())) == key);
+    match existing {
+      Some(entry) => {
+        *entry = to_entry(entry.id, input);
+        false
+      }
+      None => {
+        let id = self.next_id();
+        self.entries.push(to_entry(id, input));
+        true
+      }
+    }
+  }
+}
+
+/// The glossary (managed state).
+#[derive(Default)]
+pub struct Glossary(Mutex<Inner>);
+
+impl Glossary {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Load the saved glossary from `dir`.
+pub fn init(app: &tauri::AppHandle, dir: &Path) {
+  let path = dir.join(GLOSSARY_FILE);
+  let entries = crate::json_store::load(app, &path, "glossary_unreadable");
+  let state = app.state::<Glossary>();
+  let mut inner = state.lock();
+  inner.path = Some(path);
+  inner.entries = entries;
+}
+
+fn non_empty(s: Option<String>) -> Option<String> {
+  s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn validate(input: GlossaryInput) -> Result<GlossaryInput, String> {
+  let input = GlossaryInput {
+    source: input.source.trim().to_string(),
+    target: input.target.trim().to_string(),
+    source_lang: non_empty(input.source_lang),
+    target_lang: non_empty(input.target_lang),
+    note: non_empty(input.note),
+    ..input
+  };
+  if input.source.is_empty() {
+    return Err("source term is empty".to_string());
+  }
+  if input.target.is_empty() {
+    return Err("target term is empty".to_string());
+  }
+  Ok(input)
+}
+
+fn to_entry(id: u64, input: GlossaryInput) -> GlossaryEntry {
+  GlossaryEntry {
+    id,
+    source: input.source,
+    target: input.target,
+    source_lang: input.source_lang,
+    target_lang: input.target_lang,
+    note: input.note,
+    case_sensitive: input.case_sensitive,
+    updated_at: clock::now_millis(),
+  }
+}
+
+/// Comparable form of a language name or tag: "English (US)", "en-US" and "en" are all "en".
+pub(crate) fn lang_key(lang: Option<&str>) -> Option<String> {
+  let lang = lang?.trim();
+  if lang.is_empty() {
+    return None;
+  }
+  let base = lang.split(['-', '_']).next().unwrap_or(lang);
+  let code = crate::offline_mt::lang_code(lang).or_else(|| crate::offline_mt::lang_code(base));
+  Some(code.map(str::to_string).unwrap_or_else(|| lang.to_lowercase()))
+}
+
+/// What makes an entry the same one on another machine (ids aren't): its source term and target language.
+pub(crate) fn sync_key(entry: &GlossaryEntry) -> String {
+  let lang = lang_key(entry.target_lang.as_deref()).unwrap_or_default();
+  format!("{}\u{1f}{lang}", entry.source.to_lowercase())
+}
+
+/// The glossary terms that apply to translating `text` into `target_lang`.
+pub fn terms_for(app: &tauri::AppHandle, text: &str, target_lang: &str) -> Vec<Term> {
+  let state = app.state::<Glossary>();
+  let inner = state.lock();
+  if inner.entries.is_empty() {
+    return Vec::new();
+  }
+  let target = lang_key(Some(target_lang));
+  let lower = text.to_lowercase();
+  let mut matched: Vec<&GlossaryEntry> = inner
+    .entries
+    .iter()
+    .filter(|e| e.target_lang.is_none() || lang_key(e.target_lang.as_deref()) == target)
+    .filter(|e| {
+      if e.case_sensitive {
+        text.contains(&e.source)
+      } else {
+        lower.contains(&e.source.to_lowercase())
+      }
+    })
+    .collect();
+  matched.sort_by_key(|e| std::cmp::Reverse(e.source.chars().count()));
+  matched
+    .into_iter()
+    .take(MAX_TERMS_PER_REQUEST)
+    .map(|e| Term {
+      source: e.source.clone(),
+      target: e.target.clone(),
+      note: e.note.clone(),
+    })
+    .collect()
+}
+
+/// The words of the source terms, for Tesseract's `--user-words` (which matches single words).
+pub fn source_words(app: &tauri::AppHandle) -> Vec<String> {
+  let state = app.state::<Glossary>();
+  let inner = state.lock();
+  inner.entries.iter().flat_map(|e| e.source.split_whitespace()).map(str::to_string).collect()
+}
+
+/// Every entry, for backups and sync.
+pub fn entries(app: &tauri::AppHandle) -> Vec<GlossaryEntry> {
+  app.state::<Glossary>().lock().entries.clone()
+}
+
+/// Replace every entry with `entries` and save them.
+pub fn replace(app: &tauri::AppHandle, entries: Vec<GlossaryEntry>) -> Result<(), String> {
+  let state = app.state::<Glossary>();
+  let mut inner = state.lock();
+  inner.entries = entries;
+  inner.save()
+}
+
+/// All entries, sorted by source term.
+#[tauri::command]
+pub fn list_glossary(state: tauri::State<'_, Glossary>) -> Vec<GlossaryEntry> {
+  let mut entries = state.lock().entries.clone();
+  entries.sort_by_key(|e| e.source.to_lowercase());
+  entries
+}
+
+/// Add an entry; returns it with its id.
+#[tauri::command]
+pub fn add_glossary_entry(state: tauri::State<'_, Glossary>, entry: GlossaryInput) -> Result<GlossaryEntry, String> {
+  let entry = validate(entry)?;
+  let mut inner = state.lock();
+  let created = to_entry(inner.next_id(), entry);
+  inner.entries.push(created.clone());
+  inner.save()?;
+  Ok(created)
+}
+
+/// Replace entry `id` with `entry`.
+#[tauri::command]
+pub fn update_glossary_entry(
+  state: tauri::State<'_, Glossary>,
+  id: u64,
+  entry: GlossaryInput,
+) -> Result<GlossaryEntry, String> {
+  let entry = validate(entry)?;
+  let mut inner = state.lock();
+  let slot = inner
+    .entries
+    .iter_mut()
+    .find(|e| e.id == id)
+    .ok_or_else(|| format!("no glossary entry {id}"))?;
+  *slot = to_entry(id, entry);
+  let updated = slot.clone();
+  inner.save()?;
+  Ok(updated)
+}
+
+/// Delete the entries with these ids; returns how many were deleted.
+#[tauri::command]
+pub fn delete_glossary_entries(state: tauri::State<'_, Glossary>, ids: Vec<u64>) -> Result<usize, String> {
+  let mut inner = state.lock();
+  let before = inner.entries.len();
+  inner.entries.retain(|e| !ids.contains(&e.id));
+  let deleted = before - inner.entries.len();
+  if deleted > 0 {
+    inner.save()?;
+  }
+  Ok(deleted)
+}
+
+/// Rows of a CSV (or tab-separated) file; quoted fields may hold separators, quotes (`""`) and line breaks.
+pub(crate) fn csv_rows(text: &str) -> Vec<Vec<String>> {
+  let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+  let first_line = text.lines().next().unwrap_or("");
+  let sep = if first_line.contains('\t') && !first_line.contains(',') { '\t' } else { ',' };
+  let mut rows = Vec::new();
+  let mut row = Vec::new();
+  let mut field = String::new();
+  let mut quoted = false;
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        field.push('"');
+        chars.next();
+      }
+      '"' if quoted => quoted = false,
+      '"' if field.is_empty() => quoted = true,
+      c if c == sep && !quoted => row.push(std::mem::take(&mut field)),
+      '\r' if !quoted => {}
+      '\n' if !quoted => {
+        row.push(std::mem::take(&mut field));
+        rows.push(std::mem::take(&mut row));
+      }
+      c => field.push(c),
+    }
+  }
+  if !field.is_empty() || !row.is_empty() {
+    row.push(field);
+    rows.push(row);
+  }
+  rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+  rows
+}
+
+fn parse_csv(text: &str, source_lang: Option<&str>, target_lang: Option<&str>) -> (Vec<GlossaryInput>, usize) {
+  let mut rows = csv_rows(text).into_iter().peekable();
+  // Column positions: a header row names them, otherwise source, target, note.
+  let mut columns = [Some(0), Some(1), Some(2), None, None];
+  let is_header = rows
+    .peek()
+    .and_then(|r| r.first())
+    .is_some_and(|f| matches!(f.trim().to_lowercase().as_str(), "source" | "term" | "source term" | "src"));
+  if is_header {
+    let header: Vec<String> = rows.next().unwrap_or_default().iter().map(|f| f.trim().to_lowercase()).collect();
+    let find = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    columns = [
+      find(&["source", "term", "source term", "src"]),
+      find(&["target", "translation", "target term", "tgt"]),
+      find(&["note", "notes", "comment", "description"]),
+      find(&["source_lang", "source language", "source lang"]),
+      find(&["target_lang", "target language", "target lang"]),
+    ];
+  }
+  let mut inputs = Vec::new();
+  let mut skipped = 0;
+  for row in rows {
+    let cell = |i: Option<usize>| i.and_then(|i| row.get(i)).map(|s| s.to_string());
+    let input = GlossaryInput {
+      source: cell(columns[0]).unwrap_or_default(),
+      target: cell(columns[1]).unwrap_or_default(),
+      source_lang: non_empty(cell(columns[3])).or(source_lang.map(str::to_string)),
+      target_lang: non_empty(cell(columns[4])).or(target_lang.map(str::to_string)),
+      note: cell(columns[2]),
+      case_sensitive: false,
+    };
+    match validate(input) {
+      Ok(input) => inputs.push(input),
+      Err(_) => skipped += 1,
+    }
+  }
+  (inputs, skipped)
+}
+
+/// Language of a TBX `langSet`/`langSec` (`xml:lang`).
+fn xml_lang<'a>(node: roxmltree::Node<'a, '_>) -> Option<&'a str> {
+  node.attribute((roxmltree::NS_XML_URI, "lang")).or_else(|| node.attribute("lang"))
+}
+
+fn parse_tbx(
+  text: &str,
+  source_lang: Option<&str>,
+  target_lang: Option<&str>,
+) -> Result<(Vec<GlossaryInput>, usize), String> {
+  let doc = roxmltree::Document::parse(text).map_err(|e| format!("not a TBX file: {e}"))?;
+  let root = doc.root_element();
+  let source = lang_key(source_lang.or_else(|| xml_lang(root)));
+  let target = lang_key(target_lang);
+  let mut inputs = Vec::new();
+  let mut skipped = 0;
+  for concept in doc.descendants().filter(|n| matches!(n.tag_name().name(), "termEntry" | "conceptEntry")) {
+    // The first term of each language in the concept.
+    let mut terms: Vec<(&str, String)> = Vec::new();
+    for lang_set in concept.children().filter(|n| matches!(n.tag_name().name(), "langSet" | "langSec")) {
+      let Some(lang) = xml_lang(lang_set) else {
+        continue;
+      };
+      let term = lang_set
+        .descendants()
+        .find(|n| n.tag_name().name() == "term")
+        .map(|n| n.descendants().filter_map(|t| t.text()).collect::<String>());
+      if let Some(term) = term.filter(|t| !t.trim().is_empty()) {
+        if !terms.iter().any(|(l, _)| lang_key(Some(*l)) == lang_key(Some(lang))) {
+          terms.push((lang, term));
+        }
+      }
+    }
+    let pick = |want: &Option<String>, not: Option<&str>| {
+      terms
+        .iter()
+        .find(|(l, _)| match want {
+          Some(want) => lang_key(Some(*l)).as_ref() == Some(want),
+          None => Some(*l) != not,
+        })
+        .cloned()
+    };
+    let Some((src_lang, src)) = pick(&source, None) else {
+      skipped += 1;
+      continue;
+    };
+    let Some((tgt_lang, tgt)) = pick(&target, Some(src_lang)) else {
+      skipped += 1;
+      continue;
+    };
+    let note = concept
+      .descendants()
+      .find(|n| matches!(n.tag_name().name(), "descrip" | "note"))
+      .and_then(|n| n.text())
+      .map(str::to_string);
+    let input = GlossaryInput {
+      source: src,
+      target: tgt,
+      source_lang: Some(src_lang.to_string()),
+      target_lang: Some(target_lang.unwrap_or(tgt_lang).to_string()),
+      note,
+      case_sensitive: false,
+    };
+    match validate(input) {
+      Ok(input) => inputs.push(input),
+      Err(_) => skipped += 1,
+    }
+  }
+  Ok((inputs, skipped))
+}
+
+/// Import the terms in `path` (CSV or TBX; by extension when `format` is unset). `source_lang` and `target_lang`
+/// pick the languages out of a multilingual TBX and fill in languages a CSV doesn't give. An imported term replaces
+/// the entry with the same source term and target language.
+#[tauri::command]
+pub async fn import_glossary(
+  app: tauri::AppHandle,
+  path: String,
+  format: Option<GlossaryFormat>,
+  source_lang: Option<String>,
+  target_lang: Option<String>,
+) -> Result<ImportReport, String> {
+  let format = match format {
+    Some(format) => format,
+    None => match Path::new(&path).extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+      Some("tbx") | Some("xml") => GlossaryFormat::Tbx,
+      Some("csv") | Some("tsv") | Some("txt") => GlossaryFormat::Csv,
+      _ => return Err(format!("unknown glossary format: {path} (expected .csv or .tbx)")),
+    },
+  };
+  let bytes = std::fs::read(&path).map_err(|e| format!("cannot read {path}: {e}"))?;
+  let text = String::from_utf8(bytes).map_err(|_| format!("{path} is not UTF-8"))?;
+  let (source_lang, target_lang) = (non_empty(source_lang), non_empty(target_lang));
+  let (inputs, skipped) = match format {
+    GlossaryFormat::Csv => parse_csv(&text, source_lang.as_deref(), target_lang.as_deref()),
+    GlossaryFormat::Tbx => parse_tbx(&text, source_lang.as_deref(), target_lang.as_deref())?,
+  };
+  let state = app.state::<Glossary>();
+  let mut inner = state.lock();
+  let mut report = ImportReport {
+    skipped,
+    ..Default::default()
+  };
+  for input in inputs {
+    if inner.upsert(input) {
+      report.added += 1;
+    } else {
+      report.updated += 1;
+    }
+  }
+  inner.save()?;
+  Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn row(fields: &[&str]) -> Vec<String> {
+    fields.iter().map(|f| f.to_string()).collect()
+  }
+
+  #[test]
+  fn csv_rows_reads_quoted_fields() {
+    let text = "\u{feff}source,target,note\r\n\"a, b\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n\r\nc,d,\n";
+    assert_eq!(
+      csv_rows(text),
+      vec![
+        row(&["source", "target", "note"]),
+        row(&["a, b", "say \"hi\"", "two\nlines"]),
+        row(&["c", "d", ""]),
+      ]
+    );
+  }
+
+  #[test]
+  fn csv_rows_detects_tabs() {
+    assert_eq!(csv_rows("a\tb; c\nd\te"), vec![row(&["a", "b; c"]), row(&["d", "e"])]);
+  }
+
+  #[test]
+  fn parse_csv_follows_the_header() {
+    let text = "source,note,target,target language\nErudaite,brand,エルダイト,ja\n,missing,,\n";
+    let (inputs, skipped) = parse_csv(text, Some("en"), None);
+    assert_eq!(skipped, 1);
+    assert_eq!(inputs.len(), 1);
+    let input = &inputs[0];
+    assert_eq!((input.source.as_str(), input.target.as_str()), ("Erudaite", "エルダイト"));
+    assert_eq!(input.note.as_deref(), Some("brand"));
+    assert_eq!(input.source_lang.as_deref(), Some("en"));
+    assert_eq!(input.target_lang.as_deref(), Some("ja"));
+  }
+
+  const TBX: &str = r#"<?xml version="1.0"?>
+<tbx xml:lang="en">
+  <text><body>
+    <conceptEntry id="1">
+      <descrip type="definition">a fruit</descrip>
+      <langSec xml:lang="en"><termSec><term>apple</term></termSec></langSec>
+      <langSec xml:lang="de"><termSec><term>Apfel</term></termSec></langSec>
+      <langSec xml:lang="ja-JP"><termSec><term>りんご</term></termSec></langSec>
+    </conceptEntry>
+    <termEntry id="2">
+      <langSet xml:lang="en"><tig><term>pear</term></tig></langSet>
+    </termEntry>
+  </body></text>
+</tbx>"#;
+
+  #[test]
+  fn parse_tbx_picks_the_requested_languages() {
+    let (inputs, skipped) = parse_tbx(TBX, None, Some("Japanese")).unwrap();
+    assert_eq!(skipped, 1);
+    assert_eq!(inputs.len(), 1);
+    let input = &inputs[0];
+    assert_eq!((input.source.as_str(), input.target.as_str()), ("apple", "りんご"));
+    assert_eq!(input.source_lang.as_deref(), Some("en"));
+    assert_eq!(input.target_lang.as_deref(), Some("Japanese"));
+    assert_eq!(input.note.as_deref(), Some("a fruit"));
+  }
+
+  #[test]
+  fn parse_tbx_defaults_to_the_next_language() {
+    let (inputs, _) = parse_tbx(TBX, None, None).unwrap();
+    assert_eq!(inputs[0].target, "Apfel");
+    assert_eq!(inputs[0].target_lang.as_deref(), Some("de"));
+  }
+
+  #[test]
+  fn parse_tbx_rejects_other_files() {
+    assert!(parse_tbx("source,target", None, None).is_err());
+  }
+}
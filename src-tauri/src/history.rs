@@ -0,0 +1,481 @@
+//! Translation history: every finished interactive translation (source, translation, languages, mode, provider,
+//! the app the text came from, when), kept in `history.sqlite3` in the app data dir.
+//!
+//! `run_translation` records; the frontend pages through entries newest first (`list_history`) with an optional
+//! filter, and deletes single entries or everything a filter matches. Entries can be starred to keep useful
+//! translations and phrases for later review (`starred` in the filter lists just those); clearing leaves starred
+//! entries alone unless the filter asks for them. Background requests (documents, scripts) aren't recorded, and
+//! nothing is while `saveHistory` is off. The schema is versioned with `user_version` and migrated on open.
+//!
+//! `historyRetention` limits what is kept: `{ "keep": "days", "days": 30 }`, `{ "keep": "entries", "entries":
+//! 1000 }` or `{ "keep": "nothing" }` (nothing new is recorded either); unset or `{ "keep": "all" }` keeps
+//! everything. Starred entries are never removed by it. The policy is applied on startup and every hour;
+//! `purge_history` deletes on demand, starred entries included, and compacts the file so the deleted text doesn't
+//! linger on disk. With `encryptHistory` the file is encrypted (see `history_cipher`).
+
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::{clock, history_cipher, settings};
+
+const HISTORY_FILE: &str = "history.sqlite3";
+const DEFAULT_PAGE: u32 = 50;
+const MAX_PAGE: u32 = 500;
+/// How often the retention policy is applied while the app runs.
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Schema migrations; entry `n` takes the database from version `n` to `n + 1`.
+const MIGRATIONS: &[&str] = &[
+  "CREATE TABLE translations (
+    id INTEGER PRIMARY KEY,
+    source TEXT NOT NULL,
+    translation TEXT NOT NULL,
+    source_lang TEXT,
+    target_lang TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    origin_app TEXT,
+    created_at INTEGER NOT NULL
+  );
+  CREATE INDEX translations_created_at ON translations (created_at);",
+  "ALTER TABLE translations ADD COLUMN starred INTEGER NOT NULL DEFAULT 0;
+  CREATE INDEX translations_starred ON translations (starred, created_at);",
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HistoryEntry {
+  pub id: i64,
+  pub source: String,
+  pub translation: String,
+  /// Detected language of the source, when it could be told.
+  pub source_lang: Option<String>,
+  pub target_lang: String,
+  pub mode: String,
+  /// Plugin id, "offline", or the translation server's host.
+  pub provider: String,
+  /// Executable of the app the text came from (Windows).
+  pub origin_app: Option<String>,
+  /// Milliseconds since the Unix epoch.
+  pub created_at: i64,
+  pub starred: bool,
+}
+
+/// What to record for a translation.
+pub struct NewEntry<'a> {
+  pub source: &'a str,
+  pub translation: &'a str,
+  /// Detected language of the source text.
+  pub source_lang: Option<String>,
+  pub target_lang: &'a str,
+  pub mode: &'a str,
+  pub provider: String,
+  pub origin_app: Option<String>,
+}
+
+/// Which entries a query covers (camelCase on the wire, all optional).
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryFilter {
+  /// Text the source or the translation contains (case-insensitive for ASCII).
+  pub query: Option<String>,
+  pub source_lang: Option<String>,
+  pub target_lang: Option<String>,
+  pub mode: Option<String>,
+  pub provider: Option<String>,
+  pub origin_app: Option<String>,
+  /// Entries from this time on (ms since the epoch).
+  pub since: Option<i64>,
+  /// Entries before this time (ms since the epoch).
+  pub until: Option<i64>,
+  /// Only starred (`true`) or only unstarred (`false`) entries.
+  pub starred: Option<bool>,
+}
+
+impl HistoryFilter {
+  /// SQL condition and its parameters.
+  fn to_sql(&self) -> (String, Vec<Value>) {
+    let mut clauses = vec!["1".to_string()];
+    let mut params = Vec::new();
+    if let Some(q) = self.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+      let escaped = q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+      clauses.push("(source LIKE ?1 ESCAPE '\\' OR translation LIKE ?1 ESCAPE '\\')".to_string());
+      params.push(Value::Text(format!("%{escaped}%")));
+    }
+    let columns = [
+      ("source_lang", &self.source_lang),
+      ("target_lang", &self.target_lang),
+      ("mode", &self.mode),
+      ("provider", &self.provider),
+      ("origin_app", &self.origin_app),
+    ];
+    for (column, value) in columns {
+      if let Some(v) = value.as_deref().filter(|v| !v.is_empty()) {
+        params.push(Value::Text(v.to_string()));
+        clauses.push(format!("{column} = ?{}", params.len()));
+      }
+    }
+    if let Some(since) = self.since {
+      params.push(Value::Integer(since));
+      clauses.push(format!("created_at >= ?{}", params.len()));
+    }
+    if let Some(until) = self.until {
+      params.push(Value::Integer(until));
+      clauses.push(format!("created_at < ?{}", params.len()));
+    }
+    if let Some(starred) = self.starred {
+      params.push(Value::Integer(starred as i64));
+      clauses.push(format!("starred = ?{}", params.len()));
+    }
+    (clauses.join(" AND "), params)
+  }
+}
+
+/// How much history is kept (`historyRetention`).
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(tag = "keep", rename_all = "lowercase")]
+pub enum HistoryRetention {
+  #[default]
+  All,
+  /// Entries from the last `days` days.
+  Days { days: u32 },
+  /// The newest `entries` entries.
+  Entries { entries: u32 },
+  Nothing,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HistoryPage {
+  pub entries: Vec<HistoryEntry>,
+  /// Entries the filter matches in all.
+  pub total: u64,
+}
+
+/// The history database (managed state); `None` until `init`, or if it couldn't be opened.
+#[derive(Default)]
+pub struct History(Mutex<Option<Connection>>);
+
+impl History {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Option<Connection>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Run `f` on the open database.
+fn with_db<T>(app: &tauri::AppHandle, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+  let state = app.state::<History>();
+  let db = state.lock();
+  let conn = db.as_ref().ok_or_else(|| "history is unavailable".to_string())?;
+  f(conn).map_err(|e| format!("history query failed: {e}"))
+}
+
+/// Open the database at `path`, encrypted with `key` (hex) if given, and migrate it.
+fn open(path: &std::path::Path, key: Option<&str>) -> rusqlite::Result<Connection> {
+  let conn = Connection::open(path)?;
+  if let Some(key) = key {
+    history_cipher::apply_key(&conn, key)?;
+  }
+  conn.pragma_update(None, "journal_mode", "WAL")?;
+  let version: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+  for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(migration)?;
+    tx.pragma_update(None, "user_version", i + 1)?;
+    tx.commit()?;
+  }
+  Ok(conn)
+}
+
+/// Encrypt or decrypt the (closed) database at `path` as `encryptHistory` says, then open it.
+fn connect(app: &tauri::AppHandle, path: &std::path::Path) -> Result<Connection, String> {
+  let key = history_cipher::prepare(app, path)?;
+  open(path, key.as_deref()).map_err(|e| format!("failed to open {HISTORY_FILE}: {e}"))
+}
+
+/// Open (or create) the database in `dir` and start applying the retention policy.
+pub fn init(app: &tauri::AppHandle, dir: &std::path::Path) {
+  match connect(app, &dir.join(HISTORY_FILE)) {
+    Ok(conn) => *app.state::<History>().lock() = Some(conn),
+    Err(e) => crate::events::warn(app, None, "history_unavailable", e),
+  }
+  settings::subscribe(app, "history", &["encryptHistory", "historyRetention"], reload);
+
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    loop {
+      let handle = app.clone();
+      let _ = tauri::async_runtime::spawn_blocking(move || apply_retention(&handle)).await;
+      tokio::time::sleep(RETENTION_INTERVAL).await;
+    }
+  });
+}
+
+/// Apply `encryptHistory` and `historyRetention` changes without a restart.
+fn reload(app: &tauri::AppHandle, change: &settings::ConfigChange) -> Result<(), String> {
+  if change.changed_keys().iter().any(|k| k == "encryptHistory") {
+    let dir = app.path().app_data_dir().map_err(|e| format!("no app data dir: {e}"))?;
+    let state = app.state::<History>();
+    let connected = {
+      let mut db = state.lock();
+      // Closed first: the file is rewritten in place.
+      *db = None;
+      connect(app, &dir.join(HISTORY_FILE)).map(|conn| *db = Some(conn))
+    };
+    // History stays off until it can be opened, reported as at startup.
+    if let Err(e) = connected {
+      crate::events::warn(app, None, "history_unavailable", e.clone());
+      return Err(e);
+    }
+  }
+  apply_retention(app);
+  Ok(())
+}
+
+/// Delete the unstarred entries `historyRetention` doesn't keep.
+fn apply_retention(app: &tauri::AppHandle) {
+  let (condition, params) = match settings::history_retention(app) {
+    HistoryRetention::All => return,
+    HistoryRetention::Days { days } => (
+      "starred = 0 AND created_at < ?1",
+      vec![Value::Integer(clock::now_millis() as i64 - i64::from(days.max(1)) * DAY_MS)],
+    ),
+    HistoryRetention::Entries { entries } => (
+      "starred = 0 AND id NOT IN (SELECT id FROM translations WHERE starred = 0 ORDER BY created_at DESC, id DESC \
+       LIMIT ?1)",
+      vec![Value::Integer(i64::from(entries))],
+    ),
+    HistoryRetention::Nothing => ("starred = 0", Vec::new()),
+  };
+  let result = with_db(app, |conn| {
+    conn.execute(
+      &format!("DELETE FROM translations WHERE {condition}"),
+      params_from_iter(params.iter()),
+    )
+  });
+  match result {
+    Ok(0) => {}
+    Ok(n) => log::info!("removed {n} history entries past the retention policy"),
+    Err(e) => log::warn!("failed to apply the history retention policy: {e}"),
+  }
+}
+
+/// Write a consistent, unencrypted copy of the database to `dest`, which must not exist yet (for backups).
+pub fn copy_to(app: &tauri::AppHandle, dest: &std::path::Path) -> Result<(), String> {
+  #[cfg(feature = "encrypted-history")]
+  {
+    with_db(app, |conn| history_cipher::export(conn, dest, None))
+  }
+  #[cfg(not(feature = "encrypted-history"))]
+  {
+    let dest = dest.to_string_lossy().to_string();
+    with_db(app, |conn| conn.execute("VACUUM INTO ?1", [dest]).map(|_| ()))
+  }
+}
+
+/// Replace the database in `dir` with the one at `staged`, which is moved into place once it has opened (and been
+/// migrated) without errors. The current database stays in use if anything fails.
+pub fn replace_with(app: &tauri::AppHandle, dir: &std::path::Path, staged: &std::path::Path) -> Result<(), String> {
+  drop(open(staged, None).map_err(|e| format!("the history in the backup is unreadable: {e}"))?);
+  let path = dir.join(HISTORY_FILE);
+  let state = app.state::<History>();
+  let mut db = state.lock();
+  // Closing checkpoints the WAL, so the old files can go.
+  *db = None;
+  let moved = std::fs::rename(staged, &path).map_err(|e| format!("failed to replace {HISTORY_FILE}: {e}"));
+  if moved.is_ok() {
+    for suffix in ["-wal", "-shm"] {
+      let _ = std::fs::remove_file(dir.join(format!("{HISTORY_FILE}{suffix}")));
+    }
+  }
+  // Encrypted again here if `encryptHistory` is on.
+  match connect(app, &path) {
+    Ok(conn) => *db = Some(conn),
+    Err(e) => crate::events::warn(app, None, "history_unavailable", e),
+  }
+  moved
+}
+
+/// The app text is being translated from: the foreground window's executable, or the one text was last taken
+/// from while one of our windows is in front (Windows).
+pub fn origin_app(app: &tauri::AppHandle) -> Option<String> {
+  #[cfg(windows)]
+  {
+    crate::win_window::foreground()
+      .map(|hwnd| hwnd as isize)
+      .or_else(|| crate::type_back::target(app))
+      .and_then(|hwnd| crate::win_window::process_name(hwnd as _))
+  }
+  #[cfg(not(windows))]
+  {
+    let _ = app;
+    None
+  }
+}
+
+/// Add a finished translation.
+pub fn record(app: &tauri::AppHandle, entry: NewEntry<'_>) {
+  if !settings::save_history(app)
+    || settings::history_retention(app) == HistoryRetention::Nothing
+    || entry.source.trim().is_empty()
+    || entry.translation.trim().is_empty()
+  {
+    return;
+  }
+  let result = with_db(app, |conn| {
+    conn.execute(
+      "INSERT INTO translations (source, translation, source_lang, target_lang, mode, provider, origin_app, created_at)
+       VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+      rusqlite::params![
+        entry.source,
+        entry.translation,
+        entry.source_lang,
+        entry.target_lang,
+        entry.mode,
+        entry.provider,
+        entry.origin_app,
+        clock::now_millis() as i64,
+      ],
+    )
+  });
+  if let Err(e) = result {
+    log::warn!("failed to record translation: {e}");
+  }
+}
+
+fn entry(row: &rusqlite::Row<'_>) -> rusqlite::Result<HistoryEntry> {
+  Ok(HistoryEntry {
+    id: row.get("id")?,
+    source: row.get("source")?,
+    translation: row.get("translation")?,
+    source_lang: row.get("source_lang")?,
+    target_lang: row.get("target_lang")?,
+    mode: row.get("mode")?,
+    provider: row.get("provider")?,
+    origin_app: row.get("origin_app")?,
+    created_at: row.get("created_at")?,
+    starred: row.get("starred")?,
+  })
+}
+
+/// Entries `condition` matches, newest first, from `offset` (at most `limit`, or all).
+fn select(
+  conn: &Connection,
+  condition: &str,
+  mut params: Vec<Value>,
+  limit: Option<u32>,
+  offset: u32,
+) -> rusqlite::Result<Vec<HistoryEntry>> {
+  params.push(Value::Integer(limit.map_or(-1, i64::from)));
+  params.push(Value::Integer(offset as i64));
+  let n = params.len();
+  let mut stmt = conn.prepare(&format!(
+    "SELECT * FROM translations WHERE {condition} ORDER BY created_at DESC, id DESC LIMIT ?{} OFFSET ?{n}",
+    n - 1
+  ))?;
+  let entries = stmt.query_map(params_from_iter(params.iter()), entry)?.collect();
+  entries
+}
+
+/// Every entry `filter` matches, newest first.
+pub fn entries(app: &tauri::AppHandle, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, String> {
+  let (condition, params) = filter.to_sql();
+  with_db(app, |conn| select(conn, &condition, params, None, 0))
+}
+
+/// A page of the entries `filter` matches, newest first: `limit` (default 50, at most 500) from `offset`.
+#[tauri::command]
+pub async fn list_history(
+  app: tauri::AppHandle,
+  filter: Option<HistoryFilter>,
+  offset: Option<u32>,
+  limit: Option<u32>,
+) -> Result<HistoryPage, String> {
+  let (condition, params) = filter.unwrap_or_default().to_sql();
+  with_db(&app, |conn| {
+    let total: u64 = conn.query_row(
+      &format!("SELECT COUNT(*) FROM translations WHERE {condition}"),
+      params_from_iter(params.iter()),
+      |row| row.get(0),
+    )?;
+    let limit = limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE);
+    let entries = select(conn, &condition, params, Some(limit), offset.unwrap_or(0))?;
+    Ok(HistoryPage { entries, total })
+  })
+}
+
+/// Delete entries by id; returns how many were deleted.
+#[tauri::command]
+pub async fn delete_history_entries(app: tauri::AppHandle, ids: Vec<i64>) -> Result<usize, String> {
+  if ids.is_empty() {
+    return Ok(0);
+  }
+  let placeholders = vec!["?"; ids.len()].join(", ");
+  with_db(&app, |conn| {
+    conn.execute(
+      &format!("DELETE FROM translations WHERE id IN ({placeholders})"),
+      params_from_iter(ids.iter()),
+    )
+  })
+}
+
+/// Delete every entry `filter` matches (all but the starred ones without a `starred` filter); returns how many
+/// were deleted.
+#[tauri::command]
+pub async fn clear_history(app: tauri::AppHandle, filter: Option<HistoryFilter>) -> Result<usize, String> {
+  let mut filter = filter.unwrap_or_default();
+  filter.starred.get_or_insert(false);
+  let (condition, params) = filter.to_sql();
+  with_db(&app, |conn| {
+    conn.execute(
+      &format!("DELETE FROM translations WHERE {condition}"),
+      params_from_iter(params.iter()),
+    )
+  })
+}
+
+/// Delete every entry `filter` matches, starred ones included (everything without a filter), and compact the
+/// database so the deleted text is gone from the file; returns how many were deleted.
+#[tauri::command]
+pub async fn purge_history(app: tauri::AppHandle, filter: Option<HistoryFilter>) -> Result<usize, String> {
+  let (condition, params) = filter.unwrap_or_default().to_sql();
+  with_db(&app, |conn| {
+    let deleted = conn.execute(
+      &format!("DELETE FROM translations WHERE {condition}"),
+      params_from_iter(params.iter()),
+    )?;
+    if deleted > 0 {
+      conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")?;
+    }
+    Ok(deleted)
+  })
+}
+
+fn set_starred(app: &tauri::AppHandle, ids: &[i64], starred: bool) -> Result<usize, String> {
+  if ids.is_empty() {
+    return Ok(0);
+  }
+  let placeholders = vec!["?"; ids.len()].join(", ");
+  let params = std::iter::once(Value::Integer(starred as i64)).chain(ids.iter().map(|&id| Value::Integer(id)));
+  with_db(app, |conn| {
+    conn.execute(
+      &format!("UPDATE translations SET starred = ? WHERE id IN ({placeholders})"),
+      params_from_iter(params),
+    )
+  })
+}
+
+/// Star entries by id; returns how many were found.
+#[tauri::command]
+pub async fn star_history_entries(app: tauri::AppHandle, ids: Vec<i64>) -> Result<usize, String> {
+  set_starred(&app, &ids, true)
+}
+
+/// Unstar entries by id; returns how many were found.
+#[tauri::command]
+pub async fn unstar_history_entries(app: tauri::AppHandle, ids: Vec<i64>) -> Result<usize, String> {
+  set_starred(&app, &ids, false)
+}
@@ -0,0 +1,161 @@
+//! At-rest encryption of the history database (`encryptHistory`, default off), so translated contracts or
+//! medical text aren't left readable on disk.
+//!
+//! Builds with the `encrypted-history` feature use SQLCipher: with the setting on, the whole database (and its
+//! WAL) is encrypted with a random 256-bit key kept in the OS keychain as the `historyKey` secret, which the
+//! frontend can neither read nor change. Encryption is refused unless the keychain keeps the key across restarts
+//! (and reads it back), since losing it would lose the history. Turning the setting on or off converts the existing
+//! file (a copy is written and moved into place, so a failed conversion leaves the history as it was). Backups hold
+//! a decrypted copy, since the key never leaves this machine; the settings screen says so. Other builds don't offer
+//! the setting (`history_encryption_available`).
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::settings;
+#[cfg(feature = "encrypted-history")]
+use crate::{events, secrets};
+
+/// First bytes of an unencrypted SQLite database.
+const PLAIN_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// Whether the database at `path` is unencrypted (a missing or empty file counts as one).
+fn is_plain(path: &Path) -> bool {
+  let mut header = [0u8; 16];
+  match std::fs::File::open(path).and_then(|mut f| f.read_exact(&mut header)) {
+    Ok(()) => &header == PLAIN_HEADER,
+    Err(_) => true,
+  }
+}
+
+/// A new random key, as hex.
+#[cfg(feature = "encrypted-history")]
+fn new_key() -> Result<String, String> {
+  let mut bytes = [0u8; 32];
+  getrandom::getrandom(&mut bytes).map_err(|e| format!("no randomness for the history key: {e}"))?;
+  Ok(bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// The SQLCipher key expression for a hex key (`''` leaves a database unencrypted).
+#[cfg(feature = "encrypted-history")]
+fn key_literal(key: Option<&str>) -> String {
+  key.map(|k| format!("x'{k}'")).unwrap_or_default()
+}
+
+/// Key the connection `conn` with `key` (hex); must come before anything reads the database.
+pub(crate) fn apply_key(conn: &rusqlite::Connection, key: &str) -> rusqlite::Result<()> {
+  conn.pragma_update(None, "key", format!("x'{key}'"))
+}
+
+/// Copy the database `conn` has open to `dest` (which must not exist), encrypted with `key` or unencrypted.
+#[cfg(feature = "encrypted-history")]
+pub(crate) fn export(conn: &rusqlite::Connection, dest: &Path, key: Option<&str>) -> rusqlite::Result<()> {
+  let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+  conn.execute(
+    "ATTACH DATABASE ?1 AS exported KEY ?2",
+    [dest.to_string_lossy().to_string(), key_literal(key)],
+  )?;
+  let copied = conn
+    .query_row("SELECT sqlcipher_export('exported')", [], |_| Ok(()))
+    .and_then(|()| conn.pragma_update(Some("exported"), "user_version", version));
+  conn.execute("DETACH DATABASE exported", [])?;
+  copied
+}
+
+/// Rewrite the database at `path` from key `from` to key `to` (`None`: unencrypted).
+#[cfg(feature = "encrypted-history")]
+fn convert(path: &Path, from: Option<&str>, to: Option<&str>) -> Result<(), String> {
+  let staged = path.with_extension("sqlite3.convert");
+  let _ = std::fs::remove_file(&staged);
+  let exported = rusqlite::Connection::open(path).and_then(|conn| {
+    if let Some(key) = from {
+      apply_key(&conn, key)?;
+    }
+    export(&conn, &staged, to)
+  });
+  if let Err(e) = exported {
+    let _ = std::fs::remove_file(&staged);
+    return Err(format!("failed to convert the history: {e}"));
+  }
+  std::fs::rename(&staged, path).map_err(|e| format!("failed to replace the history: {e}"))?;
+  // The old WAL belongs to the replaced file (closing the source connection checkpointed it).
+  for suffix in ["-wal", "-shm"] {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    let _ = std::fs::remove_file(name);
+  }
+  Ok(())
+}
+
+/// The stored key of an encrypted history.
+#[cfg(feature = "encrypted-history")]
+fn stored_key() -> Result<String, String> {
+  secrets::get(secrets::HISTORY_KEY)?.ok_or_else(|| "the history is encrypted, but its key is missing".to_string())
+}
+
+/// Bring the database at `path` (closed) in line with `encryptHistory`; returns the key to open it with. A
+/// conversion that fails is reported and leaves the file as it was, so this only fails when the file can't be
+/// opened at all.
+#[cfg(feature = "encrypted-history")]
+pub(crate) fn prepare(app: &tauri::AppHandle, path: &Path) -> Result<Option<String>, String> {
+  let exists = std::fs::metadata(path).is_ok_and(|m| m.len() > 0);
+  match (settings::encrypt_history(app), is_plain(path)) {
+    (false, true) => Ok(None),
+    (true, false) => stored_key().map(Some),
+    (true, true) => {
+      let key = new_key().and_then(|key| secrets::set_durably(secrets::HISTORY_KEY, &key).map(|()| key));
+      let encrypted = key.and_then(|key| {
+        if exists {
+          convert(path, None, Some(&key))?;
+        }
+        Ok(key)
+      });
+      match encrypted {
+        Ok(key) => Ok(Some(key)),
+        Err(e) => {
+          events::warn(app, None, "history_encryption_failed", format!("the history stays unencrypted: {e}"));
+          Ok(None)
+        }
+      }
+    }
+    (false, false) => {
+      let key = stored_key()?;
+      match convert(path, Some(&key), None) {
+        Ok(()) => {
+          if let Err(e) = secrets::delete(secrets::HISTORY_KEY) {
+            log::warn!("{e}");
+          }
+          Ok(None)
+        }
+        Err(e) => {
+          events::warn(app, None, "history_decryption_failed", format!("the history stays encrypted: {e}"));
+          Ok(Some(key))
+        }
+      }
+    }
+  }
+}
+
+/// Whether this build can encrypt the history (the settings screen hides `encryptHistory` otherwise).
+#[tauri::command]
+pub fn history_encryption_available() -> bool {
+  cfg!(feature = "encrypted-history")
+}
+
+/// Without SQLCipher the history is always unencrypted.
+#[cfg(not(feature = "encrypted-history"))]
+pub(crate) fn prepare(app: &tauri::AppHandle, path: &Path) -> Result<Option<String>, String> {
+  if settings::encrypt_history(app) {
+    crate::events::warn(
+      app,
+      None,
+      "history_encryption_unavailable",
+      "this build can't encrypt the history (no `encrypted-history` feature); it stays unencrypted",
+    );
+  }
+  if is_plain(path) {
+    Ok(None)
+  } else {
+    Err("the history is encrypted, which this build can't read".to_string())
+  }
+}
@@ -0,0 +1,122 @@
+//! Exporting the history (see `history`) to a file, so a day's lookups can be reviewed elsewhere or turned into
+//! flashcards.
+//!
+//! `csv` has one row per entry with all fields (UTF-8 with a BOM, so spreadsheet apps pick the encoding up; cells
+//! that would start a formula get a leading `'`); `json` is the entries as `list_history` returns them; `anki` is a
+//! tab-separated file Anki imports as notes, with the source on the front, the translation on the back and the
+//! target language as a tag. Entries are written oldest first.
+
+use serde::Deserialize;
+
+use crate::history::{self, HistoryEntry, HistoryFilter};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+  Csv,
+  Json,
+  Anki,
+}
+
+/// Local time of `ms` since the epoch, RFC 3339.
+fn timestamp(ms: i64) -> String {
+  chrono::DateTime::from_timestamp_millis(ms)
+    .map(|t| t.with_timezone(&chrono::Local).to_rfc3339())
+    .unwrap_or_default()
+}
+
+/// `s` as a CSV cell, quoted when needed. Text a spreadsheet would run as a formula (`=`, `+`, `-`, `@`) is
+/// prefixed with `'`, so an exported translation can't inject one.
+pub(crate) fn csv_field(s: &str) -> String {
+  let s = if s.starts_with(['=', '+', '-', '@']) { format!("'{s}") } else { s.to_string() };
+  if s.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", s.replace('"', "\"\""))
+  } else {
+    s
+  }
+}
+
+/// A cell read back from a CSV we wrote, without the `'` `csv_field` put before a formula.
+pub(crate) fn csv_unguard(s: String) -> String {
+  match s.strip_prefix('\'') {
+    Some(rest) if rest.starts_with(['=', '+', '-', '@']) => rest.to_string(),
+    _ => s,
+  }
+}
+
+fn csv(entries: &[HistoryEntry]) -> String {
+  let mut out = String::from("\u{feff}");
+  out.push_str("id,created_at,source_lang,target_lang,mode,provider,origin_app,starred,source,translation\r\n");
+  for e in entries {
+    let fields = [
+      e.id.to_string(),
+      timestamp(e.created_at),
+      e.source_lang.clone().unwrap_or_default(),
+      e.target_lang.clone(),
+      e.mode.clone(),
+      e.provider.clone(),
+      e.origin_app.clone().unwrap_or_default(),
+      e.starred.to_string(),
+      e.source.clone(),
+      e.translation.clone(),
+    ];
+    let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    out.push_str(&row.join(","));
+    out.push_str("\r\n");
+  }
+  out
+}
+
+/// `s` as an Anki field: HTML-escaped, line breaks as `<br>`, no tabs.
+fn anki_field(s: &str) -> String {
+  s.trim()
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace("\r\n", "\n")
+    .replace(['\n', '\r'], "<br>")
+    .replace('\t', " ")
+}
+
+/// Anki tag for a language name ("English (US)" -> "English_US").
+fn anki_tag(lang: &str) -> String {
+  lang
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<_>>()
+    .join("_")
+}
+
+fn anki(entries: &[HistoryEntry]) -> String {
+  let mut out = String::from("#separator:tab\n#html:true\n#tags column:3\n");
+  for e in entries {
+    out.push_str(&format!(
+      "{}\t{}\t{}\n",
+      anki_field(&e.source),
+      anki_field(&e.translation),
+      anki_tag(&e.target_lang)
+    ));
+  }
+  out
+}
+
+/// Write the entries `filter` matches (all without one) to `path` as `format`; returns how many were written.
+#[tauri::command]
+pub async fn export_history(
+  app: tauri::AppHandle,
+  format: ExportFormat,
+  filter: Option<HistoryFilter>,
+  path: String,
+) -> Result<usize, String> {
+  let mut entries = history::entries(&app, &filter.unwrap_or_default())?;
+  entries.reverse();
+  let out = match format {
+    ExportFormat::Csv => csv(&entries),
+    ExportFormat::Json => {
+      serde_json::to_string_pretty(&entries).map_err(|e| format!("failed to serialize history: {e}"))?
+    }
+    ExportFormat::Anki => anki(&entries),
+  };
+  std::fs::write(&path, out).map_err(|e| format!("cannot write {path}: {e}"))?;
+  Ok(entries.len())
+}
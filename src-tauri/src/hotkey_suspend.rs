@@ -0,0 +1,81 @@
+//! Suspending the hotkeys while a fullscreen game or an excluded app is in front, so they don't steal its keys.
+//!
+//! The foreground window is checked twice a second. While it covers its whole monitor (`suspendHotkeysInFullscreen`,
+//! default off) or belongs to an executable in `hotkeySuspendApps` (`"game.exe"` or `"game"`) or one whose app
+//! profile turns the hotkeys off (`hotkeys: false`, see `app_profiles`), the hotkeys are unregistered and the
+//! double-copy and mouse triggers ignored; they come back as soon as another window is in front. The hotkey status
+//! names what suspended them (`suspended_by`). Windows only; elsewhere nothing is suspended.
+
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::{app_profiles, hotkeys, settings};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Executable of the foreground window that suspended the hotkeys, if any (managed state).
+#[derive(Default)]
+pub struct HotkeySuspend(Mutex<Option<String>>);
+
+impl HotkeySuspend {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Option<String>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// What suspended the hotkeys: the foreground app's executable.
+pub fn suspended_by(app: &tauri::AppHandle) -> Option<String> {
+  app.state::<HotkeySuspend>().lock().clone()
+}
+
+#[cfg(windows)]
+fn foreground_reason(fullscreen: bool, apps: &[String]) -> Option<String> {
+  use crate::app_profiles::stem;
+  use crate::win_window;
+
+  let hwnd = win_window::foreground()?;
+  let name = win_window::process_name(hwnd);
+  if let Some(name) = &name {
+    if apps.iter().any(|app| stem(app) == stem(name)) {
+      return Some(name.clone());
+    }
+  }
+  (fullscreen && win_window::is_fullscreen(hwnd)).then(|| name.unwrap_or_else(|| "a fullscreen app".to_string()))
+}
+
+#[cfg(not(windows))]
+fn foreground_reason(_fullscreen: bool, _apps: &[String]) -> Option<String> {
+  None
+}
+
+fn check(app: &tauri::AppHandle) {
+  let fullscreen = settings::suspend_hotkeys_in_fullscreen(app);
+  let mut apps = settings::hotkey_suspend_apps(app);
+  apps.extend(app_profiles::hotkeys_disabled(app));
+  let reason = if fullscreen || !apps.is_empty() { foreground_reason(fullscreen, &apps) } else { None };
+  {
+    let state = app.state::<HotkeySuspend>();
+    let mut current = state.lock();
+    if *current == reason {
+      return;
+    }
+    *current = reason.clone();
+  }
+  match &reason {
+    Some(name) => log::info!("hotkeys suspended while {name} is in front"),
+    None => log::info!("hotkeys resumed"),
+  }
+  hotkeys::register(app);
+}
+
+/// Start watching the foreground window (`setup`).
+pub fn init(app: &tauri::AppHandle) {
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(POLL_INTERVAL).await;
+      check(&app);
+    }
+  });
+}
@@ -0,0 +1,363 @@
+//! The global hotkeys: a registry of actions, each bound to the accelerator in its setting.
+//!
+//! The backend owns the hotkeys whether or not the main window exists, so they work the same in background agent
+//! mode and keep working while the window reloads. Actions that need the frontend (translate the selection or the
+//! clipboard, OCR a region, repeat the last region, reopen the popup) are handed to the main window through
+//! `agent`; the others run here.
+//!
+//! A hotkey that isn't registered is reported, never dropped silently: an accelerator that doesn't parse, one
+//! already bound to an earlier action in the list, or one the OS refuses (usually because another app holds it).
+//! `get_hotkey_status` returns the outcome per action, `erudaite://hotkeys/status` sends it on every
+//! (re-)registration, failures are published as `hotkey_unavailable` warnings, and a settings save that leaves a
+//! hotkey dead fails the `hotkeys` reload in `config.reloaded`. Paused from the tray, or suspended for the app in
+//! front (`hotkey_suspend`), no hotkey is registered.
+//!
+//! Some games and full-screen apps swallow keys before the OS hotkey sees them. On Windows, `hotkeyBackend` can
+//! catch hotkeys with the low-level keyboard hook (`input_hook`) instead: `"auto"` for those the OS refuses,
+//! `"hook"` for all of them. The default, `"register"`, and every other platform use only OS registration.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+use crate::{agent, events, input_hook, popup, settings};
+
+/// Sent to every window with the new `HotkeyReport` whenever the hotkeys are registered again.
+const STATUS_EVENT: &str = "erudaite://hotkeys/status";
+const DEFAULT_HOTKEY: &str = "CommandOrControl+Shift+Alt+Z";
+const DEFAULT_OCR_HOTKEY: &str = "CommandOrControl+Shift+Alt+X";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+  /// Copy the selection and translate it.
+  Translate,
+  /// Translate the clipboard's text as it is, without simulating a copy.
+  TranslateClipboard,
+  /// Open the OCR overlay.
+  Ocr,
+  /// OCR the last selected region again.
+  RepeatRegion,
+  /// Hide the popup, or reopen it with the last translation.
+  TogglePopup,
+  /// Toggle whether the popup lets clicks through.
+  ClickThrough,
+}
+
+impl Action {
+  /// In priority order: when two actions share keys, the first keeps them.
+  pub(crate) const ALL: [Action; 6] = [
+    Action::Translate,
+    Action::TranslateClipboard,
+    Action::Ocr,
+    Action::RepeatRegion,
+    Action::TogglePopup,
+    Action::ClickThrough,
+  ];
+
+  /// The setting holding the accelerator.
+  pub(crate) fn setting(self) -> &'static str {
+    match self {
+      Action::Translate => "hotkey",
+      Action::TranslateClipboard => "clipboardHotkey",
+      Action::Ocr => "ocrHotkey",
+      Action::RepeatRegion => "repeatRegionHotkey",
+      Action::TogglePopup => "togglePopupHotkey",
+      Action::ClickThrough => "clickThroughHotkey",
+    }
+  }
+
+  /// Accelerator used while the setting is unset; optional actions have none.
+  fn default_accelerator(self) -> Option<&'static str> {
+    match self {
+      Action::Translate => Some(DEFAULT_HOTKEY),
+      Action::Ocr => Some(DEFAULT_OCR_HOTKEY),
+      _ => None,
+    }
+  }
+
+  fn name(self) -> &'static str {
+    match self {
+      Action::Translate => "translate",
+      Action::TranslateClipboard => "translate clipboard",
+      Action::Ocr => "OCR",
+      Action::RepeatRegion => "repeat region",
+      Action::TogglePopup => "toggle popup",
+      Action::ClickThrough => "click-through",
+    }
+  }
+}
+
+/// Setting keys the registry depends on.
+const SETTING_KEYS: [&str; 7] = [
+  "hotkeyBackend",
+  "hotkey",
+  "clipboardHotkey",
+  "ocrHotkey",
+  "repeatRegionHotkey",
+  "togglePopupHotkey",
+  "clickThroughHotkey",
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct HotkeyStatus {
+  pub action: Action,
+  pub accelerator: String,
+  /// Whether pressing it runs the action now (false while paused or suspended).
+  pub registered: bool,
+  /// Caught by the keyboard hook rather than registered with the OS.
+  pub hooked: bool,
+  /// Why it isn't registered; `None` when it is, or when only the pause or suspension holds it back.
+  pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct HotkeyReport {
+  pub paused: bool,
+  /// The foreground app the hotkeys are suspended for, if any.
+  pub suspended_by: Option<String>,
+  /// Bound actions only; optional actions without an accelerator are left out.
+  pub hotkeys: Vec<HotkeyStatus>,
+}
+
+#[derive(Default)]
+pub struct Hotkeys {
+  report: Mutex<HotkeyReport>,
+  /// Hotkeys the keyboard hook catches.
+  hooked: Mutex<Vec<(Shortcut, Action)>>,
+  /// Shortcuts registered with the OS for actions; scripts register their own (see `scripting`), left alone here.
+  registered: Mutex<Vec<Shortcut>>,
+}
+
+impl Hotkeys {
+  fn lock(&self) -> std::sync::MutexGuard<'_, HotkeyReport> {
+    self.report.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn hooked(&self) -> std::sync::MutexGuard<'_, Vec<(Shortcut, Action)>> {
+    self.hooked.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn registered(&self) -> std::sync::MutexGuard<'_, Vec<Shortcut>> {
+    self.registered.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Unregister the shortcuts `register` registered.
+fn unregister_own(app: &tauri::AppHandle) {
+  let shortcuts = app.global_shortcut();
+  for shortcut in std::mem::take(&mut *app.state::<Hotkeys>().registered()) {
+    let _ = shortcuts.unregister(shortcut);
+  }
+}
+
+/// How hotkeys are caught (`hotkeyBackend`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+  Register,
+  /// Registered, and hooked when the OS refuses.
+  Auto,
+  Hook,
+}
+
+fn backend(app: &tauri::AppHandle) -> Backend {
+  if !cfg!(windows) {
+    return Backend::Register;
+  }
+  match settings::hotkey_backend(app).as_str() {
+    "auto" => Backend::Auto,
+    "hook" => Backend::Hook,
+    _ => Backend::Register,
+  }
+}
+
+/// Whether any hotkey needs the keyboard hook.
+pub fn hooked(app: &tauri::AppHandle) -> bool {
+  !app.state::<Hotkeys>().hooked().is_empty()
+}
+
+/// A key was pressed (`input_hook`, on its thread); runs the hooked hotkey it completes, and claims the press.
+pub fn on_key(app: &tauri::AppHandle, code: Code, mods: Modifiers) -> bool {
+  let action = app
+    .state::<Hotkeys>()
+    .hooked()
+    .iter()
+    .find(|(shortcut, _)| shortcut.matches(mods, code))
+    .map(|(_, action)| *action);
+  let Some(action) = action.filter(|_| !held(app)) else {
+    return false;
+  };
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move { run(&app, action) });
+  true
+}
+
+pub(crate) fn run(app: &tauri::AppHandle, action: Action) {
+  match action {
+    Action::Translate => agent::run_action(app, "translate"),
+    Action::TranslateClipboard => agent::run_action(app, "translate_clipboard"),
+    Action::Ocr => agent::run_action(app, "ocr"),
+    Action::RepeatRegion => agent::run_action(app, "repeat_region"),
+    Action::TogglePopup => {
+      let open = app
+        .get_webview_window(popup::POPUP_LABEL)
+        .filter(|w| w.is_visible().unwrap_or(false));
+      let Some(window) = open else {
+        agent::run_action(app, "show_popup");
+        return;
+      };
+      let duration = std::time::Duration::from_millis(settings::popup_fade_ms(app));
+      tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::fade::fade_out(&window, duration, true).await {
+          log::warn!("{e}");
+          let _ = window.destroy();
+        }
+      });
+    }
+    Action::ClickThrough => {
+      if let Err(e) = popup::set_click_through(app.clone(), app.state(), None, None) {
+        log::warn!("{e}");
+      }
+    }
+  }
+}
+
+/// Whether hotkey actions are held back: paused from the tray, or suspended for the app in front.
+pub fn held(app: &tauri::AppHandle) -> bool {
+  crate::tray::hotkeys_paused(app) || crate::hotkey_suspend::suspended_by(app).is_some()
+}
+
+/// Register every bound action's accelerator (none while paused or suspended), replacing what was registered
+/// for actions, and report the outcome.
+pub fn register(app: &tauri::AppHandle) -> HotkeyReport {
+  unregister_own(app);
+  let shortcuts = app.global_shortcut();
+  let paused = crate::tray::hotkeys_paused(app);
+  let suspended_by = crate::hotkey_suspend::suspended_by(app);
+  let held = paused || suspended_by.is_some();
+  let backend = backend(app);
+  let mut taken: Vec<(Shortcut, Action)> = Vec::new();
+  let mut hooked: Vec<(Shortcut, Action)> = Vec::new();
+  let mut hotkeys = Vec::new();
+  for action in Action::ALL {
+    let accelerator =
+      settings::hotkey(app, action.setting()).or_else(|| action.default_accelerator().map(str::to_string));
+    let Some(accelerator) = accelerator else {
+      continue;
+    };
+    let error = match accelerator.parse::<Shortcut>() {
+      Err(e) => Some(format!("{accelerator} is not a valid hotkey: {e}")),
+      Ok(shortcut) => match taken.iter().find(|(t, _)| *t == shortcut) {
+        Some((_, other)) => Some(format!("{accelerator} is already the {} hotkey", other.name())),
+        None => {
+          taken.push((shortcut, action));
+          if held {
+            None
+          } else if backend == Backend::Hook {
+            hooked.push((shortcut, action));
+            None
+          } else {
+            let refused = shortcuts
+              .on_shortcut(shortcut, move |app, _, event| {
+                if event.state == ShortcutState::Pressed {
+                  run(app, action);
+                }
+              })
+              .err();
+            if refused.is_none() {
+              app.state::<Hotkeys>().registered().push(shortcut);
+            }
+            match refused {
+              Some(e) if backend == Backend::Auto => {
+                log::info!("{accelerator} could not be registered ({e}); catching it with the keyboard hook");
+                hooked.push((shortcut, action));
+                None
+              }
+              refused => refused
+                .map(|e| format!("{accelerator} could not be registered (another app may be using it): {e}")),
+            }
+          }
+        }
+      },
+    };
+    if let Some(e) = &error {
+      events::warn(app, None, "hotkey_unavailable", format!("{} hotkey: {e}", action.name()));
+    }
+    hotkeys.push(HotkeyStatus {
+      action,
+      registered: !held && error.is_none(),
+      hooked: hooked.iter().any(|(_, a)| *a == action),
+      accelerator,
+      error,
+    });
+  }
+  let hooking = !hooked.is_empty();
+  *app.state::<Hotkeys>().hooked() = hooked;
+  if let Err(e) = input_hook::sync(app) {
+    if hooking {
+      app.state::<Hotkeys>().hooked().clear();
+      for hotkey in hotkeys.iter_mut().filter(|h| h.hooked) {
+        let error = format!("{} could not be caught: {e}", hotkey.accelerator);
+        events::warn(app, None, "hotkey_unavailable", format!("{} hotkey: {error}", hotkey.action.name()));
+        hotkey.registered = false;
+        hotkey.hooked = false;
+        hotkey.error = Some(error);
+      }
+    } else {
+      log::warn!("{e}");
+    }
+  }
+  let report = HotkeyReport {
+    paused,
+    suspended_by,
+    hotkeys,
+  };
+  *app.state::<Hotkeys>().lock() = report.clone();
+  let _ = app.emit(STATUS_EVENT, &report);
+  report
+}
+
+/// Unregister every action's hotkey (paused from the tray).
+pub fn unregister(app: &tauri::AppHandle) {
+  unregister_own(app);
+  let state = app.state::<Hotkeys>();
+  state.hooked().clear();
+  if let Err(e) = input_hook::sync(app) {
+    log::warn!("{e}");
+  }
+  let mut report = state.lock();
+  report.paused = true;
+  for hotkey in &mut report.hotkeys {
+    hotkey.registered = false;
+    hotkey.hooked = false;
+  }
+  let _ = app.emit(STATUS_EVENT, &*report);
+}
+
+fn reload(app: &tauri::AppHandle, _: &settings::ConfigChange) -> Result<(), String> {
+  let failed: Vec<String> = register(app).hotkeys.into_iter().filter_map(|h| h.error).collect();
+  if failed.is_empty() {
+    Ok(())
+  } else {
+    Err(failed.join("; "))
+  }
+}
+
+/// Register the hotkeys and follow their settings (`setup`).
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "hotkeys", &SETTING_KEYS, reload);
+  register(app);
+}
+
+/// Which hotkeys are registered, and why the others aren't.
+#[tauri::command]
+pub fn get_hotkey_status(state: tauri::State<'_, Hotkeys>) -> HotkeyReport {
+  state.lock().clone()
+}
+
+/// Translate the clipboard's text, as its hotkey does (for the tray, scripts, or a button).
+#[tauri::command]
+pub fn translate_clipboard(app: tauri::AppHandle) {
+  run(&app, Action::TranslateClipboard);
+}
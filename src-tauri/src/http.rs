@@ -0,0 +1,296 @@
+//! HTTP client factory and request signing for backend calls.
+//!
+//! Deployments behind a gateway that requires HMAC-signed requests configure `requestSigning` in settings:
+//! a map from a base URL prefix to a `SigningConfig`. The key itself lives in the OS keychain (set with
+//! `set_signing_key`), never in the settings file. Each signed request carries a timestamp, a nonce and
+//! the signature over
+//!
+//! ```text
+//! METHOD \n path?query \n timestamp \n nonce \n hex(sha256(body))
+//! ```
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256, Sha512};
+use std::sync::OnceLock;
+
+use crate::clock;
+use crate::settings;
+
+const KEYRING_SERVICE: &str = "erudaite";
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Algorithm {
+  #[default]
+  HmacSha256,
+  HmacSha512,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+  #[default]
+  Hex,
+  Base64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SigningConfig {
+  pub algorithm: Algorithm,
+  pub encoding: Encoding,
+  pub signature_header: String,
+  /// Prepended to the signature value (e.g. `"HMAC-SHA256 "`).
+  pub signature_prefix: String,
+  pub timestamp_header: String,
+  pub nonce_header: String,
+  /// Sent in `key_id_header` so the gateway can pick the key (omitted when empty).
+  pub key_id: String,
+  pub key_id_header: String,
+}
+
+impl Default for SigningConfig {
+  fn default() -> Self {
+    SigningConfig {
+      algorithm: Algorithm::default(),
+      encoding: Encoding::default(),
+      signature_header: "X-Signature".to_string(),
+      signature_prefix: String::new(),
+      timestamp_header: "X-Timestamp".to_string(),
+      nonce_header: "X-Nonce".to_string(),
+      key_id: String::new(),
+      key_id_header: "X-Key-Id".to_string(),
+    }
+  }
+}
+
+/// Shared client, so backend calls reuse connections.
+pub fn client() -> reqwest::Client {
+  static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+  CLIENT.get_or_init(reqwest::Client::new).clone()
+}
+
+/// The signing config whose URL prefix covers `url` (same origin, within its path; longest prefix wins).
+fn signing_config(app: &tauri::AppHandle, url: &str) -> Option<(String, SigningConfig)> {
+  let all = settings::request_signing(app);
+  all
+    .into_iter()
+    .filter(|(prefix, _)| url_within(url, prefix))
+    .max_by_key(|(prefix, _)| prefix.len())
+}
+
+fn keyring_entry(prefix: &str) -> Result<keyring::Entry, String> {
+  keyring::Entry::new(KEYRING_SERVICE, &format!("signing:{}", prefix.trim_end_matches('/')))
+    .map_err(|e| format!("keychain unavailable: {e}"))
+}
+
+fn nonce() -> String {
+  if cfg!(feature = "deterministic") {
+    return clock::next_id("nonce");
+  }
+  let mut bytes = [0u8; 16];
+  if getrandom::getrandom(&mut bytes).is_err() {
+    return clock::next_id("nonce");
+  }
+  hex(&bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn mac(algorithm: Algorithm, key: &[u8], message: &[u8]) -> Vec<u8> {
+  match algorithm {
+    Algorithm::HmacSha256 => {
+      let mut mac = <Hmac<Sha256>>::new_from_slice(key).expect("HMAC accepts any key length");
+      mac.update(message);
+      mac.finalize().into_bytes().to_vec()
+    }
+    Algorithm::HmacSha512 => {
+      let mut mac = <Hmac<Sha512>>::new_from_slice(key).expect("HMAC accepts any key length");
+      mac.update(message);
+      mac.finalize().into_bytes().to_vec()
+    }
+  }
+}
+
+/// Headers to add for a request, or none when no signing is configured for `url`.
+fn signature_headers(
+  app: &tauri::AppHandle,
+  method: &str,
+  url: &str,
+  body: &[u8],
+) -> Result<Vec<(String, String)>, String> {
+  let Some((prefix, config)) = signing_config(app, url) else {
+    return Ok(Vec::new());
+  };
+  let key = keyring_entry(&prefix)?
+    .get_password()
+    .map_err(|e| format!("SIGNING_KEY_MISSING: no signing key for {prefix} ({e})"))?;
+
+  let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid url: {e}"))?;
+  let path = match parsed.query() {
+    Some(q) => format!("{}?{q}", parsed.path()),
+    None => parsed.path().to_string(),
+  };
+  let timestamp = (clock::now_millis() / 1000).to_string();
+  let nonce = nonce();
+  let canonical = format!(
+    "{}\n{path}\n{timestamp}\n{nonce}\n{}",
+    method.to_ascii_uppercase(),
+    hex(&Sha256::digest(body))
+  );
+  let raw = mac(config.algorithm, key.as_bytes(), canonical.as_bytes());
+  let signature = match config.encoding {
+    Encoding::Hex => hex(&raw),
+    Encoding::Base64 => base64::engine::general_purpose::STANDARD.encode(raw),
+  };
+
+  let mut headers = vec![
+    (config.signature_header, format!("{}{signature}", config.signature_prefix)),
+    (config.timestamp_header, timestamp),
+    (config.nonce_header, nonce),
+  ];
+  if !config.key_id.is_empty() {
+    headers.push((config.key_id_header, config.key_id));
+  }
+  Ok(headers)
+}
+
+/// Whether `url` is `base` or below it: the same origin (scheme, host and port) and a path that is `base`'s or
+/// continues it at a `/`, so `https://api.example.com.evil` or `https://api.example.com/v1x` don't match
+/// `https://api.example.com/v1`.
+pub(crate) fn url_within(url: &str, base: &str) -> bool {
+  let (Ok(url), Ok(base)) = (reqwest::Url::parse(url.trim()), reqwest::Url::parse(base.trim())) else {
+    return false;
+  };
+  if url.origin() != base.origin() {
+    return false;
+  }
+  let prefix = base.path().trim_end_matches('/');
+  let path = url.path();
+  path == prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The translation server's API key (see `secrets`), when `url` is on the server.
+fn api_key(app: &tauri::AppHandle, url: &str) -> Option<String> {
+  let base = settings::api_base_url(app).ok()?;
+  if !url_within(url, &crate::commands::normalize_base_url(&base)) {
+    return None;
+  }
+  crate::secrets::get(crate::secrets::API_KEY).unwrap_or_else(|e| {
+    log::warn!("API key unavailable: {e}");
+    None
+  })
+}
+
+/// A JSON POST to the backend, signed when `requestSigning` covers `url` and authorized with the stored API key.
+pub fn post_json(
+  app: &tauri::AppHandle,
+  client: &reqwest::Client,
+  url: &str,
+  body: &serde_json::Value,
+) -> Result<reqwest::RequestBuilder, String> {
+  let bytes = serde_json::to_vec(body).map_err(|e| format!("invalid body: {e}"))?;
+  let mut request = client.post(url).header("Content-Type", "application/json");
+  for (name, value) in signature_headers(app, "POST", url, &bytes)? {
+    request = request.header(name, value);
+  }
+  if let Some(key) = api_key(app, url) {
+    request = request.bearer_auth(key);
+  }
+  Ok(request.body(bytes))
+}
+
+/// Report malformed `requestSigning` entries when settings change, instead of requests silently going out
+/// unsigned.
+fn validate_signing(app: &tauri::AppHandle, _: &settings::ConfigChange) -> Result<(), String> {
+  let all = settings::load(app);
+  let Some(map) = all.get("requestSigning").and_then(|v| v.as_object()) else {
+    return Ok(());
+  };
+  let invalid: Vec<&str> = map
+    .iter()
+    .filter(|(_, v)| serde_json::from_value::<SigningConfig>((*v).clone()).is_err())
+    .map(|(prefix, _)| prefix.as_str())
+    .collect();
+  if invalid.is_empty() {
+    Ok(())
+  } else {
+    Err(format!("invalid requestSigning entries: {}", invalid.join(", ")))
+  }
+}
+
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "providers", &["requestSigning"], validate_signing);
+}
+
+/// Store the signing key for a base URL in the OS keychain.
+#[tauri::command]
+pub fn set_signing_key(base_url: String, key: String) -> Result<(), String> {
+  let key = key.trim();
+  if key.is_empty() {
+    return Err("key is empty".to_string());
+  }
+  keyring_entry(&base_url)?
+    .set_password(key)
+    .map_err(|e| format!("failed to store signing key: {e}"))
+}
+
+#[tauri::command]
+pub fn delete_signing_key(base_url: String) -> Result<(), String> {
+  match keyring_entry(&base_url)?.delete_credential() {
+    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+    Err(e) => Err(format!("failed to delete signing key: {e}")),
+  }
+}
+
+/// Whether a signing key is stored for a base URL (the key itself is never returned).
+#[tauri::command]
+pub fn has_signing_key(base_url: String) -> Result<bool, String> {
+  match keyring_entry(&base_url)?.get_password() {
+    Ok(_) => Ok(true),
+    Err(keyring::Error::NoEntry) => Ok(false),
+    Err(e) => Err(format!("keychain error: {e}")),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn url_within_needs_the_same_origin() {
+    let base = "https://api.example.com";
+    assert!(url_within("https://api.example.com/translate", base));
+    assert!(url_within("https://api.example.com", base));
+    assert!(!url_within("https://api.example.com.evil/translate", base));
+    assert!(!url_within("http://api.example.com/translate", base));
+    assert!(!url_within("https://api.example.com:8443/translate", base));
+    assert!(url_within("https://api.example.com:443/translate", base));
+  }
+
+  #[test]
+  fn url_within_matches_whole_path_segments() {
+    let base = "https://api.example.com/v1/";
+    assert!(url_within("https://api.example.com/v1", base));
+    assert!(url_within("https://api.example.com/v1/translate?x=1", base));
+    assert!(!url_within("https://api.example.com/v1x/translate", base));
+    assert!(!url_within("https://api.example.com/v2", base));
+  }
+
+  #[test]
+  fn url_within_rejects_what_isnt_a_url() {
+    assert!(!url_within("api.example.com/translate", "https://api.example.com"));
+    assert!(!url_within("https://api.example.com/translate", ""));
+  }
+
+  #[test]
+  fn mac_is_hmac() {
+    // RFC 4231, test case 2.
+    let mac = mac(Algorithm::HmacSha256, b"Jefe", b"what do ya want for nothing?");
+    assert_eq!(hex(&mac), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+  }
+}
@@ -0,0 +1,42 @@
+//! Reading and writing the image files captures, snapshots and OCR pass around, through the `image` crate.
+//!
+//! Captures are PNGs; reading sniffs the format from the contents, so the other formats `save_capture` accepts
+//! decode too. Pixels come out as 8-bit RGB(A) whatever the file holds (gray is repeated, 16-bit is narrowed).
+//! Captures that are streamed row by row as they are taken go through `capture_output` instead.
+
+use std::path::Path;
+
+fn open(path: &Path) -> Result<image::DynamicImage, String> {
+  image::ImageReader::open(path)
+    .and_then(|reader| reader.with_guessed_format())
+    .map_err(|e| format!("cannot open {}: {e}", path.display()))?
+    .decode()
+    .map_err(|e| format!("cannot read {}: {e}", path.display()))
+}
+
+/// The image at `path` as 8-bit RGB.
+pub fn read_rgb(path: impl AsRef<Path>) -> Result<image::RgbImage, String> {
+  open(path.as_ref()).map(image::DynamicImage::into_rgb8)
+}
+
+/// The image at `path` as 8-bit RGBA.
+pub fn read_rgba(path: impl AsRef<Path>) -> Result<image::RgbaImage, String> {
+  open(path.as_ref()).map(image::DynamicImage::into_rgba8)
+}
+
+/// Width and height of the image at `path`, from its header.
+pub fn size(path: impl AsRef<Path>) -> Result<(u32, u32), String> {
+  let path = path.as_ref();
+  image::image_dimensions(path).map_err(|e| format!("cannot read {}: {e}", path.display()))
+}
+
+/// The `width` x `height` area at `x`, `y` of `image` as RGB rows; callers check that `image` holds it.
+pub fn crop_rgb(image: &image::RgbImage, x: u32, y: u32, width: u32, height: u32) -> Vec<u8> {
+  image::imageops::crop_imm(image, x, y, width, height).to_image().into_raw()
+}
+
+/// Write `width` x `height` RGB pixels to `path` as a PNG.
+pub fn write_png(path: &Path, rgb: &[u8], width: u32, height: u32) -> Result<(), String> {
+  image::save_buffer_with_format(path, rgb, width, height, image::ExtendedColorType::Rgb8, image::ImageFormat::Png)
+    .map_err(|e| format!("png write failed: {e}"))
+}
@@ -0,0 +1,409 @@
+//! Low-level input hooks: system-wide key presses and mouse buttons, seen before the focused app handles them.
+//!
+//! Windows installs `WH_KEYBOARD_LL` and `WH_MOUSE_LL` hooks on a thread of their own that pumps messages. Only the
+//! hooks something needs are installed, and they're removed again when nothing does, since every keystroke or
+//! click on the desktop passes through them. macOS taps the session's key events (listen-only `CGEventTap`, which
+//! needs the Accessibility permission the selection copy already asks for); a tap can't be taken down from
+//! another thread, so once created it stays and simply stops reporting. Mouse hooks are Windows-only, and
+//! elsewhere starting any hook fails.
+//!
+//! A consumer may claim a key press (a hotkey caught by the hook) or a mouse button, and then the focused app
+//! doesn't see it (Windows); the copy shortcut is only listened to. Events the app synthesizes itself (the
+//! simulated copy of `capture_selected_text`) are not reported. Callbacks run on the hook thread and must return
+//! quickly; consumers hand real work to the async runtime.
+
+use serde::Deserialize;
+use std::sync::OnceLock;
+use tauri_plugin_global_shortcut::{Code, Modifiers};
+
+use crate::{double_copy, hotkeys, mouse_triggers};
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MouseButton {
+  Middle,
+  /// The first side button (XBUTTON1, "mouse 4").
+  Back,
+  /// The second side button (XBUTTON2, "mouse 5").
+  Forward,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+  /// Ctrl+C (Cmd+C on macOS) pressed; auto-repeat isn't reported.
+  Copy,
+  /// A key pressed with the modifiers held at the time (Windows); auto-repeat isn't reported.
+  KeyDown(Code, Modifiers),
+  MouseDown(MouseButton),
+  MouseUp(MouseButton),
+}
+
+/// Which hooks are wanted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Hooks {
+  pub keyboard: bool,
+  pub mouse: bool,
+}
+
+static APP: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+/// Hand `event` to its consumer; true when it claims the event (hotkeys and mouse buttons only).
+fn deliver(event: InputEvent) -> bool {
+  let Some(app) = APP.get() else {
+    return false;
+  };
+  match event {
+    InputEvent::Copy => {
+      double_copy::on_copy(app);
+      false
+    }
+    InputEvent::KeyDown(code, mods) => hotkeys::on_key(app, code, mods),
+    InputEvent::MouseDown(button) => mouse_triggers::on_button(app, button, true),
+    InputEvent::MouseUp(button) => mouse_triggers::on_button(app, button, false),
+  }
+}
+
+#[cfg(windows)]
+mod platform {
+  use std::sync::atomic::{AtomicU32, Ordering};
+  use std::sync::Mutex;
+  use tauri_plugin_global_shortcut::{Code, Modifiers};
+  use windows_sys::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+  use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+  use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VK_CONTROL, VK_LWIN,
+    VK_MENU, VK_RWIN, VK_SHIFT,
+  };
+  use windows_sys::Win32::UI::WindowsAndMessaging::{
+    CallNextHookEx, DispatchMessageW, GetMessageW, PeekMessageW, PostThreadMessageW, SetWindowsHookExW,
+    UnhookWindowsHookEx, HHOOK, KBDLLHOOKSTRUCT, LLKHF_INJECTED, LLMHF_INJECTED, MSG, MSLLHOOKSTRUCT, PM_NOREMOVE,
+    WH_KEYBOARD_LL, WH_MOUSE_LL, WM_KEYDOWN, WM_KEYUP, WM_MBUTTONDOWN, WM_MBUTTONUP, WM_QUIT, WM_SYSKEYDOWN,
+    WM_SYSKEYUP, WM_XBUTTONDOWN, WM_XBUTTONUP,
+  };
+
+  use super::{Hooks, InputEvent, MouseButton};
+
+  type HookProc = unsafe extern "system" fn(i32, WPARAM, LPARAM) -> LRESULT;
+
+  const VK_C: u32 = 0x43;
+  /// Unassigned virtual key, pressed to keep a released Alt or Win from opening a menu.
+  const VK_MASK: u16 = 0xE8;
+  const XBUTTON1: u32 = 1;
+  const XBUTTON2: u32 = 2;
+  /// The hook thread and what it installed.
+  static RUNNING: Mutex<Option<(u32, Hooks)>> = Mutex::new(None);
+  /// The key last pressed and not yet released; only it auto-repeats.
+  static LAST_DOWN: AtomicU32 = AtomicU32::new(0);
+  /// A key whose press was claimed; its release is claimed too.
+  static CLAIMED: AtomicU32 = AtomicU32::new(0);
+
+  const LETTERS: [Code; 26] = [
+    Code::KeyA, Code::KeyB, Code::KeyC, Code::KeyD, Code::KeyE, Code::KeyF, Code::KeyG, Code::KeyH, Code::KeyI,
+    Code::KeyJ, Code::KeyK, Code::KeyL, Code::KeyM, Code::KeyN, Code::KeyO, Code::KeyP, Code::KeyQ, Code::KeyR,
+    Code::KeyS, Code::KeyT, Code::KeyU, Code::KeyV, Code::KeyW, Code::KeyX, Code::KeyY, Code::KeyZ,
+  ];
+  const DIGITS: [Code; 10] = [
+    Code::Digit0, Code::Digit1, Code::Digit2, Code::Digit3, Code::Digit4, Code::Digit5, Code::Digit6, Code::Digit7,
+    Code::Digit8, Code::Digit9,
+  ];
+  const NUMPAD: [Code; 10] = [
+    Code::Numpad0, Code::Numpad1, Code::Numpad2, Code::Numpad3, Code::Numpad4, Code::Numpad5, Code::Numpad6,
+    Code::Numpad7, Code::Numpad8, Code::Numpad9,
+  ];
+  const FUNCTION: [Code; 24] = [
+    Code::F1, Code::F2, Code::F3, Code::F4, Code::F5, Code::F6, Code::F7, Code::F8, Code::F9, Code::F10, Code::F11,
+    Code::F12, Code::F13, Code::F14, Code::F15, Code::F16, Code::F17, Code::F18, Code::F19, Code::F20, Code::F21,
+    Code::F22, Code::F23, Code::F24,
+  ];
+
+  /// The key code of virtual key `vk`, for the keys an accelerator can use.
+  fn code(vk: u32) -> Option<Code> {
+    Some(match vk {
+      0x41..=0x5A => LETTERS[(vk - 0x41) as usize],
+      0x30..=0x39 => DIGITS[(vk - 0x30) as usize],
+      0x60..=0x69 => NUMPAD[(vk - 0x60) as usize],
+      0x70..=0x87 => FUNCTION[(vk - 0x70) as usize],
+      0x08 => Code::Backspace,
+      0x09 => Code::Tab,
+      0x0D => Code::Enter,
+      0x13 => Code::Pause,
+      0x1B => Code::Escape,
+      0x20 => Code::Space,
+      0x21 => Code::PageUp,
+      0x22 => Code::PageDown,
+      0x23 => Code::End,
+      0x24 => Code::Home,
+      0x25 => Code::ArrowLeft,
+      0x26 => Code::ArrowUp,
+      0x27 => Code::ArrowRight,
+      0x28 => Code::ArrowDown,
+      0x2C => Code::PrintScreen,
+      0x2D => Code::Insert,
+      0x2E => Code::Delete,
+      0x6A => Code::NumpadMultiply,
+      0x6B => Code::NumpadAdd,
+      0x6D => Code::NumpadSubtract,
+      0x6E => Code::NumpadDecimal,
+      0x6F => Code::NumpadDivide,
+      0xBA => Code::Semicolon,
+      0xBB => Code::Equal,
+      0xBC => Code::Comma,
+      0xBD => Code::Minus,
+      0xBE => Code::Period,
+      0xBF => Code::Slash,
+      0xC0 => Code::Backquote,
+      0xDB => Code::BracketLeft,
+      0xDC => Code::Backslash,
+      0xDD => Code::BracketRight,
+      0xDE => Code::Quote,
+      _ => return None,
+    })
+  }
+
+  fn held(vk: u16) -> bool {
+    unsafe { GetAsyncKeyState(vk as i32) as u16 & 0x8000 != 0 }
+  }
+
+  fn modifiers() -> Modifiers {
+    let mut mods = Modifiers::empty();
+    mods.set(Modifiers::SHIFT, held(VK_SHIFT));
+    mods.set(Modifiers::CONTROL, held(VK_CONTROL));
+    mods.set(Modifiers::ALT, held(VK_MENU));
+    mods.set(Modifiers::SUPER, held(VK_LWIN) || held(VK_RWIN));
+    mods
+  }
+
+  /// Tap the mask key, so releasing Alt or Win after a claimed press doesn't activate a menu bar or Start.
+  fn mask_menu() {
+    let key = |flags| INPUT {
+      r#type: INPUT_KEYBOARD,
+      Anonymous: INPUT_0 {
+        ki: KEYBDINPUT {
+          wVk: VK_MASK,
+          wScan: 0,
+          dwFlags: flags,
+          time: 0,
+          dwExtraInfo: 0,
+        },
+      },
+    };
+    let inputs = [key(0), key(KEYEVENTF_KEYUP)];
+    unsafe { SendInput(inputs.len() as u32, inputs.as_ptr(), std::mem::size_of::<INPUT>() as i32) };
+  }
+
+  unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+      let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+      let vk = info.vkCode;
+      if info.flags & LLKHF_INJECTED == 0 {
+        match wparam as u32 {
+          WM_KEYDOWN | WM_SYSKEYDOWN if LAST_DOWN.swap(vk, Ordering::SeqCst) != vk => {
+            let mods = modifiers();
+            if vk == VK_C && mods.contains(Modifiers::CONTROL) {
+              super::deliver(InputEvent::Copy);
+            }
+            if let Some(key) = self::code(vk) {
+              if super::deliver(InputEvent::KeyDown(key, mods)) {
+                CLAIMED.store(vk, Ordering::SeqCst);
+                if mods.intersects(Modifiers::ALT | Modifiers::SUPER) {
+                  mask_menu();
+                }
+                return 1;
+              }
+            }
+          }
+          WM_KEYDOWN | WM_SYSKEYDOWN if CLAIMED.load(Ordering::SeqCst) == vk => return 1,
+          WM_KEYUP | WM_SYSKEYUP => {
+            let _ = LAST_DOWN.compare_exchange(vk, 0, Ordering::SeqCst, Ordering::SeqCst);
+            if CLAIMED.compare_exchange(vk, 0, Ordering::SeqCst, Ordering::SeqCst).is_ok() {
+              return 1;
+            }
+          }
+          _ => {}
+        }
+      }
+    }
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+  }
+
+  unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+      let info = &*(lparam as *const MSLLHOOKSTRUCT);
+      let side = match info.mouseData >> 16 {
+        XBUTTON1 => Some(MouseButton::Back),
+        XBUTTON2 => Some(MouseButton::Forward),
+        _ => None,
+      };
+      let event = match wparam as u32 {
+        WM_MBUTTONDOWN => Some(InputEvent::MouseDown(MouseButton::Middle)),
+        WM_MBUTTONUP => Some(InputEvent::MouseUp(MouseButton::Middle)),
+        WM_XBUTTONDOWN => side.map(InputEvent::MouseDown),
+        WM_XBUTTONUP => side.map(InputEvent::MouseUp),
+        _ => None,
+      };
+      if let Some(event) = event.filter(|_| info.flags & LLMHF_INJECTED == 0) {
+        if super::deliver(event) {
+          return 1;
+        }
+      }
+    }
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+  }
+
+  unsafe fn unhook(hooks: &[HHOOK]) {
+    for hook in hooks {
+      UnhookWindowsHookEx(*hook);
+    }
+  }
+
+  fn spawn(hooks: Hooks) -> Result<u32, String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+      .name("input-hook".to_string())
+      .spawn(move || unsafe {
+        let mut installed = Vec::new();
+        let wanted: [(bool, _, HookProc, &str); 2] = [
+          (hooks.keyboard, WH_KEYBOARD_LL, keyboard_proc, "keyboard"),
+          (hooks.mouse, WH_MOUSE_LL, mouse_proc, "mouse"),
+        ];
+        for (want, id, proc, name) in wanted {
+          if !want {
+            continue;
+          }
+          let hook = SetWindowsHookExW(id, Some(proc), std::ptr::null_mut(), 0);
+          if hook.is_null() {
+            let e = std::io::Error::last_os_error();
+            unhook(&installed);
+            let _ = tx.send(Err(format!("cannot install the {name} hook: {e}")));
+            return;
+          }
+          installed.push(hook);
+        }
+        // Create the message queue before anyone can post the quit message to it.
+        let mut msg: MSG = std::mem::zeroed();
+        PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_NOREMOVE);
+        let _ = tx.send(Ok(GetCurrentThreadId()));
+        while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {
+          DispatchMessageW(&msg);
+        }
+        unhook(&installed);
+      })
+      .map_err(|e| format!("cannot start the input hook: {e}"))?;
+    rx.recv().unwrap_or_else(|_| Err("the input hook thread exited".to_string()))
+  }
+
+  pub fn apply(hooks: Hooks) -> Result<(), String> {
+    let mut running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    if running.map(|(_, h)| h) == Some(hooks) {
+      return Ok(());
+    }
+    if let Some((thread, _)) = running.take() {
+      unsafe { PostThreadMessageW(thread, WM_QUIT, 0, 0) };
+      LAST_DOWN.store(0, Ordering::SeqCst);
+      CLAIMED.store(0, Ordering::SeqCst);
+    }
+    if hooks == Hooks::default() {
+      return Ok(());
+    }
+    *running = Some((spawn(hooks)?, hooks));
+    Ok(())
+  }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  use core_foundation::runloop::{kCFRunLoopCommonModes, CFRunLoop};
+  use core_graphics::event::{
+    CGEventFlags, CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement, CGEventType, EventField,
+  };
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  use super::{Hooks, InputEvent};
+
+  /// `kVK_ANSI_C`.
+  const KEYCODE_C: i64 = 8;
+  static STARTED: AtomicBool = AtomicBool::new(false);
+  static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+  fn start() -> Result<(), String> {
+    ACTIVE.store(true, Ordering::SeqCst);
+    if STARTED.load(Ordering::SeqCst) {
+      return Ok(());
+    }
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::Builder::new()
+      .name("input-hook".to_string())
+      .spawn(move || {
+        let pid = std::process::id() as i64;
+        let tap = CGEventTap::new(
+          CGEventTapLocation::Session,
+          CGEventTapPlacement::HeadInsertEventTap,
+          CGEventTapOptions::ListenOnly,
+          vec![CGEventType::KeyDown],
+          move |_, _, event| {
+            let is_copy = event.get_integer_value_field(EventField::KEYBOARD_EVENT_KEYCODE) == KEYCODE_C
+              && event.get_flags().contains(CGEventFlags::CGEventFlagCommand)
+              && event.get_integer_value_field(EventField::KEYBOARD_EVENT_AUTOREPEAT) == 0
+              && event.get_integer_value_field(EventField::EVENT_SOURCE_UNIX_PROCESS_ID) != pid;
+            if is_copy && ACTIVE.load(Ordering::SeqCst) {
+              super::deliver(InputEvent::Copy);
+            }
+            None
+          },
+        );
+        let Ok(tap) = tap else {
+          let _ = tx.send(Err("cannot tap key events (allow ErudAite under Accessibility)".to_string()));
+          return;
+        };
+        let Ok(source) = tap.mach_port.create_runloop_source(0) else {
+          let _ = tx.send(Err("cannot attach the key event tap".to_string()));
+          return;
+        };
+        CFRunLoop::get_current().add_source(&source, unsafe { kCFRunLoopCommonModes });
+        tap.enable();
+        STARTED.store(true, Ordering::SeqCst);
+        let _ = tx.send(Ok(()));
+        CFRunLoop::run_current();
+      })
+      .map_err(|e| format!("cannot start the input hook: {e}"))?;
+    let started = rx.recv().unwrap_or_else(|_| Err("the input hook thread exited".to_string()));
+    if started.is_err() {
+      ACTIVE.store(false, Ordering::SeqCst);
+    }
+    started
+  }
+
+  pub fn apply(hooks: Hooks) -> Result<(), String> {
+    if hooks.mouse {
+      return Err("mouse triggers are only supported on Windows".to_string());
+    }
+    if hooks.keyboard {
+      start()
+    } else {
+      ACTIVE.store(false, Ordering::SeqCst);
+      Ok(())
+    }
+  }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+mod platform {
+  use super::Hooks;
+
+  pub fn apply(hooks: Hooks) -> Result<(), String> {
+    if hooks == Hooks::default() {
+      Ok(())
+    } else {
+      Err("input hooks aren't supported on this platform".to_string())
+    }
+  }
+}
+
+/// Install the hooks something needs and remove the others.
+pub fn sync(app: &tauri::AppHandle) -> Result<(), String> {
+  let _ = APP.set(app.clone());
+  platform::apply(Hooks {
+    keyboard: double_copy::is_enabled(app) || hotkeys::hooked(app),
+    mouse: mouse_triggers::is_active(app),
+  })
+}
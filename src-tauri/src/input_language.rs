@@ -0,0 +1,156 @@
+//! The language being typed in: the keyboard layout (input language) of the foreground app, to suggest a target
+//! language without any configuration.
+//!
+//! Windows reads the foreground thread's keyboard layout (`GetKeyboardLayout`) and names its language ID; macOS
+//! asks for the current keyboard input source's first language (Text Input Sources, on the main thread, as TIS
+//! requires). The BCP-47 tag is mapped to one of the translation target names ("Japanese", "English (UK)", ...).
+//! Elsewhere, or when the layout says nothing, the answer is `None`.
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InputLanguage {
+  /// BCP-47 tag of the layout's language ("ja-JP", "en-GB", "zh-Hant").
+  pub tag: String,
+  /// Matching target language name, if it's one the translator offers.
+  pub language: Option<String>,
+}
+
+/// Target language name for BCP-47 `tag`: the language, with the region or script where the name depends on it.
+fn target_language(tag: &str) -> Option<&'static str> {
+  let lower = tag.to_ascii_lowercase().replace('_', "-");
+  let mut parts = lower.split('-');
+  let lang = parts.next()?;
+  let rest: Vec<&str> = parts.collect();
+  let has = |subtag: &str| rest.contains(&subtag);
+  Some(match lang {
+    "ja" => "Japanese",
+    "en" if has("gb") || has("ie") => "English (UK)",
+    "en" => "English (US)",
+    "ko" => "Korean",
+    "zh" if has("hant") || has("tw") || has("hk") || has("mo") => "Chinese (Traditional)",
+    "zh" => "Chinese (Simplified)",
+    "th" => "Thai",
+    "id" | "in" => "Indonesian",
+    "km" => "Khmer",
+    "tl" | "fil" => "Tagalog",
+    "vi" => "Vietnamese",
+    "mn" => "Standard Mongolian",
+    "bo" => "Tibetan",
+    "dz" => "Dzongkha",
+    "hi" => "Hindi",
+    "ur" => "Urdu",
+    "ta" => "Tamil",
+    "si" => "Sinhala",
+    "ne" => "Nepali",
+    "as" => "Assamese",
+    "ar" => "Arabic",
+    "he" | "iw" => "Hebrew",
+    "fa" => "Persian",
+    "tr" => "Turkish",
+    "es" if has("mx") => "Spanish (Mexico)",
+    "es" => "Spanish",
+    "fr" => "French",
+    "de" => "German",
+    "it" => "Italian",
+    "nl" => "Dutch",
+    "sv" => "Swedish",
+    "da" => "Danish",
+    "nb" | "nn" | "no" => "Norwegian",
+    "pt" if has("br") => "Portuguese (Brazil)",
+    "pt" => "Portuguese (Portugal)",
+    "ro" => "Romanian",
+    "pl" => "Polish",
+    "cs" => "Czech",
+    "sk" => "Slovak",
+    "hu" => "Hungarian",
+    "bg" => "Bulgarian",
+    "mk" => "Macedonian",
+    "uk" => "Ukrainian",
+    "ru" => "Russian",
+    "sr" => "Serbian",
+    "hr" => "Croatian",
+    "sl" => "Slovenian",
+    "el" => "Greek",
+    "lt" => "Lithuanian",
+    "lv" => "Latvian",
+    "ga" => "Irish",
+    "cy" => "Welsh",
+    "fi" => "Finnish",
+    "et" => "Estonian",
+    "mt" => "Maltese",
+    "am" => "Amharic",
+    "ti" => "Tigrinya",
+    "om" => "Oromo",
+    _ => return None,
+  })
+}
+
+#[cfg(windows)]
+async fn layout_tag(_app: &tauri::AppHandle) -> Option<String> {
+  use windows_sys::Win32::Globalization::LCIDToLocaleName;
+  use windows_sys::Win32::UI::Input::KeyboardAndMouse::GetKeyboardLayout;
+  use windows_sys::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+  unsafe {
+    let foreground = GetForegroundWindow();
+    let thread = if foreground.is_null() { 0 } else { GetWindowThreadProcessId(foreground, std::ptr::null_mut()) };
+    // The low word of the layout handle is the input language ID.
+    let lang_id = GetKeyboardLayout(thread) as usize as u32 & 0xFFFF;
+    let mut name = [0u16; 85];
+    let n = LCIDToLocaleName(lang_id, name.as_mut_ptr(), name.len() as i32, 0);
+    (n > 1).then(|| String::from_utf16_lossy(&name[..n as usize - 1]))
+  }
+}
+
+#[cfg(target_os = "macos")]
+async fn layout_tag(app: &tauri::AppHandle) -> Option<String> {
+  use core_foundation::array::{CFArray, CFArrayRef};
+  use core_foundation::base::{CFRelease, TCFType};
+  use core_foundation::string::{CFString, CFStringRef};
+  use std::ffi::c_void;
+
+  #[link(name = "Carbon", kind = "framework")]
+  extern "C" {
+    fn TISCopyCurrentKeyboardInputSource() -> *const c_void;
+    fn TISGetInputSourceProperty(source: *const c_void, key: CFStringRef) -> *const c_void;
+    static kTISPropertyInputSourceLanguages: CFStringRef;
+  }
+
+  let (tx, rx) = tokio::sync::oneshot::channel();
+  app
+    .run_on_main_thread(move || {
+      let tag = unsafe {
+        let source = TISCopyCurrentKeyboardInputSource();
+        if source.is_null() {
+          None
+        } else {
+          let languages = TISGetInputSourceProperty(source, kTISPropertyInputSourceLanguages);
+          let tag = (!languages.is_null()).then(|| {
+            let languages: CFArray<CFString> = CFArray::wrap_under_get_rule(languages as CFArrayRef);
+            languages.get(0).map(|l| l.to_string())
+          });
+          CFRelease(source);
+          tag.flatten()
+        }
+      };
+      let _ = tx.send(tag);
+    })
+    .ok()?;
+  rx.await.ok().flatten()
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+async fn layout_tag(_app: &tauri::AppHandle) -> Option<String> {
+  None
+}
+
+/// The foreground app's input language, and the target language it suggests.
+#[tauri::command]
+pub async fn get_input_language(app: tauri::AppHandle) -> Option<InputLanguage> {
+  let tag = layout_tag(&app).await.filter(|t| !t.is_empty())?;
+  Some(InputLanguage {
+    language: target_language(&tag).map(str::to_string),
+    tag,
+  })
+}
@@ -0,0 +1,50 @@
+//! The small JSON files kept in the app data dir (glossary, phrasebook, usage statistics, language pairs, capture
+//! regions).
+//!
+//! A save writes a temp file next to the target and renames it over the target, so a crash or full disk mid-write
+//! leaves the previous file whole. A file that exists but can't be parsed is moved aside to
+//! `<name>.unreadable-<ms>` (and reported) before the store starts out empty, so the next save doesn't overwrite
+//! what may still be recovered by hand.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+
+use crate::{clock, events};
+
+/// Read the JSON file at `path`; empty when it doesn't exist. An unparsable file is moved aside and reported as
+/// `<code>`.
+pub fn load<T: DeserializeOwned + Default>(app: &tauri::AppHandle, path: &Path, code: &str) -> T {
+  let Ok(bytes) = std::fs::read(path) else {
+    return T::default();
+  };
+  let e = match serde_json::from_slice(&bytes) {
+    Ok(value) => return value,
+    Err(e) => e,
+  };
+  let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+  let aside = path.with_file_name(format!("{name}.unreadable-{}", clock::now_millis()));
+  let kept = match std::fs::rename(path, &aside) {
+    Ok(()) => format!("it was kept as {}", aside.display()),
+    Err(move_err) => format!("it couldn't be moved aside ({move_err})"),
+  };
+  events::warn(app, None, code, format!("{name} couldn't be read ({e}); {kept}"));
+  T::default()
+}
+
+/// Write `value` to `path` as pretty JSON, replacing the file only once the new contents are fully written.
+pub fn save<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+  let bytes = serde_json::to_vec_pretty(value).map_err(|e| format!("serialize failed: {e}"))?;
+  let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+  let partial = path.with_file_name(format!("{name}.partial"));
+  let written = std::fs::File::create(&partial).and_then(|mut file| {
+    use std::io::Write;
+    file.write_all(&bytes)?;
+    file.sync_all()
+  });
+  if let Err(e) = written.and_then(|()| std::fs::rename(&partial, path)) {
+    let _ = std::fs::remove_file(&partial);
+    return Err(format!("write failed: {e}"));
+  }
+  Ok(())
+}
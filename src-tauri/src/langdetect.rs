@@ -0,0 +1,104 @@
+//! Offline language detection (whatlang), so routing doesn't need a server round trip per hotkey press.
+
+use serde::Serialize;
+use whatlang::{Lang, Script};
+
+/// Share of letters in the dominant script below which text counts as mixed.
+const MIXED_SCRIPT_THRESHOLD: f64 = 0.8;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LocalDetectResult {
+  /// English language name, same labels as the server's `detected_lang` ("Japanese", "Chinese", ...).
+  pub detected_lang: String,
+  /// ISO 639-1 code when one is known, otherwise ISO 639-3.
+  pub code: String,
+  pub confidence: f64,
+  pub is_reliable: bool,
+  /// Dominant script ("Latin", "Cyrillic", "Hiragana", ...).
+  pub script: String,
+  pub is_mixed: bool,
+}
+
+fn lang_name(lang: Lang) -> &'static str {
+  match lang {
+    Lang::Cmn => "Chinese",
+    l => l.eng_name(),
+  }
+}
+
+/// Han and kana are grouped: Japanese text mixes them in every sentence.
+fn script_group(script: Script) -> Script {
+  match script {
+    Script::Hiragana | Script::Katakana | Script::Mandarin => Script::Mandarin,
+    s => s,
+  }
+}
+
+fn is_mixed_script(text: &str) -> bool {
+  let mut groups: Vec<(Script, usize)> = Vec::new();
+  let mut buf = [0u8; 4];
+  for c in text.chars().filter(|c| c.is_alphabetic()) {
+    let Some(script) = whatlang::detect_script(c.encode_utf8(&mut buf)) else {
+      continue;
+    };
+    let g = script_group(script);
+    match groups.iter_mut().find(|(s, _)| *s == g) {
+      Some(entry) => entry.1 += 1,
+      None => groups.push((g, 1)),
+    }
+  }
+  let total: usize = groups.iter().map(|(_, n)| n).sum();
+  let top = groups.iter().map(|(_, n)| *n).max().unwrap_or(0);
+  total > 0 && (top as f64) < total as f64 * MIXED_SCRIPT_THRESHOLD
+}
+
+/// Detect the language of `text`; `None` if it has no letters to go on.
+pub fn detect(text: &str) -> Option<LocalDetectResult> {
+  let info = whatlang::detect(text)?;
+  let name = lang_name(info.lang());
+  let code = crate::offline_mt::lang_code(name).unwrap_or(info.lang().code());
+  Some(LocalDetectResult {
+    detected_lang: name.to_string(),
+    code: code.to_string(),
+    confidence: info.confidence(),
+    is_reliable: info.is_reliable(),
+    script: info.script().name().to_string(),
+    is_mixed: is_mixed_script(text),
+  })
+}
+
+#[tauri::command]
+pub async fn detect_language_local(text: String) -> Result<LocalDetectResult, String> {
+  tauri::async_runtime::spawn_blocking(move || detect(&text))
+    .await
+    .map_err(|e| format!("language detection failed: {e}"))?
+    .ok_or_else(|| "LANGUAGE_UNDETECTED".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn detects_with_server_labels() {
+    let ja = detect("今日はとても良い天気ですね。散歩に行きましょう。").unwrap();
+    assert_eq!((ja.detected_lang.as_str(), ja.code.as_str()), ("Japanese", "ja"));
+    assert!(!ja.is_mixed);
+    let zh = detect("我们今天下午去图书馆学习，然后一起吃晚饭。").unwrap();
+    assert_eq!((zh.detected_lang.as_str(), zh.code.as_str()), ("Chinese", "zh"));
+    let ru = detect("Сегодня очень хорошая погода, пойдём гулять в парк.").unwrap();
+    assert_eq!(ru.script, "Cyrillic");
+  }
+
+  #[test]
+  fn flags_mixed_scripts() {
+    assert!(is_mixed_script("Hello world こんにちは世界"));
+    assert!(!is_mixed_script("ひらがなとカタカナと漢字"));
+    assert!(!is_mixed_script("12345 !!!"));
+  }
+
+  #[test]
+  fn needs_letters() {
+    assert!(detect("12345 !!!").is_none());
+  }
+}
@@ -0,0 +1,110 @@
+//! Recently used language pairs, so the popup can offer one-click switching to the pairs the user really
+//! translates between.
+//!
+//! Every finished interactive translation records its (source language, target language, mode); the same
+//! combination is kept once, with how often and when it was last used. Up to `MAX_PAIRS` of the most recently used
+//! are saved to `language-pairs.json` in the app data dir. The source language is detected locally and is `None`
+//! when the text gave nothing to go on.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::clock;
+
+const PAIRS_FILE: &str = "language-pairs.json";
+const MAX_PAIRS: usize = 30;
+const DEFAULT_LIMIT: usize = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LanguagePair {
+  pub source_lang: Option<String>,
+  pub target_lang: String,
+  pub mode: String,
+  pub count: u64,
+  pub last_used_at: u128,
+}
+
+#[derive(Default)]
+struct Inner {
+  path: Option<PathBuf>,
+  pairs: Vec<LanguagePair>,
+}
+
+impl Inner {
+  fn save(&self) {
+    let Some(path) = &self.path else {
+      return;
+    };
+    if let Err(e) = crate::json_store::save(path, &self.pairs) {
+      log::warn!("failed to save language pairs: {e}");
+    }
+  }
+}
+
+/// Recent language pairs (managed state).
+#[derive(Default)]
+pub struct LanguagePairs(Mutex<Inner>);
+
+impl LanguagePairs {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Load the saved pairs from `dir`.
+pub fn init(app: &tauri::AppHandle, dir: &std::path::Path) {
+  let path = dir.join(PAIRS_FILE);
+  let pairs = crate::json_store::load(app, &path, "language_pairs_unreadable");
+  let state = app.state::<LanguagePairs>();
+  let mut inner = state.lock();
+  inner.path = Some(path);
+  inner.pairs = pairs;
+}
+
+/// Count a translation from `source_lang` into `target_lang` in `mode`.
+pub fn record(app: &tauri::AppHandle, source_lang: Option<&str>, target_lang: &str, mode: &str) {
+  if target_lang.trim().is_empty() {
+    return;
+  }
+  let state = app.state::<LanguagePairs>();
+  let mut inner = state.lock();
+  let now = clock::now_millis();
+  let existing = inner
+    .pairs
+    .iter()
+    .position(|p| p.source_lang.as_deref() == source_lang && p.target_lang == target_lang && p.mode == mode);
+  let mut pair = match existing {
+    Some(i) => inner.pairs.remove(i),
+    None => LanguagePair {
+      source_lang: source_lang.map(str::to_string),
+      target_lang: target_lang.to_string(),
+      mode: mode.to_string(),
+      count: 0,
+      last_used_at: now,
+    },
+  };
+  pair.count += 1;
+  pair.last_used_at = now;
+  inner.pairs.insert(0, pair);
+  inner.pairs.truncate(MAX_PAIRS);
+  inner.save();
+}
+
+/// The `limit` (default 5) most used recent pairs, most used first; ties go to the most recently used.
+#[tauri::command]
+pub fn get_recent_language_pairs(state: tauri::State<'_, LanguagePairs>, limit: Option<usize>) -> Vec<LanguagePair> {
+  let mut pairs = state.lock().pairs.clone();
+  pairs.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_used_at.cmp(&a.last_used_at)));
+  pairs.truncate(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_PAIRS));
+  pairs
+}
+
+/// Forget all recorded pairs.
+#[tauri::command]
+pub fn clear_recent_language_pairs(state: tauri::State<'_, LanguagePairs>) {
+  let mut inner = state.lock();
+  inner.pairs.clear();
+  inner.save();
+}
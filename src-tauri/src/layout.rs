@@ -0,0 +1,178 @@
+//! Reading order and paragraphs for OCR output (`ocrLayout`).
+//!
+//! Engines report lines roughly top to bottom, which interleaves the columns of a two-column article. Columns
+//! are found as vertical gutters no line crosses, ignoring lines wide enough to be headings; such a wide line
+//! splits the page into bands, and each band is read column by column, left to right. Within a column a larger
+//! gap or a first-line indent starts a paragraph (a blank line in the text, which reflow keeps).
+
+use crate::ocr::{OcrLine, OcrOutput};
+
+/// Lines wider than this share of the text are headings or single-column text, never inside a column.
+const WIDE_LINE: f32 = 0.6;
+/// Vertical gap, in line heights, that separates paragraphs.
+const PARAGRAPH_GAP: f32 = 0.8;
+/// Indent, in line heights, that starts a paragraph.
+const PARAGRAPH_INDENT: f32 = 1.5;
+
+fn median(mut values: Vec<i32>) -> i32 {
+  values.sort_unstable();
+  values.get(values.len() / 2).copied().unwrap_or(0)
+}
+
+/// x positions of the gutters between columns, left to right.
+fn gutters(lines: &[OcrLine], line_height: i32) -> Vec<i32> {
+  let left = lines.iter().map(|l| l.bbox.x).min().unwrap_or(0);
+  let right = lines.iter().map(|l| l.bbox.x + l.bbox.width).max().unwrap_or(0);
+  let wide = ((right - left) as f32 * WIDE_LINE) as i32;
+  let mut spans: Vec<(i32, i32)> = lines
+    .iter()
+    .filter(|l| l.bbox.width <= wide)
+    .map(|l| (l.bbox.x, l.bbox.x + l.bbox.width))
+    .collect();
+  spans.sort_unstable();
+  let mut out = Vec::new();
+  let mut covered_to: Option<i32> = None;
+  for (start, end) in spans {
+    match covered_to {
+      Some(to) if start - to >= line_height => {
+        out.push((to + start) / 2);
+        covered_to = Some(end);
+      }
+      Some(to) => covered_to = Some(to.max(end)),
+      None => covered_to = Some(end),
+    }
+  }
+  out
+}
+
+/// Where a line sits: inside column `n`, or across a gutter.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Place {
+  Column(usize),
+  Across,
+}
+
+fn place(line: &OcrLine, gutters: &[i32]) -> Place {
+  let (start, end) = (line.bbox.x, line.bbox.x + line.bbox.width);
+  if gutters.iter().any(|g| start < *g && end > *g) {
+    Place::Across
+  } else {
+    Place::Column(gutters.iter().filter(|g| **g <= start).count())
+  }
+}
+
+/// Put `output`'s lines in reading order and rebuild its text with paragraph breaks. Leaves output without usable
+/// boxes alone.
+pub fn arrange(output: &mut OcrOutput) {
+  if output.lines.len() < 2 || output.lines.iter().any(|l| l.bbox.width <= 0 || l.bbox.height <= 0) {
+    return;
+  }
+  let line_height = median(output.lines.iter().map(|l| l.bbox.height).collect()).max(1);
+  let gutters = gutters(&output.lines, line_height);
+
+  let mut lines = std::mem::take(&mut output.lines);
+  lines.sort_by_key(|l| (l.bbox.y, l.bbox.x));
+  let mut ordered: Vec<(Place, OcrLine)> = Vec::with_capacity(lines.len());
+  let mut band: Vec<(Place, OcrLine)> = Vec::new();
+  let flush = |band: &mut Vec<(Place, OcrLine)>, ordered: &mut Vec<(Place, OcrLine)>| {
+    // Stable: each column stays top to bottom.
+    band.sort_by_key(|(p, _)| match p {
+      Place::Column(n) => *n,
+      Place::Across => 0,
+    });
+    ordered.append(band);
+  };
+  for line in lines {
+    match place(&line, &gutters) {
+      Place::Across => {
+        flush(&mut band, &mut ordered);
+        ordered.push((Place::Across, line));
+      }
+      column => band.push((column, line)),
+    }
+  }
+  flush(&mut band, &mut ordered);
+
+  let mut text = String::new();
+  for (i, (placed, line)) in ordered.iter().enumerate() {
+    if i > 0 {
+      let (prev_placed, prev) = &ordered[i - 1];
+      let gap = line.bbox.y - (prev.bbox.y + prev.bbox.height);
+      let indent = line.bbox.x - prev.bbox.x;
+      let new_paragraph = if placed == prev_placed {
+        gap as f32 > line_height as f32 * PARAGRAPH_GAP || indent as f32 > line_height as f32 * PARAGRAPH_INDENT
+      } else {
+        // Headings stand apart; the next column may continue the sentence, which reflow judges.
+        *placed == Place::Across || *prev_placed == Place::Across
+      };
+      text.push_str(if new_paragraph { "\n\n" } else { "\n" });
+    }
+    text.push_str(&line.text);
+  }
+  output.text = text;
+  output.lines = ordered.into_iter().map(|(_, l)| l).collect();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ocr::BBox;
+
+  fn line(text: &str, x: i32, y: i32, width: i32) -> OcrLine {
+    OcrLine {
+      text: text.to_string(),
+      bbox: BBox {
+        x,
+        y,
+        width,
+        height: 10,
+      },
+      confidence: None,
+      words: Vec::new(),
+    }
+  }
+
+  fn arranged(lines: Vec<OcrLine>) -> OcrOutput {
+    let mut output = OcrOutput {
+      text: String::new(),
+      lines,
+    };
+    arrange(&mut output);
+    output
+  }
+
+  #[test]
+  fn reads_columns_left_to_right_under_a_heading() {
+    let output = arranged(vec![
+      line("Heading", 0, 0, 200),
+      line("left one", 0, 20, 90),
+      line("right one", 110, 20, 90),
+      line("left two", 0, 32, 90),
+      line("right two", 110, 32, 90),
+    ]);
+    assert_eq!(output.text, "Heading\n\nleft one\nleft two\nright one\nright two");
+    assert_eq!(output.lines[3].text, "right one");
+  }
+
+  #[test]
+  fn breaks_paragraphs_on_gaps_and_indents() {
+    let output = arranged(vec![
+      line("a", 0, 0, 100),
+      line("b", 0, 12, 100),
+      line("c", 0, 40, 100),
+      line("d", 20, 52, 80),
+    ]);
+    assert_eq!(output.text, "a\nb\n\nc\n\nd");
+  }
+
+  #[test]
+  fn leaves_output_without_boxes_alone() {
+    let mut output = OcrOutput {
+      text: "b\na".to_string(),
+      lines: vec![line("b", 0, 20, 0), line("a", 0, 0, 0)],
+    };
+    arrange(&mut output);
+    assert_eq!(output.text, "b\na");
+    assert_eq!(output.lines[0].text, "b");
+  }
+}
@@ -5,35 +5,249 @@ pub fn run() {
     .plugin(tauri_plugin_clipboard_manager::init())
     .plugin(tauri_plugin_global_shortcut::Builder::new().build())
     .plugin(tauri_plugin_store::Builder::new().build())
+    .plugin(tauri_plugin_notification::init())
+    .manage(usage::UsageState::default())
+    .manage(plugins::PluginRegistry::default())
+    .manage(queue::RequestQueue::default())
+    .manage(scripting::ScriptHost::default())
+    .manage(events::EventBus::default())
+    .manage(session::SessionState::default())
+    .manage(agent::AgentState::default())
+    .manage(settings::ConfigWatchers::default())
+    .manage(ocr::OcrJobs::default())
+    .manage(live_ocr::LiveOcr::default())
+    .manage(snapshot::Snapshots::default())
+    .manage(regions::RegionHistory::default())
+    .manage(history::History::default())
+    .manage(glossary::Glossary::default())
+    .manage(phrasebook::Phrasebook::default())
+    .manage(tts::Player::default())
+    .manage(speech::VoiceInput::default())
+    .manage(language_pairs::LanguagePairs::default())
+    .manage(popup::ClickThrough::default())
+    .manage(popup::Pins::default())
+    .manage(popup::AutoHide::default())
+    .manage(fade::Fades::default())
+    .manage(tray::TrayState::default())
+    .manage(hotkeys::Hotkeys::default())
+    .manage(double_copy::DoubleCopy::default())
+    .manage(mouse_triggers::MouseTriggers::default())
+    .manage(hotkey_suspend::HotkeySuspend::default())
+    .manage(type_back::TypeBack::default())
+    .manage(translation_overlay::TranslationOverlay::default())
     .invoke_handler(tauri::generate_handler![
       commands::translate_sse,
+      app_profiles::get_app_profile,
+      history::list_history,
+      history::delete_history_entries,
+      history::clear_history,
+      history::star_history_entries,
+      history::unstar_history_entries,
+      history::purge_history,
+      history_cipher::history_encryption_available,
+      history_export::export_history,
+      glossary::list_glossary,
+      glossary::add_glossary_entry,
+      glossary::update_glossary_entry,
+      glossary::delete_glossary_entries,
+      glossary::import_glossary,
+      phrasebook::list_phrases,
+      phrasebook::list_phrase_categories,
+      phrasebook::add_phrase,
+      phrasebook::update_phrase,
+      phrasebook::delete_phrases,
+      phrasebook::copy_phrase,
+      phrasebook::export_phrasebook,
+      phrasebook::import_phrasebook,
+      backup::create_backup,
+      backup::restore_backup,
+      sync::sync_now,
+      language_pairs::get_recent_language_pairs,
+      language_pairs::clear_recent_language_pairs,
       commands::capture_selected_text,
+      commands::read_clipboard_text,
       commands::detect_language,
       commands::get_cursor_position,
+      caret::get_caret_position,
+      input_language::get_input_language,
       commands::capture_screen_region,
+      commands::capture_window,
+      commands::copy_capture_to_clipboard,
+      commands::save_capture,
+      scroll_capture::capture_scrolling,
+      commands::screen_capture_permission,
+      commands::request_screen_capture_permission,
+      screen::resolve_screen_rect,
+      screen::list_monitors,
+      ocr_overlay::open_ocr_overlay,
+      ocr_overlay::close_ocr_overlay,
+      ocr_overlay::is_ocr_overlay_open,
+      translation_overlay::translation_overlay_blocks,
+      translation_overlay::open_translation_overlay,
+      translation_overlay::get_translation_overlay,
+      translation_overlay::close_translation_overlay,
+      popup::position_popup,
+      popup::set_click_through,
+      popup::is_click_through,
+      popup::set_always_on_top,
+      popup::is_window_pinned,
+      fade::fade_window,
+      fade::fade_out_window,
+      fade::set_window_opacity,
+      fade::get_window_opacity,
+      tray::set_hotkeys_paused,
+      tray::are_hotkeys_paused,
+      hotkeys::get_hotkey_status,
+      hotkeys::translate_clipboard,
+      type_back::replace_selection_with,
+      double_copy::set_double_copy_enabled,
+      double_copy::double_copy_status,
+      snapshot::capture_full_screen_snapshot,
+      snapshot::crop_snapshot,
+      snapshot::snapshot_image,
+      snapshot::discard_snapshot,
+      regions::list_capture_regions,
+      regions::clear_capture_regions,
+      regions::last_capture_region,
+      regions::capture_last_region,
       commands::detect_tesseract_path,
-      commands::tesseract_list_langs,
-      commands::download_tessdata,
-      commands::ocr_tesseract,
+      ocr::tesseract_list_langs,
+      tessdata::download_tessdata,
+      tessdata::list_installed_tessdata,
+      tessdata::delete_tessdata,
+      winrt_ocr::list_windows_ocr_languages,
+      ocr::ocr_image,
+      ocr::ocr_image_bytes,
+      ocr::ocr_regions,
+      ocr::capture_active_window,
+      ocr::list_ocr_engines,
+      ocr::cancel_ocr,
+      ocr_translate::ocr_translate_region,
+      remote_ocr::check_remote_ocr,
+      live_ocr::start_live_ocr,
+      live_ocr::pause_live_ocr,
+      live_ocr::resume_live_ocr,
+      live_ocr::stop_live_ocr,
+      live_ocr::live_ocr_status,
+      recording::ocr_recording,
+      temp::cleanup_temp_files,
+      tts::speak,
+      tts::stop_speaking,
+      tts::clear_tts_cache,
+      speech::start_voice_translation,
+      speech::stop_voice_translation,
       commands::download_tesseract_installer,
-      commands::launch_installer
+      commands::launch_installer,
+      documents::translate_file,
+      usage::get_usage_stats,
+      usage::reset_usage_stats,
+      plugins::list_plugins,
+      plugins::reload_plugins,
+      plugins::install_plugin,
+      plugins::uninstall_plugin,
+      plugins::set_plugin_enabled,
+      plugins::run_plugin,
+      queue::get_queue_status,
+      queue::set_max_concurrency,
+      queue::cancel_request,
+      queue::cancel_all_requests,
+      scripting::list_scripts,
+      scripting::read_script,
+      scripting::save_script,
+      scripting::delete_script,
+      scripting::run_script,
+      scripting::reload_scripts,
+      events::subscribe_events,
+      events::unsubscribe_events,
+      offline_mt::detect_offline_engine,
+      offline_mt::list_offline_models,
+      offline_mt::list_available_offline_models,
+      offline_mt::download_offline_model,
+      offline_mt::remove_offline_model,
+      accel::get_acceleration_info,
+      collation::collation_locale,
+      collation::sort_strings,
+      langdetect::detect_language_local,
+      session::get_previous_session,
+      session::restore_previous_session,
+      session::discard_previous_session,
+      session::take_restored_draft,
+      settings_schema::get_settings,
+      settings_schema::set_settings,
+      settings::get_reload_status,
+      settings::reload_config,
+      http::set_signing_key,
+      http::delete_signing_key,
+      http::has_signing_key,
+      secrets::set_secret,
+      secrets::get_secret,
+      secrets::delete_secret,
+      #[cfg(feature = "deterministic")]
+      mock::deterministic_reset,
+      #[cfg(feature = "deterministic")]
+      mock::deterministic_advance_clock
     ])
+    .on_page_load(|webview, payload| {
+      session::on_page_load(webview, payload);
+      capture_exclusion::on_page_load(webview, payload);
+      popup::on_page_load(webview, payload);
+      fade::on_page_load(webview, payload);
+    })
     .on_window_event(|window, event| {
+      session::on_window_event(window, event);
+      agent::on_window_event(window, event);
+      popup::on_window_event(window, event);
       // Safety: if the main window is closed/destroyed while OCR overlay is open,
       // force-close other windows so the user never gets stuck with an overlay.
       let label = window.label().to_string();
       let should_cleanup = matches!(event, tauri::WindowEvent::CloseRequested { .. } | tauri::WindowEvent::Destroyed);
       if label == "main" && should_cleanup {
         // Best-effort cleanup
-        if let Some(w) = window.app_handle().get_webview_window("ocr-overlay") {
-          let _ = w.close();
-        }
+        ocr_overlay::close(window.app_handle());
+        translation_overlay::close(window.app_handle());
+        // A pinned popup was asked to stay.
         if let Some(w) = window.app_handle().get_webview_window("popup") {
-          let _ = w.close();
+          if !popup::is_pinned(window.app_handle(), "popup") {
+            let _ = w.close();
+          }
         }
       }
     })
     .setup(|app| {
+      secrets::init(app.handle());
+      settings_schema::init(app.handle());
+      if let Ok(dir) = app.path().app_data_dir() {
+        let plugins_dir = dir.join("plugins");
+        let _ = std::fs::create_dir_all(&plugins_dir);
+        app.state::<plugins::PluginRegistry>().init(plugins_dir);
+        let scripts_dir = dir.join("scripts");
+        let _ = std::fs::create_dir_all(&scripts_dir);
+        if let Err(e) = scripting::init(app.handle(), Some(scripts_dir)) {
+          log::warn!("{e}");
+        }
+        let _ = std::fs::create_dir_all(&dir);
+        temp::init(app.handle(), &dir);
+        regions::init(app.handle(), &dir);
+        history::init(app.handle(), &dir);
+        glossary::init(app.handle(), &dir);
+        phrasebook::init(app.handle(), &dir);
+        usage::init(app.handle(), &dir);
+        language_pairs::init(app.handle(), &dir);
+        popup::init(app.handle(), &dir);
+        session::init(app.handle(), dir);
+      }
+      settings::watch(app.handle());
+      http::init(app.handle());
+      agent::init(app.handle());
+      tray::init(app.handle());
+      hotkeys::init(app.handle());
+      double_copy::init(app.handle());
+      mouse_triggers::init(app.handle());
+      hotkey_suspend::init(app.handle());
+      sync::init(app.handle());
+      fade::init(app.handle());
+      live_ocr::init(app.handle());
+      capture_exclusion::init(app.handle());
       if cfg!(debug_assertions) {
         app.handle().plugin(
           tauri_plugin_log::Builder::default()
@@ -43,8 +257,98 @@ pub fn run() {
       }
       Ok(())
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while running tauri application")
+    .run(|app, event| match event {
+      tauri::RunEvent::ExitRequested { code, api, .. } => agent::on_exit_requested(app, code, &api),
+      tauri::RunEvent::Exit => {
+        session::mark_clean_exit(app);
+        usage::flush(app);
+        temp::shutdown();
+      }
+      _ => {}
+    });
 }
 
+mod accel;
+mod agent;
+mod app_profiles;
+mod backup;
+mod capture_exclusion;
+mod capture_output;
+mod caret;
+mod chunking;
+mod clock;
+mod collation;
 mod commands;
+mod documents;
+mod double_copy;
+mod events;
+mod fade;
+mod glossary;
+mod history;
+mod history_cipher;
+mod history_export;
+mod hotkey_suspend;
+mod hotkeys;
+mod http;
+mod image_io;
+mod input_hook;
+mod input_language;
+mod json_store;
+mod langdetect;
+mod language_pairs;
+mod layout;
+#[cfg(target_os = "linux")]
+mod linux_capture;
+mod live_ocr;
+#[cfg(target_os = "macos")]
+mod mac_capture;
+#[cfg(feature = "deterministic")]
+mod mock;
+mod mouse_triggers;
+mod ocr;
+mod ocr_merge;
+mod ocr_overlay;
+mod ocr_translate;
+mod offline_mt;
+mod osd;
+mod output;
+mod phrasebook;
+mod plugins;
+mod popup;
+mod protect;
+mod queue;
+mod recording;
+mod reflow;
+mod regions;
+mod remote_ocr;
+mod screen;
+mod secrets;
+mod scroll_capture;
+mod scripting;
+mod sections;
+mod session;
+mod settings;
+mod settings_schema;
+mod snapshot;
+mod speech;
+mod sync;
+mod temp;
+mod tessdata;
+#[cfg(feature = "embedded-tesseract")]
+mod tesseract_embedded;
+mod tone_map;
+mod translate;
+mod translation_overlay;
+mod tray;
+mod tts;
+mod type_back;
+mod usage;
+#[cfg(target_os = "macos")]
+mod vision_ocr;
+#[cfg(windows)]
+mod win_gfx;
+#[cfg(windows)]
+mod win_window;
+mod winrt_ocr;
@@ -0,0 +1,196 @@
+//! Screen capture on Linux.
+//!
+//! X11 sessions read the root window (`GetImage`). Wayland doesn't let clients read the screen (XWayland's root
+//! window shows X clients only), so there the xdg-desktop-portal Screenshot API takes the whole desktop and the
+//! region is cropped out of it. Regions are in screen pixels. On X11 the pointer can be drawn in (XFixes); the
+//! portal decides that itself. Scrolling capture turns the wheel with XTest, which Wayland has no equivalent of.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use x11rb::connection::Connection;
+use x11rb::image::{Image, PixelLayout};
+use x11rb::protocol::xfixes::ConnectionExt as _;
+use x11rb::protocol::xproto::{BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+use crate::commands::CaptureRect;
+
+fn is_wayland() -> bool {
+  std::env::var_os("WAYLAND_DISPLAY").is_some()
+    || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+}
+
+/// Capture `rect` to a temp PNG and return its path.
+pub fn capture(rect: &CaptureRect, include_cursor: bool) -> Result<String, String> {
+  let rows = if is_wayland() { portal_capture(rect)? } else { x11_capture(rect, include_cursor)? };
+  let out_path = crate::temp::path("capture", "png");
+  if let Err(e) = crate::image_io::write_png(&out_path, &rows, rect.width, rect.height) {
+    let _ = std::fs::remove_file(&out_path);
+    return Err(e);
+  }
+  Ok(out_path.to_string_lossy().to_string())
+}
+
+/// `rect` of the root window as RGB rows.
+fn x11_capture(rect: &CaptureRect, include_cursor: bool) -> Result<Vec<u8>, String> {
+  let (conn, screen_num) = x11rb::connect(None).map_err(|e| format!("cannot connect to the X server: {e}"))?;
+  let screen = &conn.setup().roots[screen_num];
+  let to_u16 = |v: u32| u16::try_from(v).map_err(|_| "invalid rect".to_string());
+  let to_i16 = |v: i32| i16::try_from(v).map_err(|_| "invalid rect".to_string());
+  let (image, visual_id) = Image::get(
+    &conn,
+    screen.root,
+    to_i16(rect.x)?,
+    to_i16(rect.y)?,
+    to_u16(rect.width)?,
+    to_u16(rect.height)?,
+  )
+  .map_err(|e| format!("GetImage failed: {e}"))?;
+  let visual = screen
+    .allowed_depths
+    .iter()
+    .flat_map(|d| &d.visuals)
+    .find(|v| v.visual_id == visual_id)
+    .ok_or_else(|| "GetImage failed: unknown visual".to_string())?;
+  let layout = PixelLayout::from_visual_type(*visual).map_err(|e| format!("unsupported visual: {e}"))?;
+
+  let mut rows = Vec::with_capacity(rect.width as usize * rect.height as usize * 3);
+  for y in 0..image.height() {
+    for x in 0..image.width() {
+      let (r, g, b) = layout.decode(image.get_pixel(x, y));
+      rows.extend([(r >> 8) as u8, (g >> 8) as u8, (b >> 8) as u8]);
+    }
+  }
+  if include_cursor {
+    // Best effort: a server without XFixes just leaves the pointer out.
+    let _ = draw_cursor(&conn, &mut rows, rect);
+  }
+  Ok(rows)
+}
+
+/// Blend the pointer image (premultiplied ARGB from XFixes) into `rows`, which hold `rect` of the screen.
+fn draw_cursor(conn: &impl Connection, rows: &mut [u8], rect: &CaptureRect) -> Result<(), Box<dyn std::error::Error>> {
+  conn.xfixes_query_version(4, 0)?.reply()?;
+  let cursor = conn.xfixes_get_cursor_image()?.reply()?;
+  let left = cursor.x as i32 - cursor.xhot as i32 - rect.x;
+  let top = cursor.y as i32 - cursor.yhot as i32 - rect.y;
+  for cy in 0..cursor.height as i32 {
+    for cx in 0..cursor.width as i32 {
+      let (x, y) = (left + cx, top + cy);
+      if x < 0 || y < 0 || x >= rect.width as i32 || y >= rect.height as i32 {
+        continue;
+      }
+      let argb = cursor.cursor_image[(cy * cursor.width as i32 + cx) as usize];
+      let alpha = argb >> 24;
+      let i = (y as usize * rect.width as usize + x as usize) * 3;
+      for (c, shift) in [16, 8, 0].into_iter().enumerate() {
+        let src = (argb >> shift) & 0xff;
+        let dst = rows[i + c] as u32;
+        rows[i + c] = (src + dst * (255 - alpha) / 255).min(255) as u8;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Turn the mouse wheel down by `notches` wherever the pointer is (X11 only).
+pub fn scroll_down(notches: u32) -> Result<(), String> {
+  if is_wayland() {
+    return Err("scrolling capture is not supported on Wayland".to_string());
+  }
+  let fake = || -> Result<(), Box<dyn std::error::Error>> {
+    let (conn, _) = x11rb::connect(None)?;
+    conn.xtest_get_version(2, 2)?.reply()?;
+    // Button 5 is the wheel turning down.
+    for _ in 0..notches {
+      conn.xtest_fake_input(BUTTON_PRESS_EVENT, 5, x11rb::CURRENT_TIME, x11rb::NONE, 0, 0, 0)?;
+      conn.xtest_fake_input(BUTTON_RELEASE_EVENT, 5, x11rb::CURRENT_TIME, x11rb::NONE, 0, 0, 0)?;
+    }
+    conn.flush()?;
+    Ok(())
+  };
+  fake().map_err(|e| format!("XTest failed: {e}"))
+}
+
+/// Take a screenshot through the portal and crop `rect` out of it (RGB rows).
+fn portal_capture(rect: &CaptureRect) -> Result<Vec<u8>, String> {
+  let path = portal_screenshot().map_err(|e| format!("screenshot portal failed: {e}"))?;
+  let result = crop_png(&path, rect);
+  // The portal saves into the user's Pictures folder; the file was only for us.
+  let _ = std::fs::remove_file(&path);
+  result
+}
+
+/// Ask `org.freedesktop.portal.Screenshot` for a non-interactive screenshot; returns the file it wrote.
+fn portal_screenshot() -> Result<std::path::PathBuf, String> {
+  static TOKEN: AtomicU64 = AtomicU64::new(0);
+  const DESKTOP: &str = "org.freedesktop.portal.Desktop";
+
+  let conn = zbus::blocking::Connection::session().map_err(|e| e.to_string())?;
+  let sender = conn
+    .unique_name()
+    .ok_or_else(|| "no D-Bus name".to_string())?
+    .trim_start_matches(':')
+    .replace('.', "_");
+  let token = format!("erudaite{}", TOKEN.fetch_add(1, Ordering::SeqCst));
+  // Listen on the request object before asking, so a quick reply isn't missed.
+  let request_path = format!("/org/freedesktop/portal/desktop/request/{sender}/{token}");
+  let request = zbus::blocking::Proxy::new(&conn, DESKTOP, request_path.as_str(), "org.freedesktop.portal.Request")
+    .map_err(|e| e.to_string())?;
+  let mut responses = request.receive_signal("Response").map_err(|e| e.to_string())?;
+
+  let portal = zbus::blocking::Proxy::new(
+    &conn,
+    DESKTOP,
+    "/org/freedesktop/portal/desktop",
+    "org.freedesktop.portal.Screenshot",
+  )
+  .map_err(|e| e.to_string())?;
+  let options: HashMap<&str, Value> =
+    HashMap::from([("handle_token", Value::from(token.as_str())), ("interactive", Value::from(false))]);
+  let _: OwnedObjectPath = portal.call("Screenshot", &("", options)).map_err(|e| e.to_string())?;
+
+  let message = responses.next().ok_or_else(|| "no response".to_string())?;
+  let (response, results): (u32, HashMap<String, OwnedValue>) =
+    message.body().deserialize().map_err(|e| e.to_string())?;
+  if response != 0 {
+    return Err("SCREENSHOT_DENIED".to_string());
+  }
+  let uri = results
+    .get("uri")
+    .and_then(|v| String::try_from(v.clone()).ok())
+    .ok_or_else(|| "no screenshot uri".to_string())?;
+  let path = uri.strip_prefix("file://").ok_or_else(|| format!("unexpected screenshot uri: {uri}"))?;
+  Ok(std::path::PathBuf::from(percent_decode(path)))
+}
+
+fn percent_decode(s: &str) -> String {
+  let bytes = s.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let hex = bytes.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+    match (bytes[i], hex) {
+      (b'%', Some(b)) => {
+        out.push(b);
+        i += 3;
+      }
+      (b, _) => {
+        out.push(b);
+        i += 1;
+      }
+    }
+  }
+  String::from_utf8_lossy(&out).into_owned()
+}
+
+/// `rect` of a PNG as RGB rows.
+fn crop_png(path: &std::path::Path, rect: &CaptureRect) -> Result<Vec<u8>, String> {
+  let image = crate::image_io::read_rgb(path)?;
+  let (x0, y0) = (rect.x.max(0) as u32, rect.y.max(0) as u32);
+  if x0 + rect.width > image.width() || y0 + rect.height > image.height() {
+    return Err("capture region is outside the screen".to_string());
+  }
+  Ok(crate::image_io::crop_rgb(&image, x0, y0, rect.width, rect.height))
+}
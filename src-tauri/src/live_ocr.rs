@@ -0,0 +1,327 @@
+//! Live OCR: subtitle translation for videos and games.
+//!
+//! A session re-captures one screen region every `interval_ms`. A frame that looks like the last one (a coarse
+//! grayscale grid compared within `liveOcrChangeThreshold`) is skipped; otherwise it's OCR'd, and text that
+//! differs from the last line shown is translated. Everything is reported on the session's channel. One session
+//! runs at a time; `liveOcrIntervalMs` and `liveOcrChangeThreshold` apply to it as soon as settings are saved.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::Manager;
+
+use crate::clock;
+use crate::commands::CaptureRect;
+use crate::ocr::{self, OcrImage};
+use crate::output::{OutputControls, Verbosity};
+use crate::queue::{CancelToken, Priority};
+use crate::settings;
+use crate::translate::{self, TranslateRequest};
+
+const MIN_INTERVAL_MS: u64 = 200;
+/// Cells of the grid frames are compared on.
+const GRID: (usize, usize) = (32, 8);
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type")]
+pub enum LiveOcrEvent {
+  /// New text in the region.
+  #[serde(rename = "text")]
+  Text { text: String },
+  #[serde(rename = "translation")]
+  Translation { source: String, translation: String },
+  #[serde(rename = "paused")]
+  Paused,
+  #[serde(rename = "resumed")]
+  Resumed,
+  #[serde(rename = "stopped")]
+  Stopped,
+  /// A capture, OCR or translation failed; the session keeps going.
+  #[serde(rename = "error")]
+  Error { message: String },
+}
+
+struct Session {
+  id: String,
+  rect: CaptureRect,
+  target_lang: String,
+  interval_ms: Arc<AtomicU64>,
+  paused: Arc<AtomicBool>,
+  cancel: Arc<CancelToken>,
+  channel: Channel<LiveOcrEvent>,
+}
+
+/// The running session, if any (managed state).
+#[derive(Default)]
+pub struct LiveOcr(Mutex<Option<Session>>);
+
+impl LiveOcr {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Option<Session>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LiveOcrStatus {
+  pub id: String,
+  pub rect: CaptureRect,
+  pub target_lang: String,
+  pub interval_ms: u64,
+  pub paused: bool,
+}
+
+/// Mean luminance of each grid cell of a PNG.
+pub(crate) fn thumbnail(path: &str) -> Result<Vec<f32>, String> {
+  let image = crate::image_io::read_rgb(path)?;
+  let (w, h) = (image.width() as usize, image.height() as usize);
+  let (gw, gh) = GRID;
+  let mut sums = vec![0f32; gw * gh];
+  let mut counts = vec![0u32; gw * gh];
+  for (x, y, px) in image.enumerate_pixels() {
+    let [r, g, b] = px.0;
+    let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    let cell = (y as usize * gh / h.max(1)) * gw + x as usize * gw / w.max(1);
+    sums[cell] += luma;
+    counts[cell] += 1;
+  }
+  Ok(sums.iter().zip(counts).map(|(s, n)| s / n.max(1) as f32).collect())
+}
+
+/// How different two thumbnails are (0 = same, 1 = black vs white).
+pub(crate) fn difference(a: &[f32], b: &[f32]) -> f32 {
+  if a.len() != b.len() || a.is_empty() {
+    return 1.0;
+  }
+  a.iter().zip(b).map(|(x, y)| (x - y).abs()).sum::<f32>() / a.len() as f32 / 255.0
+}
+
+/// Text compared without whitespace, so OCR jitter in spacing doesn't count as new text.
+pub(crate) fn same_text(a: &str, b: &str) -> bool {
+  a.chars().filter(|c| !c.is_whitespace()).eq(b.chars().filter(|c| !c.is_whitespace()))
+}
+
+async fn translate(app: &tauri::AppHandle, text: &str, target_lang: &str) -> Result<String, String> {
+  let req = TranslateRequest {
+    base_url: settings::api_base_url(app)?,
+    text: text.to_string(),
+    target_lang: target_lang.to_string(),
+    mode: "standard".to_string(),
+    explanation_lang: settings::explanation_lang(app),
+    // Background, so a subtitle line doesn't cancel a translation the user asked for (or get cancelled by one).
+    priority: Priority::Background,
+    output: OutputControls {
+      verbosity: Some(Verbosity::Concise),
+      include_examples: Some(false),
+      ..Default::default()
+    },
+    ..Default::default()
+  };
+  translate::run_translation(app, req, &|_| {}).await
+}
+
+struct Worker {
+  app: tauri::AppHandle,
+  rect: CaptureRect,
+  target_lang: String,
+  interval_ms: Arc<AtomicU64>,
+  paused: Arc<AtomicBool>,
+  cancel: Arc<CancelToken>,
+  channel: Channel<LiveOcrEvent>,
+  req: ocr::OcrRequest,
+}
+
+impl Worker {
+  fn send(&self, event: LiveOcrEvent) {
+    let _ = self.channel.send(event);
+  }
+
+  /// Capture once; OCR and translate if the frame changed. Returns the frame's thumbnail and text.
+  async fn tick(&self, last_frame: Option<&[f32]>, last_text: &str) -> Result<Option<(Vec<f32>, String)>, String> {
+    let path = crate::commands::capture_region(&self.app, &self.rect, false)?;
+    let result = self.recognize_if_changed(&path, last_frame, last_text).await;
+    let _ = std::fs::remove_file(&path);
+    result
+  }
+
+  async fn recognize_if_changed(
+    &self,
+    path: &str,
+    last_frame: Option<&[f32]>,
+    last_text: &str,
+  ) -> Result<Option<(Vec<f32>, String)>, String> {
+    let frame = thumbnail(path)?;
+    let threshold = settings::live_ocr_change_threshold(&self.app);
+    if last_frame.is_some_and(|last| difference(last, &frame) < threshold) {
+      return Ok(None);
+    }
+    let result = ocr::recognize_text(
+      &self.app,
+      OcrImage::Path(path.to_string()),
+      self.req.clone(),
+      None,
+      self.cancel.clone(),
+      None,
+    )
+    .await?;
+    let text = result.text.trim().to_string();
+    if !text.is_empty() && !same_text(&text, last_text) {
+      self.send(LiveOcrEvent::Text { text: text.clone() });
+      match translate(&self.app, &text, &self.target_lang).await {
+        Ok(translation) => self.send(LiveOcrEvent::Translation {
+          source: text.clone(),
+          translation,
+        }),
+        Err(message) => self.send(LiveOcrEvent::Error { message }),
+      }
+    }
+    Ok(Some((frame, text)))
+  }
+
+  async fn run(self) {
+    let mut last_frame: Option<Vec<f32>> = None;
+    let mut last_text = String::new();
+    loop {
+      let interval = Duration::from_millis(self.interval_ms.load(Ordering::SeqCst));
+      tokio::select! {
+        _ = tokio::time::sleep(interval) => {}
+        _ = self.cancel.cancelled() => break,
+      }
+      if self.paused.load(Ordering::SeqCst) {
+        continue;
+      }
+      match self.tick(last_frame.as_deref(), &last_text).await {
+        Ok(Some((frame, text))) => {
+          last_frame = Some(frame);
+          if !text.is_empty() {
+            last_text = text;
+          }
+        }
+        Ok(None) => {}
+        Err(_) if self.cancel.is_cancelled() => break,
+        Err(message) => self.send(LiveOcrEvent::Error { message }),
+      }
+    }
+    self.send(LiveOcrEvent::Stopped);
+  }
+}
+
+fn stop_session(session: Session) {
+  session.cancel.cancel();
+}
+
+/// Apply `liveOcrIntervalMs` to the running session (the threshold is read per frame).
+fn reload(app: &tauri::AppHandle, _: &settings::ConfigChange) -> Result<(), String> {
+  if let Some(session) = app.state::<LiveOcr>().lock().as_ref() {
+    if let Some(ms) = settings::live_ocr_interval_ms(app) {
+      session.interval_ms.store(ms.max(MIN_INTERVAL_MS), Ordering::SeqCst);
+    }
+  }
+  Ok(())
+}
+
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "live-ocr", &["liveOcrIntervalMs"], reload);
+}
+
+/// Start watching `rect` (replacing a running session) and translate its text into `target_lang` (default: the
+/// default target language) as it changes. `interval_ms` overrides `liveOcrIntervalMs` (default 1000); `lang`,
+/// `engine` and `options` are `ocr_image`'s. Returns the session id.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn start_live_ocr(
+  app: tauri::AppHandle,
+  rect: CaptureRect,
+  interval_ms: Option<u64>,
+  target_lang: Option<String>,
+  lang: Option<String>,
+  engine: Option<String>,
+  options: Option<ocr::TesseractOptions>,
+  on_event: Channel<LiveOcrEvent>,
+) -> Result<String, String> {
+  if rect.width == 0 || rect.height == 0 {
+    return Err("invalid rect".to_string());
+  }
+  let req = ocr::request(&app, lang, engine, None, None, options);
+  req.options.validate()?;
+  let target_lang = target_lang
+    .filter(|s| !s.trim().is_empty())
+    .unwrap_or_else(|| settings::default_target_lang(&app));
+  let interval = interval_ms
+    .or_else(|| settings::live_ocr_interval_ms(&app))
+    .unwrap_or(1000)
+    .max(MIN_INTERVAL_MS);
+
+  let session = Session {
+    id: clock::next_id("live-ocr"),
+    rect: rect.clone(),
+    target_lang: target_lang.clone(),
+    interval_ms: Arc::new(AtomicU64::new(interval)),
+    paused: Arc::new(AtomicBool::new(false)),
+    cancel: Arc::new(CancelToken::default()),
+    channel: on_event.clone(),
+  };
+  let worker = Worker {
+    app: app.clone(),
+    rect,
+    target_lang,
+    interval_ms: session.interval_ms.clone(),
+    paused: session.paused.clone(),
+    cancel: session.cancel.clone(),
+    channel: on_event,
+    req,
+  };
+  let id = session.id.clone();
+  if let Some(previous) = app.state::<LiveOcr>().lock().replace(session) {
+    stop_session(previous);
+  }
+  tauri::async_runtime::spawn(worker.run());
+  Ok(id)
+}
+
+fn set_paused(state: &LiveOcr, paused: bool) -> bool {
+  match state.lock().as_ref() {
+    Some(session) => {
+      if session.paused.swap(paused, Ordering::SeqCst) != paused {
+        let _ = session.channel.send(if paused { LiveOcrEvent::Paused } else { LiveOcrEvent::Resumed });
+      }
+      true
+    }
+    None => false,
+  }
+}
+
+/// Stop capturing without ending the session. Returns whether a session is running.
+#[tauri::command]
+pub fn pause_live_ocr(state: tauri::State<'_, LiveOcr>) -> bool {
+  set_paused(&state, true)
+}
+
+#[tauri::command]
+pub fn resume_live_ocr(state: tauri::State<'_, LiveOcr>) -> bool {
+  set_paused(&state, false)
+}
+
+/// End the session (its channel gets `stopped`). Returns whether one was running.
+#[tauri::command]
+pub fn stop_live_ocr(state: tauri::State<'_, LiveOcr>) -> bool {
+  match state.lock().take() {
+    Some(session) => {
+      stop_session(session);
+      true
+    }
+    None => false,
+  }
+}
+
+#[tauri::command]
+pub fn live_ocr_status(state: tauri::State<'_, LiveOcr>) -> Option<LiveOcrStatus> {
+  state.lock().as_ref().map(|s| LiveOcrStatus {
+    id: s.id.clone(),
+    rect: s.rect.clone(),
+    target_lang: s.target_lang.clone(),
+    interval_ms: s.interval_ms.load(Ordering::SeqCst),
+    paused: s.paused.load(Ordering::SeqCst),
+  })
+}
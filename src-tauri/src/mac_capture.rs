@@ -0,0 +1,109 @@
+//! Screen capture on macOS (CoreGraphics).
+//!
+//! Needs the Screen Recording permission (macOS 10.15+): without it the system hands back the wallpaper and menu
+//! bar only, so captures are refused up front. Regions come in physical pixels, as the app's monitors report them
+//! (each display's bounds in points times its scale), and are converted to the global display points CoreGraphics
+//! takes with the scale of the display under them; the image is taken at the display's full resolution (twice the
+//! points on Retina), which OCR reads better.
+
+use core_graphics::display::{
+  kCGNullWindowID, kCGWindowImageBestResolution, kCGWindowListOptionOnScreenOnly, CGDisplay,
+};
+use core_graphics::geometry::{CGPoint, CGRect, CGSize};
+use std::io::Write;
+
+use crate::commands::CaptureRect;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+  fn CGPreflightScreenCaptureAccess() -> bool;
+  fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// Whether the app may record the screen.
+pub fn has_permission() -> bool {
+  unsafe { CGPreflightScreenCaptureAccess() }
+}
+
+/// Ask for the Screen Recording permission (the system prompt, once; later calls point to System Settings).
+/// Returns whether it's granted; a grant takes effect after the app restarts.
+pub fn request_permission() -> bool {
+  unsafe { CGRequestScreenCaptureAccess() }
+}
+
+/// Capture `rect` to a temp PNG and return its path.
+pub fn capture(rect: &CaptureRect) -> Result<String, String> {
+  if !has_permission() {
+    return Err("SCREEN_RECORDING_PERMISSION_DENIED".to_string());
+  }
+  let bounds = to_points(rect);
+  let image = CGDisplay::screenshot(
+    bounds,
+    kCGWindowListOptionOnScreenOnly,
+    kCGNullWindowID,
+    kCGWindowImageBestResolution,
+  )
+  .ok_or_else(|| "CGWindowListCreateImage failed".to_string())?;
+  if image.bits_per_pixel() != 32 || image.bits_per_component() != 8 {
+    return Err(format!("unsupported capture format: {} bits per pixel", image.bits_per_pixel()));
+  }
+  let (width, height, stride) = (image.width(), image.height(), image.bytes_per_row());
+  let data = image.data();
+
+  let out_path = crate::temp::path("capture", "png");
+  if let Err(e) = write_png(&out_path, data.bytes(), width, height, stride) {
+    let _ = std::fs::remove_file(&out_path);
+    return Err(e);
+  }
+  Ok(out_path.to_string_lossy().to_string())
+}
+
+/// `rect` (physical pixels) in global display points, scaled by the display under its center (1 when none is).
+fn to_points(rect: &CaptureRect) -> CGRect {
+  let (cx, cy) = (
+    rect.x as f64 + rect.width as f64 / 2.0,
+    rect.y as f64 + rect.height as f64 / 2.0,
+  );
+  let scale = CGDisplay::active_displays()
+    .unwrap_or_default()
+    .into_iter()
+    .map(CGDisplay::new)
+    .find_map(|display| {
+      let mode = display.display_mode()?;
+      let scale = mode.pixel_width() as f64 / mode.width().max(1) as f64;
+      let b = display.bounds();
+      let (x, y) = (b.origin.x * scale, b.origin.y * scale);
+      let inside = cx >= x && cy >= y && cx < x + b.size.width * scale && cy < y + b.size.height * scale;
+      inside.then_some(scale)
+    })
+    .unwrap_or(1.0);
+  CGRect::new(
+    &CGPoint::new(rect.x as f64 / scale, rect.y as f64 / scale),
+    &CGSize::new(rect.width as f64 / scale, rect.height as f64 / scale),
+  )
+}
+
+/// Encode 32-bit BGRA rows (the display's native layout) as an RGB PNG.
+fn write_png(out_path: &std::path::Path, bgra: &[u8], width: usize, height: usize, stride: usize) -> Result<(), String> {
+  if bgra.len() < stride * height {
+    return Err("capture buffer too small".to_string());
+  }
+  let file = std::fs::File::create(out_path).map_err(|e| format!("create png failed: {e}"))?;
+  let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width as u32, height as u32);
+  encoder.set_color(png::ColorType::Rgb);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header().map_err(|e| format!("png header failed: {e}"))?;
+  let mut stream = writer.stream_writer().map_err(|e| format!("png write failed: {e}"))?;
+  let mut row_rgb = vec![0u8; width * 3];
+  for row in bgra.chunks_exact(stride).take(height) {
+    for (dst, px) in row_rgb.chunks_exact_mut(3).zip(row.chunks_exact(4)) {
+      dst[0] = px[2];
+      dst[1] = px[1];
+      dst[2] = px[0];
+    }
+    stream.write_all(&row_rgb).map_err(|e| format!("png write failed: {e}"))?;
+  }
+  stream.finish().map_err(|e| format!("png write failed: {e}"))?;
+  writer.finish().map_err(|e| format!("png write failed: {e}"))?;
+  Ok(())
+}
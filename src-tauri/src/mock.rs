@@ -0,0 +1,91 @@
+//! Mock provider and OCR engine for the `deterministic` feature.
+//!
+//! Providers are mocked when the base URL (or tesseract path) uses the `mock://` scheme. Responses are
+//! replayed from `$ERUDAITE_FIXTURES` when the matching fixture file exists, otherwise canned output is used:
+//! - `translate.sse`: raw SSE lines for `translate_sse`
+//! - `detect.json`: response body for `detect_language`
+//! - `ocr.txt`: text returned by OCR
+//! - `capture.png`: image returned by `capture_screen_region`
+
+use crate::clock;
+use crate::commands::{CaptureRect, DetectResult};
+
+pub const SCHEME: &str = "mock://";
+
+pub fn is_mock(s: &str) -> bool {
+  s.trim().starts_with(SCHEME)
+}
+
+fn fixture_path(name: &str) -> Option<std::path::PathBuf> {
+  let dir = std::env::var("ERUDAITE_FIXTURES").ok()?;
+  let p = std::path::PathBuf::from(dir).join(name);
+  p.is_file().then_some(p)
+}
+
+fn fixture(name: &str) -> Option<String> {
+  std::fs::read_to_string(fixture_path(name)?).ok()
+}
+
+pub fn translate_sse_lines(text: &str, target_lang: &str) -> Vec<String> {
+  if let Some(s) = fixture("translate.sse") {
+    return s.lines().map(|l| l.to_string()).collect();
+  }
+  let content = format!("[{}] {}", target_lang, text);
+  vec![
+    format!("data: {}", serde_json::json!({ "content": content })),
+    "data: [DONE]".to_string(),
+  ]
+}
+
+pub fn detect_language(_text: &str) -> DetectResult {
+  let v: serde_json::Value = fixture("detect.json")
+    .and_then(|s| serde_json::from_str(&s).ok())
+    .unwrap_or_else(|| serde_json::json!({}));
+  DetectResult {
+    detected_lang: v
+      .get("detected_lang")
+      .and_then(|x| x.as_str())
+      .unwrap_or("English")
+      .to_string(),
+    confidence: v.get("confidence").and_then(|x| x.as_f64()).unwrap_or(1.0),
+    is_mixed: v.get("is_mixed").and_then(|x| x.as_bool()).unwrap_or(false),
+    engine: "mock".to_string(),
+  }
+}
+
+pub fn ocr_text() -> String {
+  fixture("ocr.txt").unwrap_or_else(|| "mock ocr text".to_string()).trim().to_string()
+}
+
+/// Copy the capture fixture (or write a blank PNG of the requested size) into the temp area.
+pub fn capture_png(rect: &CaptureRect) -> Result<String, String> {
+  if rect.width == 0 || rect.height == 0 {
+    return Err("invalid rect".to_string());
+  }
+  let out_path = crate::temp::path("capture", "png");
+  if let Some(src) = fixture_path("capture.png") {
+    std::fs::copy(&src, &out_path).map_err(|e| format!("copy fixture failed: {e}"))?;
+    return Ok(out_path.to_string_lossy().to_string());
+  }
+  let file = std::fs::File::create(&out_path).map_err(|e| format!("create png failed: {e}"))?;
+  let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), rect.width, rect.height);
+  encoder.set_color(png::ColorType::Rgba);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header().map_err(|e| format!("png header failed: {e}"))?;
+  writer
+    .write_image_data(&vec![255u8; rect.width as usize * rect.height as usize * 4])
+    .map_err(|e| format!("png write failed: {e}"))?;
+  Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Set the fake clock and reset the ID sequence to `seed`.
+#[tauri::command]
+pub fn deterministic_reset(now_ms: Option<u64>, seed: Option<u64>) {
+  clock::set_now(now_ms.unwrap_or(1_700_000_000_000));
+  clock::reset_ids(seed.unwrap_or(0));
+}
+
+#[tauri::command]
+pub fn deterministic_advance_clock(ms: u64) {
+  clock::advance(ms);
+}
@@ -0,0 +1,77 @@
+//! Mouse triggers: hotkey actions bound to the middle button or the side buttons (mouse 4/5), for keyboards
+//! without convenient free hotkeys.
+//!
+//! `mouseTriggers` maps a button (`middle`, `back`, `forward`) to a hotkey action (`translate`, `ocr`, ...).
+//! Buttons come from `input_hook`'s mouse hook, installed only while a button is bound (Windows only; bindings
+//! fail the `mouse_triggers` reload elsewhere). A bound side button is claimed, so the app under the pointer
+//! doesn't also go back or forward. The middle button is left alone, since it pastes, scrolls and opens links,
+//! and when bound to `translate` it only translates when something is selected: a middle click on nothing says
+//! nothing. Paused or suspended hotkeys pause the triggers too.
+
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::hotkeys::{self, Action};
+use crate::input_hook::{self, MouseButton};
+use crate::{agent, settings};
+
+#[derive(Default)]
+pub struct MouseTriggers(Mutex<Vec<(MouseButton, Action)>>);
+
+impl MouseTriggers {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Vec<(MouseButton, Action)>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+fn binding(app: &tauri::AppHandle, button: MouseButton) -> Option<Action> {
+  app.state::<MouseTriggers>().lock().iter().find(|(b, _)| *b == button).map(|(_, a)| *a)
+}
+
+/// A bound button was pressed or released (`input_hook`, on its thread); true when the press is claimed.
+pub fn on_button(app: &tauri::AppHandle, button: MouseButton, pressed: bool) -> bool {
+  let Some(action) = binding(app, button) else {
+    return false;
+  };
+  if hotkeys::held(app) {
+    return false;
+  }
+  if pressed {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+      match (button, action) {
+        (MouseButton::Middle, Action::Translate) => agent::run_action(&app, "translate_if_selected"),
+        _ => hotkeys::run(&app, action),
+      }
+    });
+  }
+  // Claim the release too, or the app sees an unmatched button-up.
+  button != MouseButton::Middle
+}
+
+/// Whether any button is bound and the mouse hook is wanted.
+pub fn is_active(app: &tauri::AppHandle) -> bool {
+  cfg!(windows) && !app.state::<MouseTriggers>().lock().is_empty()
+}
+
+fn load(app: &tauri::AppHandle) -> Result<(), String> {
+  let bindings = settings::mouse_triggers(app);
+  let bound = !bindings.is_empty();
+  *app.state::<MouseTriggers>().lock() = bindings;
+  if bound && !cfg!(windows) {
+    return Err("mouse triggers are only supported on Windows".to_string());
+  }
+  input_hook::sync(app)
+}
+
+fn reload(app: &tauri::AppHandle, _: &settings::ConfigChange) -> Result<(), String> {
+  load(app)
+}
+
+/// Hook the mouse if a button is bound, and follow `mouseTriggers` (`setup`).
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "mouse_triggers", &["mouseTriggers"], reload);
+  if let Err(e) = load(app) {
+    crate::events::warn(app, None, "mouse_triggers_unavailable", e);
+  }
+}
@@ -0,0 +1,1289 @@
+//! OCR engines behind one interface.
+//!
+//! Engines: the Tesseract binary (`external`), in-process Tesseract (`embedded`, feature-gated), Windows OCR
+//! (`windows`), Apple Vision (`vision`) and a PaddleOCR / EasyOCR server (`remote`). `ocr_image` runs the engine
+//! named by `ocrBackend`; when that one isn't available here or has no data for the language, the next capable
+//! engine runs instead. "auto" tries the OS's own OCR first, then in-process Tesseract, then the binary.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::events;
+use crate::clock;
+use crate::commands::CaptureRect;
+use crate::osd::{self, Osd};
+use crate::queue::CancelToken;
+use crate::settings;
+use tauri::ipc::Channel;
+use tauri::Manager;
+use tokio::io::AsyncWriteExt;
+
+pub trait OcrEngine: Send {
+  /// Engine name, as used by the `ocrBackend` setting.
+  fn id(&self) -> &'static str;
+  /// Whether the engine can run on this machine at all.
+  fn is_available(&self) -> bool;
+  /// Installed languages, in the engine's own codes.
+  fn languages(&self) -> Result<Vec<String>, String>;
+  /// Whether the engine can recognize `lang` (Tesseract codes, `+`-separated).
+  fn supports(&self, lang: &str) -> bool {
+    self
+      .languages()
+      .map(|installed| lang.split('+').all(|code| installed.iter().any(|l| l == code.trim())))
+      .unwrap_or(false)
+  }
+  /// OCR an image. Blocking; engines that run a helper process kill it when `cancel` fires.
+  fn recognize(&self, image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String>;
+  /// Whether the engine reads vertical CJK text (`TesseractOptions::vertical`).
+  fn reads_vertical(&self) -> bool {
+    false
+  }
+  /// Orientation and script of an image, for engines that can detect them. Blocking.
+  fn osd(&self, _image: &OcrImage, _cancel: &CancelToken) -> Result<Option<Osd>, String> {
+    Ok(None)
+  }
+}
+
+/// The image to OCR: a file, or encoded image data (PNG, JPEG, ...) that engines read without a temp file.
+#[derive(Debug, Clone)]
+pub enum OcrImage {
+  Path(String),
+  Bytes(Vec<u8>),
+}
+
+impl OcrImage {
+  /// The encoded image (reads the file).
+  pub fn data(&self) -> Result<Cow<'_, [u8]>, String> {
+    match self {
+      OcrImage::Path(p) => std::fs::read(p).map(Cow::Owned).map_err(|e| format!("cannot open image: {e}")),
+      OcrImage::Bytes(b) => Ok(Cow::Borrowed(b)),
+    }
+  }
+
+  /// Input argument for a helper that reads `-`/`stdin` for piped data, and the data to pipe.
+  pub fn process_input<'a>(&'a self, stdin_arg: &'a str) -> (&'a str, Option<&'a [u8]>) {
+    match self {
+      OcrImage::Path(p) => (p, None),
+      OcrImage::Bytes(b) => (stdin_arg, Some(b)),
+    }
+  }
+}
+
+/// A region of the OCR'd image, in image pixels.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BBox {
+  pub x: i32,
+  pub y: i32,
+  pub width: i32,
+  pub height: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OcrWord {
+  pub text: String,
+  pub bbox: BBox,
+  /// 0-100, when the engine reports one.
+  pub confidence: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OcrLine {
+  pub text: String,
+  pub bbox: BBox,
+  /// 0-100, when the engine reports one.
+  pub confidence: Option<f32>,
+  pub words: Vec<OcrWord>,
+}
+
+/// Mean of the scored items' confidences, weighted by text length.
+fn weighted_confidence<'a>(items: impl Iterator<Item = (Option<f32>, &'a str)>) -> Option<f32> {
+  let (sum, total) = items
+    .filter_map(|(c, text)| Some((c?, text.chars().count() as f32)))
+    .fold((0.0, 0.0), |(sum, total), (c, n)| (sum + c * n, total + n));
+  (total > 0.0).then(|| sum / total)
+}
+
+/// What an engine recognized: the plain text and its lines with positions.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct OcrOutput {
+  pub text: String,
+  pub lines: Vec<OcrLine>,
+}
+
+impl OcrOutput {
+  /// Overall confidence; `None` when the engine scores nothing.
+  pub fn confidence(&self) -> Option<f32> {
+    weighted_confidence(self.lines.iter().map(|l| (l.confidence, l.text.as_str())))
+  }
+}
+
+#[cfg(feature = "deterministic")]
+impl OcrOutput {
+  /// Text without positions (mock fixtures).
+  pub fn plain(text: String) -> OcrOutput {
+    let lines = text
+      .lines()
+      .filter(|l| !l.trim().is_empty())
+      .map(|l| OcrLine {
+        text: l.to_string(),
+        bbox: BBox::default(),
+        confidence: None,
+        words: Vec::new(),
+      })
+      .collect();
+    OcrOutput { text, lines }
+  }
+}
+
+/// Parse Tesseract's TSV output (`level page block par line word left top width height conf text`). Text is
+/// rebuilt the way Tesseract's plain output lays it out: a line per line, a blank line between paragraphs.
+pub fn parse_tsv(tsv: &str) -> OcrOutput {
+  // Keyed by (block, paragraph, line).
+  let mut lines: Vec<((i32, i32, i32), OcrLine)> = Vec::new();
+  for row in tsv.lines() {
+    let cols: Vec<&str> = row.split('\t').collect();
+    let num = |i: usize| cols.get(i).and_then(|c| c.trim().parse::<i32>().ok());
+    // The header (CLI output only) has no numeric level.
+    let (Some(level), Some(block), Some(par), Some(line)) = (num(0), num(2), num(3), num(4)) else {
+      continue;
+    };
+    let bbox = BBox {
+      x: num(6).unwrap_or(0),
+      y: num(7).unwrap_or(0),
+      width: num(8).unwrap_or(0),
+      height: num(9).unwrap_or(0),
+    };
+    match level {
+      4 => lines.push((
+        (block, par, line),
+        OcrLine {
+          text: String::new(),
+          bbox,
+          confidence: None,
+          words: Vec::new(),
+        },
+      )),
+      5 => {
+        let word = cols.get(11).map(|w| w.trim()).unwrap_or_default();
+        if word.is_empty() {
+          continue;
+        }
+        if let Some((_, l)) = lines.last_mut().filter(|(key, _)| *key == (block, par, line)) {
+          if !l.text.is_empty() {
+            l.text.push(' ');
+          }
+          l.text.push_str(word);
+          l.words.push(OcrWord {
+            text: word.to_string(),
+            bbox,
+            // -1 for words Tesseract didn't score.
+            confidence: cols.get(10).and_then(|c| c.trim().parse::<f32>().ok()).filter(|c| *c >= 0.0),
+          });
+        }
+      }
+      _ => {}
+    }
+  }
+  lines.retain(|(_, l)| !l.words.is_empty());
+  for (_, l) in lines.iter_mut() {
+    l.confidence = weighted_confidence(l.words.iter().map(|w| (w.confidence, w.text.as_str())));
+  }
+
+  let mut text = String::new();
+  for (i, ((block, par, _), l)) in lines.iter().enumerate() {
+    if i > 0 {
+      let (prev_block, prev_par, _) = lines[i - 1].0;
+      text.push_str(if (prev_block, prev_par) == (*block, *par) { "\n" } else { "\n\n" });
+    }
+    text.push_str(&l.text);
+  }
+  OcrOutput {
+    text,
+    lines: lines.into_iter().map(|(_, l)| l).collect(),
+  }
+}
+
+/// BCP-47 tag for a Tesseract language code; anything else is passed through as a tag.
+#[cfg(any(windows, target_os = "macos"))]
+pub fn to_bcp47(code: &str) -> &str {
+  match code {
+    "eng" => "en-US",
+    "jpn" | "jpn_vert" => "ja",
+    "chi_sim" | "chi_sim_vert" => "zh-Hans",
+    "chi_tra" | "chi_tra_vert" => "zh-Hant",
+    "kor" | "kor_vert" => "ko",
+    "deu" => "de",
+    "fra" => "fr",
+    "spa" => "es",
+    "ita" => "it",
+    "por" => "pt",
+    "rus" => "ru",
+    "nld" => "nl",
+    "pol" => "pl",
+    "tur" => "tr",
+    "ukr" => "uk",
+    "ara" => "ar",
+    other => other,
+  }
+}
+
+/// Tesseract tuning for a capture (e.g. `psm: 7` for a single line). Engines other than Tesseract ignore it.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TesseractOptions {
+  /// Page segmentation mode (`--psm`, 0-13).
+  pub psm: Option<u8>,
+  /// OCR engine mode (`--oem`, 0-3). Only the binary honors it; the in-process engine is initialized once.
+  pub oem: Option<u8>,
+  /// Resolution hint for images without one (`--dpi`, 70-2400).
+  pub dpi: Option<u32>,
+  /// Extra config variables (`-c name=value`).
+  pub variables: BTreeMap<String, String>,
+  /// Vertical CJK text (manga, many games): OCR with the `_vert` traineddata and PSM 5 unless `psm` is set, and
+  /// return the columns right to left.
+  pub vertical: bool,
+  /// Words Tesseract should prefer over dictionary look-alikes (names, jargon), one per entry (`--user-words`).
+  /// Only the binary honors it and `user_patterns`: Tesseract loads both at initialization, before variables are set.
+  pub user_words: Vec<String>,
+  /// Patterns for terms like part numbers (`--user-patterns`), e.g. `\d\d\d-\A\A`.
+  pub user_patterns: Vec<String>,
+}
+
+impl TesseractOptions {
+  pub fn validate(&self) -> Result<(), String> {
+    if self.psm.is_some_and(|p| p > 13) {
+      return Err("invalid psm: expected 0-13".to_string());
+    }
+    if self.oem.is_some_and(|o| o > 3) {
+      return Err("invalid oem: expected 0-3".to_string());
+    }
+    if self.dpi.is_some_and(|d| !(70..=2400).contains(&d)) {
+      return Err("invalid dpi: expected 70-2400".to_string());
+    }
+    if let Some(name) = self
+      .variables
+      .keys()
+      .find(|k| k.is_empty() || !k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    {
+      return Err(format!("invalid config variable name: {name:?}"));
+    }
+    if let Some(entry) = self.user_words.iter().chain(&self.user_patterns).find(|e| e.contains(['\n', '\r'])) {
+      return Err(format!("invalid user word or pattern: {entry:?}"));
+    }
+    Ok(())
+  }
+
+  /// Config variables to set (`-c`), with `psm` and `dpi` as the variables behind their CLI flags.
+  pub fn config_variables(&self) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    // 5: a single block of vertically aligned text.
+    if let Some(psm) = self.psm.or(self.vertical.then_some(5)) {
+      vars.push(("tessedit_pageseg_mode".to_string(), psm.to_string()));
+    }
+    if let Some(dpi) = self.dpi {
+      vars.push(("user_defined_dpi".to_string(), dpi.to_string()));
+    }
+    vars.extend(self.variables.iter().map(|(k, v)| (k.clone(), v.clone())));
+    vars
+  }
+}
+
+/// `user_words` and `user_patterns` written out for the binary; the files are removed on drop.
+struct UserFiles(Vec<(&'static str, std::path::PathBuf)>);
+
+impl UserFiles {
+  fn write(options: &TesseractOptions) -> Result<Self, String> {
+    let mut files = UserFiles(Vec::new());
+    for (flag, prefix, entries) in [
+      ("--user-words", "user-words", &options.user_words),
+      ("--user-patterns", "user-patterns", &options.user_patterns),
+    ] {
+      let mut lines: Vec<&str> = entries.iter().map(|e| e.trim()).filter(|e| !e.is_empty()).collect();
+      lines.sort_unstable();
+      lines.dedup();
+      if lines.is_empty() {
+        continue;
+      }
+      let path = crate::temp::path(prefix, "txt");
+      std::fs::write(&path, lines.join("\n") + "\n").map_err(|e| format!("cannot write {prefix} file: {e}"))?;
+      files.0.push((flag, path));
+    }
+    Ok(files)
+  }
+
+  fn args(&self) -> impl Iterator<Item = &std::ffi::OsStr> {
+    self.0.iter().flat_map(|(flag, path)| [std::ffi::OsStr::new(flag), path.as_os_str()])
+  }
+}
+
+impl Drop for UserFiles {
+  fn drop(&mut self) {
+    for (_, path) in &self.0 {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+}
+
+/// `lang` with the languages that have vertical traineddata switched to it (`"jpn+eng"` -> `"jpn_vert+eng"`).
+fn vertical_lang(lang: &str) -> String {
+  lang
+    .split('+')
+    .map(|code| match code.trim() {
+      c @ ("jpn" | "chi_sim" | "chi_tra" | "kor") => format!("{c}_vert"),
+      c => c.to_string(),
+    })
+    .collect::<Vec<_>>()
+    .join("+")
+}
+
+/// Put vertical text in reading order: columns right to left (top to bottom for columns side by side), one per
+/// line of `text`.
+fn order_columns(output: &mut OcrOutput) {
+  output
+    .lines
+    .sort_by_key(|l| (std::cmp::Reverse(l.bbox.x + l.bbox.width), l.bbox.y));
+  output.text = output.lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+}
+
+/// Run a helper process to completion with `input` on its stdin, killing it if `cancel` fires (then fails with
+/// `CANCELLED`). Blocking.
+pub fn run_process(
+  cmd: std::process::Command,
+  input: Option<&[u8]>,
+  cancel: &CancelToken,
+) -> Result<std::process::Output, String> {
+  let mut cmd = tokio::process::Command::from(cmd);
+  cmd
+    .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true);
+  tauri::async_runtime::block_on(async {
+    let mut child = cmd.spawn().map_err(|e| e.to_string())?;
+    let stdin = child.stdin.take();
+    // Written alongside reading the output, so a helper that answers before reading everything can't block us.
+    let write = async move {
+      if let (Some(mut stdin), Some(data)) = (stdin, input) {
+        // A helper that exits early closes the pipe; its own error is the one to report.
+        let _ = stdin.write_all(data).await;
+      }
+    };
+    tokio::select! {
+      (_, output) = async { tokio::join!(write, child.wait_with_output()) } => output.map_err(|e| e.to_string()),
+      // Dropping the child kills it.
+      _ = cancel.cancelled() => Err("CANCELLED".to_string()),
+    }
+  })
+}
+
+/// The `tesseract` binary.
+struct ExternalTesseract {
+  exe: Option<String>,
+  tessdata_prefix: Option<String>,
+  options: TesseractOptions,
+}
+
+impl ExternalTesseract {
+  fn command(&self) -> Result<std::process::Command, String> {
+    let exe = self.exe.as_deref().ok_or_else(|| "TESSERACT_NOT_FOUND".to_string())?;
+    let mut cmd = std::process::Command::new(exe);
+    if let Some(prefix) = self
+      .tessdata_prefix
+      .as_deref()
+      .filter(|s| !s.trim().is_empty())
+      .map(crate::commands::normalize_tessdata_prefix)
+    {
+      cmd.env("TESSDATA_PREFIX", prefix);
+    }
+    Ok(cmd)
+  }
+}
+
+impl OcrEngine for ExternalTesseract {
+  fn id(&self) -> &'static str {
+    "external"
+  }
+
+  fn is_available(&self) -> bool {
+    self.exe.is_some()
+  }
+
+  fn languages(&self) -> Result<Vec<String>, String> {
+    let out = self
+      .command()?
+      .arg("--list-langs")
+      .output()
+      .map_err(|e| format!("failed to list langs: {e}"))?;
+    if !out.status.success() {
+      return Err(format!("list langs failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    let s = String::from_utf8_lossy(&out.stdout);
+    let mut langs: Vec<String> = Vec::new();
+    for line in s.lines() {
+      let t = line.trim();
+      if t.is_empty() {
+        continue;
+      }
+      if t.starts_with("List of available languages") {
+        continue;
+      }
+      langs.push(t.to_string());
+    }
+    Ok(langs)
+  }
+
+  fn recognize(&self, image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String> {
+    let (input, data) = image.process_input("stdin");
+    let user_files = UserFiles::write(&self.options)?;
+    let mut cmd = self.command()?;
+    cmd
+      .arg(input)
+      .arg("stdout")
+      .arg("-l")
+      .arg(lang)
+      // Tesseract's option parser takes `--oem N`, not `--oem=N`.
+      .args(self.options.oem.into_iter().flat_map(|o| ["--oem".to_string(), o.to_string()]))
+      .args(user_files.args())
+      .args(self.options.config_variables().into_iter().flat_map(|(k, v)| ["-c".to_string(), format!("{k}={v}")]))
+      .arg("tsv");
+    let output = run_process(cmd, data, cancel).map_err(|e| format!("failed to run tesseract: {e}"))?;
+
+    if !output.status.success() {
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      let msg = stderr.trim().to_string();
+      // If a language traineddata is missing, tesseract prints an "Error opening data file" message.
+      if msg.contains("Error opening data file") || msg.contains("Failed loading language") {
+        return Err(format!("TESSDATA_MISSING\n\n{}", msg));
+      }
+      return Err(format!("tesseract failed: {}", msg));
+    }
+    Ok(parse_tsv(&String::from_utf8_lossy(&output.stdout)))
+  }
+
+  fn reads_vertical(&self) -> bool {
+    true
+  }
+
+  fn osd(&self, image: &OcrImage, cancel: &CancelToken) -> Result<Option<Osd>, String> {
+    let (input, data) = image.process_input("stdin");
+    let mut cmd = self.command()?;
+    cmd.arg(input).arg("stdout").args(["--psm", "0", "-l", "osd"]);
+    let output = run_process(cmd, data, cancel).map_err(|e| format!("failed to run tesseract: {e}"))?;
+    let report = String::from_utf8_lossy(&output.stdout);
+    match osd::parse(&report) {
+      Some(osd) => Ok(Some(osd)),
+      // Missing osd.traineddata, or too little text to tell ("Too few characters").
+      None => Err(format!("orientation detection failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+    }
+  }
+}
+
+#[cfg(feature = "embedded-tesseract")]
+struct EmbeddedTesseract {
+  tessdata_prefix: Option<String>,
+  options: TesseractOptions,
+}
+
+#[cfg(feature = "embedded-tesseract")]
+impl OcrEngine for EmbeddedTesseract {
+  fn id(&self) -> &'static str {
+    "embedded"
+  }
+
+  fn is_available(&self) -> bool {
+    true
+  }
+
+  fn languages(&self) -> Result<Vec<String>, String> {
+    Ok(crate::tesseract_embedded::list_langs(self.tessdata_prefix.as_deref()))
+  }
+
+  fn reads_vertical(&self) -> bool {
+    true
+  }
+
+  fn recognize(&self, image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String> {
+    // Tesseract can't be interrupted in-process; a cancelled job just stops waiting for it.
+    let _ = cancel;
+    crate::tesseract_embedded::recognize(image, lang, self.tessdata_prefix.as_deref(), &self.options)
+  }
+}
+
+#[cfg(windows)]
+struct WindowsOcr;
+
+#[cfg(windows)]
+impl OcrEngine for WindowsOcr {
+  fn id(&self) -> &'static str {
+    "windows"
+  }
+
+  fn is_available(&self) -> bool {
+    true
+  }
+
+  fn languages(&self) -> Result<Vec<String>, String> {
+    Ok(crate::winrt_ocr::languages()?.into_iter().map(|l| l.tag).collect())
+  }
+
+  fn supports(&self, lang: &str) -> bool {
+    crate::winrt_ocr::supports(lang)
+  }
+
+  fn recognize(&self, image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String> {
+    let _ = cancel;
+    crate::winrt_ocr::recognize(image, lang)
+  }
+}
+
+#[cfg(target_os = "macos")]
+struct VisionOcr;
+
+#[cfg(target_os = "macos")]
+impl OcrEngine for VisionOcr {
+  fn id(&self) -> &'static str {
+    "vision"
+  }
+
+  fn is_available(&self) -> bool {
+    crate::vision_ocr::languages().is_ok_and(|l| !l.is_empty())
+  }
+
+  fn languages(&self) -> Result<Vec<String>, String> {
+    crate::vision_ocr::languages()
+  }
+
+  fn supports(&self, lang: &str) -> bool {
+    crate::vision_ocr::supports(lang)
+  }
+
+  fn recognize(&self, image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String> {
+    crate::vision_ocr::recognize(image, lang, cancel)
+  }
+}
+
+/// A PaddleOCR / EasyOCR server (see `remote_ocr`).
+struct RemoteOcr {
+  url: Option<String>,
+  /// Languages the server's models read (`remoteOcrLangs`); empty trusts it with any.
+  langs: Vec<String>,
+}
+
+impl RemoteOcr {
+  fn from_settings(app: &tauri::AppHandle) -> Self {
+    RemoteOcr {
+      url: settings::remote_ocr_url(app),
+      langs: settings::remote_ocr_langs(app),
+    }
+  }
+}
+
+impl OcrEngine for RemoteOcr {
+  fn id(&self) -> &'static str {
+    "remote"
+  }
+
+  fn is_available(&self) -> bool {
+    self.url.is_some()
+  }
+
+  fn languages(&self) -> Result<Vec<String>, String> {
+    Ok(self.langs.clone())
+  }
+
+  fn supports(&self, lang: &str) -> bool {
+    self.langs.is_empty() || lang.split('+').all(|code| self.langs.iter().any(|l| l == code.trim()))
+  }
+
+  fn recognize(&self, image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String> {
+    let url = self.url.as_deref().ok_or_else(|| "REMOTE_OCR_URL_NOT_SET".to_string())?;
+    crate::remote_ocr::recognize(url, image, lang, cancel)
+  }
+}
+
+/// Engines built into this binary, in the order "auto" tries them. The OCR server comes last: it's there for
+/// those who pick it.
+#[allow(clippy::vec_init_then_push)] // which pushes exist depends on the platform and features
+fn engines(
+  exe: Option<String>,
+  tessdata_prefix: Option<String>,
+  options: TesseractOptions,
+  remote: RemoteOcr,
+) -> Vec<Box<dyn OcrEngine>> {
+  let mut all: Vec<Box<dyn OcrEngine>> = Vec::new();
+  #[cfg(windows)]
+  all.push(Box::new(WindowsOcr));
+  #[cfg(target_os = "macos")]
+  all.push(Box::new(VisionOcr));
+  #[cfg(feature = "embedded-tesseract")]
+  all.push(Box::new(EmbeddedTesseract {
+    tessdata_prefix: tessdata_prefix.clone(),
+    options: options.clone(),
+  }));
+  all.push(Box::new(ExternalTesseract {
+    exe,
+    tessdata_prefix,
+    options,
+  }));
+  all.push(Box::new(remote));
+  all
+}
+
+/// The configured Tesseract binary, else one found in the usual install locations.
+async fn resolve_tesseract(tesseract_path: Option<String>) -> Result<Option<String>, String> {
+  match tesseract_path.filter(|s| !s.trim().is_empty()) {
+    Some(p) => Ok(Some(p)),
+    None => crate::commands::detect_tesseract_path().await,
+  }
+}
+
+/// Pick the engine for `lang` (and `vertical` text): `preferred` if it can handle it, else the first capable one
+/// in "auto" order. With nothing capable, the Tesseract binary still runs so its missing-data error reaches the
+/// user. Blocking.
+fn select(
+  app: &tauri::AppHandle,
+  all: &mut Vec<Box<dyn OcrEngine>>,
+  preferred: &str,
+  lang: &str,
+  vertical: bool,
+) -> Result<Box<dyn OcrEngine>, String> {
+  if let Some(i) = all.iter().position(|e| e.id() == preferred) {
+    let engine = all.remove(i);
+    all.insert(0, engine);
+  }
+  let capable = |e: &dyn OcrEngine| e.is_available() && (!vertical || e.reads_vertical()) && e.supports(lang);
+  let chosen = match all.iter().position(|e| capable(e.as_ref())) {
+    Some(i) => i,
+    None => all
+      .iter()
+      .position(|e| e.id() == "external" && e.is_available())
+      .ok_or_else(|| "TESSERACT_NOT_FOUND".to_string())?,
+  };
+  let engine = all.remove(chosen);
+  if preferred != "auto" && engine.id() != preferred {
+    events::warn(
+      app,
+      None,
+      "ocr_engine_fallback",
+      format!("{preferred} OCR can't handle {lang} here; used {} instead", engine.id()),
+    );
+  }
+  Ok(engine)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OcrResult {
+  /// Text to translate (reflowed unless disabled).
+  pub text: String,
+  /// The engine's output as-is.
+  pub raw_text: String,
+  pub reflowed: bool,
+  /// Engine that produced the text.
+  pub engine: String,
+  /// Recognized lines and words with their positions in the image (for highlighting and picking lines).
+  pub lines: Vec<OcrLine>,
+  /// Overall confidence (0-100), when the engine reports one.
+  pub confidence: Option<f32>,
+  /// Whether `confidence` is below `ocrMinConfidence`.
+  pub low_confidence: bool,
+  /// Tesseract language codes OCR ran with (detected when "auto" was asked for).
+  pub lang: String,
+  /// Clockwise degrees the image was turned before OCR (boxes are still in the original's coordinates).
+  pub rotation: u16,
+}
+
+/// The engine `id` from what's left after `select`, if it can read `lang` here; warns when it can't.
+fn second_engine(app: &tauri::AppHandle, all: Vec<Box<dyn OcrEngine>>, id: &str, lang: &str) -> Option<Box<dyn OcrEngine>> {
+  let found = all.into_iter().find(|e| e.id() == id && e.is_available() && e.supports(lang));
+  if found.is_none() {
+    events::warn(app, None, "ocr_merge_unavailable", format!("{id} OCR can't read {lang} here; used one engine"));
+  }
+  found
+}
+
+/// Resolve `lang` = "auto" with the first engine that detects orientation and script: returns the language to
+/// OCR with and the clockwise rotation that makes the image upright. Blocking.
+fn detect(
+  app: &tauri::AppHandle,
+  all: &[Box<dyn OcrEngine>],
+  image: &OcrImage,
+  cancel: &CancelToken,
+) -> (String, u16) {
+  for engine in all.iter().filter(|e| e.is_available()) {
+    match engine.osd(image, cancel) {
+      Ok(None) => continue,
+      Ok(Some(found)) => {
+        let installed = engine.languages().unwrap_or_default();
+        let lang = osd::lang_for(&found, &installed).unwrap_or_else(|| osd::FALLBACK_LANG.to_string());
+        return (lang, osd::rotation(&found));
+      }
+      Err(e) => {
+        if !cancel.is_cancelled() {
+          events::warn(app, None, "ocr_detect_failed", format!("{e}; using {}", osd::FALLBACK_LANG));
+        }
+        break;
+      }
+    }
+  }
+  (osd::FALLBACK_LANG.to_string(), 0)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrStage {
+  /// Detecting script and orientation (`lang` = "auto").
+  Detecting,
+  /// Turning the image upright.
+  Preprocessing,
+  /// The engine is recognizing text.
+  Running,
+  /// Mapping boxes back and cleaning up the text.
+  Parsing,
+}
+
+/// Progress of an `ocr_image` call, streamed through its `on_event` channel.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum OcrEvent {
+  #[serde(rename = "stage")]
+  Stage { stage: OcrStage },
+  /// `done` of `total` regions recognized.
+  #[serde(rename = "progress")]
+  Progress { done: usize, total: usize },
+  #[serde(rename = "done")]
+  Done,
+  #[serde(rename = "error")]
+  Error { message: String },
+}
+
+fn emit(on_event: Option<&Channel<OcrEvent>>, event: OcrEvent) {
+  if let Some(channel) = on_event {
+    let _ = channel.send(event);
+  }
+}
+
+struct Recognized {
+  engine: String,
+  output: OcrOutput,
+  lang: String,
+  rotation: u16,
+}
+
+/// How to OCR an image.
+#[derive(Clone)]
+pub struct OcrRequest {
+  /// Tesseract codes (`"jpn+eng"`) or "auto".
+  pub lang: String,
+  /// Engine to prefer (`ocrBackend` value).
+  pub preferred: String,
+  pub tesseract_path: Option<String>,
+  pub tessdata_prefix: Option<String>,
+  pub options: TesseractOptions,
+  /// A second engine to run alongside and merge with (`ocrMergeEngine`).
+  pub merge_with: Option<String>,
+}
+
+/// Detect the language and orientation if asked to, then run the selected engine. Fails with `CANCELLED` as soon
+/// as `cancel` fires.
+async fn recognize(
+  app: &tauri::AppHandle,
+  image: OcrImage,
+  req: OcrRequest,
+  cancel: Arc<CancelToken>,
+  on_event: Option<&Channel<OcrEvent>>,
+) -> Result<Recognized, String> {
+  let OcrRequest {
+    lang,
+    preferred,
+    tesseract_path,
+    tessdata_prefix,
+    options,
+    merge_with,
+  } = req;
+  options.validate()?;
+
+  #[cfg(feature = "deterministic")]
+  if tesseract_path.as_deref().is_some_and(crate::mock::is_mock) {
+    let _ = (image, preferred, tessdata_prefix, options, merge_with, cancel);
+    emit(on_event, OcrEvent::Progress { done: 1, total: 1 });
+    return Ok(Recognized {
+      engine: "mock".to_string(),
+      output: OcrOutput::plain(crate::mock::ocr_text()),
+      lang,
+      rotation: 0,
+    });
+  }
+
+  let exe = resolve_tesseract(tesseract_path).await?;
+  let app = app.clone();
+  let token = cancel.clone();
+  let channel = on_event.cloned();
+  let job = tauri::async_runtime::spawn_blocking(move || {
+    let cancel = token;
+    let on_event = channel.as_ref();
+    let vertical = options.vertical;
+    let mut all = engines(exe, tessdata_prefix, options, RemoteOcr::from_settings(&app));
+    let (lang, rotation) = if lang == "auto" {
+      emit(on_event, OcrEvent::Stage { stage: OcrStage::Detecting });
+      detect(&app, &all, &image, &cancel)
+    } else {
+      (lang, 0)
+    };
+    // Upright vertical text reads as turned to OSD; the caller vouches for the orientation instead.
+    let (lang, rotation) = if vertical { (vertical_lang(&lang), 0) } else { (lang, rotation) };
+    if rotation != 0 {
+      emit(on_event, OcrEvent::Stage { stage: OcrStage::Preprocessing });
+    }
+    let rotated = match rotation {
+      0 => None,
+      degrees => match osd::rotate_png(&image, degrees) {
+        Ok(r) => Some(r),
+        Err(e) => {
+          events::warn(&app, None, "ocr_rotate_failed", e);
+          None
+        }
+      },
+    };
+    let (image, size) = match rotated {
+      Some((data, width, height)) => (OcrImage::Bytes(data), Some((width, height))),
+      None => (image, None),
+    };
+
+    let engine = select(&app, &mut all, &preferred, &lang, vertical)?;
+    // Columns of vertical text don't pair up by position the way lines do.
+    let second = merge_with
+      .filter(|id| !vertical && id != engine.id())
+      .and_then(|id| second_engine(&app, all, &id, &lang));
+    emit(on_event, OcrEvent::Stage { stage: OcrStage::Running });
+    let (mut output, engine) = match second {
+      None => (engine.recognize(&image, &lang, &cancel)?, engine.id().to_string()),
+      Some(second) => {
+        let second_id = second.id();
+        let (first, other) = std::thread::scope(|s| {
+          let (image, lang, cancel) = (&image, &lang, &*cancel);
+          let other = s.spawn(move || second.recognize(image, lang, cancel));
+          (engine.recognize(image, lang, cancel), other.join())
+        });
+        let first = first?;
+        match other {
+          Ok(Ok(other)) => (
+            crate::ocr_merge::merge(first, other, settings::ocr_min_confidence(&app)),
+            format!("{}+{second_id}", engine.id()),
+          ),
+          Ok(Err(e)) if !cancel.is_cancelled() => {
+            events::warn(&app, None, "ocr_merge_failed", format!("{second_id} OCR failed: {e}"));
+            (first, engine.id().to_string())
+          }
+          _ => (first, engine.id().to_string()),
+        }
+      }
+    };
+    emit(on_event, OcrEvent::Progress { done: 1, total: 1 });
+    emit(on_event, OcrEvent::Stage { stage: OcrStage::Parsing });
+    if !vertical && settings::ocr_layout(&app) {
+      crate::layout::arrange(&mut output);
+    }
+    // Report boxes against the image the caller passed in.
+    let rotation = match size {
+      Some((width, height)) => {
+        osd::unrotate_output(&mut output, rotation, width, height);
+        rotation
+      }
+      None => 0,
+    };
+    if vertical {
+      order_columns(&mut output);
+    }
+    Ok(Recognized {
+      engine,
+      output,
+      lang,
+      rotation,
+    })
+  });
+  tokio::select! {
+    result = job => result.map_err(|e| format!("ocr failed: {e}"))?,
+    _ = cancel.cancelled() => Err("CANCELLED".to_string()),
+  }
+}
+
+/// Running OCR jobs by id, for `cancel_ocr` (managed state).
+#[derive(Default)]
+pub struct OcrJobs {
+  jobs: Mutex<HashMap<String, Arc<CancelToken>>>,
+}
+
+impl OcrJobs {
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Arc<CancelToken>>> {
+    self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn start(&self, id: &str) -> Arc<CancelToken> {
+    let cancel = Arc::new(CancelToken::default());
+    self.lock().insert(id.to_string(), cancel.clone());
+    cancel
+  }
+
+  fn finish(&self, id: &str) {
+    self.lock().remove(id);
+  }
+}
+
+/// Recognize `image`, flag low confidence and reflow the text unless `reflow` says otherwise.
+pub(crate) async fn recognize_text(
+  app: &tauri::AppHandle,
+  image: OcrImage,
+  req: OcrRequest,
+  reflow: Option<bool>,
+  cancel: Arc<CancelToken>,
+  on_event: Option<&Channel<OcrEvent>>,
+) -> Result<OcrResult, String> {
+  let Recognized {
+    engine,
+    output,
+    lang,
+    rotation,
+  } = recognize(app, image, req, cancel, on_event).await?;
+  app.state::<crate::usage::UsageState>().record_ocr(app);
+  let confidence = output.confidence();
+  let low_confidence = confidence.is_some_and(|c| c < settings::ocr_min_confidence(app));
+  if low_confidence {
+    events::warn(
+      app,
+      None,
+      "ocr_low_confidence",
+      format!("OCR confidence is {:.0}%; the text may be misrecognized", confidence.unwrap_or_default()),
+    );
+  }
+  let raw_text = output.text;
+
+  let reflowed = reflow.unwrap_or_else(|| settings::ocr_reflow(app));
+  let text = if reflowed {
+    crate::reflow::reflow(&raw_text)
+  } else {
+    raw_text.clone()
+  };
+  Ok(OcrResult {
+    text,
+    raw_text,
+    reflowed,
+    engine,
+    lines: output.lines,
+    confidence,
+    low_confidence,
+    lang,
+    rotation,
+  })
+}
+
+/// Run `job` as `job_id` (a fresh id when none is given) so `cancel_ocr` can abort it, and report how it ended on
+/// `on_event`.
+pub(crate) async fn run_job<T, F>(
+  app: &tauri::AppHandle,
+  job_id: Option<String>,
+  on_event: &Channel<OcrEvent>,
+  job: impl FnOnce(Arc<CancelToken>) -> F,
+) -> Result<T, String>
+where
+  F: std::future::Future<Output = Result<T, String>>,
+{
+  let job_id = job_id
+    .filter(|s| !s.trim().is_empty())
+    .unwrap_or_else(|| clock::next_id("ocr"));
+  let jobs = app.state::<OcrJobs>();
+  let cancel = jobs.start(&job_id);
+  let result = job(cancel).await;
+  jobs.finish(&job_id);
+  let _ = on_event.send(match &result {
+    Ok(_) => OcrEvent::Done,
+    Err(e) => OcrEvent::Error { message: e.clone() },
+  });
+  result
+}
+
+/// `ocr_image`'s settings, with the defaults filled in.
+pub(crate) fn request(
+  app: &tauri::AppHandle,
+  lang: Option<String>,
+  engine: Option<String>,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+  options: Option<TesseractOptions>,
+) -> OcrRequest {
+  let options = options.unwrap_or_default();
+  OcrRequest {
+    lang: lang.filter(|s| !s.trim().is_empty()).unwrap_or_else(|| "auto".to_string()),
+    preferred: engine
+      .filter(|s| !s.trim().is_empty())
+      .unwrap_or_else(|| settings::ocr_backend(app)),
+    tesseract_path,
+    tessdata_prefix,
+    options: TesseractOptions {
+      user_words: [options.user_words, settings::ocr_user_words(app), crate::glossary::source_words(app)].concat(),
+      user_patterns: [options.user_patterns, settings::ocr_user_patterns(app)].concat(),
+      ..options
+    },
+    merge_with: settings::ocr_merge_engine(app),
+  }
+}
+
+/// OCR an image and clean up its line breaks for translation. `lang` "auto" (the default) detects the script and
+/// orientation first; `engine` overrides the `ocrBackend` setting, `reflow` the `ocrReflow` setting, and
+/// `options` tunes Tesseract (e.g. `{ "psm": 7 }` for a single-line capture). Pass a `job_id` to be able to abort
+/// the call with `cancel_ocr`; `on_event` reports its progress.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ocr_image(
+  app: tauri::AppHandle,
+  image_path: String,
+  lang: Option<String>,
+  engine: Option<String>,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+  reflow: Option<bool>,
+  options: Option<TesseractOptions>,
+  job_id: Option<String>,
+  on_event: Channel<OcrEvent>,
+) -> Result<OcrResult, String> {
+  let req = request(&app, lang, engine, tesseract_path, tessdata_prefix, options);
+  run_job(&app, job_id, &on_event, |cancel| {
+    recognize_text(&app, OcrImage::Path(image_path), req, reflow, cancel, Some(&on_event))
+  })
+  .await
+}
+
+/// `ocr_image` for encoded image data (base64, optionally as a `data:` URL) instead of a file. The image is piped
+/// to the engine and never written to disk.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ocr_image_bytes(
+  app: tauri::AppHandle,
+  image: String,
+  lang: Option<String>,
+  engine: Option<String>,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+  reflow: Option<bool>,
+  options: Option<TesseractOptions>,
+  job_id: Option<String>,
+  on_event: Channel<OcrEvent>,
+) -> Result<OcrResult, String> {
+  let encoded = image.split_once(";base64,").map_or(image.as_str(), |(_, data)| data);
+  let data = base64::engine::general_purpose::STANDARD
+    .decode(encoded.trim())
+    .map_err(|e| format!("invalid image data: {e}"))?;
+  if data.is_empty() {
+    return Err("invalid image data: empty".to_string());
+  }
+  let req = request(&app, lang, engine, tesseract_path, tessdata_prefix, options);
+  run_job(&app, job_id, &on_event, |cancel| {
+    recognize_text(&app, OcrImage::Bytes(data), req, reflow, cancel, Some(&on_event))
+  })
+  .await
+}
+
+/// Regions `ocr_regions` recognizes at once.
+const MAX_PARALLEL_REGIONS: usize = 3;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RegionResult {
+  pub rect: CaptureRect,
+  /// `None` when the region couldn't be captured or recognized (see `error`).
+  pub result: Option<OcrResult>,
+  pub error: Option<String>,
+}
+
+/// Capture every rect, then OCR the captures a few at a time. One region failing doesn't fail the others.
+async fn recognize_regions(
+  app: &tauri::AppHandle,
+  rects: Vec<CaptureRect>,
+  snapshot: Option<&str>,
+  req: OcrRequest,
+  reflow: Option<bool>,
+  cancel: Arc<CancelToken>,
+  on_event: &Channel<OcrEvent>,
+) -> Result<Vec<RegionResult>, String> {
+  use futures_util::StreamExt;
+
+  let total = rects.len();
+  // All captures first, so the regions show the screen at the same moment.
+  let captures: Vec<Result<String, String>> = rects
+    .iter()
+    .map(|r| match snapshot {
+      Some(id) => crate::snapshot::crop(&app.state::<crate::snapshot::Snapshots>(), id, r),
+      None => crate::commands::capture_region(app, r, false),
+    })
+    .collect();
+  let _ = on_event.send(OcrEvent::Stage { stage: OcrStage::Running });
+  let done = AtomicUsize::new(0);
+  let futures = rects.into_iter().zip(captures).map(|(rect, capture)| {
+    let (req, cancel, done) = (req.clone(), cancel.clone(), &done);
+    async move {
+      let outcome = match capture {
+        Ok(path) => {
+          // Per-region stages would interleave; progress is reported per finished region instead.
+          let result = recognize_text(app, OcrImage::Path(path.clone()), req, reflow, cancel, None).await;
+          let _ = std::fs::remove_file(&path);
+          result
+        }
+        Err(e) => Err(e),
+      };
+      let _ = on_event.send(OcrEvent::Progress {
+        done: done.fetch_add(1, Ordering::SeqCst) + 1,
+        total,
+      });
+      match outcome {
+        Ok(result) => RegionResult {
+          rect,
+          result: Some(result),
+          error: None,
+        },
+        Err(e) => RegionResult {
+          rect,
+          result: None,
+          error: Some(e),
+        },
+      }
+    }
+  });
+  let results: Vec<RegionResult> = futures_util::stream::iter(futures).buffered(MAX_PARALLEL_REGIONS).collect().await;
+  if cancel.is_cancelled() {
+    return Err("CANCELLED".to_string());
+  }
+  Ok(results)
+}
+
+/// Capture and OCR several screen regions in one call (e.g. the speech boxes of a dialog). Results come back in
+/// the order of `rects`; the other parameters are `ocr_image`'s and apply to every region. `on_event` reports
+/// progress as regions finish. With `snapshot` (see `snapshot`) the regions are cropped from that frozen frame
+/// instead of the live screen.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ocr_regions(
+  app: tauri::AppHandle,
+  rects: Vec<CaptureRect>,
+  lang: Option<String>,
+  engine: Option<String>,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+  reflow: Option<bool>,
+  options: Option<TesseractOptions>,
+  job_id: Option<String>,
+  snapshot: Option<String>,
+  on_event: Channel<OcrEvent>,
+) -> Result<Vec<RegionResult>, String> {
+  if rects.is_empty() {
+    return Err("no regions to OCR".to_string());
+  }
+  let req = request(&app, lang, engine, tesseract_path, tessdata_prefix, options);
+  run_job(&app, job_id, &on_event, |cancel| {
+    recognize_regions(&app, rects, snapshot.as_deref(), req, reflow, cancel, &on_event)
+  })
+  .await
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WindowOcrResult {
+  /// The window's bounds on screen; line and word boxes are relative to its top-left corner.
+  pub rect: CaptureRect,
+  pub result: OcrResult,
+}
+
+/// Capture the foreground window and OCR all of it: every line with its position, for translating a whole dialog
+/// without selecting a region. Takes `ocr_image`'s parameters.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn capture_active_window(
+  app: tauri::AppHandle,
+  lang: Option<String>,
+  engine: Option<String>,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+  reflow: Option<bool>,
+  options: Option<TesseractOptions>,
+  job_id: Option<String>,
+  on_event: Channel<OcrEvent>,
+) -> Result<WindowOcrResult, String> {
+  let req = request(&app, lang, engine, tesseract_path, tessdata_prefix, options);
+  run_job(&app, job_id, &on_event, |cancel| async {
+    let (path, rect) = crate::commands::capture_active_window_png(&app)?;
+    let result = recognize_text(&app, OcrImage::Path(path.clone()), req, reflow, cancel, Some(&on_event)).await;
+    let _ = std::fs::remove_file(&path);
+    Ok(WindowOcrResult { rect, result: result? })
+  })
+  .await
+}
+
+/// Abort a running OCR call (`ocr_image`, `ocr_image_bytes`, `ocr_regions`, `capture_active_window`, `ocr_recording`) (it fails with `CANCELLED`). Returns whether the job was running.
+#[tauri::command]
+pub fn cancel_ocr(job_id: String, jobs: tauri::State<'_, OcrJobs>) -> bool {
+  match jobs.lock().get(&job_id) {
+    Some(cancel) => {
+      cancel.cancel();
+      true
+    }
+    None => false,
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OcrEngineInfo {
+  pub id: String,
+  pub available: bool,
+  pub languages: Vec<String>,
+}
+
+/// Engines built into this binary and what each can do here.
+#[tauri::command]
+pub async fn list_ocr_engines(
+  app: tauri::AppHandle,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+) -> Result<Vec<OcrEngineInfo>, String> {
+  let exe = resolve_tesseract(tesseract_path).await?;
+  let remote = RemoteOcr::from_settings(&app);
+  tauri::async_runtime::spawn_blocking(move || {
+    engines(exe, tessdata_prefix, TesseractOptions::default(), remote)
+      .into_iter()
+      .map(|e| {
+        let available = e.is_available();
+        OcrEngineInfo {
+          id: e.id().to_string(),
+          available,
+          languages: if available { e.languages().unwrap_or_default() } else { Vec::new() },
+        }
+      })
+      .collect()
+  })
+  .await
+  .map_err(|e| format!("ocr failed: {e}"))
+}
+
+/// Languages Tesseract has traineddata for: the in-process engine's when it's in use, else the binary's.
+#[tauri::command]
+pub async fn tesseract_list_langs(
+  app: tauri::AppHandle,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+) -> Result<Vec<String>, String> {
+  let id = if cfg!(feature = "embedded-tesseract") && settings::ocr_backend(&app) != "external" {
+    "embedded"
+  } else {
+    "external"
+  };
+  let exe = resolve_tesseract(tesseract_path).await?;
+  tauri::async_runtime::spawn_blocking(move || {
+    let engine = engines(exe, tessdata_prefix, TesseractOptions::default(), RemoteOcr::from_settings(&app))
+      .into_iter()
+      .find(|e| e.id() == id && e.is_available())
+      .ok_or_else(|| "TESSERACT_NOT_FOUND".to_string())?;
+    engine.languages()
+  })
+  .await
+  .map_err(|e| format!("failed to list langs: {e}"))?
+}
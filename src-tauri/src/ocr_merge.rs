@@ -0,0 +1,142 @@
+//! Combining two engines' reads of the same image (`ocrMergeEngine`).
+//!
+//! Lines are paired by position. Of a pair, the line the engines agree on or the more confident one is kept; a
+//! line only one engine found is added when it isn't a low-confidence guess. The result is laid out top to
+//! bottom again, with a blank line where the vertical gap suggests a new paragraph.
+
+use crate::ocr::{BBox, OcrLine, OcrOutput};
+
+/// Share of the smaller line's box two boxes must overlap to count as the same line.
+const SAME_LINE_OVERLAP: f32 = 0.5;
+
+fn area(b: &BBox) -> i64 {
+  b.width.max(0) as i64 * b.height.max(0) as i64
+}
+
+/// Overlap of `a` and `b` as a share of the smaller one.
+fn overlap(a: &BBox, b: &BBox) -> f32 {
+  let w = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+  let h = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+  let smaller = area(a).min(area(b));
+  if w <= 0 || h <= 0 || smaller == 0 {
+    return 0.0;
+  }
+  (w as i64 * h as i64) as f32 / smaller as f32
+}
+
+/// Text compared without whitespace (engines space CJK differently).
+fn normalized(text: &str) -> String {
+  text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// Of two reads of one line, the one to keep.
+fn pick(a: OcrLine, b: OcrLine, min_confidence: f32) -> OcrLine {
+  if normalized(&a.text) == normalized(&b.text) {
+    // Agreement: keep the scored one, so the overall confidence reflects it.
+    return if a.confidence.is_none() && b.confidence.is_some() { b } else { a };
+  }
+  match (a.confidence, b.confidence) {
+    (Some(ca), Some(cb)) => {
+      if cb > ca {
+        b
+      } else {
+        a
+      }
+    }
+    // An engine that doesn't score (Windows OCR) wins over a score that says the other read is a guess.
+    (Some(ca), None) if ca < min_confidence => b,
+    (None, Some(cb)) if cb >= min_confidence => b,
+    _ => a,
+  }
+}
+
+/// Merge `secondary` into `primary`. Both must have boxes in the same image's coordinates.
+pub fn merge(primary: OcrOutput, secondary: OcrOutput, min_confidence: f32) -> OcrOutput {
+  let mut others: Vec<Option<OcrLine>> = secondary.lines.into_iter().map(Some).collect();
+  let mut lines: Vec<OcrLine> = Vec::new();
+  for line in primary.lines {
+    let best = others
+      .iter()
+      .enumerate()
+      .filter_map(|(i, o)| Some((i, overlap(&line.bbox, &o.as_ref()?.bbox))))
+      .filter(|(_, o)| *o >= SAME_LINE_OVERLAP)
+      .max_by(|a, b| a.1.total_cmp(&b.1));
+    lines.push(match best.and_then(|(i, _)| others[i].take()) {
+      Some(other) => pick(line, other, min_confidence),
+      None => line,
+    });
+  }
+  // Lines only the second engine saw.
+  lines.extend(
+    others
+      .into_iter()
+      .flatten()
+      .filter(|l| l.confidence.map_or(true, |c| c >= min_confidence)),
+  );
+
+  lines.sort_by_key(|l| (l.bbox.y + l.bbox.height / 2, l.bbox.x));
+  let mut text = String::new();
+  for (i, line) in lines.iter().enumerate() {
+    if i > 0 {
+      let prev = &lines[i - 1].bbox;
+      let gap = line.bbox.y - (prev.y + prev.height);
+      text.push_str(if gap > prev.height.max(line.bbox.height) { "\n\n" } else { "\n" });
+    }
+    text.push_str(&line.text);
+  }
+  OcrOutput { text, lines }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn line(text: &str, y: i32, confidence: Option<f32>) -> OcrLine {
+    OcrLine {
+      text: text.to_string(),
+      bbox: BBox {
+        x: 0,
+        y,
+        width: 100,
+        height: 10,
+      },
+      confidence,
+      words: Vec::new(),
+    }
+  }
+
+  fn output(lines: Vec<OcrLine>) -> OcrOutput {
+    OcrOutput {
+      text: String::new(),
+      lines,
+    }
+  }
+
+  #[test]
+  fn keeps_the_scored_read_when_engines_agree() {
+    let merged = merge(output(vec![line("日本 語", 0, None)]), output(vec![line("日本語", 1, Some(90.0))]), 50.0);
+    assert_eq!(merged.text, "日本語");
+    assert_eq!(merged.lines[0].confidence, Some(90.0));
+  }
+
+  #[test]
+  fn picks_the_more_trusted_read_of_a_line() {
+    let merged = merge(output(vec![line("he1lo", 0, Some(40.0))]), output(vec![line("hello", 0, Some(80.0))]), 50.0);
+    assert_eq!(merged.text, "hello");
+    let merged = merge(output(vec![line("he1lo", 0, Some(40.0))]), output(vec![line("hello", 0, None)]), 50.0);
+    assert_eq!(merged.text, "hello");
+    let merged = merge(output(vec![line("hello", 0, None)]), output(vec![line("he1lo", 0, Some(40.0))]), 50.0);
+    assert_eq!(merged.text, "hello");
+  }
+
+  #[test]
+  fn adds_confident_lines_only_the_second_engine_found() {
+    let merged = merge(
+      output(vec![line("first", 0, Some(90.0))]),
+      output(vec![line("guess", 12, Some(20.0)), line("later", 40, Some(70.0))]),
+      50.0,
+    );
+    assert_eq!(merged.text, "first\n\nlater");
+    assert_eq!(merged.lines.len(), 2);
+  }
+}
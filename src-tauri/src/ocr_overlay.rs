@@ -0,0 +1,143 @@
+//! The OCR selection overlay: one borderless window per monitor, opened and closed together.
+//!
+//! A single window stretched over the virtual screen has one scale factor, so on a 100% + 150% setup part of it is
+//! drawn at the wrong size and selections there map to the wrong pixels; some compositors also refuse windows
+//! that span monitors. Each overlay window instead covers exactly one monitor and is told that monitor's physical
+//! origin and scale in its URL (`ox`, `oy`, `scale`). A selection's CSS pixels plus `ox / scale` are logical
+//! desktop coordinates of that monitor, which `screen` maps back to global physical (capture) pixels.
+//!
+//! The window on the monitor under the pointer is labelled `ocr-overlay` and takes focus; the others are
+//! `ocr-overlay-2`, `ocr-overlay-3`, ...
+
+use serde::Serialize;
+use tauri::Manager;
+
+use crate::commands::CaptureRect;
+
+pub const LABEL: &str = "ocr-overlay";
+
+/// Whether `label` is one of the overlay windows.
+pub fn is_overlay_window(label: &str) -> bool {
+  label == LABEL || label.strip_prefix(LABEL).is_some_and(|rest| rest.starts_with('-'))
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OverlayWindow {
+  pub label: String,
+  pub monitor: Option<String>,
+  /// Physical pixels of the desktop.
+  pub bounds: CaptureRect,
+  pub scale_factor: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OverlayInfo {
+  /// False when the overlay was already open (nothing was opened).
+  pub opened: bool,
+  pub windows: Vec<OverlayWindow>,
+}
+
+/// Whether any overlay window is open.
+pub fn is_open(app: &tauri::AppHandle) -> bool {
+  app.webview_windows().keys().any(|label| is_overlay_window(label))
+}
+
+/// Open the selection overlay over every monitor. Snapshot `snapshot` (see `capture_full_screen_snapshot`) is
+/// shown under the selection instead of the live screen.
+#[tauri::command]
+pub async fn open_ocr_overlay(app: tauri::AppHandle, snapshot: Option<String>) -> Result<OverlayInfo, String> {
+  if is_open(&app) {
+    return Ok(OverlayInfo { opened: false, windows: Vec::new() });
+  }
+  // The app under the overlay is where the OCR'd text comes from (see `app_profiles`).
+  crate::type_back::remember_target(&app);
+  let snapshot = match snapshot {
+    Some(id) => Some((app.state::<crate::snapshot::Snapshots>().bounds(&id)?, id)),
+    None => None,
+  };
+  let monitors = app.available_monitors().map_err(|e| format!("cannot list monitors: {e}"))?;
+  if monitors.is_empty() {
+    return Err("no monitors found".to_string());
+  }
+  let cursor = crate::commands::get_cursor_position().ok();
+  let under_cursor = |m: &tauri::Monitor| {
+    let (p, s) = (m.position(), m.size());
+    cursor.as_ref().is_some_and(|c| {
+      c.x >= p.x && c.y >= p.y && c.x < p.x + s.width as i32 && c.y < p.y + s.height as i32
+    })
+  };
+  let focused = monitors.iter().position(under_cursor).unwrap_or(0);
+
+  let mut windows = Vec::new();
+  let mut others = 1;
+  for (i, monitor) in monitors.iter().enumerate() {
+    let label = if i == focused {
+      LABEL.to_string()
+    } else {
+      others += 1;
+      format!("{LABEL}-{others}")
+    };
+    let (position, size, scale) = (*monitor.position(), *monitor.size(), monitor.scale_factor());
+    let mut route = format!("index.html#/ocr-overlay?ox={}&oy={}&scale={scale}", position.x, position.y);
+    if let Some((rect, id)) = &snapshot {
+      route.push_str(&format!("&snapshot={id}&x={}&y={}", rect.x, rect.y));
+    }
+    let opened = tauri::WebviewWindowBuilder::new(&app, &label, tauri::WebviewUrl::App(route.into()))
+      .resizable(false)
+      .decorations(false)
+      .transparent(true)
+      .always_on_top(true)
+      .skip_taskbar(true)
+      .shadow(false)
+      .visible(false)
+      .focused(false)
+      .build()
+      .and_then(|window| {
+        // Physical placement: builder positions are logical, and which scale they'd use is up to the platform.
+        // Position first; moving onto a monitor with another scale rescales the window, which the size then fixes.
+        window.set_position(position)?;
+        window.set_size(size)?;
+        window.show()?;
+        Ok(window)
+      });
+    match opened {
+      Ok(window) => {
+        if i == focused {
+          let _ = window.set_focus();
+        }
+      }
+      Err(e) => {
+        close(&app);
+        return Err(format!("failed to open the OCR overlay: {e}"));
+      }
+    }
+    windows.push(OverlayWindow {
+      label,
+      monitor: monitor.name().cloned(),
+      bounds: CaptureRect { x: position.x, y: position.y, width: size.width, height: size.height },
+      scale_factor: scale,
+    });
+  }
+  Ok(OverlayInfo { opened: true, windows })
+}
+
+/// Destroy every overlay window.
+pub fn close(app: &tauri::AppHandle) {
+  for (label, window) in app.webview_windows() {
+    if is_overlay_window(&label) {
+      let _ = window.destroy();
+    }
+  }
+}
+
+/// Close the overlay on every monitor (a selection made, or Esc in any of them).
+#[tauri::command]
+pub fn close_ocr_overlay(app: tauri::AppHandle) {
+  close(&app);
+}
+
+/// Whether the overlay is open.
+#[tauri::command]
+pub fn is_ocr_overlay_open(app: tauri::AppHandle) -> bool {
+  is_open(&app)
+}
@@ -0,0 +1,216 @@
+//! Capture → OCR → translate in one call: `ocr_translate_region` does what the frontend otherwise chains through
+//! `capture_screen_region`, `ocr_image` and `translate_sse`, without a temp-file path travelling between them.
+//!
+//! Everything is reported on one channel: a `stage` event as each step starts, the OCR engine's own progress
+//! (`ocr`), the recognized text (`recognized`), the translation's stream events (`translation`), and finally `done`
+//! or `error` (naming the step that failed). The call runs as an OCR job, so `cancel_ocr` with its `job_id` stops
+//! it at any step.
+
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::ipc::{Channel, InvokeResponseBody};
+
+use crate::commands::{self, CaptureRect, StreamEvent};
+use crate::ocr::{self, OcrEvent, OcrImage, OcrRequest, OcrResult, TesseractOptions};
+use crate::output::OutputControls;
+use crate::queue::{CancelToken, Priority};
+use crate::settings;
+use crate::translate::{self, TranslateRequest};
+
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+  Capture,
+  Ocr,
+  Translate,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type")]
+pub enum PipelineEvent {
+  #[serde(rename = "stage")]
+  Stage { stage: PipelineStage },
+  /// OCR progress (an `ocr_image` stage or progress event).
+  #[serde(rename = "ocr")]
+  Ocr { event: OcrEvent },
+  /// The text OCR found, before it's translated.
+  #[serde(rename = "recognized")]
+  Recognized { text: String, engine: String, low_confidence: bool },
+  /// A `translate_sse` stream event.
+  #[serde(rename = "translation")]
+  Translation { event: StreamEvent },
+  #[serde(rename = "done")]
+  Done,
+  #[serde(rename = "error")]
+  Error { stage: PipelineStage, message: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PipelineResult {
+  /// The captured region in physical pixels; OCR boxes are relative to its top-left corner.
+  pub rect: CaptureRect,
+  pub ocr: OcrResult,
+  /// `None` when OCR found no text.
+  pub translation: Option<String>,
+}
+
+/// What to translate the recognized text with.
+struct Translation {
+  target_lang: String,
+  mode: String,
+  explanation_lang: String,
+  output: OutputControls,
+}
+
+/// A channel for the OCR engine's events that passes them on as `ocr` events (without the job's own done / error,
+/// which the pipeline reports itself).
+fn ocr_channel(on_event: Channel<PipelineEvent>) -> Channel<OcrEvent> {
+  Channel::new(move |body| {
+    if let InvokeResponseBody::Json(json) = body {
+      match serde_json::from_str::<OcrEvent>(&json) {
+        Ok(OcrEvent::Done | OcrEvent::Error { .. }) | Err(_) => {}
+        Ok(event) => {
+          let _ = on_event.send(PipelineEvent::Ocr { event });
+        }
+      }
+    }
+    Ok(())
+  })
+}
+
+struct Pipeline<'a> {
+  app: &'a tauri::AppHandle,
+  on_event: &'a Channel<PipelineEvent>,
+  ocr_events: &'a Channel<OcrEvent>,
+}
+
+impl Pipeline<'_> {
+  fn send(&self, event: PipelineEvent) {
+    let _ = self.on_event.send(event);
+  }
+
+  /// Report that `stage` failed with `message`, and pass the message on.
+  fn fail(&self, stage: PipelineStage, message: String) -> String {
+    self.send(PipelineEvent::Error {
+      stage,
+      message: message.clone(),
+    });
+    message
+  }
+
+  async fn run(
+    &self,
+    rect: CaptureRect,
+    req: OcrRequest,
+    reflow: Option<bool>,
+    translation: Translation,
+    cancel: Arc<CancelToken>,
+  ) -> Result<PipelineResult, String> {
+    self.send(PipelineEvent::Stage { stage: PipelineStage::Capture });
+    let captured = {
+      let (app, rect) = (self.app.clone(), rect.clone());
+      tauri::async_runtime::spawn_blocking(move || commands::capture_region(&app, &rect, false))
+        .await
+        .map_err(|e| format!("capture failed: {e}"))
+        .and_then(|r| r)
+    };
+    let path = captured.map_err(|e| self.fail(PipelineStage::Capture, e))?;
+    crate::regions::record(self.app, &rect);
+
+    self.send(PipelineEvent::Stage { stage: PipelineStage::Ocr });
+    let result = ocr::recognize_text(
+      self.app,
+      OcrImage::Path(path.clone()),
+      req,
+      reflow,
+      cancel.clone(),
+      Some(self.ocr_events),
+    )
+    .await;
+    let _ = std::fs::remove_file(&path);
+    let ocr = result.map_err(|e| self.fail(PipelineStage::Ocr, e))?;
+    let text = ocr.text.trim().to_string();
+    self.send(PipelineEvent::Recognized {
+      text: text.clone(),
+      engine: ocr.engine.clone(),
+      low_confidence: ocr.low_confidence,
+    });
+    if text.is_empty() {
+      self.send(PipelineEvent::Done);
+      return Ok(PipelineResult {
+        rect,
+        ocr,
+        translation: None,
+      });
+    }
+
+    self.send(PipelineEvent::Stage { stage: PipelineStage::Translate });
+    let base_url = settings::api_base_url(self.app).map_err(|e| self.fail(PipelineStage::Translate, e))?;
+    let req = TranslateRequest {
+      base_url,
+      text,
+      target_lang: translation.target_lang,
+      mode: translation.mode,
+      explanation_lang: translation.explanation_lang,
+      priority: Priority::Interactive,
+      output: translation.output,
+      ..Default::default()
+    };
+    let sink = |event: StreamEvent| self.send(PipelineEvent::Translation { event });
+    let translated = tokio::select! {
+      result = translate::run_translation(self.app, req, &sink) => result,
+      _ = cancel.cancelled() => Err("CANCELLED".to_string()),
+    }
+    .map_err(|e| self.fail(PipelineStage::Translate, e))?;
+    self.send(PipelineEvent::Done);
+    Ok(PipelineResult {
+      rect,
+      ocr,
+      translation: Some(translated),
+    })
+  }
+}
+
+/// Capture a region, OCR it and translate the text, reporting every step on `on_event` (see the module docs).
+/// `rect` / `logical` are `capture_screen_region`'s, the OCR parameters `ocr_image`'s; `target_lang` defaults to
+/// `defaultLanguage`, `mode` to the app profile's (see `app_profiles::mode`) and `explanation_lang` to
+/// `explanationLanguage`. Cancel with `cancel_ocr(job_id)`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ocr_translate_region(
+  app: tauri::AppHandle,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+  lang: Option<String>,
+  engine: Option<String>,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+  reflow: Option<bool>,
+  options: Option<TesseractOptions>,
+  target_lang: Option<String>,
+  mode: Option<String>,
+  explanation_lang: Option<String>,
+  output: Option<OutputControls>,
+  job_id: Option<String>,
+  on_event: Channel<PipelineEvent>,
+) -> Result<PipelineResult, String> {
+  let pipeline = Pipeline {
+    app: &app,
+    on_event: &on_event,
+    ocr_events: &ocr_channel(on_event.clone()),
+  };
+  let rect = commands::physical_rect(&app, rect, logical).map_err(|e| pipeline.fail(PipelineStage::Capture, e))?;
+  let req = ocr::request(&app, lang, engine, tesseract_path, tessdata_prefix, options);
+  let translation = Translation {
+    target_lang: target_lang
+      .filter(|s| !s.trim().is_empty())
+      .unwrap_or_else(|| settings::default_target_lang(&app)),
+    mode: mode.unwrap_or_else(|| crate::app_profiles::mode(&app)),
+    explanation_lang: explanation_lang.unwrap_or_else(|| settings::explanation_lang(&app)),
+    output: output.unwrap_or_default(),
+  };
+  ocr::run_job(&app, job_id, pipeline.ocr_events, |cancel| {
+    pipeline.run(rect, req, reflow, translation, cancel)
+  })
+  .await
+}
@@ -0,0 +1,270 @@
+//! Offline neural MT via the Bergamot `translateLocally` CLI.
+//!
+//! Like Tesseract, the engine is an external executable; models are managed through its own
+//! `--available-models` / `--download-model` / `--list-models` / `--remove-model` commands. Models are
+//! named `<src>-<tgt>-<size>` (e.g. `en-de-tiny`).
+//!
+//! Used as the `offline://` provider and as a fallback when the translation server is unreachable.
+
+use serde::Serialize;
+use std::io::{Read, Write};
+
+const ENGINE_TIMEOUT_MS: u128 = 120_000;
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct OfflineModel {
+  pub name: String,
+  pub src: String,
+  pub tgt: String,
+}
+
+pub const PROVIDER_SCHEME: &str = "offline://";
+
+pub fn is_offline(base_url: &str) -> bool {
+  base_url.trim().starts_with(PROVIDER_SCHEME)
+}
+
+/// ISO 639-1 code for the app's language names ("English (US)" -> "en").
+pub fn lang_code(name: &str) -> Option<&'static str> {
+  let n = name.trim().to_lowercase();
+  let base = n.split(" (").next().unwrap_or(&n);
+  let code = match base {
+    "japanese" | "ja" => "ja",
+    "english" | "en" => "en",
+    "korean" | "ko" => "ko",
+    "chinese" | "zh" => "zh",
+    "thai" | "th" => "th",
+    "indonesian" | "id" => "id",
+    "vietnamese" | "vi" => "vi",
+    "hindi" | "hi" => "hi",
+    "german" | "de" => "de",
+    "french" | "fr" => "fr",
+    "spanish" | "es" => "es",
+    "italian" | "it" => "it",
+    "portuguese" | "pt" => "pt",
+    "russian" | "ru" => "ru",
+    "ukrainian" | "uk" => "uk",
+    "polish" | "pl" => "pl",
+    "czech" | "cs" => "cs",
+    "dutch" | "nl" => "nl",
+    _ => return None,
+  };
+  Some(code)
+}
+
+/// Cheap script-based source guess, good enough to pick a model pair.
+pub fn guess_source_code(text: &str) -> &'static str {
+  let mut kana = 0usize;
+  let mut han = 0usize;
+  let mut hangul = 0usize;
+  let mut cyrillic = 0usize;
+  let mut thai = 0usize;
+  for c in text.chars() {
+    match c as u32 {
+      0x3040..=0x30FF => kana += 1,
+      0x4E00..=0x9FFF => han += 1,
+      0xAC00..=0xD7AF => hangul += 1,
+      0x0400..=0x04FF => cyrillic += 1,
+      0x0E00..=0x0E7F => thai += 1,
+      _ => {}
+    }
+  }
+  if kana > 0 {
+    "ja"
+  } else if hangul > 0 {
+    "ko"
+  } else if han > 0 {
+    "zh"
+  } else if thai > 0 {
+    "th"
+  } else if cyrillic > 0 {
+    "ru"
+  } else {
+    "en"
+  }
+}
+
+fn parse_model_name(token: &str) -> Option<OfflineModel> {
+  let token = token.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '-');
+  let parts: Vec<&str> = token.split('-').collect();
+  if parts.len() != 3 || parts[0].len() != 2 || parts[1].len() != 2 || parts[2].is_empty() {
+    return None;
+  }
+  if !parts.iter().all(|p| p.chars().all(|c| c.is_ascii_alphanumeric())) {
+    return None;
+  }
+  Some(OfflineModel {
+    name: token.to_string(),
+    src: parts[0].to_string(),
+    tgt: parts[1].to_string(),
+  })
+}
+
+fn parse_models(out: &str) -> Vec<OfflineModel> {
+  let mut models: Vec<OfflineModel> = out.split_whitespace().filter_map(parse_model_name).collect();
+  models.sort_by(|a, b| a.name.cmp(&b.name));
+  models.dedup();
+  models
+}
+
+pub fn detect_engine(configured: Option<&str>) -> Option<String> {
+  if let Some(p) = configured.map(|s| s.trim()).filter(|s| !s.is_empty()) {
+    return std::path::Path::new(p).exists().then(|| p.to_string());
+  }
+  let exe = if cfg!(windows) { "translateLocally.exe" } else { "translateLocally" };
+  #[cfg(windows)]
+  {
+    for base in [std::env::var("ProgramFiles").ok(), std::env::var("LOCALAPPDATA").ok().map(|l| format!(r"{l}\Programs"))]
+      .into_iter()
+      .flatten()
+    {
+      let p = std::path::PathBuf::from(base).join("translateLocally").join(exe);
+      if p.exists() {
+        return Some(p.to_string_lossy().to_string());
+      }
+    }
+  }
+  #[cfg(target_os = "macos")]
+  {
+    let p = "/Applications/translateLocally.app/Contents/MacOS/translateLocally";
+    if std::path::Path::new(p).exists() {
+      return Some(p.to_string());
+    }
+  }
+  let path = std::env::var_os("PATH")?;
+  std::env::split_paths(&path)
+    .map(|d| d.join(exe))
+    .find(|p| p.is_file())
+    .map(|p| p.to_string_lossy().to_string())
+}
+
+fn engine_or_err(engine_path: Option<&str>) -> Result<String, String> {
+  detect_engine(engine_path).ok_or_else(|| "OFFLINE_ENGINE_NOT_FOUND".to_string())
+}
+
+fn run_engine(exe: &str, args: &[&str], stdin: Option<&str>) -> Result<String, String> {
+  let mut child = std::process::Command::new(exe)
+    .args(args)
+    .stdin(std::process::Stdio::piped())
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped())
+    .spawn()
+    .map_err(|e| format!("failed to run offline engine: {e}"))?;
+  if let Some(mut pipe) = child.stdin.take() {
+    let input = stdin.unwrap_or_default().to_string();
+    std::thread::spawn(move || {
+      let _ = pipe.write_all(input.as_bytes());
+    });
+  }
+  // Drain both pipes while waiting: an engine that fills one (model listings, verbose logs) would block forever.
+  let stdout = child.stdout.take().map(drain);
+  let stderr = child.stderr.take().map(drain);
+  let started = std::time::Instant::now();
+  let status = loop {
+    if let Some(status) = child.try_wait().map_err(|e| format!("offline engine wait failed: {e}"))? {
+      break status;
+    }
+    if started.elapsed().as_millis() > ENGINE_TIMEOUT_MS {
+      let _ = child.kill();
+      let _ = child.wait();
+      return Err("offline engine timed out".to_string());
+    }
+    std::thread::sleep(std::time::Duration::from_millis(20));
+  };
+  let collect = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| {
+    String::from_utf8_lossy(&reader.and_then(|r| r.join().ok()).unwrap_or_default()).to_string()
+  };
+  let (out, err) = (collect(stdout), collect(stderr));
+  if !status.success() {
+    return Err(format!("offline engine failed: {}", err.trim()));
+  }
+  Ok(out)
+}
+
+/// Read `pipe` to the end on its own thread.
+fn drain(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+  std::thread::spawn(move || {
+    let mut buf = Vec::new();
+    let _ = pipe.read_to_end(&mut buf);
+    buf
+  })
+}
+
+async fn run_engine_async(exe: String, args: Vec<String>, stdin: Option<String>) -> Result<String, String> {
+  tauri::async_runtime::spawn_blocking(move || {
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    run_engine(&exe, &args, stdin.as_deref())
+  })
+  .await
+  .map_err(|e| format!("offline engine task failed: {e}"))?
+}
+
+pub async fn installed_models(engine_path: Option<&str>) -> Result<Vec<OfflineModel>, String> {
+  let exe = engine_or_err(engine_path)?;
+  Ok(parse_models(&run_engine_async(exe, vec!["--list-models".to_string()], None).await?))
+}
+
+/// Installed model for `src -> tgt`, preferring larger models.
+pub async fn find_model(engine_path: Option<&str>, src: &str, tgt: &str) -> Result<Option<OfflineModel>, String> {
+  let models = installed_models(engine_path).await?;
+  let rank = |m: &OfflineModel| match m.name.rsplit('-').next() {
+    Some("base") => 0,
+    Some("tiny") => 2,
+    _ => 1,
+  };
+  Ok(
+    models
+      .into_iter()
+      .filter(|m| m.src == src && m.tgt == tgt)
+      .min_by_key(rank),
+  )
+}
+
+/// Translate `text` into `target_lang` with an installed model.
+pub async fn translate(engine_path: Option<&str>, text: &str, target_lang: &str) -> Result<String, String> {
+  let tgt = lang_code(target_lang).ok_or_else(|| format!("OFFLINE_UNSUPPORTED_LANG: {target_lang}"))?;
+  let src = guess_source_code(text);
+  if src == tgt {
+    return Ok(text.to_string());
+  }
+  let exe = engine_or_err(engine_path)?;
+  let model = find_model(Some(&exe), src, tgt)
+    .await?
+    .ok_or_else(|| format!("OFFLINE_MODEL_MISSING: {src}-{tgt}"))?;
+  let out = run_engine_async(exe, vec!["-m".to_string(), model.name], Some(text.to_string())).await?;
+  Ok(out.trim_end().to_string())
+}
+
+#[tauri::command]
+pub async fn detect_offline_engine(engine_path: Option<String>) -> Result<Option<String>, String> {
+  Ok(detect_engine(engine_path.as_deref()))
+}
+
+#[tauri::command]
+pub async fn list_offline_models(engine_path: Option<String>) -> Result<Vec<OfflineModel>, String> {
+  installed_models(engine_path.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn list_available_offline_models(engine_path: Option<String>) -> Result<Vec<OfflineModel>, String> {
+  let exe = engine_or_err(engine_path.as_deref())?;
+  Ok(parse_models(
+    &run_engine_async(exe, vec!["--available-models".to_string()], None).await?,
+  ))
+}
+
+#[tauri::command]
+pub async fn download_offline_model(name: String, engine_path: Option<String>) -> Result<(), String> {
+  let model = parse_model_name(&name).ok_or_else(|| format!("invalid model name: {name}"))?;
+  let exe = engine_or_err(engine_path.as_deref())?;
+  run_engine_async(exe, vec!["--download-model".to_string(), model.name], None).await?;
+  Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_offline_model(name: String, engine_path: Option<String>) -> Result<(), String> {
+  let model = parse_model_name(&name).ok_or_else(|| format!("invalid model name: {name}"))?;
+  let exe = engine_or_err(engine_path.as_deref())?;
+  run_engine_async(exe, vec!["--remove-model".to_string(), model.name], None).await?;
+  Ok(())
+}
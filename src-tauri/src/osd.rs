@@ -0,0 +1,141 @@
+//! Orientation and script detection ahead of OCR (`ocrLang` = "auto").
+//!
+//! Tesseract's OSD pass (`--psm 0`, needs `osd.traineddata`) reports the script of the captured text and
+//! the clockwise rotation that makes it upright. The script picks the traineddata for the main pass; a
+//! rotated capture is turned upright in memory, and the boxes found in it are mapped back onto the original
+//! image.
+
+use crate::ocr::{BBox, OcrImage, OcrOutput};
+
+/// Used when detection fails or finds nothing installed for the script.
+pub const FALLBACK_LANG: &str = "jpn+eng";
+/// Tesseract's OSD confidences below this are guesses.
+const MIN_CONFIDENCE: f32 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Osd {
+  /// Clockwise degrees (0, 90, 180, 270) that make the text upright.
+  pub rotate: u16,
+  pub orientation_confidence: f32,
+  pub script: String,
+  pub script_confidence: f32,
+}
+
+/// Parse the `--psm 0` report:
+///
+/// ```text
+/// Rotate: 90
+/// Orientation confidence: 2.15
+/// Script: Japanese
+/// Script confidence: 1.33
+/// ```
+pub fn parse(report: &str) -> Option<Osd> {
+  let field = |name: &str| {
+    report
+      .lines()
+      .find_map(|l| l.strip_prefix(name).and_then(|rest| rest.strip_prefix(':')))
+      .map(str::trim)
+  };
+  Some(Osd {
+    rotate: field("Rotate")?.parse::<u16>().ok().filter(|r| r % 90 == 0 && *r < 360)?,
+    orientation_confidence: field("Orientation confidence")?.parse().ok()?,
+    script: field("Script")?.to_string(),
+    script_confidence: field("Script confidence")?.parse().ok()?,
+  })
+}
+
+/// Traineddata to try for a detected script, best first.
+fn script_langs(script: &str) -> &'static [&'static str] {
+  match script {
+    "Japanese" | "Hiragana" | "Katakana" => &["jpn"],
+    // Kanji-only text is as likely Japanese as Chinese here.
+    "Han" => &["jpn", "chi_sim", "chi_tra"],
+    "Hangul" | "Korean" => &["kor"],
+    "Latin" => &["eng"],
+    "Cyrillic" => &["rus", "ukr"],
+    "Greek" => &["ell"],
+    "Arabic" => &["ara"],
+    "Hebrew" => &["heb"],
+    "Devanagari" => &["hin"],
+    "Thai" => &["tha"],
+    _ => &[],
+  }
+}
+
+/// The `-l` value for a detected script among the `installed` traineddata: its language, plus English for the
+/// Latin text that's usually mixed in.
+pub fn lang_for(osd: &Osd, installed: &[String]) -> Option<String> {
+  if osd.script_confidence < MIN_CONFIDENCE {
+    return None;
+  }
+  let has = |l: &str| installed.iter().any(|i| i == l);
+  let primary = script_langs(&osd.script).iter().find(|l| has(l))?;
+  Some(if *primary != "eng" && has("eng") {
+    format!("{primary}+eng")
+  } else {
+    primary.to_string()
+  })
+}
+
+/// Rotation to apply, if the orientation is confident enough.
+pub fn rotation(osd: &Osd) -> u16 {
+  if osd.orientation_confidence < MIN_CONFIDENCE {
+    0
+  } else {
+    osd.rotate
+  }
+}
+
+/// Encode `image` (PNG) rotated `degrees` clockwise as a new PNG. Returns it and the original image's width and
+/// height.
+pub fn rotate_png(image: &OcrImage, degrees: u16) -> Result<(Vec<u8>, u32, u32), String> {
+  let data = image.data()?;
+  let decoded = image::load_from_memory(&data).map_err(|e| format!("cannot rotate image: {e}"))?;
+  let (w, h) = (decoded.width(), decoded.height());
+  let rotated = match degrees {
+    90 => decoded.rotate90(),
+    180 => decoded.rotate180(),
+    270 => decoded.rotate270(),
+    _ => decoded,
+  };
+  let mut png_data: Vec<u8> = Vec::new();
+  rotated
+    .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
+    .map_err(|e| format!("cannot encode rotated image: {e}"))?;
+  Ok((png_data, w, h))
+}
+
+/// Map a box found in the image rotated `degrees` clockwise back onto the original `width`x`height` image.
+fn unrotate(b: BBox, degrees: u16, width: i32, height: i32) -> BBox {
+  match degrees {
+    90 => BBox {
+      x: b.y,
+      y: height - b.x - b.width,
+      width: b.height,
+      height: b.width,
+    },
+    180 => BBox {
+      x: width - b.x - b.width,
+      y: height - b.y - b.height,
+      ..b
+    },
+    270 => BBox {
+      x: width - b.y - b.height,
+      y: b.x,
+      width: b.height,
+      height: b.width,
+    },
+    _ => b,
+  }
+}
+
+/// Put every box in `output` back into the original image's coordinates.
+pub fn unrotate_output(output: &mut OcrOutput, degrees: u16, width: u32, height: u32) {
+  let (w, h) = (width as i32, height as i32);
+  for line in &mut output.lines {
+    line.bbox = unrotate(line.bbox, degrees, w, h);
+    for word in &mut line.words {
+      word.bbox = unrotate(word.bbox, degrees, w, h);
+    }
+  }
+}
@@ -0,0 +1,115 @@
+//! Output length and verbosity controls.
+//!
+//! Set per mode in settings (`outputControls.<mode>`) and overridable per request. They are sent to the
+//! server as structured fields plus plain-language `constraints` for the prompt, and enforced locally:
+//! over-long output is cut at a sentence boundary, or re-requested once with stricter constraints.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Verbosity {
+  Concise,
+  Detailed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Enforcement {
+  /// Cut the output at the last sentence boundary within the limit.
+  #[default]
+  Truncate,
+  /// Ask once more with stricter constraints; truncate if that is still too long.
+  Rerequest,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputControls {
+  pub verbosity: Option<Verbosity>,
+  pub max_output_chars: Option<usize>,
+  pub include_examples: Option<bool>,
+  pub enforce: Option<Enforcement>,
+}
+
+impl OutputControls {
+  /// Fields set here win; unset ones come from `defaults`.
+  pub fn or(self, defaults: OutputControls) -> OutputControls {
+    OutputControls {
+      verbosity: self.verbosity.or(defaults.verbosity),
+      max_output_chars: self.max_output_chars.or(defaults.max_output_chars),
+      include_examples: self.include_examples.or(defaults.include_examples),
+      enforce: self.enforce.or(defaults.enforce),
+    }
+  }
+
+  /// Effective character limit (limits under 20 characters are ignored as nonsensical).
+  pub fn limit(&self) -> Option<usize> {
+    self.max_output_chars.filter(|n| *n >= 20)
+  }
+
+  /// Constraints for the re-request after the first answer came back too long.
+  pub fn stricter(&self) -> OutputControls {
+    OutputControls {
+      verbosity: Some(Verbosity::Concise),
+      max_output_chars: self.limit().map(|n| n * 2 / 3),
+      include_examples: Some(false),
+      enforce: Some(Enforcement::Truncate),
+    }
+  }
+
+  /// Plain-language prompt constraints.
+  pub fn constraints(&self) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    match self.verbosity {
+      Some(Verbosity::Concise) => out.push("Be concise: keep any explanation brief and to the point.".to_string()),
+      Some(Verbosity::Detailed) => out.push("Give a thorough, detailed explanation.".to_string()),
+      None => {}
+    }
+    if let Some(n) = self.limit() {
+      out.push(format!("Keep the whole response under {n} characters."));
+    }
+    match self.include_examples {
+      Some(true) => out.push("Include example sentences.".to_string()),
+      Some(false) => out.push("Do not include example sentences.".to_string()),
+      None => {}
+    }
+    out
+  }
+
+  /// Add the controls to a `/api/translate` body (nothing is added when no control is set).
+  pub fn apply_to_body(&self, body: &mut serde_json::Value) {
+    let constraints = self.constraints();
+    if constraints.is_empty() {
+      return;
+    }
+    body["output"] = serde_json::json!({
+      "verbosity": self.verbosity,
+      "max_output_chars": self.limit(),
+      "include_examples": self.include_examples,
+    });
+    body["constraints"] = serde_json::json!(constraints);
+  }
+}
+
+/// Cut `text` to at most `max_chars` characters, preferring a paragraph or sentence end, and mark the cut.
+pub fn truncate(text: &str, max_chars: usize) -> String {
+  if text.chars().count() <= max_chars {
+    return text.to_string();
+  }
+  let limit = text.char_indices().nth(max_chars).map(|(i, _)| i).unwrap_or(text.len());
+  let head = &text[..limit];
+  let boundary = head
+    .rfind("\n\n")
+    .or_else(|| {
+      head
+        .char_indices()
+        .rev()
+        .find(|(_, c)| matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n'))
+        .map(|(i, c)| i + c.len_utf8())
+    })
+    // Don't throw away most of the allowance just to end on a boundary.
+    .filter(|&i| i >= limit / 2)
+    .unwrap_or(limit);
+  format!("{}…", head[..boundary].trim_end())
+}
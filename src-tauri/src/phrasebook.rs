@@ -0,0 +1,411 @@
+//! The phrasebook: phrases the user curates on purpose (a greeting for a trip, a sentence worth learning), kept
+//! apart from the history, which records everything.
+//!
+//! A phrase has its text, a translation, optional languages, a category and a note. Phrases are added, edited and
+//! deleted one at a time, listed by category or search, copied to the clipboard with one command
+//! (`copy_phrase`), and exported or imported as JSON (everything) or CSV (`text,translation,category,note,
+//! source_lang,target_lang`, with a header row). They're saved to `phrasebook.json` in the app data dir, backed up
+//! with the rest of the app data and synced like the glossary.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::clock;
+use crate::glossary::{csv_rows, lang_key};
+use crate::history_export::{csv_field, csv_unguard};
+
+const PHRASEBOOK_FILE: &str = "phrasebook.json";
+const CSV_COLUMNS: [&str; 6] = ["text", "translation", "category", "note", "source_lang", "target_lang"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Phrase {
+  pub id: u64,
+  pub text: String,
+  pub translation: String,
+  #[serde(default)]
+  pub source_lang: Option<String>,
+  #[serde(default)]
+  pub target_lang: Option<String>,
+  #[serde(default)]
+  pub category: Option<String>,
+  #[serde(default)]
+  pub note: Option<String>,
+  pub created_at: u128,
+  pub updated_at: u128,
+}
+
+/// A phrase as the frontend sends it to `add_phrase` and `update_phrase`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PhraseInput {
+  pub text: String,
+  pub translation: String,
+  #[serde(default)]
+  pub source_lang: Option<String>,
+  #[serde(default)]
+  pub target_lang: Option<String>,
+  #[serde(default)]
+  pub category: Option<String>,
+  #[serde(default)]
+  pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PhraseCategory {
+  pub name: String,
+  pub count: usize,
+}
+
+/// Which side of a phrase `copy_phrase` copies.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PhraseSide {
+  Text,
+  #[default]
+  Translation,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PhrasebookFormat {
+  Json,
+  Csv,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ImportReport {
+  pub added: usize,
+  /// Phrases that replaced one with the same text and target language.
+  pub updated: usize,
+  /// Rows without both a text and a translation.
+  pub skipped: usize,
+}
+
+#[derive(Default)]
+struct Inner {
+  path: Option<PathBuf>,
+  phrases: Vec<Phrase>,
+}
+
+impl Inner {
+  fn save(&self) -> Result<(), String> {
+    let Some(path) = &self.path else {
+      return Ok(());
+    };
+    crate::json_store::save(path, &self.phrases).map_err(|e| format!("failed to save phrasebook: {e}"))
+  }
+
+  fn next_id(&self) -> u64 {
+    self.phrases.iter().map(|p| p.id).max().unwrap_or(0) + 1
+  }
+
+  /// Add `input`, or replace the phrase with the same text and target language; true when added.
+  fn upsert(&mut self, input: PhraseInput) -> bool {
+    let now = clock::now_millis();
+    let key = input_key(&input);
+    match self.phrases.iter_mut().find(|p| sync_key(p) == key) {
+      Some(phrase) => {
+        *phrase = to_phrase(phrase.id, phrase.created_at, now, input);
+        false
+      }
+      None => {
+        let id = self.next_id();
+        self.phrases.push(to_phrase(id, now, now, input));
+        true
+      }
+    }
+  }
+}
+
+/// The phrasebook (managed state).
+#[derive(Default)]
+pub struct Phrasebook(Mutex<Inner>);
+
+impl Phrasebook {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Load the saved phrasebook from `dir`.
+pub fn init(app: &tauri::AppHandle, dir: &Path) {
+  let path = dir.join(PHRASEBOOK_FILE);
+  let phrases = crate::json_store::load(app, &path, "phrasebook_unreadable");
+  let state = app.state::<Phrasebook>();
+  let mut inner = state.lock();
+  inner.path = Some(path);
+  inner.phrases = phrases;
+}
+
+fn non_empty(s: Option<String>) -> Option<String> {
+  s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn validate(input: PhraseInput) -> Result<PhraseInput, String> {
+  let input = PhraseInput {
+    text: input.text.trim().to_string(),
+    translation: input.translation.trim().to_string(),
+    source_lang: non_empty(input.source_lang),
+    target_lang: non_empty(input.target_lang),
+    category: non_empty(input.category),
+    note: non_empty(input.note),
+  };
+  if input.text.is_empty() {
+    return Err("phrase text is empty".to_string());
+  }
+  if input.translation.is_empty() {
+    return Err("phrase translation is empty".to_string());
+  }
+  Ok(input)
+}
+
+fn to_phrase(id: u64, created_at: u128, updated_at: u128, input: PhraseInput) -> Phrase {
+  Phrase {
+    id,
+    text: input.text,
+    translation: input.translation,
+    source_lang: input.source_lang,
+    target_lang: input.target_lang,
+    category: input.category,
+    note: input.note,
+    created_at,
+    updated_at,
+  }
+}
+
+fn key(text: &str, target_lang: Option<&str>) -> String {
+  format!("{}\u{1f}{}", text.to_lowercase(), lang_key(target_lang).unwrap_or_default())
+}
+
+fn input_key(input: &PhraseInput) -> String {
+  key(&input.text, input.target_lang.as_deref())
+}
+
+/// What makes a phrase the same one on another machine (ids aren't): its text and target language.
+pub(crate) fn sync_key(phrase: &Phrase) -> String {
+  key(&phrase.text, phrase.target_lang.as_deref())
+}
+
+/// Every phrase, for backups and sync.
+pub fn phrases(app: &tauri::AppHandle) -> Vec<Phrase> {
+  app.state::<Phrasebook>().lock().phrases.clone()
+}
+
+/// Replace every phrase with `phrases` and save them.
+pub fn replace(app: &tauri::AppHandle, phrases: Vec<Phrase>) -> Result<(), String> {
+  let state = app.state::<Phrasebook>();
+  let mut inner = state.lock();
+  inner.phrases = phrases;
+  inner.save()
+}
+
+/// Phrases in `category` (those without one for `""`) whose text, translation or note contains `query`
+/// (ignoring case), sorted by category, then text.
+#[tauri::command]
+pub fn list_phrases(
+  state: tauri::State<'_, Phrasebook>,
+  category: Option<String>,
+  query: Option<String>,
+) -> Vec<Phrase> {
+  let query = query.map(|q| q.trim().to_lowercase()).filter(|q| !q.is_empty());
+  let mut phrases: Vec<Phrase> = state
+    .lock()
+    .phrases
+    .iter()
+    .filter(|p| match &category {
+      Some(c) if c.is_empty() => p.category.is_none(),
+      Some(c) => p.category.as_deref() == Some(c.as_str()),
+      None => true,
+    })
+    .filter(|p| {
+      query.as_ref().map_or(true, |q| {
+        [Some(&p.text), Some(&p.translation), p.note.as_ref()]
+          .into_iter()
+          .flatten()
+          .any(|s| s.to_lowercase().contains(q))
+      })
+    })
+    .cloned()
+    .collect();
+  phrases.sort_by_key(|p| (p.category.clone().unwrap_or_default().to_lowercase(), p.text.to_lowercase()));
+  phrases
+}
+
+/// The categories in use and how many phrases each has, by name.
+#[tauri::command]
+pub fn list_phrase_categories(state: tauri::State<'_, Phrasebook>) -> Vec<PhraseCategory> {
+  let mut counts: std::collections::BTreeMap<String, usize> = std::collections::BTreeMap::new();
+  for phrase in &state.lock().phrases {
+    if let Some(category) = &phrase.category {
+      *counts.entry(category.clone()).or_default() += 1;
+    }
+  }
+  counts.into_iter().map(|(name, count)| PhraseCategory { name, count }).collect()
+}
+
+/// Add a phrase; returns it with its id.
+#[tauri::command]
+pub fn add_phrase(state: tauri::State<'_, Phrasebook>, phrase: PhraseInput) -> Result<Phrase, String> {
+  let phrase = validate(phrase)?;
+  let now = clock::now_millis();
+  let mut inner = state.lock();
+  let created = to_phrase(inner.next_id(), now, now, phrase);
+  inner.phrases.push(created.clone());
+  inner.save()?;
+  Ok(created)
+}
+
+/// Replace phrase `id` with `phrase`.
+#[tauri::command]
+pub fn update_phrase(state: tauri::State<'_, Phrasebook>, id: u64, phrase: PhraseInput) -> Result<Phrase, String> {
+  let phrase = validate(phrase)?;
+  let mut inner = state.lock();
+  let slot = inner
+    .phrases
+    .iter_mut()
+    .find(|p| p.id == id)
+    .ok_or_else(|| format!("no phrase {id}"))?;
+  *slot = to_phrase(id, slot.created_at, clock::now_millis(), phrase);
+  let updated = slot.clone();
+  inner.save()?;
+  Ok(updated)
+}
+
+/// Delete the phrases with these ids; returns how many were deleted.
+#[tauri::command]
+pub fn delete_phrases(state: tauri::State<'_, Phrasebook>, ids: Vec<u64>) -> Result<usize, String> {
+  let mut inner = state.lock();
+  let before = inner.phrases.len();
+  inner.phrases.retain(|p| !ids.contains(&p.id));
+  let deleted = before - inner.phrases.len();
+  if deleted > 0 {
+    inner.save()?;
+  }
+  Ok(deleted)
+}
+
+/// Copy phrase `id` to the clipboard: its translation, or its text with `side: "text"`.
+#[tauri::command]
+pub fn copy_phrase(state: tauri::State<'_, Phrasebook>, id: u64, side: Option<PhraseSide>) -> Result<(), String> {
+  let text = {
+    let inner = state.lock();
+    let phrase = inner.phrases.iter().find(|p| p.id == id).ok_or_else(|| format!("no phrase {id}"))?;
+    match side.unwrap_or_default() {
+      PhraseSide::Text => phrase.text.clone(),
+      PhraseSide::Translation => phrase.translation.clone(),
+    }
+  };
+  let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
+  clipboard.set_text(text).map_err(|e| format!("clipboard write failed: {e}"))
+}
+
+fn csv(phrases: &[Phrase]) -> String {
+  let mut out = String::from("\u{feff}");
+  out.push_str(&CSV_COLUMNS.join(","));
+  out.push_str("\r\n");
+  for p in phrases {
+    let fields = [
+      p.text.as_str(),
+      p.translation.as_str(),
+      p.category.as_deref().unwrap_or_default(),
+      p.note.as_deref().unwrap_or_default(),
+      p.source_lang.as_deref().unwrap_or_default(),
+      p.target_lang.as_deref().unwrap_or_default(),
+    ];
+    let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    out.push_str(&row.join(","));
+    out.push_str("\r\n");
+  }
+  out
+}
+
+fn format_of(path: &str, format: Option<PhrasebookFormat>) -> Result<PhrasebookFormat, String> {
+  if let Some(format) = format {
+    return Ok(format);
+  }
+  match Path::new(path).extension().and_then(|e| e.to_str()).map(str::to_lowercase).as_deref() {
+    Some("json") => Ok(PhrasebookFormat::Json),
+    Some("csv") | Some("tsv") | Some("txt") => Ok(PhrasebookFormat::Csv),
+    _ => Err(format!("unknown phrasebook format: {path} (expected .json or .csv)")),
+  }
+}
+
+/// Write the phrases (those in `category` when given) to `path` as `format` (by extension when unset); returns how
+/// many were written.
+#[tauri::command]
+pub async fn export_phrasebook(
+  app: tauri::AppHandle,
+  path: String,
+  format: Option<PhrasebookFormat>,
+  category: Option<String>,
+) -> Result<usize, String> {
+  let format = format_of(&path, format)?;
+  let phrases = list_phrases(app.state::<Phrasebook>(), category, None);
+  let out = match format {
+    PhrasebookFormat::Json => {
+      serde_json::to_string_pretty(&phrases).map_err(|e| format!("failed to serialize phrasebook: {e}"))?
+    }
+    PhrasebookFormat::Csv => csv(&phrases),
+  };
+  std::fs::write(&path, out).map_err(|e| format!("cannot write {path}: {e}"))?;
+  Ok(phrases.len())
+}
+
+fn parse_csv(text: &str) -> Vec<PhraseInput> {
+  let mut rows = csv_rows(text).into_iter().peekable();
+  let has_header = rows
+    .peek()
+    .and_then(|r| r.first())
+    .is_some_and(|f| f.trim().eq_ignore_ascii_case(CSV_COLUMNS[0]));
+  // Columns named by the header, otherwise in `CSV_COLUMNS` order.
+  let columns: Vec<Option<usize>> = if has_header {
+    let header: Vec<String> = rows.next().unwrap_or_default().iter().map(|h| h.trim().to_lowercase()).collect();
+    CSV_COLUMNS.iter().map(|c| header.iter().position(|h| h == c)).collect()
+  } else {
+    (0..CSV_COLUMNS.len()).map(Some).collect()
+  };
+  rows
+    .map(|row| {
+      let cell = |i: usize| columns[i].and_then(|i| row.get(i)).cloned().map(csv_unguard);
+      PhraseInput {
+        text: cell(0).unwrap_or_default(),
+        translation: cell(1).unwrap_or_default(),
+        category: cell(2),
+        note: cell(3),
+        source_lang: cell(4),
+        target_lang: cell(5),
+      }
+    })
+    .collect()
+}
+
+/// Import the phrases in `path` (a JSON export or CSV; by extension when `format` is unset). An imported phrase
+/// replaces the one with the same text and target language.
+#[tauri::command]
+pub async fn import_phrasebook(
+  app: tauri::AppHandle,
+  path: String,
+  format: Option<PhrasebookFormat>,
+) -> Result<ImportReport, String> {
+  let format = format_of(&path, format)?;
+  let bytes = std::fs::read(&path).map_err(|e| format!("cannot read {path}: {e}"))?;
+  let text = String::from_utf8(bytes).map_err(|_| format!("{path} is not UTF-8"))?;
+  let inputs = match format {
+    PhrasebookFormat::Json => serde_json::from_str::<Vec<PhraseInput>>(text.trim_start_matches('\u{feff}'))
+      .map_err(|e| format!("not a phrasebook export: {e}"))?,
+    PhrasebookFormat::Csv => parse_csv(&text),
+  };
+  let state = app.state::<Phrasebook>();
+  let mut inner = state.lock();
+  let mut report = ImportReport::default();
+  for input in inputs {
+    match validate(input).map(|input| inner.upsert(input)) {
+      Ok(true) => report.added += 1,
+      Ok(false) => report.updated += 1,
+      Err(_) => report.skipped += 1,
+    }
+  }
+  inner.save()?;
+  Ok(report)
+}
@@ -0,0 +1,543 @@
+//! User-provided pipeline extensions.
+//!
+//! A plugin is a directory under `<app data>/plugins/<id>/` containing a `plugin.json` manifest. Plugins get JSON
+//! on stdin and write the result to stdout (either `{"text": "..."}` or plain text). Each run is bounded by
+//! `timeout_ms` and an output cap.
+//!
+//! WASM plugins (`"kind": "wasm"`, a WASI command module) run in wasmtime: the plugin directory, read-only, is the
+//! only file system they see, they have no network access, and only `allow_env` variables are set. Executable
+//! plugins run with the plugin directory as cwd and a cleared environment but are not sandboxed: they have the
+//! user's privileges, so only install ones you trust.
+
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MANIFEST_FILE: &str = "plugin.json";
+const STATE_FILE: &str = "plugins-state.json";
+const MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+pub const PROVIDER_SCHEME: &str = "plugin://";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginStage {
+  PreProcess,
+  PostProcess,
+  Provider,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+  #[default]
+  Exec,
+  Wasm,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginManifest {
+  pub id: String,
+  pub name: String,
+  #[serde(default)]
+  pub version: String,
+  pub stage: PluginStage,
+  #[serde(default)]
+  pub kind: PluginKind,
+  /// Executable or `.wasm` module path, relative to the plugin directory.
+  pub entry: String,
+  #[serde(default)]
+  pub args: Vec<String>,
+  #[serde(default = "default_timeout_ms")]
+  pub timeout_ms: u64,
+  /// Environment variables passed through to the plugin (everything else is cleared).
+  #[serde(default)]
+  pub allow_env: Vec<String>,
+}
+
+fn default_timeout_ms() -> u64 {
+  10_000
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginInfo {
+  #[serde(flatten)]
+  pub manifest: PluginManifest,
+  pub enabled: bool,
+  pub dir: String,
+}
+
+/// Payload written to the plugin's stdin.
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginInput<'a> {
+  pub stage: PluginStage,
+  pub text: &'a str,
+  pub target_lang: &'a str,
+  pub mode: &'a str,
+  /// Glossary terms in the text (providers only, see `glossary`).
+  #[serde(skip_serializing_if = "<[_]>::is_empty")]
+  pub glossary: &'a [crate::glossary::Term],
+}
+
+#[derive(Default)]
+struct Registry {
+  dir: Option<PathBuf>,
+  plugins: Vec<PluginInfo>,
+}
+
+/// Installed plugins (managed state).
+#[derive(Default)]
+pub struct PluginRegistry(Mutex<Registry>);
+
+impl PluginRegistry {
+  /// Set the plugins directory and scan it.
+  pub fn init(&self, dir: PathBuf) {
+    let mut r = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    r.dir = Some(dir);
+    r.plugins = scan(r.dir.as_deref());
+  }
+
+  fn reload(&self) -> Vec<PluginInfo> {
+    let mut r = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    r.plugins = scan(r.dir.as_deref());
+    r.plugins.clone()
+  }
+
+  fn dir(&self) -> Result<PathBuf, String> {
+    let r = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    r.dir.clone().ok_or_else(|| "plugins dir not initialized".to_string())
+  }
+
+  pub fn list(&self) -> Vec<PluginInfo> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner()).plugins.clone()
+  }
+
+  fn get(&self, id: &str) -> Option<PluginInfo> {
+    self.list().into_iter().find(|p| p.manifest.id == id)
+  }
+
+  /// Enabled plugins for a stage, in id order.
+  pub fn enabled_for(&self, stage: PluginStage) -> Vec<PluginInfo> {
+    self
+      .list()
+      .into_iter()
+      .filter(|p| p.enabled && p.manifest.stage == stage)
+      .collect()
+  }
+}
+
+fn read_disabled(dir: &Path) -> Vec<String> {
+  std::fs::read_to_string(dir.join(STATE_FILE))
+    .ok()
+    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+    .and_then(|v| v.get("disabled").cloned())
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+fn write_disabled(dir: &Path, disabled: &[String]) -> Result<(), String> {
+  let s = serde_json::to_string_pretty(&serde_json::json!({ "disabled": disabled }))
+    .map_err(|e| format!("serialize plugin state failed: {e}"))?;
+  std::fs::write(dir.join(STATE_FILE), s).map_err(|e| format!("write plugin state failed: {e}"))
+}
+
+fn scan(dir: Option<&Path>) -> Vec<PluginInfo> {
+  let Some(dir) = dir else { return Vec::new() };
+  let disabled = read_disabled(dir);
+  let mut out: Vec<PluginInfo> = Vec::new();
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    return out;
+  };
+  for entry in entries.flatten() {
+    // Hidden directories are installs in progress (see `install_plugin`).
+    if entry.file_name().to_string_lossy().starts_with('.') {
+      continue;
+    }
+    let pdir = entry.path();
+    let Ok(s) = std::fs::read_to_string(pdir.join(MANIFEST_FILE)) else {
+      continue;
+    };
+    match serde_json::from_str::<PluginManifest>(&s) {
+      Ok(manifest) => out.push(PluginInfo {
+        enabled: !disabled.contains(&manifest.id),
+        manifest,
+        dir: pdir.to_string_lossy().to_string(),
+      }),
+      Err(e) => log::warn!("invalid plugin manifest in {}: {e}", pdir.display()),
+    }
+  }
+  out.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+  out
+}
+
+fn validate_id(id: &str) -> Result<(), String> {
+  let ok = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if ok {
+    Ok(())
+  } else {
+    Err(format!("invalid plugin id: {id}"))
+  }
+}
+
+/// Resolve `entry` inside the plugin dir, rejecting paths that escape it.
+fn resolve_entry(plugin: &PluginInfo) -> Result<PathBuf, String> {
+  let dir = PathBuf::from(&plugin.dir)
+    .canonicalize()
+    .map_err(|e| format!("plugin dir missing: {e}"))?;
+  let entry = dir
+    .join(&plugin.manifest.entry)
+    .canonicalize()
+    .map_err(|e| format!("plugin entry missing: {e}"))?;
+  if !entry.starts_with(&dir) {
+    return Err("plugin entry must be inside the plugin directory".to_string());
+  }
+  Ok(entry)
+}
+
+/// Memory a WASM plugin may grow to.
+const WASM_MAX_MEMORY_BYTES: usize = 256 * 1024 * 1024;
+
+struct WasmState {
+  wasi: wasmtime_wasi::preview1::WasiP1Ctx,
+  limits: wasmtime::StoreLimits,
+}
+
+fn run_wasm(plugin: &PluginInfo, input: &[u8]) -> Result<String, String> {
+  use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+  use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+  let entry = resolve_entry(plugin)?;
+  let id = &plugin.manifest.id;
+  let mut config = wasmtime::Config::new();
+  config.epoch_interruption(true);
+  let engine = wasmtime::Engine::new(&config).map_err(|e| format!("wasm engine failed: {e}"))?;
+  let module = wasmtime::Module::from_file(&engine, &entry).map_err(|e| format!("invalid wasm plugin {id}: {e}"))?;
+  let mut linker = wasmtime::Linker::new(&engine);
+  wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s: &mut WasmState| &mut s.wasi)
+    .map_err(|e| format!("wasm link failed: {e}"))?;
+
+  let stdout = MemoryOutputPipe::new(MAX_OUTPUT_BYTES + 1);
+  let stderr = MemoryOutputPipe::new(MAX_OUTPUT_BYTES);
+  let mut wasi = WasiCtxBuilder::new();
+  wasi
+    .stdin(MemoryInputPipe::new(input.to_vec()))
+    .stdout(stdout.clone())
+    .stderr(stderr.clone())
+    .arg(&plugin.manifest.entry)
+    .args(plugin.manifest.args.as_slice());
+  for key in &plugin.manifest.allow_env {
+    if let Ok(v) = std::env::var(key) {
+      wasi.env(key, v);
+    }
+  }
+  // No sockets are granted, so there is no network; the plugin's own directory is all it can read.
+  wasi
+    .preopened_dir(&plugin.dir, ".", DirPerms::READ, FilePerms::READ)
+    .map_err(|e| format!("plugin dir missing: {e}"))?;
+  let state = WasmState {
+    wasi: wasi.build_p1(),
+    limits: wasmtime::StoreLimitsBuilder::new().memory_size(WASM_MAX_MEMORY_BYTES).build(),
+  };
+  let mut store = wasmtime::Store::new(&engine, state);
+  store.limiter(|s| &mut s.limits);
+  store.set_epoch_deadline(1);
+
+  // Interrupts the module once `timeout_ms` passes; finishing first drops `done` and stops the watchdog.
+  let (done, finished) = std::sync::mpsc::channel::<()>();
+  let timeout = std::time::Duration::from_millis(plugin.manifest.timeout_ms);
+  let watchdog = engine.clone();
+  std::thread::spawn(move || {
+    if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = finished.recv_timeout(timeout) {
+      watchdog.increment_epoch();
+    }
+  });
+  let result = linker
+    .instantiate(&mut store, &module)
+    .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+    .and_then(|start| start.call(&mut store, ()));
+  drop(done);
+
+  let code = match result {
+    Ok(()) => 0,
+    Err(e) => match (e.downcast_ref::<wasmtime_wasi::I32Exit>(), e.downcast_ref::<wasmtime::Trap>()) {
+      (Some(exit), _) => exit.0,
+      (_, Some(wasmtime::Trap::Interrupt)) => return Err(format!("PLUGIN_TIMEOUT: {id}")),
+      _ => return Err(format!("plugin {id} failed: {e}")),
+    },
+  };
+  let out = stdout.contents();
+  if out.len() > MAX_OUTPUT_BYTES {
+    return Err(format!("plugin output too large: {id}"));
+  }
+  if code != 0 {
+    let err = String::from_utf8_lossy(&stderr.contents()).to_string();
+    return Err(format!("plugin {id} failed: {}", err.trim()));
+  }
+  parse_output(plugin, &out)
+}
+
+fn run_exec(plugin: &PluginInfo, input: &[u8]) -> Result<String, String> {
+  let entry = resolve_entry(plugin)?;
+  let mut cmd = std::process::Command::new(&entry);
+  cmd
+    .args(&plugin.manifest.args)
+    .current_dir(&plugin.dir)
+    .env_clear()
+    .stdin(std::process::Stdio::piped())
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped());
+  // Keep the minimum needed for executables to start on Windows.
+  for key in ["SYSTEMROOT", "TEMP", "TMP"]
+    .iter()
+    .map(|s| s.to_string())
+    .chain(plugin.manifest.allow_env.iter().cloned())
+  {
+    if let Ok(v) = std::env::var(&key) {
+      cmd.env(&key, v);
+    }
+  }
+
+  let mut child = cmd.spawn().map_err(|e| format!("failed to start plugin: {e}"))?;
+  if let Some(mut stdin) = child.stdin.take() {
+    let input = input.to_vec();
+    std::thread::spawn(move || {
+      let _ = stdin.write_all(&input);
+    });
+  }
+  let mut stdout = child.stdout.take().ok_or_else(|| "plugin stdout unavailable".to_string())?;
+  let reader = std::thread::spawn(move || {
+    let mut buf: Vec<u8> = Vec::new();
+    let _ = (&mut stdout).take(MAX_OUTPUT_BYTES as u64 + 1).read_to_end(&mut buf);
+    let _ = std::io::copy(&mut stdout, &mut std::io::sink());
+    buf
+  });
+  // Drained alongside stdout, so a plugin that logs a lot can't block on a full pipe.
+  let mut stderr = child.stderr.take().ok_or_else(|| "plugin stderr unavailable".to_string())?;
+  let err_reader = std::thread::spawn(move || {
+    let mut buf: Vec<u8> = Vec::new();
+    let _ = (&mut stderr).take(MAX_OUTPUT_BYTES as u64).read_to_end(&mut buf);
+    let _ = std::io::copy(&mut stderr, &mut std::io::sink());
+    buf
+  });
+
+  let started = std::time::Instant::now();
+  let status = loop {
+    if let Some(status) = child.try_wait().map_err(|e| format!("plugin wait failed: {e}"))? {
+      break status;
+    }
+    if started.elapsed().as_millis() > plugin.manifest.timeout_ms as u128 {
+      let _ = child.kill();
+      let _ = child.wait();
+      return Err(format!("PLUGIN_TIMEOUT: {}", plugin.manifest.id));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(20));
+  };
+  let out = reader.join().unwrap_or_default();
+  if out.len() > MAX_OUTPUT_BYTES {
+    return Err(format!("plugin output too large: {}", plugin.manifest.id));
+  }
+  let err = err_reader.join().unwrap_or_default();
+  if !status.success() {
+    let err = String::from_utf8_lossy(&err);
+    return Err(format!("plugin {} failed: {}", plugin.manifest.id, err.trim()));
+  }
+  parse_output(plugin, &out)
+}
+
+fn parse_output(plugin: &PluginInfo, out: &[u8]) -> Result<String, String> {
+  let s = String::from_utf8_lossy(out).to_string();
+  // Accept {"text": "..."} or plain text.
+  if let Ok(v) = serde_json::from_str::<serde_json::Value>(&s) {
+    if let Some(t) = v.get("text").and_then(|x| x.as_str()) {
+      return Ok(t.to_string());
+    }
+    if let Some(e) = v.get("error").and_then(|x| x.as_str()) {
+      return Err(format!("plugin {} error: {e}", plugin.manifest.id));
+    }
+  }
+  Ok(s.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Run one plugin off the async runtime.
+pub async fn run(plugin: PluginInfo, input: PluginInput<'_>) -> Result<String, String> {
+  let payload = serde_json::to_vec(&input).map_err(|e| format!("serialize plugin input failed: {e}"))?;
+  tauri::async_runtime::spawn_blocking(move || match plugin.manifest.kind {
+    PluginKind::Exec => run_exec(&plugin, &payload),
+    PluginKind::Wasm => run_wasm(&plugin, &payload),
+  })
+    .await
+    .map_err(|e| format!("plugin task failed: {e}"))?
+}
+
+/// Pipe `text` through every enabled plugin of `stage`.
+pub async fn apply_stage(
+  registry: &PluginRegistry,
+  stage: PluginStage,
+  text: String,
+  target_lang: &str,
+  mode: &str,
+) -> Result<String, String> {
+  let mut text = text;
+  for plugin in registry.enabled_for(stage) {
+    text = run(
+      plugin,
+      PluginInput {
+        stage,
+        text: &text,
+        target_lang,
+        mode,
+        glossary: &[],
+      },
+    )
+    .await?;
+  }
+  Ok(text)
+}
+
+/// `plugin://<id>` base URLs route translation to a provider plugin.
+pub fn provider_id(base_url: &str) -> Option<&str> {
+  base_url.strip_prefix(PROVIDER_SCHEME).map(|s| s.trim_matches('/'))
+}
+
+pub fn provider(registry: &PluginRegistry, id: &str) -> Result<PluginInfo, String> {
+  let p = registry.get(id).ok_or_else(|| format!("PLUGIN_NOT_FOUND: {id}"))?;
+  if p.manifest.stage != PluginStage::Provider {
+    return Err(format!("plugin {id} is not a provider"));
+  }
+  if !p.enabled {
+    return Err(format!("plugin {id} is disabled"));
+  }
+  Ok(p)
+}
+
+fn copy_dir(src: &Path, dst: &Path) -> Result<(), String> {
+  std::fs::create_dir_all(dst).map_err(|e| format!("create dir failed: {e}"))?;
+  for entry in std::fs::read_dir(src).map_err(|e| format!("read dir failed: {e}"))? {
+    let entry = entry.map_err(|e| format!("read dir failed: {e}"))?;
+    let from = entry.path();
+    let to = dst.join(entry.file_name());
+    if from.is_dir() {
+      copy_dir(&from, &to)?;
+    } else {
+      std::fs::copy(&from, &to).map_err(|e| format!("copy failed: {e}"))?;
+    }
+  }
+  Ok(())
+}
+
+#[tauri::command]
+pub fn list_plugins(registry: tauri::State<'_, PluginRegistry>) -> Vec<PluginInfo> {
+  registry.list()
+}
+
+#[tauri::command]
+pub fn reload_plugins(registry: tauri::State<'_, PluginRegistry>) -> Vec<PluginInfo> {
+  registry.reload()
+}
+
+/// Install a plugin by copying a directory containing `plugin.json`. The copy is made next to the plugins and only
+/// then swapped in, so a failed copy leaves an installed version of the plugin as it was.
+#[tauri::command]
+pub fn install_plugin(source_dir: String, registry: tauri::State<'_, PluginRegistry>) -> Result<PluginInfo, String> {
+  let src = PathBuf::from(source_dir.trim());
+  let s = std::fs::read_to_string(src.join(MANIFEST_FILE)).map_err(|e| format!("read manifest failed: {e}"))?;
+  let manifest: PluginManifest = serde_json::from_str(&s).map_err(|e| format!("invalid manifest: {e}"))?;
+  validate_id(&manifest.id)?;
+  let dir = registry.dir()?;
+  let dst = dir.join(&manifest.id);
+  let staging = dir.join(format!(".{}.installing", manifest.id));
+  let old = dir.join(format!(".{}.old", manifest.id));
+  for leftover in [&staging, &old] {
+    if leftover.exists() {
+      std::fs::remove_dir_all(leftover).map_err(|e| format!("remove old plugin failed: {e}"))?;
+    }
+  }
+  if let Err(e) = copy_dir(&src, &staging) {
+    let _ = std::fs::remove_dir_all(&staging);
+    return Err(e);
+  }
+  let replacing = dst.exists();
+  if replacing {
+    if let Err(e) = std::fs::rename(&dst, &old) {
+      let _ = std::fs::remove_dir_all(&staging);
+      return Err(format!("replace old plugin failed: {e}"));
+    }
+  }
+  if let Err(e) = std::fs::rename(&staging, &dst) {
+    if replacing {
+      let _ = std::fs::rename(&old, &dst);
+    }
+    let _ = std::fs::remove_dir_all(&staging);
+    return Err(format!("install plugin failed: {e}"));
+  }
+  if replacing {
+    let _ = std::fs::remove_dir_all(&old);
+  }
+  registry.reload();
+  registry
+    .get(&manifest.id)
+    .ok_or_else(|| "plugin install failed".to_string())
+}
+
+#[tauri::command]
+pub fn uninstall_plugin(id: String, registry: tauri::State<'_, PluginRegistry>) -> Result<(), String> {
+  validate_id(&id)?;
+  let dir = registry.dir()?;
+  // Only ever delete the directory of an installed plugin, never a path built from the id alone.
+  let plugin = registry.get(&id).ok_or_else(|| format!("PLUGIN_NOT_FOUND: {id}"))?;
+  let pdir = PathBuf::from(&plugin.dir);
+  let inside = match (pdir.canonicalize(), dir.canonicalize()) {
+    (Ok(p), Ok(d)) => p.parent() == Some(d.as_path()),
+    _ => false,
+  };
+  if !inside {
+    return Err(format!("plugin {id} is not in the plugins directory"));
+  }
+  std::fs::remove_dir_all(&pdir).map_err(|e| format!("remove plugin failed: {e}"))?;
+  let disabled: Vec<String> = read_disabled(&dir).into_iter().filter(|d| d != &id).collect();
+  write_disabled(&dir, &disabled)?;
+  registry.reload();
+  Ok(())
+}
+
+#[tauri::command]
+pub fn set_plugin_enabled(
+  id: String,
+  enabled: bool,
+  registry: tauri::State<'_, PluginRegistry>,
+) -> Result<(), String> {
+  let dir = registry.dir()?;
+  if registry.get(&id).is_none() {
+    return Err(format!("PLUGIN_NOT_FOUND: {id}"));
+  }
+  let mut disabled: Vec<String> = read_disabled(&dir).into_iter().filter(|d| d != &id).collect();
+  if !enabled {
+    disabled.push(id);
+  }
+  write_disabled(&dir, &disabled)?;
+  registry.reload();
+  Ok(())
+}
+
+/// Run a single plugin directly (for testing a plugin from the settings UI).
+#[tauri::command]
+pub async fn run_plugin(
+  id: String,
+  text: String,
+  target_lang: Option<String>,
+  registry: tauri::State<'_, PluginRegistry>,
+) -> Result<String, String> {
+  let plugin = registry.get(&id).ok_or_else(|| format!("PLUGIN_NOT_FOUND: {id}"))?;
+  let stage = plugin.manifest.stage;
+  run(
+    plugin,
+    PluginInput {
+      stage,
+      text: &text,
+      target_lang: target_lang.as_deref().unwrap_or(""),
+      mode: "standard",
+      glossary: &[],
+    },
+  )
+  .await
+}
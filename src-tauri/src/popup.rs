@@ -0,0 +1,335 @@
+//! The translation popup window: where it opens, and whether clicks pass through it.
+//!
+//! The popup opens just below the point it belongs to (the pointer, or the bottom of an OCR selection), centered
+//! on it, and flips above the point when there isn't room below. It is kept inside the work area of the monitor
+//! holding the point, so it never lands under the taskbar or dock or straddles two monitors. Sizes are logical
+//! pixels and are scaled by that monitor's factor, so the popup fits the same way on a 100% and a 200% display.
+//!
+//! A click-through popup or OCR overlay ignores the mouse (`WS_EX_TRANSPARENT | WS_EX_LAYERED` on Windows,
+//! `ignoresMouseEvents` on macOS, an empty input shape on X11), so a translation can sit over a game while the game
+//! keeps getting the clicks. Such a window can't be clicked to turn it back off; that's what the hotkey is for.
+//!
+//! A pinned window stays on top of other windows and isn't closed for the app's own reasons: losing focus, the
+//! translate hotkey pressed again, the main window closing. It isn't moved to the pointer for the next
+//! translation either. Pins are kept by label in `pins.json` in the app data dir, so a popup opened later under a
+//! pinned label, also after a restart, comes up pinned.
+//!
+//! The popup hides itself when the user moves on (`popupAutoHide`, default on): when it loses focus, or when one
+//! of our other windows takes focus while the popup never had it (an unfocused popup knows nothing of clicks
+//! outside it). The hide waits `popupAutoHideGraceMs` (default 150) and is called off if the popup gets focus
+//! back or moves in the meantime, as it does while dragged by its handle. Pinned and click-through popups stay.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::commands::CaptureRect;
+use crate::screen::LogicalRect;
+use crate::settings;
+
+pub const POPUP_LABEL: &str = "popup";
+const PINS_FILE: &str = "pins.json";
+const PINNED_EVENT: &str = "erudaite://window/pinned";
+/// Logical gap between the point and the popup below it, and above it.
+const GAP_BELOW: f64 = 18.0;
+const GAP_ABOVE: f64 = 12.0;
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct PhysicalPoint {
+  pub x: i32,
+  pub y: i32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PopupPlacement {
+  /// Physical pixels of the desktop.
+  pub physical: CaptureRect,
+  /// The same rectangle in logical pixels (divided by the monitor's scale), as window options take it.
+  pub logical: LogicalRect,
+  pub scale_factor: f64,
+  pub monitor: Option<String>,
+  /// Whether the popup had to go above the point.
+  pub above: bool,
+  /// Whether an open popup window was moved there.
+  pub applied: bool,
+}
+
+/// Where a `width` x `height` (logical) popup for `point` goes, or `None` when no monitor is known.
+pub fn place(app: &tauri::AppHandle, point: PhysicalPoint, width: f64, height: f64) -> Option<PopupPlacement> {
+  let all = app.available_monitors().ok()?;
+  let contains = |m: &&tauri::Monitor| {
+    let (p, s) = (m.position(), m.size());
+    point.x >= p.x && point.y >= p.y && point.x < p.x + s.width as i32 && point.y < p.y + s.height as i32
+  };
+  // A point off every monitor (a stale position after a display was unplugged) falls back to the primary.
+  let monitor = match all.iter().find(contains) {
+    Some(m) => m.clone(),
+    None => app.primary_monitor().ok().flatten().or_else(|| all.first().cloned())?,
+  };
+  let scale = monitor.scale_factor();
+  let work = monitor.work_area();
+  let (left, top) = (work.position.x, work.position.y);
+  let (right, bottom) = (left + work.size.width as i32, top + work.size.height as i32);
+
+  let w = ((width * scale).round() as i32).clamp(1, work.size.width.max(1) as i32);
+  let h = ((height * scale).round() as i32).clamp(1, work.size.height.max(1) as i32);
+  let x = (point.x - w / 2).clamp(left, right - w);
+  let below = point.y + (GAP_BELOW * scale).round() as i32;
+  let above = below + h > bottom;
+  let y = if above { point.y - h - (GAP_ABOVE * scale).round() as i32 } else { below };
+  let y = y.clamp(top, bottom - h);
+
+  Some(PopupPlacement {
+    physical: CaptureRect { x, y, width: w as u32, height: h as u32 },
+    logical: LogicalRect {
+      x: x as f64 / scale,
+      y: y as f64 / scale,
+      width: w as f64 / scale,
+      height: h as f64 / scale,
+    },
+    scale_factor: scale,
+    monitor: monitor.name().cloned(),
+    above,
+    applied: false,
+  })
+}
+
+/// Work out where a `width` x `height` (logical pixels) popup goes for `anchor` (physical; default the pointer)
+/// and, if the popup window is open, move and size it there.
+#[tauri::command]
+pub fn position_popup(
+  app: tauri::AppHandle,
+  width: f64,
+  height: f64,
+  anchor: Option<PhysicalPoint>,
+) -> Result<PopupPlacement, String> {
+  if !(width > 0.0 && height > 0.0) {
+    return Err("invalid popup size".to_string());
+  }
+  let point = match anchor {
+    Some(point) => point,
+    None => {
+      let cursor = crate::commands::get_cursor_position()?;
+      PhysicalPoint { x: cursor.x, y: cursor.y }
+    }
+  };
+  let mut placement = place(&app, point, width, height).ok_or_else(|| "no monitors found".to_string())?;
+  let window = app.get_webview_window(POPUP_LABEL).filter(|_| !is_pinned(&app, POPUP_LABEL));
+  if let Some(window) = window {
+    let r = &placement.physical;
+    // Position first: moving onto a monitor with another scale rescales the window, which the size then fixes.
+    window
+      .set_position(tauri::PhysicalPosition::new(r.x, r.y))
+      .and_then(|_| window.set_size(tauri::PhysicalSize::new(r.width, r.height)))
+      .map_err(|e| format!("cannot move the popup: {e}"))?;
+    placement.applied = true;
+  }
+  Ok(placement)
+}
+
+/// Labels of the windows currently ignoring the mouse (managed state).
+#[derive(Default)]
+pub struct ClickThrough(Mutex<HashSet<String>>);
+
+impl ClickThrough {
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Make window `label` (default the popup; only the popups and the OCR overlay) let clicks through to what's
+/// under it, or stop; `enabled: None` toggles. Returns whether it now lets clicks through.
+#[tauri::command]
+pub fn set_click_through(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, ClickThrough>,
+  label: Option<String>,
+  enabled: Option<bool>,
+) -> Result<bool, String> {
+  let label = label.unwrap_or_else(|| POPUP_LABEL.to_string());
+  if !crate::capture_exclusion::is_overlay(&label) {
+    return Err(format!("click-through is only for the popup and the OCR overlay, not {label}"));
+  }
+  let window = app.get_webview_window(&label).ok_or_else(|| format!("no window {label}"))?;
+  let enabled = {
+    let mut windows = state.lock();
+    let enabled = enabled.unwrap_or(!windows.contains(&label));
+    window
+      .set_ignore_cursor_events(enabled)
+      .map_err(|e| format!("cannot change click-through: {e}"))?;
+    if enabled {
+      windows.insert(label);
+    } else {
+      windows.remove(&label);
+    }
+    enabled
+  };
+  // Turning click-through off drops the layered style the window's opacity lives in (see `fade`).
+  crate::fade::reapply(&window)?;
+  Ok(enabled)
+}
+
+/// Whether window `label` (default the popup) lets clicks through.
+#[tauri::command]
+pub fn is_click_through(state: tauri::State<'_, ClickThrough>, label: Option<String>) -> bool {
+  state.lock().contains(label.as_deref().unwrap_or(POPUP_LABEL))
+}
+
+/// A pending auto-hide of the popup (managed state); bumping the generation calls it off.
+#[derive(Default)]
+pub struct AutoHide {
+  generation: AtomicU64,
+}
+
+/// Hide the popup after the grace period, unless something calls it off first.
+fn schedule_hide(app: &tauri::AppHandle) {
+  if !settings::popup_auto_hide(app) || is_pinned(app, POPUP_LABEL) {
+    return;
+  }
+  if app.state::<ClickThrough>().lock().contains(POPUP_LABEL) {
+    return;
+  }
+  let generation = app.state::<AutoHide>().generation.fetch_add(1, Ordering::SeqCst) + 1;
+  let grace = Duration::from_millis(settings::popup_auto_hide_grace_ms(app));
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(grace).await;
+    if app.state::<AutoHide>().generation.load(Ordering::SeqCst) != generation || is_pinned(&app, POPUP_LABEL) {
+      return;
+    }
+    let Some(window) = app.get_webview_window(POPUP_LABEL) else {
+      return;
+    };
+    if window.is_focused().unwrap_or(false) {
+      return;
+    }
+    // Destroyed, not just hidden, so a hidden window doesn't hold the label.
+    let duration = Duration::from_millis(settings::popup_fade_ms(&app));
+    if let Err(e) = crate::fade::fade_out(&window, duration, true).await {
+      log::warn!("{e}");
+      let _ = window.destroy();
+    }
+  });
+}
+
+fn cancel_hide(app: &tauri::AppHandle) {
+  app.state::<AutoHide>().generation.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Auto-hide of the popup; a window opened again under the same label starts out taking clicks, so forget
+/// closed ones.
+pub fn on_window_event(window: &tauri::Window, event: &tauri::WindowEvent) {
+  let app = window.app_handle();
+  let is_popup = window.label() == POPUP_LABEL;
+  match event {
+    tauri::WindowEvent::Destroyed => {
+      window.state::<ClickThrough>().lock().remove(window.label());
+    }
+    tauri::WindowEvent::Focused(false) if is_popup => schedule_hide(app),
+    tauri::WindowEvent::Focused(true) | tauri::WindowEvent::Moved(_) if is_popup => cancel_hide(app),
+    // The popup can't see clicks outside it unless it had focus; one of our windows taking focus is one.
+    tauri::WindowEvent::Focused(true) if !crate::capture_exclusion::is_overlay(window.label()) => {
+      let unfocused = app.get_webview_window(POPUP_LABEL).is_some_and(|w| !w.is_focused().unwrap_or(true));
+      if unfocused {
+        schedule_hide(app);
+      }
+    }
+    _ => {}
+  }
+}
+
+#[derive(Default)]
+struct PinsInner {
+  path: Option<PathBuf>,
+  labels: BTreeSet<String>,
+}
+
+/// Labels of the pinned windows (managed state).
+#[derive(Default)]
+pub struct Pins(Mutex<PinsInner>);
+
+impl Pins {
+  fn lock(&self) -> std::sync::MutexGuard<'_, PinsInner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Load the saved pins from `dir`.
+pub fn init(app: &tauri::AppHandle, dir: &std::path::Path) {
+  let path = dir.join(PINS_FILE);
+  let labels = std::fs::read(&path)
+    .ok()
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default();
+  let state = app.state::<Pins>();
+  let mut inner = state.lock();
+  inner.path = Some(path);
+  inner.labels = labels;
+}
+
+/// Whether window `label` is pinned.
+pub fn is_pinned(app: &tauri::AppHandle, label: &str) -> bool {
+  app.state::<Pins>().lock().labels.contains(label)
+}
+
+#[derive(Debug, Serialize, Clone)]
+struct PinnedPayload<'a> {
+  label: &'a str,
+  pinned: bool,
+}
+
+/// Whether window `label` opens on top of other windows (popups and overlays), pinned or not.
+fn opens_on_top(label: &str) -> bool {
+  label.starts_with(POPUP_LABEL) || label == crate::ocr_overlay::LABEL || label == crate::translation_overlay::LABEL
+}
+
+/// Pin window `label` (keep it on top and open, see above) or unpin it. Unpinning lets other windows cover it,
+/// except popups and overlays, which stay on top as they opened. The pin is saved even when the window isn't open;
+/// it applies when it opens. Every window gets `erudaite://window/pinned` with `{ label, pinned }`.
+#[tauri::command]
+pub fn set_always_on_top(app: tauri::AppHandle, label: String, pinned: bool) -> Result<(), String> {
+  if let Some(window) = app.get_webview_window(&label) {
+    window
+      .set_always_on_top(pinned || opens_on_top(&label))
+      .map_err(|e| format!("cannot change always-on-top: {e}"))?;
+  }
+  {
+    let state = app.state::<Pins>();
+    let mut inner = state.lock();
+    let changed = if pinned { inner.labels.insert(label.clone()) } else { inner.labels.remove(&label) };
+    if changed {
+      if let Some(path) = &inner.path {
+        match serde_json::to_vec_pretty(&inner.labels) {
+          Ok(bytes) => {
+            if let Err(e) = std::fs::write(path, bytes) {
+              log::warn!("failed to write pins: {e}");
+            }
+          }
+          Err(e) => log::warn!("failed to serialize pins: {e}"),
+        }
+      }
+    }
+  }
+  let _ = app.emit(PINNED_EVENT, PinnedPayload { label: &label, pinned });
+  Ok(())
+}
+
+/// Whether window `label` (default the popup) is pinned.
+#[tauri::command]
+pub fn is_window_pinned(app: tauri::AppHandle, label: Option<String>) -> bool {
+  is_pinned(&app, label.as_deref().unwrap_or(POPUP_LABEL))
+}
+
+/// Put a pinned window on top as it loads.
+pub fn on_page_load(webview: &tauri::Webview, payload: &tauri::webview::PageLoadPayload<'_>) {
+  if payload.event() != tauri::webview::PageLoadEvent::Started || !is_pinned(webview.app_handle(), webview.label()) {
+    return;
+  }
+  if let Err(e) = webview.window().set_always_on_top(true) {
+    log::warn!("failed to keep {} on top: {e}", webview.label());
+  }
+}
@@ -0,0 +1,232 @@
+//! Placeholder and markup protection.
+//!
+//! URLs, code spans, format variables (`{name}`, `${x}`, `%s`, `%1$d`), HTML/XML tags and emoji are swapped
+//! for opaque markers (`⟦0⟧`, `⟦1⟧`, ...) before the text goes to the provider, and swapped back in the
+//! output, so the model can't translate or reformat them.
+
+const OPEN: char = '⟦';
+const CLOSE: char = '⟧';
+/// Longest `{...}` / `<...>` span still treated as a placeholder or tag.
+const MAX_SPAN_CHARS: usize = 64;
+
+#[derive(Debug, Default, Clone)]
+pub struct Masks {
+  spans: Vec<String>,
+}
+
+impl Masks {
+  pub fn is_empty(&self) -> bool {
+    self.spans.is_empty()
+  }
+
+  /// Replace every marker with its original span. Unknown markers are left alone.
+  pub fn restore(&self, text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(OPEN) {
+      out.push_str(&rest[..start]);
+      let after = &rest[start + OPEN.len_utf8()..];
+      match after.find(CLOSE).and_then(|end| Some((end, after[..end].trim().parse::<usize>().ok()?))) {
+        Some((end, i)) if i < self.spans.len() => {
+          out.push_str(&self.spans[i]);
+          rest = &after[end + CLOSE.len_utf8()..];
+        }
+        _ => {
+          out.push(OPEN);
+          rest = after;
+        }
+      }
+    }
+    out.push_str(rest);
+    out
+  }
+
+  /// Spans whose marker doesn't appear in `masked_output` (the model dropped them).
+  pub fn missing(&self, masked_output: &str) -> Vec<&str> {
+    (0..self.spans.len())
+      .filter(|i| !masked_output.contains(&marker(*i)))
+      .map(|i| self.spans[i].as_str())
+      .collect()
+  }
+}
+
+fn marker(i: usize) -> String {
+  format!("{OPEN}{i}{CLOSE}")
+}
+
+fn is_emoji(c: char) -> bool {
+  matches!(c as u32,
+    0x1F000..=0x1FAFF // pictographs, incl. regional indicators (flags)
+    | 0x2600..=0x27BF
+    | 0x2B00..=0x2BFF
+  )
+}
+
+/// Characters that continue an emoji sequence (ZWJ, variation selectors, skin tones, keycaps).
+fn is_emoji_joiner(c: char) -> bool {
+  matches!(c as u32, 0x200D | 0xFE0E | 0xFE0F | 0x20E3 | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F)
+}
+
+/// Byte length of a protected span starting at `s`, if one starts there.
+fn span_len(s: &str) -> Option<usize> {
+  let first = s.chars().next()?;
+
+  // Fenced and inline code.
+  if let Some(body) = s.strip_prefix("```") {
+    let end = body.find("```")?;
+    return Some(3 + end + 3);
+  }
+  if first == '`' {
+    let end = s[1..].find(['`', '\n'])?;
+    return (s.as_bytes()[1 + end] == b'`').then_some(1 + end + 1);
+  }
+
+  // URLs
+  if s.starts_with("http://") || s.starts_with("https://") || s.starts_with("www.") {
+    let end = s
+      .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '「' | '」' | '、' | '。'))
+      .unwrap_or(s.len());
+    let url = s[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']']);
+    return (url.len() > "www.".len()).then_some(url.len());
+  }
+
+  // {name}, {{name}}, ${name}
+  let brace_start = if s.starts_with("${") || s.starts_with("{{") {
+    Some(2)
+  } else if first == '{' {
+    Some(1)
+  } else {
+    None
+  };
+  if let Some(open) = brace_start {
+    let close = if s.starts_with("{{") { "}}" } else { "}" };
+    let end = s[open..].find(close)?;
+    let inner = &s[open..open + end];
+    let ok = !inner.is_empty() && inner.chars().count() <= MAX_SPAN_CHARS && !inner.contains(['{', '\n']);
+    return ok.then_some(open + end + close.len());
+  }
+
+  // printf-style: %s, %d, %1$s, %.2f, %@
+  if first == '%' {
+    let rest = &s[1..];
+    let spec = rest
+      .char_indices()
+      .find(|(_, c)| !(c.is_ascii_digit() || matches!(c, '$' | '.' | '-' | '+')))?;
+    return matches!(spec.1, 's' | 'd' | 'i' | 'f' | 'u' | 'x' | 'X' | 'c' | '@' | 'e' | 'g')
+      .then_some(1 + spec.0 + spec.1.len_utf8());
+  }
+
+  // HTML/XML tags: <b>, </b>, <br/>, <a href="...">
+  if first == '<' {
+    let end = s.find('>')?;
+    let inner = &s[1..end];
+    let name = inner.trim_start_matches('/');
+    let ok = inner.chars().count() <= MAX_SPAN_CHARS
+      && !inner.contains(['<', '\n'])
+      && name.chars().next().is_some_and(|c| c.is_ascii_alphabetic());
+    return ok.then_some(end + 1);
+  }
+
+  if is_emoji(first) {
+    let end = s
+      .char_indices()
+      .find(|(_, c)| !is_emoji(*c) && !is_emoji_joiner(*c))
+      .map(|(i, _)| i)
+      .unwrap_or(s.len());
+    return Some(end);
+  }
+  None
+}
+
+/// Swap protected spans for markers. Returns the masked text and what to put back.
+pub fn mask(text: &str) -> (String, Masks) {
+  let mut out = String::with_capacity(text.len());
+  let mut masks = Masks::default();
+  let mut i = 0;
+  while i < text.len() {
+    let rest = &text[i..];
+    if let Some(len) = span_len(rest).filter(|n| *n > 0) {
+      out.push_str(&marker(masks.spans.len()));
+      masks.spans.push(rest[..len].to_string());
+      i += len;
+      continue;
+    }
+    let c = rest.chars().next().unwrap_or_default();
+    out.push(c);
+    i += c.len_utf8().max(1);
+  }
+  (out, masks)
+}
+
+/// Restores markers in streamed deltas, holding back a marker split across deltas.
+#[derive(Debug, Default)]
+pub struct Unmasker {
+  held: String,
+}
+
+impl Unmasker {
+  pub fn feed(&mut self, masks: &Masks, content: &str) -> String {
+    self.held.push_str(content);
+    // Hold back from an unclosed `⟦` (it may be completed by the next delta), unless it's clearly not a marker.
+    let split = match self.held.rfind(OPEN) {
+      Some(i) if !self.held[i..].contains(CLOSE) && self.held.len() - i <= 8 => i,
+      _ => self.held.len(),
+    };
+    let ready: String = self.held.drain(..split).collect();
+    masks.restore(&ready)
+  }
+
+  pub fn flush(&mut self, masks: &Masks) -> String {
+    masks.restore(&std::mem::take(&mut self.held))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn masks_urls_variables_and_printf_specs() {
+    let text = "Open https://example.com/a, then {name} and %1$s.";
+    let (masked, masks) = mask(text);
+    assert_eq!(masked, "Open ⟦0⟧, then ⟦1⟧ and ⟦2⟧.");
+    assert_eq!(masks.restore(&masked), text);
+  }
+
+  #[test]
+  fn masks_tags_code_and_emoji() {
+    let text = "<b>bold</b> `code` ${x} {{y}} 👍🏽!";
+    let (masked, masks) = mask(text);
+    assert_eq!(masked, "⟦0⟧bold⟦1⟧ ⟦2⟧ ⟦3⟧ ⟦4⟧ ⟦5⟧!");
+    assert_eq!(masks.restore(&masked), text);
+  }
+
+  #[test]
+  fn leaves_plain_text_alone() {
+    let (masked, masks) = mask("a < b, {} and 100%");
+    assert_eq!(masked, "a < b, {} and 100%");
+    assert!(masks.is_empty());
+  }
+
+  #[test]
+  fn restore_skips_unknown_markers() {
+    let (_, masks) = mask("{a}");
+    assert_eq!(masks.restore("⟦ 0 ⟧ ⟦5⟧ ⟦x"), "{a} ⟦5⟧ ⟦x");
+  }
+
+  #[test]
+  fn missing_lists_dropped_spans() {
+    let (_, masks) = mask("{a} {b}");
+    assert_eq!(masks.missing("⟦1⟧"), ["{a}"]);
+  }
+
+  #[test]
+  fn unmasker_holds_back_a_split_marker() {
+    let (_, masks) = mask("see {name}");
+    let mut unmasker = Unmasker::default();
+    assert_eq!(unmasker.feed(&masks, "Hello ⟦"), "Hello ");
+    assert_eq!(unmasker.feed(&masks, "0⟧!"), "{name}!");
+    assert_eq!(unmasker.feed(&masks, " ⟦"), " ");
+    assert_eq!(unmasker.flush(&masks), "⟦");
+  }
+}
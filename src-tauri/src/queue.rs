@@ -0,0 +1,284 @@
+//! Request queue for translation streams.
+//!
+//! - A new interactive request cancels older interactive ones (queued or running), so hammering the hotkey
+//!   leaves only the latest stream alive.
+//! - Background jobs (batch work) start only when no interactive request is waiting.
+//! - At most `max_concurrency` requests run at once.
+
+use crate::clock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+const DEFAULT_MAX_CONCURRENCY: usize = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+  #[default]
+  Interactive,
+  Background,
+}
+
+/// Cancellation flag shared between the queue and a job.
+#[derive(Default)]
+pub struct CancelToken {
+  cancelled: AtomicBool,
+  notify: Notify,
+}
+
+impl CancelToken {
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::SeqCst);
+    self.notify.notify_waiters();
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::SeqCst)
+  }
+
+  /// Resolves once `cancel` has been called.
+  pub async fn cancelled(&self) {
+    loop {
+      let notified = self.notify.notified();
+      tokio::pin!(notified);
+      notified.as_mut().enable();
+      if self.is_cancelled() {
+        return;
+      }
+      notified.await;
+    }
+  }
+}
+
+struct Entry {
+  id: String,
+  priority: Priority,
+  seq: u64,
+  label: String,
+  cancel: Arc<CancelToken>,
+}
+
+#[derive(Default)]
+struct Inner {
+  max_concurrency: usize,
+  seq: u64,
+  running: Vec<Entry>,
+  waiting: Vec<Entry>,
+}
+
+impl Inner {
+  /// The waiting entry that should start next: interactive first, then FIFO.
+  fn next_waiting(&self) -> Option<&Entry> {
+    self.waiting.iter().min_by_key(|e| (e.priority != Priority::Interactive, e.seq))
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QueueEntryInfo {
+  pub id: String,
+  pub priority: Priority,
+  pub label: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct QueueStatus {
+  pub max_concurrency: usize,
+  pub running: Vec<QueueEntryInfo>,
+  pub waiting: Vec<QueueEntryInfo>,
+}
+
+/// Managed state.
+pub struct RequestQueue {
+  inner: Mutex<Inner>,
+  changed: Notify,
+}
+
+impl Default for RequestQueue {
+  fn default() -> Self {
+    Self {
+      inner: Mutex::new(Inner {
+        max_concurrency: DEFAULT_MAX_CONCURRENCY,
+        ..Default::default()
+      }),
+      changed: Notify::new(),
+    }
+  }
+}
+
+/// A queued request; call `wait_turn` to get a running slot.
+pub struct Ticket<'a> {
+  queue: &'a RequestQueue,
+  pub id: String,
+  pub cancel: Arc<CancelToken>,
+}
+
+/// Holds a running slot; releases it on drop.
+pub struct JobGuard<'a> {
+  queue: &'a RequestQueue,
+  pub id: String,
+  pub cancel: Arc<CancelToken>,
+}
+
+impl RequestQueue {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+    self.inner.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Register a request. Interactive requests cancel all older interactive ones.
+  pub fn enqueue(&self, priority: Priority, label: &str) -> Ticket<'_> {
+    let id = clock::next_id("req");
+    let cancel = Arc::new(CancelToken::default());
+    {
+      let mut inner = self.lock();
+      if priority == Priority::Interactive {
+        for e in inner.running.iter().chain(inner.waiting.iter()) {
+          if e.priority == Priority::Interactive {
+            e.cancel.cancel();
+          }
+        }
+      }
+      inner.seq += 1;
+      let seq = inner.seq;
+      inner.waiting.push(Entry {
+        id: id.clone(),
+        priority,
+        seq,
+        label: label.to_string(),
+        cancel: cancel.clone(),
+      });
+    }
+    self.changed.notify_waiters();
+    Ticket {
+      queue: self,
+      id,
+      cancel,
+    }
+  }
+
+  fn remove(&self, id: &str) {
+    {
+      let mut inner = self.lock();
+      inner.running.retain(|e| e.id != id);
+      inner.waiting.retain(|e| e.id != id);
+    }
+    self.changed.notify_waiters();
+  }
+
+  pub fn cancel(&self, id: &str) -> bool {
+    let inner = self.lock();
+    match inner.running.iter().chain(inner.waiting.iter()).find(|e| e.id == id) {
+      Some(e) => {
+        e.cancel.cancel();
+        true
+      }
+      None => false,
+    }
+  }
+
+  pub fn cancel_all(&self) {
+    let inner = self.lock();
+    for e in inner.running.iter().chain(inner.waiting.iter()) {
+      e.cancel.cancel();
+    }
+  }
+
+  pub fn set_max_concurrency(&self, n: usize) {
+    self.lock().max_concurrency = n.max(1);
+    self.changed.notify_waiters();
+  }
+
+  pub fn status(&self) -> QueueStatus {
+    let inner = self.lock();
+    let info = |e: &Entry| QueueEntryInfo {
+      id: e.id.clone(),
+      priority: e.priority,
+      label: e.label.clone(),
+    };
+    QueueStatus {
+      max_concurrency: inner.max_concurrency,
+      running: inner.running.iter().map(info).collect(),
+      waiting: inner.waiting.iter().map(info).collect(),
+    }
+  }
+}
+
+impl<'a> Ticket<'a> {
+  /// Wait for a running slot. Fails with `CANCELLED` if the request is cancelled while queued.
+  pub async fn wait_turn(self) -> Result<JobGuard<'a>, String> {
+    let queue = self.queue;
+    loop {
+      let changed = queue.changed.notified();
+      tokio::pin!(changed);
+      changed.as_mut().enable();
+      {
+        let mut inner = queue.lock();
+        if self.cancel.is_cancelled() {
+          drop(inner);
+          queue.remove(&self.id);
+          return Err("CANCELLED".to_string());
+        }
+        let is_next = inner.next_waiting().is_some_and(|e| e.id == self.id);
+        if is_next && inner.running.len() < inner.max_concurrency {
+          if let Some(pos) = inner.waiting.iter().position(|e| e.id == self.id) {
+            let entry = inner.waiting.remove(pos);
+            inner.running.push(entry);
+          }
+          drop(inner);
+          queue.changed.notify_waiters();
+          return Ok(JobGuard {
+            queue,
+            id: self.id.clone(),
+            cancel: self.cancel.clone(),
+          });
+        }
+      }
+      tokio::select! {
+        _ = &mut changed => {}
+        _ = self.cancel.cancelled() => {}
+      }
+    }
+  }
+}
+
+impl Drop for Ticket<'_> {
+  fn drop(&mut self) {
+    // Abandoned while queued (e.g. the command future was dropped); running entries belong to the guard.
+    let removed = {
+      let mut inner = self.queue.lock();
+      let before = inner.waiting.len();
+      inner.waiting.retain(|e| e.id != self.id);
+      inner.waiting.len() != before
+    };
+    if removed {
+      self.queue.changed.notify_waiters();
+    }
+  }
+}
+
+impl Drop for JobGuard<'_> {
+  fn drop(&mut self) {
+    self.queue.remove(&self.id);
+  }
+}
+
+#[tauri::command]
+pub fn get_queue_status(queue: tauri::State<'_, RequestQueue>) -> QueueStatus {
+  queue.status()
+}
+
+#[tauri::command]
+pub fn set_max_concurrency(max_concurrency: usize, queue: tauri::State<'_, RequestQueue>) {
+  queue.set_max_concurrency(max_concurrency);
+}
+
+#[tauri::command]
+pub fn cancel_request(request_id: String, queue: tauri::State<'_, RequestQueue>) -> bool {
+  queue.cancel(&request_id)
+}
+
+#[tauri::command]
+pub fn cancel_all_requests(queue: tauri::State<'_, RequestQueue>) {
+  queue.cancel_all();
+}
@@ -0,0 +1,166 @@
+//! Burst OCR of a region: capture a few frames over a short time and collect all the text they show, for credits,
+//! marquees and subtitles that change faster than a single capture can catch.
+//!
+//! Frames are captured first, at even intervals; one that looks like the last kept frame (the live OCR grid
+//! comparison, `liveOcrChangeThreshold`) is dropped. The distinct frames are then OCR'd in order, and each line
+//! not seen in an earlier frame (ignoring whitespace) is added to the result.
+
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::ipc::Channel;
+
+use crate::commands::CaptureRect;
+use crate::live_ocr;
+use crate::ocr::{self, OcrEvent, OcrImage, OcrStage, TesseractOptions};
+use crate::queue::CancelToken;
+use crate::settings;
+
+const DEFAULT_FRAMES: u32 = 10;
+const MAX_FRAMES: u32 = 60;
+const DEFAULT_DURATION_MS: u64 = 3000;
+const MAX_DURATION_MS: u64 = 30_000;
+
+/// How many frames to capture, and over how long.
+#[derive(Clone, Copy)]
+struct Burst {
+  frames: u32,
+  duration: Duration,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RecordingResult {
+  /// The distinct lines, one per line, in the order they first appeared.
+  pub text: String,
+  pub lines: Vec<String>,
+  /// Frames captured, and how many of them differed enough to be OCR'd.
+  pub frames: u32,
+  pub distinct_frames: u32,
+}
+
+/// Captured frame files, deleted when dropped, however the recording ends.
+struct Frames(Vec<String>);
+
+impl Drop for Frames {
+  fn drop(&mut self) {
+    for path in &self.0 {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+}
+
+/// Capture the burst's frames of `rect`, evenly spread over its duration; returns the distinct ones.
+async fn capture_frames(
+  app: &tauri::AppHandle,
+  rect: &CaptureRect,
+  burst: Burst,
+  cancel: &CancelToken,
+) -> Result<Frames, String> {
+  let interval = burst.duration / burst.frames.saturating_sub(1).max(1);
+  let threshold = settings::live_ocr_change_threshold(app);
+  let mut kept = Frames(Vec::new());
+  let mut last: Option<Vec<f32>> = None;
+  for i in 0..burst.frames {
+    if i > 0 {
+      tokio::select! {
+        _ = tokio::time::sleep(interval) => {}
+        _ = cancel.cancelled() => return Err("CANCELLED".to_string()),
+      }
+    }
+    let path = crate::commands::capture_region(app, rect, false)?;
+    let thumb = match live_ocr::thumbnail(&path) {
+      Ok(thumb) => thumb,
+      Err(e) => {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+      }
+    };
+    if last.as_deref().is_some_and(|last| live_ocr::difference(last, &thumb) < threshold) {
+      let _ = std::fs::remove_file(&path);
+      continue;
+    }
+    last = Some(thumb);
+    kept.0.push(path);
+  }
+  Ok(kept)
+}
+
+async fn record(
+  app: &tauri::AppHandle,
+  rect: CaptureRect,
+  burst: Burst,
+  req: ocr::OcrRequest,
+  reflow: Option<bool>,
+  cancel: Arc<CancelToken>,
+  on_event: &Channel<OcrEvent>,
+) -> Result<RecordingResult, String> {
+  let frames = capture_frames(app, &rect, burst, &cancel).await?;
+  let _ = on_event.send(OcrEvent::Stage { stage: OcrStage::Running });
+  let total = frames.0.len();
+  let mut lines: Vec<String> = Vec::new();
+  let mut first_error = None;
+  for (i, path) in frames.0.iter().enumerate() {
+    let result = ocr::recognize_text(app, OcrImage::Path(path.clone()), req.clone(), reflow, cancel.clone(), None).await;
+    match result {
+      Ok(result) => {
+        for line in result.text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+          if !lines.iter().any(|seen| live_ocr::same_text(seen, line)) {
+            lines.push(line.to_string());
+          }
+        }
+      }
+      Err(e) if e == "CANCELLED" => return Err(e),
+      Err(e) => {
+        first_error.get_or_insert(e);
+      }
+    }
+    let _ = on_event.send(OcrEvent::Progress { done: i + 1, total });
+  }
+  if lines.is_empty() {
+    if let Some(e) = first_error {
+      return Err(e);
+    }
+  }
+  Ok(RecordingResult {
+    text: lines.join("\n"),
+    lines,
+    frames: burst.frames,
+    distinct_frames: total as u32,
+  })
+}
+
+/// Record `rect` (or `logical`, see `capture_screen_region`) for `duration_ms` (default 3000, at most 30000),
+/// capturing `frames` frames (default 10, at most 60), and OCR the distinct ones. The other parameters are
+/// `ocr_image`'s; the job can be cancelled with `cancel_ocr(job_id)`, and `on_event` reports progress per OCR'd
+/// frame.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ocr_recording(
+  app: tauri::AppHandle,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+  frames: Option<u32>,
+  duration_ms: Option<u64>,
+  lang: Option<String>,
+  engine: Option<String>,
+  tesseract_path: Option<String>,
+  tessdata_prefix: Option<String>,
+  reflow: Option<bool>,
+  options: Option<TesseractOptions>,
+  job_id: Option<String>,
+  on_event: Channel<OcrEvent>,
+) -> Result<RecordingResult, String> {
+  let rect = crate::commands::physical_rect(&app, rect, logical)?;
+  if rect.width == 0 || rect.height == 0 {
+    return Err("invalid rect".to_string());
+  }
+  let burst = Burst {
+    frames: frames.unwrap_or(DEFAULT_FRAMES).clamp(2, MAX_FRAMES),
+    duration: Duration::from_millis(duration_ms.unwrap_or(DEFAULT_DURATION_MS).min(MAX_DURATION_MS)),
+  };
+  let req = ocr::request(&app, lang, engine, tesseract_path, tessdata_prefix, options);
+  ocr::run_job(&app, job_id, &on_event, |cancel| {
+    record(&app, rect, burst, req, reflow, cancel, &on_event)
+  })
+  .await
+}
@@ -0,0 +1,178 @@
+//! Reflow of OCR text before translation.
+//!
+//! Tesseract keeps the visual line breaks of the image, so sentences arrive split mid-way. Lines are joined
+//! unless the previous one ends a sentence, the next one starts a list item, or the previous one is
+//! clearly short (a heading or the last line of a paragraph). Blank lines stay paragraph breaks. CJK lines
+//! are joined without a space, and hyphenated words split across lines are rejoined.
+
+pub(crate) fn is_cjk(c: char) -> bool {
+  matches!(c as u32,
+    0x3000..=0x303F // CJK punctuation
+    | 0x3040..=0x30FF // hiragana / katakana
+    | 0x3400..=0x4DBF
+    | 0x4E00..=0x9FFF
+    | 0xAC00..=0xD7AF // hangul
+    | 0xF900..=0xFAFF
+    | 0xFF00..=0xFFEF)
+}
+
+fn ends_sentence(line: &str) -> bool {
+  line.chars().last().is_some_and(|c| {
+    matches!(
+      c,
+      '.' | '!' | '?' | ':' | ';' | '。' | '！' | '？' | '：' | '；' | '…' | '」' | '』' | '）' | ')'
+    )
+  })
+}
+
+fn is_list_item(line: &str) -> bool {
+  let t = line.trim_start();
+  if t.starts_with(['-', '*', '•', '・', '●', '○', '■', '□', '※', '▪', '–']) {
+    return true;
+  }
+  // "1.", "2)", "(3)", "a)", "①"
+  let t = t.strip_prefix('(').unwrap_or(t);
+  let marker_len = t.chars().take_while(|c| c.is_ascii_digit()).count();
+  let rest = if marker_len > 0 {
+    &t[marker_len..]
+  } else if t.chars().next().is_some_and(|c| c.is_ascii_lowercase()) {
+    &t[1..]
+  } else {
+    return t.chars().next().is_some_and(|c| ('\u{2460}'..='\u{2473}').contains(&c));
+  };
+  rest.starts_with(['.', ')']) && (marker_len > 0 || rest.starts_with(')'))
+}
+
+/// Remove the spaces Tesseract inserts between CJK characters ("日 本 語" -> "日本語").
+pub(crate) fn collapse_cjk_spaces(line: &str) -> String {
+  let chars: Vec<char> = line.chars().collect();
+  let mut out = String::with_capacity(line.len());
+  for (i, &c) in chars.iter().enumerate() {
+    if c == ' ' && i > 0 && i + 1 < chars.len() && is_cjk(chars[i - 1]) && is_cjk(chars[i + 1]) {
+      continue;
+    }
+    out.push(c);
+  }
+  out
+}
+
+/// Join `next` onto `out` (the paragraph so far).
+fn join_line(out: &mut String, next: &str) {
+  let prev_last = out.chars().last();
+  let next_first = next.chars().next();
+  if let (Some('-'), Some(n)) = (prev_last, next_first) {
+    let before_hyphen = out.chars().rev().nth(1);
+    if n.is_lowercase() && before_hyphen.is_some_and(|c| c.is_alphabetic()) {
+      out.pop();
+      out.push_str(next);
+      return;
+    }
+  }
+  if prev_last.is_some_and(is_cjk) && next_first.is_some_and(is_cjk) {
+    out.push_str(next);
+  } else {
+    out.push(' ');
+    out.push_str(next);
+  }
+}
+
+/// Approximate visual width: full-width CJK glyphs count double.
+fn width(line: &str) -> usize {
+  line.chars().map(|c| if is_cjk(c) { 2 } else { 1 }).sum()
+}
+
+/// Width of the longest line in each line's paragraph.
+fn paragraph_widths(lines: &[String]) -> Vec<usize> {
+  let mut out = vec![0; lines.len()];
+  let mut start = 0;
+  for i in 0..=lines.len() {
+    if i == lines.len() || lines[i].trim().is_empty() {
+      let longest = lines[start..i].iter().map(|l| width(l)).max().unwrap_or(0);
+      out[start..i].iter_mut().for_each(|w| *w = longest);
+      start = i + 1;
+    }
+  }
+  out
+}
+
+/// Reflow OCR text; the result has the same paragraphs with mid-sentence breaks removed.
+pub fn reflow(text: &str) -> String {
+  let lines: Vec<String> = text
+    .lines()
+    .map(|l| collapse_cjk_spaces(l.trim_end()))
+    .collect();
+  let longest = paragraph_widths(&lines);
+
+  let mut out = String::with_capacity(text.len());
+  let mut prev: Option<&str> = None;
+  for (i, line) in lines.iter().enumerate() {
+    let trimmed = line.trim();
+    let Some(p) = prev else {
+      out.push_str(line);
+      prev = Some(line);
+      continue;
+    };
+    if trimmed.is_empty() {
+      // Paragraph break (collapse runs of blank lines to one).
+      if !p.trim().is_empty() {
+        out.push_str("\n\n");
+      }
+      prev = Some(line);
+      continue;
+    }
+    if p.trim().is_empty() {
+      out.push_str(line);
+    } else {
+      // A line much shorter than its neighbours ended early on purpose, unless it was cut at a hyphen or comma.
+      let short = width(p) * 10 < longest[i] * 6 && !p.ends_with(['-', ',', '、']);
+      if ends_sentence(p.trim_end()) || is_list_item(trimmed) || short {
+        out.push('\n');
+        out.push_str(line);
+      } else {
+        join_line(&mut out, trimmed);
+      }
+    }
+    prev = Some(line);
+  }
+  out.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn joins_lines_broken_mid_sentence() {
+    assert_eq!(reflow("The quick brown fox\njumps over the lazy dog."), "The quick brown fox jumps over the lazy dog.");
+    assert_eq!(reflow("First sentence here.\nSecond sentence here."), "First sentence here.\nSecond sentence here.");
+  }
+
+  #[test]
+  fn rejoins_hyphenated_words() {
+    assert_eq!(reflow("an exam-\nple of text"), "an example of text");
+  }
+
+  #[test]
+  fn keeps_paragraphs_lists_and_short_lines() {
+    assert_eq!(reflow("Line one\n\n\nLine two"), "Line one\n\nLine two");
+    assert_eq!(reflow("Items:\n- apple\n- pear"), "Items:\n- apple\n- pear");
+    let titled = "Title\nThis is a much longer body line of text";
+    assert_eq!(reflow(titled), titled);
+  }
+
+  #[test]
+  fn joins_cjk_without_spaces() {
+    assert_eq!(collapse_cjk_spaces("日 本 abc d"), "日本 abc d");
+    assert_eq!(reflow("日 本 語 の\n文 章 で す"), "日本語の文章です");
+  }
+
+  #[test]
+  fn recognizes_list_markers() {
+    for item in ["- x", "• x", "1. x", "12) x", "(3) x", "a) x", "①x"] {
+      assert!(is_list_item(item), "{item}");
+    }
+    for line in ["apple", "12 apples", "a. x", ""] {
+      assert!(!is_list_item(line), "{line}");
+    }
+  }
+}
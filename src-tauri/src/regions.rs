@@ -0,0 +1,161 @@
+//! Recently used capture regions, per monitor, so the last one can be captured again with a hotkey (a subtitle
+//! line, a game's text box).
+//!
+//! Regions selected by the user (`capture_screen_region`, `crop_snapshot`) are recorded most recent first, the same
+//! rectangle only once, up to `PER_MONITOR` per monitor, and saved to `regions.json` in the app data dir.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::clock;
+use crate::commands::CaptureRect;
+use crate::screen::LogicalRect;
+
+const REGIONS_FILE: &str = "regions.json";
+const PER_MONITOR: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegionEntry {
+  /// Physical pixels, as captured.
+  pub rect: CaptureRect,
+  pub logical: LogicalRect,
+  /// Monitor under the region's center when it was used.
+  pub monitor: Option<String>,
+  pub used_at: u128,
+}
+
+#[derive(Default)]
+struct Inner {
+  path: Option<PathBuf>,
+  entries: Vec<RegionEntry>,
+}
+
+impl Inner {
+  fn save(&self) {
+    let Some(path) = &self.path else {
+      return;
+    };
+    if let Err(e) = crate::json_store::save(path, &self.entries) {
+      log::warn!("failed to save region history: {e}");
+    }
+  }
+}
+
+/// Region history (managed state).
+#[derive(Default)]
+pub struct RegionHistory(Mutex<Inner>);
+
+impl RegionHistory {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Load the saved history from `dir`.
+pub fn init(app: &tauri::AppHandle, dir: &std::path::Path) {
+  let path = dir.join(REGIONS_FILE);
+  let entries = crate::json_store::load(app, &path, "regions_unreadable");
+  let state = app.state::<RegionHistory>();
+  let mut inner = state.lock();
+  inner.path = Some(path);
+  inner.entries = entries;
+}
+
+/// Remember `rect` (physical pixels) as the latest region on its monitor.
+pub fn record(app: &tauri::AppHandle, rect: &CaptureRect) {
+  let geometry = crate::screen::to_logical(app, rect);
+  let state = app.state::<RegionHistory>();
+  let mut inner = state.lock();
+  inner.entries.retain(|e| !(e.rect == *rect && e.monitor == geometry.monitor));
+  inner.entries.insert(
+    0,
+    RegionEntry {
+      rect: rect.clone(),
+      logical: geometry.logical,
+      monitor: geometry.monitor.clone(),
+      used_at: clock::now_millis(),
+    },
+  );
+  let mut kept = 0;
+  inner.entries.retain(|e| {
+    if e.monitor != geometry.monitor {
+      return true;
+    }
+    kept += 1;
+    kept <= PER_MONITOR
+  });
+  inner.save();
+}
+
+/// Recent regions, most recent first; only those on `monitor` when given.
+#[tauri::command]
+pub fn list_capture_regions(state: tauri::State<'_, RegionHistory>, monitor: Option<String>) -> Vec<RegionEntry> {
+  let inner = state.lock();
+  inner
+    .entries
+    .iter()
+    .filter(|e| monitor.is_none() || e.monitor == monitor)
+    .cloned()
+    .collect()
+}
+
+/// Forget all recorded regions.
+#[tauri::command]
+pub fn clear_capture_regions(state: tauri::State<'_, RegionHistory>) {
+  let mut inner = state.lock();
+  inner.entries.clear();
+  inner.save();
+}
+
+/// The region to repeat: the latest on `monitor`, else on the monitor under the pointer, else the latest anywhere.
+fn last_region(app: &tauri::AppHandle, monitor: Option<String>) -> Option<RegionEntry> {
+  let monitor = monitor.or_else(|| {
+    let cursor = crate::commands::get_cursor_position().ok()?;
+    crate::screen::monitor_at(app, cursor.x, cursor.y)
+  });
+  let state = app.state::<RegionHistory>();
+  let inner = state.lock();
+  inner
+    .entries
+    .iter()
+    .find(|e| monitor.is_some() && e.monitor == monitor)
+    .or_else(|| inner.entries.first())
+    .cloned()
+}
+
+/// The region `capture_last_region` would capture, without capturing it.
+#[tauri::command]
+pub fn last_capture_region(app: tauri::AppHandle, monitor: Option<String>) -> Option<RegionEntry> {
+  last_region(&app, monitor)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LastRegionCapture {
+  pub path: String,
+  pub region: RegionEntry,
+}
+
+/// Capture the most recently used region again (see `last_region`), saved as `capture_screen_region` saves it;
+/// errors with `NO_RECENT_REGION` when there's none yet. The repeat-region hotkey OCRs the result.
+#[tauri::command]
+pub async fn capture_last_region(
+  app: tauri::AppHandle,
+  monitor: Option<String>,
+) -> Result<LastRegionCapture, String> {
+  let region = last_region(&app, monitor).ok_or_else(|| "NO_RECENT_REGION".to_string())?;
+  // No overlay opens on this path; the app in front is where the OCR'd text comes from (see `open_ocr_overlay`).
+  crate::type_back::remember_target(&app);
+  let path = {
+    let (app, rect) = (app.clone(), region.rect.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+      let output = crate::settings::capture_output(&app);
+      crate::commands::capture_region_as(&app, &rect, false, &output)
+    })
+    .await
+    .map_err(|e| format!("capture failed: {e}"))??
+  };
+  record(&app, &region.rect);
+  Ok(LastRegionCapture { path, region })
+}
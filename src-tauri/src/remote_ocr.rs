@@ -0,0 +1,153 @@
+//! OCR on a local PaddleOCR / EasyOCR HTTP server (`remoteOcrUrl`), for CJK text Tesseract reads poorly.
+//!
+//! The image is POSTed as JSON: `{"images": [<base64>], "image": <base64>, "lang": "jpn+eng"}`. Two replies are
+//! understood: PaddleHub Serving's `ocr_system` (`{"results": [[{"text", "confidence", "text_region"}]]}`) and
+//! EasyOCR's `readtext` result serialized as-is (`[[box, text, confidence], ...]`). Confidences are 0-1 in both.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::ocr::{BBox, OcrImage, OcrLine, OcrOutput, OcrWord};
+use crate::queue::CancelToken;
+use crate::settings;
+
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct PaddleLine {
+  text: String,
+  confidence: f32,
+  text_region: Vec<[f32; 2]>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Reply {
+  PaddleHub { results: Vec<Vec<PaddleLine>> },
+  EasyOcr(Vec<(Vec<[f32; 2]>, String, f32)>),
+}
+
+/// The box around a detected quadrilateral.
+fn bbox(points: &[[f32; 2]]) -> BBox {
+  let (mut x0, mut y0, mut x1, mut y1) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+  for [x, y] in points {
+    x0 = x0.min(*x);
+    y0 = y0.min(*y);
+    x1 = x1.max(*x);
+    y1 = y1.max(*y);
+  }
+  if points.is_empty() {
+    return BBox::default();
+  }
+  BBox {
+    x: x0.round() as i32,
+    y: y0.round() as i32,
+    width: (x1 - x0).round() as i32,
+    height: (y1 - y0).round() as i32,
+  }
+}
+
+fn line(text: String, confidence: f32, points: &[[f32; 2]]) -> OcrLine {
+  let bbox = bbox(points);
+  let confidence = Some(confidence * 100.0);
+  OcrLine {
+    // The servers box lines only; the line stands in as its single word.
+    words: vec![OcrWord {
+      text: text.clone(),
+      bbox,
+      confidence,
+    }],
+    text,
+    bbox,
+    confidence,
+  }
+}
+
+fn parse(body: &str) -> Result<OcrOutput, String> {
+  let reply: Reply = serde_json::from_str(body).map_err(|e| format!("remote ocr failed: unexpected reply: {e}"))?;
+  let mut lines: Vec<OcrLine> = match reply {
+    Reply::PaddleHub { results } => results
+      .into_iter()
+      .flatten()
+      .map(|l| line(l.text, l.confidence, &l.text_region))
+      .collect(),
+    Reply::EasyOcr(items) => items.into_iter().map(|(points, text, c)| line(text, c, &points)).collect(),
+  };
+  lines.retain(|l| !l.text.trim().is_empty());
+  lines.sort_by_key(|l| (l.bbox.y, l.bbox.x));
+  let text = lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+  Ok(OcrOutput { text, lines })
+}
+
+async fn post(url: &str, data: &[u8], lang: &str) -> Result<OcrOutput, String> {
+  let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+  let body = serde_json::json!({ "images": [&encoded], "image": &encoded, "lang": lang });
+  let res = crate::http::client()
+    .post(url)
+    .timeout(TIMEOUT)
+    .json(&body)
+    .send()
+    .await
+    .map_err(|e| format!("REMOTE_OCR_UNREACHABLE\n\n{e}"))?;
+  let status = res.status();
+  let text = res.text().await.map_err(|e| format!("remote ocr failed: {e}"))?;
+  if !status.is_success() {
+    return Err(format!("remote ocr failed: HTTP {status}: {}", text.trim()));
+  }
+  parse(&text)
+}
+
+/// OCR an image on the server at `url`. Blocking; gives up with `CANCELLED` when `cancel` fires.
+pub fn recognize(url: &str, image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String> {
+  let data = image.data()?;
+  tauri::async_runtime::block_on(async {
+    tokio::select! {
+      result = post(url, &data, lang) => result,
+      _ = cancel.cancelled() => Err("CANCELLED".to_string()),
+    }
+  })
+}
+
+/// A blank PNG to probe the server with.
+fn probe_image() -> Result<Vec<u8>, String> {
+  let (w, h) = (64, 32);
+  let mut out = Vec::new();
+  let mut encoder = png::Encoder::new(&mut out, w, h);
+  encoder.set_color(png::ColorType::Grayscale);
+  encoder.set_depth(png::BitDepth::Eight);
+  let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+  writer
+    .write_image_data(&vec![255; (w * h) as usize])
+    .map_err(|e| e.to_string())?;
+  writer.finish().map_err(|e| e.to_string())?;
+  Ok(out)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RemoteOcrHealth {
+  pub url: String,
+  pub ok: bool,
+  /// Round trip of the probe, when it answered.
+  pub latency_ms: Option<u128>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+/// Check that the OCR server at `url` (default: `remoteOcrUrl`) answers a blank image with a reply it can parse.
+#[tauri::command]
+pub async fn check_remote_ocr(app: tauri::AppHandle, url: Option<String>) -> Result<RemoteOcrHealth, String> {
+  let url = url
+    .filter(|s| !s.trim().is_empty())
+    .or_else(|| settings::remote_ocr_url(&app))
+    .ok_or_else(|| "REMOTE_OCR_URL_NOT_SET".to_string())?;
+  let image = probe_image().map_err(|e| format!("remote ocr check failed: {e}"))?;
+  let started = Instant::now();
+  let result = post(&url, &image, "eng").await;
+  Ok(RemoteOcrHealth {
+    url,
+    ok: result.is_ok(),
+    latency_ms: result.is_ok().then(|| started.elapsed().as_millis()),
+    error: result.err(),
+  })
+}
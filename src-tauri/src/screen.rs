@@ -0,0 +1,183 @@
+//! Screen geometry: mapping the logical rectangles the UI works in to the physical pixels capture needs.
+//!
+//! Each monitor has its own scale factor. A logical point belongs to the monitor whose logical bounds (physical
+//! bounds divided by its scale) contain it, and converts with that monitor's scale, so a rectangle drawn on a
+//! 150% monitor next to a 100% one lands on the right pixels. Where monitors of different scales meet, their
+//! logical bounds can overlap or leave gaps, so a window that knows its own monitor (the OCR overlay) converts
+//! with that monitor's origin and scale itself and passes physical pixels.
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::CaptureRect;
+
+/// A rectangle in logical (CSS) pixels of the desktop.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct LogicalRect {
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+}
+
+/// One rectangle in both coordinate spaces, and the monitor that decided the conversion.
+#[derive(Debug, Serialize, Clone)]
+pub struct ScreenGeometry {
+  pub logical: LogicalRect,
+  pub physical: CaptureRect,
+  pub scale_factor: f64,
+  /// Name of the monitor under the rectangle's center; `None` when no monitor is (then the scale is 1).
+  pub monitor: Option<String>,
+}
+
+struct MonitorBounds {
+  name: Option<String>,
+  /// Physical position and size.
+  x: i32,
+  y: i32,
+  width: u32,
+  height: u32,
+  scale: f64,
+}
+
+impl MonitorBounds {
+  fn contains_logical(&self, x: f64, y: f64) -> bool {
+    let (left, top) = (self.x as f64 / self.scale, self.y as f64 / self.scale);
+    x >= left && y >= top && x < left + self.width as f64 / self.scale && y < top + self.height as f64 / self.scale
+  }
+
+  fn contains_physical(&self, x: i32, y: i32) -> bool {
+    x >= self.x && y >= self.y && x < self.x + self.width as i32 && y < self.y + self.height as i32
+  }
+}
+
+fn monitors(app: &tauri::AppHandle) -> Vec<MonitorBounds> {
+  app
+    .available_monitors()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|m| MonitorBounds {
+      name: m.name().cloned(),
+      x: m.position().x,
+      y: m.position().y,
+      width: m.size().width,
+      height: m.size().height,
+      scale: m.scale_factor(),
+    })
+    .collect()
+}
+
+/// A monitor as `list_monitors` reports it.
+#[derive(Debug, Serialize, Clone)]
+pub struct MonitorInfo {
+  pub name: Option<String>,
+  /// Physical pixels of the desktop.
+  pub bounds: CaptureRect,
+  /// `bounds` less the taskbar / dock / menu bar.
+  pub work_area: CaptureRect,
+  /// `bounds` in logical pixels (divided by `scale_factor`).
+  pub logical: LogicalRect,
+  pub scale_factor: f64,
+  pub primary: bool,
+}
+
+/// Every monitor with its bounds, work area, scale factor and whether it's the primary one.
+#[tauri::command]
+pub fn list_monitors(app: tauri::AppHandle) -> Vec<MonitorInfo> {
+  let primary = app.primary_monitor().ok().flatten();
+  app
+    .available_monitors()
+    .unwrap_or_default()
+    .into_iter()
+    .map(|m| {
+      let (position, size, scale) = (*m.position(), *m.size(), m.scale_factor());
+      let work = m.work_area();
+      MonitorInfo {
+        name: m.name().cloned(),
+        bounds: CaptureRect { x: position.x, y: position.y, width: size.width, height: size.height },
+        work_area: CaptureRect {
+          x: work.position.x,
+          y: work.position.y,
+          width: work.size.width,
+          height: work.size.height,
+        },
+        logical: LogicalRect {
+          x: position.x as f64 / scale,
+          y: position.y as f64 / scale,
+          width: size.width as f64 / scale,
+          height: size.height as f64 / scale,
+        },
+        scale_factor: scale,
+        primary: primary
+          .as_ref()
+          .is_some_and(|p| p.position() == m.position() && p.name() == m.name()),
+      }
+    })
+    .collect()
+}
+
+/// Physical bounds of all monitors together, if any are known.
+pub fn desktop(app: &tauri::AppHandle) -> Option<CaptureRect> {
+  let all = monitors(app);
+  let left = all.iter().map(|m| m.x).min()?;
+  let top = all.iter().map(|m| m.y).min()?;
+  let right = all.iter().map(|m| m.x + m.width as i32).max()?;
+  let bottom = all.iter().map(|m| m.y + m.height as i32).max()?;
+  Some(CaptureRect { x: left, y: top, width: (right - left) as u32, height: (bottom - top) as u32 })
+}
+
+/// Name of the monitor containing physical point (`x`, `y`).
+pub fn monitor_at(app: &tauri::AppHandle, x: i32, y: i32) -> Option<String> {
+  monitors(app).into_iter().find(|m| m.contains_physical(x, y)).and_then(|m| m.name)
+}
+
+/// Physical geometry of a logical rectangle, scaled by the monitor under its center.
+pub fn to_physical(app: &tauri::AppHandle, rect: LogicalRect) -> ScreenGeometry {
+  let (cx, cy) = (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0);
+  let all = monitors(app);
+  let monitor = all.iter().find(|m| m.contains_logical(cx, cy));
+  let scale = monitor.map_or(1.0, |m| m.scale);
+  ScreenGeometry {
+    logical: rect,
+    physical: CaptureRect {
+      x: (rect.x * scale).round() as i32,
+      y: (rect.y * scale).round() as i32,
+      width: (rect.width * scale).round().max(0.0) as u32,
+      height: (rect.height * scale).round().max(0.0) as u32,
+    },
+    scale_factor: scale,
+    monitor: monitor.and_then(|m| m.name.clone()),
+  }
+}
+
+/// Logical geometry of a physical rectangle, scaled by the monitor under its center.
+pub fn to_logical(app: &tauri::AppHandle, rect: &CaptureRect) -> ScreenGeometry {
+  let (cx, cy) = (rect.x + rect.width as i32 / 2, rect.y + rect.height as i32 / 2);
+  let all = monitors(app);
+  let monitor = all.iter().find(|m| m.contains_physical(cx, cy));
+  let scale = monitor.map_or(1.0, |m| m.scale);
+  ScreenGeometry {
+    logical: LogicalRect {
+      x: rect.x as f64 / scale,
+      y: rect.y as f64 / scale,
+      width: rect.width as f64 / scale,
+      height: rect.height as f64 / scale,
+    },
+    physical: rect.clone(),
+    scale_factor: scale,
+    monitor: monitor.and_then(|m| m.name.clone()),
+  }
+}
+
+/// Both geometries of a rectangle given in `logical` or `physical` pixels (exactly one).
+#[tauri::command]
+pub fn resolve_screen_rect(
+  app: tauri::AppHandle,
+  logical: Option<LogicalRect>,
+  physical: Option<CaptureRect>,
+) -> Result<ScreenGeometry, String> {
+  match (logical, physical) {
+    (Some(rect), None) => Ok(to_physical(&app, rect)),
+    (None, Some(rect)) => Ok(to_logical(&app, &rect)),
+    _ => Err("pass either logical or physical".to_string()),
+  }
+}
@@ -0,0 +1,386 @@
+//! User automation scripts (Rhai).
+//!
+//! Scripts live in `<app data>/scripts/*.rhai`; `scripts.json` in the same directory binds them to global
+//! hotkeys or backend events:
+//!
+//! ```json
+//! { "bindings": [{ "script": "note.rhai", "hotkey": "CommandOrControl+Alt+N" },
+//!                { "script": "log.rhai", "event": "translation.done" }] }
+//! ```
+//!
+//! Scripts run on a blocking thread with an operation limit, no module imports and a fixed API:
+//! `get_selection()`, `translate(text)`, `translate(text, target_lang)`, `get_clipboard()`,
+//! `set_clipboard(text)`, `notify(title, body)`, `append_note(name, text)`, `today()` and `print(..)`.
+//! `translate` translates like the hotkey does: into `defaultLanguage` unless given a target, in the app profile's
+//! mode, explaining in `explanationLanguage`.
+//! The scope provides `input` (string), `event` (event kind or "") and `payload` (event payload).
+//! Event bindings accept the same patterns as event bus filters (`capture.*`, `*`), except that streaming deltas
+//! (`stream.delta` and `stream.variant_delta`, one per token) only run scripts bound to that exact kind. A script
+//! bound to an event isn't run again by that event while it is still running, so a script whose own work
+//! publishes the event it is bound to (`translate()` and `stream.*`) doesn't set off a loop.
+
+use crate::events::{self, BusEvent};
+use crate::queue::Priority;
+use crate::settings;
+use crate::translate::{self, TranslateRequest};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tauri_plugin_notification::NotificationExt;
+
+const CONFIG_FILE: &str = "scripts.json";
+const SCRIPT_EXT: &str = "rhai";
+const MAX_OPERATIONS: u64 = 5_000_000;
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptBinding {
+  pub script: String,
+  #[serde(default)]
+  pub hotkey: Option<String>,
+  #[serde(default)]
+  pub event: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct ScriptsConfig {
+  #[serde(default)]
+  bindings: Vec<ScriptBinding>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScriptOutput {
+  pub result: String,
+  pub log: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScriptInfo {
+  pub name: String,
+  pub bindings: Vec<ScriptBinding>,
+}
+
+#[derive(Default)]
+struct HostInner {
+  dir: Option<PathBuf>,
+  bindings: Vec<ScriptBinding>,
+  hotkeys: Vec<String>,
+  /// Scripts running for an event, which events don't start again until they finish.
+  running: HashSet<String>,
+}
+
+/// Script bindings and registered hotkeys (managed state).
+#[derive(Default)]
+pub struct ScriptHost(Mutex<HostInner>);
+
+impl ScriptHost {
+  fn lock(&self) -> std::sync::MutexGuard<'_, HostInner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn dir(&self) -> Result<PathBuf, String> {
+    self.lock().dir.clone().ok_or_else(|| "scripts dir not initialized".to_string())
+  }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+  let ok = !name.is_empty()
+    && name.ends_with(&format!(".{SCRIPT_EXT}"))
+    && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    && !name.starts_with('.');
+  if ok {
+    Ok(())
+  } else {
+    Err(format!("invalid script name: {name}"))
+  }
+}
+
+fn read_config(dir: &std::path::Path) -> ScriptsConfig {
+  std::fs::read_to_string(dir.join(CONFIG_FILE))
+    .ok()
+    .and_then(|s| serde_json::from_str(&s).ok())
+    .unwrap_or_default()
+}
+
+fn block_on<F: std::future::Future>(f: F) -> F::Output {
+  tauri::async_runtime::block_on(f)
+}
+
+fn build_engine(app: tauri::AppHandle, log: Arc<Mutex<Vec<String>>>) -> rhai::Engine {
+  let mut engine = rhai::Engine::new();
+  engine.set_max_operations(MAX_OPERATIONS);
+  engine.set_max_string_size(MAX_STRING_SIZE);
+  engine.set_max_call_levels(32);
+  engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+
+  let l = log.clone();
+  engine.on_print(move |s| l.lock().unwrap_or_else(|e| e.into_inner()).push(s.to_string()));
+  let l = log;
+  engine.on_debug(move |s, _, _| l.lock().unwrap_or_else(|e| e.into_inner()).push(s.to_string()));
+
+  type FnResult<T> = Result<T, Box<rhai::EvalAltResult>>;
+  fn script_err<T>(e: String) -> FnResult<T> {
+    Err(e.into())
+  }
+
+  let a = app.clone();
+  engine.register_fn("get_selection", move || -> FnResult<String> {
+    block_on(crate::commands::capture_selected_text(a.clone(), None)).or_else(script_err)
+  });
+
+  let translate_with = {
+    let app = app.clone();
+    move |text: &str, target_lang: &str| -> FnResult<String> {
+      let base_url = settings::api_base_url(&app).or_else(script_err)?;
+      let req = TranslateRequest {
+        base_url,
+        text: text.to_string(),
+        target_lang: target_lang.to_string(),
+        mode: crate::app_profiles::mode(&app),
+        explanation_lang: settings::explanation_lang(&app),
+        priority: Priority::Background,
+        ..Default::default()
+      };
+      block_on(translate::run_translation(&app, req, &|_| {})).or_else(script_err)
+    }
+  };
+  let t = translate_with.clone();
+  engine.register_fn("translate", move |text: &str, target_lang: &str| t(text, target_lang));
+  let a = app.clone();
+  engine.register_fn("translate", move |text: &str| {
+    translate_with(text, &settings::default_target_lang(&a))
+  });
+
+  engine.register_fn("get_clipboard", || -> FnResult<String> {
+    let mut cb = arboard::Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
+    Ok(cb.get_text().unwrap_or_default())
+  });
+  engine.register_fn("set_clipboard", |text: &str| -> FnResult<()> {
+    let mut cb = arboard::Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
+    cb.set_text(text.to_string())
+      .map_err(|e| format!("clipboard write failed: {e}").into())
+  });
+
+  let a = app.clone();
+  engine.register_fn("notify", move |title: &str, body: &str| -> FnResult<()> {
+    a.notification()
+      .builder()
+      .title(title)
+      .body(body)
+      .show()
+      .map_err(|e| format!("notify failed: {e}").into())
+  });
+
+  // Notes are confined to `<app data>/notes/`.
+  let a = app;
+  engine.register_fn("append_note", move |name: &str, text: &str| -> FnResult<()> {
+    let valid = !name.is_empty()
+      && !name.starts_with('.')
+      && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' '));
+    if !valid {
+      return script_err(format!("invalid note name: {name}"));
+    }
+    let dir = a
+      .path()
+      .app_data_dir()
+      .map_err(|e| format!("app data dir unavailable: {e}"))?
+      .join("notes");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create dir failed: {e}"))?;
+    use std::io::Write;
+    let mut f = std::fs::OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(dir.join(name))
+      .map_err(|e| format!("open note failed: {e}"))?;
+    writeln!(f, "{text}").map_err(|e| format!("write note failed: {e}").into())
+  });
+
+  engine.register_fn("today", || chrono::Local::now().format("%Y-%m-%d").to_string());
+  engine
+}
+
+/// Run a script synchronously. Must not be called from an async context.
+fn run_blocking(
+  app: tauri::AppHandle,
+  name: &str,
+  input: String,
+  event: String,
+  payload: serde_json::Value,
+) -> Result<ScriptOutput, String> {
+  validate_name(name)?;
+  let dir = app.state::<ScriptHost>().dir()?;
+  let source = std::fs::read_to_string(dir.join(name)).map_err(|e| format!("SCRIPT_NOT_FOUND: {name}: {e}"))?;
+
+  let log = Arc::new(Mutex::new(Vec::new()));
+  let engine = build_engine(app, log.clone());
+  let mut scope = rhai::Scope::new();
+  scope.push("input", input);
+  scope.push("event", event);
+  scope.push_dynamic("payload", rhai::serde::to_dynamic(&payload).unwrap_or(rhai::Dynamic::UNIT));
+
+  let result = engine
+    .eval_with_scope::<rhai::Dynamic>(&mut scope, &source)
+    .map_err(|e| format!("script {name} failed: {e}"))?;
+  let result = if result.is_unit() { String::new() } else { result.to_string() };
+  let log = log.lock().unwrap_or_else(|e| e.into_inner()).clone();
+  Ok(ScriptOutput { result, log })
+}
+
+pub async fn run(
+  app: tauri::AppHandle,
+  name: String,
+  input: String,
+  event: String,
+  payload: serde_json::Value,
+) -> Result<ScriptOutput, String> {
+  tauri::async_runtime::spawn_blocking(move || run_blocking(app, &name, input, event, payload))
+    .await
+    .map_err(|e| format!("script task failed: {e}"))?
+}
+
+/// Whether a binding to `pattern` runs for events of `kind`: deltas need an exact binding.
+fn triggers(pattern: &str, kind: &str) -> bool {
+  if kind.ends_with("delta") {
+    pattern == kind
+  } else {
+    events::kind_matches(pattern, kind)
+  }
+}
+
+/// Run every script bound to the event's kind in the background, except those still running for an earlier event
+/// (called by the event bus).
+pub fn fire_event(app: &tauri::AppHandle, ev: &BusEvent) {
+  let scripts: Vec<String> = {
+    let host = app.state::<ScriptHost>();
+    let mut inner = host.lock();
+    let bound: Vec<String> = inner
+      .bindings
+      .iter()
+      .filter(|b| b.event.as_deref().is_some_and(|k| triggers(k, &ev.kind)))
+      .map(|b| b.script.clone())
+      .collect();
+    bound.into_iter().filter(|s| inner.running.insert(s.clone())).collect()
+  };
+  for script in scripts {
+    let app = app.clone();
+    let kind = ev.kind.clone();
+    let payload = ev.payload.clone();
+    tauri::async_runtime::spawn(async move {
+      if let Err(e) = run(app.clone(), script.clone(), String::new(), kind, payload).await {
+        log::warn!("{e}");
+      }
+      app.state::<ScriptHost>().lock().running.remove(&script);
+    });
+  }
+}
+
+/// Load bindings and (re)register script hotkeys.
+pub fn init(app: &tauri::AppHandle, dir: Option<PathBuf>) -> Result<(), String> {
+  let host = app.state::<ScriptHost>();
+  let (dir, old_hotkeys) = {
+    let mut inner = host.lock();
+    if dir.is_some() {
+      inner.dir = dir;
+    }
+    (inner.dir.clone(), std::mem::take(&mut inner.hotkeys))
+  };
+  let Some(dir) = dir else {
+    return Err("scripts dir not initialized".to_string());
+  };
+
+  let gs = app.global_shortcut();
+  for hk in &old_hotkeys {
+    let _ = gs.unregister(hk.as_str());
+  }
+
+  let config = read_config(&dir);
+  let mut hotkeys: Vec<String> = Vec::new();
+  for b in &config.bindings {
+    let Some(hk) = b.hotkey.clone().filter(|s| !s.trim().is_empty()) else {
+      continue;
+    };
+    let script = b.script.clone();
+    let hotkey = hk.clone();
+    let registered = gs.on_shortcut(hk.as_str(), move |app, _shortcut, ev| {
+      if ev.state != ShortcutState::Pressed {
+        return;
+      }
+      events::publish(
+        app,
+        "hotkey.fired",
+        None,
+        serde_json::json!({ "hotkey": hotkey, "script": script }),
+      );
+      let app = app.clone();
+      let script = script.clone();
+      tauri::async_runtime::spawn(async move {
+        if let Err(e) = run(app, script, String::new(), "hotkey".to_string(), serde_json::Value::Null).await {
+          log::warn!("{e}");
+        }
+      });
+    });
+    match registered {
+      Ok(()) => hotkeys.push(hk),
+      Err(e) => log::warn!("script hotkey {hk} not registered: {e}"),
+    }
+  }
+
+  let mut inner = host.lock();
+  inner.bindings = config.bindings;
+  inner.hotkeys = hotkeys;
+  Ok(())
+}
+
+#[tauri::command]
+pub fn list_scripts(host: tauri::State<'_, ScriptHost>) -> Result<Vec<ScriptInfo>, String> {
+  let dir = host.dir()?;
+  let bindings = host.lock().bindings.clone();
+  let mut out: Vec<ScriptInfo> = Vec::new();
+  for entry in std::fs::read_dir(&dir).map_err(|e| format!("read dir failed: {e}"))?.flatten() {
+    let name = entry.file_name().to_string_lossy().to_string();
+    if validate_name(&name).is_err() {
+      continue;
+    }
+    out.push(ScriptInfo {
+      bindings: bindings.iter().filter(|b| b.script == name).cloned().collect(),
+      name,
+    });
+  }
+  out.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(out)
+}
+
+#[tauri::command]
+pub fn read_script(name: String, host: tauri::State<'_, ScriptHost>) -> Result<String, String> {
+  validate_name(&name)?;
+  std::fs::read_to_string(host.dir()?.join(&name)).map_err(|e| format!("SCRIPT_NOT_FOUND: {name}: {e}"))
+}
+
+/// Save a script after checking that it compiles.
+#[tauri::command]
+pub fn save_script(name: String, source: String, host: tauri::State<'_, ScriptHost>) -> Result<(), String> {
+  validate_name(&name)?;
+  rhai::Engine::new()
+    .compile(&source)
+    .map_err(|e| format!("script syntax error: {e}"))?;
+  std::fs::write(host.dir()?.join(&name), source).map_err(|e| format!("write script failed: {e}"))
+}
+
+#[tauri::command]
+pub fn delete_script(name: String, host: tauri::State<'_, ScriptHost>) -> Result<(), String> {
+  validate_name(&name)?;
+  std::fs::remove_file(host.dir()?.join(&name)).map_err(|e| format!("delete script failed: {e}"))
+}
+
+#[tauri::command]
+pub async fn run_script(app: tauri::AppHandle, name: String, input: Option<String>) -> Result<ScriptOutput, String> {
+  run(app, name, input.unwrap_or_default(), String::new(), serde_json::Value::Null).await
+}
+
+#[tauri::command]
+pub fn reload_scripts(app: tauri::AppHandle) -> Result<(), String> {
+  init(&app, None)
+}
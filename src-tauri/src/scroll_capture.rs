@@ -0,0 +1,227 @@
+//! Scrolling capture: scroll the window under the pointer with the mouse wheel, capture the region after each
+//! step, and stitch the frames into one tall image.
+//!
+//! Consecutive frames are lined up by comparing rows: the scroll distance is the shift at which most rows of the
+//! new frame repeat the old one. Rows that stay put between the first two frames (toolbars, sticky headers and
+//! footers) are kept once, at the top and bottom. Capturing stops when a scroll changes nothing (the end of the
+//! page), when no overlap is found (the step scrolled further than the region is tall), or at the frame limit.
+
+use serde::Serialize;
+use std::hash::{Hash, Hasher};
+
+use crate::commands::CaptureRect;
+use crate::settings;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScrollCapture {
+  pub path: String,
+  pub width: u32,
+  pub height: u32,
+  pub frames: u32,
+}
+
+/// A captured frame as RGB rows.
+struct Frame {
+  width: usize,
+  height: usize,
+  rgb: Vec<u8>,
+  rows: Vec<RowKey>,
+}
+
+/// What rows are compared by: a hash of the pixels, and whether the row is one flat color (blank rows match
+/// at any shift, so they don't count as evidence).
+#[derive(Clone, Copy, PartialEq)]
+struct RowKey {
+  hash: u64,
+  flat: bool,
+}
+
+impl Frame {
+  fn load(path: &str) -> Result<Frame, String> {
+    let image = crate::image_io::read_rgb(path)?;
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let rgb = image.into_raw();
+    let rows = rgb
+      .chunks_exact(width * 3)
+      .map(|row| {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        row.hash(&mut hasher);
+        RowKey { hash: hasher.finish(), flat: row.chunks_exact(3).all(|px| px == &row[..3]) }
+      })
+      .collect();
+    Ok(Frame { width, height, rgb, rows })
+  }
+
+  fn row_bytes(&self, rows: std::ops::Range<usize>) -> &[u8] {
+    &self.rgb[rows.start * self.width * 3..rows.end * self.width * 3]
+  }
+}
+
+/// Rows at the top and bottom that are the same in both frames.
+fn static_bands(a: &[RowKey], b: &[RowKey]) -> (usize, usize) {
+  let top = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+  let bottom = a[top..].iter().rev().zip(b[top..].iter().rev()).take_while(|(x, y)| x == y).count();
+  (top, bottom)
+}
+
+/// How many rows `cur` moved up relative to `prev` (0 = not at all), or `None` when they don't overlap by at least
+/// a quarter of their height.
+fn scroll_offset(prev: &[RowKey], cur: &[RowKey]) -> Option<usize> {
+  let n = prev.len().min(cur.len());
+  let min_overlap = (n / 4).max(1);
+  let mut best: Option<(usize, f32)> = None;
+  for shift in 0..=n.saturating_sub(min_overlap) {
+    let (mut matched, mut counted) = (0usize, 0usize);
+    for i in 0..n - shift {
+      if cur[i].flat {
+        continue;
+      }
+      counted += 1;
+      if cur[i] == prev[shift + i] {
+        matched += 1;
+      }
+    }
+    if counted < 3 {
+      continue;
+    }
+    let score = matched as f32 / counted as f32;
+    if score >= 0.9 && best.map_or(true, |(_, s)| score > s) {
+      best = Some((shift, score));
+    }
+  }
+  best.map(|(shift, _)| shift)
+}
+
+/// Turn the mouse wheel under the pointer down by `notches`.
+#[cfg_attr(feature = "deterministic", allow(unreachable_code))]
+fn scroll_down(notches: u32) -> Result<(), String> {
+  #[cfg(feature = "deterministic")]
+  {
+    let _ = notches;
+    return Ok(());
+  }
+
+  #[cfg(windows)]
+  {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+      SendInput, INPUT, INPUT_0, INPUT_MOUSE, MOUSEEVENTF_WHEEL, MOUSEINPUT,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::WHEEL_DELTA;
+
+    let input = INPUT {
+      r#type: INPUT_MOUSE,
+      Anonymous: INPUT_0 {
+        mi: MOUSEINPUT {
+          dx: 0,
+          dy: 0,
+          mouseData: (-(WHEEL_DELTA as i32) * notches as i32) as u32,
+          dwFlags: MOUSEEVENTF_WHEEL,
+          time: 0,
+          dwExtraInfo: 0,
+        },
+      },
+    };
+    if unsafe { SendInput(1, &input, std::mem::size_of::<INPUT>() as i32) } != 1 {
+      return Err("SendInput failed".to_string());
+    }
+    Ok(())
+  }
+
+  #[cfg(target_os = "macos")]
+  {
+    use core_graphics::event::{CGEvent, CGEventTapLocation, ScrollEventUnit};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    let source =
+      CGEventSource::new(CGEventSourceStateID::HIDSystemState).map_err(|_| "CGEventSource failed".to_string())?;
+    let event = CGEvent::new_scroll_event(source, ScrollEventUnit::LINE, 1, -(notches as i32), 0, 0)
+      .map_err(|_| "CGEventCreateScrollWheelEvent failed".to_string())?;
+    event.post(CGEventTapLocation::HID);
+    Ok(())
+  }
+
+  #[cfg(target_os = "linux")]
+  {
+    crate::linux_capture::scroll_down(notches)
+  }
+
+  #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+  {
+    let _ = notches;
+    Err("scrolling capture not supported on this platform".to_string())
+  }
+}
+
+fn capture_frame(app: &tauri::AppHandle, rect: &CaptureRect) -> Result<Frame, String> {
+  let path = crate::commands::capture_region(app, rect, false)?;
+  let frame = Frame::load(&path);
+  let _ = std::fs::remove_file(&path);
+  frame
+}
+
+fn capture(app: &tauri::AppHandle, rect: &CaptureRect, max_frames: u32) -> Result<ScrollCapture, String> {
+  let notches = settings::scroll_capture_notches(app);
+  let delay = std::time::Duration::from_millis(settings::scroll_capture_delay_ms(app));
+
+  let first = capture_frame(app, rect)?;
+  let mut frames = 1;
+  // Scrolling rows are `top..first.height - bottom`; decided by the first step.
+  let mut bands: Option<(usize, usize)> = None;
+  let mut out = Vec::new();
+  let mut prev = first;
+  while frames < max_frames {
+    scroll_down(notches)?;
+    std::thread::sleep(delay);
+    let cur = capture_frame(app, rect)?;
+    if cur.width != prev.width || cur.height != prev.height {
+      return Err("capture size changed while scrolling".to_string());
+    }
+    let (top, bottom) = match bands {
+      Some(bands) => bands,
+      None => {
+        let (top, bottom) = static_bands(&prev.rows, &cur.rows);
+        if top == prev.height {
+          break;
+        }
+        out.extend_from_slice(prev.row_bytes(0..prev.height - bottom));
+        bands = Some((top, bottom));
+        (top, bottom)
+      }
+    };
+    let end = cur.height - bottom;
+    match scroll_offset(&prev.rows[top..end], &cur.rows[top..end]) {
+      Some(0) | None => break,
+      Some(shift) => out.extend_from_slice(cur.row_bytes(end - shift..end)),
+    }
+    frames += 1;
+    prev = cur;
+  }
+  match bands {
+    Some((_, bottom)) => out.extend_from_slice(prev.row_bytes(prev.height - bottom..prev.height)),
+    None => out = prev.rgb,
+  }
+
+  let (width, height) = (prev.width as u32, (out.len() / (prev.width * 3).max(1)) as u32);
+  let out_path = crate::temp::path("capture", "png");
+  if let Err(e) = crate::image_io::write_png(&out_path, &out, width, height) {
+    let _ = std::fs::remove_file(&out_path);
+    return Err(e);
+  }
+  Ok(ScrollCapture { path: out_path.to_string_lossy().to_string(), width, height, frames })
+}
+
+/// Scroll the window under the pointer through `rect` (or `logical`, see `capture_screen_region`) and capture it
+/// as one tall PNG. `max_frames` caps the number of screens (default `scrollCaptureMaxFrames`).
+#[tauri::command]
+pub async fn capture_scrolling(
+  app: tauri::AppHandle,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+  max_frames: Option<u32>,
+) -> Result<ScrollCapture, String> {
+  let rect = crate::commands::physical_rect(&app, rect, logical)?;
+  let max_frames = max_frames.unwrap_or_else(|| settings::scroll_capture_max_frames(&app)).max(1);
+  tauri::async_runtime::spawn_blocking(move || capture(&app, &rect, max_frames))
+    .await
+    .map_err(|e| format!("capture failed: {e}"))?
+}
@@ -0,0 +1,137 @@
+//! Secrets (provider API keys) in the OS keychain: Windows Credential Manager, the macOS Keychain or the Secret
+//! Service, never in the settings file.
+//!
+//! A secret is stored under a name. `apiKey` is the translation server's key: requests to `apiBaseUrl` carry it as
+//! `Authorization: Bearer <key>` (see `http::post_json`). Keys older versions kept in the settings (`apiKey`, or
+//! any `...ApiKey` string) are moved into the keychain at startup, and removed from the settings only once they read
+//! back from a keychain that persists them.
+
+use crate::settings;
+
+const KEYRING_SERVICE: &str = "erudaite";
+/// The translation server's API key.
+pub const API_KEY: &str = "apiKey";
+/// The history database's key (see `history_cipher`); only the backend uses it.
+pub const HISTORY_KEY: &str = "historyKey";
+const MAX_NAME_LEN: usize = 128;
+
+fn validate_name(name: &str) -> Result<(), String> {
+  let valid = !name.is_empty() && name.len() <= MAX_NAME_LEN && !name.chars().any(char::is_control);
+  if valid {
+    Ok(())
+  } else {
+    Err(format!("invalid secret name: {name:?}"))
+  }
+}
+
+/// Secrets the frontend can't read or change.
+fn reject_internal(name: &str) -> Result<(), String> {
+  if name == HISTORY_KEY {
+    Err(format!("{name} is managed by the app"))
+  } else {
+    Ok(())
+  }
+}
+
+fn entry(name: &str) -> Result<keyring::Entry, String> {
+  validate_name(name)?;
+  keyring::Entry::new(KEYRING_SERVICE, &format!("secret:{name}")).map_err(|e| format!("keychain unavailable: {e}"))
+}
+
+/// The secret stored as `name`, if any.
+pub fn get(name: &str) -> Result<Option<String>, String> {
+  match entry(name)?.get_password() {
+    Ok(secret) => Ok(Some(secret)),
+    Err(keyring::Error::NoEntry) => Ok(None),
+    Err(e) => Err(format!("keychain error: {e}")),
+  }
+}
+
+pub(crate) fn set(name: &str, secret: &str) -> Result<(), String> {
+  entry(name)?
+    .set_password(secret)
+    .map_err(|e| format!("failed to store secret {name}: {e}"))
+}
+
+/// Whether the keychain keeps secrets until they're deleted (not just until logout or reboot).
+pub(crate) fn is_persistent() -> bool {
+  matches!(
+    keyring::default::default_credential_builder().persistence(),
+    keyring::credential::CredentialPersistence::UntilDelete
+  )
+}
+
+/// Store `secret` as `name` and read it back, for callers that drop their own copy afterwards: fails unless the
+/// keychain is persistent and returns exactly what was stored.
+pub(crate) fn set_durably(name: &str, secret: &str) -> Result<(), String> {
+  if !is_persistent() {
+    return Err("the keychain doesn't keep secrets across restarts".to_string());
+  }
+  set(name, secret)?;
+  match get(name)? {
+    Some(stored) if stored == secret => Ok(()),
+    _ => Err(format!("secret {name} didn't read back from the keychain")),
+  }
+}
+
+/// Remove the secret stored as `name` (nothing to do if there is none).
+pub(crate) fn delete(name: &str) -> Result<(), String> {
+  match entry(name)?.delete_credential() {
+    Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+    Err(e) => Err(format!("failed to delete secret {name}: {e}")),
+  }
+}
+
+/// Whether settings key `key` held an API key in older versions.
+pub(crate) fn is_plaintext_key(key: &str) -> bool {
+  key == API_KEY || key.ends_with("ApiKey")
+}
+
+/// Move API keys saved in the settings into the keychain (`setup`, before the settings are read).
+pub fn init(app: &tauri::AppHandle) {
+  let Some(serde_json::Value::Object(mut stored)) = settings::stored(app) else {
+    return;
+  };
+  let keys: Vec<String> = stored.keys().filter(|k| is_plaintext_key(k)).cloned().collect();
+  if keys.is_empty() {
+    return;
+  }
+  for key in keys {
+    let secret = stored.get(&key).and_then(|v| v.as_str()).map(str::trim).unwrap_or_default();
+    let moved = secret.is_empty() || set_durably(&key, secret).is_ok();
+    // Left in place unless the keychain verifiably kept it, rather than losing the key.
+    if moved {
+      stored.remove(&key);
+    } else {
+      crate::events::warn(app, None, "secret_migration_failed", format!("{key} couldn't be moved to the keychain"));
+    }
+  }
+  if let Err(e) = settings::save(app, serde_json::Value::Object(stored)) {
+    log::warn!("failed to save settings after moving API keys: {e}");
+  }
+}
+
+/// Store `secret` as `name` in the OS keychain, replacing what was there.
+#[tauri::command]
+pub fn set_secret(name: String, secret: String) -> Result<(), String> {
+  let secret = secret.trim();
+  if secret.is_empty() {
+    return Err("secret is empty".to_string());
+  }
+  reject_internal(&name)?;
+  set(&name, secret)
+}
+
+/// The secret stored as `name`, or `None`.
+#[tauri::command]
+pub fn get_secret(name: String) -> Result<Option<String>, String> {
+  reject_internal(&name)?;
+  get(&name)
+}
+
+/// Remove the secret stored as `name` (nothing to do if there is none).
+#[tauri::command]
+pub fn delete_secret(name: String) -> Result<(), String> {
+  reject_internal(&name)?;
+  delete(&name)
+}
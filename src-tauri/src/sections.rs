@@ -0,0 +1,173 @@
+//! Section boundaries in the translation stream.
+//!
+//! Providers mark sections either with a `section` field on a frame or inline with `[[section:<name>]]` in the
+//! content. Inline markers may be split across deltas, so a possible partial marker is held back until the
+//! next delta (or `flush`).
+
+const MARKER_OPEN: &str = "[[section:";
+const MARKER_CLOSE: &str = "]]";
+const MAX_NAME_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Piece {
+  Text(String),
+  Section(String),
+}
+
+#[derive(Debug, Default)]
+pub struct SectionSplitter {
+  pending: String,
+  current: Option<String>,
+}
+
+/// Normalize a provider section name (`Translation`, ` explanation ` -> `translation`, `explanation`).
+pub fn normalize_name(name: &str) -> Option<String> {
+  let n = name.trim().to_lowercase();
+  let valid = !n.is_empty()
+    && n.len() <= MAX_NAME_LEN
+    && n.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+  valid.then_some(n)
+}
+
+impl SectionSplitter {
+  /// Switch to `name`; returns the piece to emit if it differs from the current section.
+  pub fn enter(&mut self, name: &str) -> Option<Piece> {
+    let name = normalize_name(name)?;
+    if self.current.as_deref() == Some(name.as_str()) {
+      return None;
+    }
+    self.current = Some(name.clone());
+    Some(Piece::Section(name))
+  }
+
+  pub fn feed(&mut self, content: &str) -> Vec<Piece> {
+    self.pending.push_str(content);
+    let mut out: Vec<Piece> = Vec::new();
+    loop {
+      let Some(start) = self.pending.find(MARKER_OPEN) else {
+        // Hold back a trailing prefix of the opening marker.
+        let keep = partial_suffix_len(&self.pending, MARKER_OPEN);
+        let emit_len = self.pending.len() - keep;
+        if emit_len > 0 {
+          out.push(Piece::Text(self.pending[..emit_len].to_string()));
+          self.pending.drain(..emit_len);
+        }
+        break;
+      };
+      let name_start = start + MARKER_OPEN.len();
+      match self.pending[name_start..].find(MARKER_CLOSE) {
+        Some(rel_end) => {
+          let name = self.pending[name_start..name_start + rel_end].to_string();
+          let marker_end = name_start + rel_end + MARKER_CLOSE.len();
+          if start > 0 {
+            out.push(Piece::Text(self.pending[..start].to_string()));
+          }
+          match self.enter(&name) {
+            Some(p) => out.push(p),
+            None if normalize_name(&name).is_none() => {
+              // Not a valid marker; pass it through as text.
+              out.push(Piece::Text(self.pending[start..marker_end].to_string()));
+            }
+            None => {}
+          }
+          self.pending.drain(..marker_end);
+        }
+        None if self.pending.len() - name_start > MAX_NAME_LEN + MARKER_CLOSE.len() => {
+          // Too long to be a marker; emit through the opening bracket and keep scanning.
+          let upto = start + MARKER_OPEN.len();
+          out.push(Piece::Text(self.pending[..upto].to_string()));
+          self.pending.drain(..upto);
+        }
+        None => {
+          if start > 0 {
+            out.push(Piece::Text(self.pending[..start].to_string()));
+            self.pending.drain(..start);
+          }
+          break;
+        }
+      }
+    }
+    merge_text(out)
+  }
+
+  /// Emit anything still held back.
+  pub fn flush(&mut self) -> Option<Piece> {
+    if self.pending.is_empty() {
+      return None;
+    }
+    Some(Piece::Text(std::mem::take(&mut self.pending)))
+  }
+}
+
+fn partial_suffix_len(s: &str, marker: &str) -> usize {
+  (1..marker.len())
+    .rev()
+    .find(|&n| s.len() >= n && s.is_char_boundary(s.len() - n) && marker.starts_with(&s[s.len() - n..]))
+    .unwrap_or(0)
+}
+
+fn merge_text(pieces: Vec<Piece>) -> Vec<Piece> {
+  let mut out: Vec<Piece> = Vec::new();
+  for p in pieces {
+    match (out.last_mut(), p) {
+      (Some(Piece::Text(prev)), Piece::Text(t)) => prev.push_str(&t),
+      (_, p) => out.push(p),
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn text(s: &str) -> Piece {
+    Piece::Text(s.to_string())
+  }
+
+  fn section(s: &str) -> Piece {
+    Piece::Section(s.to_string())
+  }
+
+  #[test]
+  fn normalizes_section_names() {
+    assert_eq!(normalize_name(" Explanation ").as_deref(), Some("explanation"));
+    assert_eq!(normalize_name("bad name"), None);
+    assert_eq!(normalize_name(""), None);
+  }
+
+  #[test]
+  fn splits_inline_markers() {
+    let mut splitter = SectionSplitter::default();
+    assert_eq!(
+      splitter.feed("Hello [[section:Explanation]]World"),
+      [text("Hello "), section("explanation"), text("World")]
+    );
+  }
+
+  #[test]
+  fn holds_back_a_marker_split_across_deltas() {
+    let mut splitter = SectionSplitter::default();
+    assert_eq!(splitter.feed("abc [[sec"), [text("abc ")]);
+    assert_eq!(splitter.feed("tion:notes]]x"), [section("notes"), text("x")]);
+    assert_eq!(splitter.feed("a["), [text("a")]);
+    assert_eq!(splitter.flush(), Some(text("[")));
+    assert_eq!(splitter.flush(), None);
+  }
+
+  #[test]
+  fn passes_invalid_markers_through() {
+    let mut splitter = SectionSplitter::default();
+    assert_eq!(splitter.feed("[[section:bad name]]"), [text("[[section:bad name]]")]);
+    let long = format!("[[section:{}", "x".repeat(40));
+    assert_eq!(splitter.feed(&long), [text(&long)]);
+  }
+
+  #[test]
+  fn enters_each_section_once() {
+    let mut splitter = SectionSplitter::default();
+    assert_eq!(splitter.enter("translation"), Some(section("translation")));
+    assert_eq!(splitter.enter(" Translation "), None);
+    assert_eq!(splitter.feed("[[section:translation]]ok"), [text("ok")]);
+  }
+}
@@ -0,0 +1,349 @@
+//! Crash-safe record of the auxiliary windows (translation popup, OCR overlay) and their content.
+//!
+//! `session.json` in the app data dir lists the open windows and their geometry; the popup's latest
+//! state (`erudaite://popup/state`) is kept as a draft under `drafts/<session>/<label>.json`. A clean
+//! exit marks the session as such. If the previous run didn't exit cleanly, its windows are offered
+//! through `get_previous_session` and `restore_previous_session` reopens the popups; each takes its draft with
+//! `take_restored_draft` once its page listens for state, instead of asking the main window for the current one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Listener, Manager};
+
+use crate::clock;
+use crate::events;
+
+const SESSION_FILE: &str = "session.json";
+const DRAFTS_DIR: &str = "drafts";
+const POPUP_STATE_EVENT: &str = "erudaite://popup/state";
+/// Minimum spacing between draft writes while a translation is streaming.
+const DRAFT_FLUSH_MS: u64 = 1000;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SessionFile {
+  pub id: String,
+  pub started_at: u128,
+  pub clean_exit: bool,
+  pub windows: Vec<SessionWindow>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionWindow {
+  pub label: String,
+  /// "popup" or "ocr-overlay".
+  pub kind: String,
+  /// Logical coordinates.
+  pub x: f64,
+  pub y: f64,
+  pub width: f64,
+  pub height: f64,
+  /// Last popup state (`status`, `source`, `translation`), loaded from the draft store.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub draft: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct RestoreReport {
+  pub reopened: Vec<String>,
+  /// Windows that can't be meaningfully reopened (an OCR overlay's screen content is gone).
+  pub skipped: Vec<String>,
+}
+
+#[derive(Default)]
+struct Inner {
+  dir: Option<PathBuf>,
+  current: SessionFile,
+  previous: Option<SessionFile>,
+  drafts: HashMap<String, serde_json::Value>,
+  /// Drafts of reopened popups, until their page takes them.
+  restored: HashMap<String, serde_json::Value>,
+  last_draft_write: HashMap<String, u128>,
+  flush_pending: HashSet<String>,
+}
+
+impl Inner {
+  fn drafts_dir(&self, session_id: &str) -> Option<PathBuf> {
+    self.dir.as_ref().map(|d| d.join(DRAFTS_DIR).join(session_id))
+  }
+
+  fn save(&self) {
+    let Some(dir) = &self.dir else {
+      return;
+    };
+    match serde_json::to_vec_pretty(&self.current) {
+      Ok(bytes) => {
+        if let Err(e) = std::fs::write(dir.join(SESSION_FILE), bytes) {
+          log::warn!("failed to write session: {e}");
+        }
+      }
+      Err(e) => log::warn!("failed to serialize session: {e}"),
+    }
+  }
+
+  fn write_draft(&mut self, label: &str) {
+    let Some(dir) = self.drafts_dir(&self.current.id) else {
+      return;
+    };
+    let Some(draft) = self.drafts.get(label) else {
+      return;
+    };
+    let _ = std::fs::create_dir_all(&dir);
+    if let Ok(bytes) = serde_json::to_vec(draft) {
+      let _ = std::fs::write(dir.join(format!("{label}.json")), bytes);
+    }
+    self.last_draft_write.insert(label.to_string(), clock::now_millis());
+  }
+}
+
+/// Session tracking (managed state).
+#[derive(Default)]
+pub struct SessionState(Mutex<Inner>);
+
+impl SessionState {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+fn window_kind(url: &str) -> Option<&'static str> {
+  if url.contains("#/popup") {
+    Some("popup")
+  } else if url.contains("#/ocr-overlay") {
+    Some("ocr-overlay")
+  } else {
+    None
+  }
+}
+
+fn read_draft(dir: &std::path::Path, label: &str) -> Option<serde_json::Value> {
+  let bytes = std::fs::read(dir.join(format!("{label}.json"))).ok()?;
+  serde_json::from_slice(&bytes).ok()
+}
+
+/// Load the previous session, start a new one, and listen for popup state updates.
+pub fn init(app: &tauri::AppHandle, dir: PathBuf) {
+  let state = app.state::<SessionState>();
+  {
+    let mut inner = state.lock();
+    let previous: Option<SessionFile> = std::fs::read(dir.join(SESSION_FILE))
+      .ok()
+      .and_then(|b| serde_json::from_slice(&b).ok());
+    inner.dir = Some(dir.clone());
+    inner.previous = previous.filter(|p| !p.clean_exit && !p.windows.is_empty()).map(|mut p| {
+      let drafts = dir.join(DRAFTS_DIR).join(&p.id);
+      for w in &mut p.windows {
+        w.draft = read_draft(&drafts, &w.label);
+      }
+      p
+    });
+
+    // Drop drafts of older sessions; keep the ones of a session we may still restore.
+    let keep = inner.previous.as_ref().map(|p| p.id.clone());
+    if let Ok(entries) = std::fs::read_dir(dir.join(DRAFTS_DIR)) {
+      for entry in entries.flatten() {
+        if Some(entry.file_name().to_string_lossy().to_string()) != keep {
+          let _ = std::fs::remove_dir_all(entry.path());
+        }
+      }
+    }
+
+    inner.current = SessionFile {
+      id: clock::next_id("session"),
+      started_at: clock::now_millis(),
+      clean_exit: false,
+      windows: Vec::new(),
+    };
+    inner.save();
+  }
+
+  let handle = app.clone();
+  app.listen_any(POPUP_STATE_EVENT, move |event| {
+    let Ok(partial) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+      return;
+    };
+    record_draft(&handle, "popup", partial);
+  });
+}
+
+/// Merge a popup state update into the draft and persist it, at most once per `DRAFT_FLUSH_MS`.
+fn record_draft(app: &tauri::AppHandle, label: &str, partial: serde_json::Value) {
+  let state = app.state::<SessionState>();
+  let mut inner = state.lock();
+  let draft = inner
+    .drafts
+    .entry(label.to_string())
+    .or_insert_with(|| serde_json::json!({}));
+  if let (Some(d), Some(p)) = (draft.as_object_mut(), partial.as_object()) {
+    for (k, v) in p {
+      d.insert(k.clone(), v.clone());
+    }
+  }
+  let last = inner.last_draft_write.get(label).copied().unwrap_or(0);
+  if clock::now_millis().saturating_sub(last) >= DRAFT_FLUSH_MS as u128 {
+    inner.write_draft(label);
+    return;
+  }
+  if !inner.flush_pending.insert(label.to_string()) {
+    return;
+  }
+  let app = app.clone();
+  let label = label.to_string();
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(std::time::Duration::from_millis(DRAFT_FLUSH_MS)).await;
+    let state = app.state::<SessionState>();
+    let mut inner = state.lock();
+    inner.flush_pending.remove(&label);
+    inner.write_draft(&label);
+  });
+}
+
+fn geometry(window: &tauri::Window) -> Option<(f64, f64, f64, f64)> {
+  let scale = window.scale_factor().ok()?;
+  let pos = window.outer_position().ok()?.to_logical::<f64>(scale);
+  let size = window.inner_size().ok()?.to_logical::<f64>(scale);
+  Some((pos.x, pos.y, size.width, size.height))
+}
+
+/// Register auxiliary windows once their page has loaded (`on_page_load`).
+pub fn on_page_load(webview: &tauri::Webview, payload: &tauri::webview::PageLoadPayload<'_>) {
+  if payload.event() != tauri::webview::PageLoadEvent::Finished {
+    return;
+  }
+  let Some(kind) = window_kind(payload.url().as_str()) else {
+    return;
+  };
+  let window = webview.window();
+  let Some((x, y, width, height)) = geometry(&window) else {
+    return;
+  };
+  let state = webview.app_handle().state::<SessionState>();
+  let mut inner = state.lock();
+  let label = webview.label().to_string();
+  inner.current.windows.retain(|w| w.label != label);
+  inner.current.windows.push(SessionWindow {
+    label,
+    kind: kind.to_string(),
+    x,
+    y,
+    width,
+    height,
+    draft: None,
+  });
+  inner.save();
+}
+
+/// Keep geometry current and forget windows (and their drafts) once they close (`on_window_event`).
+pub fn on_window_event(window: &tauri::Window, event: &tauri::WindowEvent) {
+  let label = window.label().to_string();
+  let state = window.app_handle().state::<SessionState>();
+  match event {
+    tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => {
+      let Some((x, y, width, height)) = geometry(window) else {
+        return;
+      };
+      let mut inner = state.lock();
+      let Some(w) = inner.current.windows.iter_mut().find(|w| w.label == label) else {
+        return;
+      };
+      if (w.x, w.y, w.width, w.height) == (x, y, width, height) {
+        return;
+      }
+      (w.x, w.y, w.width, w.height) = (x, y, width, height);
+      inner.save();
+    }
+    tauri::WindowEvent::Destroyed => {
+      let mut inner = state.lock();
+      let before = inner.current.windows.len();
+      inner.current.windows.retain(|w| w.label != label);
+      if inner.current.windows.len() == before {
+        return;
+      }
+      inner.drafts.remove(&label);
+      if let Some(dir) = inner.drafts_dir(&inner.current.id) {
+        let _ = std::fs::remove_file(dir.join(format!("{label}.json")));
+      }
+      inner.save();
+    }
+    _ => {}
+  }
+}
+
+/// Mark the session as cleanly finished (`RunEvent::Exit`).
+pub fn mark_clean_exit(app: &tauri::AppHandle) {
+  let state = app.state::<SessionState>();
+  let mut inner = state.lock();
+  inner.current.clean_exit = true;
+  inner.save();
+}
+
+fn discard_previous(inner: &mut Inner) {
+  if let Some(prev) = inner.previous.take() {
+    if let Some(dir) = inner.drafts_dir(&prev.id) {
+      let _ = std::fs::remove_dir_all(dir);
+    }
+  }
+}
+
+/// The unfinished session from a previous run that crashed, if any.
+#[tauri::command]
+pub fn get_previous_session(state: tauri::State<'_, SessionState>) -> Option<SessionFile> {
+  state.lock().previous.clone()
+}
+
+#[tauri::command]
+pub fn discard_previous_session(state: tauri::State<'_, SessionState>) {
+  discard_previous(&mut state.lock());
+}
+
+/// The draft a reopened popup `label` shows, once (see the module docs).
+#[tauri::command]
+pub fn take_restored_draft(state: tauri::State<'_, SessionState>, label: String) -> Option<serde_json::Value> {
+  state.lock().restored.remove(&label)
+}
+
+/// Reopen the previous session's popups at their old position and reload their drafts.
+#[tauri::command]
+pub async fn restore_previous_session(app: tauri::AppHandle) -> Result<RestoreReport, String> {
+  let previous = {
+    let state = app.state::<SessionState>();
+    let inner = state.lock();
+    inner.previous.clone()
+  };
+  let Some(previous) = previous else {
+    return Ok(RestoreReport::default());
+  };
+
+  let mut report = RestoreReport::default();
+  for w in &previous.windows {
+    if w.kind != "popup" || app.get_webview_window(&w.label).is_some() {
+      report.skipped.push(w.label.clone());
+      continue;
+    }
+    if let Some(draft) = w.draft.clone() {
+      app.state::<SessionState>().lock().restored.insert(w.label.clone(), draft);
+    }
+    tauri::WebviewWindowBuilder::new(&app, &w.label, tauri::WebviewUrl::App("index.html#/popup".into()))
+      .inner_size(w.width, w.height)
+      .position(w.x, w.y)
+      .resizable(false)
+      .decorations(false)
+      .always_on_top(true)
+      .skip_taskbar(true)
+      .focused(false)
+      .shadow(true)
+      .build()
+      .map_err(|e| format!("failed to reopen {}: {e}", w.label))?;
+    report.reopened.push(w.label.clone());
+  }
+
+  discard_previous(&mut app.state::<SessionState>().lock());
+  events::publish(
+    &app,
+    "session.restored",
+    None,
+    serde_json::to_value(&report).unwrap_or_default(),
+  );
+  Ok(report)
+}
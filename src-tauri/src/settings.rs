@@ -0,0 +1,570 @@
+//! Read access to the settings persisted by the frontend (`settings.json` store, key `settings`), and the
+//! change broadcast for subsystems that hold on to settings.
+//!
+//! Most settings are read when used and need nothing else. Subsystems that apply a setting once (hotkeys,
+//! the tray, watchers) `subscribe` with the keys they care about; when the frontend saves settings, every
+//! subscriber whose keys changed is called, and the per-subsystem outcome is published as
+//! `config.reloaded` and kept for `get_reload_status`. What may be saved is checked in `settings_schema`.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{Listener, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::clock;
+use crate::events;
+
+pub const STORE_FILE: &str = "settings.json";
+const SETTINGS_KEY: &str = "settings";
+const DEFAULT_TARGET_LANG: &str = "Japanese";
+const DEFAULT_EXPLANATION_LANG: &str = "ja";
+
+/// The raw settings object (`{}` if nothing has been saved yet).
+pub fn load(app: &tauri::AppHandle) -> serde_json::Value {
+  stored(app).unwrap_or_else(|| serde_json::json!({}))
+}
+
+/// The settings object as saved, if it has been (see `settings_schema`).
+pub fn stored(app: &tauri::AppHandle) -> Option<serde_json::Value> {
+  app
+    .store(STORE_FILE)
+    .ok()
+    .and_then(|store| store.get(SETTINGS_KEY))
+    .filter(|v| v.is_object())
+}
+
+/// Replace the saved settings object and write the store to disk; subscribers reload as for a frontend save.
+pub fn save(app: &tauri::AppHandle, settings: serde_json::Value) -> Result<(), String> {
+  let store = app.store(STORE_FILE).map_err(|e| format!("failed to open {STORE_FILE}: {e}"))?;
+  store.set(SETTINGS_KEY, settings);
+  store.save().map_err(|e| format!("failed to write {STORE_FILE}: {e}"))
+}
+
+fn get_str(app: &tauri::AppHandle, key: &str) -> Option<String> {
+  load(app)
+    .get(key)
+    .and_then(|x| x.as_str())
+    .map(|s| s.trim().to_string())
+    .filter(|s| !s.is_empty())
+}
+
+fn get_u64(app: &tauri::AppHandle, key: &str) -> Option<u64> {
+  load(app).get(key).and_then(|x| x.as_u64())
+}
+
+fn get_str_list(app: &tauri::AppHandle, key: &str) -> Vec<String> {
+  load(app)
+    .get(key)
+    .and_then(|x| x.as_array())
+    .map(|a| a.iter().filter_map(|v| v.as_str()).map(str::to_string).collect())
+    .unwrap_or_default()
+}
+
+fn get_bool(app: &tauri::AppHandle, key: &str) -> Option<bool> {
+  load(app).get(key).and_then(|x| x.as_bool())
+}
+
+pub fn api_base_url(app: &tauri::AppHandle) -> Result<String, String> {
+  get_str(app, "apiBaseUrl").ok_or_else(|| "API_BASE_URL_NOT_SET".to_string())
+}
+
+pub fn default_target_lang(app: &tauri::AppHandle) -> String {
+  get_str(app, "defaultLanguage").unwrap_or_else(|| DEFAULT_TARGET_LANG.to_string())
+}
+
+/// Language explanations are written in (`explanationLanguage`, default "ja").
+pub fn explanation_lang(app: &tauri::AppHandle) -> String {
+  get_str(app, "explanationLanguage").unwrap_or_else(|| DEFAULT_EXPLANATION_LANG.to_string())
+}
+
+/// Optional path to the offline MT engine (`translateLocally`).
+pub fn offline_engine_path(app: &tauri::AppHandle) -> Option<String> {
+  get_str(app, "offlineEnginePath")
+}
+
+/// Maximum characters per translation request before the text is chunked.
+pub fn max_chunk_chars(app: &tauri::AppHandle) -> usize {
+  get_u64(app, "maxChunkChars")
+    .map(|n| n as usize)
+    .filter(|n| *n >= 200)
+    .unwrap_or(crate::chunking::DEFAULT_MAX_CHUNK_CHARS)
+}
+
+/// How many chunks may be translated at once (1 = sequential, streamed live).
+pub fn chunk_concurrency(app: &tauri::AppHandle) -> usize {
+  get_u64(app, "chunkConcurrency").map(|n| n.clamp(1, 8) as usize).unwrap_or(1)
+}
+
+/// BCP-47 locale for sorting lists (history, glossary); `None` uses the OS locale.
+pub fn collation_locale(app: &tauri::AppHandle) -> Option<String> {
+  get_str(app, "collationLocale")
+}
+
+/// Output controls configured for `mode` (`outputControls.<mode>`).
+pub fn output_controls(app: &tauri::AppHandle, mode: &str) -> crate::output::OutputControls {
+  load(app)
+    .get("outputControls")
+    .and_then(|m| m.get(mode))
+    .and_then(|v| serde_json::from_value(v.clone()).ok())
+    .unwrap_or_default()
+}
+
+/// Whether OCR text is reflowed (line breaks joined) before translation (default on).
+pub fn ocr_reflow(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "ocrReflow").unwrap_or(true)
+}
+
+/// Whether OCR lines are put in reading order (columns, paragraphs) before reflow (default on).
+pub fn ocr_layout(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "ocrLayout").unwrap_or(true)
+}
+
+/// OCR confidence (0-100) below which recognized text is flagged as unreliable (default 60).
+pub fn ocr_min_confidence(app: &tauri::AppHandle) -> f32 {
+  get_u64(app, "ocrMinConfidence").map(|v| v.min(100) as f32).unwrap_or(60.0)
+}
+
+/// Minutes a temp file (capture, download) is kept before it's purged (default 60).
+pub fn temp_file_ttl_minutes(app: &tauri::AppHandle) -> u64 {
+  get_u64(app, "tempFileTtlMinutes").unwrap_or(60).max(1)
+}
+
+/// Whether URLs, code, placeholders and emoji are masked before translation (default on).
+pub fn protect_placeholders(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "protectPlaceholders").unwrap_or(true)
+}
+
+/// Start as a background agent: tray icon and hotkeys only, windows created on demand.
+pub fn background_agent(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "backgroundAgent").unwrap_or(false)
+}
+
+/// Accelerator bound in hotkey setting `key` (e.g. `ocrHotkey`), if set.
+pub fn hotkey(app: &tauri::AppHandle, key: &str) -> Option<String> {
+  get_str(app, key)
+}
+
+/// How hotkeys are caught (`hotkeyBackend`): "register" with the OS (default), "auto" (the keyboard hook for
+/// those the OS refuses) or "hook" (all through the keyboard hook).
+pub fn hotkey_backend(app: &tauri::AppHandle) -> String {
+  get_str(app, "hotkeyBackend").unwrap_or_else(|| "register".to_string())
+}
+
+/// Whether the hotkeys are suspended while a fullscreen window is in front (`suspendHotkeysInFullscreen`, default
+/// off).
+pub fn suspend_hotkeys_in_fullscreen(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "suspendHotkeysInFullscreen").unwrap_or(false)
+}
+
+/// Executables the hotkeys are suspended for while one of their windows is in front (`hotkeySuspendApps`).
+pub fn hotkey_suspend_apps(app: &tauri::AppHandle) -> Vec<String> {
+  get_str_list(app, "hotkeySuspendApps")
+    .into_iter()
+    .map(|s| s.trim().to_string())
+    .filter(|s| !s.is_empty())
+    .collect()
+}
+
+/// Per-application profiles (`appProfiles`, see `app_profiles`); entries that don't parse are skipped.
+pub fn app_profiles(app: &tauri::AppHandle) -> Vec<crate::app_profiles::AppProfile> {
+  let Some(list) = load(app).get("appProfiles").and_then(|v| v.as_array()).cloned() else {
+    return Vec::new();
+  };
+  list
+    .into_iter()
+    .filter_map(|v| match serde_json::from_value::<crate::app_profiles::AppProfile>(v) {
+      Ok(profile) if !profile.app.trim().is_empty() => Some(profile),
+      Ok(_) => None,
+      Err(e) => {
+        log::warn!("ignoring appProfiles entry: {e}");
+        None
+      }
+    })
+    .collect()
+}
+
+/// Where and how to sync the settings and glossary (`sync`, see `sync`); `None` when unset or malformed.
+pub fn sync_config(app: &tauri::AppHandle) -> Option<crate::sync::SyncConfig> {
+  let value = load(app).get("sync").filter(|v| v.is_object())?.clone();
+  serde_json::from_value(value)
+    .map_err(|e| log::warn!("ignoring sync setting: {e}"))
+    .ok()
+}
+
+/// The cloud TTS service and voice (`tts`, see `tts`); `None` when unset or malformed.
+pub fn tts_config(app: &tauri::AppHandle) -> Option<crate::tts::TtsConfig> {
+  let value = load(app).get("tts").filter(|v| v.is_object())?.clone();
+  serde_json::from_value(value)
+    .map_err(|e| log::warn!("ignoring tts setting: {e}"))
+    .ok()
+}
+
+/// The speech recognizer for voice translation (`speechToText`, see `speech`); `None` when unset or malformed.
+pub fn speech_config(app: &tauri::AppHandle) -> Option<crate::speech::SpeechConfig> {
+  let value = load(app).get("speechToText").filter(|v| v.is_object())?.clone();
+  serde_json::from_value(value)
+    .map_err(|e| log::warn!("ignoring speechToText setting: {e}"))
+    .ok()
+}
+
+/// Whether pressing the copy shortcut twice translates the clipboard (`doubleCopyTrigger`, default off).
+pub fn double_copy_trigger(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "doubleCopyTrigger").unwrap_or(false)
+}
+
+/// Most milliseconds between the two presses of a double copy (`doubleCopyIntervalMs`, default 400).
+pub fn double_copy_interval_ms(app: &tauri::AppHandle) -> u64 {
+  get_u64(app, "doubleCopyIntervalMs").map(|v| v.clamp(150, 1500)).unwrap_or(400)
+}
+
+/// Hotkey actions bound to mouse buttons (`mouseTriggers`, e.g. `{"back": "translate"}`); entries that don't
+/// parse are skipped.
+pub fn mouse_triggers(app: &tauri::AppHandle) -> Vec<(crate::input_hook::MouseButton, crate::hotkeys::Action)> {
+  let Some(map) = load(app).get("mouseTriggers").and_then(|v| v.as_object()).cloned() else {
+    return Vec::new();
+  };
+  map
+    .into_iter()
+    .filter(|(_, v)| !v.is_null() && v.as_str() != Some(""))
+    .filter_map(|(button, action)| {
+      let parsed = serde_json::from_value(serde_json::Value::String(button.clone()))
+        .and_then(|b| serde_json::from_value(action).map(|a| (b, a)));
+      match parsed {
+        Ok(binding) => Some(binding),
+        Err(e) => {
+          log::warn!("ignoring mouseTriggers entry for {button}: {e}");
+          None
+        }
+      }
+    })
+    .collect()
+}
+
+/// How translations replace the selection (`typeBackMethod`): "paste" through the clipboard (default) or "type".
+pub fn type_back_method(app: &tauri::AppHandle) -> String {
+  get_str(app, "typeBackMethod").unwrap_or_else(|| "paste".to_string())
+}
+
+/// Whether finished translations are saved to the history (`saveHistory`, default on).
+pub fn save_history(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "saveHistory").unwrap_or(true)
+}
+
+/// Whether the history database is encrypted at rest (`encryptHistory`, default off; see `history_cipher`).
+pub fn encrypt_history(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "encryptHistory").unwrap_or(false)
+}
+
+/// How much history is kept (`historyRetention`, see `history`); everything when unset or malformed.
+pub fn history_retention(app: &tauri::AppHandle) -> crate::history::HistoryRetention {
+  let Some(value) = load(app).get("historyRetention").filter(|v| v.is_object()).cloned() else {
+    return Default::default();
+  };
+  serde_json::from_value(value)
+    .map_err(|e| log::warn!("ignoring historyRetention setting: {e}"))
+    .unwrap_or_default()
+}
+
+/// Whether the tray icon is shown outside background agent mode too (default on).
+pub fn tray_icon(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "trayIcon").unwrap_or(true)
+}
+
+/// Whether the popup hides itself when it loses focus or the user clicks another of our windows (default on).
+pub fn popup_auto_hide(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "popupAutoHide").unwrap_or(true)
+}
+
+/// Milliseconds the popup waits after losing focus before it hides (default 150, at most 5000).
+pub fn popup_auto_hide_grace_ms(app: &tauri::AppHandle) -> u64 {
+  get_u64(app, "popupAutoHideGraceMs").unwrap_or(150).min(5000)
+}
+
+/// Milliseconds the popup takes to fade in and out (default 120, at most 2000; 0 turns fading off).
+pub fn popup_fade_ms(app: &tauri::AppHandle) -> u64 {
+  get_u64(app, "popupFadeMs").unwrap_or(120).min(2000)
+}
+
+/// Opacity the popup rests at, from `popupOpacity` in percent (default 100, at least 10).
+pub fn popup_opacity(app: &tauri::AppHandle) -> f64 {
+  get_u64(app, "popupOpacity").map(|v| v.clamp(10, 100) as f64 / 100.0).unwrap_or(1.0)
+}
+
+/// HMAC request signing per base URL prefix (`requestSigning`); entries that don't parse are skipped.
+pub fn request_signing(app: &tauri::AppHandle) -> Vec<(String, crate::http::SigningConfig)> {
+  let Some(map) = load(app).get("requestSigning").and_then(|v| v.as_object()).cloned() else {
+    return Vec::new();
+  };
+  map
+    .into_iter()
+    .filter_map(|(prefix, v)| match serde_json::from_value(v) {
+      Ok(config) => Some((prefix, config)),
+      Err(e) => {
+        log::warn!("ignoring requestSigning entry for {prefix}: {e}");
+        None
+      }
+    })
+    .collect()
+}
+
+/// Preferred OCR engine (see `ocr`): "auto", "windows", "vision", "embedded", "external" or "remote".
+pub fn ocr_backend(app: &tauri::AppHandle) -> String {
+  get_str(app, "ocrBackend").unwrap_or_else(|| "auto".to_string())
+}
+
+/// Engine to run next to the selected one and merge results with (`ocrMergeEngine`, e.g. "external"); off when
+/// unset or the same engine.
+pub fn ocr_merge_engine(app: &tauri::AppHandle) -> Option<String> {
+  get_str(app, "ocrMergeEngine").filter(|s| s != "off")
+}
+
+/// Milliseconds between live OCR captures (`liveOcrIntervalMs`), if set.
+pub fn live_ocr_interval_ms(app: &tauri::AppHandle) -> Option<u64> {
+  get_u64(app, "liveOcrIntervalMs")
+}
+
+/// How much a live OCR frame must differ from the last one to be OCR'd, as a share of the brightness range
+/// (`liveOcrChangeThreshold`, percent; default 2).
+pub fn live_ocr_change_threshold(app: &tauri::AppHandle) -> f32 {
+  get_u64(app, "liveOcrChangeThreshold").map(|v| v.min(100) as f32).unwrap_or(2.0) / 100.0
+}
+
+/// Wheel notches per step of a scrolling capture (`scrollCaptureNotches`, default 3).
+pub fn scroll_capture_notches(app: &tauri::AppHandle) -> u32 {
+  get_u64(app, "scrollCaptureNotches").map(|v| v.clamp(1, 20) as u32).unwrap_or(3)
+}
+
+/// Milliseconds to let the window repaint after each scroll (`scrollCaptureDelayMs`, default 250).
+pub fn scroll_capture_delay_ms(app: &tauri::AppHandle) -> u64 {
+  get_u64(app, "scrollCaptureDelayMs").unwrap_or(250)
+}
+
+/// Most screens a scrolling capture stitches together (`scrollCaptureMaxFrames`, default 20).
+pub fn scroll_capture_max_frames(app: &tauri::AppHandle) -> u32 {
+  get_u64(app, "scrollCaptureMaxFrames").map(|v| v.clamp(1, 200) as u32).unwrap_or(20)
+}
+
+/// Whether the popup and OCR overlay are hidden from screen captures (`excludeOwnWindowsFromCapture`, default on).
+pub fn exclude_own_windows_from_capture(app: &tauri::AppHandle) -> bool {
+  get_bool(app, "excludeOwnWindowsFromCapture").unwrap_or(true)
+}
+
+/// HDR tone mapping of captures (`captureToneMap`): `Some(true)` for "on", `Some(false)` for "off", `None` for
+/// "auto" (the default: on where an HDR display is detected).
+pub fn capture_tone_map(app: &tauri::AppHandle) -> Option<bool> {
+  match get_str(app, "captureToneMap").as_deref() {
+    Some("on") => Some(true),
+    Some("off") => Some(false),
+    _ => None,
+  }
+}
+
+/// How user-facing captures are saved (see `capture_output`): `captureMaxDimension` (longest side, 0 or unset for
+/// no limit), `captureFormat` ("png", the default, "jpeg" or "webp") and `captureJpegQuality` (default 90).
+pub fn capture_output(app: &tauri::AppHandle) -> crate::capture_output::CaptureOutput {
+  let defaults = crate::capture_output::CaptureOutput::default();
+  crate::capture_output::CaptureOutput {
+    max_dimension: get_u64(app, "captureMaxDimension").filter(|&n| n > 0).map(|n| n.min(u32::MAX as u64) as u32),
+    format: get_str(app, "captureFormat")
+      .and_then(|f| crate::capture_output::ImageFormat::parse(&f).ok())
+      .unwrap_or(defaults.format),
+    quality: get_u64(app, "captureJpegQuality").map_or(defaults.quality, |q| q.clamp(1, 100) as u8),
+  }
+}
+
+/// Endpoint of a local PaddleOCR / EasyOCR server (`remoteOcrUrl`, see `remote_ocr`).
+pub fn remote_ocr_url(app: &tauri::AppHandle) -> Option<String> {
+  get_str(app, "remoteOcrUrl")
+}
+
+/// Tesseract codes of the languages the OCR server reads (`remoteOcrLangs`); empty means any.
+pub fn remote_ocr_langs(app: &tauri::AppHandle) -> Vec<String> {
+  get_str_list(app, "remoteOcrLangs")
+}
+
+/// Terms Tesseract should recognize as they are (`ocrUserWords`), besides the glossary's source terms.
+pub fn ocr_user_words(app: &tauri::AppHandle) -> Vec<String> {
+  get_str_list(app, "ocrUserWords")
+}
+
+/// Tesseract user patterns (`ocrUserPatterns`) for terms a word list can't enumerate.
+pub fn ocr_user_patterns(app: &tauri::AppHandle) -> Vec<String> {
+  get_str_list(app, "ocrUserPatterns")
+}
+
+/// Tesseract language data `download_tessdata` fetches: "fast" (default) or "best".
+pub fn tessdata_variant(app: &tauri::AppHandle) -> String {
+  get_str(app, "tessdataVariant").unwrap_or_else(|| "fast".to_string())
+}
+
+/// The settings before and after a save.
+pub struct ConfigChange {
+  pub old: serde_json::Value,
+  pub new: serde_json::Value,
+}
+
+impl ConfigChange {
+  /// Top-level keys whose value differs.
+  pub fn changed_keys(&self) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let old = self.old.as_object().unwrap_or(&empty);
+    let new = self.new.as_object().unwrap_or(&empty);
+    let mut keys: Vec<String> = old
+      .keys()
+      .chain(new.keys())
+      .filter(|k| old.get(*k) != new.get(*k))
+      .cloned()
+      .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+  }
+}
+
+type ReloadFn = dyn Fn(&tauri::AppHandle, &ConfigChange) -> Result<(), String> + Send + Sync;
+
+struct Subscriber {
+  subsystem: String,
+  /// Top-level setting keys this subsystem depends on.
+  keys: Vec<&'static str>,
+  reload: Arc<ReloadFn>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ReloadStatus {
+  pub subsystem: String,
+  pub ok: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  /// The changed keys that triggered this reload.
+  pub keys: Vec<String>,
+  pub at: u128,
+}
+
+/// Saves arrive per keystroke while the user edits a field; reload once they settle.
+const RELOAD_DEBOUNCE_MS: u64 = 500;
+
+/// Config subscribers and the last seen settings (managed state).
+#[derive(Default)]
+pub struct ConfigWatchers {
+  /// Bumped per save; a pending reload only runs if no newer save came in.
+  generation: AtomicU64,
+  subscribers: Mutex<Vec<Subscriber>>,
+  last: Mutex<serde_json::Value>,
+  status: Mutex<Vec<ReloadStatus>>,
+}
+
+/// Call `reload` whenever one of `keys` changes.
+pub fn subscribe<F>(app: &tauri::AppHandle, subsystem: &str, keys: &[&'static str], reload: F)
+where
+  F: Fn(&tauri::AppHandle, &ConfigChange) -> Result<(), String> + Send + Sync + 'static,
+{
+  let watchers = app.state::<ConfigWatchers>();
+  let mut subs = watchers.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+  subs.retain(|s| s.subsystem != subsystem);
+  subs.push(Subscriber {
+    subsystem: subsystem.to_string(),
+    keys: keys.to_vec(),
+    reload: Arc::new(reload),
+  });
+}
+
+/// Snapshot the current settings and start watching the store for saves (`setup`).
+pub fn watch(app: &tauri::AppHandle) {
+  *app.state::<ConfigWatchers>().last.lock().unwrap_or_else(|e| e.into_inner()) = load(app);
+  let handle = app.clone();
+  app.listen_any("store://change", move |event| {
+    let Ok(v) = serde_json::from_str::<serde_json::Value>(event.payload()) else {
+      return;
+    };
+    let is_settings = v.get("key").and_then(|k| k.as_str()) == Some(SETTINGS_KEY)
+      && v
+        .get("path")
+        .and_then(|p| p.as_str())
+        .is_some_and(|p| p.ends_with(STORE_FILE));
+    if !is_settings {
+      return;
+    }
+    // The store emits while holding its lock, so reading settings here would deadlock; reload later.
+    let generation = handle.state::<ConfigWatchers>().generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let app = handle.clone();
+    tauri::async_runtime::spawn(async move {
+      tokio::time::sleep(std::time::Duration::from_millis(RELOAD_DEBOUNCE_MS)).await;
+      if app.state::<ConfigWatchers>().generation.load(Ordering::SeqCst) == generation {
+        broadcast(&app);
+      }
+    });
+  });
+}
+
+/// Compare the stored settings with the last snapshot and reload the affected subsystems.
+fn broadcast(app: &tauri::AppHandle) -> Vec<ReloadStatus> {
+  let watchers = app.state::<ConfigWatchers>();
+  let new = load(app);
+  let old = std::mem::replace(&mut *watchers.last.lock().unwrap_or_else(|e| e.into_inner()), new.clone());
+  let change = ConfigChange { old, new };
+  let changed = change.changed_keys();
+  if changed.is_empty() {
+    return Vec::new();
+  }
+  crate::sync::record_setting_changes(app, &change.new, &changed);
+
+  // Run reloads outside the lock so a subscriber may (re)subscribe.
+  let affected: Vec<(String, Vec<String>, Arc<ReloadFn>)> = watchers
+    .subscribers
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .iter()
+    .filter_map(|s| {
+      let keys: Vec<String> = changed.iter().filter(|k| s.keys.contains(&k.as_str())).cloned().collect();
+      (!keys.is_empty()).then(|| (s.subsystem.clone(), keys, s.reload.clone()))
+    })
+    .collect();
+  let results: Vec<ReloadStatus> = affected
+    .into_iter()
+    .map(|(subsystem, keys, reload)| {
+      let result = reload(app, &change);
+      if let Err(e) = &result {
+        events::warn(app, None, "config_reload_failed", format!("{subsystem}: {e}"));
+      }
+      ReloadStatus {
+        subsystem,
+        ok: result.is_ok(),
+        error: result.err(),
+        keys,
+        at: clock::now_millis(),
+      }
+    })
+    .collect();
+
+  events::publish(
+    app,
+    "config.reloaded",
+    None,
+    serde_json::json!({ "changed": changed, "subsystems": results }),
+  );
+  let mut status = watchers.status.lock().unwrap_or_else(|e| e.into_inner());
+  for r in &results {
+    status.retain(|s| s.subsystem != r.subsystem);
+    status.push(r.clone());
+  }
+  results
+}
+
+/// Latest reload outcome per subsystem.
+#[tauri::command]
+pub fn get_reload_status(watchers: tauri::State<'_, ConfigWatchers>) -> Vec<ReloadStatus> {
+  watchers.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Re-read the settings and reload what changed (e.g. after `settings.json` was edited by hand).
+#[tauri::command]
+pub fn reload_config(app: tauri::AppHandle) -> Vec<ReloadStatus> {
+  if let Ok(store) = app.store(STORE_FILE) {
+    if let Err(e) = store.reload() {
+      log::warn!("failed to reload {STORE_FILE}: {e}");
+    }
+  }
+  broadcast(&app)
+}
@@ -0,0 +1,529 @@
+//! The settings schema: a typed, versioned `Settings` the backend owns, so a malformed or stale value is caught
+//! where it's saved instead of silently breaking the subsystem that reads it.
+//!
+//! The frontend loads with `get_settings` and saves with `set_settings`, which migrates, checks every value's type
+//! and meaning (hotkeys parse and don't collide, the API base URL is a URL, numbers are in range, ...) and refuses
+//! the whole save when something is wrong. At startup the stored settings are migrated to `SCHEMA_VERSION` and
+//! values that don't pass are reset to their defaults, with a `settings_invalid` warning. Keys without a typed
+//! field are kept as they are. `settings` still reads single values from the store.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{events, hotkeys, settings};
+
+/// Version written as `schemaVersion`; see `migrate`.
+pub const SCHEMA_VERSION: u64 = 1;
+
+const DEFAULT_HOTKEY: &str = "CommandOrControl+Shift+Alt+Z";
+const DEFAULT_OCR_HOTKEY: &str = "CommandOrControl+Shift+Alt+X";
+/// Hotkey older versions fell back to and saved when the default couldn't be registered.
+const FALLBACK_HOTKEY: &str = "CommandOrControl+Shift+Alt+Q";
+const DEFAULT_API_BASE_URL: &str = "https://lighting-translation.vercel.app";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+  pub schema_version: u64,
+
+  // Hotkeys and triggers
+  pub hotkey: String,
+  pub ocr_hotkey: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub repeat_region_hotkey: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub click_through_hotkey: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub toggle_popup_hotkey: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub clipboard_hotkey: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub hotkey_backend: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub suspend_hotkeys_in_fullscreen: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub hotkey_suspend_apps: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub app_profiles: Option<Vec<crate::app_profiles::AppProfile>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub double_copy_trigger: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub double_copy_interval_ms: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mouse_triggers: Option<Map<String, Value>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub auto_paste: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub type_back_method: Option<String>,
+
+  // Translation
+  pub clipboard_mode: String,
+  pub api_base_url: String,
+  pub default_language: String,
+  pub secondary_language: String,
+  pub routing_strategy: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub last_used_target_lang: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub explanation_language: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub offline_engine_path: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub max_chunk_chars: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub chunk_concurrency: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub protect_placeholders: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub output_controls: Option<Map<String, Value>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub request_signing: Option<Map<String, Value>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub save_history: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub history_retention: Option<Map<String, Value>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub encrypt_history: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub sync: Option<Map<String, Value>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tts: Option<Map<String, Value>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub speech_to_text: Option<Map<String, Value>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub collation_locale: Option<String>,
+
+  // Popup and app
+  pub popup_focus_on_open: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub popup_auto_hide: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub popup_auto_hide_grace_ms: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub popup_fade_ms: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub popup_opacity: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub onboarded: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub background_agent: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tray_icon: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub temp_file_ttl_minutes: Option<u64>,
+
+  // OCR and capture
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_lang: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_backend: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_merge_engine: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_reflow: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_layout: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_min_confidence: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_user_words: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ocr_user_patterns: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub remote_ocr_url: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub remote_ocr_langs: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tesseract_path: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tessdata_prefix: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tessdata_variant: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub live_ocr_interval_ms: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub live_ocr_change_threshold: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub scroll_capture_notches: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub scroll_capture_delay_ms: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub scroll_capture_max_frames: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub exclude_own_windows_from_capture: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub capture_tone_map: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub capture_max_dimension: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub capture_format: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub capture_jpeg_quality: Option<u64>,
+
+  /// Settings without a typed field (frontend-only options, newer keys), kept as they are.
+  #[serde(flatten)]
+  pub other: Map<String, Value>,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Settings {
+      schema_version: SCHEMA_VERSION,
+      hotkey: DEFAULT_HOTKEY.to_string(),
+      ocr_hotkey: DEFAULT_OCR_HOTKEY.to_string(),
+      repeat_region_hotkey: None,
+      click_through_hotkey: None,
+      toggle_popup_hotkey: None,
+      clipboard_hotkey: None,
+      hotkey_backend: None,
+      suspend_hotkeys_in_fullscreen: None,
+      hotkey_suspend_apps: None,
+      app_profiles: None,
+      double_copy_trigger: None,
+      double_copy_interval_ms: None,
+      mouse_triggers: None,
+      auto_paste: None,
+      type_back_method: None,
+      clipboard_mode: "displayOnly".to_string(),
+      api_base_url: DEFAULT_API_BASE_URL.to_string(),
+      default_language: "Japanese".to_string(),
+      secondary_language: "English (US)".to_string(),
+      routing_strategy: "alwaysFixed".to_string(),
+      last_used_target_lang: None,
+      explanation_language: None,
+      offline_engine_path: None,
+      max_chunk_chars: None,
+      chunk_concurrency: None,
+      protect_placeholders: None,
+      output_controls: None,
+      request_signing: None,
+      save_history: None,
+      history_retention: None,
+      encrypt_history: None,
+      sync: None,
+      tts: None,
+      speech_to_text: None,
+      collation_locale: None,
+      popup_focus_on_open: true,
+      popup_auto_hide: None,
+      popup_auto_hide_grace_ms: None,
+      popup_fade_ms: None,
+      popup_opacity: None,
+      onboarded: None,
+      background_agent: None,
+      tray_icon: None,
+      temp_file_ttl_minutes: None,
+      ocr_lang: None,
+      ocr_backend: None,
+      ocr_merge_engine: None,
+      ocr_reflow: None,
+      ocr_layout: None,
+      ocr_min_confidence: None,
+      ocr_user_words: None,
+      ocr_user_patterns: None,
+      remote_ocr_url: None,
+      remote_ocr_langs: None,
+      tesseract_path: None,
+      tessdata_prefix: None,
+      tessdata_variant: None,
+      live_ocr_interval_ms: None,
+      live_ocr_change_threshold: None,
+      scroll_capture_notches: None,
+      scroll_capture_delay_ms: None,
+      scroll_capture_max_frames: None,
+      exclude_own_windows_from_capture: None,
+      capture_tone_map: None,
+      capture_max_dimension: None,
+      capture_format: None,
+      capture_jpeg_quality: None,
+      other: Map::new(),
+    }
+  }
+}
+
+/// A value that doesn't pass, by setting key.
+#[derive(Debug, Serialize, Clone)]
+pub struct SettingsIssue {
+  pub key: String,
+  pub message: String,
+}
+
+fn issue(key: &str, message: impl Into<String>) -> SettingsIssue {
+  SettingsIssue {
+    key: key.to_string(),
+    message: message.into(),
+  }
+}
+
+/// Values a choice setting accepts.
+const CHOICES: &[(&str, &[&str])] = &[
+  ("clipboardMode", &["displayOnly", "displayAndCopy", "copyOnly"]),
+  ("routingStrategy", &["defaultBased", "alwaysLastUsed", "alwaysFixed"]),
+  ("hotkeyBackend", &["register", "auto", "hook"]),
+  ("typeBackMethod", &["paste", "type"]),
+  ("ocrBackend", &["auto", "windows", "vision", "embedded", "external", "remote"]),
+  ("ocrMergeEngine", &["off", "windows", "vision", "embedded", "external", "remote"]),
+  ("tessdataVariant", &["fast", "best"]),
+  ("captureToneMap", &["auto", "on", "off"]),
+  ("captureFormat", &["png", "jpeg", "jpg", "webp"]),
+];
+
+/// Inclusive ranges of number settings.
+const RANGES: &[(&str, u64, u64)] = &[
+  ("doubleCopyIntervalMs", 150, 1500),
+  ("maxChunkChars", 200, u64::MAX),
+  ("chunkConcurrency", 1, 8),
+  ("popupAutoHideGraceMs", 0, 5000),
+  ("popupFadeMs", 0, 2000),
+  ("popupOpacity", 10, 100),
+  ("tempFileTtlMinutes", 1, u64::MAX),
+  ("ocrMinConfidence", 0, 100),
+  ("liveOcrChangeThreshold", 0, 100),
+  ("scrollCaptureNotches", 1, 20),
+  ("scrollCaptureMaxFrames", 1, 200),
+  ("captureJpegQuality", 1, 100),
+  ("alternatives", 0, crate::translate::MAX_ALTERNATIVES as u64),
+];
+
+impl Settings {
+  /// Problems with the values' meaning (types are checked when deserializing).
+  pub fn validate(&self) -> Vec<SettingsIssue> {
+    let value = serde_json::to_value(self).unwrap_or_default();
+    let mut issues = Vec::new();
+
+    let mut taken: Vec<(tauri_plugin_global_shortcut::Shortcut, &str)> = Vec::new();
+    for action in hotkeys::Action::ALL {
+      let key = action.setting();
+      let Some(accelerator) = value.get(key).and_then(|v| v.as_str()).filter(|s| !s.trim().is_empty()) else {
+        continue;
+      };
+      match accelerator.parse::<tauri_plugin_global_shortcut::Shortcut>() {
+        Err(e) => issues.push(issue(key, format!("{accelerator} is not a valid hotkey: {e}"))),
+        Ok(shortcut) => match taken.iter().find(|(t, _)| *t == shortcut) {
+          Some((_, other)) => issues.push(issue(key, format!("{accelerator} is already used by {other}"))),
+          None => taken.push((shortcut, key)),
+        },
+      }
+    }
+
+    for (key, choices) in CHOICES {
+      if let Some(v) = value.get(*key).and_then(|v| v.as_str()) {
+        if !choices.contains(&v) {
+          issues.push(issue(key, format!("{v} is not one of {}", choices.join(", "))));
+        }
+      }
+    }
+    for &(key, min, max) in RANGES {
+      if let Some(n) = value.get(key).and_then(|v| v.as_u64()) {
+        if n < min || n > max {
+          let range = if max == u64::MAX { format!("at least {min}") } else { format!("{min} to {max}") };
+          issues.push(issue(key, format!("{n} is out of range ({range})")));
+        }
+      }
+    }
+
+    let base = self.api_base_url.trim();
+    let is_provider = crate::plugins::provider_id(base).is_some() || crate::offline_mt::is_offline(base);
+    if base.is_empty() {
+      issues.push(issue("apiBaseUrl", "is empty"));
+    } else if !is_provider && !matches!(reqwest::Url::parse(base), Ok(u) if matches!(u.scheme(), "http" | "https")) {
+      issues.push(issue("apiBaseUrl", format!("{base} is not an http(s) URL")));
+    }
+    if let Some(url) = self.remote_ocr_url.as_deref().filter(|s| !s.trim().is_empty()) {
+      if reqwest::Url::parse(url).is_err() {
+        issues.push(issue("remoteOcrUrl", format!("{url} is not a URL")));
+      }
+    }
+    for (key, lang) in [
+      ("defaultLanguage", &self.default_language),
+      ("secondaryLanguage", &self.secondary_language),
+    ]
+    .into_iter()
+    .chain(self.explanation_language.as_ref().map(|lang| ("explanationLanguage", lang)))
+    {
+      if lang.trim().is_empty() {
+        issues.push(issue(key, "is empty"));
+      }
+    }
+
+    for (button, action) in self.mouse_triggers.iter().flatten() {
+      if action.is_null() || action.as_str() == Some("") {
+        continue;
+      }
+      let parsed = serde_json::from_value::<crate::input_hook::MouseButton>(Value::String(button.clone()))
+        .and_then(|_| serde_json::from_value::<hotkeys::Action>(action.clone()));
+      if let Err(e) = parsed {
+        issues.push(issue("mouseTriggers", format!("{button}: {e}")));
+      }
+    }
+    if let Some(retention) = &self.history_retention {
+      match serde_json::from_value::<crate::history::HistoryRetention>(Value::Object(retention.clone())) {
+        Err(e) => issues.push(issue("historyRetention", e.to_string())),
+        Ok(crate::history::HistoryRetention::Days { days: 0 }) => {
+          issues.push(issue("historyRetention", "days must be at least 1"))
+        }
+        Ok(_) => {}
+      }
+    }
+    if let Some(sync) = &self.sync {
+      if let Err(e) = serde_json::from_value::<crate::sync::SyncConfig>(Value::Object(sync.clone())) {
+        issues.push(issue("sync", e.to_string()));
+      }
+    }
+    if let Some(tts) = &self.tts {
+      match serde_json::from_value::<crate::tts::TtsConfig>(Value::Object(tts.clone())) {
+        Err(e) => issues.push(issue("tts", e.to_string())),
+        Ok(config) => {
+          if let Err(e) = config.check() {
+            issues.push(issue("tts", e));
+          }
+        }
+      }
+    }
+    if let Some(speech) = &self.speech_to_text {
+      match serde_json::from_value::<crate::speech::SpeechConfig>(Value::Object(speech.clone())) {
+        Err(e) => issues.push(issue("speechToText", e.to_string())),
+        Ok(config) => {
+          if let Err(e) = config.check() {
+            issues.push(issue("speechToText", e));
+          }
+        }
+      }
+    }
+    for (i, profile) in self.app_profiles.iter().flatten().enumerate() {
+      if profile.app.trim().is_empty() {
+        issues.push(issue("appProfiles", format!("profile {}: app is empty", i + 1)));
+      }
+    }
+    for key in self.other.keys().filter(|k| crate::secrets::is_plaintext_key(k)) {
+      issues.push(issue(key, "API keys belong in the keychain (set_secret), not the settings"));
+    }
+    for (prefix, config) in self.request_signing.iter().flatten() {
+      if let Err(e) = serde_json::from_value::<crate::http::SigningConfig>(config.clone()) {
+        issues.push(issue("requestSigning", format!("{prefix}: {e}")));
+      }
+    }
+    issues
+  }
+}
+
+/// Language names older versions saved (the language picker's labels) and their codes.
+const OLD_LANGUAGE_NAMES: &[(&str, &str)] = &[
+  ("日本語", "Japanese"),
+  ("英語（アメリカ）", "English (US)"),
+  ("英語（イギリス）", "English (UK)"),
+  ("繁体字中国語", "Chinese (Traditional)"),
+  ("簡体字中国語", "Chinese (Simplified)"),
+  ("韓国語", "Korean"),
+  ("インドネシア語", "Indonesian"),
+  ("English", "English (US)"),
+  ("english", "English (US)"),
+];
+
+/// Bring settings saved by an older version up to `SCHEMA_VERSION`.
+fn migrate(settings: &mut Map<String, Value>) {
+  let version = settings.get("schemaVersion").and_then(|v| v.as_u64()).unwrap_or(0);
+  if version < 1 {
+    for key in ["defaultLanguage", "secondaryLanguage", "lastUsedTargetLang"] {
+      let old = settings.get(key).and_then(|v| v.as_str()).map(str::trim);
+      if let Some((_, code)) = old.and_then(|old| OLD_LANGUAGE_NAMES.iter().find(|(name, _)| *name == old)) {
+        settings.insert(key.to_string(), Value::String(code.to_string()));
+      }
+    }
+    if settings.get("hotkey").and_then(|v| v.as_str()) == Some(FALLBACK_HOTKEY) {
+      settings.remove("hotkey");
+    }
+  }
+  settings.insert("schemaVersion".to_string(), SCHEMA_VERSION.into());
+}
+
+/// Migrate `raw` and type-check every key. Keys whose value has the wrong type are dropped and reported.
+fn parse(raw: Value) -> (Map<String, Value>, Vec<SettingsIssue>) {
+  let mut map = match raw {
+    Value::Object(map) => map,
+    _ => Map::new(),
+  };
+  migrate(&mut map);
+  let mut issues = Vec::new();
+  map.retain(|key, value| {
+    let single = Value::Object(Map::from_iter([(key.clone(), value.clone())]));
+    match serde_json::from_value::<Settings>(single) {
+      Ok(_) => true,
+      Err(e) => {
+        issues.push(issue(key, e.to_string()));
+        false
+      }
+    }
+  });
+  (map, issues)
+}
+
+/// `raw` as valid settings: migrated, with every value that doesn't pass reset to its default.
+fn sanitize(raw: Value) -> (Settings, Vec<SettingsIssue>) {
+  let (mut map, mut issues) = parse(raw);
+  let settings: Settings = serde_json::from_value(Value::Object(map.clone())).unwrap_or_default();
+  let invalid = settings.validate();
+  if invalid.is_empty() {
+    return (settings, issues);
+  }
+  for i in &invalid {
+    map.remove(&i.key);
+  }
+  issues.extend(invalid);
+  (serde_json::from_value(Value::Object(map)).unwrap_or_default(), issues)
+}
+
+/// `raw` migrated, with values that don't pass reset to their defaults (settings from a backup or another machine).
+pub(crate) fn sanitized(raw: Value) -> Value {
+  serde_json::to_value(sanitize(raw).0).unwrap_or_default()
+}
+
+fn describe(issues: &[SettingsIssue]) -> String {
+  issues
+    .iter()
+    .map(|i| format!("{}: {}", i.key, i.message))
+    .collect::<Vec<_>>()
+    .join("; ")
+}
+
+/// Migrate the stored settings and reset values that don't pass (`setup`, before anything reads them).
+pub fn init(app: &tauri::AppHandle) {
+  let Some(raw) = settings::stored(app) else {
+    return;
+  };
+  let (settings, issues) = sanitize(raw.clone());
+  if !issues.is_empty() {
+    events::warn(
+      app,
+      None,
+      "settings_invalid",
+      format!("reset settings that didn't pass: {}", describe(&issues)),
+    );
+  }
+  let value = serde_json::to_value(&settings).unwrap_or_default();
+  if value != raw {
+    if let Err(e) = settings::save(app, value) {
+      log::warn!("failed to save migrated settings: {e}");
+    }
+  }
+}
+
+/// The settings, migrated and with defaults filled in; `None` until they've been saved once (first run).
+#[tauri::command]
+pub fn get_settings(app: tauri::AppHandle) -> Option<Settings> {
+  settings::stored(&app).map(|raw| sanitize(raw).0)
+}
+
+/// Validate and save `settings`; nothing is saved if a value doesn't pass (`INVALID_SETTINGS: key: problem; ...`).
+/// Returns the settings as saved.
+#[tauri::command]
+pub fn set_settings(app: tauri::AppHandle, settings: Value) -> Result<Settings, String> {
+  let (map, mut issues) = parse(settings);
+  let parsed: Settings = serde_json::from_value(Value::Object(map)).map_err(|e| format!("INVALID_SETTINGS: {e}"))?;
+  issues.extend(parsed.validate());
+  if !issues.is_empty() {
+    return Err(format!("INVALID_SETTINGS: {}", describe(&issues)));
+  }
+  settings::save(&app, serde_json::to_value(&parsed).map_err(|e| format!("failed to serialize settings: {e}"))?)?;
+  Ok(parsed)
+}
@@ -0,0 +1,140 @@
+//! Freeze-frame capture: grab every monitor once, then crop regions out of that image.
+//!
+//! The OCR overlay shows the snapshot while the user draws a selection, so videos, animations and tooltips that
+//! vanish on focus change hold still. Snapshots are temp PNGs kept by id; the oldest are dropped once more than
+//! `KEEP` exist, so an overlay that is cancelled doesn't leak.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::clock;
+use crate::commands::CaptureRect;
+
+const KEEP: usize = 4;
+
+struct Snapshot {
+  id: String,
+  path: std::path::PathBuf,
+  rect: CaptureRect,
+}
+
+#[derive(Default)]
+pub struct Snapshots(Mutex<VecDeque<Snapshot>>);
+
+impl Snapshots {
+  fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Snapshot>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn get(&self, id: &str) -> Result<(std::path::PathBuf, CaptureRect), String> {
+    self
+      .lock()
+      .iter()
+      .find(|s| s.id == id)
+      .map(|s| (s.path.clone(), s.rect.clone()))
+      .ok_or_else(|| format!("unknown snapshot: {id}"))
+  }
+
+  /// Physical bounds of the desktop snapshot `id` covers.
+  pub fn bounds(&self, id: &str) -> Result<CaptureRect, String> {
+    self.get(id).map(|(_, rect)| rect)
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SnapshotInfo {
+  pub id: String,
+  /// Physical bounds of the desktop the image covers; crops are in the same coordinates.
+  pub rect: CaptureRect,
+}
+
+/// Capture all monitors to one image and keep it for `crop_snapshot`.
+#[tauri::command]
+pub async fn capture_full_screen_snapshot(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Snapshots>,
+) -> Result<SnapshotInfo, String> {
+  let rect = crate::screen::desktop(&app).ok_or_else(|| "no monitors found".to_string())?;
+  let path = {
+    let (app, rect) = (app.clone(), rect.clone());
+    tauri::async_runtime::spawn_blocking(move || crate::commands::capture_region(&app, &rect, false))
+      .await
+      .map_err(|e| format!("capture failed: {e}"))??
+  };
+  let id = clock::next_id("snapshot");
+  let mut snapshots = state.lock();
+  snapshots.push_back(Snapshot { id: id.clone(), path: path.into(), rect: rect.clone() });
+  while snapshots.len() > KEEP {
+    if let Some(old) = snapshots.pop_front() {
+      let _ = std::fs::remove_file(&old.path);
+    }
+  }
+  Ok(SnapshotInfo { id, rect })
+}
+
+/// Crop `rect` (physical desktop pixels) out of snapshot `id` into a temp PNG and return its path.
+pub fn crop(state: &Snapshots, id: &str, rect: &CaptureRect) -> Result<String, String> {
+  let (path, bounds) = state.get(id)?;
+  if rect.width == 0 || rect.height == 0 {
+    return Err("invalid rect".to_string());
+  }
+  let (x0, y0) = (rect.x - bounds.x, rect.y - bounds.y);
+  if x0 < 0 || y0 < 0 || x0 as u32 + rect.width > bounds.width || y0 as u32 + rect.height > bounds.height {
+    return Err("capture region is outside the snapshot".to_string());
+  }
+
+  let image = crate::image_io::read_rgb(&path)?;
+  let (x0, y0) = (x0 as u32, y0 as u32);
+  // The snapshot covers the desktop's bounding box; a capture fixture (deterministic builds) may be smaller.
+  if x0 + rect.width > image.width() || y0 + rect.height > image.height() {
+    return Err("capture region is outside the snapshot".to_string());
+  }
+  let rows = crate::image_io::crop_rgb(&image, x0, y0, rect.width, rect.height);
+
+  let out_path = crate::temp::path("capture", "png");
+  if let Err(e) = crate::image_io::write_png(&out_path, &rows, rect.width, rect.height) {
+    let _ = std::fs::remove_file(&out_path);
+    return Err(e);
+  }
+  Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Crop a region out of snapshot `id` (as `capture_screen_region`: `rect` in physical pixels or `logical`) into a
+/// temp PNG and return its path. The region is added to the history (see `regions`).
+#[tauri::command]
+pub async fn crop_snapshot(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, Snapshots>,
+  id: String,
+  rect: Option<CaptureRect>,
+  logical: Option<crate::screen::LogicalRect>,
+) -> Result<String, String> {
+  let rect = crate::commands::physical_rect(&app, rect, logical)?;
+  let path = crop(&state, &id, &rect)?;
+  crate::regions::record(&app, &rect);
+  Ok(path)
+}
+
+/// The PNG bytes of snapshot `id`, for the overlay to show as its background.
+#[tauri::command]
+pub fn snapshot_image(state: tauri::State<'_, Snapshots>, id: String) -> Result<tauri::ipc::Response, String> {
+  let (path, _) = state.get(&id)?;
+  let bytes = std::fs::read(&path).map_err(|e| format!("cannot read snapshot: {e}"))?;
+  Ok(tauri::ipc::Response::new(bytes))
+}
+
+/// Drop snapshot `id` and its file. Returns whether it existed.
+#[tauri::command]
+pub fn discard_snapshot(state: tauri::State<'_, Snapshots>, id: String) -> bool {
+  let mut snapshots = state.lock();
+  match snapshots.iter().position(|s| s.id == id) {
+    Some(i) => {
+      if let Some(old) = snapshots.remove(i) {
+        let _ = std::fs::remove_file(&old.path);
+      }
+      true
+    }
+    None => false,
+  }
+}
@@ -0,0 +1,518 @@
+//! Voice translation: record from the default microphone, transcribe what was said and translate it.
+//!
+//! `start_voice_translation` records until `stop_voice_translation` (or `MAX_RECORDING`) and reports on one
+//! channel: `recording` once the microphone is open, `interim` transcripts of the last `INTERIM_WINDOW` of audio
+//! every `interimSeconds` while recording (default 3 for whisper.cpp; off for an endpoint, where each one is a
+//! paid request, unless set; 0 for none), the final `transcript`, the translation's stream events
+//! (`translation`), and `done` or `error`. `speechToText` in the settings picks the recognizer:
+//! `{ "engine": "local", "binary": ".../whisper-cli", "model": ".../ggml-base.bin" }` runs whisper.cpp, and
+//! `{ "engine": "remote", "url": "https://api.openai.com/v1/audio/transcriptions", "model": "whisper-1" }` posts to
+//! a Whisper-compatible endpoint, with the `speechApiKey` secret as its bearer token when set. `language` (an app
+//! language name or code) tells the recognizer what is spoken; it guesses otherwise. On macOS the microphone
+//! prompt's text is `NSMicrophoneUsageDescription` in `Info.plist`.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::Sample;
+use serde::{Deserialize, Serialize};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::Manager;
+use tokio::sync::Notify;
+
+use crate::commands::StreamEvent;
+use crate::output::OutputControls;
+use crate::queue::Priority;
+use crate::translate::{self, TranslateRequest};
+use crate::{http, offline_mt, secrets, settings, temp};
+
+/// Secret holding the remote endpoint's API key.
+pub const API_KEY_SECRET: &str = "speechApiKey";
+/// Whisper's input rate.
+const SAMPLE_RATE: u32 = 16_000;
+/// Recordings stop by themselves after this long.
+const MAX_RECORDING: Duration = Duration::from_secs(120);
+const DEFAULT_INTERIM_SECONDS: u64 = 3;
+/// Audio an interim transcription covers: the tail of the recording, so each one takes about as long as the last.
+const INTERIM_WINDOW: Duration = Duration::from_secs(10);
+/// Shorter audio isn't worth an interim transcription.
+const MIN_INTERIM: Duration = Duration::from_millis(800);
+const TRANSCRIBE_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "engine", rename_all = "lowercase")]
+pub enum SpeechEngine {
+  /// whisper.cpp's command-line program and a ggml model file.
+  Local { binary: String, model: String },
+  /// A Whisper-compatible `/audio/transcriptions` endpoint.
+  Remote {
+    url: String,
+    #[serde(default)]
+    model: Option<String>,
+  },
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeechConfig {
+  #[serde(flatten)]
+  pub engine: SpeechEngine,
+  #[serde(default)]
+  pub language: Option<String>,
+  #[serde(default)]
+  pub interim_seconds: Option<u64>,
+}
+
+impl SpeechConfig {
+  /// Problems that would make every transcription fail.
+  pub fn check(&self) -> Result<(), String> {
+    match &self.engine {
+      SpeechEngine::Local { binary, .. } if binary.trim().is_empty() => Err("binary is empty".to_string()),
+      SpeechEngine::Local { model, .. } if model.trim().is_empty() => Err("model is empty".to_string()),
+      SpeechEngine::Remote { url, .. } => match reqwest::Url::parse(url.trim()) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => Ok(()),
+        _ => Err(format!("{url} is not an http(s) URL")),
+      },
+      SpeechEngine::Local { .. } => Ok(()),
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type")]
+pub enum VoiceEvent {
+  /// The microphone is open.
+  #[serde(rename = "recording")]
+  Recording,
+  /// The last `INTERIM_WINDOW` of what was said, transcribed while still recording.
+  #[serde(rename = "interim")]
+  Interim { text: String },
+  /// The whole recording, transcribed.
+  #[serde(rename = "transcript")]
+  Transcript { text: String },
+  /// A `translate_sse` stream event.
+  #[serde(rename = "translation")]
+  Translation { event: StreamEvent },
+  #[serde(rename = "done")]
+  Done,
+  #[serde(rename = "error")]
+  Error { message: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct VoiceResult {
+  pub transcript: String,
+  /// `None` when nothing was said.
+  pub translation: Option<String>,
+}
+
+/// The recording in progress, if any (managed state): what stops it.
+#[derive(Default)]
+pub struct VoiceInput(Mutex<Option<Arc<Notify>>>);
+
+impl VoiceInput {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Option<Arc<Notify>>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Audio from the microphone, mono at the device's rate; the stream lives on its own thread (cpal streams can't
+/// move between threads) until `stop` is dropped.
+struct Recorder {
+  stop: mpsc::Sender<()>,
+  thread: std::thread::JoinHandle<()>,
+  samples: Arc<Mutex<Vec<f32>>>,
+  rate: u32,
+}
+
+impl Recorder {
+  fn start() -> Result<Self, String> {
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<u32, String>>();
+    let (stop, stopped) = mpsc::channel::<()>();
+    let buffer = samples.clone();
+    let thread = std::thread::spawn(move || {
+      let stream = match open_microphone(buffer) {
+        Ok((stream, rate)) => {
+          let _ = ready_tx.send(Ok(rate));
+          stream
+        }
+        Err(e) => {
+          let _ = ready_tx.send(Err(e));
+          return;
+        }
+      };
+      let _ = stopped.recv();
+      drop(stream);
+    });
+    let rate = ready_rx
+      .recv()
+      .map_err(|_| "the microphone couldn't be opened".to_string())??;
+    Ok(Recorder {
+      stop,
+      thread,
+      samples,
+      rate,
+    })
+  }
+
+  fn duration(&self) -> Duration {
+    let len = self.samples.lock().unwrap_or_else(|e| e.into_inner()).len();
+    Duration::from_secs_f64(len as f64 / f64::from(self.rate))
+  }
+
+  /// The last `window` of what has been recorded, at `SAMPLE_RATE`.
+  fn tail(&self, window: Duration) -> Vec<f32> {
+    let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner());
+    let len = (window.as_secs_f64() * f64::from(self.rate)) as usize;
+    let tail = samples[samples.len().saturating_sub(len)..].to_vec();
+    drop(samples);
+    resample(&tail, self.rate)
+  }
+
+  /// Stop recording; returns everything recorded, at `SAMPLE_RATE`.
+  fn finish(self) -> Vec<f32> {
+    drop(self.stop);
+    let _ = self.thread.join();
+    let samples = std::mem::take(&mut *self.samples.lock().unwrap_or_else(|e| e.into_inner()));
+    resample(&samples, self.rate)
+  }
+}
+
+fn open_microphone(samples: Arc<Mutex<Vec<f32>>>) -> Result<(cpal::Stream, u32), String> {
+  let device = cpal::default_host()
+    .default_input_device()
+    .ok_or_else(|| "no microphone found".to_string())?;
+  let supported = device
+    .default_input_config()
+    .map_err(|e| format!("the microphone can't record: {e}"))?;
+  let config = supported.config();
+  let stream = match supported.sample_format() {
+    cpal::SampleFormat::F32 => input_stream::<f32>(&device, &config, samples),
+    cpal::SampleFormat::I16 => input_stream::<i16>(&device, &config, samples),
+    cpal::SampleFormat::U16 => input_stream::<u16>(&device, &config, samples),
+    other => return Err(format!("unsupported microphone sample format: {other:?}")),
+  }
+  .map_err(|e| format!("the microphone can't record: {e}"))?;
+  stream.play().map_err(|e| format!("the microphone can't record: {e}"))?;
+  Ok((stream, config.sample_rate.0))
+}
+
+/// An input stream that appends each frame, mixed down to mono, to `samples`.
+fn input_stream<T>(
+  device: &cpal::Device,
+  config: &cpal::StreamConfig,
+  samples: Arc<Mutex<Vec<f32>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+  T: cpal::SizedSample,
+  f32: cpal::FromSample<T>,
+{
+  let channels = usize::from(config.channels).max(1);
+  device.build_input_stream(
+    config,
+    move |data: &[T], _: &cpal::InputCallbackInfo| {
+      let mut samples = samples.lock().unwrap_or_else(|e| e.into_inner());
+      for frame in data.chunks(channels) {
+        let sum: f32 = frame.iter().map(|s| s.to_sample::<f32>()).sum();
+        samples.push(sum / frame.len() as f32);
+      }
+    },
+    |e| log::warn!("microphone error: {e}"),
+    None,
+  )
+}
+
+/// `samples` at `rate` converted to `SAMPLE_RATE` (linear interpolation; speech doesn't need better).
+fn resample(samples: &[f32], rate: u32) -> Vec<f32> {
+  if rate == SAMPLE_RATE || samples.is_empty() {
+    return samples.to_vec();
+  }
+  let step = f64::from(rate) / f64::from(SAMPLE_RATE);
+  let len = (samples.len() as f64 / step) as usize;
+  (0..len)
+    .map(|i| {
+      let pos = i as f64 * step;
+      let at = pos as usize;
+      let next = samples.get(at + 1).copied().unwrap_or(samples[at]);
+      let frac = (pos - at as f64) as f32;
+      samples[at] + (next - samples[at]) * frac
+    })
+    .collect()
+}
+
+/// 16-bit mono PCM WAV of `samples` at `SAMPLE_RATE`.
+fn wav(samples: &[f32]) -> Vec<u8> {
+  let data_len = (samples.len() * 2) as u32;
+  let mut out = Vec::with_capacity(44 + data_len as usize);
+  out.extend_from_slice(b"RIFF");
+  out.extend_from_slice(&(36 + data_len).to_le_bytes());
+  out.extend_from_slice(b"WAVEfmt ");
+  out.extend_from_slice(&16u32.to_le_bytes());
+  out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+  out.extend_from_slice(&1u16.to_le_bytes()); // mono
+  out.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+  out.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+  out.extend_from_slice(&2u16.to_le_bytes());
+  out.extend_from_slice(&16u16.to_le_bytes());
+  out.extend_from_slice(b"data");
+  out.extend_from_slice(&data_len.to_le_bytes());
+  for s in samples {
+    out.extend_from_slice(&((s.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16).to_le_bytes());
+  }
+  out
+}
+
+/// Transcribe `samples` (at `SAMPLE_RATE`) with the configured recognizer.
+async fn transcribe(config: &SpeechConfig, samples: &[f32]) -> Result<String, String> {
+  let audio = wav(samples);
+  let language = config.language.as_deref().and_then(offline_mt::lang_code);
+  let text = match &config.engine {
+    SpeechEngine::Local { binary, model } => {
+      let path = temp::path("speech", "wav");
+      std::fs::write(&path, audio).map_err(|e| format!("cannot write the recording: {e}"))?;
+      let output = tokio::time::timeout(
+        TRANSCRIBE_TIMEOUT,
+        tokio::process::Command::new(binary.trim())
+          .arg("-m")
+          .arg(model.trim())
+          .arg("-f")
+          .arg(&path)
+          .args(["-nt", "-np", "-l", language.unwrap_or("auto")])
+          .kill_on_drop(true)
+          .output(),
+      )
+      .await;
+      let _ = std::fs::remove_file(&path);
+      let output = output
+        .map_err(|_| "whisper.cpp timed out".to_string())?
+        .map_err(|e| format!("failed to run whisper.cpp: {e}"))?;
+      if !output.status.success() {
+        return Err(format!(
+          "whisper.cpp failed: {}",
+          String::from_utf8_lossy(&output.stderr).trim()
+        ));
+      }
+      let stdout = String::from_utf8_lossy(&output.stdout);
+      stdout.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ")
+    }
+    SpeechEngine::Remote { url, model } => {
+      let file = reqwest::multipart::Part::bytes(audio)
+        .file_name("speech.wav")
+        .mime_str("audio/wav")
+        .map_err(|e| format!("transcription request failed: {e}"))?;
+      let mut form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("model", model.clone().unwrap_or_else(|| "whisper-1".to_string()))
+        .text("response_format", "json");
+      if let Some(language) = language {
+        form = form.text("language", language);
+      }
+      let mut request = http::client().post(url.trim()).multipart(form);
+      if let Some(key) = secrets::get(API_KEY_SECRET)? {
+        request = request.bearer_auth(key);
+      }
+      let resp = tokio::time::timeout(TRANSCRIBE_TIMEOUT, request.send())
+        .await
+        .map_err(|_| "transcription request timed out".to_string())?
+        .map_err(|e| format!("transcription request failed: {e}"))?;
+      let status = resp.status();
+      if !status.is_success() {
+        let body: String = resp.text().await.unwrap_or_default().chars().take(200).collect();
+        return Err(format!("transcription request failed: HTTP {status}: {body}"));
+      }
+      let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("unexpected transcription response: {e}"))?;
+      body
+        .get("text")
+        .and_then(|t| t.as_str())
+        .ok_or_else(|| "unexpected transcription response: no text".to_string())?
+        .to_string()
+    }
+  };
+  Ok(text.trim().to_string())
+}
+
+/// What to translate the transcript with.
+struct Translation {
+  target_lang: String,
+  mode: String,
+  explanation_lang: String,
+  output: OutputControls,
+}
+
+async fn run(
+  app: &tauri::AppHandle,
+  config: SpeechConfig,
+  translation: Translation,
+  stop: Arc<Notify>,
+  on_event: &Channel<VoiceEvent>,
+) -> Result<VoiceResult, String> {
+  let recorder = tauri::async_runtime::spawn_blocking(Recorder::start)
+    .await
+    .map_err(|e| format!("the microphone couldn't be opened: {e}"))??;
+  let _ = on_event.send(VoiceEvent::Recording);
+
+  let default_interim = match config.engine {
+    SpeechEngine::Local { .. } => DEFAULT_INTERIM_SECONDS,
+    SpeechEngine::Remote { .. } => 0,
+  };
+  let interim = Duration::from_secs(config.interim_seconds.unwrap_or(default_interim));
+  let deadline = tokio::time::Instant::now() + MAX_RECORDING;
+  loop {
+    let tick = if interim.is_zero() { MAX_RECORDING } else { interim };
+    tokio::select! {
+      _ = stop.notified() => break,
+      _ = tokio::time::sleep_until(deadline) => break,
+      _ = tokio::time::sleep(tick) => {}
+    }
+    if interim.is_zero() || recorder.duration() < MIN_INTERIM {
+      continue;
+    }
+    // A stop drops the interim transcription (killing whisper.cpp or the request) instead of waiting for it.
+    let tail = recorder.tail(INTERIM_WINDOW);
+    let result = tokio::select! {
+      _ = stop.notified() => break,
+      result = transcribe(&config, &tail) => result,
+    };
+    match result {
+      Ok(text) if !text.is_empty() => {
+        let _ = on_event.send(VoiceEvent::Interim { text });
+      }
+      Ok(_) => {}
+      Err(e) => log::warn!("interim transcription failed: {e}"),
+    }
+  }
+
+  let samples = recorder.finish();
+  let transcript = transcribe(&config, &samples).await?;
+  let _ = on_event.send(VoiceEvent::Transcript {
+    text: transcript.clone(),
+  });
+  if transcript.is_empty() {
+    return Ok(VoiceResult {
+      transcript,
+      translation: None,
+    });
+  }
+
+  let req = TranslateRequest {
+    base_url: settings::api_base_url(app)?,
+    text: transcript.clone(),
+    target_lang: translation.target_lang,
+    mode: translation.mode,
+    explanation_lang: translation.explanation_lang,
+    priority: Priority::Interactive,
+    output: translation.output,
+    ..Default::default()
+  };
+  let sink = |event: StreamEvent| {
+    let _ = on_event.send(VoiceEvent::Translation { event });
+  };
+  let translated = translate::run_translation(app, req, &sink).await?;
+  Ok(VoiceResult {
+    transcript,
+    translation: Some(translated),
+  })
+}
+
+/// Record from the default microphone until `stop_voice_translation`, then transcribe and translate what was said,
+/// reporting on `on_event` (see the module docs). `target_lang` defaults to `defaultLanguage`, `mode` to
+/// "standard" and `explanation_lang` to `explanationLanguage`; `language` overrides the setting's spoken language.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_voice_translation(
+  app: tauri::AppHandle,
+  language: Option<String>,
+  target_lang: Option<String>,
+  mode: Option<String>,
+  explanation_lang: Option<String>,
+  output: Option<OutputControls>,
+  on_event: Channel<VoiceEvent>,
+) -> Result<VoiceResult, String> {
+  let mut config =
+    settings::speech_config(&app).ok_or_else(|| "no speech recognizer is set up (`speechToText` setting)".to_string())?;
+  config.check()?;
+  if let Some(language) = language.filter(|l| !l.trim().is_empty()) {
+    config.language = Some(language);
+  }
+  let translation = Translation {
+    target_lang: target_lang
+      .filter(|s| !s.trim().is_empty())
+      .unwrap_or_else(|| settings::default_target_lang(&app)),
+    mode: mode.unwrap_or_else(|| "standard".to_string()),
+    explanation_lang: explanation_lang.unwrap_or_else(|| settings::explanation_lang(&app)),
+    output: output.unwrap_or_default(),
+  };
+
+  let stop = Arc::new(Notify::new());
+  {
+    let state = app.state::<VoiceInput>();
+    let mut active = state.lock();
+    if active.is_some() {
+      return Err("already recording".to_string());
+    }
+    *active = Some(stop.clone());
+  }
+  let result = run(&app, config, translation, stop, &on_event).await;
+  *app.state::<VoiceInput>().lock() = None;
+  match &result {
+    Ok(_) => {
+      let _ = on_event.send(VoiceEvent::Done);
+    }
+    Err(message) => {
+      let _ = on_event.send(VoiceEvent::Error {
+        message: message.clone(),
+      });
+    }
+  }
+  result
+}
+
+/// End the recording `start_voice_translation` is making, which then transcribes and translates it.
+#[tauri::command]
+pub fn stop_voice_translation(state: tauri::State<'_, VoiceInput>) -> Result<(), String> {
+  match state.lock().as_ref() {
+    Some(stop) => {
+      stop.notify_one();
+      Ok(())
+    }
+    None => Err("not recording".to_string()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn resample_interpolates_down_to_the_sample_rate() {
+    let samples: Vec<f32> = (0..6).map(|i| i as f32).collect();
+    assert_eq!(resample(&samples, SAMPLE_RATE * 2), [0.0, 2.0, 4.0]);
+    assert_eq!(resample(&samples, SAMPLE_RATE), samples);
+    assert!(resample(&[], 44_100).is_empty());
+  }
+
+  #[test]
+  fn resample_interpolates_up_to_the_sample_rate() {
+    assert_eq!(resample(&[0.0, 1.0], SAMPLE_RATE / 2), [0.0, 0.5, 1.0, 1.0]);
+  }
+
+  #[test]
+  fn wav_is_16_bit_mono_pcm() {
+    let out = wav(&[0.0, 1.0, -2.0]);
+    assert_eq!(out.len(), 44 + 6);
+    assert_eq!(&out[..4], b"RIFF");
+    assert_eq!(u32::from_le_bytes(out[4..8].try_into().unwrap()), 36 + 6);
+    assert_eq!(&out[8..16], b"WAVEfmt ");
+    assert_eq!(u16::from_le_bytes([out[22], out[23]]), 1);
+    assert_eq!(u32::from_le_bytes(out[24..28].try_into().unwrap()), SAMPLE_RATE);
+    assert_eq!(u16::from_le_bytes([out[34], out[35]]), 16);
+    assert_eq!(&out[36..40], b"data");
+    assert_eq!(u32::from_le_bytes(out[40..44].try_into().unwrap()), 6);
+    let samples: Vec<i16> = out[44..].chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+    assert_eq!(samples, [0, i16::MAX, -i16::MAX]);
+  }
+}
@@ -0,0 +1,690 @@
+//! Syncing the settings, the glossary and the phrasebook between machines through a shared folder (a Dropbox or
+//! OneDrive directory) or a WebDAV server.
+//!
+//! `sync` in the settings says where: `{ "kind": "folder", "path": "..." }` or `{ "kind": "webdav", "url":
+//! "https://...", "username": "..." }` (the password is the `syncPassword` secret, see `secrets`); with `onStartup`
+//! the app syncs once when it starts. Each item is one JSON file there. `sync_now` merges both sides against what
+//! the last sync left (kept in `sync-state.json`), key by key for the settings and entry by entry for the
+//! glossary and phrasebook, so changes made on different machines are combined. A key changed differently on both
+//! sides is a conflict, settled by `prefer`: `newer` (the default) keeps the side changed last, by the time of each
+//! setting (recorded as settings are saved, in `setting-times.json`) or glossary entry or phrase. Machine-specific
+//! settings (tool paths, the sync setting itself) are never synced. Writes are conditional (WebDAV `If-Match`; in a
+//! folder or on a server without ETags, the file must still hold what was read), so a machine that wrote in between
+//! makes the item merge again instead of being overwritten.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use crate::{clock, events, glossary, http, phrasebook, secrets, settings, settings_schema};
+
+const STATE_FILE: &str = "sync-state.json";
+const SETTING_TIMES_FILE: &str = "setting-times.json";
+/// Secret holding the WebDAV password.
+pub const PASSWORD_SECRET: &str = "syncPassword";
+const REMOTE_FORMAT: u32 = 1;
+/// Times an item is merged again when another machine wrote it in between.
+const ATTEMPTS: usize = 3;
+/// Settings that only make sense on the machine they were set on.
+const LOCAL_ONLY_SETTINGS: &[&str] = &[
+  "sync",
+  "tesseractPath",
+  "tessdataPrefix",
+  "offlineEnginePath",
+  "speechToText",
+  "onboarded",
+  "lastUsedTargetLang",
+];
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SyncTarget {
+  Folder {
+    path: String,
+  },
+  Webdav {
+    url: String,
+    #[serde(default)]
+    username: Option<String>,
+  },
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConfig {
+  #[serde(flatten)]
+  pub target: SyncTarget,
+  #[serde(default)]
+  pub on_startup: bool,
+}
+
+/// Which side a conflicting key keeps.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+  /// The side saved last.
+  #[default]
+  Newer,
+  Local,
+  Remote,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+  Unchanged,
+  Pushed,
+  Pulled,
+  /// Changes from both sides were combined.
+  Merged,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ItemReport {
+  pub item: String,
+  pub action: SyncAction,
+  /// Keys changed differently on both sides (setting names, glossary source terms, phrases).
+  pub conflicts: Vec<String>,
+}
+
+/// An item as stored in the sync folder.
+#[derive(Debug, Serialize, Deserialize)]
+struct RemoteFile {
+  format: u32,
+  modified_at: u128,
+  data: Map<String, Value>,
+  /// When each setting was changed (older files have none; `modified_at` stands in).
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  modified: BTreeMap<String, u128>,
+}
+
+/// What each item looked like after the last sync: the base both sides are merged against.
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct SyncState {
+  items: BTreeMap<String, Map<String, Value>>,
+}
+
+/// The version of an item's file a write expects to replace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Version {
+  /// WebDAV `ETag`, checked by the server (`If-Match`).
+  ETag(String),
+  /// Hash of the contents (`content_tag`), checked just before writing: in a folder, or on a WebDAV server that
+  /// sends no `ETag`.
+  Content(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Item {
+  Settings,
+  Glossary,
+  Phrasebook,
+}
+
+const ITEMS: [Item; 3] = [Item::Settings, Item::Glossary, Item::Phrasebook];
+
+impl Item {
+  fn name(self) -> &'static str {
+    match self {
+      Item::Settings => "settings.json",
+      Item::Glossary => "glossary.json",
+      Item::Phrasebook => "phrasebook.json",
+    }
+  }
+
+  /// The synced part of the local data, by key.
+  fn local(self, app: &tauri::AppHandle) -> Map<String, Value> {
+    match self {
+      Item::Settings => {
+        let mut map = settings::load(app).as_object().cloned().unwrap_or_default();
+        map.retain(|k, _| !LOCAL_ONLY_SETTINGS.contains(&k.as_str()));
+        map
+      }
+      Item::Glossary => glossary::entries(app)
+        .iter()
+        .filter_map(|e| {
+          let mut value = serde_json::to_value(e).ok()?;
+          value.as_object_mut()?.remove("id");
+          Some((glossary::sync_key(e), value))
+        })
+        .collect(),
+      Item::Phrasebook => phrasebook::phrases(app)
+        .iter()
+        .filter_map(|p| {
+          let mut value = serde_json::to_value(p).ok()?;
+          value.as_object_mut()?.remove("id");
+          Some((phrasebook::sync_key(p), value))
+        })
+        .collect(),
+    }
+  }
+
+  /// Replace the local data with `merged`.
+  fn apply(self, app: &tauri::AppHandle, merged: &Map<String, Value>) -> Result<(), String> {
+    match self {
+      Item::Settings => {
+        let mut all = settings::load(app).as_object().cloned().unwrap_or_default();
+        all.retain(|k, _| LOCAL_ONLY_SETTINGS.contains(&k.as_str()));
+        all.extend(merged.clone());
+        settings::save(app, settings_schema::sanitized(Value::Object(all)))
+      }
+      Item::Glossary => {
+        let local = glossary::entries(app);
+        let ids = local.iter().map(|e| (glossary::sync_key(e), e.id)).collect();
+        glossary::replace(app, with_ids(merged, ids, "glossary entry"))
+      }
+      Item::Phrasebook => {
+        let local = phrasebook::phrases(app);
+        let ids = local.iter().map(|p| (phrasebook::sync_key(p), p.id)).collect();
+        phrasebook::replace(app, with_ids(merged, ids, "phrase"))
+      }
+    }
+  }
+
+  /// Whether the remote value of the conflicting `key` (in `file`) was changed after the local one; `times` are
+  /// the local settings' change times.
+  fn remote_is_newer(
+    self,
+    times: &BTreeMap<String, u128>,
+    file: &RemoteFile,
+    key: &str,
+    local: Option<&Value>,
+    remote: Option<&Value>,
+  ) -> bool {
+    match self {
+      // A setting changed before times were recorded counts as old.
+      Item::Settings => remote_time(file, key) > times.get(key).copied().unwrap_or(0),
+      // A deleted entry has no time, so an edit wins over a deletion.
+      Item::Glossary | Item::Phrasebook => {
+        let updated = |v: Option<&Value>| v.and_then(|v| v.get("updated_at")).and_then(|t| t.as_u64()).unwrap_or(0);
+        updated(remote) > updated(local)
+      }
+    }
+  }
+}
+
+/// When `key` was changed in `file`.
+fn remote_time(file: &RemoteFile, key: &str) -> u128 {
+  file.modified.get(key).copied().unwrap_or(file.modified_at)
+}
+
+/// The change times of the `merged` settings: the remote one for a value taken from `remote`, else the local one.
+fn merged_times(
+  merged: &Map<String, Value>,
+  remote: Option<&RemoteFile>,
+  times: &BTreeMap<String, u128>,
+) -> BTreeMap<String, u128> {
+  let now = clock::now_millis();
+  merged
+    .iter()
+    .map(|(key, value)| {
+      let time = match remote {
+        Some(file) if file.data.get(key) == Some(value) => remote_time(file, key),
+        _ => times.get(key).copied().unwrap_or(now),
+      };
+      (key.clone(), time)
+    })
+    .collect()
+}
+
+fn setting_times_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  let dir = app.path().app_data_dir().map_err(|e| format!("no app data dir: {e}"))?;
+  Ok(dir.join(SETTING_TIMES_FILE))
+}
+
+/// Held while `setting-times.json` is read and written back.
+static SETTING_TIMES_LOCK: Mutex<()> = Mutex::new(());
+/// Settings a sync just saved, so the change they cause isn't recorded as made now (see `record_setting_changes`).
+static PULLED_SETTINGS: Mutex<Option<Map<String, Value>>> = Mutex::new(None);
+
+fn load_setting_times(app: &tauri::AppHandle) -> BTreeMap<String, u128> {
+  setting_times_path(app)
+    .ok()
+    .and_then(|p| std::fs::read(p).ok())
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default()
+}
+
+fn save_setting_times(app: &tauri::AppHandle, times: &BTreeMap<String, u128>) {
+  let written = setting_times_path(app).and_then(|path| crate::json_store::save(&path, times));
+  if let Err(e) = written {
+    log::warn!("failed to write {SETTING_TIMES_FILE}: {e}");
+  }
+}
+
+/// Note that the settings `keys` changed now, for `newer` conflicts (`settings` calls this after each save, with the
+/// `new` settings). Keys that hold what a sync just pulled keep the times the sync gave them.
+pub fn record_setting_changes(app: &tauri::AppHandle, new: &Value, keys: &[String]) {
+  let _writing = SETTING_TIMES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+  let pulled = PULLED_SETTINGS.lock().unwrap_or_else(|e| e.into_inner()).take();
+  let mut times = load_setting_times(app);
+  let now = clock::now_millis();
+  for key in keys.iter().filter(|k| !LOCAL_ONLY_SETTINGS.contains(&k.as_str())) {
+    let from_sync = pulled.as_ref().is_some_and(|p| p.get(key) == new.get(key));
+    if !from_sync {
+      times.insert(key.clone(), now);
+    }
+  }
+  save_setting_times(app, &times);
+}
+
+/// Save pulled settings with their change times, `times` from the merge.
+fn apply_settings(
+  app: &tauri::AppHandle,
+  merged: &Map<String, Value>,
+  times: BTreeMap<String, u128>,
+) -> Result<(), String> {
+  {
+    let _writing = SETTING_TIMES_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut all_times = load_setting_times(app);
+    all_times.extend(times);
+    save_setting_times(app, &all_times);
+  }
+  *PULLED_SETTINGS.lock().unwrap_or_else(|e| e.into_inner()) = Some(merged.clone());
+  Item::Settings.apply(app, merged)
+}
+
+/// The merged entries of a list item, keeping the local ids (`ids`, by sync key) and numbering new ones after them.
+fn with_ids<T: serde::de::DeserializeOwned>(
+  merged: &Map<String, Value>,
+  ids: HashMap<String, u64>,
+  what: &str,
+) -> Vec<T> {
+  let mut next_id = ids.values().copied().max().unwrap_or(0);
+  let mut entries = Vec::new();
+  for (key, value) in merged {
+    let id = ids.get(key).copied().unwrap_or_else(|| {
+      next_id += 1;
+      next_id
+    });
+    let mut value = value.clone();
+    if let Some(obj) = value.as_object_mut() {
+      obj.insert("id".to_string(), Value::from(id));
+    }
+    match serde_json::from_value(value) {
+      Ok(entry) => entries.push(entry),
+      Err(e) => log::warn!("skipping synced {what} {key:?}: {e}"),
+    }
+  }
+  entries
+}
+
+/// Three-way merge of `local` and `remote` against `base`, key by key. Returns the merged map and the keys
+/// changed differently on both sides, which `remote_wins` (given the key and both values) settles.
+fn merge3(
+  base: &Map<String, Value>,
+  local: &Map<String, Value>,
+  remote: &Map<String, Value>,
+  remote_wins: impl Fn(&str, Option<&Value>, Option<&Value>) -> bool,
+) -> (Map<String, Value>, Vec<String>) {
+  let keys: BTreeSet<&String> = base.keys().chain(local.keys()).chain(remote.keys()).collect();
+  let mut merged = Map::new();
+  let mut conflicts = Vec::new();
+  for key in keys {
+    let (b, l, r) = (base.get(key), local.get(key), remote.get(key));
+    let value = if l == r || r == b {
+      l
+    } else if l == b {
+      r
+    } else {
+      conflicts.push(key.clone());
+      if remote_wins(key, l, r) {
+        r
+      } else {
+        l
+      }
+    };
+    if let Some(value) = value {
+      merged.insert(key.clone(), value.clone());
+    }
+  }
+  (merged, conflicts)
+}
+
+/// Where the items are synced to.
+enum Remote {
+  Folder(PathBuf),
+  WebDav {
+    base: reqwest::Url,
+    username: Option<String>,
+    password: Option<String>,
+  },
+}
+
+impl Remote {
+  fn from_config(config: &SyncConfig) -> Result<Self, String> {
+    match &config.target {
+      SyncTarget::Folder { path } if path.trim().is_empty() => Err("the sync folder is not set".to_string()),
+      SyncTarget::Folder { path } => Ok(Remote::Folder(PathBuf::from(path.trim()))),
+      SyncTarget::Webdav { url, username } => {
+        let mut url = url.trim().to_string();
+        if !url.ends_with('/') {
+          url.push('/');
+        }
+        let base = reqwest::Url::parse(&url).map_err(|e| format!("invalid WebDAV URL {url}: {e}"))?;
+        Ok(Remote::WebDav {
+          base,
+          username: username.clone().filter(|u| !u.is_empty()),
+          password: secrets::get(PASSWORD_SECRET)?,
+        })
+      }
+    }
+  }
+
+  fn request(&self, method: reqwest::Method, name: &str) -> Result<reqwest::RequestBuilder, String> {
+    let Remote::WebDav {
+      base,
+      username,
+      password,
+    } = self
+    else {
+      unreachable!("only WebDAV remotes make requests");
+    };
+    let url = base.join(name).map_err(|e| format!("invalid WebDAV URL: {e}"))?;
+    let req = http::client().request(method, url);
+    Ok(match username {
+      Some(user) => req.basic_auth(user, password.as_deref()),
+      None => req,
+    })
+  }
+
+  /// The item's file and its version, or `None` if it doesn't exist yet.
+  async fn get(&self, name: &str) -> Result<Option<(Vec<u8>, Version)>, String> {
+    match self {
+      Remote::Folder(dir) => Ok(read_folder_file(dir, name)?.map(|bytes| {
+        let version = Version::Content(content_tag(&bytes));
+        (bytes, version)
+      })),
+      Remote::WebDav { .. } => {
+        let resp = self
+          .request(reqwest::Method::GET, name)?
+          .send()
+          .await
+          .map_err(|e| format!("sync request failed: {e}"))?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+          return Ok(None);
+        }
+        if !resp.status().is_success() {
+          return Err(format!("WebDAV GET {name} failed: HTTP {}", resp.status()));
+        }
+        let etag = resp
+          .headers()
+          .get(reqwest::header::ETAG)
+          .and_then(|v| v.to_str().ok())
+          .map(str::to_string);
+        let bytes = resp.bytes().await.map_err(|e| format!("sync request failed: {e}"))?;
+        let version = match etag {
+          Some(etag) => Version::ETag(etag),
+          None => Version::Content(content_tag(&bytes)),
+        };
+        Ok(Some((bytes.to_vec(), version)))
+      }
+    }
+  }
+
+  /// Write the item's file, only if it's still the version read (`version`), or still missing when there was none;
+  /// `Ok(false)` when another machine wrote it in between. Contents are compared just before the file is replaced,
+  /// which narrows the window for a lost write to the write itself.
+  async fn put(&self, name: &str, bytes: Vec<u8>, version: Option<&Version>) -> Result<bool, String> {
+    match self {
+      Remote::Folder(dir) => {
+        std::fs::create_dir_all(dir).map_err(|e| format!("cannot create the sync folder: {e}"))?;
+        let current = read_folder_file(dir, name)?.map(|b| Version::Content(content_tag(&b)));
+        if current.as_ref() != version {
+          return Ok(false);
+        }
+        let partial = dir.join(format!("{name}.partial"));
+        std::fs::write(&partial, bytes).map_err(|e| format!("cannot write {name} to the sync folder: {e}"))?;
+        std::fs::rename(&partial, dir.join(name)).map_err(|e| format!("cannot write {name} to the sync folder: {e}"))?;
+        Ok(true)
+      }
+      Remote::WebDav { .. } => {
+        if let Some(Version::Content(_)) = version {
+          let current = self.get(name).await?.map(|(_, v)| v);
+          if current.as_ref() != version {
+            return Ok(false);
+          }
+        }
+        let mut req = self.request(reqwest::Method::PUT, name)?.body(bytes);
+        req = match version {
+          Some(Version::ETag(etag)) => req.header(reqwest::header::IF_MATCH, etag),
+          Some(Version::Content(_)) => req,
+          None => req.header(reqwest::header::IF_NONE_MATCH, "*"),
+        };
+        let resp = req.send().await.map_err(|e| format!("sync request failed: {e}"))?;
+        if resp.status() == reqwest::StatusCode::PRECONDITION_FAILED {
+          return Ok(false);
+        }
+        if !resp.status().is_success() {
+          return Err(format!("WebDAV PUT {name} failed: HTTP {}", resp.status()));
+        }
+        Ok(true)
+      }
+    }
+  }
+}
+
+/// The file `name` in the sync folder `dir`, or `None` if it doesn't exist.
+fn read_folder_file(dir: &std::path::Path, name: &str) -> Result<Option<Vec<u8>>, String> {
+  match std::fs::read(dir.join(name)) {
+    Ok(bytes) => Ok(Some(bytes)),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(format!("cannot read {name} from the sync folder: {e}")),
+  }
+}
+
+/// Version tag of a file in the sync folder: the hash of its contents.
+fn content_tag(bytes: &[u8]) -> String {
+  Sha256::digest(bytes).iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn state_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  let dir = app.path().app_data_dir().map_err(|e| format!("no app data dir: {e}"))?;
+  Ok(dir.join(STATE_FILE))
+}
+
+fn load_state(app: &tauri::AppHandle) -> SyncState {
+  state_path(app)
+    .ok()
+    .and_then(|p| std::fs::read(p).ok())
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default()
+}
+
+fn save_state(app: &tauri::AppHandle, state: &SyncState) -> Result<(), String> {
+  crate::json_store::save(&state_path(app)?, state).map_err(|e| format!("failed to save {STATE_FILE}: {e}"))
+}
+
+async fn sync_item(
+  app: &tauri::AppHandle,
+  remote: &Remote,
+  item: Item,
+  prefer: ConflictPolicy,
+  state: &mut SyncState,
+) -> Result<ItemReport, String> {
+  let name = item.name();
+  for _ in 0..ATTEMPTS {
+    let fetched = remote.get(name).await?;
+    let (remote_file, version) = match fetched {
+      Some((bytes, version)) => {
+        let file: RemoteFile =
+          serde_json::from_slice(&bytes).map_err(|e| format!("{name} in the sync folder is unreadable: {e}"))?;
+        if file.format > REMOTE_FORMAT {
+          return Err(format!("{name} was synced by a newer version of the app; update to sync it"));
+        }
+        (Some(file), Some(version))
+      }
+      None => (None, None),
+    };
+    let local = item.local(app);
+    let times = match item {
+      Item::Settings => load_setting_times(app),
+      Item::Glossary | Item::Phrasebook => BTreeMap::new(),
+    };
+    let base = state.items.get(name).cloned().unwrap_or_default();
+    let (merged, conflicts) = match &remote_file {
+      Some(file) => merge3(&base, &local, &file.data, |key, l, r| match prefer {
+        ConflictPolicy::Local => false,
+        ConflictPolicy::Remote => true,
+        ConflictPolicy::Newer => item.remote_is_newer(&times, file, key, l, r),
+      }),
+      None => (local.clone(), Vec::new()),
+    };
+
+    let push = remote_file.as_ref().map_or(true, |f| f.data != merged);
+    if push {
+      let file = RemoteFile {
+        format: REMOTE_FORMAT,
+        modified_at: clock::now_millis(),
+        modified: match item {
+          Item::Settings => merged_times(&merged, remote_file.as_ref(), &times),
+          Item::Glossary | Item::Phrasebook => BTreeMap::new(),
+        },
+        data: merged.clone(),
+      };
+      let bytes = serde_json::to_vec_pretty(&file).map_err(|e| format!("failed to serialize {name}: {e}"))?;
+      if !remote.put(name, bytes, version.as_ref()).await? {
+        // Written by another machine since it was read: merge with that version.
+        continue;
+      }
+    }
+    let pull = merged != local;
+    if pull {
+      match item {
+        // Pulled settings keep the times they were changed at, not the time of this save.
+        Item::Settings => apply_settings(app, &merged, merged_times(&merged, remote_file.as_ref(), &times))?,
+        Item::Glossary | Item::Phrasebook => item.apply(app, &merged)?,
+      }
+    }
+    state.items.insert(name.to_string(), merged);
+    let action = match (push, pull) {
+      (false, false) => SyncAction::Unchanged,
+      (true, false) => SyncAction::Pushed,
+      (false, true) => SyncAction::Pulled,
+      (true, true) => SyncAction::Merged,
+    };
+    let conflicts = match item {
+      Item::Settings => conflicts,
+      // Keys are "source\u{1f}language"; the source term or phrase is what the user recognizes.
+      Item::Glossary | Item::Phrasebook => conflicts
+        .into_iter()
+        .map(|k| k.split('\u{1f}').next().unwrap_or_default().to_string())
+        .collect(),
+    };
+    return Ok(ItemReport {
+      item: name.to_string(),
+      action,
+      conflicts,
+    });
+  }
+  Err(format!("{name} kept changing on the server; try again"))
+}
+
+/// Sync every item; the reports of those that synced, or the first error.
+pub async fn sync_all(app: &tauri::AppHandle, prefer: ConflictPolicy) -> Result<Vec<ItemReport>, String> {
+  static RUNNING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+  let _running = RUNNING.lock().await;
+  let config = settings::sync_config(app).ok_or_else(|| "sync is not set up".to_string())?;
+  let remote = Remote::from_config(&config)?;
+  let mut state = load_state(app);
+  let mut reports = Vec::new();
+  let mut result = Ok(());
+  for item in ITEMS {
+    match sync_item(app, &remote, item, prefer, &mut state).await {
+      Ok(report) => reports.push(report),
+      Err(e) => {
+        result = Err(e);
+        break;
+      }
+    }
+  }
+  // What did sync is recorded even when a later item failed.
+  save_state(app, &state)?;
+  result?;
+  events::publish(app, "sync.completed", None, serde_json::json!({ "items": reports }));
+  Ok(reports)
+}
+
+/// Sync once at startup when `sync.onStartup` is set (`setup`).
+pub fn init(app: &tauri::AppHandle) {
+  if !settings::sync_config(app).is_some_and(|c| c.on_startup) {
+    return;
+  }
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    if let Err(e) = sync_all(&app, ConflictPolicy::default()).await {
+      events::warn(&app, None, "sync_failed", e);
+    }
+  });
+}
+
+/// Sync the settings, glossary and phrasebook with the configured folder or WebDAV server now. `prefer` settles keys
+/// changed on both sides (`newer` when unset).
+#[tauri::command]
+pub async fn sync_now(app: tauri::AppHandle, prefer: Option<ConflictPolicy>) -> Result<Vec<ItemReport>, String> {
+  sync_all(&app, prefer.unwrap_or_default()).await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn map(value: Value) -> Map<String, Value> {
+    match value {
+      Value::Object(map) => map,
+      _ => unreachable!(),
+    }
+  }
+
+  #[test]
+  fn merge3_takes_each_sides_changes() {
+    let base = map(json!({ "a": 1, "b": 1, "c": 1, "d": 1 }));
+    let local = map(json!({ "a": 2, "b": 1, "d": 1, "e": 5 }));
+    let remote = map(json!({ "a": 1, "b": 3, "c": 1, "f": 6 }));
+    let (merged, conflicts) = merge3(&base, &local, &remote, |_, _, _| unreachable!());
+    assert_eq!(merged, map(json!({ "a": 2, "b": 3, "e": 5, "f": 6 })));
+    assert!(conflicts.is_empty());
+  }
+
+  #[test]
+  fn merge3_settles_conflicts_by_key() {
+    let base = map(json!({ "a": 1, "b": 1 }));
+    let local = map(json!({ "a": 2, "b": 2 }));
+    let remote = map(json!({ "a": 3, "b": 3 }));
+    let (merged, conflicts) = merge3(&base, &local, &remote, |key, l, r| {
+      assert_eq!((l, r), (Some(&json!(2)), Some(&json!(3))));
+      key == "b"
+    });
+    assert_eq!(merged, map(json!({ "a": 2, "b": 3 })));
+    assert_eq!(conflicts, vec!["a".to_string(), "b".to_string()]);
+  }
+
+  #[test]
+  fn settings_conflicts_compare_per_key_times() {
+    let file = RemoteFile {
+      format: REMOTE_FORMAT,
+      modified_at: 100,
+      data: Map::new(),
+      modified: BTreeMap::from([("theme".to_string(), 300)]),
+    };
+    let times = BTreeMap::from([("theme".to_string(), 200), ("hotkey".to_string(), 200)]);
+    assert!(Item::Settings.remote_is_newer(&times, &file, "theme", None, None));
+    // No time of its own: the file's.
+    assert!(!Item::Settings.remote_is_newer(&times, &file, "hotkey", None, None));
+    assert!(Item::Settings.remote_is_newer(&times, &file, "unrecorded", None, None));
+  }
+
+  #[test]
+  fn entry_conflicts_compare_update_times() {
+    let file = RemoteFile { format: REMOTE_FORMAT, modified_at: 0, data: Map::new(), modified: BTreeMap::new() };
+    let (older, newer) = (json!({ "updated_at": 1 }), json!({ "updated_at": 2 }));
+    assert!(Item::Glossary.remote_is_newer(&BTreeMap::new(), &file, "k", Some(&older), Some(&newer)));
+    assert!(!Item::Phrasebook.remote_is_newer(&BTreeMap::new(), &file, "k", Some(&newer), Some(&older)));
+    assert!(Item::Glossary.remote_is_newer(&BTreeMap::new(), &file, "k", None, Some(&older)));
+  }
+}
@@ -0,0 +1,115 @@
+//! Scratch files (screen captures, downloaded installers).
+//!
+//! Everything lives in one directory under the app data dir, so nothing is left behind in the system temp dir:
+//! the area is emptied on startup and exit, and files older than `tempFileTtlMinutes` are purged while the app
+//! runs.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use crate::clock;
+use crate::settings;
+
+const DIR_NAME: &str = "tmp";
+/// How often expired files are looked for.
+const PURGE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Prefix of the files earlier versions wrote straight into the system temp dir.
+const LEGACY_PREFIX: &str = "erudaite-";
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// The temp area (created on demand). Before `init` runs, a directory in the system temp dir.
+pub fn dir() -> PathBuf {
+  let dir = DIR.get().cloned().unwrap_or_else(|| std::env::temp_dir().join("erudaite"));
+  let _ = std::fs::create_dir_all(&dir);
+  dir
+}
+
+/// A fresh path in the temp area, e.g. `path("capture", "png")`.
+pub fn path(prefix: &str, ext: &str) -> PathBuf {
+  dir().join(format!("{}.{ext}", clock::next_id(prefix)))
+}
+
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+pub struct Reclaimed {
+  pub files: usize,
+  pub bytes: u64,
+}
+
+/// Remove the files in `dir` whose name passes `matches` and that were last modified before `max_age` ago (all of
+/// them when `None`).
+fn purge_dir(dir: &Path, max_age: Option<Duration>, matches: impl Fn(&str) -> bool) -> Reclaimed {
+  let mut reclaimed = Reclaimed::default();
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    return reclaimed;
+  };
+  let now = SystemTime::now();
+  for entry in entries.flatten() {
+    let Ok(meta) = entry.metadata() else {
+      continue;
+    };
+    if !meta.is_file() || !matches(&entry.file_name().to_string_lossy()) {
+      continue;
+    }
+    let expired = match max_age {
+      None => true,
+      Some(max) => meta
+        .modified()
+        .ok()
+        .and_then(|m| now.duration_since(m).ok())
+        .is_some_and(|age| age >= max),
+    };
+    if expired && std::fs::remove_file(entry.path()).is_ok() {
+      reclaimed.files += 1;
+      reclaimed.bytes += meta.len();
+    }
+  }
+  reclaimed
+}
+
+/// Remove temp files older than `max_age` (all of them when `None`).
+pub fn purge(max_age: Option<Duration>) -> Reclaimed {
+  purge_dir(&dir(), max_age, |_| true)
+}
+
+fn ttl(app: &tauri::AppHandle) -> Duration {
+  Duration::from_secs(settings::temp_file_ttl_minutes(app) * 60)
+}
+
+/// Use `app_data_dir/tmp`, clear what the last run left (including files older versions put in the system temp
+/// dir) and start purging expired files.
+pub fn init(app: &tauri::AppHandle, app_data_dir: &Path) {
+  let _ = DIR.set(app_data_dir.join(DIR_NAME));
+  let mut reclaimed = purge(None);
+  let legacy = purge_dir(&std::env::temp_dir(), None, |name| name.starts_with(LEGACY_PREFIX));
+  reclaimed.files += legacy.files;
+  reclaimed.bytes += legacy.bytes;
+  if reclaimed.files > 0 {
+    log::info!("removed {} leftover temp files ({} bytes)", reclaimed.files, reclaimed.bytes);
+  }
+
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(PURGE_INTERVAL).await;
+      let max_age = ttl(&app);
+      tauri::async_runtime::spawn_blocking(move || purge(Some(max_age)));
+    }
+  });
+}
+
+/// Empty the temp area (on exit).
+pub fn shutdown() {
+  purge(None);
+}
+
+/// Remove temp files now: those past `tempFileTtlMinutes`, or every one with `all`. Reports what was freed.
+#[tauri::command]
+pub async fn cleanup_temp_files(app: tauri::AppHandle, all: Option<bool>) -> Result<Reclaimed, String> {
+  let max_age = (!all.unwrap_or(false)).then(|| ttl(&app));
+  tauri::async_runtime::spawn_blocking(move || purge(max_age))
+    .await
+    .map_err(|e| format!("cleanup failed: {e}"))
+}
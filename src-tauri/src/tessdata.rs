@@ -0,0 +1,399 @@
+//! Tesseract language data installed by the app.
+//!
+//! `download_tessdata` puts `<lang>.traineddata` from the official `tessdata_fast` (small, quick) or
+//! `tessdata_best` (most accurate, 10-30 MB) repositories into a per-user directory, and records which variant
+//! and SHA-256 each file has in `tessdata.json` next to them. Only files in that directory are listed or deleted
+//! here; a system Tesseract's own tessdata is left alone.
+//!
+//! No file is installed unchecked: it must match the SHA-256 the caller gives or, without one, the git blob hash
+//! GitHub's API lists for it. A download whose checksum can't be had is refused.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+
+use crate::settings;
+
+const MANIFEST_FILE: &str = "tessdata.json";
+/// Tries per `download_tessdata` call before giving up (the partial file stays for the next call).
+const DOWNLOAD_ATTEMPTS: usize = 3;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Variant {
+  /// Integer models: small and quick (the default).
+  #[default]
+  Fast,
+  /// Float models: the most accurate, several times larger and slower.
+  Best,
+}
+
+impl Variant {
+  fn repository(self) -> &'static str {
+    match self {
+      Variant::Fast => "tessdata_fast",
+      Variant::Best => "tessdata_best",
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ManifestEntry {
+  variant: Variant,
+  sha256: String,
+}
+
+type Manifest = BTreeMap<String, ManifestEntry>;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InstalledTessdata {
+  pub lang: String,
+  pub path: String,
+  pub size: u64,
+  /// Unknown for files that weren't downloaded by the app.
+  pub variant: Option<Variant>,
+  pub sha256: Option<String>,
+}
+
+/// The directory downloads go to (`%LOCALAPPDATA%\Erudaite\tessdata`); it's the `TESSDATA_PREFIX` to use them.
+pub fn install_dir() -> Result<PathBuf, String> {
+  #[cfg(windows)]
+  {
+    let local = std::env::var("LOCALAPPDATA").map_err(|_| "LOCALAPPDATA not set".to_string())?;
+    Ok(PathBuf::from(local).join("Erudaite").join("tessdata"))
+  }
+
+  #[cfg(not(windows))]
+  {
+    Err("tessdata downloads are not supported on this platform".to_string())
+  }
+}
+
+/// Tesseract language codes are lowercase letters, digits and `_` (`jpn`, `chi_sim_vert`); anything else could
+/// point outside the tessdata directory.
+fn validate_lang(lang: &str) -> Result<String, String> {
+  let lang = lang.trim().to_lowercase();
+  if lang.is_empty() || !lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+    return Err(format!("invalid lang: {lang:?}"));
+  }
+  Ok(lang)
+}
+
+fn read_manifest(dir: &Path) -> Manifest {
+  std::fs::read(dir.join(MANIFEST_FILE))
+    .ok()
+    .and_then(|b| serde_json::from_slice(&b).ok())
+    .unwrap_or_default()
+}
+
+fn write_manifest(dir: &Path, manifest: &Manifest) -> Result<(), String> {
+  let bytes = serde_json::to_vec_pretty(manifest).map_err(|e| format!("write manifest failed: {e}"))?;
+  std::fs::write(dir.join(MANIFEST_FILE), bytes).map_err(|e| format!("write manifest failed: {e}"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Variant to download when the caller doesn't say (`tessdataVariant` setting, default "fast").
+fn default_variant(app: &tauri::AppHandle) -> Variant {
+  serde_json::from_value(serde_json::Value::String(settings::tessdata_variant(app))).unwrap_or_default()
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type")]
+pub enum DownloadEvent {
+  /// `resumed_from` bytes were already on disk from an interrupted download.
+  #[serde(rename = "started")]
+  Started { total: Option<u64>, resumed_from: u64 },
+  #[serde(rename = "progress")]
+  Progress {
+    downloaded: u64,
+    total: Option<u64>,
+    /// Average over this attempt.
+    bytes_per_sec: u64,
+  },
+  #[serde(rename = "done")]
+  Done { prefix: String },
+  #[serde(rename = "error")]
+  Error { message: String },
+}
+
+/// Why a download attempt stopped.
+enum Failure {
+  /// Network trouble: try again, resuming from what's on disk.
+  Retry(String),
+  Fatal(String),
+}
+
+/// Append the rest of `url` to `part`, asking for only the missing bytes when part of it is already there.
+async fn fetch(
+  client: &reqwest::Client,
+  url: &str,
+  part: &Path,
+  on_event: &Channel<DownloadEvent>,
+) -> Result<(), Failure> {
+  use futures_util::StreamExt;
+  use std::io::Write;
+
+  let have = std::fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+  let mut request = client.get(url);
+  if have > 0 {
+    request = request.header(reqwest::header::RANGE, format!("bytes={have}-"));
+  }
+  let res = request
+    .send()
+    .await
+    .map_err(|e| Failure::Retry(format!("download failed: {e}")))?;
+  let status = res.status();
+  if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && have > 0 {
+    // "bytes */<size>": nothing past what we have, so the part is complete unless it's a stale, longer file.
+    let size = res
+      .headers()
+      .get(reqwest::header::CONTENT_RANGE)
+      .and_then(|v| v.to_str().ok())
+      .and_then(|v| v.rsplit('/').next())
+      .and_then(|n| n.parse::<u64>().ok());
+    if size == Some(have) {
+      return Ok(());
+    }
+    let _ = std::fs::remove_file(part);
+    return Err(Failure::Retry("download restarted: partial file was stale".to_string()));
+  }
+  if !status.is_success() {
+    let message = format!("download failed: http {status}");
+    return Err(if status.is_server_error() {
+      Failure::Retry(message)
+    } else {
+      Failure::Fatal(message)
+    });
+  }
+
+  // A server that ignores the range sends the whole file again.
+  let start = if status == reqwest::StatusCode::PARTIAL_CONTENT { have } else { 0 };
+  let file = if start > 0 {
+    std::fs::OpenOptions::new().append(true).open(part)
+  } else {
+    std::fs::File::create(part)
+  };
+  let mut file = file.map_err(|e| Failure::Fatal(format!("write traineddata failed: {e}")))?;
+  let total = res.content_length().map(|n| n + start);
+  let _ = on_event.send(DownloadEvent::Started {
+    total,
+    resumed_from: start,
+  });
+
+  let began = Instant::now();
+  let mut reported = began;
+  let mut downloaded = start;
+  let mut stream = res.bytes_stream();
+  while let Some(chunk) = stream.next().await {
+    let chunk = chunk.map_err(|e| Failure::Retry(format!("download read failed: {e}")))?;
+    file
+      .write_all(&chunk)
+      .map_err(|e| Failure::Fatal(format!("write traineddata failed: {e}")))?;
+    downloaded += chunk.len() as u64;
+    if reported.elapsed() >= PROGRESS_INTERVAL || Some(downloaded) == total {
+      reported = Instant::now();
+      let secs = began.elapsed().as_secs_f64().max(0.001);
+      let _ = on_event.send(DownloadEvent::Progress {
+        downloaded,
+        total,
+        bytes_per_sec: ((downloaded - start) as f64 / secs) as u64,
+      });
+    }
+  }
+  file
+    .flush()
+    .map_err(|e| Failure::Fatal(format!("write traineddata failed: {e}")))?;
+  if total.is_some_and(|t| downloaded < t) {
+    return Err(Failure::Retry("download interrupted".to_string()));
+  }
+  Ok(())
+}
+
+/// What a downloaded file must hash to.
+enum Expected {
+  /// The caller's SHA-256.
+  Sha256(String),
+  /// The git blob SHA-1 GitHub lists for the file.
+  GitBlob(String),
+}
+
+/// The git blob hash GitHub lists for `<lang>.traineddata` in `variant`'s repository.
+async fn listed_blob_hash(client: &reqwest::Client, variant: Variant, lang: &str) -> Result<String, String> {
+  #[derive(Deserialize)]
+  struct Content {
+    sha: String,
+  }
+  let url = format!(
+    "https://api.github.com/repos/tesseract-ocr/{}/contents/{lang}.traineddata?ref=main",
+    variant.repository()
+  );
+  let unavailable =
+    |e: reqwest::Error| format!("TESSDATA_CHECKSUM_UNAVAILABLE\n\nno checksum for {lang}.traineddata: {e}");
+  let content: Content = client
+    .get(&url)
+    .header(reqwest::header::USER_AGENT, "erudaite-desktop")
+    .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+    .send()
+    .await
+    .and_then(|r| r.error_for_status())
+    .map_err(unavailable)?
+    .json()
+    .await
+    .map_err(unavailable)?;
+  Ok(content.sha.to_lowercase())
+}
+
+/// Git's blob hash of the file at `path` (SHA-1 over `blob <size>\0` and the contents).
+fn git_blob_file(path: &Path) -> Result<String, String> {
+  let mut file = std::fs::File::open(path).map_err(|e| format!("read traineddata failed: {e}"))?;
+  let size = file.metadata().map_err(|e| format!("read traineddata failed: {e}"))?.len();
+  let mut hasher = sha1::Sha1::new();
+  hasher.update(format!("blob {size}\0"));
+  std::io::copy(&mut file, &mut hasher).map_err(|e| format!("read traineddata failed: {e}"))?;
+  Ok(hex(&hasher.finalize()))
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+  let mut file = std::fs::File::open(path).map_err(|e| format!("read traineddata failed: {e}"))?;
+  let mut hasher = Sha256::new();
+  std::io::copy(&mut file, &mut hasher).map_err(|e| format!("read traineddata failed: {e}"))?;
+  Ok(hex(&hasher.finalize()))
+}
+
+async fn download(
+  app: &tauri::AppHandle,
+  lang: &str,
+  variant: Option<Variant>,
+  sha256: Option<String>,
+  on_event: &Channel<DownloadEvent>,
+) -> Result<String, String> {
+  let lang = validate_lang(lang)?;
+  let variant = variant.unwrap_or_else(|| default_variant(app));
+  let base = install_dir()?;
+  std::fs::create_dir_all(&base).map_err(|e| format!("create dir failed: {e}"))?;
+  let url = format!(
+    "https://github.com/tesseract-ocr/{}/raw/main/{}.traineddata",
+    variant.repository(),
+    lang
+  );
+  // Kept across calls so a failed download resumes; named per variant so the two never mix.
+  let part = base.join(format!("{lang}.traineddata.{}.part", variant.repository()));
+
+  let client = reqwest::Client::builder()
+    .connect_timeout(Duration::from_secs(30))
+    // Per read, not for the whole body: the best models take minutes on a slow line.
+    .read_timeout(Duration::from_secs(60))
+    .build()
+    .map_err(|e| format!("client build failed: {e}"))?;
+  let expected = match sha256.map(|s| s.trim().to_lowercase()).filter(|s| !s.is_empty()) {
+    Some(sha256) => Expected::Sha256(sha256),
+    None => Expected::GitBlob(listed_blob_hash(&client, variant, &lang).await?),
+  };
+  let mut attempt = 1;
+  loop {
+    match fetch(&client, &url, &part, on_event).await {
+      Ok(()) => break,
+      Err(Failure::Retry(e)) if attempt < DOWNLOAD_ATTEMPTS => {
+        log::warn!("{e}; retrying ({attempt}/{DOWNLOAD_ATTEMPTS})");
+        tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+        attempt += 1;
+      }
+      Err(Failure::Retry(e) | Failure::Fatal(e)) => return Err(e),
+    }
+  }
+
+  let digest = sha256_file(&part)?;
+  let (kind, expected, actual) = match expected {
+    Expected::Sha256(expected) => ("SHA-256", expected, digest.clone()),
+    Expected::GitBlob(expected) => ("git blob hash", expected, git_blob_file(&part)?),
+  };
+  if expected != actual {
+    // Not worth resuming.
+    let _ = std::fs::remove_file(&part);
+    return Err(format!(
+      "TESSDATA_CHECKSUM_MISMATCH\n\n{lang}.traineddata has {kind} {actual}, expected {expected}"
+    ));
+  }
+  // Renamed only once complete, so a failed download never leaves a truncated model in use.
+  std::fs::rename(&part, base.join(format!("{lang}.traineddata")))
+    .map_err(|e| format!("write traineddata failed: {e}"))?;
+
+  let mut manifest = read_manifest(&base);
+  manifest.insert(lang, ManifestEntry { variant, sha256: digest });
+  write_manifest(&base, &manifest)?;
+
+  // TESSDATA_PREFIX should point to the tessdata directory.
+  Ok(base.to_string_lossy().to_string())
+}
+
+/// Download `<lang>.traineddata` (`variant` overrides the `tessdataVariant` setting) and return the directory to
+/// use as `TESSDATA_PREFIX`. Progress streams through `on_event`; an interrupted download is retried and resumed
+/// from where it stopped, also by the next call. The file is checked against `sha256`, or else the hash GitHub
+/// lists for it (`TESSDATA_CHECKSUM_UNAVAILABLE` when there's none); one whose hash differs is discarded
+/// (`TESSDATA_CHECKSUM_MISMATCH`).
+#[tauri::command]
+pub async fn download_tessdata(
+  app: tauri::AppHandle,
+  lang: String,
+  variant: Option<Variant>,
+  sha256: Option<String>,
+  on_event: Channel<DownloadEvent>,
+) -> Result<String, String> {
+  let result = download(&app, &lang, variant, sha256, &on_event).await;
+  let _ = on_event.send(match &result {
+    Ok(prefix) => DownloadEvent::Done { prefix: prefix.clone() },
+    Err(e) => DownloadEvent::Error { message: e.clone() },
+  });
+  result
+}
+
+/// Language data in the app's tessdata directory, with the variant and hash of the ones it downloaded.
+#[tauri::command]
+pub fn list_installed_tessdata() -> Result<Vec<InstalledTessdata>, String> {
+  let dir = install_dir()?;
+  let Ok(entries) = std::fs::read_dir(&dir) else {
+    return Ok(Vec::new());
+  };
+  let manifest = read_manifest(&dir);
+  let mut installed: Vec<InstalledTessdata> = entries
+    .flatten()
+    .filter_map(|e| {
+      let name = e.file_name().to_string_lossy().to_string();
+      let lang = name.strip_suffix(".traineddata")?.to_string();
+      let entry = manifest.get(&lang);
+      Some(InstalledTessdata {
+        path: e.path().to_string_lossy().to_string(),
+        size: e.metadata().map(|m| m.len()).unwrap_or(0),
+        variant: entry.map(|m| m.variant),
+        sha256: entry.map(|m| m.sha256.clone()),
+        lang,
+      })
+    })
+    .collect();
+  installed.sort_by(|a, b| a.lang.cmp(&b.lang));
+  Ok(installed)
+}
+
+/// Remove a language's traineddata from the app's tessdata directory. Returns whether it was there.
+#[tauri::command]
+pub fn delete_tessdata(lang: String) -> Result<bool, String> {
+  let lang = validate_lang(&lang)?;
+  let dir = install_dir()?;
+  let existed = match std::fs::remove_file(dir.join(format!("{lang}.traineddata"))) {
+    Ok(()) => true,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+    Err(e) => return Err(format!("delete traineddata failed: {e}")),
+  };
+  let mut manifest = read_manifest(&dir);
+  if manifest.remove(&lang).is_some() {
+    write_manifest(&dir, &manifest)?;
+  }
+  Ok(existed)
+}
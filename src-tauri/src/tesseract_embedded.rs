@@ -0,0 +1,72 @@
+//! In-process Tesseract through leptess (`embedded-tesseract` feature).
+//!
+//! Same traineddata as the external binary: the configured `tessdataPrefix`, else the directory
+//! `download_tessdata` installs into, else `TESSDATA_PREFIX` / the library's compiled-in default.
+
+use leptess::{leptonica, tesseract::TessApi};
+use std::ffi::CString;
+use std::path::PathBuf;
+
+use crate::ocr::{self, OcrImage, OcrOutput, TesseractOptions};
+
+/// Directory holding `*.traineddata` for the embedded engine, if one is known.
+pub fn tessdata_dir(tessdata_prefix: Option<&str>) -> Option<PathBuf> {
+  if let Some(p) = tessdata_prefix.map(str::trim).filter(|s| !s.is_empty()) {
+    return Some(PathBuf::from(crate::commands::normalize_tessdata_prefix(p)));
+  }
+  if let Some(dir) = crate::tessdata::install_dir().ok().filter(|d| d.is_dir()) {
+    return Some(dir);
+  }
+  std::env::var("TESSDATA_PREFIX").ok().map(PathBuf::from)
+}
+
+/// Languages with a traineddata file in the embedded engine's tessdata directory.
+pub fn list_langs(tessdata_prefix: Option<&str>) -> Vec<String> {
+  let Some(dir) = tessdata_dir(tessdata_prefix) else {
+    return Vec::new();
+  };
+  let mut langs: Vec<String> = std::fs::read_dir(dir)
+    .map(|entries| {
+      entries
+        .flatten()
+        .filter_map(|e| {
+          let name = e.file_name().to_string_lossy().to_string();
+          name.strip_suffix(".traineddata").map(str::to_string)
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+  langs.sort();
+  langs
+}
+
+/// OCR an image in-process. Blocking.
+pub fn recognize(
+  image: &OcrImage,
+  lang: &str,
+  tessdata_prefix: Option<&str>,
+  options: &TesseractOptions,
+) -> Result<OcrOutput, String> {
+  let dir = tessdata_dir(tessdata_prefix);
+  let dir = dir.as_ref().map(|d| d.to_string_lossy().to_string());
+  let mut api = TessApi::new(dir.as_deref(), lang)
+    // Init fails when a language's traineddata can't be loaded.
+    .map_err(|e| format!("TESSDATA_MISSING\n\n{e}"))?;
+  for (name, value) in options.config_variables() {
+    let (Ok(n), Ok(v)) = (CString::new(name.as_str()), CString::new(value)) else {
+      return Err(format!("invalid config variable: {name}"));
+    };
+    api
+      .raw
+      .set_variable(&n, &v)
+      .map_err(|_| format!("tesseract failed: unknown config variable {name}"))?;
+  }
+  let pix = match image {
+    OcrImage::Path(p) => leptonica::pix_read(std::path::Path::new(p)),
+    OcrImage::Bytes(b) => leptonica::pix_read_mem(b),
+  }
+  .map_err(|e| format!("tesseract failed: cannot read image: {e}"))?;
+  api.set_image(&pix);
+  let tsv = api.get_tsv_text(0).map_err(|e| format!("tesseract failed: {e}"))?;
+  Ok(ocr::parse_tsv(&tsv))
+}
@@ -0,0 +1,70 @@
+//! Tone mapping for captures of HDR displays.
+//!
+//! With HDR on, Windows composites the desktop in a wide-gamut linear space and GDI hands back an SDR conversion
+//! that looks washed out (lifted blacks) or dim (white well below 255), which costs OCR its contrast. A levels
+//! stretch taken from the luminance histogram puts the darkest and brightest content back at black and white.
+
+/// Share of pixels allowed to clip at either end of the stretch.
+const CLIP: f64 = 0.005;
+
+/// Rec. 601 luma of an RGB pixel.
+pub fn luma(r: u8, g: u8, b: u8) -> u8 {
+  ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+/// Add the luma of each pixel of `rgb` to `hist`.
+pub fn add_to_histogram(hist: &mut [u64; 256], rgb: &[u8]) {
+  for px in rgb.chunks_exact(3) {
+    hist[luma(px[0], px[1], px[2]) as usize] += 1;
+  }
+}
+
+/// Lookup table stretching the histogram's range to 0..=255, or `None` when the image already spans it (or is
+/// nearly flat, where stretching would only amplify noise).
+pub fn levels(hist: &[u64; 256]) -> Option<[u8; 256]> {
+  let total: u64 = hist.iter().sum();
+  if total == 0 {
+    return None;
+  }
+  let clip = (total as f64 * CLIP) as u64;
+  let mut seen = 0;
+  let lo = hist.iter().position(|&n| {
+    seen += n;
+    seen > clip
+  })?;
+  seen = 0;
+  let hi = 255 - hist.iter().rev().position(|&n| {
+    seen += n;
+    seen > clip
+  })?;
+  if hi < lo + 32 || (lo <= 4 && hi >= 251) {
+    return None;
+  }
+  let mut lut = [0u8; 256];
+  for (v, out) in lut.iter_mut().enumerate() {
+    *out = ((v.saturating_sub(lo)) * 255 / (hi - lo)).min(255) as u8;
+  }
+  Some(lut)
+}
+
+/// Map every channel of `rgb` through `lut`.
+pub fn apply(lut: &[u8; 256], rgb: &mut [u8]) {
+  for c in rgb {
+    *c = lut[*c as usize];
+  }
+}
+
+/// Tone map an RGB PNG in place (decoded whole; used where captures aren't streamed).
+#[cfg_attr(windows, allow(dead_code))]
+pub fn apply_to_png(path: &str) -> Result<(), String> {
+  let image = crate::image_io::read_rgb(path)?;
+  let (width, height) = image.dimensions();
+  let mut rgb = image.into_raw();
+  let mut hist = [0u64; 256];
+  add_to_histogram(&mut hist, &rgb);
+  let Some(lut) = levels(&hist) else {
+    return Ok(());
+  };
+  apply(&lut, &mut rgb);
+  crate::image_io::write_png(std::path::Path::new(path), &rgb, width, height)
+}
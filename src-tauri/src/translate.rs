@@ -0,0 +1,1199 @@
+//! Translation pipeline shared by `translate_sse` and backend callers (scripts, pipelines).
+//!
+//! Events go to an `EventSink` closure instead of a `Channel` so internal callers can collect or ignore them.
+
+use crate::chunking;
+use crate::commands::{normalize_base_url, StreamEvent};
+use crate::events;
+use crate::glossary::{self, Term};
+use crate::history;
+use crate::http;
+use crate::language_pairs;
+use crate::offline_mt;
+use crate::output::{self, Enforcement, OutputControls};
+use crate::plugins::{self, PluginInput, PluginRegistry, PluginStage};
+use crate::protect;
+use crate::queue::{Priority, RequestQueue};
+use crate::sections::{Piece, SectionSplitter};
+use crate::settings;
+use crate::usage::{self, TokenPricing, UsageState};
+use tauri::Manager;
+
+pub type EventSink<'a> = &'a (dyn Fn(StreamEvent) + Send + Sync);
+
+#[derive(Debug, Clone, Default)]
+pub struct TranslateRequest {
+  pub base_url: String,
+  pub text: String,
+  pub target_lang: String,
+  pub mode: String,
+  pub explanation_lang: String,
+  pub is_reverse: bool,
+  pub pricing: Option<TokenPricing>,
+  pub priority: Priority,
+  /// Per-request overrides; unset fields fall back to the mode's settings.
+  pub output: OutputControls,
+  /// Number of alternative translations wanted (0 = none, at most `MAX_ALTERNATIVES`).
+  pub alternatives: usize,
+  /// Glossary terms found in the text; filled in by the pipeline (see `glossary`).
+  pub glossary: Vec<Term>,
+}
+
+pub const MAX_ALTERNATIVES: usize = 5;
+
+/// Queue, translate and stream one request. Returns the final output text.
+pub async fn run_translation(app: &tauri::AppHandle, req: TranslateRequest, emit: EventSink<'_>) -> Result<String, String> {
+  // Before the queue wait, while the app the text came from is likely still in front.
+  let origin_app = (req.priority == Priority::Interactive).then(|| history::origin_app(app)).flatten();
+  let request_queue = app.state::<RequestQueue>();
+  let ticket = request_queue.enqueue(req.priority, "translate");
+  let request_id = ticket.id.clone();
+  let job_event = |kind: &str| {
+    events::publish(
+      app,
+      kind,
+      Some(&request_id),
+      serde_json::json!({ "label": "translate", "priority": req.priority }),
+    );
+  };
+  // Mirror stream events onto the bus (`stream.delta`, `stream.done`, ...).
+  let emit = &|ev: StreamEvent| {
+    let payload = serde_json::to_value(&ev).unwrap_or_default();
+    let kind = payload.get("type").and_then(|x| x.as_str()).unwrap_or("unknown");
+    events::publish(app, &format!("stream.{kind}"), Some(&request_id), payload.clone());
+    emit(ev);
+  };
+
+  job_event("job.queued");
+  emit(StreamEvent::Queued {
+    request_id: request_id.clone(),
+  });
+  let job = match ticket.wait_turn().await {
+    Ok(job) => job,
+    Err(e) => {
+      job_event("job.cancelled");
+      emit(StreamEvent::Cancelled);
+      return Err(e);
+    }
+  };
+  job_event("job.started");
+
+  let result = tokio::select! {
+    r = translate_inner(app, &req, emit) => r,
+    _ = job.cancel.cancelled() => {
+      emit(StreamEvent::Cancelled);
+      Err("CANCELLED".to_string())
+    }
+  };
+  match &result {
+    Ok(translation) => {
+      job_event("job.finished");
+      events::publish(
+        app,
+        "translation.done",
+        Some(&request_id),
+        serde_json::json!({ "source": req.text, "translation": translation, "target_lang": req.target_lang }),
+      );
+      if req.priority == Priority::Interactive {
+        let source_lang = crate::langdetect::detect(&req.text).map(|d| d.detected_lang);
+        language_pairs::record(app, source_lang.as_deref(), &req.target_lang, &req.mode);
+        history::record(
+          app,
+          history::NewEntry {
+            source: &req.text,
+            translation,
+            source_lang,
+            target_lang: &req.target_lang,
+            mode: &req.mode,
+            provider: provider_name(&req.base_url),
+            origin_app,
+          },
+        );
+      }
+    }
+    Err(e) if e == "CANCELLED" => job_event("job.cancelled"),
+    Err(_) => job_event("job.failed"),
+  }
+  result
+}
+
+/// Who translated with `base_url`, for the history and usage statistics: a provider plugin's id, "offline", or
+/// the server's host.
+fn provider_name(base_url: &str) -> String {
+  let base = normalize_base_url(base_url);
+  if let Some(id) = plugins::provider_id(&base) {
+    return id.to_string();
+  }
+  if offline_mt::is_offline(&base) {
+    return "offline".to_string();
+  }
+  reqwest::Url::parse(&base)
+    .ok()
+    .and_then(|u| u.host_str().map(str::to_string))
+    .unwrap_or(base)
+}
+
+async fn translate_inner(app: &tauri::AppHandle, req: &TranslateRequest, emit: EventSink<'_>) -> Result<String, String> {
+  let req = &TranslateRequest {
+    output: req.output.clone().or(settings::output_controls(app, &req.mode)),
+    glossary: glossary::terms_for(app, &req.text, &req.target_lang),
+    ..req.clone()
+  };
+  let limit = req.output.limit();
+  let plugin_registry = app.state::<PluginRegistry>();
+  let base = normalize_base_url(&req.base_url);
+  let text = match plugins::apply_stage(
+    &plugin_registry,
+    PluginStage::PreProcess,
+    req.text.clone(),
+    &req.target_lang,
+    &req.mode,
+  )
+  .await
+  {
+    Ok(text) => text,
+    Err(e) => {
+      emit(StreamEvent::Error { message: e.clone() });
+      return Err(e);
+    }
+  };
+
+  // Keep URLs, code, format variables, tags and emoji away from the model; deltas are unmasked on the way out.
+  let (text, masks) = if settings::protect_placeholders(app) {
+    protect::mask(&text)
+  } else {
+    (text, protect::Masks::default())
+  };
+  let unmasking = Unmasking::new(&masks);
+  let raw_emit = emit;
+  let emit: EventSink<'_> = &|ev: StreamEvent| {
+    if masks.is_empty() {
+      return raw_emit(ev);
+    }
+    unmasking.forward(ev, raw_emit);
+  };
+
+  let mut st = SseState::default();
+  let mut sample = false;
+  if let Some(id) = plugins::provider_id(&base) {
+    let plugin = plugins::provider(&plugin_registry, id)?;
+    let input = PluginInput {
+      stage: PluginStage::Provider,
+      text: &text,
+      target_lang: &req.target_lang,
+      mode: &req.mode,
+      glossary: &req.glossary,
+    };
+    let out = match plugins::run(plugin, input).await {
+      Ok(out) => out,
+      Err(e) => {
+        emit(StreamEvent::Error { message: e.clone() });
+        return Err(e);
+      }
+    };
+    emit_content(&mut st, emit, &out);
+  } else if offline_mt::is_offline(&base) {
+    let engine = settings::offline_engine_path(app);
+    match offline_mt::translate(engine.as_deref(), &text, &req.target_lang).await {
+      Ok(out) => emit_content(&mut st, emit, &out),
+      Err(e) => {
+        emit(StreamEvent::Error { message: e.clone() });
+        return Err(e);
+      }
+    }
+  } else {
+    let chunks = chunking::split_text(&text, settings::max_chunk_chars(app));
+    if chunks.len() > 1 {
+      if req.alternatives > 0 {
+        emit(warning(
+          "alternatives_unsupported",
+          "Alternative translations aren't available for text long enough to be split into chunks.",
+        ));
+      }
+      translate_chunks(app, &base, req, &chunks, &mut st, emit).await?;
+    } else {
+      // Stop streaming once the limit is reached instead of reading the rest of a wall of text.
+      st.limit = limit;
+      translate_via_backend(app, &base, req, &text, &mut st, emit).await?;
+      sample = req.alternatives > 0;
+    }
+  }
+  let rest: Vec<Piece> = st.sections.flush().into_iter().collect();
+  emit_pieces(rest, &mut st, emit);
+  if sample {
+    sample_alternatives(app, &base, req, &text, &mut st, emit).await;
+  }
+
+  if let Some(limit) = limit {
+    enforce_limit(app, &base, req, &text, limit, &mut st, emit).await;
+  }
+  if !masks.is_empty() {
+    let missing = masks.missing(&st.output_text);
+    if !missing.is_empty() {
+      log::warn!("provider dropped {} protected span(s): {:?}", missing.len(), missing);
+      emit(warning(
+        "placeholders_dropped",
+        format!("The translation is missing {} protected item(s): {}", missing.len(), missing.join(", ")),
+      ));
+    }
+    st.output_text = masks.restore(&st.output_text);
+  }
+  for (index, content) in std::mem::take(&mut st.variants) {
+    let content = masks.restore(content.trim());
+    emit(StreamEvent::Variant { index, content });
+  }
+
+  // Post-process plugins see the whole output; the frontend replaces the streamed text when it changed.
+  if !plugin_registry.enabled_for(PluginStage::PostProcess).is_empty() {
+    let processed = plugins::apply_stage(
+      &plugin_registry,
+      PluginStage::PostProcess,
+      st.output_text.clone(),
+      &req.target_lang,
+      &req.mode,
+    )
+    .await;
+    let processed = match processed {
+      Ok(processed) => processed,
+      Err(e) => {
+        emit(StreamEvent::Error { message: e.clone() });
+        return Err(e);
+      }
+    };
+    if processed != st.output_text {
+      st.output_text = processed.clone();
+      emit(StreamEvent::Replace { content: processed });
+    }
+  }
+
+  let (u, estimated) = usage::finalize(st.reported, &text, &st.output_text, req.pricing);
+  app.state::<UsageState>().record(app, &provider_name(&base), req.text.chars().count(), &u, estimated);
+  emit(StreamEvent::Usage {
+    input_tokens: u.input_tokens,
+    output_tokens: u.output_tokens,
+    estimated_cost: u.estimated_cost,
+  });
+  emit(StreamEvent::Done);
+  Ok(st.output_text)
+}
+
+/// Puts protected spans back into the events of a masked request (see `protect`) on their way to the sink.
+struct Unmasking<'a> {
+  masks: &'a protect::Masks,
+  main: std::sync::Mutex<protect::Unmasker>,
+  variants: std::sync::Mutex<std::collections::HashMap<usize, protect::Unmasker>>,
+}
+
+impl<'a> Unmasking<'a> {
+  fn new(masks: &'a protect::Masks) -> Self {
+    Self {
+      masks,
+      main: Default::default(),
+      variants: Default::default(),
+    }
+  }
+
+  fn forward(&self, ev: StreamEvent, emit: EventSink<'_>) {
+    let masks = self.masks;
+    let mut unmasker = self.main.lock().unwrap_or_else(|e| e.into_inner());
+    match ev {
+      StreamEvent::Delta { content } => {
+        let content = unmasker.feed(masks, &content);
+        if !content.is_empty() {
+          emit(StreamEvent::Delta { content });
+        }
+      }
+      StreamEvent::Replace { content } => {
+        unmasker.flush(masks);
+        emit(StreamEvent::Replace {
+          content: masks.restore(&content),
+        });
+      }
+      StreamEvent::VariantDelta { index, content } => {
+        let mut unmaskers = self.variants.lock().unwrap_or_else(|e| e.into_inner());
+        let content = unmaskers.entry(index).or_default().feed(masks, &content);
+        if !content.is_empty() {
+          emit(StreamEvent::VariantDelta { index, content });
+        }
+      }
+      StreamEvent::Variant { .. } => emit(ev),
+      other => {
+        let held = unmasker.flush(masks);
+        if !held.is_empty() {
+          emit(StreamEvent::Delta { content: held });
+        }
+        emit(other);
+      }
+    }
+  }
+}
+
+fn warning(code: &str, message: impl Into<String>) -> StreamEvent {
+  StreamEvent::Warning {
+    code: code.to_string(),
+    message: message.into(),
+  }
+}
+
+/// Bring over-long output within `limit`: re-request once with stricter constraints when configured (and
+/// the server is the provider), otherwise or if still too long, truncate. Emits `Replace` when shortened.
+async fn enforce_limit(
+  app: &tauri::AppHandle,
+  base: &str,
+  req: &TranslateRequest,
+  text: &str,
+  limit: usize,
+  st: &mut SseState,
+  emit: EventSink<'_>,
+) {
+  if !st.truncated && st.output_text.chars().count() <= limit {
+    return;
+  }
+  let mut shortened: Option<String> = None;
+  let is_backend = plugins::provider_id(base).is_none() && !offline_mt::is_offline(base);
+  if req.output.enforce.unwrap_or_default() == Enforcement::Rerequest && is_backend {
+    let retry = TranslateRequest {
+      output: req.output.stricter(),
+      alternatives: 0,
+      ..req.clone()
+    };
+    let ignore = |_: StreamEvent| {};
+    let mut rst = SseState::default();
+    match translate_via_backend(app, base, &retry, text, &mut rst, &ignore).await {
+      Ok(()) => {
+        let rest: Vec<Piece> = rst.sections.flush().into_iter().collect();
+        emit_pieces(rest, &mut rst, &ignore);
+        st.reported = usage::ReportedUsage::sum(&[st.reported, rst.reported]);
+        shortened = Some(rst.output_text);
+      }
+      Err(e) => emit(warning(
+        "rerequest_failed",
+        format!("Asking for a shorter answer failed ({e}); the output was cut instead."),
+      )),
+    }
+  }
+  let content = match shortened {
+    Some(s) if s.chars().count() <= limit => s,
+    // Leave room for the ellipsis that marks the cut.
+    s => output::truncate(s.as_deref().unwrap_or(&st.output_text), limit.saturating_sub(1)),
+  };
+  if content != st.output_text {
+    if content.ends_with('…') {
+      emit(warning(
+        "output_truncated",
+        format!("The output was cut to the {limit}-character limit."),
+      ));
+    }
+    st.output_text = content.clone();
+    emit(StreamEvent::Replace { content });
+  }
+}
+
+fn backend_body(req: &TranslateRequest, text: &str) -> serde_json::Value {
+  let mut body = serde_json::json!({
+    "text": text,
+    "target_lang": req.target_lang,
+    "mode": req.mode,
+    "explanation_lang": req.explanation_lang,
+    "skip_points": true
+  });
+  if req.is_reverse {
+    body["is_reverse"] = serde_json::Value::Bool(true);
+  }
+  if req.alternatives > 0 {
+    body["alternatives"] = serde_json::json!(req.alternatives);
+  }
+  if !req.glossary.is_empty() {
+    body["glossary"] = serde_json::json!(req.glossary);
+  }
+  req.output.apply_to_body(&mut body);
+  body
+}
+
+/// Fill in alternatives the server didn't send (it ignored `alternatives`) by sampling extra requests in
+/// parallel, each streamed under its own index. Duplicates of the main output or of each other are dropped (an
+/// empty `Variant` tells the frontend).
+async fn sample_alternatives(
+  app: &tauri::AppHandle,
+  base: &str,
+  req: &TranslateRequest,
+  text: &str,
+  st: &mut SseState,
+  emit: EventSink<'_>,
+) {
+  let wanted = req.alternatives.min(MAX_ALTERNATIVES);
+  let have = st.variants.len();
+  if have >= wanted {
+    return;
+  }
+  let samples = (have + 1..=wanted).map(|index| {
+    let mut body = backend_body(req, text);
+    body["alternatives"] = serde_json::json!(0);
+    body["sample"] = serde_json::json!(index);
+    async move {
+      let tagged = |ev: StreamEvent| {
+        if let StreamEvent::Delta { content } = ev {
+          emit(StreamEvent::VariantDelta { index, content });
+        }
+      };
+      let mut vst = SseState::default();
+      let r = stream_from_backend(app, base, &body, &mut vst, &tagged).await;
+      let rest: Vec<Piece> = vst.sections.flush().into_iter().collect();
+      emit_pieces(rest, &mut vst, &tagged);
+      (index, r.map(|_| vst))
+    }
+  });
+  for (index, r) in futures_util::future::join_all(samples).await {
+    match r {
+      Ok(vst) => {
+        let candidate = vst.output_text.trim();
+        let duplicate = candidate.is_empty()
+          || candidate == st.output_text.trim()
+          || st.variants.values().any(|v| v.trim() == candidate);
+        st.reported = usage::ReportedUsage::sum(&[st.reported, vst.reported]);
+        if duplicate {
+          emit(StreamEvent::Variant { index, content: String::new() });
+        } else {
+          st.variants.insert(index, vst.output_text);
+        }
+      }
+      Err(e) => {
+        log::warn!("alternative translation failed: {e}");
+        emit(StreamEvent::Variant { index, content: String::new() });
+      }
+    }
+  }
+  if st.variants.len() < wanted {
+    emit(warning(
+      "alternatives_incomplete",
+      format!("Only {} of {wanted} alternative translations are available.", st.variants.len()),
+    ));
+  }
+}
+
+/// Translate one request-sized text through the server, falling back to the offline engine when unreachable.
+async fn translate_via_backend(
+  app: &tauri::AppHandle,
+  base: &str,
+  req: &TranslateRequest,
+  text: &str,
+  st: &mut SseState,
+  emit: EventSink<'_>,
+) -> Result<(), String> {
+  let body = backend_body(req, text);
+  let Err(e) = stream_from_backend(app, base, &body, st, emit).await else {
+    return Ok(());
+  };
+  // Server unreachable before anything streamed: fall back to the offline engine when a model exists.
+  if !e.starts_with("request failed") || !st.output_text.is_empty() {
+    return Err(e);
+  }
+  let engine = settings::offline_engine_path(app);
+  match offline_mt::translate(engine.as_deref(), text, &req.target_lang).await {
+    Ok(out) => {
+      events::publish(app, "translation.fallback", None, serde_json::json!({ "reason": e, "engine": "offline" }));
+      emit(warning(
+        "fallback_offline",
+        format!("The server was unreachable ({e}); translated with the offline engine."),
+      ));
+      emit_content(st, emit, &out);
+      Ok(())
+    }
+    Err(fallback_err) => {
+      log::warn!("offline fallback unavailable: {fallback_err}");
+      Err(e)
+    }
+  }
+}
+
+/// Translate chunk by chunk, emitting `Chunk` boundaries and deltas in source order.
+///
+/// With concurrency 1 chunks stream live; otherwise chunks run in parallel and each chunk's events are
+/// buffered until all earlier chunks have been emitted.
+async fn translate_chunks(
+  app: &tauri::AppHandle,
+  base: &str,
+  req: &TranslateRequest,
+  chunks: &[String],
+  st: &mut SseState,
+  emit: EventSink<'_>,
+) -> Result<(), String> {
+  let total = chunks.len();
+  let concurrency = settings::chunk_concurrency(app);
+  let mut reports: Vec<Option<usage::ReportedUsage>> = Vec::new();
+
+  if concurrency <= 1 {
+    for (index, chunk) in chunks.iter().enumerate() {
+      emit(StreamEvent::Chunk { index, total });
+      let mut cst = SseState::default();
+      translate_via_backend(app, base, req, chunk, &mut cst, emit).await?;
+      finish_chunk(chunk, &mut cst, emit);
+      st.output_text.push_str(&cst.output_text);
+      reports.push(cst.reported);
+    }
+  } else {
+    use futures_util::StreamExt;
+    // Collect first: a lazily-mapped iterator inside the stream trips the Send check on the command future.
+    let futures: Vec<_> = chunks
+      .iter()
+      .map(|chunk| translate_chunk_buffered(app, base, req, chunk))
+      .collect();
+    let mut results = futures_util::stream::iter(futures).buffered(concurrency);
+
+    let mut index = 0;
+    while let Some((r, cst, events)) = results.next().await {
+      emit(StreamEvent::Chunk { index, total });
+      for ev in events {
+        emit(ev);
+      }
+      r?;
+      st.output_text.push_str(&cst.output_text);
+      reports.push(cst.reported);
+      index += 1;
+    }
+  }
+
+  st.reported = usage::ReportedUsage::sum(&reports);
+  Ok(())
+}
+
+/// Translate one chunk, collecting its events for in-order emission.
+async fn translate_chunk_buffered(
+  app: &tauri::AppHandle,
+  base: &str,
+  req: &TranslateRequest,
+  chunk: &str,
+) -> (Result<(), String>, SseState, Vec<StreamEvent>) {
+  let events = std::sync::Mutex::new(Vec::new());
+  let sink = |ev: StreamEvent| events.lock().unwrap_or_else(|e| e.into_inner()).push(ev);
+  let mut cst = SseState::default();
+  let r = translate_via_backend(app, base, req, chunk, &mut cst, &sink).await;
+  if r.is_ok() {
+    finish_chunk(chunk, &mut cst, &sink);
+  }
+  (r, cst, events.into_inner().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Flush held-back text and keep paragraph breaks that fall on the chunk boundary.
+fn finish_chunk(chunk: &str, cst: &mut SseState, emit: EventSink<'_>) {
+  let rest: Vec<Piece> = cst.sections.flush().into_iter().collect();
+  emit_pieces(rest, cst, emit);
+  let tail = &chunk[chunk.trim_end().len()..];
+  if tail.contains('\n') && !cst.output_text.ends_with('\n') {
+    let breaks: String = tail.chars().filter(|c| *c == '\n').collect();
+    emit_pieces(vec![Piece::Text(breaks)], cst, emit);
+  }
+}
+
+/// Reconnect attempts after the stream drops mid-translation.
+const MAX_RESUME_ATTEMPTS: u32 = 3;
+const RESUME_BACKOFF_MS: u64 = 500;
+
+/// Why a single streaming attempt stopped early.
+struct StreamFailure {
+  message: String,
+  /// Transport-level drop that a resume request may recover from.
+  retryable: bool,
+}
+
+/// POST to `/api/translate` and forward SSE frames until `[DONE]` or end of stream.
+///
+/// If the connection drops mid-stream, the request is resent with a `Last-Event-ID` header (when the
+/// server tagged its frames with `id:`) and a `resume` object carrying the target text produced so far, so
+/// the server can continue where it stopped. Text the server repeats from the start is skipped, so the
+/// sink sees one uninterrupted stream.
+async fn stream_from_backend(
+  app: &tauri::AppHandle,
+  base: &str,
+  body: &serde_json::Value,
+  st: &mut SseState,
+  emit: EventSink<'_>,
+) -> Result<(), String> {
+  #[cfg(feature = "deterministic")]
+  if crate::mock::is_mock(base) {
+    let text = body.get("text").and_then(|x| x.as_str()).unwrap_or_default();
+    let target_lang = body.get("target_lang").and_then(|x| x.as_str()).unwrap_or_default();
+    let mut lines = crate::mock::translate_sse_lines(text, target_lang);
+    // End of stream terminates a pending record, like a trailing blank line.
+    lines.push(String::new());
+    for line in lines {
+      match handle_sse_line(&line, st, emit) {
+        SseLine::Continue if st.truncated => break,
+        SseLine::Continue => {}
+        SseLine::Done => break,
+        SseLine::Error(e) => return Err(e),
+      }
+    }
+    return Ok(());
+  }
+
+  let client = http::client();
+  let mut attempt = 0u32;
+  loop {
+    let failure = match stream_once(app, &client, base, body, attempt, st, emit).await {
+      Ok(true) => {
+        end_replay(st, emit);
+        return Ok(());
+      }
+      // Without event ids there's no way to tell a truncated stream from one that omits `[DONE]`.
+      Ok(false) if st.last_event_id.is_none() => {
+        end_replay(st, emit);
+        return Ok(());
+      }
+      Ok(false) => "stream ended before completion".to_string(),
+      Err(f) if !f.retryable => {
+        end_replay(st, emit);
+        return Err(f.message);
+      }
+      Err(f) => f.message,
+    };
+    if attempt >= MAX_RESUME_ATTEMPTS {
+      end_replay(st, emit);
+      emit(StreamEvent::Error {
+        message: failure.clone(),
+      });
+      return Err(failure);
+    }
+    attempt += 1;
+    log::warn!("translation stream interrupted ({failure}); resuming, attempt {attempt}");
+    emit(StreamEvent::Reconnecting { attempt });
+    tokio::time::sleep(std::time::Duration::from_millis(RESUME_BACKOFF_MS * attempt as u64)).await;
+    // Keep any unconfirmed replay from the previous attempt; it's re-checked against the new stream.
+    end_replay(st, emit);
+    st.replay = Some(Replay::new(&st.output_text));
+    // A record cut off by the drop is incomplete; the resumed stream starts fresh.
+    st.sse_event = None;
+    st.sse_data.clear();
+  }
+}
+
+/// One request/stream attempt. `Ok(true)` if the server sent `[DONE]`.
+async fn stream_once(
+  app: &tauri::AppHandle,
+  client: &reqwest::Client,
+  base: &str,
+  body: &serde_json::Value,
+  attempt: u32,
+  st: &mut SseState,
+  emit: EventSink<'_>,
+) -> Result<bool, StreamFailure> {
+  let url = format!("{}/api/translate", base);
+  let mut last_event_id = None;
+  let body = if attempt == 0 {
+    body.clone()
+  } else {
+    last_event_id = st.last_event_id.as_deref();
+    let mut resumed = body.clone();
+    resumed["resume"] = serde_json::json!({
+      "continue_from": st.output_text,
+      "last_event_id": st.last_event_id,
+      "attempt": attempt,
+    });
+    resumed
+  };
+  // Signed per attempt, so a resumed request gets a fresh timestamp and nonce.
+  let mut request = http::post_json(app, client, &url, &body)
+    .map_err(|message| StreamFailure {
+      message,
+      retryable: false,
+    })?
+    .header("Accept", "text/event-stream");
+  if let Some(id) = last_event_id {
+    request = request.header("Last-Event-ID", id);
+  }
+  let res = request.send().await.map_err(|e| StreamFailure {
+    message: format!("request failed: {e}"),
+    // The first connect failure is reported as-is (the caller may fall back to the offline engine).
+    retryable: attempt > 0,
+  })?;
+
+  if !res.status().is_success() {
+    let status = res.status();
+    let text = res.text().await.unwrap_or_default();
+    emit(StreamEvent::Error {
+      message: format!("api error {status}: {text}"),
+    });
+    return Err(StreamFailure {
+      message: format!("api error {status}"),
+      retryable: false,
+    });
+  }
+
+  use futures_util::StreamExt;
+  let mut buffer = String::new();
+  let mut stream = res.bytes_stream();
+  while let Some(item) = stream.next().await {
+    let chunk = item.map_err(|e| StreamFailure {
+      message: format!("stream error: {e}"),
+      retryable: true,
+    })?;
+
+    let s = String::from_utf8_lossy(&chunk);
+    buffer.push_str(&s);
+
+    // process by lines; keep trailing partial line in buffer
+    while let Some(pos) = buffer.find('\n') {
+      let line = buffer[..pos].to_string();
+      buffer = buffer[pos + 1..].to_string();
+
+      match handle_sse_line(&line, st, emit) {
+        SseLine::Continue if st.truncated => return Ok(true),
+        SseLine::Continue => {}
+        SseLine::Done => return Ok(true),
+        SseLine::Error(e) => {
+          return Err(StreamFailure {
+            message: e,
+            retryable: false,
+          })
+        }
+      }
+    }
+  }
+  // End of stream terminates a pending record, like a trailing blank line.
+  for line in [buffer.as_str(), ""] {
+    match handle_sse_line(line, st, emit) {
+      SseLine::Continue => {}
+      SseLine::Done => return Ok(true),
+      SseLine::Error(e) => {
+        return Err(StreamFailure {
+          message: e,
+          retryable: false,
+        })
+      }
+    }
+  }
+  Ok(false)
+}
+
+/// After a resume, matches incoming text against what was already emitted so a server that restarts
+/// from the beginning doesn't duplicate output. Matching text is held until it diverges (real new
+/// output, released as-is) or covers everything seen before (a replay, dropped).
+struct Replay {
+  expected: String,
+  held: String,
+}
+
+impl Replay {
+  fn new(already_emitted: &str) -> Self {
+    Self {
+      expected: already_emitted.to_string(),
+      held: String::new(),
+    }
+  }
+
+  /// Returns the text to forward and whether replay matching is finished.
+  fn feed(&mut self, content: &str) -> (String, bool) {
+    let common: usize = self
+      .expected
+      .chars()
+      .zip(content.chars())
+      .take_while(|(a, b)| a == b)
+      .map(|(a, _)| a.len_utf8())
+      .sum();
+    if common == self.expected.len() {
+      // Everything emitted before has been replayed; pass on whatever follows.
+      return (content[common..].to_string(), true);
+    }
+    if common == content.len() {
+      self.held.push_str(content);
+      self.expected = self.expected[common..].to_string();
+      return (String::new(), false);
+    }
+    // Diverged: this is a continuation, not a replay.
+    (format!("{}{}", std::mem::take(&mut self.held), content), true)
+  }
+}
+
+/// Stop replay matching and release text held back while it looked like a replay.
+fn end_replay(st: &mut SseState, emit: EventSink<'_>) {
+  if let Some(replay) = st.replay.take() {
+    if !replay.held.is_empty() {
+      let pieces = st.sections.feed(&replay.held);
+      emit_pieces(pieces, st, emit);
+    }
+  }
+}
+
+/// State accumulated across the SSE frames of one translation.
+#[derive(Default)]
+struct SseState {
+  output_text: String,
+  reported: Option<usage::ReportedUsage>,
+  sections: SectionSplitter,
+  /// Last SSE `id:` seen, sent back as `Last-Event-ID` when resuming.
+  last_event_id: Option<String>,
+  replay: Option<Replay>,
+  /// `event:` name of the record being read.
+  sse_event: Option<String>,
+  /// `data:` lines of the record being read that didn't form a complete payload yet.
+  sse_data: String,
+  /// Alternative translations by 1-based index, accumulated from variant frames.
+  variants: std::collections::BTreeMap<usize, String>,
+  /// Character limit for streamed text; text past it is dropped and `truncated` set.
+  limit: Option<usize>,
+  truncated: bool,
+}
+
+fn emit_pieces(pieces: Vec<Piece>, st: &mut SseState, emit: EventSink<'_>) {
+  for piece in pieces {
+    match piece {
+      Piece::Text(mut content) => {
+        if let Some(limit) = st.limit {
+          let room = limit.saturating_sub(st.output_text.chars().count());
+          if content.chars().count() > room {
+            content = content.chars().take(room).collect();
+            st.truncated = true;
+          }
+          if content.is_empty() {
+            continue;
+          }
+        }
+        st.output_text.push_str(&content);
+        emit(StreamEvent::Delta { content });
+      }
+      Piece::Section(name) => emit(StreamEvent::Section { name }),
+    }
+  }
+}
+
+/// Forward provider content, splitting out inline section markers.
+fn emit_content(st: &mut SseState, emit: EventSink<'_>, content: &str) {
+  if content.is_empty() {
+    return;
+  }
+  let content = match st.replay.as_mut().map(|r| r.feed(content)) {
+    Some((forward, finished)) => {
+      if finished {
+        st.replay = None;
+      }
+      forward
+    }
+    None => content.to_string(),
+  };
+  if content.is_empty() {
+    return;
+  }
+  let pieces = st.sections.feed(&content);
+  emit_pieces(pieces, st, emit);
+}
+
+enum SseLine {
+  Continue,
+  Done,
+  Error(String),
+}
+
+/// Handle one raw SSE line: forwards deltas/errors to the sink and records usage metadata.
+///
+/// Understands standard SSE records (`event:`, `id:`, multi-line `data:`, blank-line terminated). A
+/// `data:` payload that is already complete JSON (or `[DONE]`) is handled immediately, so servers that
+/// don't separate frames with blank lines keep working.
+fn handle_sse_line(line: &str, st: &mut SseState, emit: EventSink<'_>) -> SseLine {
+  let line = line.trim_end_matches('\r');
+  if line.is_empty() {
+    // Record boundary: dispatch whatever data is still pending.
+    let data = std::mem::take(&mut st.sse_data);
+    let event = st.sse_event.take();
+    if data.is_empty() {
+      return SseLine::Continue;
+    }
+    return dispatch_sse_record(event.as_deref(), &data, st, emit);
+  }
+  if line.starts_with(':') {
+    return SseLine::Continue;
+  }
+  let (field, value) = match line.split_once(':') {
+    Some((f, v)) => (f, v.strip_prefix(' ').unwrap_or(v)),
+    None => (line, ""),
+  };
+  match field {
+    "id" => {
+      st.last_event_id = Some(value.trim().to_string()).filter(|s| !s.is_empty());
+      SseLine::Continue
+    }
+    "event" => {
+      st.sse_event = Some(value.trim().to_string()).filter(|s| !s.is_empty());
+      SseLine::Continue
+    }
+    "data" => {
+      if !st.sse_data.is_empty() {
+        st.sse_data.push('\n');
+      }
+      st.sse_data.push_str(value);
+      let data = st.sse_data.trim();
+      let complete = data == "[DONE]" || serde_json::from_str::<serde_json::Value>(data).is_ok();
+      if !complete {
+        return SseLine::Continue;
+      }
+      let data = std::mem::take(&mut st.sse_data);
+      let event = st.sse_event.take();
+      dispatch_sse_record(event.as_deref(), &data, st, emit)
+    }
+    _ => SseLine::Continue,
+  }
+}
+
+/// Handle one complete SSE record.
+fn dispatch_sse_record(event: Option<&str>, data: &str, st: &mut SseState, emit: EventSink<'_>) -> SseLine {
+  let data = data.trim();
+  if data == "[DONE]" {
+    return SseLine::Done;
+  }
+  let v: Option<serde_json::Value> = serde_json::from_str(data).ok();
+  match event {
+    Some("ping") => return SseLine::Continue,
+    Some("error") => {
+      let message = v
+        .as_ref()
+        .and_then(error_message)
+        .unwrap_or_else(|| data.to_string());
+      emit(StreamEvent::Error {
+        message: message.clone(),
+      });
+      return SseLine::Error(message);
+    }
+    _ => {}
+  }
+  let done = matches!(event, Some("done") | Some("message_stop"));
+  let Some(v) = v else {
+    return if done { SseLine::Done } else { SseLine::Continue };
+  };
+
+  if let Some(u) = usage::parse_usage(&v) {
+    st.reported = Some(u);
+  }
+  if let Some(name) = v.get("section").and_then(|x| x.as_str()) {
+    // Anything held back belongs to the previous section.
+    let mut pieces: Vec<Piece> = st.sections.flush().into_iter().collect();
+    pieces.extend(st.sections.enter(name));
+    emit_pieces(pieces, st, emit);
+  }
+  // Complete alternatives sent as an array (e.g. DeepL-style).
+  if let Some(alts) = v.get("alternatives").and_then(|x| x.as_array()) {
+    for (i, alt) in alts.iter().filter_map(|a| a.as_str().or_else(|| a.get("text")?.as_str())).enumerate() {
+      st.variants.insert(i + 1, alt.to_string());
+    }
+  }
+  let variant = frame_variant(&v);
+  if let Some(content) = frame_content(&v) {
+    if variant > 0 {
+      st.variants.entry(variant).or_default().push_str(content);
+      emit(StreamEvent::VariantDelta {
+        index: variant,
+        content: content.to_string(),
+      });
+    } else {
+      emit_content(st, emit, content);
+    }
+  } else if let Some(err) = error_message(&v) {
+    emit(StreamEvent::Error { message: err.clone() });
+    return SseLine::Error(err);
+  }
+  if done {
+    SseLine::Done
+  } else {
+    SseLine::Continue
+  }
+}
+
+/// Text carried by a frame: `{"content"}` (our backend) or OpenAI chat/completions chunks
+/// (`choices[0].delta.content` / `choices[0].text`).
+fn frame_content(v: &serde_json::Value) -> Option<&str> {
+  if let Some(c) = v.get("content").and_then(|x| x.as_str()) {
+    return Some(c);
+  }
+  let choice = v.get("choices")?.get(0)?;
+  choice
+    .get("delta")
+    .and_then(|d| d.get("content"))
+    .or_else(|| choice.get("text"))
+    .and_then(|x| x.as_str())
+}
+
+/// Which variant a frame belongs to: `{"variant": n}` or an OpenAI choice index (0 = main translation).
+fn frame_variant(v: &serde_json::Value) -> usize {
+  v.get("variant")
+    .or_else(|| v.get("choices")?.get(0)?.get("index"))
+    .and_then(|x| x.as_u64())
+    .map(|n| (n as usize).min(MAX_ALTERNATIVES + 1))
+    .unwrap_or(0)
+}
+
+/// `{"error": "..."}` or OpenAI-style `{"error": {"message": "..."}}`.
+fn error_message(v: &serde_json::Value) -> Option<String> {
+  let err = v.get("error")?;
+  err
+    .as_str()
+    .or_else(|| err.get("message").and_then(|m| m.as_str()))
+    .map(|s| s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// What `f` emits, as the JSON the frontend receives.
+  fn emitted(f: impl FnOnce(EventSink<'_>)) -> Vec<serde_json::Value> {
+    let events = std::sync::Mutex::new(Vec::new());
+    let sink = |ev: StreamEvent| events.lock().unwrap().push(serde_json::to_value(ev).unwrap());
+    f(&sink);
+    events.into_inner().unwrap()
+  }
+
+  fn delta(content: &str) -> serde_json::Value {
+    serde_json::json!({ "type": "delta", "content": content })
+  }
+
+  #[test]
+  fn replay_drops_text_already_emitted() {
+    let mut replay = Replay::new("Hello world");
+    assert_eq!(replay.feed("Hello"), (String::new(), false));
+    assert_eq!(replay.feed(" world, again"), (", again".to_string(), true));
+  }
+
+  #[test]
+  fn replay_releases_held_text_once_it_diverges() {
+    let mut replay = Replay::new("Hello world");
+    assert_eq!(replay.feed("Hello"), (String::new(), false));
+    assert_eq!(replay.feed(" there"), ("Hello there".to_string(), true));
+    assert_eq!(Replay::new("Hello").feed("Hi"), ("Hi".to_string(), true));
+  }
+
+  #[test]
+  fn resumed_stream_continues_without_duplicates() {
+    let mut st = SseState::default();
+    let events = emitted(|emit| {
+      emit_content(&mut st, emit, "Hello");
+      st.replay = Some(Replay::new(&st.output_text));
+      emit_content(&mut st, emit, "Hel");
+      emit_content(&mut st, emit, "lo, world");
+    });
+    assert_eq!(events, [delta("Hello"), delta(", world")]);
+    assert_eq!(st.output_text, "Hello, world");
+    assert!(st.replay.is_none());
+  }
+
+  #[test]
+  fn end_replay_releases_unconfirmed_text() {
+    let mut st = SseState {
+      replay: Some(Replay::new("abc")),
+      ..Default::default()
+    };
+    let events = emitted(|emit| {
+      emit_content(&mut st, emit, "ab");
+      end_replay(&mut st, emit);
+    });
+    assert_eq!(events, [delta("ab")]);
+  }
+
+  /// Feed `lines` to `handle_sse_line` until one doesn't continue; what was emitted and how the last line ended.
+  fn feed(st: &mut SseState, lines: &[&str]) -> (Vec<serde_json::Value>, SseLine) {
+    let mut last = SseLine::Continue;
+    let events = emitted(|emit| {
+      for line in lines {
+        last = handle_sse_line(line, st, emit);
+        if !matches!(last, SseLine::Continue) {
+          break;
+        }
+      }
+    });
+    (events, last)
+  }
+
+  #[test]
+  fn sse_forwards_content_until_done() {
+    let mut st = SseState::default();
+    let (events, last) = feed(
+      &mut st,
+      &[": keep-alive", r#"data: {"content":"Hel"}"#, "", r#"data: {"content":"lo"}"#, "data: [DONE]", "data: x"],
+    );
+    assert_eq!(events, [delta("Hel"), delta("lo")]);
+    assert!(matches!(last, SseLine::Done));
+    assert_eq!(st.output_text, "Hello");
+  }
+
+  #[test]
+  fn sse_joins_multi_line_records() {
+    let mut st = SseState::default();
+    let (events, _) = feed(&mut st, &["id: 7", "event: message", r#"data: {"content":"#, r#"data: "Hi"}"#, "\r"]);
+    assert_eq!(events, [delta("Hi")]);
+    assert_eq!(st.last_event_id.as_deref(), Some("7"));
+  }
+
+  #[test]
+  fn sse_reads_openai_chunks_and_variants() {
+    let mut st = SseState::default();
+    let (events, _) = feed(
+      &mut st,
+      &[
+        r#"data: {"choices":[{"index":0,"delta":{"content":"A"}}]}"#,
+        r#"data: {"choices":[{"index":1,"delta":{"content":"B"}}]}"#,
+      ],
+    );
+    assert_eq!(events[0], delta("A"));
+    assert_eq!(events[1], serde_json::json!({ "type": "variant_delta", "index": 1, "content": "B" }));
+    assert_eq!(st.variants.get(&1).map(String::as_str), Some("B"));
+  }
+
+  #[test]
+  fn sse_reports_errors() {
+    let mut st = SseState::default();
+    let (events, last) = feed(&mut st, &["event: error", r#"data: {"error":{"message":"boom"}}"#]);
+    assert_eq!(events, [serde_json::json!({ "type": "error", "message": "boom" })]);
+    assert!(matches!(last, SseLine::Error(e) if e == "boom"));
+  }
+
+  #[test]
+  fn sse_done_event_ends_the_stream() {
+    let mut st = SseState::default();
+    let (_, last) = feed(&mut st, &["event: done", "data: {}"]);
+    assert!(matches!(last, SseLine::Done));
+  }
+
+  #[test]
+  fn sse_stops_at_the_limit() {
+    let mut st = SseState {
+      limit: Some(3),
+      ..Default::default()
+    };
+    let (events, _) = feed(&mut st, &[r#"data: {"content":"Hello"}"#]);
+    assert_eq!(events, [delta("Hel")]);
+    assert!(st.truncated);
+  }
+
+  #[test]
+  fn unmasking_restores_spans_split_across_deltas() {
+    let (_, masks) = protect::mask("see {name}");
+    let unmasking = Unmasking::new(&masks);
+    let events = emitted(|emit| {
+      unmasking.forward(StreamEvent::Delta { content: "Hi ⟦".to_string() }, emit);
+      unmasking.forward(StreamEvent::Delta { content: "0⟧!".to_string() }, emit);
+      unmasking.forward(StreamEvent::Delta { content: " ⟦".to_string() }, emit);
+      unmasking.forward(StreamEvent::Done, emit);
+    });
+    let done = serde_json::json!({ "type": "done" });
+    assert_eq!(events, [delta("Hi "), delta("{name}!"), delta(" "), delta("⟦"), done]);
+  }
+
+  #[test]
+  fn unmasking_keeps_variants_apart() {
+    let (_, masks) = protect::mask("{a}");
+    let unmasking = Unmasking::new(&masks);
+    let events = emitted(|emit| {
+      unmasking.forward(StreamEvent::Delta { content: "x ⟦".to_string() }, emit);
+      let variant = |content: &str| StreamEvent::VariantDelta { index: 1, content: content.to_string() };
+      unmasking.forward(variant("y ⟦0"), emit);
+      unmasking.forward(variant("⟧"), emit);
+      unmasking.forward(StreamEvent::Replace { content: "⟦0⟧ z".to_string() }, emit);
+    });
+    assert_eq!(
+      events,
+      [
+        delta("x "),
+        serde_json::json!({ "type": "variant_delta", "index": 1, "content": "y " }),
+        serde_json::json!({ "type": "variant_delta", "index": 1, "content": "{a}" }),
+        serde_json::json!({ "type": "replace", "content": "{a} z" }),
+      ]
+    );
+  }
+}
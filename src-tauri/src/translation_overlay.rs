@@ -0,0 +1,360 @@
+//! In-place translation: the translated text painted over the original in a transparent window, box by box.
+//!
+//! OCR lines are grouped into blocks (lines of about the same height, stacked less than a line apart and
+//! overlapping horizontally); the frontend translates each block as one text and hands the translations back with
+//! the blocks' boxes. The window covers the captured region exactly, in physical pixels; the boxes (pixels of the
+//! OCR'd image, which can be larger or smaller than the region when the capture was scaled) are scaled by the
+//! region's size over the image's. Each translation is wrapped into its box at the largest font
+//! size that fits, no larger than the original text; when even the smallest size overflows, the box grows
+//! downwards. Text is measured by estimate rather than shaped: wide (CJK) characters take a full em, the rest a
+//! little over half. Clicking the overlay or Esc closes it.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::commands::CaptureRect;
+use crate::ocr::{BBox, OcrLine};
+use crate::reflow;
+
+pub const LABEL: &str = "translation-overlay";
+/// Sent to an open overlay window when it gets new content.
+const CHANGED_EVENT: &str = "translation-overlay://changed";
+
+/// Line height, in font sizes.
+const LINE_SPACING: f32 = 1.2;
+/// Smallest font size, in physical pixels, worth drawing.
+const MIN_FONT_SIZE: f32 = 9.0;
+/// Vertical gap, in line heights, that still joins two lines into a block.
+const BLOCK_GAP: f32 = 0.8;
+/// Largest height ratio of two lines in the same block.
+const HEIGHT_RATIO: f32 = 1.5;
+
+/// A box of text: OCR'd lines to translate, or their translation to draw.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OverlayBlock {
+  /// Image pixels of the capture.
+  pub bbox: BBox,
+  pub text: String,
+  /// OCR lines the block was read from; sizes the translation like the original.
+  #[serde(default)]
+  pub lines: usize,
+}
+
+/// A translation laid out in its box.
+#[derive(Debug, Serialize, Clone)]
+pub struct PlacedBlock {
+  /// Window (physical) pixels; taller than the source box when the text overflowed at `MIN_FONT_SIZE`.
+  pub bbox: BBox,
+  /// Physical pixels.
+  pub font_size: f32,
+  pub line_height: f32,
+  pub lines: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OverlayContent {
+  /// Physical bounds of the overlay window on the desktop.
+  pub rect: CaptureRect,
+  pub blocks: Vec<PlacedBlock>,
+}
+
+#[derive(Default)]
+pub struct TranslationOverlay(Mutex<Option<OverlayContent>>);
+
+impl TranslationOverlay {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Option<OverlayContent>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+fn union(a: BBox, b: BBox) -> BBox {
+  let (x, y) = (a.x.min(b.x), a.y.min(b.y));
+  BBox {
+    x,
+    y,
+    width: (a.x + a.width).max(b.x + b.width) - x,
+    height: (a.y + a.height).max(b.y + b.height) - y,
+  }
+}
+
+/// Whether `line` continues the block that ends with `last` and spans `block`.
+fn continues(block: BBox, last: BBox, line: BBox) -> bool {
+  let h = last.height.max(line.height).max(1) as f32;
+  let gap = (line.y - (last.y + last.height)) as f32;
+  let (a, b) = (last.height.max(1) as f32, line.height.max(1) as f32);
+  let similar = a < b * HEIGHT_RATIO && b < a * HEIGHT_RATIO;
+  let overlaps = line.x < block.x + block.width && block.x < line.x + line.width;
+  gap > -h / 2.0 && gap < h * BLOCK_GAP && similar && overlaps
+}
+
+/// Group OCR lines (in reading order) into the blocks to translate; each block's text is its lines reflowed.
+fn group(lines: &[OcrLine]) -> Vec<OverlayBlock> {
+  let mut groups: Vec<(BBox, BBox, Vec<&str>)> = Vec::new();
+  for line in lines.iter().filter(|l| !l.text.trim().is_empty() && l.bbox.width > 0 && l.bbox.height > 0) {
+    match groups.last_mut() {
+      Some((block, last, texts)) if continues(*block, *last, line.bbox) => {
+        *block = union(*block, line.bbox);
+        *last = line.bbox;
+        texts.push(line.text.trim());
+      }
+      _ => groups.push((line.bbox, line.bbox, vec![line.text.trim()])),
+    }
+  }
+  groups
+    .into_iter()
+    .map(|(bbox, _, texts)| OverlayBlock {
+      bbox,
+      text: reflow::reflow(&texts.join("\n")),
+      lines: texts.len(),
+    })
+    .collect()
+}
+
+/// Estimated advance of `c`, in ems.
+fn advance(c: char) -> f32 {
+  if reflow::is_cjk(c) {
+    1.0
+  } else if c.is_whitespace() {
+    0.3
+  } else {
+    0.58
+  }
+}
+
+fn ems(s: &str) -> f32 {
+  s.chars().map(advance).sum()
+}
+
+/// Break points of one paragraph: words, single CJK characters and the spaces between them.
+fn units(paragraph: &str) -> Vec<&str> {
+  let mut out = Vec::new();
+  let mut word_start: Option<usize> = None;
+  for (i, c) in paragraph.char_indices() {
+    if reflow::is_cjk(c) || c.is_whitespace() {
+      if let Some(start) = word_start.take() {
+        out.push(&paragraph[start..i]);
+      }
+      out.push(&paragraph[i..i + c.len_utf8()]);
+    } else if word_start.is_none() {
+      word_start = Some(i);
+    }
+  }
+  if let Some(start) = word_start {
+    out.push(&paragraph[start..]);
+  }
+  out
+}
+
+/// Wrap `text` to lines of at most `max_ems`; words longer than a line are broken anywhere.
+fn wrap(text: &str, max_ems: f32) -> Vec<String> {
+  let mut lines = Vec::new();
+  for paragraph in text.lines() {
+    let mut line = String::new();
+    let mut width = 0.0;
+    for unit in units(paragraph.trim()) {
+      let w = ems(unit);
+      if unit.chars().all(char::is_whitespace) {
+        if !line.is_empty() {
+          line.push_str(unit);
+          width += w;
+        }
+        continue;
+      }
+      if width + w > max_ems && !line.trim_end().is_empty() {
+        lines.push(line.trim_end().to_string());
+        line.clear();
+        width = 0.0;
+      }
+      if w <= max_ems {
+        line.push_str(unit);
+        width += w;
+        continue;
+      }
+      for c in unit.chars() {
+        if width + advance(c) > max_ems && !line.is_empty() {
+          lines.push(std::mem::take(&mut line));
+          width = 0.0;
+        }
+        line.push(c);
+        width += advance(c);
+      }
+    }
+    if !line.trim_end().is_empty() {
+      lines.push(line.trim_end().to_string());
+    }
+  }
+  lines
+}
+
+/// Lay `text` out in `bbox`, starting from `max_size` (the original text's size) and shrinking until it fits.
+fn fit(bbox: BBox, text: &str, max_size: f32) -> PlacedBlock {
+  let (width, height) = (bbox.width.max(1) as f32, bbox.height.max(1) as f32);
+  let mut size = max_size.max(MIN_FONT_SIZE);
+  loop {
+    let lines = wrap(text, width / size);
+    let needed = lines.len() as f32 * size * LINE_SPACING;
+    if needed <= height || size <= MIN_FONT_SIZE {
+      return PlacedBlock {
+        bbox: BBox {
+          height: bbox.height.max(needed.ceil() as i32),
+          ..bbox
+        },
+        font_size: size,
+        line_height: size * LINE_SPACING,
+        lines,
+      };
+    }
+    size = (size * 0.92).max(MIN_FONT_SIZE);
+  }
+}
+
+/// Font size of the original text in `block`: about the height of one of its lines.
+fn original_size(block: &OverlayBlock) -> f32 {
+  block.bbox.height as f32 / block.lines.max(1) as f32
+}
+
+/// `bbox` (image pixels) in window pixels, `sx` and `sy` being the window's size over the image's.
+fn scale(bbox: BBox, sx: f32, sy: f32) -> BBox {
+  BBox {
+    x: (bbox.x as f32 * sx).round() as i32,
+    y: (bbox.y as f32 * sy).round() as i32,
+    width: (bbox.width as f32 * sx).round() as i32,
+    height: (bbox.height as f32 * sy).round() as i32,
+  }
+}
+
+/// Blocks to translate for `lines` (an `ocr_image` result's), in reading order.
+#[tauri::command]
+pub fn translation_overlay_blocks(lines: Vec<OcrLine>) -> Vec<OverlayBlock> {
+  group(&lines)
+}
+
+/// Show `blocks` (translations, with the boxes `translation_overlay_blocks` gave) over the screen region `rect`
+/// (physical pixels, the capture the lines were read from). `image_path` is the image the lines were read from;
+/// without it the boxes are taken to be window pixels. Replaces the content of an overlay that is already open.
+#[tauri::command]
+pub fn open_translation_overlay(
+  app: tauri::AppHandle,
+  state: tauri::State<'_, TranslationOverlay>,
+  rect: CaptureRect,
+  blocks: Vec<OverlayBlock>,
+  image_path: Option<String>,
+) -> Result<(), String> {
+  if rect.width == 0 || rect.height == 0 {
+    return Err("empty overlay region".to_string());
+  }
+  let (sx, sy) = match image_path.as_deref().map(crate::image_io::size) {
+    Some(Ok((w, h))) if w > 0 && h > 0 => (rect.width as f32 / w as f32, rect.height as f32 / h as f32),
+    Some(Err(e)) => {
+      log::warn!("translation overlay: {e}; drawing the boxes unscaled");
+      (1.0, 1.0)
+    }
+    _ => (1.0, 1.0),
+  };
+  let placed = blocks
+    .iter()
+    .filter(|b| !b.text.trim().is_empty())
+    .map(|b| fit(scale(b.bbox, sx, sy), b.text.trim(), original_size(b) * sy))
+    .collect();
+  *state.lock() = Some(OverlayContent { rect: rect.clone(), blocks: placed });
+
+  let position = tauri::PhysicalPosition::new(rect.x, rect.y);
+  let size = tauri::PhysicalSize::new(rect.width, rect.height);
+  if let Some(window) = app.get_webview_window(LABEL) {
+    window
+      .set_position(position)
+      .and_then(|_| window.set_size(size))
+      .map_err(|e| format!("failed to move the translation overlay: {e}"))?;
+    let _ = app.emit_to(LABEL, CHANGED_EVENT, ());
+    let _ = window.set_focus();
+    return Ok(());
+  }
+  tauri::WebviewWindowBuilder::new(&app, LABEL, tauri::WebviewUrl::App("index.html#/translation-overlay".into()))
+    .resizable(false)
+    .decorations(false)
+    .transparent(true)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .shadow(false)
+    .visible(false)
+    .build()
+    .and_then(|window| {
+      // Physical placement, as for the OCR overlay.
+      window.set_position(position)?;
+      window.set_size(size)?;
+      window.show()?;
+      window.set_focus()?;
+      Ok(())
+    })
+    .map_err(|e| {
+      close(&app);
+      format!("failed to open the translation overlay: {e}")
+    })
+}
+
+/// What the overlay window draws; `None` once it's closed.
+#[tauri::command]
+pub fn get_translation_overlay(state: tauri::State<'_, TranslationOverlay>) -> Option<OverlayContent> {
+  state.lock().clone()
+}
+
+pub fn close(app: &tauri::AppHandle) {
+  *app.state::<TranslationOverlay>().lock() = None;
+  if let Some(window) = app.get_webview_window(LABEL) {
+    let _ = window.destroy();
+  }
+}
+
+/// Close the overlay (a click on it, or Esc).
+#[tauri::command]
+pub fn close_translation_overlay(app: tauri::AppHandle) {
+  close(&app);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn bbox(width: i32, height: i32) -> BBox {
+    BBox { x: 5, y: 7, width, height }
+  }
+
+  #[test]
+  fn wrap_breaks_between_words() {
+    assert_eq!(wrap("hello world", 4.0), ["hello", "world"]);
+    assert_eq!(wrap("hello world", 7.0), ["hello world"]);
+    assert_eq!(wrap("one\n\n two ", 7.0), ["one", "two"]);
+  }
+
+  #[test]
+  fn wrap_breaks_long_words_and_cjk_anywhere() {
+    assert_eq!(wrap("abcdefghij", 2.0), ["abc", "def", "ghi", "j"]);
+    assert_eq!(wrap("日本語の文章", 2.0), ["日本", "語の", "文章"]);
+  }
+
+  #[test]
+  fn fit_keeps_the_original_size_when_it_fits() {
+    let placed = fit(bbox(200, 40), "hi", 20.0);
+    assert_eq!(placed.font_size, 20.0);
+    assert_eq!(placed.lines, ["hi"]);
+    assert_eq!((placed.bbox.x, placed.bbox.y, placed.bbox.height), (5, 7, 40));
+  }
+
+  #[test]
+  fn fit_shrinks_until_the_text_fits() {
+    let placed = fit(bbox(100, 30), "hello world", 20.0);
+    assert!(placed.font_size < 20.0 && placed.font_size >= MIN_FONT_SIZE);
+    assert_eq!(placed.lines, ["hello world"]);
+    assert_eq!(placed.bbox.height, 30);
+  }
+
+  #[test]
+  fn fit_grows_the_box_at_the_smallest_size() {
+    let placed = fit(bbox(10, 10), "a long sentence that cannot fit", 12.0);
+    assert_eq!(placed.font_size, MIN_FONT_SIZE);
+    let needed = placed.lines.len() as f32 * MIN_FONT_SIZE * LINE_SPACING;
+    assert_eq!(placed.bbox.height, needed.ceil() as i32);
+    assert!(placed.bbox.height > 10);
+  }
+}
@@ -0,0 +1,120 @@
+//! The tray icon and its menu: show or hide the main window, pause the global hotkeys, open the settings, quit.
+//!
+//! A hotkey-driven translator spends most of its time out of sight, so the tray is where it lives: with the main
+//! window hidden the hotkeys keep working, and the menu brings it back. The icon is there while `trayIcon` is on
+//! (the default) and always in background agent mode, which has no other way in.
+//!
+//! Pausing the hotkeys unregisters them (see `hotkeys`) until they're resumed, for games and other apps that want
+//! the same keys. The pause isn't saved; every start begins with the hotkeys live.
+
+use std::sync::Mutex;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::{Emitter, Manager};
+
+use crate::{agent, settings};
+
+const TRAY_ID: &str = "erudaite";
+/// Sent to every window with the new paused state (a bool) when the hotkeys are paused or resumed.
+const HOTKEYS_PAUSED_EVENT: &str = "erudaite://hotkeys/paused";
+
+#[derive(Default)]
+pub struct TrayState {
+  hotkeys_paused: Mutex<bool>,
+  /// The menu's "Pause hotkeys" item, kept in step with the state when it changes elsewhere.
+  pause_item: Mutex<Option<CheckMenuItem<tauri::Wry>>>,
+}
+
+/// Whether the global hotkeys are paused.
+pub fn hotkeys_paused(app: &tauri::AppHandle) -> bool {
+  *app.state::<TrayState>().hotkeys_paused.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Pause or resume the global hotkeys; returns whether they're now paused.
+fn set_paused(app: &tauri::AppHandle, paused: bool) -> bool {
+  let state = app.state::<TrayState>();
+  *state.hotkeys_paused.lock().unwrap_or_else(|e| e.into_inner()) = paused;
+  if let Some(item) = state.pause_item.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+    let _ = item.set_checked(paused);
+  }
+  if paused {
+    crate::hotkeys::unregister(app);
+  } else {
+    crate::hotkeys::register(app);
+  }
+  let _ = app.emit(HOTKEYS_PAUSED_EVENT, paused);
+  paused
+}
+
+/// Pause (`true`) or resume the global hotkeys; `paused: None` toggles. Returns whether they're now paused.
+#[tauri::command]
+pub fn set_hotkeys_paused(app: tauri::AppHandle, paused: Option<bool>) -> bool {
+  let paused = paused.unwrap_or(!hotkeys_paused(&app));
+  set_paused(&app, paused)
+}
+
+/// Whether the global hotkeys are paused.
+#[tauri::command]
+pub fn are_hotkeys_paused(app: tauri::AppHandle) -> bool {
+  hotkeys_paused(&app)
+}
+
+fn build(app: &tauri::AppHandle) -> tauri::Result<()> {
+  use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+
+  let toggle = MenuItem::with_id(app, "toggle", "Show / Hide ErudAite", true, None::<&str>)?;
+  let pause = CheckMenuItem::with_id(app, "pause", "Pause hotkeys", true, hotkeys_paused(app), None::<&str>)?;
+  let settings = MenuItem::with_id(app, "settings", "Settings…", true, None::<&str>)?;
+  let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+  let separator = PredefinedMenuItem::separator(app)?;
+  let menu = Menu::with_items(app, &[&toggle, &pause, &settings, &separator, &quit])?;
+  let mut tray = TrayIconBuilder::with_id(TRAY_ID)
+    .tooltip("ErudAite")
+    .menu(&menu)
+    .show_menu_on_left_click(false)
+    .on_menu_event(|app, event| match event.id().as_ref() {
+      "toggle" => agent::toggle_main(app),
+      "pause" => {
+        set_paused(app, !hotkeys_paused(app));
+      }
+      "settings" => agent::open_settings(app),
+      "quit" => app.exit(0),
+      _ => {}
+    })
+    .on_tray_icon_event(|tray, event| {
+      if let TrayIconEvent::Click {
+        button: MouseButton::Left,
+        button_state: MouseButtonState::Up,
+        ..
+      } = event
+      {
+        agent::show_main(tray.app_handle());
+      }
+    });
+  if let Some(icon) = app.default_window_icon() {
+    tray = tray.icon(icon.clone());
+  }
+  tray.build(app)?;
+  *app.state::<TrayState>().pause_item.lock().unwrap_or_else(|e| e.into_inner()) = Some(pause);
+  Ok(())
+}
+
+/// Show the tray icon when `trayIcon` is on or the app runs as a background agent, and remove it otherwise.
+pub fn sync(app: &tauri::AppHandle) -> Result<(), String> {
+  let want = settings::tray_icon(app) || agent::is_enabled(app);
+  let shown = app.tray_by_id(TRAY_ID).is_some();
+  if want && !shown {
+    build(app).map_err(|e| format!("failed to create tray icon: {e}"))?;
+  } else if !want && shown {
+    let _ = app.remove_tray_by_id(TRAY_ID);
+    *app.state::<TrayState>().pause_item.lock().unwrap_or_else(|e| e.into_inner()) = None;
+  }
+  Ok(())
+}
+
+/// Show the tray (`setup`, after `agent::init`) and follow `trayIcon` changes.
+pub fn init(app: &tauri::AppHandle) {
+  settings::subscribe(app, "tray", &["trayIcon"], |app, _| sync(app));
+  if let Err(e) = sync(app) {
+    log::warn!("{e}");
+  }
+}
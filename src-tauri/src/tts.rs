@@ -0,0 +1,385 @@
+//! Cloud text-to-speech, for languages whose local voices are poor or missing.
+//!
+//! `tts` in the settings picks the service and voice: `{ "provider": "openai", "voice": "alloy", "model":
+//! "tts-1" }`, `{ "provider": "azure", "voice": "ja-JP-NanamiNeural", "region": "japaneast" }` or `{ "provider":
+//! "google", "voice": "ja-JP-Neural2-B" }`; the service's key is the `ttsApiKey` secret (see `secrets`). `speak`
+//! synthesizes MP3 audio, keeps it in `tts` under the app cache dir, named by a hash of the service, voice and text
+//! so saying the same thing again costs no request, and plays it on a backend audio thread (`stop_speaking` cuts
+//! it short; a `tts.finished` event follows either way). The cache is kept under `MAX_CACHE_BYTES` by dropping the
+//! files played least recently.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tauri::Manager;
+
+use crate::{events, http, secrets, settings, temp};
+
+/// Secret holding the TTS service's API key.
+pub const API_KEY_SECRET: &str = "ttsApiKey";
+const CACHE_DIR: &str = "tts";
+const MAX_CACHE_BYTES: u64 = 200 * 1024 * 1024;
+/// Longest text one request may carry (OpenAI's limit, with room to spare).
+const MAX_CHARS: usize = 4000;
+/// How often the player checks whether playback has ended.
+const PLAYER_POLL: Duration = Duration::from_millis(100);
+const OPENAI_URL: &str = "https://api.openai.com/v1/audio/speech";
+const GOOGLE_URL: &str = "https://texttospeech.googleapis.com/v1/text:synthesize";
+const DEFAULT_OPENAI_VOICE: &str = "alloy";
+const DEFAULT_OPENAI_MODEL: &str = "tts-1";
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TtsProvider {
+  Openai,
+  Azure,
+  Google,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TtsConfig {
+  pub provider: TtsProvider,
+  /// Voice name; OpenAI's default is "alloy", Azure and Google need one (e.g. "ja-JP-NanamiNeural").
+  #[serde(default)]
+  pub voice: Option<String>,
+  /// OpenAI model (default "tts-1").
+  #[serde(default)]
+  pub model: Option<String>,
+  /// Azure region, e.g. "japaneast".
+  #[serde(default)]
+  pub region: Option<String>,
+}
+
+impl TtsConfig {
+  /// Problems that would make every request fail.
+  pub fn check(&self) -> Result<(), String> {
+    let missing = |v: &Option<String>| v.as_deref().map_or(true, |s| s.trim().is_empty());
+    match self.provider {
+      TtsProvider::Openai => Ok(()),
+      TtsProvider::Azure if missing(&self.region) => Err("azure needs a region".to_string()),
+      TtsProvider::Azure | TtsProvider::Google if missing(&self.voice) => Err("a voice is required".to_string()),
+      TtsProvider::Azure | TtsProvider::Google => Ok(()),
+    }
+  }
+
+  fn voice(&self) -> &str {
+    match self.voice.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+      Some(voice) => voice,
+      None => DEFAULT_OPENAI_VOICE,
+    }
+  }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Spoken {
+  /// The audio file (MP3), for callers that want to play or save it themselves.
+  pub path: String,
+  /// Whether it came from the cache rather than a request.
+  pub cached: bool,
+}
+
+enum PlayerCommand {
+  Play(PathBuf),
+  Stop,
+}
+
+/// The audio thread's command channel (managed state); started on first use.
+#[derive(Default)]
+pub struct Player(Mutex<Option<mpsc::Sender<PlayerCommand>>>);
+
+impl Player {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Option<mpsc::Sender<PlayerCommand>>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  fn send(&self, app: &tauri::AppHandle, command: PlayerCommand) -> Result<(), String> {
+    let mut sender = self.lock();
+    let tx = sender.get_or_insert_with(|| spawn_player(app.clone()));
+    match tx.send(command) {
+      Ok(()) => Ok(()),
+      // The thread died (no audio device when it started); try a fresh one once.
+      Err(mpsc::SendError(command)) => {
+        let tx = sender.insert(spawn_player(app.clone()));
+        tx.send(command).map_err(|_| "audio output is unavailable".to_string())
+      }
+    }
+  }
+}
+
+/// The audio thread: rodio's output stream can't leave the thread that opened it.
+fn spawn_player(app: tauri::AppHandle) -> mpsc::Sender<PlayerCommand> {
+  let (tx, rx) = mpsc::channel::<PlayerCommand>();
+  std::thread::spawn(move || {
+    let (_stream, handle) = match rodio::OutputStream::try_default() {
+      Ok(output) => output,
+      Err(e) => {
+        events::warn(&app, None, "tts_no_audio", format!("no audio output: {e}"));
+        return;
+      }
+    };
+    let mut playing: Option<rodio::Sink> = None;
+    loop {
+      let command = match rx.recv_timeout(PLAYER_POLL) {
+        Ok(command) => Some(command),
+        Err(mpsc::RecvTimeoutError::Timeout) => None,
+        Err(mpsc::RecvTimeoutError::Disconnected) => return,
+      };
+      if command.is_some() {
+        if let Some(sink) = playing.take() {
+          sink.stop();
+          events::publish(&app, "tts.finished", None, serde_json::json!({ "stopped": true }));
+        }
+      }
+      match command {
+        Some(PlayerCommand::Play(path)) => match start(&handle, &path) {
+          Ok(sink) => playing = Some(sink),
+          Err(e) => events::warn(&app, None, "tts_playback_failed", e),
+        },
+        Some(PlayerCommand::Stop) => {}
+        None => {
+          if playing.as_ref().is_some_and(|sink| sink.empty()) {
+            playing = None;
+            events::publish(&app, "tts.finished", None, serde_json::json!({ "stopped": false }));
+          }
+        }
+      }
+    }
+  });
+  tx
+}
+
+fn start(handle: &rodio::OutputStreamHandle, path: &Path) -> Result<rodio::Sink, String> {
+  let file = std::fs::File::open(path).map_err(|e| format!("cannot open {}: {e}", path.display()))?;
+  let source = rodio::Decoder::new(std::io::BufReader::new(file)).map_err(|e| format!("unplayable audio: {e}"))?;
+  let sink = rodio::Sink::try_new(handle).map_err(|e| format!("audio output failed: {e}"))?;
+  sink.append(source);
+  Ok(sink)
+}
+
+fn cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+  let dir = app
+    .path()
+    .app_cache_dir()
+    .map_err(|e| format!("no app cache dir: {e}"))?
+    .join(CACHE_DIR);
+  std::fs::create_dir_all(&dir).map_err(|e| format!("cannot create the TTS cache: {e}"))?;
+  Ok(dir)
+}
+
+/// Cache file name for `text` said by `config`'s service and voice.
+fn cache_name(config: &TtsConfig, text: &str) -> String {
+  let mut hasher = Sha256::new();
+  for part in [
+    format!("{:?}", config.provider).as_str(),
+    config.voice(),
+    config.model.as_deref().unwrap_or_default(),
+    text,
+  ] {
+    hasher.update(part.as_bytes());
+    hasher.update([0]);
+  }
+  let hash: String = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect();
+  format!("{hash}.mp3")
+}
+
+/// Drop the least recently played files until the cache fits `MAX_CACHE_BYTES`.
+fn trim_cache(dir: &Path) {
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    return;
+  };
+  let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+    .flatten()
+    .filter_map(|e| {
+      let meta = e.metadata().ok().filter(|m| m.is_file())?;
+      Some((meta.modified().unwrap_or(SystemTime::UNIX_EPOCH), meta.len(), e.path()))
+    })
+    .collect();
+  let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+  files.sort_by_key(|(modified, _, _)| *modified);
+  for (_, len, path) in files {
+    if total <= MAX_CACHE_BYTES {
+      break;
+    }
+    if std::fs::remove_file(&path).is_ok() {
+      total -= len;
+    }
+  }
+}
+
+/// `ja-JP` from `ja-JP-NanamiNeural`.
+fn voice_locale(voice: &str) -> String {
+  voice.splitn(3, '-').take(2).collect::<Vec<_>>().join("-")
+}
+
+fn xml_escape(s: &str) -> String {
+  s.replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&apos;")
+}
+
+async fn synthesize(config: &TtsConfig, key: &str, text: &str) -> Result<Vec<u8>, String> {
+  let client = http::client();
+  let voice = config.voice();
+  let request = match config.provider {
+    TtsProvider::Openai => client.post(OPENAI_URL).bearer_auth(key).json(&serde_json::json!({
+      "model": config.model.as_deref().unwrap_or(DEFAULT_OPENAI_MODEL),
+      "voice": voice,
+      "input": text,
+      "response_format": "mp3",
+    })),
+    TtsProvider::Azure => {
+      let region = config.region.as_deref().unwrap_or_default().trim();
+      let ssml = format!(
+        "<speak version='1.0' xml:lang='{}'><voice name='{}'>{}</voice></speak>",
+        xml_escape(&voice_locale(voice)),
+        xml_escape(voice),
+        xml_escape(text)
+      );
+      client
+        .post(format!("https://{region}.tts.speech.microsoft.com/cognitiveservices/v1"))
+        .header("Ocp-Apim-Subscription-Key", key)
+        .header("Content-Type", "application/ssml+xml")
+        .header("X-Microsoft-OutputFormat", "audio-24khz-48kbitrate-mono-mp3")
+        .body(ssml)
+    }
+    TtsProvider::Google => client.post(GOOGLE_URL).header("x-goog-api-key", key).json(&serde_json::json!({
+      "input": { "text": text },
+      "voice": { "languageCode": voice_locale(voice), "name": voice },
+      "audioConfig": { "audioEncoding": "MP3" },
+    })),
+  };
+  let resp = request.send().await.map_err(|e| format!("TTS request failed: {e}"))?;
+  let status = resp.status();
+  if !status.is_success() {
+    let body = resp.text().await.unwrap_or_default();
+    let detail: String = body.chars().take(200).collect();
+    return Err(format!("TTS request failed: HTTP {status}: {detail}"));
+  }
+  let bytes = resp.bytes().await.map_err(|e| format!("TTS request failed: {e}"))?;
+  if config.provider != TtsProvider::Google {
+    return Ok(bytes.to_vec());
+  }
+  let body: serde_json::Value = serde_json::from_slice(&bytes).map_err(|e| format!("unexpected TTS response: {e}"))?;
+  let audio = body
+    .get("audioContent")
+    .and_then(|a| a.as_str())
+    .ok_or_else(|| "unexpected TTS response: no audioContent".to_string())?;
+  base64::engine::general_purpose::STANDARD
+    .decode(audio)
+    .map_err(|e| format!("unexpected TTS response: {e}"))
+}
+
+/// Say `text` with the configured cloud voice (`voice` overrides the setting's), from the cache when it was said
+/// before.
+#[tauri::command]
+pub async fn speak(app: tauri::AppHandle, text: String, voice: Option<String>) -> Result<Spoken, String> {
+  let text = text.trim();
+  if text.is_empty() {
+    return Err("nothing to say".to_string());
+  }
+  if text.chars().count() > MAX_CHARS {
+    return Err(format!("text is too long to speak (at most {MAX_CHARS} characters)"));
+  }
+  let mut config = settings::tts_config(&app).ok_or_else(|| "no TTS service is set up (`tts` setting)".to_string())?;
+  if let Some(voice) = voice.filter(|v| !v.trim().is_empty()) {
+    config.voice = Some(voice);
+  }
+  config.check()?;
+
+  let dir = cache_dir(&app)?;
+  let path = dir.join(cache_name(&config, text));
+  let cached = path.is_file();
+  if cached {
+    // Marks it recently played, for `trim_cache`.
+    if let Ok(file) = std::fs::File::options().append(true).open(&path) {
+      let _ = file.set_modified(SystemTime::now());
+    }
+  } else {
+    let key = secrets::get(API_KEY_SECRET)?.ok_or_else(|| format!("no TTS API key (`{API_KEY_SECRET}` secret)"))?;
+    let audio = synthesize(&config, &key, text).await?;
+    let partial = temp::path("tts", "mp3");
+    std::fs::write(&partial, audio).map_err(|e| format!("cannot write the TTS cache: {e}"))?;
+    if std::fs::rename(&partial, &path).is_err() {
+      // The temp area may be on another volume.
+      std::fs::copy(&partial, &path).map_err(|e| format!("cannot write the TTS cache: {e}"))?;
+      let _ = std::fs::remove_file(&partial);
+    }
+    trim_cache(&dir);
+  }
+
+  app.state::<Player>().send(&app, PlayerCommand::Play(path.clone()))?;
+  Ok(Spoken {
+    path: path.to_string_lossy().to_string(),
+    cached,
+  })
+}
+
+/// Stop what `speak` is playing.
+#[tauri::command]
+pub fn stop_speaking(app: tauri::AppHandle) -> Result<(), String> {
+  app.state::<Player>().send(&app, PlayerCommand::Stop)
+}
+
+/// Delete every cached recording; reports what was freed.
+#[tauri::command]
+pub async fn clear_tts_cache(app: tauri::AppHandle) -> Result<temp::Reclaimed, String> {
+  let dir = cache_dir(&app)?;
+  let mut reclaimed = temp::Reclaimed::default();
+  let entries = std::fs::read_dir(&dir).map_err(|e| format!("cannot read the TTS cache: {e}"))?;
+  for entry in entries.flatten() {
+    let Ok(meta) = entry.metadata() else {
+      continue;
+    };
+    if meta.is_file() && std::fs::remove_file(entry.path()).is_ok() {
+      reclaimed.files += 1;
+      reclaimed.bytes += meta.len();
+    }
+  }
+  Ok(reclaimed)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn config(provider: TtsProvider, voice: Option<&str>) -> TtsConfig {
+    TtsConfig { provider, voice: voice.map(str::to_string), model: None, region: None }
+  }
+
+  #[test]
+  fn voice_locale_is_the_language_and_region() {
+    assert_eq!(voice_locale("ja-JP-NanamiNeural"), "ja-JP");
+    assert_eq!(voice_locale("en-US-Neural2-A"), "en-US");
+    assert_eq!(voice_locale("ja-JP"), "ja-JP");
+  }
+
+  #[test]
+  fn cache_name_depends_on_service_voice_and_text() {
+    let nanami = config(TtsProvider::Azure, Some("ja-JP-NanamiNeural"));
+    let name = cache_name(&nanami, "こんにちは");
+    assert!(name.ends_with(".mp3") && name.len() == 64 + 4);
+    assert_eq!(cache_name(&nanami, "こんにちは"), name);
+    assert_ne!(cache_name(&nanami, "こんばんは"), name);
+    assert_ne!(cache_name(&config(TtsProvider::Google, Some("ja-JP-NanamiNeural")), "こんにちは"), name);
+    assert_ne!(cache_name(&config(TtsProvider::Azure, Some("ja-JP-KeitaNeural")), "こんにちは"), name);
+  }
+
+  #[test]
+  fn cache_name_separates_the_parts() {
+    // Without a separator "ab" + "c" and "a" + "bc" would hash alike.
+    let a = cache_name(&config(TtsProvider::Openai, Some("ab")), "c");
+    let b = cache_name(&config(TtsProvider::Openai, Some("a")), "bc");
+    assert_ne!(a, b);
+  }
+
+  #[test]
+  fn openai_has_a_default_voice() {
+    assert_eq!(config(TtsProvider::Openai, Some("  ")).voice(), DEFAULT_OPENAI_VOICE);
+  }
+}
@@ -0,0 +1,215 @@
+//! Type-back: replacing the selection in the app being worked in with a translation, so the app doubles as an
+//! inline writing assistant (write in your language, select, translate, put the translation in its place).
+//!
+//! `paste` (the default, `typeBackMethod`) puts the text on the clipboard, presses Ctrl+V (Cmd+V) and puts the
+//! previous clipboard text or image back once the app has had time to read it. Other clipboard contents (copied
+//! files, the formatting of rich text) can't be kept and are lost. `type` sends the text as keystrokes instead,
+//! for fields that block pasting; it's slower on long text and leaves the clipboard alone. Either way the text
+//! goes to the app the selection (or the clipboard text) was taken from: on Windows that window is remembered when
+//! the text is taken and brought back to the front; elsewhere an unpinned popup that has focus is closed first,
+//! so focus returns to the app underneath. With `autoPaste` the frontend does this for every finished translation,
+//! for chats and games where switching to the popup is too slow. Windows and macOS only.
+
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::{popup, settings};
+
+/// Time for focus to move back to the app after the popup is closed.
+const REFOCUS_MS: u64 = 200;
+/// Time for the clipboard to take the text before the paste is pressed.
+const CLIPBOARD_SETTLE_MS: u64 = 60;
+/// Time the app gets to read the clipboard before the previous text is put back.
+const RESTORE_DELAY_MS: u64 = 400;
+
+/// The window text was last taken from (`HWND`, Windows), to paste back into (managed state).
+#[derive(Default)]
+pub struct TypeBack(Mutex<Option<isize>>);
+
+impl TypeBack {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Option<isize>> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+}
+
+/// Remember the foreground window as the one text is being taken from (before a selection or clipboard read).
+pub fn remember_target(app: &tauri::AppHandle) {
+  #[cfg(windows)]
+  {
+    *app.state::<TypeBack>().lock() = crate::win_window::foreground().map(|hwnd| hwnd as isize);
+  }
+  #[cfg(not(windows))]
+  let _ = app;
+}
+
+/// The window text was last taken from (`HWND`).
+#[cfg(windows)]
+pub fn target(app: &tauri::AppHandle) -> Option<isize> {
+  *app.state::<TypeBack>().lock()
+}
+
+/// Bring `hwnd` back to the front and wait until it is.
+#[cfg(windows)]
+fn activate(hwnd: isize) -> Result<(), String> {
+  use windows_sys::Win32::Foundation::HWND;
+  use windows_sys::Win32::UI::WindowsAndMessaging::{
+    GetForegroundWindow, IsIconic, IsWindow, SetForegroundWindow, ShowWindow, SW_RESTORE,
+  };
+
+  let hwnd = hwnd as HWND;
+  if unsafe { IsWindow(hwnd) } == 0 {
+    return Err("the window the text came from has closed".to_string());
+  }
+  if unsafe { GetForegroundWindow() } == hwnd {
+    return Ok(());
+  }
+  unsafe {
+    if IsIconic(hwnd) != 0 {
+      ShowWindow(hwnd, SW_RESTORE);
+    }
+    SetForegroundWindow(hwnd);
+  }
+  let started = std::time::Instant::now();
+  while started.elapsed() < Duration::from_millis(REFOCUS_MS) {
+    if unsafe { GetForegroundWindow() } == hwnd {
+      return Ok(());
+    }
+    std::thread::sleep(Duration::from_millis(20));
+  }
+  Err("the window the text came from could not be brought to the front".to_string())
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TypeBackMethod {
+  Paste,
+  Type,
+}
+
+fn method_setting(app: &tauri::AppHandle) -> TypeBackMethod {
+  match settings::type_back_method(app).as_str() {
+    "type" => TypeBackMethod::Type,
+    _ => TypeBackMethod::Paste,
+  }
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn keyboard() -> Result<enigo::Enigo, String> {
+  use enigo::{Direction::Release, Enigo, Key, Keyboard, Settings};
+
+  let mut enigo = Enigo::new(&Settings::default()).map_err(|e| format!("enigo init failed: {e}"))?;
+  // Modifiers still held from a hotkey would turn the paste or the typing into shortcuts.
+  for key in [Key::Alt, Key::Shift, Key::Control, Key::Meta] {
+    let _ = enigo.key(key, Release);
+  }
+  Ok(enigo)
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn press_paste() -> Result<(), String> {
+  use enigo::{
+    Direction::{Click, Press, Release},
+    Key, Keyboard,
+  };
+
+  let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
+  let mut enigo = keyboard()?;
+  enigo.key(modifier, Press).map_err(|e| format!("enigo key failed: {e}"))?;
+  let clicked = enigo.key(Key::Unicode('v'), Click);
+  let _ = enigo.key(modifier, Release);
+  clicked.map_err(|e| format!("enigo key failed: {e}"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn press_paste() -> Result<(), String> {
+  Err("replacing the selection isn't supported on this platform".to_string())
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn type_text(text: &str) -> Result<(), String> {
+  use enigo::Keyboard;
+
+  keyboard()?.text(text).map_err(|e| format!("typing failed: {e}"))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn type_text(_text: &str) -> Result<(), String> {
+  Err("replacing the selection isn't supported on this platform".to_string())
+}
+
+/// What the clipboard held before a paste.
+enum Saved {
+  Text(String),
+  Image(arboard::ImageData<'static>),
+}
+
+impl Saved {
+  fn take(clipboard: &mut arboard::Clipboard) -> Option<Saved> {
+    clipboard.get_text().ok().map(Saved::Text).or_else(|| clipboard.get_image().ok().map(Saved::Image))
+  }
+
+  fn restore(self, clipboard: &mut arboard::Clipboard) {
+    let _ = match self {
+      Saved::Text(text) => clipboard.set_text(text),
+      Saved::Image(image) => clipboard.set_image(image),
+    };
+  }
+}
+
+/// Paste `text` through the clipboard, then restore the clipboard's previous text or image (if it still holds
+/// `text`).
+fn paste(text: &str) -> Result<(), String> {
+  let mut clipboard = arboard::Clipboard::new().map_err(|e| format!("clipboard init failed: {e}"))?;
+  let prev = Saved::take(&mut clipboard);
+  clipboard.set_text(text.to_string()).map_err(|e| format!("clipboard write failed: {e}"))?;
+  std::thread::sleep(Duration::from_millis(CLIPBOARD_SETTLE_MS));
+  let pasted = press_paste();
+  std::thread::sleep(Duration::from_millis(RESTORE_DELAY_MS));
+  if let Some(prev) = prev {
+    // Something else copied in the meantime: leave it.
+    if clipboard.get_text().ok().as_deref() == Some(text) {
+      prev.restore(&mut clipboard);
+    }
+  }
+  pasted
+}
+
+/// Put `text` in the focused window with `method`.
+fn insert(text: &str, method: TypeBackMethod) -> Result<(), String> {
+  match method {
+    TypeBackMethod::Paste => paste(text),
+    TypeBackMethod::Type => type_text(text),
+  }
+}
+
+/// Replace the selection in the app the text was taken from with `text`; `method` defaults to `typeBackMethod`.
+#[tauri::command]
+pub async fn replace_selection_with(
+  app: tauri::AppHandle,
+  text: String,
+  method: Option<TypeBackMethod>,
+) -> Result<(), String> {
+  if text.is_empty() {
+    return Err("nothing to insert".to_string());
+  }
+  let method = method.unwrap_or_else(|| method_setting(&app));
+  let target = *app.state::<TypeBack>().lock();
+  let focused_popup = app
+    .get_webview_window(popup::POPUP_LABEL)
+    .filter(|w| w.is_focused().unwrap_or(false) && !popup::is_pinned(&app, popup::POPUP_LABEL));
+  if let Some(window) = focused_popup.filter(|_| target.is_none()) {
+    let _ = window.destroy();
+    tokio::time::sleep(Duration::from_millis(REFOCUS_MS)).await;
+  }
+  tauri::async_runtime::spawn_blocking(move || {
+    #[cfg(windows)]
+    if let Some(hwnd) = target {
+      activate(hwnd)?;
+    }
+    insert(&text, method)
+  })
+  .await
+  .map_err(|e| format!("type-back task failed: {e}"))?
+}
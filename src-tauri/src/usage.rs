@@ -0,0 +1,378 @@
+//! Token usage and cost of translation requests, and daily activity statistics.
+//!
+//! Each day's characters translated, OCR runs, requests per provider and token counts are kept in `usage.json` in
+//! the app data dir, keyed by local date, so users can see their activity and stay under provider quotas;
+//! `get_usage_stats` returns the days in a range along with the totals since app start. Counts are written
+//! `SAVE_DELAY` after the first change, so a burst of requests costs one write, and on exit.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Manager;
+
+const USAGE_FILE: &str = "usage.json";
+/// How long a change waits before it is written, gathering the ones that follow.
+const SAVE_DELAY: Duration = Duration::from_secs(5);
+/// Days of statistics kept; older ones are dropped.
+const MAX_DAYS: usize = 731;
+
+/// Per-1k-token prices used to estimate request cost when the provider doesn't report one.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct TokenPricing {
+  pub input_per_1k: f64,
+  pub output_per_1k: f64,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, Default)]
+pub struct Usage {
+  pub input_tokens: u64,
+  pub output_tokens: u64,
+  pub estimated_cost: f64,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct UsageStats {
+  pub requests: u64,
+  pub input_tokens: u64,
+  pub output_tokens: u64,
+  pub estimated_cost: f64,
+  /// Requests whose counts were estimated locally (provider sent no usage metadata).
+  pub estimated_requests: u64,
+}
+
+/// One day's activity (or a range's, added up).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DailyUsage {
+  /// Local date, `YYYY-MM-DD`; empty for a range total.
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub date: String,
+  /// Characters of source text translated.
+  pub characters: u64,
+  pub ocr_runs: u64,
+  pub requests: u64,
+  pub input_tokens: u64,
+  pub output_tokens: u64,
+  pub estimated_cost: f64,
+  /// Requests per provider: a provider plugin's id, "offline", or the server's host.
+  pub providers: BTreeMap<String, u64>,
+}
+
+impl DailyUsage {
+  fn add(&mut self, other: &DailyUsage) {
+    self.characters += other.characters;
+    self.ocr_runs += other.ocr_runs;
+    self.requests += other.requests;
+    self.input_tokens += other.input_tokens;
+    self.output_tokens += other.output_tokens;
+    self.estimated_cost += other.estimated_cost;
+    for (provider, n) in &other.providers {
+      *self.providers.entry(provider.clone()).or_default() += n;
+    }
+  }
+}
+
+fn by_date(days: Vec<DailyUsage>) -> BTreeMap<String, DailyUsage> {
+  days.into_iter().filter(|d| !d.date.is_empty()).map(|d| (d.date.clone(), d)).collect()
+}
+
+#[derive(Default)]
+struct Inner {
+  /// Since app start (or `reset_usage_stats`).
+  session: UsageStats,
+  path: Option<PathBuf>,
+  days: BTreeMap<String, DailyUsage>,
+  /// A save is scheduled (see `schedule_save`).
+  save_pending: bool,
+}
+
+impl Inner {
+  fn today(&mut self) -> &mut DailyUsage {
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    if !self.days.contains_key(&date) {
+      while self.days.len() >= MAX_DAYS {
+        self.days.pop_first();
+      }
+    }
+    self.days.entry(date.clone()).or_insert_with(|| DailyUsage {
+      date,
+      ..Default::default()
+    })
+  }
+
+  fn save(&self) {
+    let Some(path) = &self.path else {
+      return;
+    };
+    let days: Vec<&DailyUsage> = self.days.values().collect();
+    if let Err(e) = crate::json_store::save(path, &days) {
+      log::warn!("failed to save usage statistics: {e}");
+    }
+  }
+
+  /// Save `SAVE_DELAY` from now, unless a save is already waiting.
+  fn schedule_save(&mut self, app: &tauri::AppHandle) {
+    if self.save_pending {
+      return;
+    }
+    self.save_pending = true;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+      tokio::time::sleep(SAVE_DELAY).await;
+      flush(&app);
+    });
+  }
+}
+
+/// Usage since app start and per day (managed state).
+#[derive(Default)]
+pub struct UsageState(Mutex<Inner>);
+
+impl UsageState {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Count a finished translation of `characters` source characters by `provider`.
+  pub fn record(&self, app: &tauri::AppHandle, provider: &str, characters: usize, usage: &Usage, estimated: bool) {
+    let mut inner = self.lock();
+    let s = &mut inner.session;
+    s.requests += 1;
+    s.input_tokens += usage.input_tokens;
+    s.output_tokens += usage.output_tokens;
+    s.estimated_cost += usage.estimated_cost;
+    if estimated {
+      s.estimated_requests += 1;
+    }
+    let day = inner.today();
+    day.characters += characters as u64;
+    day.requests += 1;
+    day.input_tokens += usage.input_tokens;
+    day.output_tokens += usage.output_tokens;
+    day.estimated_cost += usage.estimated_cost;
+    *day.providers.entry(provider.to_string()).or_default() += 1;
+    inner.schedule_save(app);
+  }
+
+  /// Count a finished OCR run.
+  pub fn record_ocr(&self, app: &tauri::AppHandle) {
+    let mut inner = self.lock();
+    inner.today().ocr_runs += 1;
+    inner.schedule_save(app);
+  }
+
+  /// Every saved day, oldest first (for backups).
+  pub fn days(&self) -> Vec<DailyUsage> {
+    self.lock().days.values().cloned().collect()
+  }
+
+  /// Replace the saved days with `days` and save them.
+  pub fn replace_days(&self, days: Vec<DailyUsage>) {
+    let mut inner = self.lock();
+    inner.days = by_date(days);
+    inner.save();
+  }
+
+  pub fn snapshot(&self) -> UsageStats {
+    self.lock().session.clone()
+  }
+
+  pub fn reset(&self) {
+    self.lock().session = UsageStats::default();
+  }
+}
+
+/// Write the counts now if a save is waiting (and on exit).
+pub fn flush(app: &tauri::AppHandle) {
+  let state = app.state::<UsageState>();
+  let mut inner = state.lock();
+  if inner.save_pending {
+    inner.save_pending = false;
+    inner.save();
+  }
+}
+
+/// Load the saved daily statistics from `dir`.
+pub fn init(app: &tauri::AppHandle, dir: &Path) {
+  let path = dir.join(USAGE_FILE);
+  let days: Vec<DailyUsage> = crate::json_store::load(app, &path, "usage_unreadable");
+  let state = app.state::<UsageState>();
+  let mut inner = state.lock();
+  inner.path = Some(path);
+  inner.days = by_date(days);
+}
+
+/// Rough token estimate: CJK characters count as one token each, everything else ~4 chars per token.
+pub fn estimate_tokens(text: &str) -> u64 {
+  let mut cjk: u64 = 0;
+  let mut other: u64 = 0;
+  for c in text.chars() {
+    let cp = c as u32;
+    let is_cjk = matches!(cp,
+      0x3040..=0x30FF // hiragana / katakana
+      | 0x3400..=0x4DBF
+      | 0x4E00..=0x9FFF
+      | 0xAC00..=0xD7AF // hangul
+      | 0xF900..=0xFAFF
+      | 0xFF00..=0xFFEF);
+    if is_cjk {
+      cjk += 1;
+    } else {
+      other += 1;
+    }
+  }
+  cjk + other.div_ceil(4)
+}
+
+/// Provider-reported usage found in an SSE frame.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportedUsage {
+  pub input_tokens: u64,
+  pub output_tokens: u64,
+  pub cost: Option<f64>,
+}
+
+impl ReportedUsage {
+  /// Sum per-chunk reports; `None` if any chunk was unreported.
+  pub fn sum(reports: &[Option<ReportedUsage>]) -> Option<ReportedUsage> {
+    let mut total = ReportedUsage {
+      input_tokens: 0,
+      output_tokens: 0,
+      cost: Some(0.0),
+    };
+    for r in reports {
+      let r = (*r)?;
+      total.input_tokens += r.input_tokens;
+      total.output_tokens += r.output_tokens;
+      total.cost = match (total.cost, r.cost) {
+        (Some(a), Some(b)) => Some(a + b),
+        _ => None,
+      };
+    }
+    Some(total)
+  }
+}
+
+/// Extract usage metadata from a frame. Accepts both OpenAI-style (`prompt_tokens`/`completion_tokens`)
+/// and Anthropic-style (`input_tokens`/`output_tokens`) field names.
+pub fn parse_usage(v: &serde_json::Value) -> Option<ReportedUsage> {
+  let u = v.get("usage").filter(|u| u.is_object())?;
+  let num = |keys: &[&str]| keys.iter().find_map(|k| u.get(*k).and_then(|x| x.as_u64()));
+  let input_tokens = num(&["input_tokens", "prompt_tokens"]);
+  let output_tokens = num(&["output_tokens", "completion_tokens"]);
+  if input_tokens.is_none() && output_tokens.is_none() {
+    return None;
+  }
+  let cost = u
+    .get("cost")
+    .or_else(|| u.get("estimated_cost"))
+    .or_else(|| v.get("cost"))
+    .and_then(|x| x.as_f64());
+  Some(ReportedUsage {
+    input_tokens: input_tokens.unwrap_or(0),
+    output_tokens: output_tokens.unwrap_or(0),
+    cost,
+  })
+}
+
+pub fn estimate_cost(input_tokens: u64, output_tokens: u64, pricing: Option<TokenPricing>) -> f64 {
+  let p = pricing.unwrap_or_default();
+  (input_tokens as f64 / 1000.0) * p.input_per_1k + (output_tokens as f64 / 1000.0) * p.output_per_1k
+}
+
+/// Resolve the final usage for a request: prefer provider metadata, fall back to character estimates.
+/// Returns the usage and whether it was estimated.
+pub fn finalize(
+  reported: Option<ReportedUsage>,
+  input_text: &str,
+  output_text: &str,
+  pricing: Option<TokenPricing>,
+) -> (Usage, bool) {
+  match reported {
+    Some(r) => {
+      let estimated_cost = r
+        .cost
+        .unwrap_or_else(|| estimate_cost(r.input_tokens, r.output_tokens, pricing));
+      (
+        Usage {
+          input_tokens: r.input_tokens,
+          output_tokens: r.output_tokens,
+          estimated_cost,
+        },
+        false,
+      )
+    }
+    None => {
+      let input_tokens = estimate_tokens(input_text);
+      let output_tokens = estimate_tokens(output_text);
+      (
+        Usage {
+          input_tokens,
+          output_tokens,
+          estimated_cost: estimate_cost(input_tokens, output_tokens, pricing),
+        },
+        true,
+      )
+    }
+  }
+}
+
+/// Days to report: `from`..=`to` (local dates, `YYYY-MM-DD`), or the last `days` days including today.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct UsageRange {
+  pub from: Option<String>,
+  pub to: Option<String>,
+  pub days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct UsageReport {
+  /// Since app start (or `reset_usage_stats`).
+  pub session: UsageStats,
+  /// Days in the range with any activity, oldest first.
+  pub days: Vec<DailyUsage>,
+  /// The range's days added up.
+  pub total: DailyUsage,
+}
+
+fn parse_date(s: &str) -> Result<chrono::NaiveDate, String> {
+  chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+    .map_err(|_| format!("invalid date {s:?} (expected YYYY-MM-DD)"))
+}
+
+/// Usage since app start, and per day over `range` (every saved day without one).
+#[tauri::command]
+pub fn get_usage_stats(state: tauri::State<'_, UsageState>, range: Option<UsageRange>) -> Result<UsageReport, String> {
+  let range = range.unwrap_or_default();
+  let mut from = range.from.as_deref().map(parse_date).transpose()?;
+  let to = range.to.as_deref().map(parse_date).transpose()?;
+  if let Some(days) = range.days.filter(|d| *d > 0) {
+    let today = chrono::Local::now().date_naive();
+    from = today.checked_sub_days(chrono::Days::new(u64::from(days) - 1));
+  }
+  let (from, to) = (from.map(|d| d.to_string()), to.map(|d| d.to_string()));
+  let inner = state.lock();
+  let days: Vec<DailyUsage> = inner
+    .days
+    .values()
+    .filter(|d| from.as_ref().map_or(true, |f| d.date >= *f) && to.as_ref().map_or(true, |t| d.date <= *t))
+    .cloned()
+    .collect();
+  let mut total = DailyUsage::default();
+  for day in &days {
+    total.add(day);
+  }
+  Ok(UsageReport {
+    session: inner.session.clone(),
+    days,
+    total,
+  })
+}
+
+/// Reset the totals since app start; the daily statistics are kept.
+#[tauri::command]
+pub fn reset_usage_stats(state: tauri::State<'_, UsageState>) {
+  state.reset();
+}
@@ -0,0 +1,113 @@
+//! Apple Vision OCR (`VNRecognizeTextRequest`, macOS 10.15+).
+//!
+//! Driven through `osascript`'s JavaScript-for-Automation bridge, so no Objective-C bindings are linked.
+//! Japanese and Korean need macOS 13.
+
+use std::process::Command;
+
+use crate::ocr::{self, OcrImage, OcrOutput};
+use crate::queue::CancelToken;
+
+const SCRIPT: &str = r#"
+ObjC.import("Vision");
+function run(argv) {
+  const request = $.VNRecognizeTextRequest.alloc.init;
+  if (argv[0] === "--list") {
+    const langs = request.supportedRecognitionLanguagesAndReturnError(null);
+    const out = [];
+    for (let i = 0; i < langs.count; i++) out.push(langs.objectAtIndex(i).js);
+    return out.join("\n");
+  }
+  request.recognitionLevel = 0; // accurate
+  request.usesLanguageCorrection = true;
+  if (argv[1]) request.recognitionLanguages = $(argv[1].split(","));
+  // "-": the image is piped in.
+  const data =
+    argv[0] === "-"
+      ? $.NSFileHandle.fileHandleWithStandardInput.readDataToEndOfFile
+      : $.NSData.dataWithContentsOfFile(argv[0]);
+  const handler = $.VNImageRequestHandler.alloc.initWithDataOptions(data, $({}));
+  const error = Ref();
+  if (!handler.performRequestsError($([request]), error)) {
+    throw new Error(ObjC.unwrap(error[0].localizedDescription));
+  }
+  // Vision boxes are normalized with a bottom-left origin; report image pixels from the top-left.
+  const rep = $.NSBitmapImageRep.imageRepWithData(data);
+  const w = rep.pixelsWide, h = rep.pixelsHigh;
+  const toBox = (b) => ({
+    x: Math.round(b.origin.x * w),
+    y: Math.round((1 - b.origin.y - b.size.height) * h),
+    width: Math.round(b.size.width * w),
+    height: Math.round(b.size.height * h),
+  });
+  const results = request.results;
+  const lines = [];
+  for (let i = 0; i < results.count; i++) {
+    const observation = results.objectAtIndex(i);
+    const top = observation.topCandidates(1);
+    if (top.count === 0) continue;
+    const candidate = top.objectAtIndex(0);
+    const text = candidate.string.js;
+    const words = [];
+    const re = /\S+/g;
+    let m;
+    while ((m = re.exec(text)) !== null) {
+      const range = candidate.boundingBoxForRangeError({ location: m.index, length: m[0].length }, null);
+      if (range && !range.isNil()) words.push({ text: m[0], bbox: toBox(range.boundingBox) });
+    }
+    // Vision scores whole lines only (0-1).
+    lines.push({ text, bbox: toBox(observation.boundingBox), confidence: candidate.confidence * 100, words });
+  }
+  return JSON.stringify({ text: lines.map((l) => l.text).join("\n"), lines });
+}
+"#;
+
+fn run(args: &[&str], input: Option<&[u8]>, cancel: &CancelToken) -> Result<String, String> {
+  let mut cmd = Command::new("osascript");
+  cmd.args(["-l", "JavaScript", "-e", SCRIPT]).args(args);
+  let out = ocr::run_process(cmd, input, cancel).map_err(|e| format!("vision ocr failed: {e}"))?;
+  if !out.status.success() {
+    return Err(format!("vision ocr failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+  }
+  Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Recognition languages Vision supports on this macOS (identifiers like `"en-US"`, `"ja-JP"`).
+pub fn languages() -> Result<Vec<String>, String> {
+  Ok(run(&["--list"], None, &CancelToken::default())?.lines().map(str::to_string).filter(|l| !l.is_empty()).collect())
+}
+
+/// Vision identifiers for the languages in `lang` (`+`-separated Tesseract codes or tags) it supports.
+fn vision_languages(lang: &str, supported: &[String]) -> Vec<String> {
+  let primary = |tag: &str| tag.split('-').next().unwrap_or(tag).to_ascii_lowercase();
+  let mut out: Vec<String> = Vec::new();
+  for code in lang.split('+') {
+    let tag = crate::ocr::to_bcp47(code.trim());
+    let found = supported
+      .iter()
+      .find(|s| s.eq_ignore_ascii_case(tag))
+      .or_else(|| supported.iter().find(|s| primary(s) == primary(tag)));
+    if let Some(s) = found.filter(|s| !out.contains(s)) {
+      out.push(s.clone());
+    }
+  }
+  out
+}
+
+/// Whether Vision supports every language in `lang`.
+pub fn supports(lang: &str) -> bool {
+  let Ok(supported) = languages() else { return false };
+  let codes: Vec<&str> = lang.split('+').map(str::trim).filter(|c| !c.is_empty()).collect();
+  !codes.is_empty() && codes.iter().all(|code| !vision_languages(code, &supported).is_empty())
+}
+
+/// OCR an image. Blocking.
+pub fn recognize(image: &OcrImage, lang: &str, cancel: &CancelToken) -> Result<OcrOutput, String> {
+  let langs = vision_languages(lang, &languages()?);
+  if langs.is_empty() {
+    return Err(format!("VISION_OCR_LANG_MISSING\n\nVision can't recognize {lang} on this macOS"));
+  }
+  let (input, data) = image.process_input("-");
+  let json = run(&[input, &langs.join(",")], data, cancel)?;
+  serde_json::from_str(&json).map_err(|e| format!("vision ocr failed: unexpected output: {e}"))
+}
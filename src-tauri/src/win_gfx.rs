@@ -0,0 +1,211 @@
+//! RAII wrappers for the GDI handles used by screen capture.
+//!
+//! Each guard releases its handle on drop, so early returns can't leak device contexts or bitmaps. Declare
+//! guards in acquisition order; Rust drops locals in reverse, which is the order GDI expects (restore the
+//! selection, delete the bitmap, delete the memory DC, release the window DC).
+
+use windows_sys::Win32::Devices::Display::{
+  DisplayConfigGetDeviceInfo, GetDisplayConfigBufferSizes, QueryDisplayConfig,
+  DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO, DISPLAYCONFIG_DEVICE_INFO_HEADER,
+  DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO, DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE,
+  DISPLAYCONFIG_PATH_INFO, QDC_ONLY_ACTIVE_PATHS,
+};
+use windows_sys::Win32::Foundation::{ERROR_SUCCESS, HWND};
+use windows_sys::Win32::Graphics::Gdi::{
+  CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, ReleaseDC, SelectObject, HBITMAP, HDC,
+  HGDIOBJ,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+  DrawIconEx, GetCursorInfo, GetIconInfo, GetSystemMetrics, CURSORINFO, CURSOR_SHOWING, DI_NORMAL, ICONINFO,
+  SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN,
+};
+
+/// Bounds of the virtual screen (all monitors) as `(x, y, width, height)`. Monitors left of or above the primary
+/// one have negative coordinates.
+pub fn virtual_screen() -> (i32, i32, i32, i32) {
+  unsafe {
+    (
+      GetSystemMetrics(SM_XVIRTUALSCREEN),
+      GetSystemMetrics(SM_YVIRTUALSCREEN),
+      GetSystemMetrics(SM_CXVIRTUALSCREEN),
+      GetSystemMetrics(SM_CYVIRTUALSCREEN),
+    )
+  }
+}
+
+/// Whether any display overlapping the `width` x `height` rectangle at (`x`, `y`) has HDR ("advanced color") on.
+pub fn hdr_active(x: i32, y: i32, width: u32, height: u32) -> bool {
+  let (mut path_count, mut mode_count) = (0u32, 0u32);
+  let status = unsafe { GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count) };
+  if status != ERROR_SUCCESS {
+    return false;
+  }
+  let mut paths: Vec<DISPLAYCONFIG_PATH_INFO> = vec![unsafe { std::mem::zeroed() }; path_count as usize];
+  let mut modes: Vec<DISPLAYCONFIG_MODE_INFO> = vec![unsafe { std::mem::zeroed() }; mode_count as usize];
+  let status = unsafe {
+    QueryDisplayConfig(
+      QDC_ONLY_ACTIVE_PATHS,
+      &mut path_count,
+      paths.as_mut_ptr(),
+      &mut mode_count,
+      modes.as_mut_ptr(),
+      std::ptr::null_mut(),
+    )
+  };
+  if status != ERROR_SUCCESS {
+    return false;
+  }
+  paths.truncate(path_count as usize);
+  modes.truncate(mode_count as usize);
+
+  let (right, bottom) = (x + width as i32, y + height as i32);
+  paths.iter().any(|path| {
+    let index = unsafe { path.sourceInfo.Anonymous.modeInfoIdx } as usize;
+    let Some(mode) = modes.get(index).filter(|m| m.infoType == DISPLAYCONFIG_MODE_INFO_TYPE_SOURCE) else {
+      return false;
+    };
+    let source = unsafe { mode.Anonymous.sourceMode };
+    let (left, top) = (source.position.x, source.position.y);
+    if right <= left || bottom <= top || x >= left + source.width as i32 || y >= top + source.height as i32 {
+      return false;
+    }
+    let mut info: DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO = unsafe { std::mem::zeroed() };
+    info.header = DISPLAYCONFIG_DEVICE_INFO_HEADER {
+      r#type: DISPLAYCONFIG_DEVICE_INFO_GET_ADVANCED_COLOR_INFO,
+      size: std::mem::size_of::<DISPLAYCONFIG_GET_ADVANCED_COLOR_INFO>() as u32,
+      adapterId: path.targetInfo.adapterId,
+      id: path.targetInfo.id,
+    };
+    // Bit 0 is "supported", bit 1 "enabled".
+    unsafe { DisplayConfigGetDeviceInfo(&mut info.header) == 0 && info.Anonymous.value & 0b10 != 0 }
+  })
+}
+
+/// Draw the mouse pointer into `dc`, whose top-left corner is at (`origin_x`, `origin_y`) on screen. Does nothing
+/// when the pointer is hidden.
+pub fn draw_cursor(dc: HDC, origin_x: i32, origin_y: i32) {
+  let mut info: CURSORINFO = unsafe { std::mem::zeroed() };
+  info.cbSize = std::mem::size_of::<CURSORINFO>() as u32;
+  if unsafe { GetCursorInfo(&mut info) } == 0 || info.flags & CURSOR_SHOWING == 0 || info.hCursor.is_null() {
+    return;
+  }
+  let mut icon: ICONINFO = unsafe { std::mem::zeroed() };
+  if unsafe { GetIconInfo(info.hCursor, &mut icon) } == 0 {
+    return;
+  }
+  // GetIconInfo hands over copies of the cursor's bitmaps.
+  let _mask = Bitmap(icon.hbmMask);
+  let _color = (!icon.hbmColor.is_null()).then(|| Bitmap(icon.hbmColor));
+  let x = info.ptScreenPos.x - icon.xHotspot as i32 - origin_x;
+  let y = info.ptScreenPos.y - icon.yHotspot as i32 - origin_y;
+  unsafe { DrawIconEx(dc, x, y, info.hCursor, 0, 0, 0, std::ptr::null_mut(), DI_NORMAL) };
+}
+
+/// A DC obtained with `GetDC` (released with `ReleaseDC`).
+pub struct WindowDc {
+  hwnd: HWND,
+  hdc: HDC,
+}
+
+impl WindowDc {
+  /// DC for `hwnd`; a null window gives the whole screen.
+  pub fn get(hwnd: HWND) -> Result<Self, String> {
+    let hdc = unsafe { GetDC(hwnd) };
+    if hdc.is_null() {
+      return Err("GetDC failed".to_string());
+    }
+    Ok(Self { hwnd, hdc })
+  }
+
+  /// DC for the whole virtual screen, addressed in screen coordinates (negative left of / above the primary
+  /// monitor).
+  pub fn screen() -> Result<Self, String> {
+    Self::get(std::ptr::null_mut())
+  }
+
+  pub fn hdc(&self) -> HDC {
+    self.hdc
+  }
+}
+
+impl Drop for WindowDc {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = ReleaseDC(self.hwnd, self.hdc);
+    }
+  }
+}
+
+/// A memory DC from `CreateCompatibleDC` (deleted with `DeleteDC`).
+pub struct MemoryDc(HDC);
+
+impl MemoryDc {
+  pub fn compatible_with(dc: HDC) -> Result<Self, String> {
+    let hdc = unsafe { CreateCompatibleDC(dc) };
+    if hdc.is_null() {
+      return Err("CreateCompatibleDC failed".to_string());
+    }
+    Ok(Self(hdc))
+  }
+
+  pub fn hdc(&self) -> HDC {
+    self.0
+  }
+
+  /// Select `bitmap` into this DC until the returned guard is dropped.
+  pub fn select<'a>(&'a self, bitmap: &'a Bitmap) -> Result<Selection<'a>, String> {
+    let old = unsafe { SelectObject(self.0, bitmap.0 as HGDIOBJ) };
+    if old.is_null() {
+      return Err("SelectObject failed".to_string());
+    }
+    Ok(Selection { dc: self, old })
+  }
+}
+
+impl Drop for MemoryDc {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = DeleteDC(self.0);
+    }
+  }
+}
+
+/// A bitmap from `CreateCompatibleBitmap` (deleted with `DeleteObject`).
+pub struct Bitmap(HBITMAP);
+
+impl Bitmap {
+  pub fn compatible(dc: HDC, width: i32, height: i32) -> Result<Self, String> {
+    let bmp = unsafe { CreateCompatibleBitmap(dc, width, height) };
+    if bmp.is_null() {
+      return Err("CreateCompatibleBitmap failed".to_string());
+    }
+    Ok(Self(bmp))
+  }
+
+  pub fn handle(&self) -> HBITMAP {
+    self.0
+  }
+}
+
+impl Drop for Bitmap {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = DeleteObject(self.0 as HGDIOBJ);
+    }
+  }
+}
+
+/// An object selected into a memory DC; the previous object is restored on drop.
+/// Borrows the DC and the bitmap so neither can be destroyed while still selected.
+pub struct Selection<'a> {
+  dc: &'a MemoryDc,
+  old: HGDIOBJ,
+}
+
+impl Drop for Selection<'_> {
+  fn drop(&mut self) {
+    unsafe {
+      let _ = SelectObject(self.dc.0, self.old);
+    }
+  }
+}
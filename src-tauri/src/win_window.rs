@@ -0,0 +1,115 @@
+//! Looking up top-level windows on Windows: titles, owning process, finding one by title or executable, and the
+//! foreground window.
+
+use windows_sys::Win32::Foundation::{CloseHandle, BOOL, HWND, LPARAM, RECT};
+use windows_sys::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONULL};
+use windows_sys::Win32::System::Threading::{
+  OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows_sys::Win32::UI::WindowsAndMessaging::{
+  EnumWindows, GetClassNameW, GetForegroundWindow, GetShellWindow, GetWindowRect, GetWindowTextLengthW, GetWindowTextW,
+  GetWindowThreadProcessId, IsWindowVisible,
+};
+
+/// Visible top-level windows, front to back.
+pub fn visible_windows() -> Vec<HWND> {
+  unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let windows = &mut *(lparam as *mut Vec<HWND>);
+    if IsWindowVisible(hwnd) != 0 {
+      windows.push(hwnd);
+    }
+    1
+  }
+  let mut windows: Vec<HWND> = Vec::new();
+  unsafe { EnumWindows(Some(collect), &mut windows as *mut Vec<HWND> as LPARAM) };
+  windows
+}
+
+pub fn title(hwnd: HWND) -> String {
+  let len = unsafe { GetWindowTextLengthW(hwnd) };
+  if len <= 0 {
+    return String::new();
+  }
+  let mut buf = vec![0u16; len as usize + 1];
+  let n = unsafe { GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32) };
+  String::from_utf16_lossy(&buf[..n.max(0) as usize])
+}
+
+/// File name of the executable that owns `hwnd` (`"notepad.exe"`), if it can be queried.
+pub fn process_name(hwnd: HWND) -> Option<String> {
+  let mut pid = 0u32;
+  unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+  if pid == 0 {
+    return None;
+  }
+  let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid) };
+  if process.is_null() {
+    return None;
+  }
+  let mut buf = vec![0u16; 1024];
+  let mut len = buf.len() as u32;
+  let ok = unsafe { QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, buf.as_mut_ptr(), &mut len) };
+  unsafe { CloseHandle(process) };
+  if ok == 0 {
+    return None;
+  }
+  let path = String::from_utf16_lossy(&buf[..len as usize]);
+  path.rsplit(['\\', '/']).next().map(str::to_string)
+}
+
+/// The frontmost visible window whose title contains `needle` (case-insensitive).
+pub fn find_by_title(needle: &str) -> Option<HWND> {
+  let needle = needle.to_lowercase();
+  visible_windows()
+    .into_iter()
+    .find(|&hwnd| title(hwnd).to_lowercase().contains(&needle))
+}
+
+/// The frontmost titled window of the process running `exe` (`"notepad"` or `"notepad.exe"`).
+pub fn find_by_process(exe: &str) -> Option<HWND> {
+  let exe = exe.to_lowercase();
+  let exe = exe.strip_suffix(".exe").unwrap_or(&exe);
+  visible_windows().into_iter().find(|&hwnd| {
+    !title(hwnd).is_empty()
+      && process_name(hwnd).is_some_and(|name| {
+        let name = name.to_lowercase();
+        name.strip_suffix(".exe").unwrap_or(&name) == exe
+      })
+  })
+}
+
+/// The foreground window, unless it's the desktop or belongs to this app.
+pub fn foreground() -> Option<HWND> {
+  let hwnd = unsafe { GetForegroundWindow() };
+  if hwnd.is_null() || hwnd == unsafe { GetShellWindow() } {
+    return None;
+  }
+  let mut pid = 0u32;
+  unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+  if pid == std::process::id() {
+    return None;
+  }
+  let mut class = [0u16; 64];
+  let n = unsafe { GetClassNameW(hwnd, class.as_mut_ptr(), class.len() as i32) };
+  let class = String::from_utf16_lossy(&class[..n.max(0) as usize]);
+  (class != "Progman" && class != "WorkerW").then_some(hwnd)
+}
+
+/// Whether `hwnd` covers its whole monitor (exclusive or borderless fullscreen).
+pub fn is_fullscreen(hwnd: HWND) -> bool {
+  let mut rect: RECT = unsafe { std::mem::zeroed() };
+  if unsafe { GetWindowRect(hwnd, &mut rect) } == 0 {
+    return false;
+  }
+  let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONULL) };
+  if monitor.is_null() {
+    return false;
+  }
+  let mut info: MONITORINFO = unsafe { std::mem::zeroed() };
+  info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+  if unsafe { GetMonitorInfoW(monitor, &mut info) } == 0 {
+    return false;
+  }
+  let m = info.rcMonitor;
+  rect.left <= m.left && rect.top <= m.top && rect.right >= m.right && rect.bottom >= m.bottom
+}
@@ -0,0 +1,195 @@
+//! Built-in Windows 10/11 OCR (`Windows.Media.Ocr`).
+//!
+//! Needs no install: recognizers come with the Windows language packs. One recognizer handles one language,
+//! so a Tesseract-style `"jpn+eng"` uses the first of those languages Windows has installed.
+
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WindowsOcrLanguage {
+  /// BCP-47 tag (e.g. `"ja"`, `"en-US"`).
+  pub tag: String,
+  pub name: String,
+}
+
+#[cfg(windows)]
+mod imp {
+  use windows::core::HSTRING;
+  use windows::Globalization::Language;
+  use windows::Graphics::Imaging::BitmapDecoder;
+  use windows::Media::Ocr::OcrEngine;
+  use windows::Storage::Streams::{DataWriter, IRandomAccessStream, InMemoryRandomAccessStream};
+  use windows::Storage::{FileAccessMode, StorageFile};
+  use windows::Win32::System::WinRT::{RoInitialize, RO_INIT_MULTITHREADED};
+
+  use crate::ocr::{BBox, OcrImage, OcrLine, OcrOutput, OcrWord};
+
+  fn init_thread() {
+    // Blocking-pool threads start without a WinRT apartment; "already initialized" is fine.
+    let _ = unsafe { RoInitialize(RO_INIT_MULTITHREADED) };
+  }
+
+  /// The first language in `lang` (`+`-separated) that has an installed recognizer.
+  pub fn language_for(lang: &str) -> Option<Language> {
+    init_thread();
+    lang
+      .split('+')
+      .map(|code| crate::ocr::to_bcp47(code.trim()))
+      .filter(|tag| !tag.is_empty())
+      .filter_map(|tag| Language::CreateLanguage(&HSTRING::from(tag)).ok())
+      .find(|l| OcrEngine::IsLanguageSupported(l).unwrap_or(false))
+  }
+
+  /// Whether every language in `lang` has an installed recognizer.
+  pub fn supports_all(lang: &str) -> bool {
+    init_thread();
+    let tags: Vec<&str> = lang
+      .split('+')
+      .map(|code| crate::ocr::to_bcp47(code.trim()))
+      .filter(|tag| !tag.is_empty())
+      .collect();
+    !tags.is_empty()
+      && tags.iter().all(|tag| {
+        Language::CreateLanguage(&HSTRING::from(*tag))
+          .is_ok_and(|l| OcrEngine::IsLanguageSupported(&l).unwrap_or(false))
+      })
+  }
+
+  /// Smallest box containing both.
+  fn union(a: BBox, b: BBox) -> BBox {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    BBox {
+      x,
+      y,
+      width: (a.x + a.width).max(b.x + b.width) - x,
+      height: (a.y + a.height).max(b.y + b.height) - y,
+    }
+  }
+
+  /// A readable stream over the image: the file, or its data copied into memory.
+  fn open(image: &OcrImage) -> windows::core::Result<IRandomAccessStream> {
+    match image {
+      OcrImage::Path(p) => StorageFile::GetFileFromPathAsync(&HSTRING::from(p.as_str()))?
+        .get()?
+        .OpenAsync(FileAccessMode::Read)?
+        .get(),
+      OcrImage::Bytes(b) => {
+        let stream = InMemoryRandomAccessStream::new()?;
+        let writer = DataWriter::CreateDataWriter(&stream)?;
+        writer.WriteBytes(b)?;
+        writer.StoreAsync()?.get()?;
+        writer.FlushAsync()?.get()?;
+        // Keep the stream open when the writer goes away.
+        writer.DetachStream()?;
+        stream.Seek(0)?;
+        windows::core::Interface::cast(&stream)
+      }
+    }
+  }
+
+  pub fn recognize(image: &OcrImage, lang: &str) -> Result<OcrOutput, String> {
+    init_thread();
+    let language = language_for(lang)
+      .ok_or_else(|| format!("WINDOWS_OCR_LANG_MISSING\n\nno Windows OCR language installed for {lang}"))?;
+    let engine = OcrEngine::TryCreateFromLanguage(&language).map_err(|e| format!("windows ocr failed: {e}"))?;
+
+    let bitmap = (|| -> windows::core::Result<_> {
+      let stream = open(image)?;
+      let decoder = BitmapDecoder::CreateAsync(&stream)?.get()?;
+      decoder.GetSoftwareBitmapAsync()?.get()
+    })()
+    .map_err(|e| format!("windows ocr failed: cannot read image: {e}"))?;
+
+    let max = OcrEngine::MaxImageDimension().unwrap_or(u32::MAX);
+    let (w, h) = (bitmap.PixelWidth().unwrap_or(0), bitmap.PixelHeight().unwrap_or(0));
+    if w.max(h) as u32 > max {
+      return Err(format!("windows ocr failed: image is {w}x{h}, larger than the {max}px limit"));
+    }
+
+    let result = engine
+      .RecognizeAsync(&bitmap)
+      .and_then(|op| op.get())
+      .map_err(|e| format!("windows ocr failed: {e}"))?;
+    let lines = result.Lines().map_err(|e| format!("windows ocr failed: {e}"))?;
+    let mut out: Vec<OcrLine> = Vec::new();
+    for line in lines {
+      let mut bbox: Option<BBox> = None;
+      let mut words: Vec<OcrWord> = Vec::new();
+      for word in line.Words().map_err(|e| format!("windows ocr failed: {e}"))? {
+        let (Ok(text), Ok(r)) = (word.Text(), word.BoundingRect()) else {
+          continue;
+        };
+        let word_box = BBox {
+          x: r.X.round() as i32,
+          y: r.Y.round() as i32,
+          width: r.Width.round() as i32,
+          height: r.Height.round() as i32,
+        };
+        bbox = Some(bbox.map_or(word_box, |b| union(b, word_box)));
+        words.push(OcrWord {
+          text: text.to_string_lossy(),
+          bbox: word_box,
+          // Windows OCR reports no confidence.
+          confidence: None,
+        });
+      }
+      // Words are space-joined even in CJK text; drop those spaces like Tesseract's.
+      let text = line
+        .Text()
+        .map(|t| crate::reflow::collapse_cjk_spaces(&t.to_string_lossy()))
+        .unwrap_or_default();
+      out.push(OcrLine {
+        text,
+        bbox: bbox.unwrap_or_default(),
+        confidence: None,
+        words,
+      });
+    }
+    let text = out.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+    Ok(OcrOutput { text, lines: out })
+  }
+
+  pub fn languages() -> Result<Vec<super::WindowsOcrLanguage>, String> {
+    init_thread();
+    let langs = OcrEngine::AvailableRecognizerLanguages().map_err(|e| format!("windows ocr failed: {e}"))?;
+    Ok(
+      langs
+        .into_iter()
+        .filter_map(|l| {
+          Some(super::WindowsOcrLanguage {
+            tag: l.LanguageTag().ok()?.to_string_lossy(),
+            name: l.DisplayName().ok()?.to_string_lossy(),
+          })
+        })
+        .collect(),
+    )
+  }
+}
+
+/// Whether Windows has a recognizer for every language in `lang`, so a missing one falls back to an engine that
+/// reads it instead of being skipped.
+#[cfg(windows)]
+pub fn supports(lang: &str) -> bool {
+  imp::supports_all(lang)
+}
+
+// Both block on WinRT's async operations.
+#[cfg(windows)]
+pub use imp::{languages, recognize};
+
+/// Languages with a Windows OCR recognizer installed (added under Settings > Time & language).
+#[tauri::command]
+pub async fn list_windows_ocr_languages() -> Result<Vec<WindowsOcrLanguage>, String> {
+  #[cfg(windows)]
+  {
+    tauri::async_runtime::spawn_blocking(imp::languages)
+      .await
+      .map_err(|e| format!("windows ocr failed: {e}"))?
+  }
+
+  #[cfg(not(windows))]
+  {
+    Err("WINDOWS_OCR_UNAVAILABLE: Windows OCR is only available on Windows".to_string())
+  }
+}
@@ -1,34 +1,107 @@
-import { useCallback, useEffect, useMemo, useRef, useState } from "react";
-import { register, unregisterAll } from "@tauri-apps/plugin-global-shortcut";
+import { useCallback, useEffect, useRef, useState } from "react";
 import { writeText } from "@tauri-apps/plugin-clipboard-manager";
-import { load } from "@tauri-apps/plugin-store";
 import { Channel, invoke } from "@tauri-apps/api/core";
 import { WebviewWindow, getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
-import { emitTo } from "@tauri-apps/api/event";
-import { LogicalPosition, PhysicalSize } from "@tauri-apps/api/dpi";
-import { monitorFromPoint } from "@tauri-apps/api/window";
+import { emit, emitTo } from "@tauri-apps/api/event";
+import { PhysicalSize } from "@tauri-apps/api/dpi";
 import "./App.css";
 
 type ClipboardMode = "displayOnly" | "displayAndCopy" | "copyOnly";
 
 type RoutingStrategy = "defaultBased" | "alwaysLastUsed" | "alwaysFixed";
 
+// Registered by the backend from settings (see `get_hotkey_status`).
+type HotkeyReport = {
+  paused: boolean;
+  suspended_by: string | null; // the app in front the hotkeys are suspended for (see `hotkey_suspend`)
+  // `hooked`: caught by the low-level keyboard hook instead of registered with the OS (`hotkeyBackend`)
+  hotkeys: { action: string; accelerator: string; registered: boolean; hooked: boolean; error: string | null }[];
+};
+
+// Settings that follow the app text is taken from (`appProfiles`, see `get_app_profile`).
+type AppProfile = { app: string; targetLang?: string; mode?: string; ocrLang?: string; hotkeys?: boolean };
+
+// How much history the backend keeps (`historyRetention`, see `history`); starred entries are always kept.
+type HistoryRetention =
+  | { keep: "all" }
+  | { keep: "days"; days: number }
+  | { keep: "entries"; entries: number }
+  | { keep: "nothing" };
+
+async function activeAppProfile(): Promise<AppProfile | null> {
+  try {
+    return (await invoke("get_app_profile")) as AppProfile | null;
+  } catch {
+    return null;
+  }
+}
+
+type MouseButton = "middle" | "back" | "forward";
+
+const MOUSE_BUTTONS: { button: MouseButton; label: string }[] = [
+  { button: "middle", label: "中ボタン" },
+  { button: "back", label: "サイドボタン（戻る / マウス4）" },
+  { button: "forward", label: "サイドボタン（進む / マウス5）" },
+];
+
+const MOUSE_ACTIONS: { action: string; label: string }[] = [
+  { action: "", label: "なし" },
+  { action: "translate", label: "選択テキストを翻訳" },
+  { action: "translate_clipboard", label: "クリップボードを翻訳" },
+  { action: "ocr", label: "OCR" },
+  { action: "repeat_region", label: "前回の範囲をOCR" },
+  { action: "toggle_popup", label: "ポップアップの表示切替" },
+  { action: "click_through", label: "クリック透過の切替" },
+];
+
 type Settings = {
   hotkey: string; // e.g. "CommandOrControl+Shift+E"
   ocrHotkey: string; // e.g. "CommandOrControl+Shift+Alt+X"
+  repeatRegionHotkey?: string; // OCR + translate the last selected region again
+  clickThroughHotkey?: string; // toggle whether clicks pass through the popup to what's under it
+  togglePopupHotkey?: string; // hide the popup, or reopen it with the last translation
+  autoPaste?: boolean; // paste each finished hotkey translation into the app the text came from
+  saveHistory?: boolean; // keep finished translations in the history (default true)
+  historyRetention?: HistoryRetention; // delete old history automatically (default: keep everything)
+  encryptHistory?: boolean; // encrypt the history database with a key in the OS keychain (builds with SQLCipher)
+  typeBackMethod?: "paste" | "type"; // how "replace selection" inserts the translation (type: paste-blocking fields)
+  hotkeyBackend?: "register" | "auto" | "hook"; // Windows: catch hotkeys with a keyboard hook (games that swallow them)
+  clipboardHotkey?: string; // translate the clipboard's text without simulating a copy
+  doubleCopyTrigger?: boolean; // pressing Ctrl/Cmd+C twice quickly translates what was copied (Windows/macOS)
+  mouseTriggers?: Partial<Record<MouseButton, string>>; // hotkey action per mouse button (Windows)
+  suspendHotkeysInFullscreen?: boolean; // no hotkeys while a fullscreen window (a game) is in front (Windows)
+  hotkeySuspendApps?: string[]; // executables the hotkeys are suspended for while in front, e.g. "game.exe"
+  appProfiles?: AppProfile[]; // per-app target language, mode, OCR language and hotkeys (see `app_profiles`)
   clipboardMode: ClipboardMode;
   apiBaseUrl: string; // e.g. "https://lighting-translation.vercel.app"
   defaultLanguage: string; // e.g. "Japanese"
   secondaryLanguage: string; // e.g. "English (US)"
   routingStrategy: RoutingStrategy;
   popupFocusOnOpen: boolean;
+  popupAutoHide?: boolean; // hide the popup when it loses focus (default true)
+  popupAutoHideGraceMs?: number; // delay before the popup hides after losing focus (default 150)
+  popupFadeMs?: number; // popup fade-in/out duration, 0 = no fade (default 120; Windows/macOS)
+  popupOpacity?: number; // popup opacity in percent (default 100; Windows/macOS)
   lastUsedTargetLang?: string;
+  explanationLanguage?: string; // language explanations are written in, as a code (default "ja")
+  alternatives?: number; // alternative translations shown under the popup's (0-5, default 0)
   onboarded?: boolean;
   favoritePairs?: Array<{ from: string; to: string }>;
   // OCR (external Tesseract)
-  ocrLang?: string; // default "jpn+eng"
+  ocrLang?: string; // default "auto" (detect script and orientation)
+  ocrVertical?: boolean; // vertical Japanese/Chinese text (jpn_vert traineddata)
+  ocrScrolling?: boolean; // scroll the window under the cursor and OCR the stitched capture
+  ocrFreezeFrame?: boolean; // freeze the screen while selecting (OCR the snapshot, not the live screen)
+  ocrInPlace?: boolean; // draw the translation over the selected text instead of in the popup (single region)
+  captureToneMap?: "auto" | "on" | "off"; // restore contrast of captures on HDR displays (default "auto")
+  captureMaxDimension?: number; // scale captures down to this longest side (0 or unset: no limit)
+  captureFormat?: "png" | "jpeg" | "webp"; // file format of captures (default "png")
+  excludeOwnWindowsFromCapture?: boolean; // hide the popup/overlay from screen captures (default true)
+  tessdataVariant?: "fast" | "best"; // language data to download (default "fast")
   tesseractPath?: string; // optional absolute path to tesseract.exe
   tessdataPrefix?: string; // optional TESSDATA_PREFIX (parent containing tessdata/)
+  backgroundAgent?: boolean; // start with tray + hotkeys only (applies on next launch)
+  trayIcon?: boolean; // show the tray icon outside background mode too (default true)
 };
 
 // デフォルト言語として選択可能な6言語
@@ -140,13 +213,31 @@ const DEFAULT_SETTINGS: Settings = {
   routingStrategy: "alwaysFixed",
   popupFocusOnOpen: true,
   onboarded: false,
-  ocrLang: "jpn+eng",
+  ocrLang: "auto",
   favoritePairs: [
     { from: "English (US)", to: "Japanese" },
     { from: "Japanese", to: "English (US)" },
   ],
 };
 
+// Native language suggested by the keyboard layout of the app in front (`get_input_language`), if it's one of the
+// native-language choices.
+async function suggestedNativeLanguage(): Promise<string | null> {
+  try {
+    const lang = await invoke<{ tag: string; language: string | null } | null>("get_input_language");
+    const code = lang?.language ?? null;
+    return code && DEFAULT_LANG_OPTIONS.some((o) => o.code === code) ? code : null;
+  } catch {
+    return null;
+  }
+}
+
+// Use `native` as the native language, moving the other language off it if they'd be the same.
+function withNativeLanguage(s: Settings, native: string): Settings {
+  const secondaryLanguage = s.secondaryLanguage === native ? s.defaultLanguage : s.secondaryLanguage;
+  return { ...s, defaultLanguage: native, secondaryLanguage };
+}
+
 function isMostlyAscii(text: string): boolean {
   if (!text) return true;
   let ascii = 0;
@@ -235,12 +326,12 @@ function guessDetectedLangHeuristic(text: string, defaultLanguage: string): "def
 }
 
 const sleep = (ms: number) => new Promise((r) => setTimeout(r, ms));
-const FALLBACK_HOTKEY = "CommandOrControl+Shift+Alt+Q";
 
 // (popup-close instrumentation removed)
 
 function App() {
   const [settings, setSettings] = useState<Settings>(DEFAULT_SETTINGS);
+  const [settingsLoaded, setSettingsLoaded] = useState<boolean>(false);
   const [status, setStatus] = useState<string>("");
   const [sourceText, setSourceText] = useState<string>("");
   const [translatedText, setTranslatedText] = useState<string>("");
@@ -248,30 +339,38 @@ function App() {
   const [targetLang, setTargetLang] = useState<string>(""); // computed per strategy; shown in UI
   const [showWizard, setShowWizard] = useState<boolean>(false);
   const [showSettings, setShowSettings] = useState<boolean>(false);
+  // Which hotkeys the backend registered; paused from the tray menu, they stay unregistered until resumed.
+  const [hotkeyReport, setHotkeyReport] = useState<HotkeyReport | null>(null);
   const [showAutoRouteHelp, setShowAutoRouteHelp] = useState<boolean>(false);
+  // Whether this build can encrypt the history (`history_encryption_available`).
+  const [historyEncryptionAvailable, setHistoryEncryptionAvailable] = useState<boolean>(false);
   const hotkeyInFlightRef = useRef(false);
   const ocrHotkeyInFlightRef = useRef(false);
   const lastHotkeyAtRef = useRef(0);
   const lastOcrHotkeyAtRef = useRef(0);
   const translationRunIdRef = useRef(0);
   const popupRef = useRef<WebviewWindow | null>(null);
-  const overlayRef = useRef<WebviewWindow | null>(null);
-  const lastPopupStateRef = useRef<{ status?: string; source?: string; translation?: string; action?: string }>({
+  // `replaceable`: the translation can replace the selection it was made from (`replace_selection_with`).
+  const lastPopupStateRef = useRef<{
+    status?: string;
+    source?: string;
+    translation?: string;
+    action?: string;
+    replaceable?: boolean;
+  }>({
     status: "Translating…",
     source: "",
     translation: "",
   });
   const pendingOcrImagePathRef = useRef<string | null>(null);
-  const storePromise = useMemo(
-    () =>
-      load("settings.json", {
-        autoSave: true,
-        defaults: {
-          settings: DEFAULT_SETTINGS,
-        },
-      }),
-    [],
-  );
+  // Freeze-frame snapshot the open overlay shows; selections are cropped from it.
+  const ocrSnapshotRef = useRef<string | null>(null);
+
+  useEffect(() => {
+    invoke<boolean>("history_encryption_available")
+      .then(setHistoryEncryptionAvailable)
+      .catch(() => setHistoryEncryptionAvailable(false));
+  }, []);
 
   // Close help pop when clicking outside (settings panel)
   useEffect(() => {
@@ -288,9 +387,12 @@ function App() {
   useEffect(() => {
     let mounted = true;
     (async () => {
-      const store = await storePromise;
-      const s = (await store.get<Settings>("settings")) ?? DEFAULT_SETTINGS;
+      // Migrated and checked by the backend; null before the first save.
+      const stored = await invoke<Settings | null>("get_settings");
+      // First run: take the native language from the keyboard layout instead of assuming Japanese.
+      const native = stored ? null : await suggestedNativeLanguage();
       if (!mounted) return;
+      const s = stored ?? (native ? withNativeLanguage(DEFAULT_SETTINGS, native) : DEFAULT_SETTINGS);
       const merged = { ...DEFAULT_SETTINGS, ...s };
       // Migrate old stored values (Japanese labels) to API codes.
       merged.defaultLanguage = normalizeLangCode(merged.defaultLanguage, DEFAULT_SETTINGS.defaultLanguage);
@@ -299,13 +401,8 @@ function App() {
         merged.lastUsedTargetLang = normalizeLangCode(merged.lastUsedTargetLang, merged.defaultLanguage);
       }
 
-      // If we previously persisted a fallback hotkey (Q), automatically revert to the default (Z).
-      // This avoids surprising users with an unintended key, now that hotkey re-register loops are fixed.
-      if (merged.hotkey === FALLBACK_HOTKEY) {
-        merged.hotkey = DEFAULT_SETTINGS.hotkey;
-      }
-
       setSettings(merged);
+      setSettingsLoaded(true);
       if (!merged.onboarded) setShowWizard(true);
     })().catch(() => {
       // ignore
@@ -313,19 +410,25 @@ function App() {
     return () => {
       mounted = false;
     };
-  }, [storePromise]);
+  }, []);
 
   useEffect(() => {
-    (async () => {
-      const store = await storePromise;
-      await store.set("settings", settings);
-    })().catch(() => {
-      // ignore
+    if (!settingsLoaded) return;
+    // The backend refuses settings that don't pass (a bad hotkey, a malformed URL) and keeps the last good ones.
+    invoke("set_settings", { settings }).catch((e) => {
+      setStatus(`Settings not saved: ${e instanceof Error ? e.message : String(e)}`);
     });
-  }, [settings, storePromise]);
+  }, [settings, settingsLoaded]);
 
   const emitPopupState = useCallback(
-    (partial: { status?: string; source?: string; translation?: string; action?: string }) => {
+    (partial: {
+      status?: string;
+      source?: string;
+      translation?: string;
+      variants?: string[];
+      action?: string;
+      replaceable?: boolean;
+    }) => {
       lastPopupStateRef.current = { ...lastPopupStateRef.current, ...partial };
       const payload = lastPopupStateRef.current;
       void emitTo("popup", "erudaite://popup/state", payload)
@@ -354,6 +457,8 @@ function App() {
     } catch (e) {
       void e;
     }
+    // A pinned popup stays open; the next translation goes into it.
+    if (await invoke("is_window_pinned", { label: "popup" }).catch(() => false)) return false;
     try {
       // Force-destroy to avoid leaving a hidden zombie window with the same label.
       await w.destroy();
@@ -378,42 +483,23 @@ function App() {
 
       const initialW = 360;
       const initialH = 220;
-      const offsetY = 18;
-
-      // On Windows with DPI scaling, cursor/selection point tends to be physical pixels.
-      // Window positioning APIs behave like logical coordinates. Convert using monitor scaleFactor.
-      let sf = 1;
-      let pointLogical = { x: pointPhysical.x, y: pointPhysical.y };
-      let x = Math.floor(pointLogical.x - initialW / 2);
-      let y = Math.floor(pointLogical.y + offsetY);
 
+      // The backend knows the monitors' work areas and scale factors: it clamps the popup onto the monitor
+      // holding the point (flipping above it when there's no room below) and moves an open popup there.
+      let x = Math.floor(pointPhysical.x - initialW / 2);
+      let y = Math.floor(pointPhysical.y + 18);
       try {
-        const m = await monitorFromPoint(pointPhysical.x, pointPhysical.y);
-        if (m) {
-          sf = m.scaleFactor || 1;
-          pointLogical = { x: pointPhysical.x / sf, y: pointPhysical.y / sf };
-          const mx = m.position.x / sf;
-          const my = m.position.y / sf;
-          const mw = m.size.width / sf;
-          const mh = m.size.height / sf;
-
-          x = Math.floor(pointLogical.x - initialW / 2);
-          y = Math.floor(pointLogical.y + offsetY);
-          x = Math.max(mx, Math.min(x, mx + mw - initialW));
-          // If not enough space below point, show above
-          if (y + initialH > my + mh) {
-            y = Math.max(my, Math.min(pointLogical.y - initialH - 12, my + mh - initialH));
-          } else {
-            y = Math.max(my, Math.min(y, my + mh - initialH));
-          }
-        }
+        const placement = (await invoke("position_popup", {
+          width: initialW,
+          height: initialH,
+          anchor: { x: Math.round(pointPhysical.x), y: Math.round(pointPhysical.y) },
+        })) as { logical: { x: number; y: number } };
+        x = Math.round(placement.logical.x);
+        y = Math.round(placement.logical.y);
       } catch {
-        // ignore clamp failures
+        // ignore placement failures; open at the unclamped point
       }
 
-      x = Math.round(x);
-      y = Math.round(y);
-
       if (existing) {
         popupRef.current = existing;
         try {
@@ -433,7 +519,6 @@ function App() {
         if (existing) {
           try {
             await existing.show();
-            await existing.setPosition(new LogicalPosition(x, y)).catch(() => {});
             if (settings.popupFocusOnOpen) await existing.setFocus();
           } catch {
             // ignore; fall through to create new
@@ -499,7 +584,7 @@ function App() {
     });
 
     // Ensure content starts in "Translating…" state (best effort; may be re-sent on created/ready)
-    emitPopupState({ status: "Translating…", source: "", translation: "" });
+    emitPopupState({ status: "Translating…", source: "", translation: "", replaceable: false });
 
     return popup;
     },
@@ -507,24 +592,27 @@ function App() {
   );
 
   const ensurePopupAtCursor = useCallback(async () => {
+    // Prefer the text caret of the focused control; apps that draw their own caret report none, so fall back to
+    // the pointer.
+    try {
+      const caret = (await invoke("get_caret_position")) as
+        | { x: number; y: number; width: number; height: number }
+        | null;
+      if (caret) {
+        return await ensurePopupAtPhysicalPoint({ x: caret.x + caret.width / 2, y: caret.y + caret.height }, "caret");
+      }
+    } catch {
+      // ignore; use the pointer
+    }
     const cursorPhysical = (await invoke("get_cursor_position")) as { x: number; y: number };
     return await ensurePopupAtPhysicalPoint(cursorPhysical, "cursor");
   }, [ensurePopupAtPhysicalPoint]);
 
   const isOverlayOpen = useCallback(async () => {
-    let w: WebviewWindow | null = overlayRef.current;
-    if (!w) {
-      try {
-        w = await WebviewWindow.getByLabel("ocr-overlay");
-      } catch {
-        w = null;
-      }
-    }
-    if (!w) return false;
     try {
-      return await w.isVisible();
+      return (await invoke("is_ocr_overlay_open")) === true;
     } catch {
-      return true;
+      return false;
     }
   }, []);
 
@@ -534,73 +622,23 @@ function App() {
       return;
     }
 
-    // We want the overlay to be usable across the entire virtual desktop (all monitors),
-    // not just the current monitor.
-    const cursor = (await invoke("get_cursor_position")) as { x: number; y: number };
-
-    const windowApi = (await import("@tauri-apps/api/window")) as any;
-    const availableMonitorsFn = windowApi?.availableMonitors as undefined | (() => Promise<any[]>);
-    let monitors: Array<{ position: { x: number; y: number }; size: { width: number; height: number }; scaleFactor?: number }> = [];
-    try {
-      if (availableMonitorsFn) {
-        monitors = (await availableMonitorsFn()) || [];
+    ocrSnapshotRef.current = null;
+    if (settings.ocrFreezeFrame) {
+      try {
+        const snap = (await invoke("capture_full_screen_snapshot")) as { id: string };
+        ocrSnapshotRef.current = snap.id;
+      } catch {
+        // Fall back to selecting on the live screen.
       }
-    } catch {
-      monitors = [];
-    }
-
-    // Fallback: current monitor only
-    if (!monitors.length) {
-      const m = await monitorFromPoint(cursor.x, cursor.y);
-      if (m) monitors = [m as any];
     }
-    if (!monitors.length) throw new Error("monitor not found");
-
-    let minX = Infinity;
-    let minY = Infinity;
-    let maxX = -Infinity;
-    let maxY = -Infinity;
-    for (const m of monitors) {
-      minX = Math.min(minX, m.position.x);
-      minY = Math.min(minY, m.position.y);
-      maxX = Math.max(maxX, m.position.x + m.size.width);
-      maxY = Math.max(maxY, m.position.y + m.size.height);
-    }
-
-    const ox = Math.floor(minX);
-    const oy = Math.floor(minY);
-    const ow = Math.floor(maxX - minX);
-    const oh = Math.floor(maxY - minY);
-
-    const overlayUrl = window.location.protocol.startsWith("http")
-      ? `${window.location.origin}/#/ocr-overlay`
-      : "index.html#/ocr-overlay";
-
-    const overlay = new WebviewWindow("ocr-overlay", {
-      url: overlayUrl,
-      x: ox,
-      y: oy,
-      width: ow,
-      height: oh,
-      resizable: false,
-      decorations: false,
-      transparent: true,
-      alwaysOnTop: true,
-      skipTaskbar: true,
-      focus: true,
-      visible: true,
-      shadow: false,
-    });
-    overlayRef.current = overlay;
-    overlay.once("tauri://created", () => {
-      void 0;
-    });
-    overlay.once("tauri://destroyed", () => {
-      overlayRef.current = null;
-    });
-  }, [isOverlayOpen]);
-
-  const handleHotkey = useCallback(async () => {
+    // The backend opens one overlay window per monitor, each at that monitor's scale, so selections map to the
+    // right pixels on mixed-DPI setups.
+    await invoke("open_ocr_overlay", { snapshot: ocrSnapshotRef.current });
+  }, [isOverlayOpen, settings.ocrFreezeFrame]);
+
+  // `clipboard`: translate the clipboard's text as it is instead of copying the selection (apps where the
+  // simulated copy is unreliable). `quiet` (a middle click): no toggle-close, and nothing to select is no error.
+  const handleHotkey = useCallback(async (source: "selection" | "clipboard" = "selection", quiet = false) => {
     const now = Date.now();
     lastHotkeyAtRef.current = now;
 
@@ -614,7 +652,7 @@ function App() {
     // Toggle behavior: if popup is open, close it and stop.
     let closed = false;
     try {
-      closed = await closePopupIfOpen();
+      closed = quiet ? false : await closePopupIfOpen();
     } catch (e) {
       void e;
       closed = false;
@@ -624,7 +662,7 @@ function App() {
     }
 
     hotkeyInFlightRef.current = true;
-    setStatus("Capturing selected text…");
+    setStatus(source === "clipboard" ? "Reading clipboard…" : "Capturing selected text…");
     setTranslatedText("");
     try {
       // OS全体の選択取得（擬似Ctrl/Cmd+C→復元）をRust側で実施
@@ -632,10 +670,18 @@ function App() {
       try {
         // NOTE: Tauri invoke側はcamelCaseで渡す（Rustのtimeout_msにマッピングされる）
         const args = { timeoutMs: 1600 };
-        picked = String(await invoke("capture_selected_text", args)).trim();
+        picked =
+          source === "clipboard"
+            ? String(await invoke("read_clipboard_text")).trim()
+            : String(await invoke("capture_selected_text", args)).trim();
       } catch (e) {
         // Do NOT fallback to clipboard here; it can silently translate stale clipboard content.
         // Instead, surface an actionable error to the user.
+        const msg = e instanceof Error ? e.message : String(e);
+        if (quiet) {
+          setStatus(`Capture failed: ${msg}`);
+          return;
+        }
         try {
           const w = getCurrentWebviewWindow();
           await w.show();
@@ -643,11 +689,18 @@ function App() {
         } catch {
           // ignore
         }
-        const msg = e instanceof Error ? e.message : String(e);
+        if (source === "clipboard") {
+          setStatus(`Clipboard read failed: ${msg}`);
+          return;
+        }
         setStatus(`Capture failed: ${msg}`);
         setStatus("Capture failed. Keep Chrome focused, select text, then press hotkey again.");
         return;
       }
+      if (!picked && quiet) {
+        setStatus("No selected text detected.");
+        return;
+      }
       if (!picked) {
         // Bring the window forward so the user sees the failure reason.
         try {
@@ -658,15 +711,21 @@ function App() {
           // ignore
         }
         // status already set above
-        setStatus("No selected text detected. Select text and press the hotkey again.");
+        setStatus(
+          source === "clipboard"
+            ? "The clipboard has no text. Copy some text and press the hotkey again."
+            : "No selected text detected. Select text and press the hotkey again.",
+        );
         // Also surface this in the popup so it doesn't feel like "nothing happened".
         try {
           await ensurePopupAtCursor();
           emitPopupState({
-            status: "No selection",
+            status: source === "clipboard" ? "Clipboard empty" : "No selection",
             source: "",
             translation:
-              "選択テキストを取得できませんでした。\n\n- 対象アプリ（例: Chrome）をアクティブにする\n- テキストを選択する\n- もう一度ホットキーを押す\n\n※ うまくいかない場合は、選択をやり直して再度お試しください。",
+              source === "clipboard"
+                ? "クリップボードにテキストがありません。\n\nテキストをコピーしてから、もう一度ホットキーを押してください。"
+                : "選択テキストを取得できませんでした。\n\n- 対象アプリ（例: Chrome）をアクティブにする\n- テキストを選択する\n- もう一度ホットキーを押す\n\n※ うまくいかない場合は、選択をやり直して再度お試しください。",
           });
         } catch {
           // ignore popup failures
@@ -674,9 +733,13 @@ function App() {
         return;
       }
 
+      // Looked up while the app the text came from is still in front.
+      const profile = await activeAppProfile();
+      const mode = profile?.mode || "standard";
+
       // Show popup near cursor immediately
       await ensurePopupAtCursor();
-      emitPopupState({ status: "Translating…", source: picked, translation: "" });
+      emitPopupState({ status: "Translating…", source: picked, translation: "", replaceable: false });
 
       setSourceText(picked);
       // capture note removed
@@ -689,20 +752,33 @@ function App() {
       const runTranslate = (target: string) => {
         const runId = ++translationRunIdRef.current;
         let full = "";
+        // Alternative translations by index, each streamed on its own.
+        const variants = new Map<number, string>();
+        const emitVariants = () =>
+          emitPopupState({
+            variants: [...variants.entries()].sort(([a], [b]) => a - b).map(([, v]) => v).filter((v) => v),
+          });
 
         setTargetLang(target);
         setTranslatedText("");
         // Popup shows only translation text; use a lightweight placeholder immediately.
-        emitPopupState({ status: "Translating…", source: picked, translation: "…" });
+        emitPopupState({ status: "Translating…", source: picked, translation: "…", variants: [] });
 
         const ch = new Channel<
-          { type: "delta"; content: string } | { type: "done" } | { type: "error"; message: string }
+          | { type: "delta"; content: string }
+          | { type: "replace"; content: string }
+          | { type: "variant_delta"; index: number; content: string }
+          | { type: "variant"; index: number; content: string }
+          | { type: "done" }
+          | { type: "error"; message: string }
+          | { type: "warning"; code: string; message: string }
         >();
 
         ch.onmessage = (msg) => {
           if (runId !== translationRunIdRef.current) return; // ignore stale streams
-          if (msg.type === "delta") {
-            full += msg.content;
+          if (msg.type === "delta" || msg.type === "replace") {
+            // `replace` (post-process plugins) swaps out everything streamed so far.
+            full = msg.type === "replace" ? msg.content : full + msg.content;
             setTranslatedText(full);
             emitPopupState({ status: "Translating…", translation: full });
 
@@ -712,9 +788,16 @@ function App() {
             const w = Math.min(400, Math.max(300, 360));
             const p = popupRef.current;
             if (p) void p.setSize(new PhysicalSize(w, h)).catch(() => {});
+          } else if (msg.type === "variant_delta" || msg.type === "variant") {
+            // `variant` is the finished alternative (empty: dropped as a duplicate).
+            const sofar = msg.type === "variant" ? "" : (variants.get(msg.index) ?? "");
+            variants.set(msg.index, sofar + msg.content);
+            emitVariants();
           } else if (msg.type === "error") {
             setStatus(`Error: ${msg.message}`);
             emitPopupState({ status: `Error: ${msg.message}` });
+          } else if (msg.type === "warning") {
+            setStatus(`Warning: ${msg.message}`);
           }
         };
 
@@ -723,9 +806,10 @@ function App() {
             baseUrl: settings.apiBaseUrl,
             text: picked,
             targetLang: target,
-            mode: "standard",
-            explanationLang: "ja",
+            mode,
+            explanationLang: settings.explanationLanguage ?? "ja",
             isReverse: false,
+            alternatives: settings.alternatives ?? 0,
             onEvent: ch,
           });
           return full;
@@ -740,9 +824,12 @@ function App() {
       let detectedForUi = "Unknown";
       let active = { runId: 0, target: "", donePromise: Promise.resolve("") as Promise<string> };
 
-      if (settings.routingStrategy === "alwaysFixed") {
+      if (profile?.targetLang || settings.routingStrategy === "alwaysFixed") {
         // Spec change: when auto-routing is OFF, always translate to "default language".
-        const target = normalizeLangCode(settings.defaultLanguage, DEFAULT_SETTINGS.defaultLanguage);
+        // The app's profile target wins over any routing.
+        const target = profile?.targetLang
+          ? normalizeLangCode(profile.targetLang, settings.defaultLanguage)
+          : normalizeLangCode(settings.defaultLanguage, DEFAULT_SETTINGS.defaultLanguage);
         active = runTranslate(target);
         // detect in background for UI only
         void (async () => {
@@ -815,7 +902,18 @@ function App() {
       } else {
         setStatus("Done.");
       }
-      emitPopupState({ status: "Done." });
+      // A copied selection can be replaced; the clipboard's text has no selection behind it.
+      emitPopupState({ status: "Done.", replaceable: source === "selection" });
+
+      if (settings.autoPaste && (full ?? "").trim()) {
+        // The backend brings back the window the text came from and pastes over the selection (or at the caret).
+        try {
+          await invoke("replace_selection_with", { text: (full ?? "").trim() });
+          setStatus("Pasted translation.");
+        } catch (e) {
+          setStatus(`Paste failed: ${e instanceof Error ? e.message : String(e)}`);
+        }
+      }
     } catch (e) {
       setStatus(`Error: ${e instanceof Error ? e.message : String(e)}`);
       emitPopupState({ status: `Error: ${e instanceof Error ? e.message : String(e)}` });
@@ -827,6 +925,7 @@ function App() {
     ensurePopupAtCursor,
     emitPopupState,
     settings.apiBaseUrl,
+    settings.autoPaste,
     settings.clipboardMode,
     settings.defaultLanguage,
     settings.secondaryLanguage,
@@ -854,60 +953,246 @@ function App() {
     }
   }, [openOcrOverlayOnCurrentMonitor]);
 
+  const handleRepeatRegionHotkey = useCallback(async () => {
+    try {
+      // The latest region on the monitor under the cursor, captured by the backend; its OCR runs the same flow
+      // as a fresh selection.
+      const last = (await invoke("capture_last_region")) as {
+        path: string;
+        region: { rect: { x: number; y: number; width: number; height: number } };
+      };
+      const { emit } = await import("@tauri-apps/api/event");
+      await emit("erudaite://ocr/selected", { ...last.region.rect, path: last.path });
+    } catch (e) {
+      if (String(e) === "NO_RECENT_REGION") {
+        setStatus("OCR: no recent region yet");
+        return;
+      }
+      setStatus(`OCR hotkey error: ${e instanceof Error ? e.message : String(e)}`);
+    }
+  }, []);
+
+  // Toggle-popup hotkey with no popup showing: reopen it at the cursor with the last translation.
+  const handleShowPopup = useCallback(async () => {
+    try {
+      await ensurePopupAtCursor();
+      emitPopupState({});
+    } catch (e) {
+      setStatus(`Popup error: ${e instanceof Error ? e.message : String(e)}`);
+    }
+  }, [emitPopupState, ensurePopupAtCursor]);
+
   useEffect(() => {
     const unlistenPromise = (async () => {
       const { listen } = await import("@tauri-apps/api/event");
-      return await listen<{ x: number; y: number; width: number; height: number }>("erudaite://ocr/selected", async (e) => {
-        const { x, y, width, height } = e.payload ?? ({} as any);
+      return await listen<{ x: number; y: number; width: number; height: number }>(
+        "erudaite://ocr/copy",
+        async (e) => {
+          if (!e.payload?.width || !e.payload?.height) return;
+          try {
+            await invoke("copy_capture_to_clipboard", { rect: e.payload });
+            setStatus("Copied the selected area to the clipboard");
+          } catch (err) {
+            setStatus(`Copy error: ${err instanceof Error ? err.message : String(err)}`);
+          }
+        },
+      );
+    })();
+    return () => {
+      void unlistenPromise.then((u) => u()).catch(() => {});
+    };
+  }, []);
+
+  useEffect(() => {
+    const unlistenPromise = (async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      type Rect = { x: number; y: number; width: number; height: number };
+      return await listen<Rect & { regions?: Rect[]; path?: string }>("erudaite://ocr/selected", async (e) => {
+        if (!e.payload?.width || !e.payload?.height) return;
+        // Physical pixels (the overlay converts with its own monitor's origin and scale). Several regions
+        // (Shift+drag in the overlay) are captured and OCR'd together; bounds encloses them. `path` is a capture
+        // of the region already taken (the repeat-region hotkey).
+        const { regions: selected, path: captured, ...bounds } = e.payload;
+        const regions: Rect[] = selected ?? [];
+        const { x, y, width, height } = bounds;
         if (!width || !height) return;
+        const multi = regions.length > 1;
+        const snapshot = ocrSnapshotRef.current;
+        ocrSnapshotRef.current = null;
         try {
           // Anchor popup near the selection (bottom-center) rather than current cursor.
           await ensurePopupAtPhysicalPoint({ x: x + width / 2, y: y + height }, "ocr-rect");
-          emitPopupState({ status: "OCR…", source: "", translation: "…" });
-
-          const imagePath = String(
-            await invoke("capture_screen_region", {
-              rect: { x: Math.floor(x), y: Math.floor(y), width: Math.floor(width), height: Math.floor(height) },
-            }),
-          );
+          emitPopupState({ status: "OCR…", source: "", translation: "…", replaceable: false });
+
+          const rect = { x, y, width, height };
+          const profile = await activeAppProfile();
+          const mode = profile?.mode || "standard";
+          const ocrLang = profile?.ocrLang || settings.ocrLang || "auto";
+          // When the target doesn't depend on the text, one backend call captures, OCRs and translates.
+          const fixedTarget = profile?.targetLang
+            ? normalizeLangCode(profile.targetLang, settings.defaultLanguage)
+            : settings.routingStrategy === "alwaysFixed"
+              ? normalizeLangCode(settings.defaultLanguage, DEFAULT_SETTINGS.defaultLanguage)
+              : settings.routingStrategy === "alwaysLastUsed"
+                ? normalizeLangCode(settings.lastUsedTargetLang, settings.defaultLanguage)
+                : null;
+          const pipelined =
+            !multi && !snapshot && !captured && !settings.ocrScrolling && !settings.ocrInPlace && fixedTarget !== null;
+          const imagePath = multi || pipelined
+            ? ""
+            : captured
+              ? captured
+              : snapshot
+                ? String(await invoke("crop_snapshot", { id: snapshot, rect }))
+                : settings.ocrScrolling
+                  ? String(((await invoke("capture_scrolling", { rect })) as { path: string }).path)
+                  : String(await invoke("capture_screen_region", { rect }));
+
+          // Japanese OCR asked for but no engine here reads it: offer Tesseract's language data before trying.
+          if (ocrLang.split("+").map((s) => s.trim()).includes("jpn")) {
+            try {
+              const engines = (await invoke("list_ocr_engines", {
+                tesseractPath: settings.tesseractPath ?? null,
+                tessdataPrefix: settings.tessdataPrefix ?? null,
+              })) as { id: string; available: boolean; languages: string[] }[];
+              // Tesseract lists "jpn"; Windows OCR and Vision list BCP-47 tags ("ja", "ja-JP").
+              const readsJpn = engines.some(
+                (e) => e.available && e.languages.some((l) => l === "jpn" || /^ja(-|$)/i.test(l)),
+              );
+              if (!readsJpn) {
+                pendingOcrImagePathRef.current = multi || pipelined ? null : imagePath;
+                emitPopupState({
+                  status: "Japanese OCR data missing",
+                  source: "",
+                  translation:
+                    "日本語OCR（jpn）がインストールされていません。\n\n「日本語OCRデータを追加」を押して自動で導入してください。",
+                  action: "install_jpn",
+                });
+                return;
+              }
+            } catch {
+              // ignore; we'll try OCR anyway
+            }
+          }
 
           let ocrText = "";
+          // In-place mode: the recognized lines, whose boxes the translation is drawn into.
+          let inPlaceLines: unknown[] | null = null;
+          // Pipelined: the finished translation, and the step that failed if one did.
+          let pipelineTranslation: string | null = null;
+          let pipelineFailedAt = "";
           try {
-            // If Japanese OCR is requested but `jpn` is not installed, prompt to install language data.
-            const wantsJpn = (settings.ocrLang ?? "jpn+eng").split("+").map((s) => s.trim()).includes("jpn");
-            if (wantsJpn) {
-              try {
-                const langs = (await invoke("tesseract_list_langs", {
-                  tesseractPath: settings.tesseractPath ?? null,
-                  tessdataPrefix: settings.tessdataPrefix ?? null,
-                })) as string[];
-                const hasJpn = Array.isArray(langs) && langs.includes("jpn");
-                if (!hasJpn) {
-                  pendingOcrImagePathRef.current = imagePath;
-                  emitPopupState({
-                    status: "Japanese OCR data missing",
-                    source: "",
-                    translation:
-                      "日本語OCR（jpn）がインストールされていません。\n\n「日本語OCRデータを追加」を押して自動で導入してください。",
-                    action: "install_jpn",
-                  });
-                  return;
-                }
-              } catch (e) {
-                // ignore; we'll try OCR anyway
+            const ocrCh = new Channel<
+              | { type: "stage"; stage: "detecting" | "preprocessing" | "running" | "parsing" }
+              | { type: "progress"; done: number; total: number }
+              | { type: "done" }
+              | { type: "error"; message: string }
+            >();
+            ocrCh.onmessage = (msg) => {
+              if (msg.type === "stage") {
+                emitPopupState({ status: `OCR: ${msg.stage}…` });
+              } else if (msg.type === "progress" && msg.total > 1) {
+                emitPopupState({ status: `OCR… ${msg.done}/${msg.total}` });
               }
+            };
+            const ocrArgs = {
+              lang: ocrLang,
+              tesseractPath: settings.tesseractPath ?? null,
+              tessdataPrefix: settings.tessdataPrefix ?? null,
+              options: settings.ocrVertical ? { vertical: true } : null,
+              onEvent: ocrCh,
+            };
+            if (multi) {
+              const results = (await invoke("ocr_regions", { rects: regions, snapshot, ...ocrArgs })) as {
+                result: { text: string } | null;
+                error: string | null;
+              }[];
+              ocrText = results
+                .map((r) => String(r.result?.text ?? "").trim())
+                .filter(Boolean)
+                .join("\n\n");
+              const firstError = results.find((r) => r.error)?.error;
+              if (!ocrText && firstError) throw new Error(firstError);
+            } else if (pipelined && fixedTarget) {
+              const runId = ++translationRunIdRef.current;
+              let full = "";
+              const ch = new Channel<
+                | { type: "stage"; stage: "capture" | "ocr" | "translate" }
+                | { type: "ocr"; event: { type: string; stage?: string } }
+                | { type: "recognized"; text: string }
+                | {
+                    type: "translation";
+                    event:
+                      | { type: "delta"; content: string }
+                      | { type: "replace"; content: string }
+                      | { type: "error"; message: string }
+                      | { type: "warning"; code: string; message: string }
+                      | { type: string };
+                  }
+                | { type: "done" }
+                | { type: "error"; stage: string; message: string }
+              >();
+              ch.onmessage = (msg) => {
+                if (msg.type === "ocr" && msg.event.type === "stage") {
+                  emitPopupState({ status: `OCR: ${msg.event.stage}…` });
+                } else if (msg.type === "recognized" && msg.text.trim()) {
+                  setSourceText(msg.text);
+                  setTargetLang(fixedTarget);
+                  setTranslatedText("");
+                  emitPopupState({ status: "Translating…", source: msg.text, translation: "…" });
+                } else if (msg.type === "translation" && runId === translationRunIdRef.current) {
+                  const ev = msg.event;
+                  if ((ev.type === "delta" || ev.type === "replace") && "content" in ev) {
+                    full = ev.type === "replace" ? ev.content : full + ev.content;
+                    setTranslatedText(full);
+                    emitPopupState({ status: "Translating…", translation: full });
+                  } else if (ev.type === "warning" && "message" in ev) {
+                    setStatus(`Warning: ${ev.message}`);
+                  }
+                } else if (msg.type === "error") {
+                  pipelineFailedAt = msg.stage;
+                }
+              };
+              const r = (await invoke("ocr_translate_region", {
+                rect,
+                ...ocrArgs,
+                targetLang: fixedTarget,
+                mode,
+                onEvent: ch,
+              })) as { ocr: { text: string }; translation: string | null };
+              ocrText = String(r.ocr.text ?? "").trim();
+              pipelineTranslation = r.translation ?? "";
+            } else {
+              const ocr = (await invoke("ocr_image", { imagePath, ...ocrArgs })) as {
+                text: string;
+                raw_text: string;
+                reflowed: boolean;
+                engine: string;
+                lines: unknown[];
+              };
+              ocrText = String(ocr.text ?? "").trim();
+              if (settings.ocrInPlace && !settings.ocrScrolling) inPlaceLines = ocr.lines ?? [];
             }
-            ocrText = String(
-              await invoke("ocr_tesseract", {
-                imagePath,
-                lang: settings.ocrLang ?? "jpn+eng",
-                tesseractPath: settings.tesseractPath ?? null,
-                tessdataPrefix: settings.tessdataPrefix ?? null,
-              }),
-            ).trim();
           } catch (err) {
             const msg = err instanceof Error ? err.message : String(err);
-            pendingOcrImagePathRef.current = imagePath;
+            // A pipelined capture or translation failure is handled like the unpipelined ones, below.
+            if (pipelineFailedAt === "capture" || pipelineFailedAt === "translate") throw err;
+            pendingOcrImagePathRef.current = multi || pipelined ? null : imagePath;
+            // ocr_regions captures too; a capture permission error is handled below.
+            if (msg.includes("SCREEN_RECORDING_PERMISSION_DENIED")) throw err;
+            // No engine has Japanese: prompt to install Tesseract's language data.
+            const wantsJpn =
+              ocrLang.split("+").map((s) => s.trim()).includes("jpn") || msg.includes("jpn");
+            if (msg.includes("TESSDATA_MISSING") && wantsJpn) {
+              emitPopupState({
+                status: "Japanese OCR data missing",
+                source: "",
+                translation:
+                  "日本語OCR（jpn）がインストールされていません。\n\n「日本語OCRデータを追加」を押して自動で導入してください。",
+                action: "install_jpn",
+              });
+              return;
+            }
             emitPopupState({
               status: "OCR failed",
               source: "",
@@ -929,6 +1214,84 @@ function App() {
             return;
           }
 
+          if (pipelineTranslation !== null && fixedTarget) {
+            const full = pipelineTranslation;
+            setTranslatedText(full);
+            setSettings((s) => ({ ...s, lastUsedTargetLang: fixedTarget }));
+            if (settings.clipboardMode === "displayAndCopy" || settings.clipboardMode === "copyOnly") {
+              await writeText(full);
+            }
+            emitPopupState({ status: "Done.", translation: full });
+            void (async () => {
+              try {
+                const r = (await invoke("detect_language", { baseUrl: settings.apiBaseUrl, text: ocrText })) as {
+                  detected_lang?: string;
+                };
+                setDetectedLang(String(r?.detected_lang ?? "Unknown"));
+              } catch {
+                setDetectedLang("Unknown");
+              }
+            })();
+            return;
+          }
+
+          if (inPlaceLines) {
+            setSourceText(ocrText);
+            emitPopupState({ status: "Translating…", source: ocrText, translation: "…" });
+            const target =
+              fixedTarget ??
+              (guessDetectedLangHeuristic(ocrText, settings.defaultLanguage) === "default"
+                ? settings.secondaryLanguage
+                : settings.defaultLanguage);
+            const translateBlock = async (text: string) => {
+              let full = "";
+              let error: string | null = null;
+              const ch = new Channel<
+                | { type: "delta"; content: string }
+                | { type: "replace"; content: string }
+                | { type: "done" }
+                | { type: "error"; message: string }
+                | { type: "warning"; code: string; message: string }
+              >();
+              ch.onmessage = (msg) => {
+                if (msg.type === "delta") full += msg.content;
+                else if (msg.type === "replace") full = msg.content;
+                else if (msg.type === "error") error = msg.message;
+              };
+              await invoke("translate_sse", {
+                baseUrl: settings.apiBaseUrl,
+                text,
+                targetLang: target,
+                mode,
+                explanationLang: settings.explanationLanguage ?? "ja",
+                isReverse: false,
+                onEvent: ch,
+              });
+              if (error) throw new Error(error);
+              return full;
+            };
+            // Each block (a paragraph or caption) is translated on its own and drawn over its own box. One at a
+            // time, so a screenful of captions doesn't fire dozens of requests at once.
+            const blocks = (await invoke("translation_overlay_blocks", { lines: inPlaceLines })) as {
+              text: string;
+            }[];
+            const translated: typeof blocks = [];
+            for (const [i, b] of blocks.entries()) {
+              emitPopupState({ status: `Translating… ${i + 1}/${blocks.length}` });
+              translated.push({ ...b, text: await translateBlock(b.text) });
+            }
+            const full = translated.map((b) => b.text.trim()).join("\n\n");
+            setTargetLang(target);
+            setTranslatedText(full);
+            setSettings((s) => ({ ...s, lastUsedTargetLang: target }));
+            if (settings.clipboardMode === "displayAndCopy" || settings.clipboardMode === "copyOnly") {
+              await writeText(full);
+            }
+            await invoke("open_translation_overlay", { rect, blocks: translated, imagePath });
+            await closePopupIfOpen();
+            return;
+          }
+
           // Reuse existing translation pipeline: set picked as source, then translate via SSE
           setSourceText(ocrText);
           emitPopupState({ status: "Translating…", source: ocrText, translation: "…" });
@@ -947,17 +1310,23 @@ function App() {
             setTranslatedText("");
             emitPopupState({ status: "Translating…", source: picked, translation: "…" });
             const ch = new Channel<
-              { type: "delta"; content: string } | { type: "done" } | { type: "error"; message: string }
+              | { type: "delta"; content: string }
+              | { type: "replace"; content: string }
+              | { type: "done" }
+              | { type: "error"; message: string }
+              | { type: "warning"; code: string; message: string }
             >();
             ch.onmessage = (msg) => {
               if (runId !== translationRunIdRef.current) return;
-              if (msg.type === "delta") {
-                full += msg.content;
+              if (msg.type === "delta" || msg.type === "replace") {
+                full = msg.type === "replace" ? msg.content : full + msg.content;
                 setTranslatedText(full);
                 emitPopupState({ status: "Translating…", translation: full });
               } else if (msg.type === "error") {
                 setStatus(`Error: ${msg.message}`);
                 emitPopupState({ status: `Error: ${msg.message}` });
+              } else if (msg.type === "warning") {
+                setStatus(`Warning: ${msg.message}`);
               }
             };
             const donePromise = (async () => {
@@ -965,8 +1334,8 @@ function App() {
                 baseUrl: settings.apiBaseUrl,
                 text: picked,
                 targetLang: target,
-                mode: "standard",
-                explanationLang: "ja",
+                mode,
+                explanationLang: settings.explanationLanguage ?? "ja",
                 isReverse: false,
                 onEvent: ch,
               });
@@ -977,8 +1346,8 @@ function App() {
 
           let detectedForUi = "Unknown";
           let active = { runId: 0, target: "", donePromise: Promise.resolve("") as Promise<string> };
-          if (settings.routingStrategy === "alwaysFixed") {
-            const target = normalizeLangCode(settings.defaultLanguage, DEFAULT_SETTINGS.defaultLanguage);
+          if (profile?.targetLang || settings.routingStrategy === "alwaysFixed") {
+            const target = fixedTarget ?? normalizeLangCode(settings.defaultLanguage, DEFAULT_SETTINGS.defaultLanguage);
             active = runTranslate(target);
             void (async () => {
               try {
@@ -1029,8 +1398,21 @@ function App() {
           emitPopupState({ status: "Done." });
         } catch (err) {
           const msg = err instanceof Error ? err.message : String(err);
+          // macOS: capturing needs the Screen Recording permission; show the system prompt.
+          if (msg.includes("SCREEN_RECORDING_PERMISSION_DENIED")) {
+            void invoke("request_screen_capture_permission").catch(() => {});
+            emitPopupState({
+              status: "Screen Recording permission needed",
+              source: "",
+              translation:
+                "画面収録の許可が必要です。\n\nシステム設定 > プライバシーとセキュリティ > 画面収録 で Erudaite を許可し、アプリを再起動してください。",
+            });
+            return;
+          }
           setStatus(`OCR error: ${msg}`);
           emitPopupState({ status: `OCR error: ${msg}` });
+        } finally {
+          if (snapshot) void invoke("discard_snapshot", { id: snapshot }).catch(() => {});
         }
       });
     })();
@@ -1038,13 +1420,17 @@ function App() {
       void unlistenPromise.then((u) => u()).catch(() => {});
     };
   }, [
+    closePopupIfOpen,
     emitPopupState,
     ensurePopupAtCursor,
     settings.apiBaseUrl,
     settings.clipboardMode,
     settings.defaultLanguage,
     settings.lastUsedTargetLang,
+    settings.ocrInPlace,
     settings.ocrLang,
+    settings.ocrScrolling,
+    settings.ocrVertical,
     settings.routingStrategy,
     settings.secondaryLanguage,
     settings.tessdataPrefix,
@@ -1089,7 +1475,22 @@ function App() {
           try {
             await ensurePopupAtCursor();
             emitPopupState({ status: "Downloading…", translation: `OCR言語データ（${lang}）をダウンロードしています…`, action: undefined });
-            const prefix = String(await invoke("download_tessdata", { lang }));
+            const dlCh = new Channel<
+              | { type: "started"; total: number | null; resumed_from: number }
+              | { type: "progress"; downloaded: number; total: number | null; bytes_per_sec: number }
+              | { type: "done"; prefix: string }
+              | { type: "error"; message: string }
+            >();
+            dlCh.onmessage = (msg) => {
+              if (msg.type !== "progress") return;
+              const mb = (n: number) => (n / 1024 / 1024).toFixed(1);
+              const of = msg.total ? ` / ${mb(msg.total)}MB` : "MB";
+              emitPopupState({
+                status: "Downloading…",
+                translation: `OCR言語データ（${lang}）をダウンロードしています… ${mb(msg.downloaded)}${of}（${mb(msg.bytes_per_sec)}MB/s）`,
+              });
+            };
+            const prefix = String(await invoke("download_tessdata", { lang, onEvent: dlCh }));
             setSettings((s) => ({ ...s, tessdataPrefix: prefix }));
             emitPopupState({
               status: "Ready",
@@ -1137,6 +1538,41 @@ function App() {
     };
   }, [emitPopupState, ensurePopupAtCursor, settings.ocrLang]);
 
+  // Degradations outside a translation stream (clipboard, detection fallback, ...).
+  useEffect(() => {
+    const ch = new Channel<{ kind: string; payload: { code?: string; message?: string } }>();
+    ch.onmessage = (ev) => {
+      if (ev.payload?.message) setStatus(`Warning: ${ev.payload.message}`);
+    };
+    const subPromise = invoke<number>("subscribe_events", { filter: { kinds: ["warning"] }, onEvent: ch });
+    return () => {
+      void subPromise.then((subscriptionId) => invoke("unsubscribe_events", { subscriptionId })).catch(() => {});
+    };
+  }, []);
+
+  // Hotkey actions from the backend (see `hotkeys`), and the tray asking for the settings; in background agent
+  // mode this window may have been created by the press. Run them once settings are loaded.
+  useEffect(() => {
+    if (!settingsLoaded) return;
+    const unlistenPromise = (async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      const unlisten = await listen<string>("erudaite://agent/action", (e) => {
+        if (e.payload === "settings") setShowSettings(true);
+        else if (e.payload === "translate_clipboard") void handleHotkey("clipboard");
+        else if (e.payload === "translate_if_selected") void handleHotkey("selection", true);
+        else if (e.payload === "ocr") void handleOcrHotkey();
+        else if (e.payload === "repeat_region") void handleRepeatRegionHotkey();
+        else if (e.payload === "show_popup") void handleShowPopup();
+        else void handleHotkey();
+      });
+      await emit("erudaite://main/ready", {});
+      return unlisten;
+    })();
+    return () => {
+      void unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
+    };
+  }, [settingsLoaded, handleHotkey, handleOcrHotkey, handleRepeatRegionHotkey, handleShowPopup]);
+
   useEffect(() => {
     const unlistenPromise = (async () => {
       const { listen } = await import("@tauri-apps/api/event");
@@ -1170,46 +1606,38 @@ function App() {
   }, [handleCopy, translatedText]);
 
   useEffect(() => {
-    let disposed = false;
-    (async () => {
-      try {
-        await unregisterAll();
-      } catch {
-        // ignore
-      }
-      if (disposed) return;
-      try {
-        await register(settings.hotkey, () => {
-          // fire-and-forget; we keep UI responsive
-          void handleHotkey();
-        });
-        await register(settings.ocrHotkey, () => {
-          void handleOcrHotkey();
-        });
-        setStatus(`Hotkeys registered: ${settings.hotkey} / ${settings.ocrHotkey}`);
-      } catch (e) {
-        // fallback
-        if (settings.hotkey !== FALLBACK_HOTKEY) {
-          try {
-            await register(FALLBACK_HOTKEY, () => void handleHotkey());
-            setStatus(`Hotkey fallback registered: ${FALLBACK_HOTKEY}`);
-            // Do NOT persist fallback into settings; it should be an ephemeral safety net.
-            return;
-          } catch {
-            // ignore
-          }
-        }
-        setStatus(`Failed to register hotkey: ${e instanceof Error ? e.message : String(e)}`);
-      }
-    })().catch((e) => {
-      setStatus(`Failed to register hotkey: ${e instanceof Error ? e.message : String(e)}`);
-    });
+    void invoke<HotkeyReport>("get_hotkey_status")
+      .then(setHotkeyReport)
+      .catch(() => {});
+    const unlistenPromise = (async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      return await listen<HotkeyReport>("erudaite://hotkeys/status", (e) => setHotkeyReport(e.payload));
+    })();
     return () => {
-      disposed = true;
-      void unregisterAll();
+      void unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
     };
-    // eslint-disable-next-line react-hooks/exhaustive-deps
-  }, [settings.hotkey, settings.ocrHotkey, handleHotkey, handleOcrHotkey]);
+  }, []);
+
+  useEffect(() => {
+    if (!hotkeyReport) return;
+    if (hotkeyReport.paused) {
+      setStatus("Hotkeys paused");
+      return;
+    }
+    if (hotkeyReport.suspended_by) {
+      setStatus(`Hotkeys suspended while ${hotkeyReport.suspended_by} is in front`);
+      return;
+    }
+    const failed = hotkeyReport.hotkeys.filter((h) => h.error);
+    if (failed.length) {
+      setStatus(`Hotkey not registered: ${failed.map((h) => h.error).join(" / ")}`);
+    } else {
+      const names = hotkeyReport.hotkeys.map((h) => (h.hooked ? `${h.accelerator} (hook)` : h.accelerator));
+      setStatus(`Hotkeys registered: ${names.join(" / ")}`);
+    }
+  }, [hotkeyReport]);
+
+  const hotkeyError = (action: string) => hotkeyReport?.hotkeys.find((h) => h.action === action)?.error ?? null;
 
   const isAutoRouting = settings.routingStrategy === "defaultBased";
   const activeLabelColor = "#374151";
@@ -1267,6 +1695,18 @@ function App() {
                   </option>
                 ))}
               </select>
+              <button
+                type="button"
+                className="btn"
+                onClick={async () => {
+                  const native = await suggestedNativeLanguage();
+                  if (native) setSettings((s) => withNativeLanguage(s, native));
+                  else setStatus("キーボードの言語から母国語を判断できませんでした");
+                }}
+                style={{ fontSize: 12, alignSelf: "flex-start" }}
+              >
+                キーボードの言語を使う
+              </button>
             </label>
             <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
               <span style={{ fontWeight: 500, color: "#374151" }}>よく使う言語</span>
@@ -1319,6 +1759,24 @@ function App() {
               onChange={(e) => setSettings((s) => ({ ...s, hotkey: e.target.value }))}
               style={{ maxWidth: 300 }}
             />
+            {hotkeyError("translate") && (
+              <span style={{ fontSize: 12, color: "#dc2626" }}>{hotkeyError("translate")}</span>
+            )}
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>クリップボードを翻訳するホットキー（任意）</span>
+            <input
+              className="input"
+              value={settings.clipboardHotkey ?? ""}
+              onChange={(e) => setSettings((s) => ({ ...s, clipboardHotkey: e.target.value || undefined }))}
+              placeholder="例: CommandOrControl+Shift+Alt+V"
+              style={{ maxWidth: 300 }}
+            />
+            <span style={{ fontSize: 12, color: "#6b7280" }}>コピー操作を送らずに、コピー済みのテキストを翻訳</span>
+            {hotkeyError("translate_clipboard") && (
+              <span style={{ fontSize: 12, color: "#dc2626" }}>{hotkeyError("translate_clipboard")}</span>
+            )}
           </label>
 
           <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
@@ -1330,6 +1788,318 @@ function App() {
               style={{ maxWidth: 300 }}
             />
             <span style={{ fontSize: 12, color: "#6b7280" }}>範囲選択 → OCR → 翻訳</span>
+            {hotkeyError("ocr") && (
+              <span style={{ fontSize: 12, color: "#dc2626" }}>{hotkeyError("ocr")}</span>
+            )}
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>前回の範囲を再OCRするホットキー（任意）</span>
+            <input
+              className="input"
+              value={settings.repeatRegionHotkey ?? ""}
+              onChange={(e) => setSettings((s) => ({ ...s, repeatRegionHotkey: e.target.value || undefined }))}
+              placeholder="例: CommandOrControl+Shift+Alt+R"
+              style={{ maxWidth: 300 }}
+            />
+            <span style={{ fontSize: 12, color: "#6b7280" }}>字幕やゲームのテキスト欄を繰り返し翻訳</span>
+            {hotkeyError("repeat_region") && (
+              <span style={{ fontSize: 12, color: "#dc2626" }}>{hotkeyError("repeat_region")}</span>
+            )}
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>ポップアップの表示を切り替えるホットキー（任意）</span>
+            <input
+              className="input"
+              value={settings.togglePopupHotkey ?? ""}
+              onChange={(e) => setSettings((s) => ({ ...s, togglePopupHotkey: e.target.value || undefined }))}
+              placeholder="例: CommandOrControl+Shift+Alt+P"
+              style={{ maxWidth: 300 }}
+            />
+            <span style={{ fontSize: 12, color: "#6b7280" }}>閉じたポップアップを前回の翻訳で再表示</span>
+            {hotkeyError("toggle_popup") && (
+              <span style={{ fontSize: 12, color: "#dc2626" }}>{hotkeyError("toggle_popup")}</span>
+            )}
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>ポップアップのクリック透過を切り替えるホットキー（任意）</span>
+            <input
+              className="input"
+              value={settings.clickThroughHotkey ?? ""}
+              onChange={(e) => setSettings((s) => ({ ...s, clickThroughHotkey: e.target.value || undefined }))}
+              placeholder="例: CommandOrControl+Shift+Alt+T"
+              style={{ maxWidth: 300 }}
+            />
+            <span style={{ fontSize: 12, color: "#6b7280" }}>ゲームの上に翻訳を表示したまま下の画面を操作</span>
+            {hotkeyError("click_through") && (
+              <span style={{ fontSize: 12, color: "#dc2626" }}>{hotkeyError("click_through")}</span>
+            )}
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>ホットキーの検出方法（Windows）</span>
+            <select
+              className="input"
+              value={settings.hotkeyBackend ?? "register"}
+              onChange={(e) =>
+                setSettings((s) => ({
+                  ...s,
+                  hotkeyBackend: e.target.value === "register" ? undefined : (e.target.value as Settings["hotkeyBackend"]),
+                }))
+              }
+              style={{ width: 300 }}
+            >
+              <option value="register">OSに登録（標準）</option>
+              <option value="auto">登録できないときはキーボードフックを使う</option>
+              <option value="hook">常にキーボードフックを使う</option>
+            </select>
+            <span style={{ fontSize: 12, color: "#6b7280" }}>ゲームなどでホットキーが効かないときに変更してください</span>
+          </label>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.autoPaste ?? false}
+              onChange={(e) => setSettings((s) => ({ ...s, autoPaste: e.target.checked || undefined }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>翻訳が終わったら元のアプリに自動で貼り付け（チャット・ゲーム向け）</span>
+          </label>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.saveHistory ?? true}
+              onChange={(e) => setSettings((s) => ({ ...s, saveHistory: e.target.checked ? undefined : false }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>翻訳を履歴に保存する</span>
+          </label>
+
+          {historyEncryptionAvailable && (
+            <div style={{ display: "flex", flexDirection: "column", gap: 4 }}>
+              <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+                <input
+                  type="checkbox"
+                  checked={settings.encryptHistory ?? false}
+                  onChange={(e) => setSettings((s) => ({ ...s, encryptHistory: e.target.checked || undefined }))}
+                  style={{ width: 16, height: 16 }}
+                />
+                <span>履歴を暗号化して保存する（鍵はOSのキーチェーンに保管）</span>
+              </label>
+              {settings.encryptHistory && (
+                <span style={{ fontSize: 12, color: "#b45309" }}>
+                  注意: バックアップ（.zip）には履歴が暗号化されずに含まれます。安全な場所に保管してください。
+                </span>
+              )}
+            </div>
+          )}
+
+          <div style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>履歴の保持</span>
+            <select
+              className="input"
+              value={settings.historyRetention?.keep ?? "all"}
+              onChange={(e) => {
+                const keep = e.target.value;
+                const retention: HistoryRetention | undefined =
+                  keep === "days"
+                    ? { keep: "days", days: 30 }
+                    : keep === "entries"
+                      ? { keep: "entries", entries: 1000 }
+                      : keep === "nothing"
+                        ? { keep: "nothing" }
+                        : undefined;
+                setSettings((s) => ({ ...s, historyRetention: retention }));
+              }}
+              style={{ width: "auto" }}
+            >
+              <option value="all">すべて保持</option>
+              <option value="days">日数で制限</option>
+              <option value="entries">件数で制限</option>
+              <option value="nothing">保持しない</option>
+            </select>
+            {settings.historyRetention?.keep === "days" || settings.historyRetention?.keep === "entries" ? (
+              <input
+                className="input"
+                type="number"
+                min={1}
+                value={
+                  settings.historyRetention.keep === "days"
+                    ? settings.historyRetention.days
+                    : settings.historyRetention.entries
+                }
+                onChange={(e) => {
+                  const n = Math.max(1, Math.floor(Number(e.target.value) || 1));
+                  setSettings((s) => {
+                    const r = s.historyRetention;
+                    if (r?.keep === "days") return { ...s, historyRetention: { keep: "days", days: n } };
+                    if (r?.keep === "entries") return { ...s, historyRetention: { keep: "entries", entries: n } };
+                    return s;
+                  });
+                }}
+                style={{ width: 90 }}
+              />
+            ) : null}
+            {settings.historyRetention?.keep === "days" ? <span>日</span> : null}
+            {settings.historyRetention?.keep === "entries" ? <span>件</span> : null}
+          </div>
+
+          <div style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>APIキー（OSのキーチェーンに保存）</span>
+            <input
+              className="input"
+              type="password"
+              autoComplete="off"
+              placeholder="変更しない場合は空欄"
+              onBlur={(e) => {
+                const key = e.target.value.trim();
+                if (!key) return;
+                e.target.value = "";
+                invoke("set_secret", { name: "apiKey", secret: key })
+                  .then(() => setStatus("API key saved to the keychain"))
+                  .catch((err) => setStatus(`API key not saved: ${err instanceof Error ? err.message : String(err)}`));
+              }}
+              style={{ width: 300 }}
+            />
+            <button
+              type="button"
+              className="btn"
+              onClick={() => {
+                invoke("delete_secret", { name: "apiKey" })
+                  .then(() => setStatus("API key removed"))
+                  .catch((err) => setStatus(`API key not removed: ${err instanceof Error ? err.message : String(err)}`));
+              }}
+              style={{ alignSelf: "flex-start" }}
+            >
+              APIキーを削除
+            </button>
+          </div>
+
+          <button
+            type="button"
+            className="btn"
+            onClick={() => {
+              invoke<{ files: number; bytes: number }>("clear_tts_cache")
+                .then((r) => setStatus(`Speech cache cleared (${r.files} files, ${Math.round(r.bytes / 1024)} KB)`))
+                .catch((err) => setStatus(`Speech cache not cleared: ${err instanceof Error ? err.message : String(err)}`));
+            }}
+            style={{ alignSelf: "flex-start" }}
+          >
+            読み上げキャッシュを削除
+          </button>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>選択範囲を翻訳で置き換える方法</span>
+            <select
+              className="input"
+              value={settings.typeBackMethod ?? "paste"}
+              onChange={(e) =>
+                setSettings((s) => ({ ...s, typeBackMethod: e.target.value === "type" ? "type" : undefined }))
+              }
+              style={{ width: 300 }}
+            >
+              <option value="paste">貼り付け（クリップボード経由）</option>
+              <option value="type">キー入力（貼り付けできない入力欄向け）</option>
+            </select>
+          </label>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.doubleCopyTrigger ?? false}
+              onChange={(e) => setSettings((s) => ({ ...s, doubleCopyTrigger: e.target.checked || undefined }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>Ctrl+C（⌘C）を素早く2回押して翻訳（Windows/macOS）</span>
+          </label>
+
+          <div style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>マウスボタン（Windows）</span>
+            {MOUSE_BUTTONS.map(({ button, label }) => (
+              <label key={button} style={{ display: "flex", alignItems: "center", gap: 8 }}>
+                <span style={{ width: 220 }}>{label}</span>
+                <select
+                  className="input"
+                  value={settings.mouseTriggers?.[button] ?? ""}
+                  onChange={(e) =>
+                    setSettings((s) => {
+                      const triggers = { ...s.mouseTriggers, [button]: e.target.value || undefined };
+                      const bound = Object.values(triggers).some(Boolean);
+                      return { ...s, mouseTriggers: bound ? triggers : undefined };
+                    })
+                  }
+                  style={{ width: 220 }}
+                >
+                  {MOUSE_ACTIONS.map((o) => (
+                    <option key={o.action} value={o.action}>
+                      {o.label}
+                    </option>
+                  ))}
+                </select>
+              </label>
+            ))}
+            <span style={{ fontSize: 12, color: "#6b7280" }}>中ボタンの翻訳はテキストを選択しているときだけ動作します</span>
+          </div>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.suspendHotkeysInFullscreen ?? false}
+              onChange={(e) =>
+                setSettings((s) => ({ ...s, suspendHotkeysInFullscreen: e.target.checked || undefined }))
+              }
+              style={{ width: 16, height: 16 }}
+            />
+            <span>全画面のアプリ（ゲームなど）の使用中はホットキーを停止（Windows）</span>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>ホットキーを停止するアプリ（任意）</span>
+            <input
+              className="input"
+              // Parsed on blur, so a trailing comma can be typed; remounted when the saved list changes.
+              key={(settings.hotkeySuspendApps ?? []).join(",")}
+              defaultValue={(settings.hotkeySuspendApps ?? []).join(", ")}
+              onBlur={(e) => {
+                const apps = e.target.value
+                  .split(",")
+                  .map((a) => a.trim())
+                  .filter(Boolean);
+                setSettings((s) => ({ ...s, hotkeySuspendApps: apps.length ? apps : undefined }));
+              }}
+              placeholder="例: game.exe, obs64.exe"
+              style={{ maxWidth: 300 }}
+            />
+            <span style={{ fontSize: 12, color: "#6b7280" }}>これらのアプリが前面にある間はホットキーを停止します（Windows）</span>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>アプリごとの設定（任意・JSON）</span>
+            <textarea
+              className="input"
+              // Parsed on blur; remounted when the saved profiles change.
+              key={JSON.stringify(settings.appProfiles ?? [])}
+              defaultValue={settings.appProfiles?.length ? JSON.stringify(settings.appProfiles, null, 2) : ""}
+              onBlur={(e) => {
+                const raw = e.target.value.trim();
+                try {
+                  const profiles = raw ? (JSON.parse(raw) as AppProfile[]) : [];
+                  if (!Array.isArray(profiles)) throw new Error("not a list");
+                  setSettings((s) => ({ ...s, appProfiles: profiles.length ? profiles : undefined }));
+                } catch (err) {
+                  setStatus(`App profiles not saved: ${err instanceof Error ? err.message : String(err)}`);
+                }
+              }}
+              placeholder={'[{ "app": "discord.exe", "mode": "casual" }, { "app": "outlook.exe", "mode": "formal" }]'}
+              rows={4}
+              style={{ maxWidth: 420, fontFamily: "monospace", fontSize: 12 }}
+            />
+            <span style={{ fontSize: 12, color: "#6b7280" }}>
+              app ごとに targetLang / mode / ocrLang を上書き、"hotkeys": false でホットキーを停止（Windows）
+            </span>
           </label>
 
           <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
@@ -1347,13 +2117,150 @@ function App() {
             <span style={{ fontWeight: 500, color: "#374151" }}>OCR言語（Tesseract）</span>
             <input
               className="input"
-              value={settings.ocrLang ?? "jpn+eng"}
+              value={settings.ocrLang ?? "auto"}
               onChange={(e) => setSettings((s) => ({ ...s, ocrLang: e.target.value || undefined }))}
-              placeholder="jpn+eng"
+              placeholder="auto / jpn+eng"
               style={{ maxWidth: 220 }}
             />
           </label>
 
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.ocrVertical ?? false}
+              onChange={(e) => setSettings((s) => ({ ...s, ocrVertical: e.target.checked || undefined }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>縦書きOCR（漫画・ゲーム向け、jpn_vert が必要）</span>
+          </label>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.ocrScrolling ?? false}
+              onChange={(e) => setSettings((s) => ({ ...s, ocrScrolling: e.target.checked || undefined }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>スクロールキャプチャ（カーソル下のウィンドウをスクロールして長いページをまとめてOCR）</span>
+          </label>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.ocrFreezeFrame ?? false}
+              onChange={(e) => setSettings((s) => ({ ...s, ocrFreezeFrame: e.target.checked || undefined }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>範囲選択中は画面を静止（動画やツールチップのOCR向け）</span>
+          </label>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.ocrInPlace ?? false}
+              onChange={(e) => setSettings((s) => ({ ...s, ocrInPlace: e.target.checked || undefined }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>翻訳を元の文字の上に重ねて表示（範囲1つのとき、クリックまたはEscで閉じる）</span>
+          </label>
+
+          <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+            <input
+              type="checkbox"
+              checked={settings.excludeOwnWindowsFromCapture ?? true}
+              onChange={(e) => setSettings((s) => ({ ...s, excludeOwnWindowsFromCapture: e.target.checked }))}
+              style={{ width: 16, height: 16 }}
+            />
+            <span>翻訳ポップアップをキャプチャに写さない</span>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>OCR言語データの種類</span>
+            <select
+              className="input"
+              value={settings.tessdataVariant ?? "fast"}
+              onChange={(e) => setSettings((s) => ({ ...s, tessdataVariant: e.target.value as "fast" | "best" }))}
+              style={{ maxWidth: 220 }}
+            >
+              <option value="fast">fast（軽量・高速）</option>
+              <option value="best">best（高精度・10〜30MB）</option>
+            </select>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>HDRディスプレイの補正</span>
+            <select
+              className="input"
+              value={settings.captureToneMap ?? "auto"}
+              onChange={(e) =>
+                setSettings((s) => ({ ...s, captureToneMap: e.target.value as "auto" | "on" | "off" }))
+              }
+              style={{ maxWidth: 220 }}
+            >
+              <option value="auto">自動（HDR検出時のみ）</option>
+              <option value="on">常に補正</option>
+              <option value="off">補正しない</option>
+            </select>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>ポップアップの不透明度</span>
+            <select
+              className="input"
+              value={String(settings.popupOpacity ?? 100)}
+              onChange={(e) => setSettings((s) => ({ ...s, popupOpacity: Number(e.target.value) }))}
+              style={{ maxWidth: 220 }}
+            >
+              <option value="100">100%</option>
+              <option value="85">85%</option>
+              <option value="70">70%</option>
+              <option value="50">50%</option>
+            </select>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>ポップアップのフェード</span>
+            <select
+              className="input"
+              value={String(settings.popupFadeMs ?? 120)}
+              onChange={(e) => setSettings((s) => ({ ...s, popupFadeMs: Number(e.target.value) }))}
+              style={{ maxWidth: 220 }}
+            >
+              <option value="0">なし</option>
+              <option value="120">速い（120ms）</option>
+              <option value="250">ゆっくり（250ms）</option>
+            </select>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>キャプチャの最大サイズ</span>
+            <select
+              className="input"
+              value={String(settings.captureMaxDimension ?? 0)}
+              onChange={(e) => setSettings((s) => ({ ...s, captureMaxDimension: Number(e.target.value) }))}
+              style={{ maxWidth: 220 }}
+            >
+              <option value="0">制限なし</option>
+              <option value="3840">3840px</option>
+              <option value="2560">2560px</option>
+              <option value="1920">1920px</option>
+            </select>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>キャプチャの保存形式</span>
+            <select
+              className="input"
+              value={settings.captureFormat ?? "png"}
+              onChange={(e) => setSettings((s) => ({ ...s, captureFormat: e.target.value as "png" | "jpeg" | "webp" }))}
+              style={{ maxWidth: 220 }}
+            >
+              <option value="png">PNG（劣化なし）</option>
+              <option value="jpeg">JPEG（軽量）</option>
+              <option value="webp">WebP（劣化なし・軽量）</option>
+            </select>
+          </label>
+
           <div style={{ display: "flex", gap: 20, flexWrap: "wrap" }}>
             <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
               <input
@@ -1365,6 +2272,36 @@ function App() {
               <span>ポップアップを自動フォーカス</span>
             </label>
 
+            <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+              <input
+                type="checkbox"
+                checked={settings.popupAutoHide ?? true}
+                onChange={(e) => setSettings((s) => ({ ...s, popupAutoHide: e.target.checked }))}
+                style={{ width: 16, height: 16 }}
+              />
+              <span>フォーカスが外れたらポップアップを閉じる</span>
+            </label>
+
+            <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+              <input
+                type="checkbox"
+                checked={!!settings.backgroundAgent}
+                onChange={(e) => setSettings((s) => ({ ...s, backgroundAgent: e.target.checked }))}
+                style={{ width: 16, height: 16 }}
+              />
+              <span>バックグラウンドで起動（トレイのみ・次回起動から）</span>
+            </label>
+
+            <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
+              <input
+                type="checkbox"
+                checked={settings.trayIcon ?? true}
+                onChange={(e) => setSettings((s) => ({ ...s, trayIcon: e.target.checked }))}
+                style={{ width: 16, height: 16 }}
+              />
+              <span>トレイアイコンを表示</span>
+            </label>
+
             <div className="help">
               <label style={{ display: "flex", alignItems: "center", gap: 8, fontSize: 13, cursor: "pointer" }}>
                 <input
@@ -1416,6 +2353,36 @@ function App() {
             </select>
           </label>
 
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>解説の言語</span>
+            <select
+              className="input"
+              value={settings.explanationLanguage ?? "ja"}
+              onChange={(e) =>
+                setSettings((s) => ({ ...s, explanationLanguage: e.target.value === "ja" ? undefined : e.target.value }))
+              }
+              style={{ maxWidth: 220 }}
+            >
+              <option value="ja">日本語</option>
+              <option value="en">English</option>
+            </select>
+          </label>
+
+          <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
+            <span style={{ fontWeight: 500, color: "#374151" }}>別の訳の数（ポップアップ）</span>
+            <select
+              className="input"
+              value={settings.alternatives ?? 0}
+              onChange={(e) => setSettings((s) => ({ ...s, alternatives: Number(e.target.value) || undefined }))}
+              style={{ maxWidth: 220 }}
+            >
+              <option value={0}>表示しない</option>
+              <option value={1}>1</option>
+              <option value={2}>2</option>
+              <option value={3}>3</option>
+            </select>
+          </label>
+
           <div style={{ display: "flex", gap: 16, flexWrap: "wrap" }}>
             <label style={{ display: "flex", flexDirection: "column", gap: 4, fontSize: 13 }}>
               <span style={{ fontWeight: 500, color: activeLabelColor }}>
@@ -1,16 +1,22 @@
 import { useEffect, useMemo, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { emit } from "@tauri-apps/api/event";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 
 type RectPayload = {
-  // physical pixels in virtual-screen coordinates
+  // physical pixels of the desktop
   x: number;
   y: number;
   width: number;
   height: number;
 };
 
+// The overlay is one window per monitor (see `open_ocr_overlay`); closing any closes them all.
+const closeOverlay = async () => {
+  await invoke("close_ocr_overlay").catch(() => getCurrentWindow().destroy().catch(() => {}));
+};
+
 export default function OcrOverlay() {
   useEffect(() => {
     const html = document.documentElement;
@@ -35,35 +41,99 @@ export default function OcrOverlay() {
   }, []);
 
   const [dragging, setDragging] = useState(false);
+  // Regions kept with Shift+drag, in CSS pixels; sent together with the final one.
+  const [regions, setRegions] = useState<{ x: number; y: number; w: number; h: number }[]>([]);
+  const regionsRef = useRef(regions);
+  regionsRef.current = regions;
   const [start, setStart] = useState<{ x: number; y: number } | null>(null);
   const [cur, setCur] = useState<{ x: number; y: number } | null>(null);
   const scaleRef = useRef(1);
   const originRef = useRef<{ x: number; y: number }>({ x: 0, y: 0 });
+  // Freeze-frame: the snapshot (see `capture_full_screen_snapshot`) shown under the selection, in CSS pixels.
+  const [frozen, setFrozen] = useState<{ url: string; left: number; top: number; width: number } | null>(null);
 
   useEffect(() => {
     const w = getCurrentWebviewWindow();
     void (async () => {
-      try {
-        scaleRef.current = await w.scaleFactor();
-      } catch {
-        scaleRef.current = window.devicePixelRatio || 1;
+      const params = new URLSearchParams(window.location.hash.split("?")[1] ?? "");
+      // The backend passes this window's monitor (physical origin and scale); ask the window otherwise.
+      if (params.has("ox") && params.has("scale")) {
+        scaleRef.current = Number(params.get("scale")) || 1;
+        originRef.current = { x: Number(params.get("ox") ?? 0), y: Number(params.get("oy") ?? 0) };
+      } else {
+        try {
+          scaleRef.current = await w.scaleFactor();
+        } catch {
+          scaleRef.current = window.devicePixelRatio || 1;
+        }
+        try {
+          const pos = await w.outerPosition();
+          originRef.current = { x: pos.x, y: pos.y };
+        } catch {
+          originRef.current = { x: 0, y: 0 };
+        }
       }
+
+      const snapshot = params.get("snapshot");
+      if (!snapshot) return;
       try {
-        const pos = await w.outerPosition();
-        originRef.current = { x: pos.x, y: pos.y };
+        const bytes = (await invoke("snapshot_image", { id: snapshot })) as ArrayBuffer;
+        const url = URL.createObjectURL(new Blob([bytes], { type: "image/png" }));
+        const img = new Image();
+        img.onload = () => {
+          const scale = scaleRef.current || 1;
+          setFrozen({
+            url,
+            left: (Number(params.get("x") ?? 0) - originRef.current.x) / scale,
+            top: (Number(params.get("y") ?? 0) - originRef.current.y) / scale,
+            width: img.naturalWidth / scale,
+          });
+        };
+        img.src = url;
       } catch {
-        originRef.current = { x: 0, y: 0 };
+        // Without the image the overlay still works on the live screen.
       }
-      try {
-        await w.outerSize();
-      } catch {}
     })();
   }, []);
 
+  useEffect(() => {
+    return () => {
+      if (frozen) URL.revokeObjectURL(frozen.url);
+    };
+  }, [frozen]);
+
+  // Physical desktop pixels, converted with this window's own monitor (its physical origin and scale): logical
+  // desktop coordinates aren't well defined when monitors with different scales sit side by side.
+  const toPhysical = (r: { x: number; y: number; w: number; h: number }) => {
+    const scale = scaleRef.current || 1;
+    const origin = originRef.current;
+    return {
+      x: Math.round(origin.x + r.x * scale),
+      y: Math.round(origin.y + r.y * scale),
+      width: Math.round(r.w * scale),
+      height: Math.round(r.h * scale),
+    } satisfies RectPayload;
+  };
+
+  const submit = async (all: { x: number; y: number; w: number; h: number }[]) => {
+    const x1 = Math.min(...all.map((r) => r.x));
+    const y1 = Math.min(...all.map((r) => r.y));
+    const x2 = Math.max(...all.map((r) => r.x + r.w));
+    const y2 = Math.max(...all.map((r) => r.y + r.h));
+    const bounds = toPhysical({ x: x1, y: y1, w: x2 - x1, h: y2 - y1 });
+    await emit<RectPayload & { regions?: RectPayload[] }>(
+      "erudaite://ocr/selected",
+      all.length > 1 ? { ...bounds, regions: all.map(toPhysical) } : bounds,
+    ).catch(() => {});
+    await closeOverlay();
+  };
+
   useEffect(() => {
     const onKeyDown = (e: KeyboardEvent) => {
       if (e.key === "Escape") {
-        void getCurrentWindow().destroy();
+        void closeOverlay();
+      } else if (e.key === "Enter" && regionsRef.current.length > 0) {
+        void submit(regionsRef.current);
       }
     };
     window.addEventListener("keydown", onKeyDown);
@@ -82,7 +152,7 @@ export default function OcrOverlay() {
   const startDrag = (e: React.PointerEvent) => {
     // Right-click = immediate cancel (safety hatch)
     if (e.button === 2) {
-      void getCurrentWindow().destroy();
+      void closeOverlay();
       return;
     }
     e.preventDefault();
@@ -97,30 +167,29 @@ export default function OcrOverlay() {
     setCur({ x: e.clientX, y: e.clientY });
   };
 
-  const endDrag = async () => {
+  const endDrag = async (e: React.PointerEvent) => {
     setDragging(false);
-    if (!rect) {
-      await getCurrentWindow().destroy();
+    const minSize = 6;
+    const valid = rect && rect.w >= minSize && rect.h >= minSize ? rect : null;
+    if (e.shiftKey && valid) {
+      // Keep this region and let the user add another.
+      setRegions((prev) => [...prev, valid]);
+      setStart(null);
+      setCur(null);
       return;
     }
-    const minSize = 6;
-    if (rect.w < minSize || rect.h < minSize) {
-      await getCurrentWindow().destroy();
+    if ((e.ctrlKey || e.metaKey) && valid) {
+      // Ctrl/Cmd+drag: copy the region to the clipboard as an image instead of OCR.
+      await emit<RectPayload>("erudaite://ocr/copy", toPhysical(valid)).catch(() => {});
+      await closeOverlay();
       return;
     }
-
-    const scale = scaleRef.current || 1;
-    const origin = originRef.current;
-    const phys = {
-      x: Math.floor(origin.x + rect.x * scale),
-      y: Math.floor(origin.y + rect.y * scale),
-      width: Math.floor(rect.w * scale),
-      height: Math.floor(rect.h * scale),
-    } satisfies RectPayload;
-
-    await emit<RectPayload>("erudaite://ocr/selected", phys)
-      .catch(() => {});
-    await getCurrentWindow().destroy().catch(() => {});
+    const all = valid ? [...regions, valid] : regions;
+    if (all.length === 0) {
+      await closeOverlay();
+      return;
+    }
+    await submit(all);
   };
 
   return (
@@ -129,11 +198,11 @@ export default function OcrOverlay() {
       onPointerMove={moveDrag}
       onPointerUp={endDrag}
       onPointerCancel={() => {
-        void getCurrentWindow().destroy();
+        void closeOverlay();
       }}
       onContextMenu={(e) => {
         e.preventDefault();
-        void getCurrentWindow().destroy();
+        void closeOverlay();
       }}
       style={{
         position: "fixed",
@@ -144,6 +213,39 @@ export default function OcrOverlay() {
         userSelect: "none",
       }}
     >
+      {/* Frozen frame */}
+      {frozen && (
+        <img
+          src={frozen.url}
+          alt=""
+          draggable={false}
+          style={{
+            position: "absolute",
+            left: frozen.left,
+            top: frozen.top,
+            width: frozen.width,
+            pointerEvents: "none",
+          }}
+        />
+      )}
+
+      {/* Regions kept with Shift+drag */}
+      {regions.map((r, i) => (
+        <div
+          key={i}
+          style={{
+            position: "absolute",
+            left: r.x,
+            top: r.y,
+            width: r.w,
+            height: r.h,
+            border: "2px solid #34d399",
+            background: "rgba(52,211,153,0.15)",
+            boxSizing: "border-box",
+          }}
+        />
+      ))}
+
       {/* Selection box */}
       {rect && (
         <div
@@ -173,14 +275,14 @@ export default function OcrOverlay() {
           fontSize: 13,
         }}
       >
-        ドラッグで範囲選択（Esc/右クリックでキャンセル）
+        ドラッグで範囲選択（Shift+ドラッグで複数選択・Enterで確定／Ctrl+ドラッグで画像をコピー／Esc/右クリックでキャンセル）
       </div>
 
       {/* Always-visible close button (safety hatch) */}
       <button
         type="button"
         onClick={() => {
-          void getCurrentWindow().destroy();
+          void closeOverlay();
         }}
         aria-label="Close"
         title="Close (Esc / Right click)"
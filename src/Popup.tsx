@@ -1,5 +1,6 @@
 import { useEffect, useRef, useState } from "react";
 import { listen, emit } from "@tauri-apps/api/event";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import "./App.css"; // For popup-animate animation
@@ -8,17 +9,28 @@ type PopupState = {
   status?: string;
   source?: string;
   translation?: string;
+  variants?: string[]; // alternative translations, streamed after or alongside the main one
   action?: "enable_ocr" | "recheck_ocr" | "install_jpn";
+  replaceable?: boolean;
 };
 
 export default function Popup() {
   const [state, setState] = useState<PopupState>({ status: "Translating…", translation: "" });
-  const hasFocusedRef = useRef(false);
   const [isFocused, setIsFocused] = useState(true);
   const [showSource, setShowSource] = useState(false);
-  const dragInProgressRef = useRef(false);
+  // Pinned: stays on top and open when it loses focus (see `set_always_on_top`).
+  const [pinned, setPinned] = useState(false);
+  const pinnedRef = useRef(false);
+  // Reading the translation aloud (`speak`); cleared by the backend's `tts.finished`.
+  const [speaking, setSpeaking] = useState(false);
 
   const closeSelf = (_reason: string) => {
+    const w = getCurrentWebviewWindow();
+    // Fade out and destroy (`popupFadeMs`); the hide/destroy below is the fallback.
+    invoke("fade_out_window", { label: w.label, close: true }).catch(() => forceClose());
+  };
+
+  const forceClose = () => {
     const w = getCurrentWebviewWindow();
     // IMPORTANT: `close()` can resolve even if the window stays visible (close-request accepted but not applied).
     // To guarantee UX, hide first (disappear), then close/destroy for cleanup.
@@ -38,17 +50,27 @@ export default function Popup() {
     const w = getCurrentWebviewWindow();
     // If the popup is focused immediately on open, we can miss the initial focusChanged(true)
     // event depending on timing. In that case, document.hasFocus() will already be true.
-    // Mark as "has focused" so the very first outside click (blur) closes immediately.
     if (typeof document !== "undefined" && document.hasFocus()) {
-      hasFocusedRef.current = true;
       setIsFocused(true);
     }
     const unlistenDestroyedP = w.listen("tauri://destroyed", () => {});
     const unlistenCloseReqP = w.listen("tauri://close-requested", () => {});
-    void emit("erudaite://popup/ready", { label: getCurrentWebviewWindow().label }).catch(() => {});
+    const label = getCurrentWebviewWindow().label;
     const unlistenPromise = listen<PopupState>("erudaite://popup/state", (e) => {
       setState((s) => ({ ...s, ...e.payload }));
     });
+    // Once state is listened for: a popup reopened from a crashed session shows its draft; any other asks the
+    // main window for the current state.
+    void unlistenPromise
+      .then(() => invoke<PopupState | null>("take_restored_draft", { label }))
+      .then((draft) => {
+        if (draft) {
+          setState((s) => ({ ...s, ...draft }));
+        } else {
+          return emit("erudaite://popup/ready", { label });
+        }
+      })
+      .catch(() => {});
     return () => {
       void unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
       void unlistenDestroyedP.then((u) => u()).catch(() => {});
@@ -56,11 +78,51 @@ export default function Popup() {
     };
   }, []);
 
+  useEffect(() => {
+    const label = getCurrentWebviewWindow().label;
+    const apply = (p: boolean) => {
+      pinnedRef.current = p;
+      setPinned(p);
+    };
+    invoke("is_window_pinned", { label })
+      .then((p) => apply(p === true))
+      .catch(() => {});
+    const unlistenPromise = listen<{ label: string; pinned: boolean }>("erudaite://window/pinned", (e) => {
+      if (e.payload.label === label) apply(e.payload.pinned);
+    });
+    return () => {
+      void unlistenPromise.then((unlisten) => unlisten()).catch(() => {});
+    };
+  }, []);
+
+  useEffect(() => {
+    const ch = new Channel<{ kind: string }>();
+    ch.onmessage = () => setSpeaking(false);
+    const subPromise = invoke<number>("subscribe_events", { filter: { kinds: ["tts.finished"] }, onEvent: ch });
+    return () => {
+      void subPromise.then((subscriptionId) => invoke("unsubscribe_events", { subscriptionId })).catch(() => {});
+    };
+  }, []);
+
+  const toggleSpeaking = () => {
+    if (speaking) {
+      void invoke("stop_speaking").catch(() => {});
+      return;
+    }
+    setSpeaking(true);
+    invoke("speak", { text: state.translation }).catch((e) => {
+      setSpeaking(false);
+      setState((s) => ({ ...s, status: `読み上げに失敗しました: ${String(e)}` }));
+    });
+  };
+
+  const togglePinned = () => {
+    const label = getCurrentWebviewWindow().label;
+    void invoke("set_always_on_top", { label, pinned: !pinnedRef.current }).catch(() => {});
+  };
+
   useEffect(() => {
     const onKeyDown = (e: KeyboardEvent) => {
-      // Some platforms don't emit focusChanged(true) reliably.
-      // If we receive any key events, the popup is effectively focused.
-      hasFocusedRef.current = true;
       if (e.key === "Escape") {
         closeSelf("esc");
       }
@@ -70,34 +132,10 @@ export default function Popup() {
   }, []);
 
   useEffect(() => {
-    // If the user clicks inside the popup, treat it as focused for the purpose of blur-to-close.
-    const onPointerDown = () => {
-      hasFocusedRef.current = true;
-    };
-    window.addEventListener("pointerdown", onPointerDown, { capture: true });
-    return () => window.removeEventListener("pointerdown", onPointerDown, { capture: true } as any);
-  }, []);
-
-  useEffect(() => {
-    // Close when clicking outside (approximated by window blur).
-    // Guard: only close on blur after we have successfully received focus at least once.
+    // Closing when the user clicks elsewhere is the backend's job (`popupAutoHide`); this only tracks the look.
     const w = getCurrentWebviewWindow();
     const unsubPromise = w.onFocusChanged(({ payload }) => {
       setIsFocused(payload === true);
-      if (payload === true) {
-        hasFocusedRef.current = true;
-        // Drag end heuristic: focus regained -> allow blur-to-close again
-        if (dragInProgressRef.current) {
-          dragInProgressRef.current = false;
-        }
-        return;
-      }
-      if (payload === false && hasFocusedRef.current) {
-        if (dragInProgressRef.current) {
-          return;
-        }
-        closeSelf("blur");
-      }
     });
     return () => {
       void unsubPromise.then((unsub) => unsub()).catch(() => {});
@@ -134,13 +172,9 @@ export default function Popup() {
         onPointerDown={(e) => {
           // Only left button drags
           if (e.button !== 0) return;
-          dragInProgressRef.current = true;
           void getCurrentWindow()
             .startDragging()
-            .catch((err) => {
-              void err;
-              dragInProgressRef.current = false;
-            });
+            .catch(() => {});
         }}
         style={{
           position: "absolute",
@@ -156,6 +190,32 @@ export default function Popup() {
         title="ドラッグして移動"
       />
 
+      {/* Pin button */}
+      <button
+        onClick={togglePinned}
+        aria-label={pinned ? "Unpin" : "Pin"}
+        aria-pressed={pinned}
+        title={pinned ? "固定を解除" : "最前面に固定"}
+        style={{
+          position: "absolute",
+          top: 8,
+          right: 36,
+          width: 24,
+          height: 24,
+          padding: 0,
+          borderRadius: 6,
+          border: "none",
+          background: pinned ? "rgba(59, 130, 246, 0.15)" : isFocused ? "rgba(0,0,0,0.06)" : "rgba(0,0,0,0.04)",
+          cursor: "pointer",
+          lineHeight: "24px",
+          fontSize: 12,
+          color: pinned ? "#2563eb" : "#6b7280",
+          transition: "background 0.12s ease, color 0.12s ease",
+        }}
+      >
+        📌
+      </button>
+
       {/* Close button */}
       <button
         onClick={() => closeSelf("button")}
@@ -197,7 +257,7 @@ export default function Popup() {
           whiteSpace: "pre-wrap",
           wordBreak: "break-word",
           color: "#1f2937",
-          paddingRight: 28, // space for close button
+          paddingRight: 56, // space for the pin and close buttons
         }}
       >
         {state.status && (
@@ -210,6 +270,45 @@ export default function Popup() {
           <span style={{ color: "#9ca3af", fontStyle: "italic" }}>Translating…</span>
         )}
 
+        {state.variants && state.variants.length > 0 && (
+          <div style={{ marginTop: 12, display: "flex", flexDirection: "column", gap: 6 }}>
+            <div style={{ fontSize: 12, color: "#6b7280" }}>別の訳</div>
+            {state.variants.map((v, i) => (
+              <div
+                key={i}
+                style={{
+                  padding: "6px 8px",
+                  borderRadius: 8,
+                  border: "1px solid rgba(0,0,0,0.08)",
+                  background: "rgba(0,0,0,0.02)",
+                  fontSize: 13,
+                }}
+              >
+                {v}
+              </div>
+            ))}
+          </div>
+        )}
+
+        {state.translation && (
+          <div style={{ marginTop: 12 }}>
+            <button
+              type="button"
+              onClick={toggleSpeaking}
+              style={{
+                fontSize: 12,
+                padding: "6px 8px",
+                borderRadius: 8,
+                border: "1px solid rgba(0,0,0,0.12)",
+                background: "rgba(0,0,0,0.02)",
+                cursor: "pointer",
+              }}
+            >
+              {speaking ? "読み上げを停止" : "読み上げ"}
+            </button>
+          </div>
+        )}
+
         {state.source && (
           <div style={{ marginTop: 12 }}>
             <button
@@ -245,6 +344,31 @@ export default function Popup() {
           </div>
         )}
 
+        {state.replaceable && state.translation && (
+          <div style={{ marginTop: 12 }}>
+            <button
+              type="button"
+              onClick={() => {
+                // The backend closes this popup so the text goes to the app the selection was in.
+                void invoke("replace_selection_with", { text: state.translation }).catch((e) =>
+                  setState((s) => ({ ...s, status: `置き換えに失敗しました: ${String(e)}` })),
+                );
+              }}
+              style={{
+                fontSize: 12,
+                padding: "8px 10px",
+                borderRadius: 10,
+                border: "none",
+                background: "#2a6478",
+                color: "white",
+                cursor: "pointer",
+              }}
+            >
+              選択範囲を置き換え
+            </button>
+          </div>
+        )}
+
         {(state.action === "enable_ocr" || state.action === "recheck_ocr" || state.action === "install_jpn") && (
           <div style={{ marginTop: 12, display: "flex", gap: 8, flexWrap: "wrap" }}>
             {state.action === "enable_ocr" && (
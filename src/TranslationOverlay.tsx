@@ -0,0 +1,94 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+
+type PlacedBlock = {
+  // physical pixels relative to the window (see `open_translation_overlay`)
+  bbox: { x: number; y: number; width: number; height: number };
+  font_size: number;
+  line_height: number;
+  lines: string[];
+};
+
+const closeOverlay = async () => {
+  await invoke("close_translation_overlay").catch(() => getCurrentWindow().destroy().catch(() => {}));
+};
+
+export default function TranslationOverlay() {
+  const [blocks, setBlocks] = useState<PlacedBlock[]>([]);
+
+  useEffect(() => {
+    const html = document.documentElement;
+    const body = document.body;
+    body.classList.add("overlay");
+    const prev = { htmlBg: html.style.background, bodyBg: body.style.background };
+    html.style.background = "transparent";
+    body.style.background = "transparent";
+    return () => {
+      body.classList.remove("overlay");
+      html.style.background = prev.htmlBg;
+      body.style.background = prev.bodyBg;
+    };
+  }, []);
+
+  useEffect(() => {
+    const load = async () => {
+      const content = (await invoke("get_translation_overlay")) as { blocks: PlacedBlock[] } | null;
+      if (!content) {
+        void closeOverlay();
+        return;
+      }
+      setBlocks(content.blocks);
+    };
+    void load();
+    // The backend reuses an open overlay for the next translation.
+    const unlisten = listen("translation-overlay://changed", () => void load());
+    return () => {
+      void unlisten.then((u) => u()).catch(() => {});
+    };
+  }, []);
+
+  useEffect(() => {
+    const onKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        e.preventDefault();
+        void closeOverlay();
+      }
+    };
+    window.addEventListener("keydown", onKeyDown);
+    return () => window.removeEventListener("keydown", onKeyDown);
+  }, []);
+
+  // Boxes and sizes are physical pixels; CSS pixels are those divided by the window's scale.
+  const scale = window.devicePixelRatio || 1;
+  return (
+    <div
+      onMouseDown={() => void closeOverlay()}
+      style={{ position: "fixed", inset: 0, cursor: "pointer", userSelect: "none" }}
+    >
+      {blocks.map((b, i) => (
+        <div
+          key={i}
+          style={{
+            position: "absolute",
+            left: b.bbox.x / scale,
+            top: b.bbox.y / scale,
+            width: b.bbox.width / scale,
+            minHeight: b.bbox.height / scale,
+            fontSize: b.font_size / scale,
+            lineHeight: `${b.line_height / scale}px`,
+            background: "rgba(255, 255, 255, 0.92)",
+            color: "#111827",
+            borderRadius: 2,
+            boxShadow: "0 0 0 2px rgba(255, 255, 255, 0.92)",
+            whiteSpace: "pre",
+            overflow: "hidden",
+          }}
+        >
+          {b.lines.join("\n")}
+        </div>
+      ))}
+    </div>
+  );
+}
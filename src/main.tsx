@@ -3,12 +3,16 @@ import './index.css'
 import App from './App.tsx'
 import Popup from './Popup.tsx'
 import OcrOverlay from './OcrOverlay.tsx'
+import TranslationOverlay from './TranslationOverlay.tsx'
 
 const isPopup = window.location.hash.startsWith('#/popup')
 const isOcrOverlay = window.location.hash.startsWith('#/ocr-overlay')
+const isTranslationOverlay = window.location.hash.startsWith('#/translation-overlay')
 if (isPopup) {
   document.documentElement.classList.add('popup')
   document.body.classList.add('popup')
 }
 
-createRoot(document.getElementById('root')!).render(isPopup ? <Popup /> : isOcrOverlay ? <OcrOverlay /> : <App />)
+createRoot(document.getElementById('root')!).render(
+  isPopup ? <Popup /> : isOcrOverlay ? <OcrOverlay /> : isTranslationOverlay ? <TranslationOverlay /> : <App />,
+)